@@ -0,0 +1,169 @@
+use std::{io::BufWriter, sync::OnceLock};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use laser_pdf::{
+    build_pdf,
+    elements::{
+        column::{Column, ColumnContent},
+        padding::Padding,
+        row::{Flex, Row, RowContent},
+        text::Text,
+    },
+    fonts::builtin::BuiltinFont,
+    test_utils::binary_snapshots::LOREM_IPSUM,
+    text::cache_text_widths,
+    DrawCtx, Element, ElementSize, FirstLocationUsage, FirstLocationUsageCtx, MeasureCtx,
+    WidthConstraint,
+};
+use printpdf::PdfDocument;
+
+/// A synthetic invoice line. The corpus is generated, so it's the same on every run.
+struct Item {
+    description: String,
+    quantity: String,
+    price: String,
+    total: String,
+}
+
+fn invoice(items: usize) -> Vec<Item> {
+    (0..items)
+        .map(|i| {
+            let quantity = i % 7 + 1;
+            let price = (i * 37 % 1000) as f64 / 10.;
+
+            Item {
+                description: LOREM_IPSUM[..20 + i * 13 % 200].to_string(),
+                quantity: quantity.to_string(),
+                price: format!("{price:.2}"),
+                total: format!("{:.2}", quantity as f64 * price),
+            }
+        })
+        .collect()
+}
+
+fn items() -> &'static [Item] {
+    static ITEMS: OnceLock<Vec<Item>> = OnceLock::new();
+
+    ITEMS.get_or_init(|| invoice(1000))
+}
+
+struct Invoice<'a> {
+    items: &'a [Item],
+    font: &'a BuiltinFont,
+}
+
+impl<'a> Invoice<'a> {
+    fn element<R>(&self, f: impl FnOnce(&dyn Element) -> R) -> R {
+        let text = |text| Text::basic(text, self.font, 10.);
+
+        f(&Padding {
+            left: 20.,
+            right: 20.,
+            top: 20.,
+            bottom: 20.,
+            element: &Column {
+                gap: 2.,
+                collapse: false,
+                ..Column::new(|mut content: ColumnContent| {
+                    for item in self.items {
+                        content = content.add(&Row {
+                            gap: 4.,
+                            expand: true,
+                            collapse: false,
+                            content: |content: &mut RowContent| {
+                                content.add(&text(&item.description), Flex::Expand(1));
+                                content.add(&text(&item.quantity), Flex::Fixed(15.));
+                                content.add(&text(&item.price), Flex::Fixed(25.));
+                                content.add(&text(&item.total), Flex::Fixed(25.));
+                            },
+                        })?;
+                    }
+
+                    Some(())
+                })
+            },
+        })
+    }
+}
+
+impl<'a> Element for Invoice<'a> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element(|e| e.first_location_usage(ctx))
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element(|e| e.measure(ctx))
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        self.element(|e| e.draw(ctx))
+    }
+}
+
+fn invoice_element(font: &BuiltinFont) -> Invoice<'_> {
+    Invoice {
+        items: items(),
+        font,
+    }
+}
+
+fn measure(element: &dyn Element, width: f64) {
+    black_box(element.measure(MeasureCtx {
+        width: WidthConstraint {
+            max: width,
+            expand: true,
+        },
+        first_height: 297.,
+        breakable: None,
+    }));
+}
+
+fn text(c: &mut Criterion) {
+    let document = PdfDocument::empty("bench");
+    let font = BuiltinFont::helvetica(&document);
+    let text = LOREM_IPSUM.repeat(20);
+
+    c.bench_function("text line breaking", |b| {
+        b.iter(|| measure(&Text::basic(&text, &font, 10.), 100.))
+    });
+}
+
+fn layout(c: &mut Criterion) {
+    let document = PdfDocument::empty("bench");
+    let font = BuiltinFont::helvetica(&document);
+
+    c.bench_function("invoice layout", |b| {
+        b.iter(|| measure(&invoice_element(&font), 210.))
+    });
+}
+
+/// The same as the invoice layout, but with the widths of the repeated words and labels cached, to
+/// compare the two.
+fn cached_layout(c: &mut Criterion) {
+    let document = PdfDocument::empty("bench");
+    let font = BuiltinFont::helvetica(&document);
+
+    c.bench_function("invoice layout with cached text widths", |b| {
+        b.iter(|| cache_text_widths(10_000, || measure(&invoice_element(&font), 210.)))
+    });
+}
+
+fn build(c: &mut Criterion) {
+    c.bench_function("invoice build and save", |b| {
+        b.iter(|| {
+            let document = build_pdf(
+                "invoice",
+                (210., 297.),
+                BuiltinFont::helvetica,
+                invoice_element,
+            );
+
+            let mut bytes = Vec::new();
+            document.save(&mut BufWriter::new(&mut bytes)).unwrap();
+            black_box(bytes);
+        })
+    });
+}
+
+criterion_group!(benches, text, layout, cached_layout, build);
+criterion_main!(benches);
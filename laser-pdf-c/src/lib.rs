@@ -0,0 +1,105 @@
+//! A C interface to [render_json], so services written in other
+//! languages can render documents in-process. The fonts have to be given as paths in the `fonts`
+//! field of the JSON.
+//!
+//! ```c
+//! int32_t laser_pdf_render(const uint8_t *json, size_t json_len, uint8_t **out, size_t *out_len);
+//! void laser_pdf_free(uint8_t *out, size_t out_len);
+//! ```
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use laser_pdf::render::render_json;
+
+pub const LASER_PDF_OK: i32 = 0;
+
+/// A pointer was null or the JSON wasn't valid UTF-8.
+pub const LASER_PDF_INVALID_ARGUMENTS: i32 = 1;
+
+/// The document couldn't be rendered. The output is the error message in UTF-8.
+pub const LASER_PDF_RENDER_ERROR: i32 = 2;
+
+/// Rendering panicked. There's no output.
+pub const LASER_PDF_PANIC: i32 = 3;
+
+/// Renders the JSON document. On success and on render errors the output is written to `out` and
+/// `out_len` and has to be freed with [laser_pdf_free].
+///
+/// # Safety
+///
+/// `json` has to point to `json_len` readable bytes and `out` and `out_len` have to be valid for
+/// writes.
+#[no_mangle]
+pub unsafe extern "C" fn laser_pdf_render(
+    json: *const u8,
+    json_len: usize,
+    out: *mut *mut u8,
+    out_len: *mut usize,
+) -> i32 {
+    if json.is_null() || out.is_null() || out_len.is_null() {
+        return LASER_PDF_INVALID_ARGUMENTS;
+    }
+
+    let json = match std::str::from_utf8(std::slice::from_raw_parts(json, json_len)) {
+        Ok(json) => json,
+        Err(_) => return LASER_PDF_INVALID_ARGUMENTS,
+    };
+
+    let (code, bytes) = match catch_unwind(AssertUnwindSafe(|| render_json(json, &[]))) {
+        Ok(Ok(pdf)) => (LASER_PDF_OK, pdf),
+        Ok(Err(error)) => (LASER_PDF_RENDER_ERROR, error.into_bytes()),
+        Err(_) => return LASER_PDF_PANIC,
+    };
+
+    let bytes = bytes.into_boxed_slice();
+    *out_len = bytes.len();
+    *out = Box::into_raw(bytes) as *mut u8;
+
+    code
+}
+
+/// Frees output returned by [laser_pdf_render].
+///
+/// # Safety
+///
+/// `out` and `out_len` have to be exactly what [laser_pdf_render] returned and each output can only
+/// be freed once.
+#[no_mangle]
+pub unsafe extern "C" fn laser_pdf_free(out: *mut u8, out_len: usize) {
+    if !out.is_null() {
+        let out = std::ptr::slice_from_raw_parts_mut(out, out_len);
+        drop(Box::from_raw(out));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let render = |json: &str| unsafe {
+            let mut out = std::ptr::null_mut();
+            let mut out_len = 0;
+
+            let code = laser_pdf_render(json.as_ptr(), json.len(), &mut out, &mut out_len);
+            let output = std::slice::from_raw_parts(out, out_len).to_vec();
+            laser_pdf_free(out, out_len);
+
+            (code, output)
+        };
+
+        let (code, pdf) =
+            render(r#"{ "page_size": [210, 297], "element": { "VGap": { "gap": 10 } } }"#);
+        assert_eq!(code, LASER_PDF_OK);
+        assert!(pdf.starts_with(b"%PDF"));
+
+        let (code, error) = render("{}");
+        assert_eq!(code, LASER_PDF_RENDER_ERROR);
+        assert!(!error.is_empty());
+
+        let code =
+            unsafe { laser_pdf_render(std::ptr::null(), 0, &mut std::ptr::null_mut(), &mut 0) };
+        assert_eq!(code, LASER_PDF_INVALID_ARGUMENTS);
+    }
+}
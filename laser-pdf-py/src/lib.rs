@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use pyo3::{exceptions::PyValueError, prelude::*, types::PyBytes};
+use serde_json::{json, Map, Value};
+
+/// Renders a JSON document to PDF bytes, see `laser_pdf::render::render_json`. The fonts are
+/// TrueType font files by name.
+#[pyfunction]
+#[pyo3(signature = (input, fonts = None))]
+fn render_json<'py>(
+    py: Python<'py>,
+    input: &str,
+    fonts: Option<HashMap<String, Vec<u8>>>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    render(py, input, &fonts.unwrap_or_default())
+}
+
+fn render<'py>(
+    py: Python<'py>,
+    input: &str,
+    fonts: &HashMap<String, Vec<u8>>,
+) -> PyResult<Bound<'py, PyBytes>> {
+    let fonts = fonts
+        .iter()
+        .map(|(name, bytes)| (name.as_str(), bytes.as_slice()))
+        .collect::<Vec<_>>();
+
+    let pdf = py
+        .allow_threads(|| laser_pdf::render::render_json(input, &fonts))
+        .map_err(PyValueError::new_err)?;
+
+    Ok(PyBytes::new_bound(py, &pdf))
+}
+
+/// Converts dicts, lists, strings and numbers to JSON with Python's `json` module.
+fn to_json(value: &Bound<'_, PyAny>) -> PyResult<Value> {
+    let json = value
+        .py()
+        .import_bound("json")?
+        .call_method1("dumps", (value,))?
+        .extract::<String>()?;
+
+    serde_json::from_str(&json).map_err(|e| PyValueError::new_err(e.to_string()))
+}
+
+/// Builds a document element by element, for rendering from Python without writing the whole
+/// JSON at once. The elements are dicts in the JSON format and are laid out in a column:
+///
+/// ```python
+/// document = Document((210, 297), title="Report")
+/// document.font("body", font_bytes)
+/// document.set("defaults", {"font": "body", "size": 10})
+/// document.add({"Text": {"text": "Total: 42"}})
+/// pdf = document.render()
+/// ```
+#[pyclass]
+struct Document {
+    page_size: Value,
+    gap: f64,
+    content: Vec<Value>,
+    fonts: HashMap<String, Vec<u8>>,
+
+    /// The other fields of the document, like `title`, `constants` or `defaults`.
+    fields: Map<String, Value>,
+}
+
+#[pymethods]
+impl Document {
+    /// The page size is in mm or a name like `"A4"` and the gap between the elements is in mm.
+    #[new]
+    #[pyo3(signature = (page_size, title = None, gap = 0.))]
+    fn new(page_size: &Bound<'_, PyAny>, title: Option<String>, gap: f64) -> PyResult<Self> {
+        let mut fields = Map::new();
+
+        if let Some(title) = title {
+            fields.insert("title".to_string(), Value::String(title));
+        }
+
+        Ok(Document {
+            page_size: to_json(page_size)?,
+            gap,
+            content: Vec::new(),
+            fonts: HashMap::new(),
+            fields,
+        })
+    }
+
+    /// Adds a TrueType font file by the name the elements use for it.
+    fn font(mut slf: PyRefMut<'_, Self>, name: String, bytes: Vec<u8>) -> PyRefMut<'_, Self> {
+        slf.fonts.insert(name, bytes);
+        slf
+    }
+
+    /// Sets another field of the document, like `constants`, `defaults` or `lang`.
+    fn set<'a>(
+        mut slf: PyRefMut<'a, Self>,
+        name: String,
+        value: &Bound<'_, PyAny>,
+    ) -> PyResult<PyRefMut<'a, Self>> {
+        if matches!(&name[..], "page_size" | "element") {
+            return Err(PyValueError::new_err(format!(
+                "`{name}` is set by the document"
+            )));
+        }
+
+        slf.fields.insert(name, to_json(value)?);
+        Ok(slf)
+    }
+
+    /// Adds an element below the ones added before.
+    fn add<'a>(
+        mut slf: PyRefMut<'a, Self>,
+        element: &Bound<'_, PyAny>,
+    ) -> PyResult<PyRefMut<'a, Self>> {
+        slf.content.push(to_json(element)?);
+        Ok(slf)
+    }
+
+    /// The document in the JSON format of `render_json`.
+    fn to_json(&self) -> String {
+        let mut document = self.fields.clone();
+
+        document.insert("page_size".to_string(), self.page_size.clone());
+        document.insert(
+            "element".to_string(),
+            json!({ "Column": { "content": self.content, "gap": self.gap } }),
+        );
+
+        Value::Object(document).to_string()
+    }
+
+    /// Renders the document to PDF bytes. Errors in the document become `ValueError`s.
+    fn render<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        render(py, &self.to_json(), &self.fonts)
+    }
+}
+
+#[pymodule]
+fn laser_pdf_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_function(wrap_pyfunction!(render_json, m)?)?;
+    m.add_class::<Document>()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use pyo3::types::PyDict;
+
+    use super::*;
+
+    #[test]
+    fn test_document() {
+        pyo3::prepare_freethreaded_python();
+
+        Python::with_gil(|py| {
+            let module = PyModule::new_bound(py, "laser_pdf_py").unwrap();
+            laser_pdf_py(&module).unwrap();
+
+            let globals = PyDict::new_bound(py);
+            globals.set_item("laser_pdf_py", module).unwrap();
+
+            py.run_bound(
+                r#"
+document = laser_pdf_py.Document("A4", title="Report", gap=2)
+document.set("constants", {"gap": 10}).add({"VGap": {"gap": "gap"}})
+document.add({"VGap": {"gap": 5}})
+
+pdf = document.render()
+json = document.to_json()
+
+try:
+    laser_pdf_py.render_json("{}")
+    render_error = None
+except ValueError as e:
+    render_error = str(e)
+
+try:
+    document.set("element", {})
+    set_error = None
+except ValueError as e:
+    set_error = str(e)
+"#,
+                Some(&globals),
+                None,
+            )
+            .unwrap();
+
+            let get = |name| globals.get_item(name).unwrap().unwrap();
+
+            assert!(get("pdf")
+                .extract::<Vec<u8>>()
+                .unwrap()
+                .starts_with(b"%PDF"));
+
+            let json: Value =
+                serde_json::from_str(&get("json").extract::<String>().unwrap()).unwrap();
+            assert_eq!(
+                json,
+                json!({
+                    "title": "Report",
+                    "page_size": "A4",
+                    "constants": { "gap": 10 },
+                    "element": { "Column": {
+                        "content": [{ "VGap": { "gap": "gap" } }, { "VGap": { "gap": 5 } }],
+                        "gap": 2.0,
+                    } },
+                }),
+            );
+
+            assert!(!get("render_error").is_none());
+            assert!(!get("set_error").is_none());
+        });
+    }
+}
@@ -0,0 +1,262 @@
+//! Text markup annotations (ISO 32000-1:2008 12.5.6.10). Unlike drawn decorations, viewers and
+//! review tools treat these as highlights, underlines and strikeouts that can be selected,
+//! commented on and removed.
+//!
+//! Ranges of a [Text](crate::elements::text::Text) are marked with [TextMarkup] and spans of
+//! [RichText](crate::elements::rich_text::RichText) with [SpanMarkup]. Like threads,
+//! printpdf can't write these, so the annotations are collected while building the document and
+//! added to the saved document afterwards:
+//!
+//! ```ignore
+//! let (document, annotations) = collect_markup(|| build_pdf(..));
+//! let mut document = lopdf::Document::load_mem(&save(document))?;
+//! add_markup_annotations(&mut document, &annotations)?;
+//! ```
+
+use std::{cell::RefCell, ops::Range};
+
+use lopdf::{dictionary, Document, Object};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::{mm_to_pt, scoped, u32_to_rgb_color_array};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarkupKind {
+    Highlight,
+    Underline,
+    StrikeOut,
+}
+
+impl MarkupKind {
+    fn subtype(self) -> &'static str {
+        match self {
+            MarkupKind::Highlight => "Highlight",
+            MarkupKind::Underline => "Underline",
+            MarkupKind::StrikeOut => "StrikeOut",
+        }
+    }
+}
+
+/// Marks a range of the text, in bytes.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TextMarkup {
+    pub range: Range<usize>,
+    pub kind: MarkupKind,
+    pub color: u32,
+}
+
+/// Marks the whole text of a [Span](crate::elements::rich_text::Span).
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpanMarkup {
+    pub kind: MarkupKind,
+
+    #[serde(deserialize_with = "crate::serde_elements::color::deserialize_color")]
+    pub color: u32,
+}
+
+/// The area of the marked text on one line, in millimeters from the bottom left of the page.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Quad {
+    pub left: f64,
+    pub right: f64,
+    pub top: f64,
+    pub bottom: f64,
+}
+
+/// The part of a [TextMarkup] on one page.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MarkupAnnotation {
+    pub kind: MarkupKind,
+    pub color: u32,
+
+    /// The index of the page in the document, starting at 0.
+    pub page: usize,
+
+    /// One for every line.
+    pub quads: Vec<Quad>,
+}
+
+thread_local! {
+    static ANNOTATIONS: RefCell<Option<Vec<MarkupAnnotation>>> = const { RefCell::new(None) };
+}
+
+/// Collects the annotations of all text markup drawn on this thread while `f` is running.
+pub fn collect_markup<R>(f: impl FnOnce() -> R) -> (R, Vec<MarkupAnnotation>) {
+    let (ret, annotations) = scoped(&ANNOTATIONS, Some(Vec::new()), f);
+    (ret, annotations.unwrap_or_default())
+}
+
+/// Whether annotations are being collected, so elements can skip computing them otherwise.
+pub(crate) fn collecting() -> bool {
+    ANNOTATIONS.with(|annotations| annotations.borrow().is_some())
+}
+
+pub(crate) fn add(annotation: MarkupAnnotation) {
+    ANNOTATIONS.with(|annotations| {
+        if let Some(annotations) = annotations.borrow_mut().as_mut() {
+            annotations.push(annotation);
+        }
+    });
+}
+
+/// Adds the annotations to their pages. Annotations on pages that don't exist in the document are
+/// an error.
+pub fn add_markup_annotations(
+    document: &mut Document,
+    annotations: &[MarkupAnnotation],
+) -> lopdf::Result<()> {
+    let pages = document.get_pages();
+
+    for annotation in annotations {
+        if annotation.quads.is_empty() {
+            continue;
+        }
+
+        let page_id = *pages
+            .get(&(annotation.page as u32 + 1))
+            .ok_or(lopdf::Error::ObjectNotFound)?;
+
+        let pt = |v: f64| Object::Real(mm_to_pt(v) as _);
+
+        // The corners of every quad are in the order the common viewers expect rather than the
+        // one in the specification: top left, top right, bottom left, bottom right.
+        let quad_points = annotation
+            .quads
+            .iter()
+            .flat_map(|q| {
+                [
+                    q.left, q.top, q.right, q.top, q.left, q.bottom, q.right, q.bottom,
+                ]
+            })
+            .map(pt)
+            .collect::<Vec<_>>();
+
+        let rect = [
+            annotation
+                .quads
+                .iter()
+                .map(|q| q.left)
+                .fold(f64::INFINITY, f64::min),
+            annotation
+                .quads
+                .iter()
+                .map(|q| q.bottom)
+                .fold(f64::INFINITY, f64::min),
+            annotation
+                .quads
+                .iter()
+                .map(|q| q.right)
+                .fold(f64::NEG_INFINITY, f64::max),
+            annotation
+                .quads
+                .iter()
+                .map(|q| q.top)
+                .fold(f64::NEG_INFINITY, f64::max),
+        ];
+
+        let color = u32_to_rgb_color_array(annotation.color)
+            .into_iter()
+            .map(|c| Object::Real((c as f64 / 255.) as _))
+            .collect::<Vec<_>>();
+
+        let annotation_id = document.add_object(dictionary! {
+            "Type" => "Annot",
+            "Subtype" => annotation.kind.subtype(),
+            "Rect" => rect.into_iter().map(pt).collect::<Vec<_>>(),
+            "QuadPoints" => quad_points,
+            "C" => color,
+            "P" => page_id,
+        });
+
+        let page = document.get_object_mut(page_id)?.as_dict_mut()?;
+
+        let mut annots = match page.remove(b"Annots") {
+            Some(Object::Array(annots)) => annots,
+            _ => Vec::new(),
+        };
+
+        annots.push(Object::Reference(annotation_id));
+        page.set("Annots", annots);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_markup_annotations() {
+        let mut document = Document::with_version("1.5");
+
+        let pages_id = document.new_object_id();
+        let page_id = document.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+        });
+
+        document.objects.insert(
+            pages_id,
+            dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![Object::Reference(page_id)],
+                "Count" => 1,
+            }
+            .into(),
+        );
+
+        let catalog_id = document.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        document.trailer.set("Root", catalog_id);
+
+        let annotation = MarkupAnnotation {
+            kind: MarkupKind::Highlight,
+            color: 0xFF_FF_00_FF,
+            page: 0,
+            quads: vec![
+                Quad {
+                    left: 10.,
+                    right: 50.,
+                    top: 100.,
+                    bottom: 95.,
+                },
+                Quad {
+                    left: 5.,
+                    right: 20.,
+                    top: 95.,
+                    bottom: 90.,
+                },
+            ],
+        };
+
+        add_markup_annotations(&mut document, &[annotation.clone()]).unwrap();
+
+        let dict = |id| document.get_object(id).and_then(Object::as_dict).unwrap();
+
+        let annots = dict(page_id).get(b"Annots").unwrap().as_array().unwrap();
+        assert_eq!(annots.len(), 1);
+
+        let annot = dict(annots[0].as_reference().unwrap());
+        assert_eq!(
+            annot.get(b"Subtype").unwrap().as_name().unwrap(),
+            b"Highlight"
+        );
+        assert_eq!(
+            annot.get(b"QuadPoints").unwrap().as_array().unwrap().len(),
+            16
+        );
+
+        let rect = annot.get(b"Rect").unwrap().as_array().unwrap();
+        assert!(matches!(rect[0], Object::Real(left) if left == mm_to_pt(5.) as _));
+        assert!(matches!(rect[3], Object::Real(top) if top == mm_to_pt(100.) as _));
+
+        let annotation = MarkupAnnotation {
+            page: 1,
+            ..annotation
+        };
+        assert!(add_markup_annotations(&mut document, &[annotation]).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+//! Checks the contract between measuring and drawing: given the same constraints, `draw` has to
+//! return the size `measure` returned and break as often as `measure` said it would. Elements
+//! wrapped in [Audit](crate::elements::audit::Audit) are measured again right before they're
+//! drawn while divergences are being collected, and every mismatch is reported with the path of
+//! the element:
+//!
+//! ```ignore
+//! let (document, divergences) = collect_divergences(|| build_pdf(..));
+//! assert!(divergences.is_empty(), "{divergences:#?}");
+//! ```
+
+use std::{cell::RefCell, fmt};
+
+use crate::{utils::scoped, ElementSize};
+
+/// An element whose drawing didn't match its measurement.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Divergence {
+    /// The ids of the audited elements from the outermost one to this one, separated by `/`.
+    pub path: String,
+
+    pub measured: ElementSize,
+    pub drawn: ElementSize,
+
+    pub measured_breaks: u32,
+    pub drawn_breaks: u32,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: measured {:?} x {:?} with {} breaks, drew {:?} x {:?} with {} breaks",
+            self.path,
+            self.measured.width,
+            self.measured.height,
+            self.measured_breaks,
+            self.drawn.width,
+            self.drawn.height,
+            self.drawn_breaks,
+        )
+    }
+}
+
+thread_local! {
+    static DIVERGENCES: RefCell<Option<Vec<Divergence>>> = const { RefCell::new(None) };
+    static PATH: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Collects the divergences of all audited elements drawn on this thread while `f` is running.
+pub fn collect_divergences<R>(f: impl FnOnce() -> R) -> (R, Vec<Divergence>) {
+    let (ret, divergences) = scoped(&DIVERGENCES, Some(Vec::new()), f);
+    (ret, divergences.unwrap_or_default())
+}
+
+/// Whether divergences are being collected, so audited elements can skip the extra measurement
+/// otherwise.
+pub fn auditing() -> bool {
+    DIVERGENCES.with(|divergences| divergences.borrow().is_some())
+}
+
+/// Runs `f` with `id` added to the path of the audited elements.
+pub(crate) fn within<R>(id: &str, f: impl FnOnce() -> R) -> R {
+    struct Pop;
+
+    impl Drop for Pop {
+        fn drop(&mut self) {
+            PATH.with(|path| path.borrow_mut().pop());
+        }
+    }
+
+    PATH.with(|path| path.borrow_mut().push(id.to_string()));
+    let _pop = Pop;
+
+    f()
+}
+
+/// The path of the innermost audited element that's being drawn.
+pub(crate) fn path() -> String {
+    PATH.with(|path| path.borrow().join("/"))
+}
+
+pub(crate) fn add(divergence: Divergence) {
+    DIVERGENCES.with(|divergences| {
+        if let Some(divergences) = divergences.borrow_mut().as_mut() {
+            divergences.push(divergence);
+        }
+    });
+}
@@ -0,0 +1,44 @@
+//! Renders documents from newline delimited JSON on stdin, see [laser_pdf::render::serve].
+//!
+//! With `--progress`, progress events are written to stderr as JSON lines. With
+//! `--base-dir <dir>`, files can only be loaded from relative paths inside of that directory. With
+//! `--preview <dpi>` and the `preview` feature, a PNG of every page is written next to each PDF.
+
+use std::io::{stderr, stdin, stdout, Write};
+
+use laser_pdf::render::Renderer;
+
+fn main() -> std::io::Result<()> {
+    let mut progress = false;
+    let mut renderer = Renderer::default();
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match &arg[..] {
+            "--progress" => progress = true,
+            "--base-dir" => match args.next() {
+                Some(dir) => renderer = renderer.with_base_dir(dir),
+                None => usage(),
+            },
+            #[cfg(feature = "preview")]
+            "--preview" => match args.next().and_then(|dpi| dpi.parse().ok()) {
+                Some(dpi) => renderer = renderer.with_preview(dpi),
+                None => usage(),
+            },
+            _ => usage(),
+        }
+    }
+
+    let mut stderr = stderr().lock();
+
+    renderer.serve(
+        stdin().lock(),
+        stdout().lock(),
+        progress.then_some(&mut stderr as &mut dyn Write),
+    )
+}
+
+fn usage() -> ! {
+    eprintln!("usage: laser-pdf-serve [--progress] [--base-dir <dir>] [--preview <dpi>]");
+    std::process::exit(2);
+}
@@ -0,0 +1,117 @@
+//! The budgets of [Limits](crate::Limits) for the size of the page content streams and for the
+//! memory of the images of a document. Elements report what they add to a page while drawing,
+//! which the helpers in [utils](crate::utils) like [add_shape](crate::utils::add_shape) do for
+//! them. Once a budget is exceeded, nothing more of its kind is added, since the document fails
+//! anyway.
+//!
+//! The size of the content is an estimate from the text, paths and operators that are added, with
+//! a rough size for each, since printpdf only writes the content streams when the document is
+//! saved.
+
+use std::cell::RefCell;
+
+use crate::utils::scoped;
+
+/// What a document used of the budgets, see [crate::Stats].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub(crate) struct Usage {
+    pub content_bytes: u64,
+    pub image_memory: u64,
+}
+
+struct Budget {
+    max_content_bytes: Option<u64>,
+    max_image_memory: Option<u64>,
+    usage: Usage,
+}
+
+thread_local! {
+    static BUDGET: RefCell<Option<Budget>> = const { RefCell::new(None) };
+}
+
+/// Counts what the document drawn on this thread while `f` is running uses of the budgets.
+pub(crate) fn with_budget<R>(
+    max_content_bytes: Option<u64>,
+    max_image_memory: Option<u64>,
+    f: impl FnOnce() -> R,
+) -> (R, Usage) {
+    let budget = Budget {
+        max_content_bytes,
+        max_image_memory,
+        usage: Usage::default(),
+    };
+
+    let (ret, budget) = scoped(&BUDGET, Some(budget), f);
+    (ret, budget.map_or_else(Usage::default, |b| b.usage))
+}
+
+/// Counts content that's about to be added to a page and returns whether it still fits into the
+/// budget. Outside of a build, everything fits.
+pub fn add_content(bytes: u64) -> bool {
+    BUDGET.with(|budget| match budget.borrow_mut().as_mut() {
+        Some(budget) => {
+            budget.usage.content_bytes = budget.usage.content_bytes.saturating_add(bytes);
+            !matches!(budget.max_content_bytes, Some(max) if budget.usage.content_bytes > max)
+        }
+        None => true,
+    })
+}
+
+/// Counts a decoded image that's about to be embedded and returns whether it still fits into the
+/// budget. Every time an image is drawn it's embedded again, so it's counted every time.
+pub fn add_image_memory(bytes: u64) -> bool {
+    BUDGET.with(|budget| match budget.borrow_mut().as_mut() {
+        Some(budget) => {
+            budget.usage.image_memory = budget.usage.image_memory.saturating_add(bytes);
+            !matches!(budget.max_image_memory, Some(max) if budget.usage.image_memory > max)
+        }
+        None => true,
+    })
+}
+
+/// Roughly how many bytes a text takes up in a content stream, with every character written as a
+/// glyph id in hex and the operators for the font and the position.
+pub fn text_size(text: &str) -> u64 {
+    4 * text.chars().count() as u64 + 48
+}
+
+/// Roughly how many bytes an operator with its operands takes up in a content stream.
+pub fn operation_size(operation: &lopdf::content::Operation) -> u64 {
+    operation.operator.len() as u64 + 8 * operation.operands.len() as u64 + 1
+}
+
+/// Roughly how many bytes a shape takes up in a content stream, with the coordinates of every
+/// point and the operators for drawing it.
+pub fn shape_size(shape: &printpdf::Line) -> u64 {
+    20 * shape.points.len() as u64 + 4
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget() {
+        let (fits, usage) = with_budget(Some(100), None, || {
+            [add_content(60), add_content(40), add_content(1)]
+        });
+
+        assert_eq!(fits, [true, true, false]);
+        assert_eq!(usage.content_bytes, 101);
+
+        let (fits, usage) = with_budget(None, Some(10), || {
+            [add_image_memory(20), add_content(1_000_000)]
+        });
+
+        assert_eq!(fits, [false, true]);
+        assert_eq!(
+            usage,
+            Usage {
+                content_bytes: 1_000_000,
+                image_memory: 20,
+            }
+        );
+
+        assert!(add_content(u64::MAX));
+    }
+}
@@ -0,0 +1,147 @@
+//! The direction of a document, for Arabic and Hebrew reports that are read from right to left.
+//! Viewers show the pages of such documents side by side starting on the right once the
+//! `Direction` viewer preference (ISO 32000-1:2008 12.2) is set. Like threads, printpdf can't write
+//! this, so it's added to the saved document afterwards:
+//!
+//! ```ignore
+//! let mut document = lopdf::Document::load_mem(&save(build_pdf(..)))?;
+//! set_direction(&mut document, Direction::Rtl)?;
+//! ```
+//!
+//! The serde elements align text to the right and lay out rows from right to left by default when
+//! the direction of the [Defaults](crate::serde_elements::defaults::Defaults) is [Direction::Rtl].
+//! A document rendered with `"direction":"rtl"` gets both, see [crate::render].
+
+use itertools::Either;
+use lopdf::{Dictionary, Document, Object};
+use serde::{Deserialize, Serialize};
+
+use crate::elements::text::TextAlign;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    #[default]
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    /// The alignment of text that doesn't have one, which is the side lines start on.
+    pub fn text_align(self) -> TextAlign {
+        match self {
+            Direction::Ltr => TextAlign::Left,
+            Direction::Rtl => TextAlign::Right,
+        }
+    }
+
+    /// The items in the order they're laid out from left to right.
+    pub fn order<T>(self, items: &[T]) -> impl DoubleEndedIterator<Item = &T> {
+        match self {
+            Direction::Ltr => Either::Left(items.iter()),
+            Direction::Rtl => Either::Right(items.iter().rev()),
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Direction::Ltr => "L2R",
+            Direction::Rtl => "R2L",
+        }
+    }
+}
+
+/// Sets the `Direction` viewer preference of the document, keeping the other viewer preferences.
+pub fn set_direction(document: &mut Document, direction: Direction) -> lopdf::Result<()> {
+    let catalog_id = document.trailer.get(b"Root")?.as_reference()?;
+    let catalog = document.get_object(catalog_id)?.as_dict()?;
+    let name = Object::Name(direction.name().into());
+
+    match catalog.get(b"ViewerPreferences") {
+        Ok(&Object::Reference(id)) => {
+            document
+                .get_object_mut(id)?
+                .as_dict_mut()?
+                .set("Direction", name);
+        }
+        preferences => {
+            let mut preferences = match preferences {
+                Ok(Object::Dictionary(preferences)) => preferences.clone(),
+                _ => Dictionary::new(),
+            };
+
+            preferences.set("Direction", name);
+
+            let catalog = document.get_object_mut(catalog_id)?.as_dict_mut()?;
+            catalog.set("ViewerPreferences", preferences);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::dictionary;
+
+    use super::*;
+
+    #[test]
+    fn test_order() {
+        let items = [1, 2, 3];
+
+        assert_eq!(
+            Direction::Ltr.order(&items).collect::<Vec<_>>(),
+            [&1, &2, &3]
+        );
+        assert_eq!(
+            Direction::Rtl.order(&items).collect::<Vec<_>>(),
+            [&3, &2, &1]
+        );
+    }
+
+    #[test]
+    fn test_set_direction() {
+        let mut document = Document::with_version("1.5");
+
+        let catalog_id = document.add_object(dictionary! {
+            "Type" => "Catalog",
+        });
+        document.trailer.set("Root", catalog_id);
+
+        let direction = |document: &Document| {
+            let catalog = document.get_object(catalog_id).and_then(Object::as_dict);
+            let preferences = match catalog.unwrap().get(b"ViewerPreferences").unwrap() {
+                &Object::Reference(id) => document.get_object(id).unwrap(),
+                preferences => preferences,
+            };
+            let preferences = preferences.as_dict().unwrap();
+
+            (
+                preferences
+                    .get(b"Direction")
+                    .unwrap()
+                    .as_name()
+                    .unwrap()
+                    .to_vec(),
+                preferences.get(b"HideToolbar").is_ok(),
+            )
+        };
+
+        set_direction(&mut document, Direction::Rtl).unwrap();
+        assert_eq!(direction(&document), (b"R2L".to_vec(), false));
+
+        // Other preferences are kept, also when they're referenced.
+        let preferences_id = document.add_object(dictionary! {
+            "HideToolbar" => true,
+        });
+        document
+            .get_object_mut(catalog_id)
+            .and_then(Object::as_dict_mut)
+            .unwrap()
+            .set("ViewerPreferences", preferences_id);
+
+        set_direction(&mut document, Direction::Ltr).unwrap();
+        assert_eq!(direction(&document), (b"L2R".to_vec(), true));
+    }
+}
@@ -1,22 +1,43 @@
 pub mod align_location_bottom;
 pub mod align_preferred_height_bottom;
+pub mod arc;
+pub mod audit;
+pub mod balance_last_page;
 pub mod break_list;
 pub mod break_whole;
+pub mod cached;
+pub mod calendar;
 pub mod center_in_preferred_height;
+pub mod change_bar;
 pub mod changing_title;
 pub mod circle;
+pub mod code;
 pub mod column;
+pub mod continued;
 pub mod debug;
+pub mod ellipse;
+pub mod equal_rows;
 pub mod expand_to_preferred_height;
+pub mod fade_out;
+pub mod figure;
+pub mod fill_remaining;
 pub mod force_break;
 pub mod h_align;
 pub mod image;
+pub mod lang;
 pub mod line;
+pub mod meta;
+pub mod min_break_height;
 pub mod min_first_height;
+pub mod multi_column;
 pub mod none;
+pub mod on_break;
 pub mod padding;
 pub mod page;
+pub mod paginate;
 pub mod pin_below;
+pub mod poly_line;
+pub mod polygon;
 pub mod rectangle;
 pub mod repeat_after_break;
 pub mod repeat_bottom;
@@ -24,11 +45,15 @@ pub mod rich_text;
 pub mod rotate;
 pub mod row;
 pub mod shrink_to_fit;
+pub mod signature_line;
 pub mod stack;
 pub mod styled_box;
 pub mod svg;
+pub mod symbol;
+pub mod table_group;
 pub mod table_row;
 pub mod text;
 pub mod title_or_break;
 pub mod titled;
+pub mod trace;
 pub mod v_gap;
@@ -0,0 +1,175 @@
+use std::f64::consts::FRAC_PI_2;
+
+use printpdf::{Line, Point};
+
+use crate::{utils::*, *};
+
+/// A part of a circle outline. Angles are in degrees, counterclockwise from the right (like in
+/// mathematics). With a fill the arc is drawn as a pie slice.
+///
+/// The element is the size of the whole circle, so that arcs of the same circle line up.
+pub struct Arc {
+    pub radius: f64,
+    pub start_angle: f64,
+    pub end_angle: f64,
+    pub fill: Option<u32>,
+    pub outline: Option<LineStyle>,
+}
+
+impl Element for Arc {
+    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        let outline_thickness = outline_thickness(self);
+        ctx.break_if_appropriate_for_min_height(self.radius * 2. + outline_thickness);
+
+        size(self)
+    }
+
+    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+        let outline_thickness = outline_thickness(self);
+        ctx.break_if_appropriate_for_min_height(self.radius * 2. + outline_thickness);
+
+        let extra_outline_offset = outline_thickness / 2.0;
+
+        let center = (
+            ctx.location.pos.0 + self.radius + extra_outline_offset,
+            ctx.location.pos.1 - self.radius - extra_outline_offset,
+        );
+
+        let mut points = Vec::new();
+
+        if self.fill.is_some() {
+            points.push((Point::new(Mm(center.0), Mm(center.1)), false));
+        }
+
+        for (x, y) in arc_points(self.radius, self.start_angle, self.end_angle) {
+            points.push((Point::new(Mm(center.0 + x), Mm(center.1 + y)), true));
+        }
+
+        ctx.location.layer.save_graphics_state();
+
+        if let Some(color) = self.fill {
+            let (color, alpha) = u32_to_color_and_alpha(color);
+            ctx.location.layer.set_fill_color(color);
+            ctx.location.layer.set_fill_alpha(alpha);
+        }
+
+        if let Some(line_style) = self.outline {
+            set_line_style(&ctx.location.layer, &line_style, ctx.pdf.line_rendering);
+        }
+
+        add_shape(
+            &ctx.location.layer,
+            Line {
+                points,
+                is_closed: self.fill.is_some(),
+                has_fill: self.fill.is_some(),
+                has_stroke: self.outline.is_some(),
+                is_clipping_path: false,
+            },
+        );
+
+        ctx.location.layer.restore_graphics_state();
+
+        size(self)
+    }
+}
+
+/// Returns the start point followed by two control points and an end point for each cubic Bézier
+/// segment of the arc, relative to the center with y going up. Each segment covers at most 90°.
+fn arc_points(radius: f64, start_angle: f64, end_angle: f64) -> Vec<(f64, f64)> {
+    let start = start_angle.to_radians();
+    let sweep = (end_angle - start_angle).to_radians();
+
+    let segments = (sweep.abs() / FRAC_PI_2).ceil().max(1.) as usize;
+    let step = sweep / segments as f64;
+
+    // The distance of the control points from their end points along the tangent.
+    let k = 4. / 3. * (step / 4.).tan() * radius;
+
+    let point = |angle: f64| (radius * angle.cos(), radius * angle.sin());
+
+    let mut points = vec![point(start)];
+
+    for i in 0..segments {
+        let a = start + step * i as f64;
+        let b = a + step;
+
+        let (ax, ay) = point(a);
+        let (bx, by) = point(b);
+
+        points.push((ax - k * a.sin(), ay + k * a.cos()));
+        points.push((bx + k * b.sin(), by - k * b.cos()));
+        points.push((bx, by));
+    }
+
+    points
+}
+
+fn outline_thickness(arc: &Arc) -> f64 {
+    arc.outline.map(|o| o.thickness).unwrap_or(0.0)
+}
+
+fn size(arc: &Arc) -> ElementSize {
+    let size = arc.radius * 2. + outline_thickness(arc);
+
+    ElementSize {
+        width: Some(size),
+        height: Some(size),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_arc() {
+        for output in (ElementTestParams {
+            first_height: 11.,
+            ..Default::default()
+        })
+        .run(&Arc {
+            radius: 5.5,
+            start_angle: 0.,
+            end_angle: 225.,
+            fill: None,
+            outline: Some(LineStyle::solid(1., 0)),
+        }) {
+            output.assert_size(ElementSize {
+                width: Some(12.),
+                height: Some(12.),
+            });
+
+            if let Some(b) = output.breakable {
+                if output.first_height == 11. {
+                    b.assert_break_count(1);
+                } else {
+                    b.assert_break_count(0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_arc_points() {
+        let points = arc_points(2., 0., 180.);
+
+        // Two segments of 90° each.
+        assert_eq!(points.len(), 7);
+
+        let close = |(ax, ay): (f64, f64), (bx, by): (f64, f64)| {
+            assert!(
+                (ax - bx).abs() < 1e-9 && (ay - by).abs() < 1e-9,
+                "{ax},{ay} != {bx},{by}"
+            );
+        };
+
+        close(points[0], (2., 0.));
+        close(points[3], (0., 2.));
+        close(points[6], (-2., 0.));
+
+        // The control points of a quarter circle are about 0.5523 times the radius away.
+        close(points[1], (2., 2. * 0.5522847498307936));
+    }
+}
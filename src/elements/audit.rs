@@ -0,0 +1,169 @@
+use crate::{
+    audit::{self, Divergence},
+    *,
+};
+
+/// Checks that drawing the element matches measuring it while divergences are being collected with
+/// [collect_divergences](crate::audit::collect_divergences). Otherwise everything is passed
+/// through. Sizes and break counts are only compared when there's no preferred height, since
+/// elements may use that to draw taller than they measured.
+pub struct Audit<'a, E: Element> {
+    pub id: &'a str,
+    pub element: &'a E,
+}
+
+/// Heights that differ by less than this are the same, to allow for floating point errors.
+const TOLERANCE: f64 = 1e-6;
+
+fn same(a: Option<f64>, b: Option<f64>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => (a - b).abs() < TOLERANCE,
+        (a, b) => a.is_none() && b.is_none(),
+    }
+}
+
+impl<'a, E: Element> Element for Audit<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        if !audit::auditing() {
+            return self.element.draw(ctx);
+        }
+
+        audit::within(self.id, || {
+            let mut measured_breaks = 0;
+            let mut extra_location_min_height = None;
+
+            let measured = self.element.measure(MeasureCtx {
+                width: ctx.width,
+                first_height: ctx.first_height,
+                breakable: ctx.breakable.as_ref().map(|b| BreakableMeasure {
+                    full_height: b.full_height,
+                    break_count: &mut measured_breaks,
+                    extra_location_min_height: &mut extra_location_min_height,
+                }),
+            });
+
+            let preferred_height = ctx.preferred_height;
+            let mut drawn_breaks = 0;
+
+            let drawn = if let Some(breakable) = ctx.breakable {
+                self.element.draw(DrawCtx {
+                    breakable: Some(BreakableDraw {
+                        do_break: &mut |pdf, location_idx, height| {
+                            drawn_breaks = drawn_breaks.max(location_idx + 1);
+                            (breakable.do_break)(pdf, location_idx, height)
+                        },
+                        ..breakable
+                    }),
+                    ..ctx
+                })
+            } else {
+                self.element.draw(ctx)
+            };
+
+            let diverged = !same(measured.width, drawn.width)
+                || (preferred_height.is_none()
+                    && (!same(measured.height, drawn.height) || measured_breaks != drawn_breaks));
+
+            if diverged {
+                audit::add(Divergence {
+                    path: audit::path(),
+                    measured,
+                    drawn,
+                    measured_breaks,
+                    drawn_breaks,
+                });
+            }
+
+            drawn
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{audit::collect_divergences, test_utils::*};
+
+    /// Draws one line more than it measures.
+    struct Liar;
+
+    impl Element for Liar {
+        fn first_location_usage(&self, _: FirstLocationUsageCtx) -> FirstLocationUsage {
+            FirstLocationUsage::WillUse
+        }
+
+        fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+            ElementSize {
+                width: Some(ctx.width.constrain(5.)),
+                height: Some(1.),
+            }
+        }
+
+        fn draw(&self, ctx: DrawCtx) -> ElementSize {
+            ElementSize {
+                width: Some(ctx.width.constrain(5.)),
+                height: Some(2.),
+            }
+        }
+    }
+
+    #[test]
+    fn test_audit() {
+        let text = FakeText {
+            lines: 3,
+            line_height: 2.,
+            width: 5.,
+        };
+
+        let (_, divergences) = collect_divergences(|| {
+            for output in (ElementTestParams {
+                first_height: 4.,
+                full_height: 5.,
+                ..Default::default()
+            })
+            .run(&Audit {
+                id: "text",
+                element: &text,
+            }) {
+                output.assert_size(ElementSize {
+                    width: Some(output.width.constrain(5.)),
+                    height: Some(if output.breakable.is_some() { 2. } else { 6. }),
+                });
+            }
+        });
+
+        assert_eq!(divergences, []);
+
+        let (_, divergences) = collect_divergences(|| {
+            let inner = Audit {
+                id: "liar",
+                element: &Liar,
+            };
+
+            build_pdf(
+                "test",
+                (100., 100.),
+                |_| (),
+                |_: &()| Audit {
+                    id: "outer",
+                    element: &inner,
+                },
+            )
+        });
+
+        // The outer element diverges too, since it draws the inner one.
+        assert_eq!(divergences.len(), 2);
+        assert_eq!(divergences[0].path, "outer/liar");
+        assert_eq!(divergences[0].measured.height, Some(1.));
+        assert_eq!(divergences[0].drawn.height, Some(2.));
+        assert_eq!(divergences[1].path, "outer");
+    }
+}
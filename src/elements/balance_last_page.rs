@@ -0,0 +1,239 @@
+use std::cell::RefCell;
+
+use crate::*;
+
+/// Avoids a nearly empty last location, as they're common in letters, by tightening the spacing of
+/// the content until it fits on the locations before. The element is built with a factor for its
+/// gaps and leading, which is 1 unless the content is tightened. The element has to apply it
+/// itself, e.g. with [LineSpacing::scale_leading] for text.
+///
+/// Finding the factor measures the element once for every factor that's tried, so the chosen one
+/// is remembered for the constraints it was found for.
+pub struct BalanceLastPage<'a, E: Element, F: Fn(f64) -> E> {
+    pub element: F,
+
+    /// The last location counts as nearly empty if the content on it is less high than this
+    /// fraction of a full location.
+    pub min_fill: f64,
+
+    /// The smallest factor that's tried.
+    pub min_factor: f64,
+
+    /// How many factors between 1 and [Self::min_factor] are tried, from the largest one down.
+    pub steps: u32,
+
+    own_factors: Factors,
+    factors: Option<&'a Factors>,
+}
+
+/// The factors a [BalanceLastPage] chose, by the width, the first height and the full height. Like
+/// [Measurements](super::cached::Measurements), they can be kept somewhere that lives longer than
+/// the element, see [BalanceLastPage::with_factors].
+#[derive(Default)]
+pub struct Factors(RefCell<Vec<((WidthConstraint, f64, f64), f64)>>);
+
+impl<'a, E: Element, F: Fn(f64) -> E> BalanceLastPage<'a, E, F> {
+    pub fn new(element: F, min_fill: f64, min_factor: f64, steps: u32) -> Self {
+        BalanceLastPage {
+            element,
+            min_fill,
+            min_factor,
+            steps,
+            own_factors: Factors::default(),
+            factors: None,
+        }
+    }
+
+    /// Remembers the factors in `factors` instead of in the element, so they're shared by every
+    /// [BalanceLastPage] that's built with them. They have to be for the same element.
+    pub fn with_factors(
+        element: F,
+        min_fill: f64,
+        min_factor: f64,
+        steps: u32,
+        factors: &'a Factors,
+    ) -> Self {
+        BalanceLastPage {
+            factors: Some(factors),
+            ..BalanceLastPage::new(element, min_fill, min_factor, steps)
+        }
+    }
+
+    fn measure_with(
+        &self,
+        factor: f64,
+        width: WidthConstraint,
+        first_height: f64,
+        full_height: f64,
+    ) -> (ElementSize, u32) {
+        let mut break_count = 0;
+
+        let size = (self.element)(factor).measure(MeasureCtx {
+            width,
+            first_height,
+            breakable: Some(BreakableMeasure {
+                full_height,
+                break_count: &mut break_count,
+                extra_location_min_height: &mut None,
+            }),
+        });
+
+        (size, break_count)
+    }
+
+    /// The largest factor that saves a location, if the last one is nearly empty.
+    fn factor(&self, width: WidthConstraint, first_height: f64, full_height: Option<f64>) -> f64 {
+        let Some(full_height) = full_height else {
+            return 1.;
+        };
+
+        let key = (width, first_height, full_height);
+        let factors = &self.factors.unwrap_or(&self.own_factors).0;
+
+        if let Some(&(_, factor)) = factors.borrow().iter().find(|(k, _)| *k == key) {
+            return factor;
+        }
+
+        let factor = self.find_factor(width, first_height, full_height);
+        factors.borrow_mut().push((key, factor));
+        factor
+    }
+
+    fn find_factor(&self, width: WidthConstraint, first_height: f64, full_height: f64) -> f64 {
+        let (size, break_count) = self.measure_with(1., width, first_height, full_height);
+
+        if break_count == 0 || size.height.unwrap_or(0.) >= self.min_fill * full_height {
+            return 1.;
+        }
+
+        (1..=self.steps)
+            .map(|step| 1. - (1. - self.min_factor) * step as f64 / self.steps as f64)
+            .find(|&factor| {
+                self.measure_with(factor, width, first_height, full_height)
+                    .1
+                    < break_count
+            })
+            .unwrap_or(1.)
+    }
+}
+
+impl<E: Element, F: Fn(f64) -> E> Element for BalanceLastPage<'_, E, F> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        let factor = self.factor(ctx.width, ctx.first_height, Some(ctx.full_height));
+        (self.element)(factor).first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        let full_height = ctx.breakable.as_ref().map(|b| b.full_height);
+        let factor = self.factor(ctx.width, ctx.first_height, full_height);
+        (self.element)(factor).measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let full_height = ctx.breakable.as_ref().map(|b| b.full_height);
+        let factor = self.factor(ctx.width, ctx.first_height, full_height);
+        (self.element)(factor).draw(ctx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::{elements::column::Column, test_utils::*};
+
+    #[test]
+    fn test_balance_last_page() {
+        let line = &FakeText {
+            lines: 1,
+            line_height: 10.,
+            width: 1.,
+        };
+
+        // Nine lines with gaps of 1.5mm are 102mm high. With a location height of 100mm, the last
+        // line ends up alone on the second one, unless the gaps are 1.25mm or less.
+        let element = BalanceLastPage::new(
+            |factor| Column {
+                gap: 1.5 * factor,
+                ..Column::new(move |mut content| {
+                    for _ in 0..9 {
+                        content = content.add(line)?;
+                    }
+
+                    None
+                })
+            },
+            0.2,
+            0.5,
+            4,
+        );
+
+        for output in (ElementTestParams {
+            first_height: 100.,
+            full_height: 100.,
+            width: 10.,
+            ..Default::default()
+        })
+        .run(&element)
+        {
+            if let Some(b) = output.breakable {
+                b.assert_break_count(0);
+
+                // The factor is 0.75, 0.875 isn't enough.
+                output.assert_size(ElementSize {
+                    width: Some(if output.width.expand { 10. } else { 1. }),
+                    height: Some(9. * 10. + 8. * 1.125),
+                });
+            } else {
+                output.assert_size(ElementSize {
+                    width: Some(if output.width.expand { 10. } else { 1. }),
+                    height: Some(9. * 10. + 8. * 1.5),
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn test_factors() {
+        let builds = Cell::new(0);
+        let factors = Factors::default();
+
+        let element = BalanceLastPage::with_factors(
+            |factor| {
+                builds.set(builds.get() + 1);
+
+                FakeText {
+                    lines: if factor < 0.8 { 9 } else { 11 },
+                    line_height: 10.,
+                    width: 1.,
+                }
+            },
+            0.2,
+            0.5,
+            4,
+            &factors,
+        );
+
+        let width = WidthConstraint {
+            max: 10.,
+            expand: true,
+        };
+
+        // Tried with 1, 0.875 and 0.75.
+        assert_eq!(element.factor(width, 100., Some(100.)), 0.75);
+        assert_eq!(builds.get(), 3);
+
+        assert_eq!(element.factor(width, 100., Some(100.)), 0.75);
+        assert_eq!(builds.get(), 3);
+
+        // The factors are shared with the elements built with them.
+        let element = BalanceLastPage::with_factors(&element.element, 0.2, 0.5, 4, &factors);
+        assert_eq!(element.factor(width, 100., Some(100.)), 0.75);
+        assert_eq!(builds.get(), 3);
+
+        // Other constraints need their own factor.
+        assert_eq!(element.factor(width, 50., Some(100.)), 1.);
+        assert_eq!(builds.get(), 4);
+    }
+}
@@ -0,0 +1,614 @@
+use std::cell::{Cell, RefCell};
+
+use lopdf::{content::Operation, Object};
+
+use crate::letterhead::{inherited, resolve};
+use crate::{
+    utils::{add_op, scoped},
+    *,
+};
+
+/// Remembers the results of measurements of the element, for expensive elements that are measured
+/// many times with the same constraints, like a header that's repeated on every page or a deeply
+/// nested element whose ancestors all measure it again.
+///
+/// Drawing always goes to the element, so its operators are written on every page. Within
+/// [mark_drawings], the drawings are marked in the content though, and [share_drawings] stores
+/// the ones that repeat once as a form XObject in the saved document, like
+/// [letterhead](crate::letterhead) does for whole pages.
+///
+/// Containers like [Row](super::row::Row) measure their children again when they're drawn, so
+/// wrapping the children in [Cached] also makes those measurements hits. This works like a layout
+/// tree that's retained between measuring and drawing, but only for the wrapped elements and
+/// without changing how containers pass measurements to their children.
+///
+/// The element has to be deterministic, which all of the elements in this crate are.
+pub struct Cached<'a, E: Element> {
+    pub element: &'a E,
+    own_measurements: Measurements,
+    measurements: Option<&'a Measurements>,
+}
+
+/// The measurements a [Cached] element remembers. They're usually kept by the element itself, but
+/// an element that's built again for every measurement, like the serde elements are, can keep them
+/// somewhere that lives longer, see [Cached::with_measurements].
+#[derive(Default)]
+pub struct Measurements {
+    sizes: RefCell<Vec<((WidthConstraint, f64), ElementSize)>>,
+    breakable_sizes: RefCell<Vec<(BreakableKey, BreakableMeasurement)>>,
+}
+
+/// The width, the first height, the full height and the incoming break count and minimum height of
+/// extra locations. Elements can add to the incoming values instead of replacing them, so they're
+/// part of the key.
+type BreakableKey = (WidthConstraint, f64, f64, u32, Option<f64>);
+
+/// The size, the break count and the minimum height of extra locations.
+type BreakableMeasurement = (ElementSize, u32, Option<f64>);
+
+impl<'a, E: Element> Cached<'a, E> {
+    pub fn new(element: &'a E) -> Self {
+        Cached {
+            element,
+            own_measurements: Measurements::default(),
+            measurements: None,
+        }
+    }
+
+    /// Remembers the measurements in `measurements` instead of in the element, so they're shared
+    /// by every [Cached] that's built with them. They have to be for the same element.
+    pub fn with_measurements(element: &'a E, measurements: &'a Measurements) -> Self {
+        Cached {
+            measurements: Some(measurements),
+            ..Cached::new(element)
+        }
+    }
+
+    fn measurements(&self) -> &Measurements {
+        self.measurements.unwrap_or(&self.own_measurements)
+    }
+}
+
+impl<'a, E: Element> Element for Cached<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        if let Some(breakable) = ctx.breakable {
+            let key = (
+                ctx.width,
+                ctx.first_height,
+                breakable.full_height,
+                *breakable.break_count,
+                *breakable.extra_location_min_height,
+            );
+
+            let cached = self
+                .measurements()
+                .breakable_sizes
+                .borrow()
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|&(_, measurement)| measurement);
+
+            let (size, break_count, extra_location_min_height) = match cached {
+                Some(measurement) => measurement,
+                None => {
+                    let mut break_count = *breakable.break_count;
+                    let mut extra_location_min_height = *breakable.extra_location_min_height;
+
+                    let size = self.element.measure(MeasureCtx {
+                        breakable: Some(BreakableMeasure {
+                            full_height: breakable.full_height,
+                            break_count: &mut break_count,
+                            extra_location_min_height: &mut extra_location_min_height,
+                        }),
+                        ..ctx
+                    });
+
+                    let measurement = (size, break_count, extra_location_min_height);
+                    self.measurements()
+                        .breakable_sizes
+                        .borrow_mut()
+                        .push((key, measurement));
+                    measurement
+                }
+            };
+
+            *breakable.break_count = break_count;
+            *breakable.extra_location_min_height = extra_location_min_height;
+
+            return size;
+        }
+
+        let key = (ctx.width, ctx.first_height);
+
+        let sizes = &self.measurements().sizes;
+
+        if let Some(&(_, size)) = sizes.borrow().iter().find(|(k, _)| *k == key) {
+            return size;
+        }
+
+        let size = self.element.measure(ctx);
+        sizes.borrow_mut().push((key, size));
+
+        size
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        // A drawing that breaks is spread over multiple pages, so it can't be shared.
+        let marking = ctx.breakable.is_none() && MARKED.with(|marked| marked.get()).is_some();
+
+        if !marking {
+            return self.element.draw(ctx);
+        }
+
+        MARKED.with(|marked| marked.set(marked.get().map(|count| count + 1)));
+
+        // The graphics state is saved like for a form XObject, so that the drawing means the same
+        // when it's replaced with one.
+        let layer = ctx.location.layer.clone();
+        layer.save_graphics_state();
+        add_op(
+            &layer,
+            Operation::new("BMC", vec![Object::Name(TAG.to_vec())]),
+        );
+
+        let size = self.element.draw(ctx);
+
+        add_op(&layer, Operation::new("EMC", vec![]));
+        layer.restore_graphics_state();
+
+        size
+    }
+}
+
+/// The tag of the marked content around the drawings of [Cached] elements.
+const TAG: &[u8] = b"LaserPdfCached";
+
+thread_local! {
+    static MARKED: Cell<Option<u32>> = const { Cell::new(None) };
+}
+
+/// Marks the drawings of [Cached] elements in the content of the documents built on this thread
+/// while `f` is running, so that [share_drawings] can find them in the saved document. Drawings
+/// that break aren't marked. Also returns the number of marked drawings.
+pub fn mark_drawings<R>(f: impl FnOnce() -> R) -> (R, u32) {
+    let (ret, marked) = scoped(&MARKED, Some(0), f);
+    (ret, marked.unwrap_or_default())
+}
+
+/// Stores the marked drawings that are the same in multiple places once, as a form XObject that
+/// is drawn in all of those places instead. They're only the same if they have the same operators
+/// and the resources they use by name are the same objects, so usually a drawing is shared when
+/// it's at the same position on multiple pages, like a header.
+pub fn share_drawings(document: &mut lopdf::Document) -> lopdf::Result<()> {
+    use std::collections::HashMap;
+
+    use lopdf::{content::Content, dictionary, Dictionary, Stream};
+
+    struct Page {
+        id: lopdf::ObjectId,
+        operations: Vec<Operation>,
+        resources: Dictionary,
+
+        /// The ranges of the marked drawings and the index of the group they're in.
+        drawings: Vec<(std::ops::Range<usize>, usize)>,
+    }
+
+    /// Drawings with the same content and resources.
+    struct Group {
+        content: Vec<u8>,
+        resources: Dictionary,
+        media_box: Object,
+        count: usize,
+    }
+
+    let mut pages = Vec::new();
+    let mut groups = Vec::<Group>::new();
+    let mut group_indices = HashMap::new();
+
+    for page_id in document.get_pages().into_values() {
+        let operations = Content::decode(&document.get_page_content(page_id)?)?.operations;
+
+        let resources = match inherited(document, page_id, b"Resources") {
+            Some(Object::Dictionary(resources)) => resources.clone(),
+            _ => Dictionary::new(),
+        };
+
+        let mut drawings = Vec::new();
+
+        for range in marked_ranges(&operations) {
+            let inner = &operations[range.start + 1..range.end - 1];
+
+            let content = Content {
+                operations: inner.to_vec(),
+            }
+            .encode()?;
+
+            let used = used_resources(document, &resources, inner);
+            let key = (content, format!("{used:?}"));
+
+            let group = *group_indices.entry(key).or_insert_with_key(|(content, _)| {
+                groups.push(Group {
+                    content: content.clone(),
+                    resources: used,
+                    // US Letter is the default in the PDF specification.
+                    media_box: inherited(document, page_id, b"MediaBox")
+                        .cloned()
+                        .unwrap_or_else(|| {
+                            Object::Array([0, 0, 612, 792].map(Object::Integer).to_vec())
+                        }),
+                    count: 0,
+                });
+
+                groups.len() - 1
+            });
+
+            groups[group].count += 1;
+            drawings.push((range, group));
+        }
+
+        pages.push(Page {
+            id: page_id,
+            operations,
+            resources,
+            drawings,
+        });
+    }
+
+    // The ids of the form XObjects, for the groups that are shared.
+    let forms = groups
+        .into_iter()
+        .map(|group| {
+            (group.count > 1).then(|| {
+                document.add_object(Stream::new(
+                    dictionary! {
+                        "Type" => "XObject",
+                        "Subtype" => "Form",
+                        "BBox" => group.media_box,
+                        "Resources" => group.resources,
+                    },
+                    group.content,
+                ))
+            })
+        })
+        .collect::<Vec<_>>();
+
+    for page in pages {
+        if page
+            .drawings
+            .iter()
+            .all(|&(_, group)| forms[group].is_none())
+        {
+            continue;
+        }
+
+        let mut xobjects = match page.resources.get(b"XObject").map(|o| resolve(document, o)) {
+            Ok(Some(Object::Dictionary(xobjects))) => xobjects.clone(),
+            _ => Dictionary::new(),
+        };
+
+        let mut operations = Vec::new();
+        let mut next = 0;
+
+        for (range, group) in page.drawings {
+            let Some(form_id) = forms[group] else {
+                continue;
+            };
+
+            let name = format!("{}{group}", std::str::from_utf8(TAG).unwrap());
+            xobjects.set(name.clone(), form_id);
+
+            operations.extend_from_slice(&page.operations[next..range.start]);
+            operations.push(Operation::new("Do", vec![Object::Name(name.into_bytes())]));
+            next = range.end;
+        }
+
+        operations.extend_from_slice(&page.operations[next..]);
+
+        let content_id = document.add_object(Stream::new(
+            Dictionary::new(),
+            Content { operations }.encode()?,
+        ));
+
+        let mut resources = page.resources;
+        resources.set("XObject", xobjects);
+
+        let page = document.get_object_mut(page.id)?.as_dict_mut()?;
+        page.set("Resources", resources);
+        page.set("Contents", content_id);
+    }
+
+    Ok(())
+}
+
+/// The ranges of the outermost marked drawings, including the operators that start and end them.
+fn marked_ranges(operations: &[Operation]) -> Vec<std::ops::Range<usize>> {
+    let mut ranges = Vec::new();
+    let mut depth = 0u32;
+
+    // The index and the depth of the drawing that's currently open.
+    let mut open = None;
+
+    for (i, operation) in operations.iter().enumerate() {
+        match &operation.operator[..] {
+            "BMC" | "BDC" => {
+                let tag = operation.operands.first().and_then(|o| o.as_name().ok());
+
+                if open.is_none() && operation.operator == "BMC" && tag == Some(TAG) {
+                    open = Some((i, depth));
+                }
+
+                depth += 1;
+            }
+            "EMC" => {
+                depth = depth.saturating_sub(1);
+
+                if let Some((start, _)) = open.filter(|&(_, d)| d == depth) {
+                    ranges.push(start..i + 1);
+                    open = None;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    ranges
+}
+
+/// The resources of the page that the operators refer to by name.
+fn used_resources(
+    document: &lopdf::Document,
+    resources: &lopdf::Dictionary,
+    operations: &[Operation],
+) -> lopdf::Dictionary {
+    let mut used = lopdf::Dictionary::new();
+
+    for operation in operations {
+        let (category, name) = match (&operation.operator[..], &operation.operands[..]) {
+            ("Tf", [Object::Name(name), ..]) => ("Font", name),
+            ("Do", [Object::Name(name)]) => ("XObject", name),
+            ("gs", [Object::Name(name)]) => ("ExtGState", name),
+            ("cs" | "CS", [Object::Name(name)]) => ("ColorSpace", name),
+            ("scn" | "SCN", [.., Object::Name(name)]) => ("Pattern", name),
+            ("sh", [Object::Name(name)]) => ("Shading", name),
+            ("BDC" | "DP", [_, Object::Name(name)]) => ("Properties", name),
+            _ => continue,
+        };
+
+        let value = resources
+            .get(category.as_bytes())
+            .ok()
+            .and_then(|o| resolve(document, o))
+            .and_then(|o| o.as_dict().ok())
+            .and_then(|entries| entries.get(name).ok());
+
+        if let Some(value) = value {
+            let mut entries = match used.get(category.as_bytes()) {
+                Ok(Object::Dictionary(entries)) => entries.clone(),
+                _ => lopdf::Dictionary::new(),
+            };
+
+            entries.set(name.clone(), value.clone());
+            used.set(category, entries);
+        }
+    }
+
+    used
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    struct Counting<'a, E: Element> {
+        element: &'a E,
+        measures: &'a Cell<u32>,
+    }
+
+    impl<'a, E: Element> Element for Counting<'a, E> {
+        fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+            self.element.first_location_usage(ctx)
+        }
+
+        fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+            self.measures.set(self.measures.get() + 1);
+            self.element.measure(ctx)
+        }
+
+        fn draw(&self, ctx: DrawCtx) -> ElementSize {
+            self.element.draw(ctx)
+        }
+    }
+
+    #[test]
+    fn test_cached() {
+        let measures = Cell::new(0);
+
+        let counting = Counting {
+            element: &FakeText {
+                lines: 3,
+                line_height: 1.,
+                width: 5.,
+            },
+            measures: &measures,
+        };
+
+        let element = Cached::new(&counting);
+
+        let measure = |max| {
+            element.measure(MeasureCtx {
+                width: WidthConstraint { max, expand: false },
+                first_height: 10.,
+                breakable: None,
+            })
+        };
+
+        let size = measure(4.);
+        assert_eq!(measure(4.), size);
+        assert_eq!(measures.get(), 1);
+
+        measure(6.);
+        assert_eq!(measures.get(), 2);
+
+        let measure_breakable = |first_height| {
+            let mut break_count = 0;
+            let mut extra_location_min_height = None;
+
+            let size = element.measure(MeasureCtx {
+                width: WidthConstraint {
+                    max: 4.,
+                    expand: false,
+                },
+                first_height,
+                breakable: Some(BreakableMeasure {
+                    full_height: 2.,
+                    break_count: &mut break_count,
+                    extra_location_min_height: &mut extra_location_min_height,
+                }),
+            });
+
+            (size, break_count)
+        };
+
+        let breakable = measure_breakable(1.);
+        assert_eq!(breakable.1, 1);
+        assert_eq!(measure_breakable(1.), breakable);
+        assert_eq!(measures.get(), 3);
+
+        measure_breakable(2.);
+        assert_eq!(measures.get(), 4);
+
+        for output in ElementTestParams::default().run(&element) {
+            output.assert_size(ElementSize {
+                width: Some(output.width.constrain(5.)),
+                height: Some(3.),
+            });
+        }
+    }
+
+    /// Adds a break to the incoming break count, like an element that's measured after another one
+    /// in the same context.
+    struct AddsBreak;
+
+    impl Element for AddsBreak {
+        fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+            if let Some(breakable) = ctx.breakable {
+                *breakable.break_count += 1;
+            }
+
+            ElementSize {
+                width: None,
+                height: None,
+            }
+        }
+
+        fn draw(&self, _: DrawCtx) -> ElementSize {
+            ElementSize {
+                width: None,
+                height: None,
+            }
+        }
+    }
+
+    #[test]
+    fn test_cached_incoming_break_count() {
+        let element = Cached::new(&AddsBreak);
+
+        let measure = |incoming| {
+            let mut break_count = incoming;
+            let mut extra_location_min_height = None;
+
+            element.measure(MeasureCtx {
+                width: WidthConstraint {
+                    max: 4.,
+                    expand: false,
+                },
+                first_height: 1.,
+                breakable: Some(BreakableMeasure {
+                    full_height: 2.,
+                    break_count: &mut break_count,
+                    extra_location_min_height: &mut extra_location_min_height,
+                }),
+            });
+
+            break_count
+        };
+
+        assert_eq!(measure(0), 1);
+        assert_eq!(measure(2), 3);
+        assert_eq!(measure(0), 1);
+        assert_eq!(element.measurements().breakable_sizes.borrow().len(), 2);
+    }
+
+    #[test]
+    fn test_share_drawings() {
+        use crate::elements::{rectangle::Rectangle, repeat_after_break::RepeatAfterBreak};
+        use lopdf::content::Content;
+
+        let rectangle = Rectangle {
+            size: (50., 10.),
+            fill: Some(0x00_00_ff_ff),
+            outline: None,
+        };
+
+        let title = Cached::new(&rectangle);
+
+        let content = FakeText {
+            lines: 40,
+            line_height: 10.,
+            width: 50.,
+        };
+
+        let (document, marked) = mark_drawings(|| {
+            build_pdf(
+                "test",
+                (100., 100.),
+                |_| (),
+                |_: &()| RepeatAfterBreak {
+                    title: &title,
+                    content: &content,
+                    gap: 0.,
+                    collapse_on_empty_content: false,
+                },
+            )
+        });
+
+        let mut bytes = Vec::new();
+        document
+            .save(&mut std::io::BufWriter::new(&mut bytes))
+            .unwrap();
+
+        let mut document = lopdf::Document::load_mem(&bytes).unwrap();
+        share_drawings(&mut document).unwrap();
+
+        let pages = document.get_pages();
+        assert_eq!(pages.len(), 5);
+        assert_eq!(marked, 5);
+
+        let forms = document
+            .objects
+            .values()
+            .filter_map(|o| o.as_stream().ok())
+            .filter(|s| matches!(s.dict.get(b"Subtype"), Ok(Object::Name(n)) if n == b"Form"))
+            .collect::<Vec<_>>();
+
+        assert_eq!(forms.len(), 1);
+
+        let form = Content::decode(&forms[0].content).unwrap();
+        assert!(form.operations.iter().any(|o| o.operator == "l"));
+
+        for page_id in pages.into_values() {
+            let content = document.get_page_content(page_id).unwrap();
+            let operations = Content::decode(&content).unwrap().operations;
+
+            assert!(operations.iter().any(|o| o.operator == "Do"));
+            assert!(!operations.iter().any(|o| o.operator == "l"));
+        }
+    }
+}
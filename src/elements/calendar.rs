@@ -0,0 +1,320 @@
+use crate::{fonts::Font, *};
+
+use super::{
+    column::{Column, ColumnContent},
+    line::Line,
+    none::NoneElement,
+    padding::Padding,
+    stack::Stack,
+    table_row::{Flex, TableRow},
+    text::Text,
+    v_gap::VGap,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct CalendarEvent<'a> {
+    pub day: u32,
+    pub text: &'a str,
+    pub color: u32,
+}
+
+/// A month grid with a header row of weekday names and one row per week. Each day shows its number
+/// followed by the events on that day and then its [content](Self::day_content). The weeks are
+/// separated by lines and can be broken across pages.
+pub struct CalendarMonth<'a, F: Font, D: Fn(u32, ColumnContent) -> Option<()>> {
+    pub year: i32,
+
+    /// 1 to 12. Nothing is drawn for other months.
+    pub month: u32,
+
+    pub events: &'a [CalendarEvent<'a>],
+
+    /// Adds the content of a day, like the elements of a [Column], after its events. Gets the day
+    /// of the month starting at 1.
+    pub day_content: D,
+
+    /// The first day of the week, 0 for Monday up to 6 for Sunday.
+    pub first_weekday: u32,
+
+    /// In the order they're displayed in, so starting with [Self::first_weekday].
+    pub weekday_names: [&'a str; 7],
+
+    pub font: &'a F,
+    pub size: f64,
+    pub color: u32,
+    pub line_style: LineStyle,
+    pub padding: f64,
+
+    /// The minimum height of a week row, including the padding.
+    pub min_day_height: f64,
+}
+
+pub fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The day of the week in the proleptic Gregorian calendar, 0 for Monday up to 6 for Sunday. `None`
+/// if the date doesn't exist.
+pub fn weekday(year: i32, month: u32, day: u32) -> Option<u32> {
+    // Sakamoto's method
+    const T: [i64; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+
+    // In i64, so the years around the ends of i32 don't overflow.
+    let y = year as i64 - (month < 3) as i64;
+
+    let sunday_based = (y + y.div_euclid(4) - y.div_euclid(100)
+        + y.div_euclid(400)
+        + T[month as usize - 1]
+        + day as i64)
+        .rem_euclid(7);
+
+    Some((sunday_based as u32 + 6) % 7)
+}
+
+impl<'a, F: Font, D: Fn(u32, ColumnContent) -> Option<()>> CalendarMonth<'a, F, D> {
+    fn text<'b>(&'b self, text: &'b str, color: u32) -> Text<'b, F> {
+        Text {
+            color,
+            ..Text::basic(text, self.font, self.size)
+        }
+    }
+
+    fn padded<'b, E: Element>(&self, element: &'b E) -> Padding<'b, E> {
+        Padding {
+            left: self.padding,
+            right: self.padding,
+            top: self.padding,
+            bottom: self.padding,
+            element,
+        }
+    }
+}
+
+impl<'a, F: Font, D: Fn(u32, ColumnContent) -> Option<()>> CompositeElement
+    for CalendarMonth<'a, F, D>
+{
+    fn element(&self, callback: impl CompositeElementCallback) {
+        let Some(first_weekday) = weekday(self.year, self.month, 1) else {
+            return callback.call(&NoneElement);
+        };
+
+        let days = days_in_month(self.year, self.month);
+        let offset = (first_weekday + 7 - self.first_weekday % 7) % 7;
+        let weeks = (offset + days).div_ceil(7);
+
+        let day_numbers: Vec<String> = (1..=days).map(|d| d.to_string()).collect();
+        let day_numbers = &day_numbers;
+
+        let line = Line {
+            style: self.line_style,
+        };
+
+        let min_height = &VGap(self.min_day_height);
+
+        let week = |week: u32| TableRow {
+            line_style: self.line_style,
+            expand: true,
+            border_collapse: false,
+            content: move |content| {
+                for i in 0..7 {
+                    let day = (week * 7 + i)
+                        .checked_sub(offset)
+                        .map(|d| d + 1)
+                        .filter(|&d| d <= days);
+
+                    content.add(
+                        &Stack {
+                            content: |content| {
+                                content.add(min_height);
+
+                                if let Some(day) = day {
+                                    content.add(&self.padded(&Column::new(|content| {
+                                        let mut content = content.add(
+                                            &self.text(&day_numbers[day as usize - 1], self.color),
+                                        )?;
+
+                                        let events = self.events.iter().filter(|e| e.day == day);
+
+                                        for event in events {
+                                            content =
+                                                content.add(&self.text(event.text, event.color))?;
+                                        }
+
+                                        (self.day_content)(day, content)
+                                    })));
+                                }
+                            },
+                            expand: false,
+                        },
+                        Flex::Expand(1),
+                    );
+                }
+            },
+        };
+
+        callback.call(&Column {
+            collapse: false,
+            ..Column::new(|content| {
+                let mut content = content
+                    .add(&TableRow {
+                        line_style: self.line_style,
+                        expand: true,
+                        border_collapse: false,
+                        content: |content| {
+                            for name in self.weekday_names {
+                                content.add(
+                                    &self.padded(&self.text(name, self.color)),
+                                    Flex::Expand(1),
+                                );
+                            }
+                        },
+                    })?
+                    .add(&line)?;
+
+                for i in 0..weeks {
+                    if i > 0 {
+                        content = content.add(&line)?;
+                    }
+
+                    content = content.add(&week(i))?;
+                }
+
+                Option::None
+            })
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use printpdf::PdfDocument;
+
+    use super::*;
+    use crate::fonts::builtin::BuiltinFont;
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(1900, 2), 28);
+        assert_eq!(days_in_month(2000, 2), 29);
+        assert_eq!(days_in_month(2024, 4), 30);
+        assert_eq!(days_in_month(2024, 12), 31);
+
+        // 2024-01-01 was a Monday, 2023-10-01 a Sunday and 2000-02-29 a Tuesday.
+        assert_eq!(weekday(2024, 1, 1), Some(0));
+        assert_eq!(weekday(2023, 10, 1), Some(6));
+        assert_eq!(weekday(2000, 2, 29), Some(1));
+
+        assert_eq!(weekday(2024, 0, 1), None);
+        assert_eq!(weekday(2024, 13, 1), None);
+        assert_eq!(weekday(2023, 2, 29), None);
+        assert_eq!(weekday(2024, 1, 0), None);
+
+        // The proleptic Gregorian calendar repeats every 400 years.
+        assert!(weekday(i32::MAX, 12, 31).is_some());
+        assert!(weekday(i32::MIN, 1, 1).is_some());
+        assert_eq!(weekday(-400, 1, 1), weekday(0, 1, 1));
+    }
+
+    #[test]
+    fn test_day_content() {
+        // A fake document for adding the font to.
+        let doc = PdfDocument::empty("i contain a font");
+        let font = BuiltinFont::helvetica(&doc);
+
+        let days = RefCell::new(Vec::new());
+
+        let height = |gap: f64| {
+            days.borrow_mut().clear();
+
+            let calendar = CalendarMonth {
+                year: 2024,
+                month: 2,
+                events: &[],
+                day_content: |day, content| {
+                    days.borrow_mut().push(day);
+
+                    if day == 15 {
+                        content.add(&VGap(gap))?;
+                    }
+
+                    Option::None
+                },
+                first_weekday: 0,
+                weekday_names: ["M", "T", "W", "T", "F", "S", "S"],
+                font: &font,
+                size: 12.,
+                color: 0,
+                line_style: LineStyle::solid(0.5, 0),
+                padding: 0.,
+                min_day_height: 0.,
+            };
+
+            calendar
+                .measure(MeasureCtx {
+                    width: WidthConstraint {
+                        max: 140.,
+                        expand: true,
+                    },
+                    first_height: 1000.,
+                    breakable: None,
+                })
+                .height
+                .unwrap()
+        };
+
+        let without = height(0.);
+
+        // Every day of the month gets its slot.
+        let mut called = days.borrow().clone();
+        called.sort();
+        called.dedup();
+        assert_eq!(called, (1..=29).collect::<Vec<_>>());
+
+        // Only the week of the 15th grows.
+        assert!((height(30.) - without - 30.).abs() < 1e-9);
+
+        // Nothing is drawn for a month that doesn't exist.
+        let invalid = CalendarMonth {
+            year: 2024,
+            month: 13,
+            events: &[],
+            day_content: |_, _| Option::None,
+            first_weekday: 0,
+            weekday_names: [""; 7],
+            font: &font,
+            size: 12.,
+            color: 0,
+            line_style: LineStyle::solid(0.5, 0),
+            padding: 0.,
+            min_day_height: 0.,
+        };
+
+        let size = invalid.measure(MeasureCtx {
+            width: WidthConstraint {
+                max: 140.,
+                expand: true,
+            },
+            first_height: 1000.,
+            breakable: None,
+        });
+
+        assert_eq!(size.height, None);
+    }
+}
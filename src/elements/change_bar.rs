@@ -0,0 +1,143 @@
+use printpdf::{utils::calculate_points_for_rect, Line};
+
+use crate::{utils::*, *};
+
+/// Marks the element as changed with a vertical bar to the left of it, like the change bars in
+/// contract redlines. The bar spans the height of the element on every location it's drawn on.
+/// It's drawn outside of the element, so there needs to be a margin for it.
+pub struct ChangeBar<'a, E: Element> {
+    pub element: &'a E,
+
+    /// The distance from the left edge of the element to the bar.
+    pub offset: f64,
+
+    /// The thickness of the bar.
+    pub width: f64,
+
+    pub color: u32,
+}
+
+impl<'a, E: Element> Element for ChangeBar<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        // The location and the height used on it for every location the element was drawn on.
+        let mut locations = vec![(ctx.location.clone(), None)];
+
+        let size = if let Some(breakable) = ctx.breakable {
+            self.element.draw(DrawCtx {
+                breakable: Some(BreakableDraw {
+                    do_break: &mut |pdf, location_idx, height| {
+                        let location = (breakable.do_break)(pdf, location_idx, height);
+
+                        let idx = location_idx as usize;
+
+                        if idx + 1 >= locations.len() {
+                            // Skipped locations don't get a bar, so their location doesn't matter.
+                            while locations.len() <= idx {
+                                locations.push((location.clone(), None));
+                            }
+
+                            locations[idx].1 = height;
+                            locations.push((location.clone(), None));
+                        }
+
+                        location
+                    },
+                    ..breakable
+                }),
+                ..ctx
+            })
+        } else {
+            self.element.draw(ctx)
+        };
+
+        if let Some(last) = locations.last_mut() {
+            last.1 = size.height;
+        }
+
+        for (location, height) in locations {
+            if let Some(height) = height.filter(|&h| h > 0.) {
+                self.draw_bar(&location, height);
+            }
+        }
+
+        size
+    }
+}
+
+impl<'a, E: Element> ChangeBar<'a, E> {
+    fn draw_bar(&self, location: &Location, height: f64) {
+        let (color, alpha) = u32_to_color_and_alpha(self.color);
+        let layer = &location.layer;
+
+        layer.save_graphics_state();
+        layer.set_fill_color(color);
+        layer.set_fill_alpha(alpha);
+        add_shape(
+            layer,
+            Line {
+                points: calculate_points_for_rect(
+                    Mm(self.width),
+                    Mm(height),
+                    Mm(location.pos.0 - self.offset - self.width / 2.),
+                    Mm(location.pos.1 - height / 2.),
+                ),
+                is_closed: true,
+                has_fill: true,
+                has_stroke: false,
+                is_clipping_path: false,
+            },
+        );
+        layer.restore_graphics_state();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_change_bar() {
+        let element = ChangeBar {
+            element: &FakeText {
+                lines: 10,
+                line_height: 1.,
+                width: 5.,
+            },
+            offset: 2.,
+            width: 0.5,
+            color: 0xFF_00_00_FF,
+        };
+
+        for output in (ElementTestParams {
+            first_height: 4.,
+            full_height: 6.,
+            ..Default::default()
+        })
+        .run(&element)
+        {
+            if let Some(b) = output.breakable {
+                b.assert_break_count(1);
+            }
+
+            output.assert_size(ElementSize {
+                width: Some(output.width.constrain(5.)),
+                height: Some(if output.breakable.is_none() {
+                    10.
+                } else if output.first_height == 4. {
+                    6.
+                } else {
+                    4.
+                }),
+            });
+        }
+    }
+}
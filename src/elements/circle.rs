@@ -5,7 +5,7 @@ use crate::{utils::*, *};
 pub struct Circle {
     pub radius: f64,
     pub fill: Option<u32>,
-    pub outline: Option<(f64, u32)>,
+    pub outline: Option<LineStyle>,
 }
 
 impl Element for Circle {
@@ -36,22 +36,20 @@ impl Element for Circle {
             ctx.location.layer.set_fill_alpha(alpha);
         }
 
-        if let Some((thickness, color)) = self.outline {
-            // No outline alpha?
-            let (color, _alpha) = u32_to_color_and_alpha(color);
-            ctx.location.layer.set_outline_color(color);
-            ctx.location
-                .layer
-                .set_outline_thickness(mm_to_pt(thickness));
+        if let Some(line_style) = self.outline {
+            set_line_style(&ctx.location.layer, &line_style, ctx.pdf.line_rendering);
         }
 
-        ctx.location.layer.add_shape(Line {
-            points,
-            is_closed: true,
-            has_fill: self.fill.is_some(),
-            has_stroke: self.outline.is_some(),
-            is_clipping_path: false,
-        });
+        add_shape(
+            &ctx.location.layer,
+            Line {
+                points,
+                is_closed: true,
+                has_fill: self.fill.is_some(),
+                has_stroke: self.outline.is_some(),
+                is_clipping_path: false,
+            },
+        );
 
         ctx.location.layer.restore_graphics_state();
 
@@ -60,7 +58,7 @@ impl Element for Circle {
 }
 
 fn outline_thickness(circle: &Circle) -> f64 {
-    circle.outline.map(|o| o.0).unwrap_or(0.0)
+    circle.outline.map(|o| o.thickness).unwrap_or(0.0)
 }
 
 fn size(circle: &Circle) -> ElementSize {
@@ -88,7 +86,7 @@ mod tests {
         .run(&Circle {
             radius: 5.5,
             fill: None,
-            outline: Some((1., 0)),
+            outline: Some(LineStyle::solid(1., 0)),
         }) {
             output.assert_size(ElementSize {
                 width: Some(12.),
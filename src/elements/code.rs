@@ -0,0 +1,385 @@
+use std::borrow::Cow;
+
+use printpdf::{utils::calculate_points_for_rect, Line, PdfLayerReference};
+
+use crate::{
+    fonts::{Font, GeneralMetrics},
+    text::text_width,
+    utils::{add_shape, pt_to_mm, u32_to_color_and_alpha, use_text},
+    *,
+};
+
+/// What happens to lines that are wider than the available width.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CodeOverflow {
+    /// The lines are drawn past the edge.
+    #[default]
+    Visible,
+
+    /// The font size is reduced until the longest line fits.
+    Shrink,
+
+    /// The lines are cut off at the edge.
+    Clip,
+}
+
+/// Preformatted text, e.g. a log excerpt or source code. Lines are only broken at newlines and
+/// all whitespace is kept as it is, so it's best used with a monospace font like Courier.
+pub struct Code<'a, F: Font> {
+    pub text: &'a str,
+    pub font: &'a F,
+    pub size: f64,
+    pub color: u32,
+
+    /// The number of columns between tab stops. Tabs are replaced by spaces up to the next one.
+    pub tab_width: u32,
+
+    pub overflow: CodeOverflow,
+
+    /// Fills the area of the lines on every location.
+    pub background: Option<u32>,
+}
+
+struct Layout {
+    size: f64,
+    ascent: f64,
+    line_height: f64,
+
+    /// The width of the widest line at [Self::size].
+    content_width: f64,
+}
+
+impl<'a, F: Font> Code<'a, F> {
+    pub fn basic(text: &'a str, font: &'a F, size: f64) -> Self {
+        Code {
+            text,
+            font,
+            size,
+            color: 0x00_00_00_FF,
+            tab_width: 4,
+            overflow: CodeOverflow::Visible,
+            background: None,
+        }
+    }
+
+    fn lines(&self) -> impl Iterator<Item = Cow<'a, str>> + '_ {
+        self.text
+            .split('\n')
+            .map(|line| expand_tabs(line.strip_suffix('\r').unwrap_or(line), self.tab_width))
+    }
+
+    fn width_at(&self, size: f64) -> f64 {
+        self.lines()
+            .map(|line| pt_to_mm(text_width(&line, size, self.font, 0., 0.)))
+            .fold(0., f64::max)
+    }
+
+    fn layout(&self, width: WidthConstraint) -> Layout {
+        let mut size = self.size;
+        let mut content_width = self.width_at(size);
+
+        if self.overflow == CodeOverflow::Shrink && content_width > width.max {
+            size *= (width.max / content_width).max(0.);
+            content_width = width.max.max(0.);
+        }
+
+        let GeneralMetrics {
+            ascent,
+            line_height,
+        } = self.font.general_metrics();
+
+        let units_per_em = self.font.units_per_em() as f64;
+
+        Layout {
+            size,
+            ascent: pt_to_mm(ascent * size / units_per_em),
+            line_height: pt_to_mm(line_height * size / units_per_em),
+            content_width,
+        }
+    }
+
+    /// The number of lines on every location.
+    fn locations(&self, line_height: f64, first_height: f64, full_height: Option<f64>) -> Vec<u32> {
+        let mut locations = vec![0];
+        let mut height_available = first_height;
+
+        for _ in self.lines() {
+            if height_available < line_height {
+                if let Some(full_height) = full_height {
+                    locations.push(0);
+                    height_available = full_height;
+                }
+            }
+
+            *locations.last_mut().unwrap() += 1;
+            height_available -= line_height;
+        }
+
+        locations
+    }
+
+    fn size(&self, layout: &Layout, width: WidthConstraint, last_lines: u32) -> ElementSize {
+        ElementSize {
+            width: Some(width.constrain(layout.content_width)),
+            height: Some(last_lines as f64 * layout.line_height),
+        }
+    }
+
+    fn draw_lines<'b>(
+        &self,
+        layer: &PdfLayerReference,
+        lines: impl Iterator<Item = Cow<'b, str>>,
+        layout: &Layout,
+        pos: (f64, f64),
+        (width, height): (f64, f64),
+    ) {
+        let rect = || {
+            calculate_points_for_rect(
+                Mm(width),
+                Mm(height),
+                Mm(pos.0 + width / 2.),
+                Mm(pos.1 - height / 2.),
+            )
+        };
+
+        layer.save_graphics_state();
+
+        if let Some(color) = self.background {
+            let (color, alpha) = u32_to_color_and_alpha(color);
+            layer.set_fill_color(color);
+            layer.set_fill_alpha(alpha);
+
+            add_shape(
+                layer,
+                Line {
+                    points: rect(),
+                    is_closed: true,
+                    has_fill: true,
+                    has_stroke: false,
+                    is_clipping_path: false,
+                },
+            );
+        }
+
+        if self.overflow == CodeOverflow::Clip {
+            add_shape(
+                layer,
+                Line {
+                    points: rect(),
+                    is_closed: true,
+                    has_fill: false,
+                    has_stroke: false,
+                    is_clipping_path: true,
+                },
+            );
+        }
+
+        let (color, alpha) = u32_to_color_and_alpha(self.color);
+        layer.set_fill_color(color);
+        layer.set_fill_alpha(alpha);
+
+        for (i, line) in lines.enumerate() {
+            let y = pos.1 - layout.ascent - i as f64 * layout.line_height;
+
+            use_text(
+                layer,
+                &*line,
+                layout.size,
+                Mm(pos.0),
+                Mm(y),
+                self.font.indirect_font_ref(),
+            );
+        }
+
+        layer.restore_graphics_state();
+    }
+}
+
+/// Replaces the tabs with spaces up to the next multiple of `tab_width` characters.
+pub(crate) fn expand_tabs(line: &str, tab_width: u32) -> Cow<'_, str> {
+    if !line.contains('\t') {
+        return Cow::Borrowed(line);
+    }
+
+    let tab_width = tab_width.max(1) as usize;
+    let mut expanded = String::with_capacity(line.len());
+    let mut column = 0;
+
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = tab_width - column % tab_width;
+            expanded.push_str(&" ".repeat(spaces));
+            column += spaces;
+        } else {
+            expanded.push(c);
+            column += 1;
+        }
+    }
+
+    Cow::Owned(expanded)
+}
+
+impl<'a, F: Font> Element for Code<'a, F> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        if self.layout(ctx.width).line_height > ctx.first_height {
+            FirstLocationUsage::WillSkip
+        } else {
+            FirstLocationUsage::WillUse
+        }
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        let layout = self.layout(ctx.width);
+
+        let locations = self.locations(
+            layout.line_height,
+            ctx.first_height,
+            ctx.breakable.as_ref().map(|b| b.full_height),
+        );
+
+        if let Some(breakable) = ctx.breakable {
+            *breakable.break_count = locations.len() as u32 - 1;
+        }
+
+        self.size(&layout, ctx.width, *locations.last().unwrap())
+    }
+
+    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+        let layout = self.layout(ctx.width);
+
+        let locations = self.locations(
+            layout.line_height,
+            ctx.first_height,
+            ctx.breakable.as_ref().map(|b| b.full_height),
+        );
+
+        let width = match self.overflow {
+            CodeOverflow::Clip => ctx.width.max,
+            _ => ctx.width.constrain(layout.content_width),
+        };
+
+        let mut lines = self.lines();
+        let mut location = ctx.location;
+
+        for (i, &count) in locations.iter().enumerate() {
+            if i > 0 {
+                if let Some(ref mut breakable) = ctx.breakable {
+                    let previous = locations[i - 1];
+
+                    location = (breakable.do_break)(
+                        ctx.pdf,
+                        i as u32 - 1,
+                        if previous == 0 {
+                            None
+                        } else {
+                            Some(previous as f64 * layout.line_height)
+                        },
+                    );
+                }
+            }
+
+            if count > 0 {
+                self.draw_lines(
+                    &location.layer,
+                    lines.by_ref().take(count as usize),
+                    &layout,
+                    location.pos,
+                    (width, count as f64 * layout.line_height),
+                );
+            }
+        }
+
+        self.size(&layout, ctx.width, *locations.last().unwrap())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use printpdf::PdfDocument;
+
+    use super::*;
+    use crate::{
+        fonts::builtin::BuiltinFont,
+        test_utils::{ElementProxy, ElementTestParams},
+    };
+
+    #[test]
+    fn test_expand_tabs() {
+        assert_eq!(expand_tabs("a\tbc\td", 4), "a   bc  d");
+        assert_eq!(expand_tabs("\t\tx", 2), "    x");
+        assert_eq!(expand_tabs("abcd\t", 4), "abcd    ");
+    }
+
+    #[test]
+    fn test_code() {
+        let doc = PdfDocument::empty("i contain a font");
+        let font = BuiltinFont::courier(&doc);
+
+        // Courier characters are 0.6em wide, so the first line is 5 * 6pt wide.
+        let line_width = pt_to_mm(30.);
+
+        let code = |overflow| Code {
+            overflow,
+            background: Some(0xEE_EE_EE_FF),
+            ..Code::basic("a\tb\n  c", &font, 10.)
+        };
+
+        let proxy = |code| ElementProxy {
+            before_draw: &|ctx: &mut DrawCtx| {
+                ctx.pdf
+                    .document
+                    .add_builtin_font(printpdf::BuiltinFont::Courier)
+                    .unwrap();
+            },
+            ..ElementProxy::new(code)
+        };
+
+        let line_height = code(CodeOverflow::Visible)
+            .layout(WidthConstraint {
+                max: 20.,
+                expand: false,
+            })
+            .line_height;
+
+        let params = |width| ElementTestParams {
+            width,
+            first_height: line_height * 1.5,
+            full_height: line_height * 1.5,
+            ..Default::default()
+        };
+
+        for overflow in [CodeOverflow::Visible, CodeOverflow::Clip] {
+            for output in params(5.).run(&proxy(code(overflow))) {
+                output.assert_size(ElementSize {
+                    width: Some(output.width.constrain(line_width)),
+                    height: Some(if output.breakable.is_some() { 1. } else { 2. } * line_height),
+                });
+
+                if let Some(b) = output.breakable {
+                    b.assert_break_count(1);
+                }
+            }
+        }
+
+        let shrunk = code(CodeOverflow::Shrink);
+
+        let shrunk_line_height = shrunk
+            .layout(WidthConstraint {
+                max: line_width / 2.,
+                expand: false,
+            })
+            .line_height;
+
+        // Shrunk to half the size, both lines fit.
+        for output in params(line_width / 2.).run(&proxy(shrunk)) {
+            output.assert_size(ElementSize {
+                width: Some(line_width / 2.),
+                height: Some(2. * shrunk_line_height),
+            });
+
+            if let Some(b) = output.breakable {
+                b.assert_break_count(0);
+            }
+        }
+    }
+}
@@ -1,20 +1,212 @@
-use crate::*;
+use std::collections::BTreeMap;
 
-use self::utils::add_optional_size_with_gap;
+use printpdf::{utils::calculate_points_for_rect, Point};
+
+use crate::{
+    elements::min_first_height::MinFirstHeight, line_rendering::LineRendering, utils::add_shape, *,
+};
+
+use self::utils::{
+    add_optional_size_with_gap, max_optional_size, set_line_dash_pattern, set_line_join,
+    u32_to_color_and_alpha,
+};
 
 pub struct Column<C: Fn(ColumnContent) -> Option<()>> {
     pub content: C,
     pub gap: f64,
     pub collapse: bool,
+
+    /// A line drawn across the column in the middle of the gap between two elements. There's none
+    /// next to elements without height or at a break, so the gap should be at least as thick as the
+    /// line.
+    pub separator: Option<LineStyle>,
+
+    /// Adds the gap before the first element and after the last one, like padding. A collapsing
+    /// column without content doesn't get them either.
+    pub outer_gaps: bool,
+
+    /// Keeps the gap at the top of every location after a break, before the content continues
+    /// there.
+    pub gap_after_break: bool,
+
+    /// The fraction of the height of an element that has to fit on the location it starts on if
+    /// it breaks. If less would fit, the element starts on the next location instead, like with
+    /// [MinFirstHeight](super::min_first_height::MinFirstHeight). This keeps e.g. a single line of
+    /// a table from ending up at the bottom of a page. For elements taller than a location, it's
+    /// the fraction of a full location instead, so they can still start in the middle of one.
+    pub avoid_single_line_tail: Option<f64>,
+
+    /// The number of elements that have to fit on the location the column starts on if it breaks.
+    /// If fewer would fit, the whole column starts on the next location instead. Elements without
+    /// height don't count. This keeps e.g. a heading from ending up with only one table row below
+    /// it.
+    pub min_items_before_break: Option<u32>,
+
+    /// Fills the area of the column on every location it's on, across the whole width, behind the
+    /// content. Unlike with a [StyledBox](super::styled_box::StyledBox) around the column, there
+    /// are no edges at the breaks.
+    pub background: Option<u32>,
+
+    /// Elements added with an outline, like bordered table rows, overlap the element before them by
+    /// the thinner of both outlines if it was added with one too, see
+    /// [ColumnContent::add_outlined]. With a gap of zero they share a single border as thick as the
+    /// thicker one instead of doubling up.
+    pub border_collapse: bool,
+}
+
+impl<C: Fn(ColumnContent) -> Option<()>> Column<C> {
+    /// A collapsing column without gaps, separators or background. The other fields can be set
+    /// with `Column { gap: 2., ..Column::new(content) }`.
+    pub fn new(content: C) -> Self {
+        Column {
+            content,
+            gap: 0.,
+            collapse: true,
+            separator: None,
+            outer_gaps: false,
+            gap_after_break: false,
+            avoid_single_line_tail: None,
+            min_items_before_break: None,
+            background: None,
+            border_collapse: false,
+        }
+    }
 }
 
 impl<C: Fn(ColumnContent) -> Option<()>> Element for Column<C> {
     fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        match self.min_items_height(ctx.width, ctx.full_height) {
+            Some(min_first_height) => MinFirstHeight {
+                element: &Items(self),
+                min_first_height,
+            }
+            .first_location_usage(ctx),
+            None => self.first_location_usage_items(ctx),
+        }
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        match ctx
+            .breakable
+            .as_ref()
+            .and_then(|b| self.min_items_height(ctx.width, b.full_height))
+        {
+            Some(min_first_height) => MinFirstHeight {
+                element: &Items(self),
+                min_first_height,
+            }
+            .measure(ctx),
+            None => self.measure_items(ctx),
+        }
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        match ctx
+            .breakable
+            .as_ref()
+            .and_then(|b| self.min_items_height(ctx.width, b.full_height))
+        {
+            Some(min_first_height) => MinFirstHeight {
+                element: &Items(self),
+                min_first_height,
+            }
+            .draw(ctx),
+            None => self.draw_items(ctx),
+        }
+    }
+}
+
+/// The column without [Column::min_items_before_break], so that it can be wrapped in a
+/// [MinFirstHeight].
+struct Items<'c, C: Fn(ColumnContent) -> Option<()>>(&'c Column<C>);
+
+impl<C: Fn(ColumnContent) -> Option<()>> Element for Items<'_, C> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.0.first_location_usage_items(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.0.measure_items(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        self.0.draw_items(ctx)
+    }
+}
+
+/// An element of a column with [Column::avoid_single_line_tail].
+struct AvoidTail<'a, E: Element> {
+    element: &'a E,
+    fraction: f64,
+}
+
+impl<E: Element> AvoidTail<'_, E> {
+    /// At most the fraction of a full location, so the element only has to be measured if not even
+    /// that much is left.
+    fn min_first_height(&self, width: WidthConstraint, first_height: f64, full_height: f64) -> f64 {
+        if first_height >= full_height * self.fraction {
+            return 0.;
+        }
+
+        let size = self.element.measure(MeasureCtx {
+            width,
+            first_height: full_height,
+            breakable: None,
+        });
+
+        size.height
+            .map_or(0., |height| height.min(full_height) * self.fraction)
+    }
+}
+
+impl<E: Element> Element for AvoidTail<'_, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        MinFirstHeight {
+            element: self.element,
+            min_first_height: self.min_first_height(ctx.width, ctx.first_height, ctx.full_height),
+        }
+        .first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        match ctx.breakable.as_ref().map(|b| b.full_height) {
+            Some(full_height) => MinFirstHeight {
+                element: self.element,
+                min_first_height: self.min_first_height(ctx.width, ctx.first_height, full_height),
+            }
+            .measure(ctx),
+            None => self.element.measure(ctx),
+        }
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        match ctx.breakable.as_ref().map(|b| b.full_height) {
+            Some(full_height) => MinFirstHeight {
+                element: self.element,
+                min_first_height: self.min_first_height(ctx.width, ctx.first_height, full_height),
+            }
+            .draw(ctx),
+            None => self.element.draw(ctx),
+        }
+    }
+}
+
+impl<C: Fn(ColumnContent) -> Option<()>> Column<C> {
+    fn first_location_usage_items(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
         let mut ret = FirstLocationUsage::NoneHeight;
 
+        let ctx = FirstLocationUsageCtx {
+            first_height: (ctx.first_height - self.top_gap(0)).max(0.),
+            full_height: (ctx.full_height - self.top_gap(1)).max(0.),
+            ..ctx
+        };
+
         (self.content)(ColumnContent {
             pass: Pass::InsufficientFirstHeight { ctx, ret: &mut ret },
             gap: self.gap,
+            avoid_single_line_tail: self.avoid_single_line_tail,
+            border_collapse: self.border_collapse,
+            outline: None,
         });
 
         if !self.collapse && ret == FirstLocationUsage::NoneHeight {
@@ -24,7 +216,7 @@ impl<C: Fn(ColumnContent) -> Option<()>> Element for Column<C> {
         ret
     }
 
-    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+    fn measure_items(&self, mut ctx: MeasureCtx) -> ElementSize {
         let mut width = None;
         let mut height = None;
         let mut break_count = 0;
@@ -33,15 +225,19 @@ impl<C: Fn(ColumnContent) -> Option<()>> Element for Column<C> {
             pass: Pass::Measure {
                 width_constraint: ctx.width,
                 breakable: ctx.breakable.as_mut().map(|b| BreakableMeasure {
+                    full_height: (b.full_height - self.top_gap(1)).max(0.),
                     break_count: &mut break_count,
                     extra_location_min_height: b.extra_location_min_height,
-                    ..*b
                 }),
-                height_available: ctx.first_height,
+                height_available: (ctx.first_height - self.top_gap(0)).max(0.),
                 width: &mut width,
                 height: &mut height,
+                expand_weight: None,
             },
             gap: self.gap,
+            avoid_single_line_tail: self.avoid_single_line_tail,
+            border_collapse: self.border_collapse,
+            outline: None,
         });
 
         if let Some(breakable) = ctx.breakable {
@@ -58,26 +254,100 @@ impl<C: Fn(ColumnContent) -> Option<()>> Element for Column<C> {
             }
         }
 
-        ElementSize { width, height }
+        ElementSize {
+            width,
+            height: self.outer_height(height, break_count),
+        }
     }
 
-    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+    fn draw_items(&self, ctx: DrawCtx) -> ElementSize {
         let mut width = None;
         let mut height = None;
         let mut location_offset = 0;
+        let mut separators = Vec::new();
+
+        // The locations the column is on by index and its height there, for the background.
+        let mut backgrounds = BTreeMap::new();
+
+        if self.background.is_some() {
+            backgrounds.insert(0, (ctx.location.clone(), None));
+        }
+
+        let first_height = (ctx.first_height - self.top_gap(0)).max(0.);
+        let break_gap = self.top_gap(1);
+        let location = self.content_location(ctx.pdf, &ctx.location);
+        let line_rendering = ctx.pdf.line_rendering;
+
+        let expand = ctx.preferred_height.and_then(|preferred_height| {
+            self.expand(
+                ctx.width,
+                first_height,
+                ctx.breakable.as_ref().map(|b| {
+                    (
+                        (b.full_height - break_gap).max(0.),
+                        b.preferred_height_break_count,
+                    )
+                }),
+                preferred_height,
+            )
+        });
+
+        // The gaps at the tops of the locations are left out of the content, like padding.
+        let mut do_break;
+
+        let breakable = match ctx.breakable {
+            Some(b) => {
+                let parent_do_break = b.do_break;
+                let backgrounds = &mut backgrounds;
+
+                do_break = move |pdf: &mut Pdf, location_idx: u32, location_height: Option<f64>| {
+                    let location_height = location_height.map(|h| h + self.top_gap(location_idx));
+                    let location = parent_do_break(pdf, location_idx, location_height);
+
+                    if self.background.is_some() {
+                        if let Some((_, height)) = backgrounds.get_mut(&location_idx) {
+                            *height = max_optional_size(*height, location_height);
+                        }
+
+                        backgrounds
+                            .entry(location_idx + 1)
+                            .or_insert_with(|| (location.clone(), None));
+                    }
+
+                    let mut location = self.content_location(pdf, &location);
+                    location.pos.1 -= break_gap;
+                    location
+                };
+
+                Some(BreakableDraw {
+                    full_height: (b.full_height - break_gap).max(0.),
+                    preferred_height_break_count: b.preferred_height_break_count,
+                    do_break: &mut do_break,
+                })
+            }
+            None => None,
+        };
 
         (self.content)(ColumnContent {
             pass: Pass::Draw {
                 pdf: ctx.pdf,
-                location: ctx.location,
+                location: Location {
+                    pos: (location.pos.0, location.pos.1 - self.top_gap(0)),
+                    ..location
+                },
                 location_offset: &mut location_offset,
                 width_constraint: ctx.width,
-                breakable: ctx.breakable,
-                height_available: ctx.first_height,
+                breakable,
+                height_available: first_height,
                 width: &mut width,
                 height: &mut height,
+                expand,
+                separators: self.separator.is_some().then_some(&mut separators),
             },
             gap: self.gap,
+            avoid_single_line_tail: self.avoid_single_line_tail,
+            border_collapse: self.border_collapse,
+            outline: None,
         });
 
         if !self.collapse {
@@ -90,13 +360,232 @@ impl<C: Fn(ColumnContent) -> Option<()>> Element for Column<C> {
             }
         }
 
+        // The width is only known once all of the elements are drawn.
+        let line_width = if ctx.width.expand {
+            ctx.width.max
+        } else {
+            width.unwrap_or(0.)
+        };
+
+        if let Some(style) = self.separator {
+            for location in &separators {
+                draw_separator(location, line_width, &style, line_rendering);
+            }
+        }
+
+        let height = self.outer_height(height, location_offset);
+
+        if let Some(color) = self.background {
+            if let Some((_, last_height)) = backgrounds.get_mut(&location_offset) {
+                *last_height = max_optional_size(*last_height, height);
+            }
+
+            for (location, height) in backgrounds.values() {
+                if let Some(height) = *height {
+                    draw_background(location, line_width, height, color);
+                }
+            }
+        }
+
         ElementSize { width, height }
     }
+
+    /// With a background the content goes on a layer above the location, so that the background
+    /// can be drawn once the height is known.
+    fn content_location(&self, pdf: &mut Pdf, location: &Location) -> Location {
+        if self.background.is_some() {
+            location.next_layer(pdf)
+        } else {
+            location.clone()
+        }
+    }
+
+    /// The height the first [Self::min_items_before_break] elements need on the location the
+    /// column starts on, including the gap before them.
+    fn min_items_height(&self, width: WidthConstraint, full_height: f64) -> Option<f64> {
+        let count = self.min_items_before_break.filter(|&n| n > 0)?;
+        let mut height = None;
+
+        (self.content)(ColumnContent {
+            pass: Pass::FirstItems {
+                width,
+                full_height: (full_height - self.top_gap(1)).max(0.),
+                count,
+                height: &mut height,
+            },
+            gap: self.gap,
+            avoid_single_line_tail: self.avoid_single_line_tail,
+            border_collapse: self.border_collapse,
+            outline: None,
+        });
+
+        height.map(|h| h + self.top_gap(0))
+    }
+
+    /// The gap before the content on the location with the index.
+    fn top_gap(&self, location_idx: u32) -> f64 {
+        let has_gap = if location_idx == 0 {
+            self.outer_gaps
+        } else {
+            self.gap_after_break
+        };
+
+        if has_gap {
+            self.gap
+        } else {
+            0.
+        }
+    }
+
+    /// Adds the gaps around the content to its height on the last location.
+    fn outer_height(&self, height: Option<f64>, last_location_idx: u32) -> Option<f64> {
+        let bottom_gap = if self.outer_gaps { self.gap } else { 0. };
+
+        height.map(|h| h + self.top_gap(last_location_idx) + bottom_gap)
+    }
+
+    /// Finds out how much of the leftover preferred height each unit of weight of the elements
+    /// added with [ColumnContent::add_expand] gets. Only elements on the last location that don't
+    /// break are expanded, and only if the content ends on the location of the preferred height.
+    fn expand(
+        &self,
+        width: WidthConstraint,
+        first_height: f64,
+        breakable: Option<(f64, u32)>,
+        preferred_height: f64,
+    ) -> Option<DrawExpand> {
+        let mut has_expand = false;
+
+        (self.content)(ColumnContent {
+            pass: Pass::HasExpand {
+                ret: &mut has_expand,
+            },
+            gap: self.gap,
+            avoid_single_line_tail: self.avoid_single_line_tail,
+            border_collapse: self.border_collapse,
+            outline: None,
+        });
+
+        if !has_expand {
+            return None;
+        }
+
+        let mut measured_width = None;
+        let mut height = None;
+        let mut break_count = 0;
+        let mut extra_location_min_height = None;
+        let mut weight = 0;
+
+        (self.content)(ColumnContent {
+            pass: Pass::Measure {
+                width_constraint: width,
+                breakable: breakable.map(|(full_height, _)| BreakableMeasure {
+                    full_height,
+                    break_count: &mut break_count,
+                    extra_location_min_height: &mut extra_location_min_height,
+                }),
+                height_available: first_height,
+                width: &mut measured_width,
+                height: &mut height,
+                expand_weight: Some(&mut weight),
+            },
+            gap: self.gap,
+            avoid_single_line_tail: self.avoid_single_line_tail,
+            border_collapse: self.border_collapse,
+            outline: None,
+        });
+
+        let location_offset = breakable.map(|(_, count)| count).unwrap_or(0);
+        let leftover = preferred_height - self.outer_height(height, break_count).unwrap_or(0.);
+
+        (break_count == location_offset && weight > 0 && leftover > 0.).then(|| DrawExpand {
+            location_offset,
+            per_weight: leftover / weight as f64,
+        })
+    }
+}
+
+fn draw_separator(
+    location: &Location,
+    width: f64,
+    style: &LineStyle,
+    line_rendering: LineRendering,
+) {
+    let layer = &location.layer;
+
+    layer.save_graphics_state();
+
+    let (color, _alpha) = u32_to_color_and_alpha(style.color);
+    layer.set_outline_color(color);
+    layer.set_outline_thickness(style.stroke_width(line_rendering));
+    layer.set_line_cap_style(style.cap_style.into());
+    set_line_join(layer, style);
+    set_line_dash_pattern(layer, style);
+
+    let x = location.pos.0;
+    let y = style.snap_position(line_rendering, location.pos.1);
+
+    add_shape(
+        layer,
+        printpdf::Line {
+            points: vec![
+                (Point::new(Mm(x), Mm(y)), false),
+                (Point::new(Mm(x + width), Mm(y)), false),
+            ],
+            is_closed: false,
+            has_fill: false,
+            has_stroke: true,
+            is_clipping_path: false,
+        },
+    );
+
+    layer.restore_graphics_state();
+}
+
+fn draw_background(location: &Location, width: f64, height: f64, color: u32) {
+    let layer = &location.layer;
+
+    layer.save_graphics_state();
+
+    let (color, alpha) = u32_to_color_and_alpha(color);
+    layer.set_fill_color(color);
+    layer.set_fill_alpha(alpha);
+
+    let (x, y) = location.pos;
+
+    add_shape(
+        layer,
+        printpdf::Line {
+            points: calculate_points_for_rect(
+                Mm(width),
+                Mm(height),
+                Mm(x + width / 2.),
+                Mm(y - height / 2.),
+            ),
+            is_closed: true,
+            has_fill: true,
+            has_stroke: false,
+            is_clipping_path: false,
+        },
+    );
+
+    layer.restore_graphics_state();
+}
+
+#[derive(Clone, Copy)]
+struct DrawExpand {
+    location_offset: u32,
+    per_weight: f64,
 }
 
 pub struct ColumnContent<'a, 'b, 'r> {
     pass: Pass<'a, 'b, 'r>,
     gap: f64,
+    avoid_single_line_tail: Option<f64>,
+    border_collapse: bool,
+
+    /// The outline of the element before, if it was added with one.
+    outline: Option<f64>,
 }
 
 enum Pass<'a, 'b, 'r> {
@@ -104,6 +593,17 @@ enum Pass<'a, 'b, 'r> {
         ctx: FirstLocationUsageCtx,
         ret: &'r mut FirstLocationUsage,
     },
+    HasExpand {
+        ret: &'r mut bool,
+    },
+    FirstItems {
+        width: WidthConstraint,
+        full_height: f64,
+
+        /// The number of elements with height that are still missing.
+        count: u32,
+        height: &'r mut Option<f64>,
+    },
     Measure {
         width_constraint: WidthConstraint,
         breakable: Option<BreakableMeasure<'a>>,
@@ -112,6 +612,9 @@ enum Pass<'a, 'b, 'r> {
         height_available: f64,
         width: &'r mut Option<f64>,
         height: &'r mut Option<f64>,
+
+        /// The total weight of the expanding elements after the last break.
+        expand_weight: Option<&'r mut u32>,
     },
     Draw {
         pdf: &'a mut Pdf,
@@ -124,12 +627,112 @@ enum Pass<'a, 'b, 'r> {
         height_available: f64,
         width: &'r mut Option<f64>,
         height: &'r mut Option<f64>,
+        expand: Option<DrawExpand>,
+
+        /// Where to draw the separators, if there are any. They're drawn at the end, when the
+        /// width of the column is known.
+        separators: Option<&'r mut Vec<Location>>,
     },
 }
 
 impl<'a, 'b, 'r> ColumnContent<'a, 'b, 'r> {
-    pub fn add<E: Element>(mut self, element: &E) -> Option<Self> {
+    pub fn add<E: Element>(self, element: &E) -> Option<Self> {
+        self.add_with_outline(element, None, None)
+    }
+
+    /// Adds an element that gets a share of the leftover preferred height on the last location,
+    /// relative to its weight, like [Flex::Expand](super::row::Flex::Expand) in a row. The element
+    /// is drawn with its share added to its preferred height and the column reserves that space
+    /// even if the element doesn't use it.
+    pub fn add_expand<E: Element>(self, element: &E, weight: u8) -> Option<Self> {
+        self.add_with_outline(element, Some(weight), None)
+    }
+
+    /// Adds an element that has an outline of the thickness around it, like a bordered table row.
+    /// With [Column::border_collapse], it overlaps the element before it by the thinner of both
+    /// outlines if that was added with an outline too.
+    pub fn add_outlined<E: Element>(self, element: &E, outline: f64) -> Option<Self> {
+        self.add_with_outline(element, None, Some(outline))
+    }
+
+    /// [add_expand](Self::add_expand) for an element with an outline, see
+    /// [add_outlined](Self::add_outlined).
+    pub fn add_expand_outlined<E: Element>(
+        self,
+        element: &E,
+        weight: u8,
+        outline: f64,
+    ) -> Option<Self> {
+        self.add_with_outline(element, Some(weight), Some(outline))
+    }
+
+    /// Adds an element with a different gap before it than the one of the column, e.g. a negative
+    /// one so that the borders of adjacent boxes overlap.
+    pub fn add_with_gap<E: Element>(mut self, element: &E, gap: f64) -> Option<Self> {
+        let column_gap = std::mem::replace(&mut self.gap, gap);
+        let mut content = self.add(element)?;
+        content.gap = column_gap;
+        Some(content)
+    }
+
+    fn add_with_outline<E: Element>(
+        mut self,
+        element: &E,
+        weight: Option<u8>,
+        outline: Option<f64>,
+    ) -> Option<Self> {
+        let overlap = match (self.border_collapse, self.outline, outline) {
+            (true, Some(previous), Some(outline)) => previous.min(outline),
+            _ => 0.,
+        };
+
+        self.gap -= overlap;
+        let mut content = self.add_with_weight(element, weight)?;
+        content.gap += overlap;
+        content.outline = outline;
+
+        Some(content)
+    }
+
+    fn add_with_weight<E: Element>(self, element: &E, weight: Option<u8>) -> Option<Self> {
+        if let Some(fraction) = self.avoid_single_line_tail {
+            self.add_element(&AvoidTail { element, fraction }, weight)
+        } else {
+            self.add_element(element, weight)
+        }
+    }
+
+    fn add_element<E: Element>(mut self, element: &E, weight: Option<u8>) -> Option<Self> {
         match self.pass {
+            Pass::HasExpand {
+                ret: &mut ref mut ret,
+            } => {
+                if weight.is_some() {
+                    *ret = true;
+                    None
+                } else {
+                    Some(self)
+                }
+            }
+            Pass::FirstItems {
+                width,
+                full_height,
+                ref mut count,
+                height: &mut ref mut height,
+            } => {
+                let size = element.measure(MeasureCtx {
+                    width,
+                    first_height: full_height,
+                    breakable: None,
+                });
+
+                if size.height.is_some() {
+                    *height = add_optional_size_with_gap(*height, size.height, self.gap);
+                    *count -= 1;
+                }
+
+                (*count > 0).then_some(self)
+            }
             Pass::InsufficientFirstHeight {
                 ref mut ctx,
                 ret: &mut ref mut ret,
@@ -150,6 +753,7 @@ impl<'a, 'b, 'r> ColumnContent<'a, 'b, 'r> {
                 ref mut height_available,
                 width: &mut ref mut width,
                 height: &mut ref mut height,
+                ref mut expand_weight,
             } => {
                 // The gap is applied here, but will only be actually applied to the height and
                 // position for subsequent elements if this element ends up having a height.
@@ -162,6 +766,7 @@ impl<'a, 'b, 'r> ColumnContent<'a, 'b, 'r> {
                 };
 
                 let size;
+                let mut broke = false;
 
                 if let Some(b) = breakable {
                     let mut break_count = 0;
@@ -182,11 +787,20 @@ impl<'a, 'b, 'r> ColumnContent<'a, 'b, 'r> {
                         *height_available = b.full_height;
                         *height = None;
                         *b.break_count += break_count;
+                        broke = true;
                     }
                 } else {
                     size = element.measure(measure_ctx);
                 }
 
+                if let Some(expand_weight) = expand_weight {
+                    if broke {
+                        **expand_weight = 0;
+                    } else if let (Some(weight), Some(_)) = (weight, size.height) {
+                        **expand_weight += weight as u32;
+                    }
+                }
+
                 if let Some(h) = size.height {
                     if let Some(height) = height {
                         *height += self.gap;
@@ -215,7 +829,44 @@ impl<'a, 'b, 'r> ColumnContent<'a, 'b, 'r> {
                 ref mut height_available,
                 width: &mut ref mut width,
                 height: &mut ref mut height,
+                expand,
+                ref mut separators,
             } => {
+                let first_height = *height_available
+                    - height.unwrap_or(0.)
+                    - if height.is_some() { self.gap } else { 0. };
+
+                let preferred_height = match (weight, expand) {
+                    (Some(weight), Some(expand)) if *location_offset == expand.location_offset => {
+                        let mut break_count = 0;
+                        let mut extra_location_min_height = None;
+
+                        let size = element.measure(MeasureCtx {
+                            width: width_constraint,
+                            first_height,
+                            breakable: breakable.as_ref().map(|b| BreakableMeasure {
+                                full_height: b.full_height,
+                                break_count: &mut break_count,
+                                extra_location_min_height: &mut extra_location_min_height,
+                            }),
+                        });
+
+                        size.height
+                            .filter(|_| break_count == 0)
+                            .map(|h| h + expand.per_weight * weight as f64)
+                    }
+                    _ => None,
+                };
+
+                let separator = (separators.is_some() && height.is_some()).then(|| Location {
+                    layer: location.layer.clone(),
+                    pos: (location.pos.0, location.pos.1 - self.gap / 2.),
+                    ..*location
+                });
+
+                // Whether the element drew anything on the location it started on, if it broke.
+                let mut used_first_location = None;
+
                 // The gap is applied here, but will only be actually applied to the height and
                 // position for subsequent elements if this element ends up having a height.
                 let draw_ctx = DrawCtx {
@@ -230,10 +881,8 @@ impl<'a, 'b, 'r> ColumnContent<'a, 'b, 'r> {
                         ..*location
                     },
                     width: width_constraint,
-                    first_height: *height_available
-                        - height.unwrap_or(0.)
-                        - if height.is_some() { self.gap } else { 0. },
-                    preferred_height: None,
+                    first_height,
+                    preferred_height,
                     breakable: None,
                 };
 
@@ -247,6 +896,10 @@ impl<'a, 'b, 'r> ColumnContent<'a, 'b, 'r> {
                             do_break: &mut |pdf, location_idx, location_height| {
                                 *height_available = b.full_height;
 
+                                if location_idx == 0 {
+                                    used_first_location = Some(location_height.is_some());
+                                }
+
                                 let location_height = if location_idx == 0 {
                                     add_optional_size_with_gap(location_height, *height, self.gap)
                                 } else {
@@ -281,6 +934,17 @@ impl<'a, 'b, 'r> ColumnContent<'a, 'b, 'r> {
                     element.draw(draw_ctx)
                 };
 
+                let size = ElementSize {
+                    height: max_optional_size(size.height, preferred_height),
+                    ..size
+                };
+
+                if let (Some(separators), Some(separator)) = (separators, separator) {
+                    if used_first_location.unwrap_or(size.height.is_some()) {
+                        separators.push(separator);
+                    }
+                }
+
                 if let Some(h) = size.height {
                     if let Some(height) = height {
                         location.pos.1 -= self.gap;
@@ -311,14 +975,16 @@ impl<'a, 'b, 'r> ColumnContent<'a, 'b, 'r> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{elements::force_break::ForceBreak, elements::none::NoneElement, test_utils::*};
+    use crate::{
+        elements::{force_break::ForceBreak, line::Line, none::NoneElement},
+        test_utils::*,
+    };
 
     #[test]
     fn test_column_empty() {
         let element = Column {
             gap: 100.,
-            collapse: true,
-            content: |_| Some(()),
+            ..Column::new(|_| Some(()))
         };
 
         for output in ElementTestParams::default().run(&element) {
@@ -334,6 +1000,410 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_column_expand() {
+        let output = test_element(
+            TestElementParams {
+                first_height: 20.,
+                preferred_height: Some(12.),
+                ..Default::default()
+            },
+            |_, callback| {
+                let text = FakeText {
+                    lines: 2,
+                    line_height: 1.,
+                    width: 3.,
+                };
+
+                let expand = FakeText {
+                    lines: 1,
+                    line_height: 1.,
+                    width: 3.,
+                };
+
+                callback.call(Column::new(|content| {
+                    content
+                        .add(&text)?
+                        .add_expand(&expand, 1)?
+                        .add_expand(&expand, 3)?;
+
+                    None
+                }))
+            },
+        );
+
+        // The 8mm left over are split 2mm and 6mm between the expanding elements.
+        assert_eq!(output.size.height, Some(12.));
+    }
+
+    #[test]
+    fn test_column_separator() {
+        let text = FakeText {
+            lines: 3,
+            line_height: 2.,
+            width: 3.,
+        };
+
+        let column = |separator| Column {
+            gap: 1.,
+            separator,
+            ..Column::new(|content| {
+                content
+                    .add(&text)?
+                    .add(&NoneElement)?
+                    .add(&text)?
+                    .add(&NoneElement)?;
+
+                None
+            })
+        };
+
+        let params = ElementTestParams {
+            first_height: 5.,
+            full_height: 8.,
+            width: 10.,
+            ..Default::default()
+        };
+
+        let plain = column(None);
+        let separated = column(Some(Line::new(1.).style));
+
+        // The separators are only drawn and don't change the layout.
+        for (output, separated) in params.run(&plain).zip(params.run(&separated)) {
+            assert_eq!(output.size, separated.size);
+            assert_eq!(
+                output.breakable.as_ref().map(|b| b.break_count),
+                separated.breakable.as_ref().map(|b| b.break_count),
+            );
+        }
+    }
+
+    #[test]
+    fn test_column_gaps() {
+        let text = FakeText {
+            lines: 6,
+            line_height: 2.,
+            width: 3.,
+        };
+
+        let column = |outer_gaps, gap_after_break| Column {
+            gap: 1.,
+            outer_gaps,
+            gap_after_break,
+            ..Column::new(|content| {
+                content.add(&text)?;
+                None
+            })
+        };
+
+        let params = ElementTestParams {
+            first_height: 5.,
+            full_height: 8.,
+            width: 10.,
+            ..Default::default()
+        };
+
+        for output in params.run(&column(true, false)) {
+            if output.breakable.is_none() {
+                assert_eq!(output.size.height, Some(1. + 12. + 1.));
+            }
+        }
+
+        // Every location after a break has 7mm left for the text.
+        for output in params.run(&column(false, true)) {
+            let (height, break_count) = match output.breakable {
+                None => (12., 0),
+                Some(_) if output.first_height == 5. => (1. + 2., 2),
+                Some(_) => (1. + 4., 1),
+            };
+
+            output.assert_size(ElementSize {
+                width: Some(output.width.constrain(3.)),
+                height: Some(height),
+            });
+
+            if let Some(b) = output.breakable {
+                b.assert_break_count(break_count);
+            }
+        }
+    }
+
+    #[test]
+    fn test_column_avoid_single_line_tail() {
+        let header = FakeText {
+            lines: 1,
+            line_height: 2.,
+            width: 3.,
+        };
+
+        let text = FakeText {
+            lines: 4,
+            line_height: 2.,
+            width: 3.,
+        };
+
+        let column = |avoid_single_line_tail| Column {
+            avoid_single_line_tail,
+            ..Column::new(|content| {
+                content.add(&header)?.add(&text)?;
+                None
+            })
+        };
+
+        let params = ElementTestParams {
+            first_height: 5.,
+            full_height: 10.,
+            width: 10.,
+            ..Default::default()
+        };
+
+        // Only one of the four lines fits after the header.
+        for output in params.run(&column(None)) {
+            let (height, break_count) = match output.breakable {
+                Some(_) if output.first_height == 5. => (6., 1),
+                _ => (10., 0),
+            };
+
+            assert_eq!(output.size.height, Some(height));
+
+            if let Some(b) = output.breakable {
+                b.assert_break_count(break_count);
+            }
+        }
+
+        // Half of the text has to fit, so it starts on the next location.
+        for output in params.run(&column(Some(0.5))) {
+            let (height, break_count) = match output.breakable {
+                Some(_) if output.first_height == 5. => (8., 1),
+                _ => (10., 0),
+            };
+
+            assert_eq!(output.size.height, Some(height));
+
+            if let Some(b) = output.breakable {
+                b.assert_break_count(break_count);
+            }
+        }
+    }
+
+    #[test]
+    fn test_column_avoid_single_line_tail_long_element() {
+        let header = FakeText {
+            lines: 1,
+            line_height: 2.,
+            width: 3.,
+        };
+
+        let text = FakeText {
+            lines: 30,
+            line_height: 2.,
+            width: 3.,
+        };
+
+        let column = Column {
+            avoid_single_line_tail: Some(0.2),
+            ..Column::new(|content| {
+                content.add(&header)?.add(&text)?;
+                None
+            })
+        };
+
+        let params = ElementTestParams {
+            first_height: 5.,
+            full_height: 10.,
+            width: 10.,
+            ..Default::default()
+        };
+
+        // The text is taller than a location, so one line after the header is a fifth of a full
+        // location and it starts right there.
+        for output in params.run(&column) {
+            let (height, break_count) = match output.breakable {
+                Some(_) if output.first_height == 5. => (8., 6),
+                Some(_) => (2., 6),
+                None => (62., 0),
+            };
+
+            assert_eq!(output.size.height, Some(height));
+
+            if let Some(b) = output.breakable {
+                b.assert_break_count(break_count);
+            }
+        }
+    }
+
+    #[test]
+    fn test_column_min_items_before_break() {
+        let header = FakeText {
+            lines: 1,
+            line_height: 2.,
+            width: 3.,
+        };
+
+        let row = FakeText {
+            lines: 1,
+            line_height: 2.,
+            width: 3.,
+        };
+
+        let column = |min_items_before_break| Column {
+            min_items_before_break,
+            ..Column::new(|content| {
+                content.add(&header)?.add(&row)?.add(&row)?.add(&row)?;
+                None
+            })
+        };
+
+        let params = ElementTestParams {
+            first_height: 5.,
+            full_height: 10.,
+            width: 10.,
+            ..Default::default()
+        };
+
+        // The header and one row fit before the break either way.
+        for min_items_before_break in [None, Some(2)] {
+            for output in params.run(&column(min_items_before_break)) {
+                let (height, break_count) = match output.breakable {
+                    Some(_) if output.first_height == 5. => (4., 1),
+                    _ => (8., 0),
+                };
+
+                assert_eq!(output.size.height, Some(height));
+
+                if let Some(b) = output.breakable {
+                    b.assert_break_count(break_count);
+                }
+            }
+        }
+
+        // The header and two rows don't fit, so the whole column starts on the next location.
+        for output in params.run(&column(Some(3))) {
+            let (height, break_count) = match output.breakable {
+                Some(_) if output.first_height == 5. => (8., 1),
+                _ => (8., 0),
+            };
+
+            assert_eq!(output.size.height, Some(height));
+
+            if let Some(b) = output.breakable {
+                b.assert_break_count(break_count);
+            }
+        }
+    }
+
+    #[test]
+    fn test_column_add_with_gap() {
+        let text = FakeText {
+            lines: 1,
+            line_height: 2.,
+            width: 3.,
+        };
+
+        let column = Column {
+            gap: 1.,
+            ..Column::new(|content| {
+                content.add(&text)?.add_with_gap(&text, -0.5)?.add(&text)?;
+                None
+            })
+        };
+
+        let params = ElementTestParams {
+            first_height: 10.,
+            full_height: 10.,
+            width: 10.,
+            ..Default::default()
+        };
+
+        // Only the gap before the second element is replaced.
+        for output in params.run(&column) {
+            assert_eq!(output.size.height, Some(6.5));
+
+            if let Some(b) = output.breakable {
+                b.assert_break_count(0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_column_border_collapse() {
+        let text = FakeText {
+            lines: 1,
+            line_height: 2.,
+            width: 3.,
+        };
+
+        let column = |border_collapse| Column {
+            border_collapse,
+            ..Column::new(|content| {
+                content
+                    .add_outlined(&text, 1.)?
+                    .add_outlined(&text, 0.5)?
+                    .add_expand_outlined(&text, 1, 1.)?
+                    .add(&text)?
+                    .add_outlined(&text, 1.)?;
+                None
+            })
+        };
+
+        let params = ElementTestParams {
+            first_height: 20.,
+            full_height: 20.,
+            width: 10.,
+            ..Default::default()
+        };
+
+        // The second and third element overlap the one before by the thinner outline. The last one
+        // follows an element without outline.
+        for output in params.run(&column(true)) {
+            assert_eq!(output.size.height, Some(10. - 0.5 - 0.5));
+        }
+
+        for output in params.run(&column(false)) {
+            assert_eq!(output.size.height, Some(10.));
+        }
+    }
+
+    #[test]
+    fn test_column_background() {
+        let text = FakeText {
+            lines: 4,
+            line_height: 2.,
+            width: 3.,
+        };
+
+        let column = |background| Column {
+            gap: 1.,
+            outer_gaps: true,
+            background,
+            ..Column::new(|content| {
+                content.add(&text)?;
+                None
+            })
+        };
+
+        let params = ElementTestParams {
+            first_height: 5.,
+            full_height: 10.,
+            width: 10.,
+            ..Default::default()
+        };
+
+        // The background doesn't change the layout.
+        for output in params.run(&column(Some(0xEE_EE_EE_FF))) {
+            let (height, break_count) = match output.breakable {
+                Some(_) if output.first_height == 5. => (5., 1),
+                _ => (10., 0),
+            };
+
+            assert_eq!(output.size.height, Some(height));
+
+            if let Some(b) = output.breakable {
+                b.assert_break_count(break_count);
+            }
+        }
+    }
+
     #[test]
     fn test_column_with_multiple_nones() {
         use assert_passes::*;
@@ -381,12 +1451,11 @@ mod tests {
 
             let element = Column {
                 gap: 1.,
-                collapse: true,
-                content: |content| {
+                ..Column::new(|content| {
                     content.add(&none_0)?.add(&none_1)?.add(&none_2)?;
 
                     None
-                },
+                })
             };
 
             callback.call(element)
@@ -714,7 +1783,7 @@ mod tests {
             let element = Column {
                 gap: 1.,
                 collapse: false,
-                content: |content| {
+                ..Column::new(|content| {
                     content
                         .add(&child_0)?
                         .add(&child_1)?
@@ -723,7 +1792,7 @@ mod tests {
                         .add(&child_4)?;
 
                     Some(())
-                },
+                })
             };
 
             callback.call(element)
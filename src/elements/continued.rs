@@ -0,0 +1,303 @@
+use crate::*;
+
+/// Marks content that breaks across locations. The `bottom` marker (e.g. a right aligned
+/// "continued") is drawn at the bottom of every location the content breaks away from and the
+/// `top` marker (e.g. "continued from previous page") at the top of every location after that.
+///
+/// The space for the markers is reserved on every location, since it isn't known in advance which
+/// one will be the last. When the content doesn't break, nothing is drawn.
+pub struct Continued<'a, E: Element> {
+    pub content: &'a E,
+    pub bottom: Option<&'a dyn Element>,
+    pub top: Option<&'a dyn Element>,
+
+    /// The gap between the markers and the content.
+    pub gap: f64,
+}
+
+struct Common {
+    first_height: f64,
+    full_height: Option<f64>,
+
+    /// The height of the first location, including the space for the bottom marker.
+    location_first_height: f64,
+
+    bottom_height: Option<f64>,
+    top_height: Option<f64>,
+    pre_break: bool,
+}
+
+impl Common {
+    fn top_offset(&self, gap: f64) -> f64 {
+        self.top_height.map(|h| h + gap).unwrap_or(0.)
+    }
+
+    fn bottom_offset(&self, gap: f64) -> f64 {
+        self.bottom_height.map(|h| h + gap).unwrap_or(0.)
+    }
+}
+
+impl<'a, E: Element> Continued<'a, E> {
+    fn common(
+        &self,
+        width: WidthConstraint,
+        first_height: f64,
+        full_height: Option<f64>,
+    ) -> Common {
+        let marker_height = |marker: Option<&dyn Element>| {
+            marker.zip(full_height).and_then(|(marker, full_height)| {
+                marker
+                    .measure(MeasureCtx {
+                        width,
+                        first_height: full_height,
+                        breakable: None,
+                    })
+                    .height
+            })
+        };
+
+        let mut common = Common {
+            first_height,
+            full_height,
+            location_first_height: first_height,
+            bottom_height: marker_height(self.bottom),
+            top_height: marker_height(self.top),
+            pre_break: false,
+        };
+
+        let bottom_offset = common.bottom_offset(self.gap);
+        common.first_height -= bottom_offset;
+
+        if let Some(full_height) = full_height {
+            let content_full_height = full_height - bottom_offset - common.top_offset(self.gap);
+            common.full_height = Some(content_full_height);
+
+            common.pre_break = common.first_height < content_full_height
+                && self.content.first_location_usage(FirstLocationUsageCtx {
+                    width,
+                    first_height: common.first_height,
+                    full_height: content_full_height,
+                }) == FirstLocationUsage::WillSkip;
+
+            // Nothing has been drawn before the new location, so there's no top marker.
+            if common.pre_break {
+                common.location_first_height = full_height;
+                common.first_height = full_height - bottom_offset;
+            }
+        }
+
+        common
+    }
+
+    fn height(&self, common: &Common, break_count: u32, height: Option<f64>) -> Option<f64> {
+        match common.top_height {
+            Some(top_height) if break_count > 0 => {
+                Some(top_height + height.map(|h| h + self.gap).unwrap_or(0.))
+            }
+            _ => height,
+        }
+    }
+}
+
+impl<'a, E: Element> Element for Continued<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        let common = self.common(ctx.width, ctx.first_height, Some(ctx.full_height));
+
+        if common.pre_break {
+            FirstLocationUsage::WillSkip
+        } else {
+            self.content.first_location_usage(FirstLocationUsageCtx {
+                width: ctx.width,
+                first_height: common.first_height,
+                full_height: common.full_height.unwrap(),
+            })
+        }
+    }
+
+    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        let common = self.common(
+            ctx.width,
+            ctx.first_height,
+            ctx.breakable.as_ref().map(|b| b.full_height),
+        );
+
+        let mut break_count = 0;
+        let mut extra_location_min_height = None;
+
+        let size = self.content.measure(MeasureCtx {
+            width: ctx.width,
+            first_height: common.first_height,
+            breakable: ctx.breakable.as_mut().map(|_| BreakableMeasure {
+                full_height: common.full_height.unwrap(),
+                break_count: &mut break_count,
+                extra_location_min_height: &mut extra_location_min_height,
+            }),
+        });
+
+        if let Some(breakable) = ctx.breakable {
+            *breakable.break_count = break_count + u32::from(common.pre_break);
+            *breakable.extra_location_min_height = extra_location_min_height
+                .map(|h| h + common.top_offset(self.gap) + common.bottom_offset(self.gap));
+        }
+
+        ElementSize {
+            width: size.width,
+            height: self.height(&common, break_count, size.height),
+        }
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let common = self.common(
+            ctx.width,
+            ctx.first_height,
+            ctx.breakable.as_ref().map(|b| b.full_height),
+        );
+
+        let breakable = match ctx.breakable {
+            Some(breakable) => breakable,
+            None => {
+                return self.content.draw(DrawCtx {
+                    first_height: common.first_height,
+                    breakable: None,
+                    ..ctx
+                })
+            }
+        };
+
+        let location_offset = u32::from(common.pre_break);
+
+        let mut current_location = if common.pre_break {
+            (breakable.do_break)(ctx.pdf, 0, None)
+        } else {
+            ctx.location
+        };
+
+        let mut break_count = 0;
+
+        let size = self.content.draw(DrawCtx {
+            pdf: ctx.pdf,
+            location: current_location.clone(),
+            width: ctx.width,
+            first_height: common.first_height,
+            preferred_height: None,
+            breakable: Some(BreakableDraw {
+                full_height: common.full_height.unwrap(),
+                preferred_height_break_count: 0,
+                do_break: &mut |pdf, location_idx, height| {
+                    let location_height = if location_idx == 0 {
+                        common.location_first_height
+                    } else {
+                        breakable.full_height
+                    };
+
+                    // Locations can be requested multiple times. The markers are only drawn the
+                    // first time.
+                    let first_time = location_idx >= break_count;
+
+                    if first_time && location_idx == break_count && height.is_some() {
+                        if let (Some(bottom), Some(bottom_height)) =
+                            (self.bottom, common.bottom_height)
+                        {
+                            bottom.draw(DrawCtx {
+                                pdf,
+                                location: Location {
+                                    pos: (
+                                        current_location.pos.0,
+                                        current_location.pos.1 - location_height + bottom_height,
+                                    ),
+                                    ..current_location.clone()
+                                },
+                                width: ctx.width,
+                                first_height: bottom_height,
+                                preferred_height: None,
+                                breakable: None,
+                            });
+                        }
+                    }
+
+                    let mut location = (breakable.do_break)(
+                        pdf,
+                        location_offset + location_idx,
+                        height.map(|_| location_height),
+                    );
+
+                    if first_time {
+                        break_count = location_idx + 1;
+                        current_location = location.clone();
+
+                        if let Some(top) = self.top {
+                            top.draw(DrawCtx {
+                                pdf,
+                                location: location.clone(),
+                                width: ctx.width,
+                                first_height: breakable.full_height,
+                                preferred_height: None,
+                                breakable: None,
+                            });
+                        }
+                    }
+
+                    location.pos.1 -= common.top_offset(self.gap);
+                    location
+                },
+            }),
+        });
+
+        ElementSize {
+            width: size.width,
+            height: self.height(&common, break_count, size.height),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_continued() {
+        let content = FakeText {
+            lines: 10,
+            line_height: 1.,
+            width: 5.,
+        };
+
+        let marker = FakeText {
+            lines: 1,
+            line_height: 1.,
+            width: 3.,
+        };
+
+        let element = Continued {
+            content: &content,
+            bottom: Some(&marker),
+            top: Some(&marker),
+            gap: 0.,
+        };
+
+        for output in (ElementTestParams {
+            first_height: 5.,
+            full_height: 6.,
+            ..Default::default()
+        })
+        .run(&element)
+        {
+            if let Some(b) = output.breakable {
+                b.assert_break_count(2);
+
+                // The first location has room for 4 or 5 lines and the others for 4, because of
+                // the markers.
+                output.assert_size(ElementSize {
+                    width: Some(output.width.constrain(5.)),
+                    height: Some(if output.first_height == 5. { 3. } else { 2. }),
+                });
+            } else {
+                output.assert_size(ElementSize {
+                    width: Some(output.width.constrain(5.)),
+                    height: Some(10.),
+                });
+            }
+        }
+    }
+}
@@ -1,12 +1,24 @@
+use std::cell::Cell;
+
 use printpdf::{utils::calculate_points_for_rect, Line, Rgb};
 
-use crate::*;
+use crate::{
+    utils::{add_shape, scoped},
+    *,
+};
+
+thread_local! {
+    /// The color of the baseline guides while a [Debug] with `show_baselines` is drawing.
+    static BASELINES: Cell<Option<[f64; 3]>> = const { Cell::new(None) };
+}
 
 pub struct Debug<'a, E: Element + ?Sized> {
     pub element: &'a E,
     pub color: u8,
     pub show_max_width: bool,
     pub show_last_location_max_height: bool,
+    pub show_rulers: bool,
+    pub show_baselines: bool,
 }
 
 impl<'a, E: Element + ?Sized> Debug<'a, E> {
@@ -23,6 +35,25 @@ impl<'a, E: Element + ?Sized> Debug<'a, E> {
             ..self
         }
     }
+
+    /// Draws millimeter ticks along the top and left edges of the page where the box is on each
+    /// location. The ticks are measured from the top left corner of the page, so the position of
+    /// the box on the page can be read off.
+    pub fn show_rulers(self) -> Self {
+        Self {
+            show_rulers: true,
+            ..self
+        }
+    }
+
+    /// Draws a line along the baseline of every line of [Text](super::text::Text) in the element,
+    /// across the width of the text.
+    pub fn show_baselines(self) -> Self {
+        Self {
+            show_baselines: true,
+            ..self
+        }
+    }
 }
 
 impl<'a, E: Element + ?Sized> Element for Debug<'a, E> {
@@ -35,6 +66,17 @@ impl<'a, E: Element + ?Sized> Element for Debug<'a, E> {
     }
 
     fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        if self.show_baselines {
+            let color = calculate_color(self.color);
+            scoped(&BASELINES, Some(color), || self.draw_debug(ctx)).0
+        } else {
+            self.draw_debug(ctx)
+        }
+    }
+}
+
+impl<'a, E: Element + ?Sized> Debug<'a, E> {
+    fn draw_debug(&self, ctx: DrawCtx) -> ElementSize {
         let size;
         let mut last_location = ctx.location.clone();
 
@@ -42,6 +84,7 @@ impl<'a, E: Element + ?Sized> Element for Debug<'a, E> {
         let max_width = ctx.width.max;
         let first_height = ctx.first_height;
         let full_height = ctx.breakable.as_ref().map(|b| b.full_height);
+        let page_size = ctx.pdf.page_size;
 
         let mut break_heights = Vec::new();
 
@@ -108,6 +151,10 @@ impl<'a, E: Element + ?Sized> Element for Debug<'a, E> {
                         _ => true,
                     };
 
+                    if self.show_rulers {
+                        draw_rulers(&location, page_size, dashed_size, color);
+                    }
+
                     if dashed {
                         draw_box(location, dashed_size, color, dashed);
                     }
@@ -141,6 +188,12 @@ impl<'a, E: Element + ?Sized> Element for Debug<'a, E> {
             true
         };
 
+        if self.show_rulers {
+            if let Some(ruler_size) = dashed_size.0.zip(dashed_size.1) {
+                draw_rulers(&last_location, page_size, ruler_size, color);
+            }
+        }
+
         if let Some((width, height)) = dashed.then_some(dashed_size.0.zip(dashed_size.1)).flatten()
         {
             draw_box(last_location, (width, height), color, true);
@@ -200,13 +253,172 @@ fn draw_box(location: Location, size: (f64, f64), color: [f64; 3], dashed: bool)
             color[0], color[1], color[2], None,
         )));
 
-    location.layer.add_shape(Line {
-        points,
-        is_closed: true,
-        has_fill: false,
-        has_stroke: true,
-        is_clipping_path: false,
-    });
+    add_shape(
+        &location.layer,
+        Line {
+            points,
+            is_closed: true,
+            has_fill: false,
+            has_stroke: true,
+            is_clipping_path: false,
+        },
+    );
 
     location.layer.restore_graphics_state();
 }
+
+/// Draws the ticks for the whole millimeters the box covers along the top and left page edges.
+fn draw_rulers(location: &Location, page_size: (f64, f64), size: (f64, f64), color: [f64; 3]) {
+    fn tick_length(mm: u32) -> f64 {
+        if mm % 10 == 0 {
+            3.
+        } else if mm % 5 == 0 {
+            2.
+        } else {
+            1.
+        }
+    }
+
+    // The millimeters from the left and the top edge of the page.
+    let left = location.pos.0;
+    let top = page_size.1 - location.pos.1;
+
+    let layer = &location.layer;
+
+    layer.save_graphics_state();
+    layer.set_outline_thickness(0.);
+    layer.set_outline_color(printpdf::Color::Rgb(Rgb::new(
+        color[0], color[1], color[2], None,
+    )));
+
+    let tick = |from: (f64, f64), to: (f64, f64)| {
+        add_shape(
+            layer,
+            Line {
+                points: vec![
+                    (printpdf::Point::new(Mm(from.0), Mm(from.1)), false),
+                    (printpdf::Point::new(Mm(to.0), Mm(to.1)), false),
+                ],
+                is_closed: false,
+                has_fill: false,
+                has_stroke: true,
+                is_clipping_path: false,
+            },
+        );
+    };
+
+    for mm in ruler_ticks(left, size.0) {
+        let x = mm as f64;
+        tick((x, page_size.1), (x, page_size.1 - tick_length(mm)));
+    }
+
+    for mm in ruler_ticks(top, size.1) {
+        let y = page_size.1 - mm as f64;
+        tick((0., y), (tick_length(mm), y));
+    }
+
+    layer.restore_graphics_state();
+}
+
+/// The whole millimeters from `start` to `start + length`.
+fn ruler_ticks(start: f64, length: f64) -> std::ops::RangeInclusive<u32> {
+    start.max(0.).ceil() as u32..=(start + length).max(0.).floor() as u32
+}
+
+/// Draws the baseline guide of a line of text at `y` if a [Debug] with `show_baselines` is drawing.
+pub(crate) fn draw_baseline(layer: &PdfLayerReference, x: f64, y: f64, width: f64) {
+    let Some(color) = BASELINES.with(Cell::get) else {
+        return;
+    };
+
+    layer.save_graphics_state();
+    layer.set_outline_thickness(0.);
+    layer.set_outline_color(printpdf::Color::Rgb(Rgb::new(
+        color[0], color[1], color[2], None,
+    )));
+
+    add_shape(
+        layer,
+        Line {
+            points: vec![
+                (printpdf::Point::new(Mm(x), Mm(y)), false),
+                (printpdf::Point::new(Mm(x + width), Mm(y)), false),
+            ],
+            is_closed: false,
+            has_fill: false,
+            has_stroke: true,
+            is_clipping_path: false,
+        },
+    );
+
+    layer.restore_graphics_state();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        elements::text::Text,
+        fonts::builtin::BuiltinFont,
+        test_utils::{page_operations, save},
+    };
+
+    #[test]
+    fn test_ruler_ticks() {
+        assert_eq!(ruler_ticks(10., 5.), 10..=15);
+        assert_eq!(ruler_ticks(10.5, 2.), 11..=12);
+        assert_eq!(ruler_ticks(-3., 4.5), 0..=1);
+        assert!(ruler_ticks(10.2, 0.5).is_empty());
+    }
+
+    #[test]
+    fn test_baselines() {
+        struct Baselines<'a>(Text<'a, BuiltinFont>, bool);
+
+        impl<'a> Baselines<'a> {
+            fn debug(&self) -> Debug<'_, Text<'a, BuiltinFont>> {
+                let debug = self.0.debug(0);
+
+                if self.1 {
+                    debug.show_baselines()
+                } else {
+                    debug
+                }
+            }
+        }
+
+        impl<'a> Element for Baselines<'a> {
+            fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+                self.debug().first_location_usage(ctx)
+            }
+
+            fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+                self.debug().measure(ctx)
+            }
+
+            fn draw(&self, ctx: DrawCtx) -> ElementSize {
+                self.debug().draw(ctx)
+            }
+        }
+
+        // The number of line segments on the page.
+        let lines = |show_baselines: bool| {
+            let document = build_pdf(
+                "test",
+                (100., 100.),
+                BuiltinFont::helvetica,
+                |font: &BuiltinFont| {
+                    Baselines(Text::basic("one two three four", font, 12.), show_baselines)
+                },
+            );
+
+            page_operations(&save(document))[0]
+                .iter()
+                .filter(|op| op.operator == "l")
+                .count()
+        };
+
+        // The text fits on one line, which gets one baseline.
+        assert_eq!(lines(true), lines(false) + 1);
+    }
+}
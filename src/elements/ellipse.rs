@@ -0,0 +1,122 @@
+use printpdf::{utils::calculate_points_for_circle, Line, Point, Pt};
+
+use crate::{utils::*, *};
+
+pub struct Ellipse {
+    /// The width and height of the ellipse, not including the outline.
+    pub size: (f64, f64),
+    pub fill: Option<u32>,
+    pub outline: Option<LineStyle>,
+}
+
+impl Element for Ellipse {
+    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        let outline_thickness = outline_thickness(self);
+        ctx.break_if_appropriate_for_min_height(self.size.1 + outline_thickness);
+
+        size(self)
+    }
+
+    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+        let outline_thickness = outline_thickness(self);
+        ctx.break_if_appropriate_for_min_height(self.size.1 + outline_thickness);
+
+        let extra_outline_offset = outline_thickness / 2.0;
+
+        let center = (
+            mm_to_pt(ctx.location.pos.0 + self.size.0 / 2.0 + extra_outline_offset),
+            mm_to_pt(ctx.location.pos.1 - self.size.1 / 2.0 - extra_outline_offset),
+        );
+
+        let radii = (mm_to_pt(self.size.0 / 2.0), mm_to_pt(self.size.1 / 2.0));
+
+        // Bézier curves stay the same under affine transformations, so we can just stretch a unit
+        // circle.
+        let points = calculate_points_for_circle(Pt(1.), Pt(0.), Pt(0.))
+            .into_iter()
+            .map(|(point, bezier)| {
+                (
+                    Point {
+                        x: Pt(center.0 + point.x.0 * radii.0),
+                        y: Pt(center.1 + point.y.0 * radii.1),
+                    },
+                    bezier,
+                )
+            })
+            .collect();
+
+        ctx.location.layer.save_graphics_state();
+
+        if let Some(color) = self.fill {
+            let (color, alpha) = u32_to_color_and_alpha(color);
+            ctx.location.layer.set_fill_color(color);
+            ctx.location.layer.set_fill_alpha(alpha);
+        }
+
+        if let Some(line_style) = self.outline {
+            set_line_style(&ctx.location.layer, &line_style, ctx.pdf.line_rendering);
+        }
+
+        add_shape(
+            &ctx.location.layer,
+            Line {
+                points,
+                is_closed: true,
+                has_fill: self.fill.is_some(),
+                has_stroke: self.outline.is_some(),
+                is_clipping_path: false,
+            },
+        );
+
+        ctx.location.layer.restore_graphics_state();
+
+        size(self)
+    }
+}
+
+fn outline_thickness(ellipse: &Ellipse) -> f64 {
+    ellipse.outline.map(|o| o.thickness).unwrap_or(0.0)
+}
+
+fn size(ellipse: &Ellipse) -> ElementSize {
+    let outline_thickness = outline_thickness(ellipse);
+
+    ElementSize {
+        width: Some(ellipse.size.0 + outline_thickness),
+        height: Some(ellipse.size.1 + outline_thickness),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_ellipse() {
+        for output in (ElementTestParams {
+            first_height: 5.,
+            ..Default::default()
+        })
+        .run(&Ellipse {
+            size: (11., 5.5),
+            fill: Some(0xFF_00_00_FF),
+            outline: Some(LineStyle::solid(1., 0)),
+        }) {
+            output.assert_size(ElementSize {
+                width: Some(12.),
+                height: Some(6.5),
+            });
+
+            if let Some(b) = output.breakable {
+                if output.first_height == 5. {
+                    b.assert_break_count(1);
+                } else {
+                    b.assert_break_count(0);
+                }
+
+                b.assert_extra_location_min_height(None);
+            }
+        }
+    }
+}
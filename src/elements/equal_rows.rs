@@ -0,0 +1,264 @@
+use crate::{utils::max_optional_size, *};
+
+/// Like a [Column](super::column::Column), but all of the rows on the same page get the height of
+/// the tallest one, for form-like grids. The rows are drawn with that height as the preferred
+/// height, so for table rows the cells should be wrapped in
+/// [ExpandToPreferredHeight](super::expand_to_preferred_height::ExpandToPreferredHeight) to make
+/// the lines go all the way down.
+///
+/// Rows are measured once to find their natural heights, then assigned to pages and drawn. They
+/// can't be broken themselves.
+pub struct EqualRows<C: Fn(&mut EqualRowsContent)> {
+    pub content: C,
+    pub gap: f64,
+}
+
+struct Layout {
+    /// The page of each row, where 0 is the first location.
+    pages: Vec<u32>,
+
+    /// The height of the rows on each page.
+    heights: Vec<f64>,
+
+    width: Option<f64>,
+}
+
+impl Layout {
+    fn page_height(&self, page: u32, gap: f64) -> Option<f64> {
+        let rows = self.pages.iter().filter(|&&p| p == page).count();
+
+        if rows == 0 {
+            None
+        } else {
+            Some(rows as f64 * self.heights[page as usize] + (rows - 1) as f64 * gap)
+        }
+    }
+
+    fn break_count(&self) -> u32 {
+        self.pages.last().copied().unwrap_or(0)
+    }
+}
+
+impl<C: Fn(&mut EqualRowsContent)> EqualRows<C> {
+    fn layout(
+        &self,
+        width: WidthConstraint,
+        first_height: f64,
+        full_height: Option<f64>,
+    ) -> Layout {
+        let mut sizes = Vec::new();
+
+        (self.content)(&mut EqualRowsContent(Pass::Measure {
+            width,
+            // Rows aren't broken, so they get as much space as possible for measuring.
+            height: full_height.unwrap_or(first_height).max(first_height),
+            sizes: &mut sizes,
+        }));
+
+        let mut layout = Layout {
+            pages: Vec::with_capacity(sizes.len()),
+            heights: vec![0.],
+            width: None,
+        };
+
+        let mut page = 0;
+        let mut count = 0;
+        let mut available = first_height;
+
+        for size in sizes {
+            layout.width = max_optional_size(layout.width, size.width);
+
+            let height = size.height.unwrap_or(0.);
+
+            if let Some(full_height) = full_height {
+                let row_height = layout.heights[page as usize].max(height);
+                let needed = (count + 1) as f64 * row_height + count as f64 * self.gap;
+
+                // If the first row doesn't fit on the first location we skip it, but only if
+                // there's more space on the next one.
+                let skip_first = page == 0 && full_height > first_height;
+
+                if needed > available && (count > 0 || skip_first) {
+                    page += 1;
+                    count = 0;
+                    available = full_height;
+                    layout.heights.push(0.);
+                }
+            }
+
+            let page_height = &mut layout.heights[page as usize];
+            *page_height = page_height.max(height);
+
+            layout.pages.push(page);
+            count += 1;
+        }
+
+        layout
+    }
+}
+
+impl<C: Fn(&mut EqualRowsContent)> Element for EqualRows<C> {
+    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        let layout = self.layout(
+            ctx.width,
+            ctx.first_height,
+            ctx.breakable.as_ref().map(|b| b.full_height),
+        );
+
+        let break_count = layout.break_count();
+
+        if let Some(breakable) = ctx.breakable.as_mut() {
+            *breakable.break_count = break_count;
+        }
+
+        ElementSize {
+            width: layout.width,
+            height: layout.page_height(break_count, self.gap),
+        }
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let layout = self.layout(
+            ctx.width,
+            ctx.first_height,
+            ctx.breakable.as_ref().map(|b| b.full_height),
+        );
+
+        (self.content)(&mut EqualRowsContent(Pass::Draw {
+            layout: &layout,
+            gap: self.gap,
+            index: 0,
+            page: 0,
+            y: 0.,
+            ctx,
+        }));
+
+        ElementSize {
+            width: layout.width,
+            height: layout.page_height(layout.break_count(), self.gap),
+        }
+    }
+}
+
+pub struct EqualRowsContent<'pdf, 'a, 'r>(Pass<'pdf, 'a, 'r>);
+
+enum Pass<'pdf, 'a, 'r> {
+    Measure {
+        width: WidthConstraint,
+        height: f64,
+        sizes: &'r mut Vec<ElementSize>,
+    },
+    Draw {
+        layout: &'r Layout,
+        gap: f64,
+        index: usize,
+        page: u32,
+
+        /// The offset of the next row from the top of the current location.
+        y: f64,
+
+        ctx: DrawCtx<'pdf, 'a>,
+    },
+}
+
+impl<'pdf, 'a, 'r> EqualRowsContent<'pdf, 'a, 'r> {
+    pub fn add(&mut self, element: &impl Element) {
+        match self.0 {
+            Pass::Measure {
+                width,
+                height,
+                sizes: &mut ref mut sizes,
+            } => {
+                sizes.push(element.measure(MeasureCtx {
+                    width,
+                    first_height: height,
+                    breakable: None,
+                }));
+            }
+            Pass::Draw {
+                layout,
+                gap,
+                ref mut index,
+                ref mut page,
+                ref mut y,
+                ref mut ctx,
+            } => {
+                let row_page = layout.pages[*index];
+
+                while *page < row_page {
+                    if let Some(breakable) = ctx.breakable.as_mut() {
+                        ctx.location =
+                            (breakable.do_break)(ctx.pdf, *page, layout.page_height(*page, gap));
+                    }
+
+                    *page += 1;
+                    *y = 0.;
+                }
+
+                let height = layout.heights[row_page as usize];
+
+                element.draw(DrawCtx {
+                    pdf: ctx.pdf,
+                    location: Location {
+                        pos: (ctx.location.pos.0, ctx.location.pos.1 - *y),
+                        ..ctx.location.clone()
+                    },
+                    width: ctx.width,
+                    first_height: height,
+                    preferred_height: Some(height),
+                    breakable: None,
+                });
+
+                *y += height + gap;
+                *index += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_equal_rows() {
+        let rows = [1, 3, 2, 1, 1].map(|lines| FakeText {
+            lines,
+            line_height: 1.,
+            width: 5.,
+        });
+
+        let element = EqualRows {
+            content: |content| {
+                for row in &rows {
+                    content.add(row);
+                }
+            },
+            gap: 1.,
+        };
+
+        for output in (ElementTestParams {
+            first_height: 8.,
+            full_height: 10.,
+            ..Default::default()
+        })
+        .run(&element)
+        {
+            if let Some(b) = output.breakable {
+                // The first page fits two rows of height 3 and the second one the remaining three
+                // rows with a height of 2.
+                b.assert_break_count(1);
+                output.assert_size(ElementSize {
+                    width: Some(output.width.constrain(5.)),
+                    height: Some(3. * 2. + 2.),
+                });
+            } else {
+                output.assert_size(ElementSize {
+                    width: Some(output.width.constrain(5.)),
+                    height: Some(5. * 3. + 4.),
+                });
+            }
+        }
+    }
+}
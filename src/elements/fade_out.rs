@@ -0,0 +1,147 @@
+use printpdf::{utils::calculate_points_for_rect, Line};
+
+use crate::{utils::*, *};
+
+/// The number of bands the fade is made of. At typical fade lengths the bands are thinner than
+/// what can be seen.
+const STEPS: u32 = 48;
+
+/// Fades the bottom `length` of the element into `color`, e.g. for a preview of truncated text.
+/// When the element breaks, only the end on the last location is faded.
+///
+/// This isn't a real soft mask, since printpdf can't write those. Instead, bands of increasing
+/// opacity are drawn over the element, so it only looks right on a background of `color`.
+pub struct FadeOut<'a, E: Element> {
+    pub element: &'a E,
+    pub length: f64,
+    pub color: u32,
+}
+
+impl<'a, E: Element> Element for FadeOut<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let mut last_location = ctx.location.clone();
+        let width = ctx.width;
+
+        let size = if let Some(breakable) = ctx.breakable {
+            let mut break_count = 0;
+
+            self.element.draw(DrawCtx {
+                breakable: Some(BreakableDraw {
+                    do_break: &mut |pdf, location_idx, height| {
+                        let location = (breakable.do_break)(pdf, location_idx, height);
+
+                        if location_idx >= break_count {
+                            break_count = location_idx + 1;
+                            last_location = location.clone();
+                        }
+
+                        location
+                    },
+                    ..breakable
+                }),
+                ..ctx
+            })
+        } else {
+            self.element.draw(ctx)
+        };
+
+        if let Some(height) = size.height {
+            let width = size.width.unwrap_or(width.max);
+
+            self.draw_fade(&last_location, width, height);
+        }
+
+        size
+    }
+}
+
+impl<'a, E: Element> FadeOut<'a, E> {
+    fn draw_fade(&self, location: &Location, width: f64, height: f64) {
+        let length = self.length.min(height);
+
+        if length <= 0. {
+            return;
+        }
+
+        let (color, alpha) = u32_to_color_and_alpha(self.color);
+        let band_height = length / STEPS as f64;
+        let top = location.pos.1 - height + length;
+
+        let layer = &location.layer;
+
+        layer.save_graphics_state();
+        layer.set_fill_color(color);
+
+        for i in 0..STEPS {
+            layer.set_fill_alpha(alpha * (i + 1) as f64 / STEPS as f64);
+
+            add_shape(
+                layer,
+                Line {
+                    points: calculate_points_for_rect(
+                        Mm(width),
+                        Mm(band_height),
+                        Mm(location.pos.0 + width / 2.),
+                        Mm(top - band_height * (i as f64 + 0.5)),
+                    ),
+                    is_closed: true,
+                    has_fill: true,
+                    has_stroke: false,
+                    is_clipping_path: false,
+                },
+            );
+        }
+
+        layer.restore_graphics_state();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_fade_out() {
+        let element = FadeOut {
+            element: &FakeText {
+                lines: 10,
+                line_height: 1.,
+                width: 5.,
+            },
+            length: 3.,
+            color: 0xFF_FF_FF_FF,
+        };
+
+        for output in (ElementTestParams {
+            first_height: 4.,
+            full_height: 6.,
+            ..Default::default()
+        })
+        .run(&element)
+        {
+            if let Some(b) = output.breakable {
+                b.assert_break_count(1);
+            }
+
+            output.assert_size(ElementSize {
+                width: Some(output.width.constrain(5.)),
+                height: Some(if output.breakable.is_none() {
+                    10.
+                } else if output.first_height == 4. {
+                    6.
+                } else {
+                    4.
+                }),
+            });
+        }
+    }
+}
@@ -0,0 +1,90 @@
+use crate::*;
+
+use super::{
+    break_whole::BreakWhole,
+    column::Column,
+    image::{ImageElement, ImageRole},
+    styled_box::StyledBox,
+};
+
+/// An image with its caption below it, like the figures of a report. The figure never gets broken
+/// across pages. The border only goes around the image.
+///
+/// The caption can be any element. Its text should also be set as the caption of the image, which
+/// is what's reported for the `Figure` structure type, see [ImageElement].
+pub struct Figure<'a, C: Element> {
+    pub image: ImageElement<'a>,
+    pub caption: &'a C,
+
+    /// Between the image and the caption.
+    pub gap: f64,
+
+    pub border: Option<LineStyle>,
+}
+
+impl<'a, C: Element> CompositeElement for Figure<'a, C> {
+    fn element(&self, callback: impl CompositeElementCallback) {
+        let image = ImageElement {
+            role: ImageRole::Figure,
+            ..self.image
+        };
+
+        let image = StyledBox {
+            outline: self.border,
+            ..StyledBox::new(&image)
+        };
+
+        callback.call(&BreakWhole(&Column {
+            gap: self.gap,
+            ..Column::new(|content| {
+                content.add(&image)?.add(self.caption)?;
+                None
+            })
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use printpdf::image::DynamicImage;
+
+    use super::*;
+    use crate::{image::Image, test_utils::*};
+
+    #[test]
+    fn test_figure() {
+        let image = Image::Pixel(DynamicImage::new_rgb8(2, 1));
+
+        let caption = FakeText {
+            lines: 2,
+            line_height: 2.,
+            width: 5.,
+        };
+
+        let figure = Figure {
+            image: ImageElement::new(&image),
+            caption: &caption,
+            gap: 1.,
+            border: None,
+        };
+
+        // The image is 10mm wide and 5mm high, so the figure doesn't fit into the 8mm.
+        for output in (ElementTestParams {
+            width: 10.,
+            first_height: 8.,
+            full_height: 20.,
+            ..Default::default()
+        })
+        .run(&figure)
+        {
+            output.assert_size(ElementSize {
+                width: Some(10.),
+                height: Some(5. + 1. + 4.),
+            });
+
+            if let Some(b) = output.breakable {
+                b.assert_break_count(if output.first_height == 8. { 1 } else { 0 });
+            }
+        }
+    }
+}
@@ -0,0 +1,260 @@
+use crate::{utils::max_optional_size, *};
+
+/// Expands the element to take up all of the height that's left in the location it ends in. Meant
+/// to be the last element in a [Column](super::column::Column), e.g. to push content to the bottom
+/// of the page or to draw a notes box that goes all the way down.
+///
+/// The element itself is drawn with the remaining height of the location it ends in as its
+/// preferred height, so it can be combined with [AlignPreferredHeightBottom] to push it down or
+/// with [ExpandToPreferredHeight] to stretch e.g. a styled box.
+///
+/// [AlignPreferredHeightBottom]: super::align_preferred_height_bottom::AlignPreferredHeightBottom
+/// [ExpandToPreferredHeight]: super::expand_to_preferred_height::ExpandToPreferredHeight
+pub struct FillRemaining<'a, E: Element>(pub &'a E);
+
+impl<'a, E: Element> Element for FillRemaining<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        match self.0.first_location_usage(ctx) {
+            // Even if the element collapses we still fill the space.
+            FirstLocationUsage::NoneHeight => FirstLocationUsage::WillUse,
+            usage => usage,
+        }
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        let first_height = ctx.first_height;
+
+        if let Some(breakable) = ctx.breakable {
+            let mut break_count = 0;
+
+            let size = self.0.measure(MeasureCtx {
+                breakable: Some(BreakableMeasure {
+                    break_count: &mut break_count,
+                    ..breakable
+                }),
+                ..ctx
+            });
+
+            *breakable.break_count = break_count;
+
+            ElementSize {
+                width: size.width,
+                height: max_optional_size(
+                    size.height,
+                    Some(if break_count == 0 {
+                        first_height
+                    } else {
+                        breakable.full_height
+                    }),
+                ),
+            }
+        } else {
+            let size = self.0.measure(ctx);
+
+            ElementSize {
+                width: size.width,
+                height: max_optional_size(size.height, Some(first_height)),
+            }
+        }
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let first_height = ctx.first_height;
+
+        if let Some(breakable) = ctx.breakable {
+            let mut preferred_height_break_count = 0;
+
+            self.0.measure(MeasureCtx {
+                width: ctx.width,
+                first_height,
+                breakable: Some(BreakableMeasure {
+                    full_height: breakable.full_height,
+                    break_count: &mut preferred_height_break_count,
+                    extra_location_min_height: &mut None,
+                }),
+            });
+
+            let mut break_count = 0;
+
+            let size = self.0.draw(DrawCtx {
+                // Every location except for the last one is filled completely.
+                preferred_height: Some(if preferred_height_break_count == 0 {
+                    first_height
+                } else {
+                    breakable.full_height
+                }),
+                breakable: Some(BreakableDraw {
+                    preferred_height_break_count,
+                    do_break: &mut |pdf, location_idx, _height| {
+                        break_count = break_count.max(location_idx + 1);
+
+                        (breakable.do_break)(
+                            pdf,
+                            location_idx,
+                            Some(if location_idx == 0 {
+                                first_height
+                            } else {
+                                breakable.full_height
+                            }),
+                        )
+                    },
+                    ..breakable
+                }),
+                ..ctx
+            });
+
+            ElementSize {
+                width: size.width,
+                height: max_optional_size(
+                    size.height,
+                    Some(if break_count == 0 {
+                        first_height
+                    } else {
+                        breakable.full_height
+                    }),
+                ),
+            }
+        } else {
+            let size = self.0.draw(DrawCtx {
+                preferred_height: Some(first_height),
+                ..ctx
+            });
+
+            ElementSize {
+                width: size.width,
+                height: max_optional_size(size.height, Some(first_height)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::{elements::column::Column, test_utils::*};
+
+    /// Records the preferred height and break count of every draw.
+    struct Preferred<'a, E: Element> {
+        element: &'a E,
+        draws: &'a RefCell<Vec<(Option<f64>, u32)>>,
+    }
+
+    impl<'a, E: Element> Element for Preferred<'a, E> {
+        fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+            self.element.first_location_usage(ctx)
+        }
+
+        fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+            self.element.measure(ctx)
+        }
+
+        fn draw(&self, ctx: DrawCtx) -> ElementSize {
+            self.draws.borrow_mut().push((
+                ctx.preferred_height,
+                ctx.breakable
+                    .as_ref()
+                    .map_or(0, |b| b.preferred_height_break_count),
+            ));
+
+            self.element.draw(ctx)
+        }
+    }
+
+    #[test]
+    fn test_fill_remaining() {
+        let text = FakeText {
+            lines: 3,
+            line_height: 1.,
+            width: 5.,
+        };
+
+        let element = Column {
+            gap: 1.,
+            ..Column::new(|content| {
+                content.add(&text)?.add(&FillRemaining(&text))?;
+                Option::None
+            })
+        };
+
+        for output in ElementTestParams::default().run(&element) {
+            output.assert_no_breaks();
+            output.assert_size(ElementSize {
+                width: Some(output.width.constrain(5.)),
+                height: Some(output.first_height),
+            });
+        }
+
+        let text = FakeText {
+            lines: 300,
+            line_height: 1.,
+            width: 5.,
+        };
+
+        for output in ElementTestParams::default().run(&FillRemaining(&text)) {
+            if let Some(b) = output.breakable {
+                b.assert_break_count(1);
+                output.assert_size(ElementSize {
+                    width: Some(output.width.constrain(5.)),
+                    height: Some(b.full_height),
+                });
+            } else {
+                output.assert_size(ElementSize {
+                    width: Some(output.width.constrain(5.)),
+                    height: Some(300.),
+                });
+            }
+        }
+    }
+
+    #[test]
+    fn test_fill_remaining_breaking() {
+        let draws = RefCell::new(Vec::new());
+
+        let element = Preferred {
+            element: &FakeText {
+                lines: 15,
+                line_height: 1.,
+                width: 5.,
+            },
+            draws: &draws,
+        };
+
+        let params = ElementTestParams {
+            width: 10.,
+            first_height: 3.,
+            full_height: 10.,
+            ..Default::default()
+        };
+
+        for output in params.run(&FillRemaining(&element)) {
+            let recorded = draws.take();
+            assert!(!recorded.is_empty());
+
+            if let Some(b) = output.breakable {
+                // 3 + 10 + 2 or 10 + 5 lines.
+                b.assert_break_count(if output.first_height == 3. { 2 } else { 1 });
+
+                // The last location is the one that's filled.
+                output.assert_size(ElementSize {
+                    width: Some(output.width.constrain(5.)),
+                    height: Some(10.),
+                });
+
+                for draw in &recorded {
+                    assert_eq!(*draw, (Some(10.), b.break_count));
+                }
+            } else {
+                output.assert_size(ElementSize {
+                    width: Some(output.width.constrain(5.)),
+                    height: Some(15.),
+                });
+
+                for draw in &recorded {
+                    assert_eq!(*draw, (Some(output.first_height), 0));
+                }
+            }
+        }
+    }
+}
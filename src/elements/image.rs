@@ -1,17 +1,244 @@
-use printpdf::image::{DynamicImage, GenericImageView};
+use printpdf::{
+    image::{DynamicImage, GenericImageView, ImageBuffer, Rgb, Rgba},
+    CurTransMat,
+};
 
-use crate::{image::Image, *};
+use serde::{Deserialize, Serialize};
 
-use super::svg::Svg;
+use crate::{
+    image::Image,
+    structure,
+    utils::{add_op, add_path, mm_to_pt, pt_to_mm},
+    *,
+};
+
+use super::{meta, svg::Svg};
 
 const INCH_TO_MM: f64 = 25.4;
 
+/// What an image is to someone who can't see it, using the names of the tagged PDF structure
+/// types.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ImageRole {
+    /// An image that is part of the content, like a chart or a photo.
+    #[default]
+    Figure,
+
+    /// A mathematical formula typeset as an image.
+    Formula,
+
+    /// Decoration that screen readers should skip, like a logo in the header.
+    Artifact,
+}
+
+/// A shape the image is cut to, e.g. for avatars.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum ImageMask {
+    /// The largest circle in the middle of the image.
+    Circle,
+
+    /// A rectangle with rounded corners, with the radius in mm.
+    RoundedRect(#[serde(deserialize_with = "crate::serde_elements::expr::deserialize_f64")] f64),
+}
+
+/// Recolors the image for monochrome designs. Black becomes `dark`, white becomes `light` and the
+/// greys in between are mixed from the two, so a tint is a duotone with a white `light`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImageTint {
+    #[serde(deserialize_with = "crate::serde_elements::color::deserialize_color")]
+    pub dark: u32,
+
+    #[serde(
+        default = "default_tint_light",
+        deserialize_with = "crate::serde_elements::color::deserialize_color"
+    )]
+    pub light: u32,
+}
+
+fn default_tint_light() -> u32 {
+    0xFF_FF_FF_FF
+}
+
+impl ImageTint {
+    pub fn new(color: u32) -> Self {
+        ImageTint {
+            dark: color,
+            light: default_tint_light(),
+        }
+    }
+
+    /// The image in greyscale, with the greys replaced by the colors. The alpha of the colors is
+    /// multiplied with the alpha of the image.
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        let dark = self.dark.to_be_bytes();
+        let light = self.light.to_be_bytes();
+
+        let luma = image.to_luma_alpha8();
+
+        let pixel = |x, y| -> [u8; 4] {
+            let [l, a] = luma.get_pixel(x, y).0;
+            let t = l as f64 / 255.;
+            let mix = |i: usize| (dark[i] as f64 + (light[i] as f64 - dark[i] as f64) * t).round();
+
+            [
+                mix(0) as u8,
+                mix(1) as u8,
+                mix(2) as u8,
+                (mix(3) * a as f64 / 255.).round() as u8,
+            ]
+        };
+
+        let (width, height) = luma.dimensions();
+
+        if image.color().has_alpha() || dark[3] < 255 || light[3] < 255 {
+            DynamicImage::ImageRgba8(ImageBuffer::from_fn(width, height, |x, y| {
+                Rgba(pixel(x, y))
+            }))
+        } else {
+            DynamicImage::ImageRgb8(ImageBuffer::from_fn(width, height, |x, y| {
+                let [r, g, b, _] = pixel(x, y);
+                Rgb([r, g, b])
+            }))
+        }
+    }
+}
+
+/// Images drawn while [collect_tagged_images](structure::collect_tagged_images) is active are
+/// marked with their role and become elements of the structure tree with their alt text, see
+/// [crate::structure].
+///
+/// Every image drawn while [collect_regions](meta::collect_regions) is active also reports a
+/// [Region](meta::Region) with `{"image":{"role":..,"alt":..,"caption":..}}` as its meta, so
+/// accessibility audits can find the images without alt text.
 pub struct ImageElement<'a> {
     pub image: &'a Image,
+
+    /// The text read instead of the image.
+    pub alt: Option<&'a str>,
+
+    /// A caption for the image, which is the title of its structure element. It isn't drawn, since
+    /// the document usually lays it out itself.
+    pub caption: Option<&'a str>,
+
+    pub role: ImageRole,
+
+    pub mask: Option<ImageMask>,
+
+    /// Clockwise, in degrees. The image takes up its bounding box, so at 90 or 270 degrees the
+    /// width and height are swapped.
+    pub rotation: f64,
+
+    /// Flips the image horizontally, before it's rotated.
+    pub mirror: bool,
+
+    /// Only applies to pixel images, SVGs are drawn in their own colors.
+    pub tint: Option<ImageTint>,
+}
+
+impl<'a> ImageElement<'a> {
+    pub fn new(image: &'a Image) -> Self {
+        ImageElement {
+            image,
+            alt: None,
+            caption: None,
+            role: ImageRole::Figure,
+            mask: None,
+            rotation: 0.,
+            mirror: false,
+            tint: None,
+        }
+    }
+
+    fn transformed(&self) -> bool {
+        self.rotation % 360. != 0. || self.mirror
+    }
+
+    /// The size of the unrotated image in mm, before it's scaled to the width.
+    fn natural_size(&self) -> (f64, f64) {
+        match self.image {
+            Image::Svg(svg) => (pt_to_mm(svg.size.width()), pt_to_mm(svg.size.height())),
+            Image::Pixel(image) => {
+                let (x, y) = image.dimensions();
+                (x as f64 * INCH_TO_MM, y as f64 * INCH_TO_MM)
+            }
+        }
+    }
+
+    /// The size of the unrotated image and of its rotated bounding box at the width.
+    fn transformed_size(&self, width: WidthConstraint) -> ((f64, f64), ElementSize) {
+        let (w, h) = self.natural_size();
+
+        let degrees = self.rotation.rem_euclid(360.);
+
+        // Quarter turns are special cased so that the sizes stay exact.
+        let (box_width, box_height) = if degrees % 180. == 0. {
+            (w, h)
+        } else if degrees % 90. == 0. {
+            (h, w)
+        } else {
+            let (sin, cos) = degrees.to_radians().sin_cos();
+            (
+                (w * cos).abs() + (h * sin).abs(),
+                (w * sin).abs() + (h * cos).abs(),
+            )
+        };
+
+        let width = width.constrain(box_width);
+        let scale = if box_width > 0. {
+            width / box_width
+        } else {
+            0.
+        };
+
+        (
+            (w * scale, h * scale),
+            ElementSize {
+                width: Some(width),
+                height: Some(box_height * scale),
+            },
+        )
+    }
+
+    fn meta(&self) -> serde_json::Value {
+        serde_json::json!({
+            "image": {
+                "role": self.role,
+                "alt": self.alt,
+                "caption": self.caption,
+            },
+        })
+    }
+}
+
+/// The image without the region.
+struct Plain<'a, 'b>(&'b ImageElement<'a>);
+
+impl<'a, 'b> Element for Plain<'a, 'b> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.0.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.0.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        self.0.draw_tagged(ctx)
+    }
 }
 
 impl<'a> Element for ImageElement<'a> {
     fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        if self.transformed() {
+            let (_, size) = self.transformed_size(ctx.width);
+
+            return if ctx.break_appropriate_for_min_height(size.height.unwrap_or(0.)) {
+                FirstLocationUsage::WillSkip
+            } else {
+                FirstLocationUsage::WillUse
+            };
+        }
+
         match self.image {
             Image::Svg(svg) => Svg { data: svg }.first_location_usage(ctx),
             Image::Pixel(image) => {
@@ -27,6 +254,12 @@ impl<'a> Element for ImageElement<'a> {
     }
 
     fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        if self.transformed() {
+            let (_, size) = self.transformed_size(ctx.width);
+            ctx.break_if_appropriate_for_min_height(size.height.unwrap_or(0.));
+            return size;
+        }
+
         match self.image {
             Image::Svg(svg) => Svg { data: svg }.measure(ctx),
             Image::Pixel(image) => {
@@ -39,7 +272,131 @@ impl<'a> Element for ImageElement<'a> {
         }
     }
 
-    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        if meta::collecting() {
+            meta::Meta {
+                meta: &self.meta(),
+                element: &Plain(self),
+            }
+            .draw(ctx)
+        } else {
+            self.draw_tagged(ctx)
+        }
+    }
+}
+
+impl<'a> ImageElement<'a> {
+    fn draw_tagged(&self, ctx: DrawCtx) -> ElementSize {
+        if structure::collecting() {
+            structure::draw_tagged(ctx, self.role, self.alt, self.caption, |ctx| {
+                self.draw_image(ctx)
+            })
+        } else {
+            self.draw_image(ctx)
+        }
+    }
+
+    fn draw_image(&self, mut ctx: DrawCtx) -> ElementSize {
+        if !self.transformed() {
+            return self.draw_masked(ctx);
+        }
+
+        let ((width, height), size) = self.transformed_size(ctx.width);
+        let (box_width, box_height) = (size.width.unwrap_or(0.), size.height.unwrap_or(0.));
+
+        ctx.break_if_appropriate_for_min_height(box_height);
+
+        let (x, y) = ctx.location.pos;
+        let layer = ctx.location.layer.clone();
+        layer.save_graphics_state();
+
+        // The image is drawn centered on the origin, then mirrored, rotated and moved to the
+        // center of its bounding box. PDF rotations are counterclockwise.
+        layer.set_ctm(CurTransMat::Translate(
+            Mm(x + box_width / 2.),
+            Mm(y - box_height / 2.),
+        ));
+        layer.set_ctm(CurTransMat::Rotate(-self.rotation));
+
+        if self.mirror {
+            layer.set_ctm(CurTransMat::Scale(-1., 1.));
+        }
+
+        self.draw_masked(DrawCtx {
+            pdf: ctx.pdf,
+            location: Location {
+                layer: layer.clone(),
+                pos: (-width / 2., height / 2.),
+                ..ctx.location
+            },
+            width: WidthConstraint {
+                max: width,
+                expand: true,
+            },
+            first_height: height,
+            preferred_height: None,
+            breakable: None,
+        });
+
+        layer.restore_graphics_state();
+
+        size
+    }
+
+    fn draw_masked(&self, mut ctx: DrawCtx) -> ElementSize {
+        let Some(mask) = self.mask else {
+            return self.draw_unmasked(ctx);
+        };
+
+        let size = match self.image {
+            Image::Svg(svg) => Svg { data: svg }.measure(MeasureCtx {
+                width: ctx.width,
+                first_height: ctx.first_height,
+                breakable: None,
+            }),
+            Image::Pixel(image) => calculate_size(image, ctx.width).2,
+        };
+
+        let (width, height) = (size.width.unwrap_or(0.), size.height.unwrap_or(0.));
+
+        // The break has to happen before the clipping path is added.
+        ctx.break_if_appropriate_for_min_height(height);
+
+        let (x, y) = ctx.location.pos;
+        let rect = kurbo::Rect::new(
+            mm_to_pt(x),
+            mm_to_pt(y - height),
+            mm_to_pt(x + width),
+            mm_to_pt(y),
+        );
+
+        let layer = ctx.location.layer.clone();
+        layer.save_graphics_state();
+
+        match mask {
+            ImageMask::Circle => add_path(
+                &layer,
+                &kurbo::Circle::new(rect.center(), rect.width().min(rect.height()) / 2.),
+            ),
+            ImageMask::RoundedRect(radius) => {
+                add_path(&layer, &rect.to_rounded_rect(mm_to_pt(radius)))
+            }
+        };
+
+        add_op(&layer, lopdf::content::Operation::new("W", Vec::new()));
+        add_op(&layer, lopdf::content::Operation::new("n", Vec::new()));
+
+        let size = self.draw_unmasked(DrawCtx {
+            breakable: None,
+            ..ctx
+        });
+
+        layer.restore_graphics_state();
+
+        size
+    }
+
+    fn draw_unmasked(&self, mut ctx: DrawCtx) -> ElementSize {
         match self.image {
             Image::Svg(svg) => Svg { data: svg }.draw(ctx),
             Image::Pixel(image) => {
@@ -47,7 +404,19 @@ impl<'a> Element for ImageElement<'a> {
 
                 ctx.break_if_appropriate_for_min_height(height);
 
-                let image = printpdf::Image::from_dynamic_image(image);
+                // The image is converted and embedded again every time it's drawn.
+                let (width_px, height_px) = image.dimensions();
+                let bytes_per_pixel = image.color().bytes_per_pixel() as u64;
+                let memory = width_px as u64 * height_px as u64 * bytes_per_pixel;
+
+                if !crate::budget::add_image_memory(memory) {
+                    return element_size;
+                }
+
+                let image = match self.tint {
+                    Some(tint) => printpdf::Image::from_dynamic_image(&tint.apply(image)),
+                    None => printpdf::Image::from_dynamic_image(image),
+                };
 
                 image.add_to_layer(
                     ctx.location.layer,
@@ -86,3 +455,171 @@ fn calculate_size(image: &DynamicImage, width: WidthConstraint) -> (f64, f64, El
         },
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{elements::meta::collect_regions, test_utils::*};
+
+    #[test]
+    fn test_image_regions() {
+        let image = Image::Pixel(DynamicImage::new_rgb8(2, 1));
+
+        let element = ImageElement {
+            alt: Some("A red square"),
+            ..ImageElement::new(&image)
+        };
+
+        let (outputs, regions) = collect_regions(|| {
+            (ElementTestParams {
+                width: 10.,
+                first_height: 100.,
+                full_height: 100.,
+                ..Default::default()
+            })
+            .run(&element)
+            .collect::<Vec<_>>()
+        });
+
+        // Every configuration draws twice and the image is never broken.
+        assert_eq!(regions.len(), outputs.len() * 2);
+        assert!(regions.iter().all(|r| r.meta
+            == serde_json::json!({
+                "image": { "role": "Figure", "alt": "A red square", "caption": null },
+            })));
+    }
+
+    #[test]
+    fn test_image_mask() {
+        let image = Image::Pixel(DynamicImage::new_rgb8(2, 1));
+
+        for mask in [ImageMask::Circle, ImageMask::RoundedRect(2.)] {
+            let element = ImageElement {
+                mask: Some(mask),
+                ..ImageElement::new(&image)
+            };
+
+            // The image is 5mm high at a width of 10mm.
+            for output in (ElementTestParams {
+                width: 10.,
+                first_height: 4.,
+                full_height: 20.,
+                ..Default::default()
+            })
+            .run(&element)
+            {
+                output.assert_size(ElementSize {
+                    width: Some(10.),
+                    height: Some(5.),
+                });
+
+                if let Some(b) = output.breakable {
+                    b.assert_break_count(if output.first_height == 4. { 1 } else { 0 });
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_image_tint() {
+        let image = DynamicImage::ImageRgb8(ImageBuffer::from_fn(3, 1, |x, _| {
+            Rgb([[0, 0, 0], [255, 255, 255], [128, 128, 128]][x as usize])
+        }));
+
+        let tinted = ImageTint::new(0x00_66_CC_FF).apply(&image);
+        assert!(!tinted.color().has_alpha());
+
+        let tinted = tinted.to_rgb8();
+        assert_eq!(tinted.get_pixel(0, 0).0, [0x00, 0x66, 0xCC]);
+        assert_eq!(tinted.get_pixel(1, 0).0, [0xFF, 0xFF, 0xFF]);
+        assert_eq!(tinted.get_pixel(2, 0).0, [0x80, 0xB3, 0xE6]);
+
+        let duotone = ImageTint {
+            dark: 0x00_00_00_80,
+            light: 0xFF_00_00_FF,
+        }
+        .apply(&image)
+        .to_rgba8();
+
+        assert_eq!(duotone.get_pixel(0, 0).0, [0x00, 0x00, 0x00, 0x80]);
+        assert_eq!(duotone.get_pixel(1, 0).0, [0xFF, 0x00, 0x00, 0xFF]);
+
+        // Drawing the tinted image works like drawing the image.
+        let image = Image::Pixel(image);
+
+        let element = ImageElement {
+            tint: Some(ImageTint::new(0x00_66_CC_FF)),
+            ..ImageElement::new(&image)
+        };
+
+        for output in (ElementTestParams {
+            width: 9.,
+            ..Default::default()
+        })
+        .run(&element)
+        {
+            assert_eq!(output.size.width, Some(9.));
+            assert!((output.size.height.unwrap() - 3.).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_image_rotation() {
+        let image = Image::Pixel(DynamicImage::new_rgb8(2, 1));
+
+        let rotated = |rotation, mirror| ImageElement {
+            rotation,
+            mirror,
+            mask: Some(ImageMask::Circle),
+            ..ImageElement::new(&image)
+        };
+
+        for (rotation, mirror, height) in [
+            (90., false, 20.),
+            (-90., true, 20.),
+            (180., true, 5.),
+            (0., true, 5.),
+        ] {
+            for output in (ElementTestParams {
+                width: 10.,
+                first_height: 8.,
+                full_height: 30.,
+                ..Default::default()
+            })
+            .run(&rotated(rotation, mirror))
+            {
+                output.assert_size(ElementSize {
+                    width: Some(10.),
+                    height: Some(height),
+                });
+
+                if let Some(b) = output.breakable {
+                    b.assert_break_count(if height > 8. && output.first_height == 8. {
+                        1
+                    } else {
+                        0
+                    });
+                }
+            }
+        }
+
+        // The bounding box of a square turned by 45 degrees is a square again.
+        let square = Image::Pixel(DynamicImage::new_rgb8(1, 1));
+
+        let element = ImageElement {
+            rotation: 45.,
+            ..ImageElement::new(&square)
+        };
+
+        for output in (ElementTestParams {
+            width: 10.,
+            ..Default::default()
+        })
+        .run(&element)
+        {
+            let size = output.size;
+            assert_eq!(size.width, Some(10.));
+            assert!((size.height.unwrap() - 10.).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use crate::{
+    language::{begin_language, end_language},
+    *,
+};
+
+/// Draws the element in marked content with a `/Lang` property, so screen readers read its text in
+/// the language instead of the one of the document. See [crate::language].
+pub struct Lang<'a, E: Element> {
+    /// A language tag like `fr` or `de-CH`.
+    pub lang: &'a str,
+    pub element: &'a E,
+}
+
+impl<'a, E: Element> Element for Lang<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        // Marked content can't span content streams, so it's ended before every break and started
+        // again on the new location.
+        let mut layer = ctx.location.layer.clone();
+        begin_language(&layer, self.lang);
+
+        let size = if let Some(breakable) = ctx.breakable {
+            self.element.draw(DrawCtx {
+                breakable: Some(BreakableDraw {
+                    do_break: &mut |pdf, location_idx, height| {
+                        end_language(&layer);
+
+                        let location = (breakable.do_break)(pdf, location_idx, height);
+
+                        layer = location.layer.clone();
+                        begin_language(&layer, self.lang);
+
+                        location
+                    },
+                    ..breakable
+                }),
+                ..ctx
+            })
+        } else {
+            self.element.draw(ctx)
+        };
+
+        end_language(&layer);
+
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::Object;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_lang() {
+        let content = FakeText {
+            lines: 3,
+            line_height: 2.,
+            width: 5.,
+        };
+
+        let element = Lang {
+            lang: "fr",
+            element: &content,
+        };
+
+        let params = ElementTestParams {
+            first_height: 4.,
+            full_height: 5.,
+            ..Default::default()
+        };
+
+        for (output, expected) in params.run(&element).zip(params.run(&content)) {
+            assert_eq!(output.size, expected.size);
+            assert_eq!(
+                output.breakable.map(|b| b.break_count),
+                expected.breakable.map(|b| b.break_count),
+            );
+        }
+
+        // The text is drawn on two pages, which both get the language.
+        let content = FakeText {
+            lines: 4,
+            line_height: 2.,
+            width: 5.,
+        };
+
+        let document = build_pdf(
+            "test",
+            (10., 5.),
+            |_| (),
+            |_: &()| Lang {
+                lang: "fr",
+                element: &content,
+            },
+        );
+
+        let pages = page_operations(&save(document));
+        assert_eq!(pages.len(), 2);
+
+        for operations in pages {
+            let operators = operations
+                .iter()
+                .map(|o| &o.operator[..])
+                .filter(|o| *o == "BDC" || *o == "EMC")
+                .collect::<Vec<_>>();
+
+            assert_eq!(operators, ["BDC", "EMC"]);
+
+            let begin = operations.iter().find(|o| o.operator == "BDC").unwrap();
+            let properties = begin.operands[1].as_dict().unwrap();
+            assert!(matches!(properties.get(b"Lang"), Ok(Object::String(s, _)) if s == b"fr"));
+        }
+    }
+}
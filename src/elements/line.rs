@@ -14,6 +14,9 @@ impl Line {
                 color: 0x00_00_00_FF,
                 dash_pattern: None,
                 cap_style: LineCapStyle::Butt,
+                join_style: LineJoinStyle::Miter,
+                miter_limit: DEFAULT_MITER_LIMIT,
+                rendering: None,
             },
         }
     }
@@ -36,33 +39,34 @@ impl Element for Line {
             ctx.location.layer.set_outline_color(color);
             ctx.location
                 .layer
-                .set_outline_thickness(mm_to_pt(self.style.thickness));
+                .set_outline_thickness(self.style.stroke_width(ctx.pdf.line_rendering));
             ctx.location
                 .layer
                 .set_line_cap_style(self.style.cap_style.into());
-            ctx.location.layer.set_line_dash_pattern(
-                if let Some(pattern) = self.style.dash_pattern {
-                    pattern.into()
-                } else {
-                    printpdf::LineDashPattern::default()
-                },
+            set_line_join(&ctx.location.layer, &self.style);
+            set_line_dash_pattern(&ctx.location.layer, &self.style);
+
+            let line_y = self.style.snap_position(
+                ctx.pdf.line_rendering,
+                ctx.location.pos.1 - self.style.thickness / 2.0,
             );
 
-            let line_y = ctx.location.pos.1 - self.style.thickness / 2.0;
-
-            ctx.location.layer.add_shape(printpdf::Line {
-                points: vec![
-                    (Point::new(Mm(ctx.location.pos.0), Mm(line_y)), false),
-                    (
-                        Point::new(Mm(ctx.location.pos.0 + ctx.width.max), Mm(line_y)),
-                        false,
-                    ),
-                ],
-                is_closed: false,
-                has_fill: false,
-                has_stroke: true,
-                is_clipping_path: false,
-            });
+            add_shape(
+                &ctx.location.layer,
+                printpdf::Line {
+                    points: vec![
+                        (Point::new(Mm(ctx.location.pos.0), Mm(line_y)), false),
+                        (
+                            Point::new(Mm(ctx.location.pos.0 + ctx.width.max), Mm(line_y)),
+                            false,
+                        ),
+                    ],
+                    is_closed: false,
+                    has_fill: false,
+                    has_stroke: true,
+                    is_clipping_path: false,
+                },
+            );
 
             ctx.location.layer.restore_graphics_state();
         }
@@ -95,6 +99,9 @@ mod tests {
                 color: 0,
                 dash_pattern: None,
                 cap_style: LineCapStyle::Butt,
+                join_style: LineJoinStyle::Miter,
+                miter_limit: DEFAULT_MITER_LIMIT,
+                rendering: None,
             },
         }) {
             output.assert_size(ElementSize {
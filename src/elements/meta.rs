@@ -0,0 +1,150 @@
+use std::cell::RefCell;
+
+use serde::Serialize;
+
+use crate::{utils::scoped, *};
+
+/// The area an element with [Meta] was drawn in on one page.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Region {
+    pub meta: serde_json::Value,
+
+    /// The index of the page in the document, starting at 0.
+    pub page: usize,
+
+    /// The top left corner in millimeters from the bottom left of the page, like [Location::pos].
+    pub pos: (f64, f64),
+
+    pub size: (f64, f64),
+}
+
+thread_local! {
+    static REGIONS: RefCell<Option<Vec<Region>>> = const { RefCell::new(None) };
+}
+
+/// Collects the regions of all [Meta] elements drawn on this thread while `f` is running, e.g. for
+/// post-processors that need to find the total of an invoice in the finished PDF.
+pub fn collect_regions<R>(f: impl FnOnce() -> R) -> (R, Vec<Region>) {
+    let (ret, regions) = scoped(&REGIONS, Some(Vec::new()), f);
+    (ret, regions.unwrap_or_default())
+}
+
+/// Whether regions are being collected, so elements can skip computing their metadata otherwise.
+pub(crate) fn collecting() -> bool {
+    REGIONS.with(|regions| regions.borrow().is_some())
+}
+
+/// Attaches arbitrary metadata to an element, which ends up in the [Region]s collected by
+/// [collect_regions]. There's one region for each location the element is drawn on. Positions are
+/// only correct outside of scaling elements like [ShrinkToFit](super::shrink_to_fit::ShrinkToFit).
+pub struct Meta<'a, E: Element> {
+    pub meta: &'a serde_json::Value,
+    pub element: &'a E,
+}
+
+impl<'a, E: Element> Element for Meta<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        if !collecting() {
+            return self.element.draw(ctx);
+        }
+
+        // The location and the height used on it for every location the element was drawn on.
+        let mut locations = vec![(ctx.location.clone(), None)];
+
+        let size = if let Some(breakable) = ctx.breakable {
+            self.element.draw(DrawCtx {
+                breakable: Some(BreakableDraw {
+                    do_break: &mut |pdf, location_idx, height| {
+                        let location = (breakable.do_break)(pdf, location_idx, height);
+
+                        let idx = location_idx as usize;
+
+                        if idx + 1 >= locations.len() {
+                            // Skipped locations don't get a region, so their location doesn't
+                            // matter.
+                            while locations.len() <= idx {
+                                locations.push((location.clone(), None));
+                            }
+
+                            locations[idx].1 = height;
+                            locations.push((location.clone(), None));
+                        }
+
+                        location
+                    },
+                    ..breakable
+                }),
+                ..ctx
+            })
+        } else {
+            self.element.draw(ctx)
+        };
+
+        if let Some(last) = locations.last_mut() {
+            last.1 = size.height;
+        }
+
+        let width = size.width.unwrap_or(0.);
+
+        REGIONS.with(|regions| {
+            if let Some(regions) = regions.borrow_mut().as_mut() {
+                for (location, height) in locations {
+                    if let Some(height) = height {
+                        regions.push(Region {
+                            meta: self.meta.clone(),
+                            page: location.layer.page.0,
+                            pos: location.pos,
+                            size: (width, height),
+                        });
+                    }
+                }
+            }
+        });
+
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_meta() {
+        let meta = serde_json::json!({ "field": "total" });
+
+        let element = Meta {
+            meta: &meta,
+            element: &FakeText {
+                lines: 3,
+                line_height: 2.,
+                width: 5.,
+            },
+        };
+
+        let (outputs, regions) = collect_regions(|| {
+            (ElementTestParams {
+                first_height: 4.,
+                full_height: 5.,
+                ..Default::default()
+            })
+            .run(&element)
+            .collect::<Vec<_>>()
+        });
+
+        // Every configuration draws twice. The breakable ones take two locations each.
+        let breakable = outputs.iter().filter(|o| o.breakable.is_some()).count();
+        assert_eq!(regions.len(), (outputs.len() + breakable) * 2);
+
+        assert!(regions.iter().all(|r| r.meta == meta));
+    }
+}
@@ -0,0 +1,139 @@
+use crate::*;
+
+/// Starts the element on the next location if less than [Self::min] is left on the first one, even
+/// if the element would fit there. [MinFirstHeight](super::min_first_height::MinFirstHeight) only
+/// does that for elements that would break.
+pub struct MinBreakHeight<'a, E: Element> {
+    pub element: &'a E,
+    pub min: f64,
+}
+
+impl<'a, E: Element> Element for MinBreakHeight<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        if self.pre_break(ctx.width, ctx.first_height, ctx.full_height) {
+            FirstLocationUsage::WillSkip
+        } else {
+            self.element.first_location_usage(ctx)
+        }
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        match ctx.breakable {
+            Some(breakable)
+                if self.pre_break(ctx.width, ctx.first_height, breakable.full_height) =>
+            {
+                let size = self.element.measure(MeasureCtx {
+                    width: ctx.width,
+                    first_height: breakable.full_height,
+                    breakable: Some(BreakableMeasure {
+                        full_height: breakable.full_height,
+                        break_count: breakable.break_count,
+                        extra_location_min_height: breakable.extra_location_min_height,
+                    }),
+                });
+
+                *breakable.break_count += 1;
+                size
+            }
+            breakable => self.element.measure(MeasureCtx { breakable, ..ctx }),
+        }
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        match ctx.breakable {
+            Some(breakable)
+                if self.pre_break(ctx.width, ctx.first_height, breakable.full_height) =>
+            {
+                let location = (breakable.do_break)(ctx.pdf, 0, None);
+
+                self.element.draw(DrawCtx {
+                    pdf: ctx.pdf,
+                    location,
+                    width: ctx.width,
+                    first_height: breakable.full_height,
+                    preferred_height: if breakable.preferred_height_break_count == 0 {
+                        None
+                    } else {
+                        ctx.preferred_height
+                    },
+                    breakable: Some(BreakableDraw {
+                        full_height: breakable.full_height,
+                        preferred_height_break_count: breakable
+                            .preferred_height_break_count
+                            .saturating_sub(1),
+                        do_break: &mut |pdf, location_idx, height| {
+                            (breakable.do_break)(pdf, location_idx + 1, height)
+                        },
+                    }),
+                })
+            }
+            breakable => self.element.draw(DrawCtx { breakable, ..ctx }),
+        }
+    }
+}
+
+impl<'a, E: Element> MinBreakHeight<'a, E> {
+    /// Elements without height don't need any space, so they don't cause a break.
+    fn pre_break(&self, width: WidthConstraint, first_height: f64, full_height: f64) -> bool {
+        first_height < self.min
+            && first_height < full_height
+            && self.element.first_location_usage(FirstLocationUsageCtx {
+                width,
+                first_height: full_height,
+                full_height,
+            }) != FirstLocationUsage::NoneHeight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{elements::none::NoneElement, test_utils::*};
+
+    #[test]
+    fn test_min_break_height() {
+        // It would fit into the first location.
+        let text = FakeText {
+            lines: 1,
+            line_height: 5.,
+            width: 3.,
+        };
+
+        let element = MinBreakHeight {
+            element: &text,
+            min: 10.,
+        };
+
+        for output in (ElementTestParams {
+            first_height: 9.,
+            full_height: 15.,
+            ..Default::default()
+        })
+        .run(&element)
+        {
+            output.assert_size(ElementSize {
+                width: Some(output.width.constrain(3.)),
+                height: Some(5.),
+            });
+
+            if let Some(b) = output.breakable {
+                b.assert_break_count(if output.first_height == 9. { 1 } else { 0 });
+            }
+        }
+
+        let element = MinBreakHeight {
+            element: &NoneElement,
+            min: 10.,
+        };
+
+        for output in (ElementTestParams {
+            first_height: 9.,
+            full_height: 15.,
+            ..Default::default()
+        })
+        .run(&element)
+        {
+            output.assert_no_breaks();
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use crate::*;
+
+/// How close the height of balanced columns gets to the lowest one the content fits into, in mm.
+const BALANCE_PRECISION: f64 = 0.01;
+
+/// Flows the element through side by side columns of equal width, like a newspaper. Every column
+/// is a location for the element, so it breaks from one column into the next and from the last
+/// column of a location into the first one of the next location.
+///
+/// All columns of a location have the same height. If the content doesn't fit into the columns of
+/// the first location and the first location is lower than the others, the columns start on the
+/// next location.
+pub struct MultiColumn<'a, E: Element> {
+    pub element: &'a E,
+    pub columns: u32,
+    pub gap: f64,
+
+    /// Makes the columns about as high as each other instead of filling them one after the other,
+    /// e.g. for signature blocks side by side. This is only done if the content fits into the
+    /// columns of one location.
+    pub balance: bool,
+}
+
+impl<'a, E: Element> Element for MultiColumn<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        let layout = self.layout(ctx.width, ctx.first_height, Some(ctx.full_height));
+
+        if layout.pre_break {
+            FirstLocationUsage::WillSkip
+        } else if layout.columns_used == 1 && layout.last_height.is_none() {
+            FirstLocationUsage::NoneHeight
+        } else {
+            FirstLocationUsage::WillUse
+        }
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        let layout = self.layout(
+            ctx.width,
+            ctx.first_height,
+            ctx.breakable.as_ref().map(|b| b.full_height),
+        );
+
+        if let Some(b) = ctx.breakable {
+            *b.break_count = layout.pre_break as u32 + (layout.columns_used - 1) / self.count();
+        }
+
+        self.size(&layout, ctx.width)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let layout = self.layout(
+            ctx.width,
+            ctx.first_height,
+            ctx.breakable.as_ref().map(|b| b.full_height),
+        );
+
+        let count = self.count();
+        let column_width = self.column_width(ctx.width);
+        let column_height = layout.column_height;
+
+        let in_column = |top: &Location, column: u32| Location {
+            pos: (
+                top.pos.0 + (column_width.max + self.gap) * column as f64,
+                top.pos.1,
+            ),
+            ..top.clone()
+        };
+
+        if let Some(breakable) = ctx.breakable {
+            let pre_break = layout.pre_break as u32;
+
+            let first = if layout.pre_break {
+                (breakable.do_break)(ctx.pdf, 0, None)
+            } else {
+                ctx.location
+            };
+
+            self.element.draw(DrawCtx {
+                pdf: ctx.pdf,
+                location: first.clone(),
+                width: column_width,
+                first_height: column_height,
+                preferred_height: None,
+                breakable: Some(BreakableDraw {
+                    full_height: column_height,
+                    preferred_height_break_count: 0,
+                    do_break: &mut |pdf, location_idx, height| {
+                        let next = location_idx + 1;
+                        let (location, column) = (next / count, next % count);
+
+                        let top = if location == 0 {
+                            first.clone()
+                        } else {
+                            // A location is left after its last column, which only has the whole
+                            // height of the location if it's the only one.
+                            let height = if count == 1 {
+                                height
+                            } else {
+                                Some(column_height)
+                            };
+
+                            (breakable.do_break)(pdf, pre_break + location - 1, height)
+                        };
+
+                        in_column(&top, column)
+                    },
+                }),
+            });
+        } else {
+            let first = ctx.location.clone();
+
+            // Without breaking, the content only doesn't fit if it breaks by itself. What doesn't
+            // fit is drawn into the last column.
+            self.element.draw(DrawCtx {
+                pdf: ctx.pdf,
+                location: ctx.location,
+                width: column_width,
+                first_height: column_height,
+                preferred_height: None,
+                breakable: Some(BreakableDraw {
+                    full_height: column_height,
+                    preferred_height_break_count: 0,
+                    do_break: &mut |_, location_idx, _| {
+                        in_column(&first, (location_idx + 1).min(count - 1))
+                    },
+                }),
+            });
+        }
+
+        self.size(&layout, ctx.width)
+    }
+}
+
+struct Layout {
+    /// Whether the columns start on the next location.
+    pre_break: bool,
+
+    /// The height of the columns on every location.
+    column_height: f64,
+
+    /// Across all locations.
+    columns_used: u32,
+
+    /// The size of the content in the last column used.
+    last_width: Option<f64>,
+    last_height: Option<f64>,
+}
+
+impl<'a, E: Element> MultiColumn<'a, E> {
+    fn count(&self) -> u32 {
+        self.columns.max(1)
+    }
+
+    fn column_width(&self, width: WidthConstraint) -> WidthConstraint {
+        let count = self.count() as f64;
+
+        WidthConstraint {
+            max: ((width.max - self.gap * (count - 1.)) / count).max(0.),
+            expand: width.expand,
+        }
+    }
+
+    fn layout(
+        &self,
+        width: WidthConstraint,
+        first_height: f64,
+        full_height: Option<f64>,
+    ) -> Layout {
+        let fits = |layout: &Layout| layout.columns_used <= self.count();
+
+        let balance = |layout: Layout| {
+            if self.balance && fits(&layout) {
+                self.balanced(width, layout)
+            } else {
+                layout
+            }
+        };
+
+        let layout = self.layout_columns(width, first_height);
+
+        if fits(&layout) {
+            balance(layout)
+        } else if let Some(full_height) = full_height {
+            Layout {
+                pre_break: first_height < full_height,
+                ..balance(self.layout_columns(width, full_height))
+            }
+        } else {
+            // Without breaking the content has to fit into the columns somehow, which it does at
+            // the height of the content in a single column.
+            let size = self.element.measure(MeasureCtx {
+                width: self.column_width(width),
+                first_height,
+                breakable: None,
+            });
+
+            self.balanced(width, self.layout_columns(width, size.height.unwrap_or(0.)))
+        }
+    }
+
+    /// Finds the lowest column height the content still fits into the columns at, starting from
+    /// the layout at a height it fits at.
+    fn balanced(&self, width: WidthConstraint, mut layout: Layout) -> Layout {
+        let mut low = 0.;
+
+        while layout.column_height - low > BALANCE_PRECISION {
+            let height = (low + layout.column_height) / 2.;
+            let attempt = self.layout_columns(width, height);
+
+            if attempt.columns_used <= self.count() {
+                layout = attempt;
+            } else {
+                low = height;
+            }
+        }
+
+        layout
+    }
+
+    fn layout_columns(&self, width: WidthConstraint, column_height: f64) -> Layout {
+        let mut break_count = 0;
+        let mut extra_location_min_height = None;
+
+        let size = self.element.measure(MeasureCtx {
+            width: self.column_width(width),
+            first_height: column_height,
+            breakable: Some(BreakableMeasure {
+                full_height: column_height,
+                break_count: &mut break_count,
+                extra_location_min_height: &mut extra_location_min_height,
+            }),
+        });
+
+        Layout {
+            pre_break: false,
+            column_height,
+            columns_used: break_count + 1,
+            last_width: size.width,
+            last_height: size.height,
+        }
+    }
+
+    fn size(&self, layout: &Layout, width: WidthConstraint) -> ElementSize {
+        let columns_on_last = (layout.columns_used - 1) % self.count() + 1;
+
+        ElementSize {
+            width: if width.expand || layout.columns_used > 1 {
+                Some(width.max)
+            } else {
+                layout.last_width
+            },
+
+            // The columns before the last one are as high as the location as far as the element
+            // is concerned.
+            height: if columns_on_last > 1 {
+                Some(layout.column_height.max(layout.last_height.unwrap_or(0.)))
+            } else {
+                layout.last_height
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_multi_column() {
+        let text = FakeText {
+            lines: 7,
+            line_height: 2.,
+            width: 3.,
+        };
+
+        let element = |balance| MultiColumn {
+            element: &text,
+            columns: 2,
+            gap: 2.,
+            balance,
+        };
+
+        let params = ElementTestParams {
+            first_height: 6.,
+            full_height: 10.,
+            width: 12.,
+            ..Default::default()
+        };
+
+        let assert_height = |output: &ElementMeasureDrawCompatibilityOutput, height: f64| {
+            assert_eq!(output.size.width, Some(12.));
+            assert!((output.size.height.unwrap() - height).abs() <= BALANCE_PRECISION);
+        };
+
+        // The first column is filled before the second one.
+        for output in params.run(&element(false)) {
+            let (height, break_count) = match output.breakable {
+                // Without breaking, content that doesn't fit is balanced, here 4 and 3 lines.
+                None if output.first_height == 6. => (8., 0),
+                None => (10., 0),
+
+                // The 7 lines don't fit into two columns of 6mm, so they start on the next
+                // location.
+                Some(_) if output.first_height == 6. => (10., 1),
+                Some(_) => (10., 0),
+            };
+
+            assert_height(&output, height);
+
+            if let Some(b) = output.breakable {
+                b.assert_break_count(break_count);
+            }
+        }
+
+        // Balanced, the 7 lines are always split 4 and 3.
+        for output in params.run(&element(true)) {
+            assert_height(&output, 8.);
+
+            if let Some(b) = output.breakable {
+                b.assert_break_count(if output.first_height == 6. { 1 } else { 0 });
+            }
+        }
+    }
+}
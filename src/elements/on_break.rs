@@ -0,0 +1,90 @@
+use crate::*;
+
+/// Calls `on_break` whenever the element breaks to a new location, with the index of the break
+/// (starting at 0) and the new location. This is only done while drawing and only once per
+/// location, even if the element requests a location multiple times. Useful for counters,
+/// markers or logging without having to reimplement the breaking logic.
+pub struct OnBreak<'a, E: Element, F: Fn(&mut Pdf, u32, &Location)> {
+    pub element: &'a E,
+    pub on_break: F,
+}
+
+impl<'a, E: Element, F: Fn(&mut Pdf, u32, &Location)> Element for OnBreak<'a, E, F> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        if let Some(breakable) = ctx.breakable {
+            let mut break_count = 0;
+
+            self.element.draw(DrawCtx {
+                breakable: Some(BreakableDraw {
+                    do_break: &mut |pdf, location_idx, height| {
+                        let location = (breakable.do_break)(pdf, location_idx, height);
+
+                        if location_idx >= break_count {
+                            (self.on_break)(pdf, location_idx, &location);
+                            break_count = location_idx + 1;
+                        }
+
+                        location
+                    },
+                    ..breakable
+                }),
+                ..ctx
+            })
+        } else {
+            self.element.draw(ctx)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_on_break() {
+        let content = FakeText {
+            lines: 10,
+            line_height: 1.,
+            width: 5.,
+        };
+
+        let breaks = RefCell::new(Vec::new());
+
+        let element = OnBreak {
+            element: &content,
+            on_break: |_: &mut Pdf, index, _: &Location| breaks.borrow_mut().push(index),
+        };
+
+        for output in (ElementTestParams {
+            first_height: 3.,
+            full_height: 4.,
+            ..Default::default()
+        })
+        .run(&element)
+        {
+            // Measure doesn't call the callback and there are two draw passes per configuration.
+            let expected: &[u32] = if output.breakable.is_some() {
+                &[0, 1, 0, 1]
+            } else {
+                &[]
+            };
+
+            assert_eq!(breaks.take(), expected);
+
+            if let Some(b) = output.breakable {
+                b.assert_break_count(2);
+            }
+        }
+    }
+}
@@ -103,15 +103,16 @@ impl<'a, E: Element> Element for Padding<'a, E> {
 }
 
 impl<'a, E: Element> Padding<'a, E> {
+    // Padding that's larger than the available space leaves nothing, not a negative size.
     fn width(&self, constraint: WidthConstraint) -> WidthConstraint {
         WidthConstraint {
-            max: constraint.max - self.left - self.right,
+            max: (constraint.max - self.left - self.right).max(0.),
             expand: constraint.expand,
         }
     }
 
     fn height(&self, input: f64) -> f64 {
-        input - self.top - self.bottom
+        (input - self.top - self.bottom).max(0.)
     }
 
     fn size(&self, size: ElementSize) -> ElementSize {
@@ -124,6 +125,8 @@ impl<'a, E: Element> Padding<'a, E> {
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use super::*;
     use crate::test_utils::*;
 
@@ -189,4 +192,46 @@ mod tests {
             }
         }
     }
+
+    proptest! {
+        #[test]
+        fn test_padding_random_sizes(
+            lines in 0u32..20,
+            line_height in 0.5..5.5,
+            content_width in 0.0..100.,
+            left in 0.0..50.,
+            right in 0.0..50.,
+            top in 0.0..50.,
+            bottom in 0.0..50.,
+            width in 0.0..100.,
+            first_height in 0.0..100.,
+            full_height in 0.0..100.,
+        ) {
+            let content = FakeText {
+                lines,
+                line_height,
+                width: content_width,
+            };
+
+            let element = Padding {
+                left,
+                right,
+                top,
+                bottom,
+                element: &content,
+            };
+
+            let params = ElementTestParams {
+                width,
+                first_height,
+                full_height,
+                ..Default::default()
+            };
+
+            for output in params.run(&element) {
+                assert!(output.size.width.unwrap() >= left + right);
+                assert!(output.size.height.unwrap() >= top + bottom);
+            }
+        }
+    }
 }
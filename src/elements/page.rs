@@ -1,4 +1,4 @@
-use crate::*;
+use crate::{utils::add_shape, *};
 
 pub struct Page<'a, P: Element, D: Fn(&mut DecorationElements, usize, usize)> {
     pub primary: &'a P,
@@ -6,9 +6,39 @@ pub struct Page<'a, P: Element, D: Fn(&mut DecorationElements, usize, usize)> {
     pub border_right: f64,
     pub border_top: f64,
     pub border_bottom: f64,
+
+    /// Drawn at the top of the content area on every page. Unlike the decoration elements the
+    /// header takes up space: the primary content starts below it and gets that much less height.
+    pub header: Option<&'a dyn Element>,
+
+    /// Overrides for the first page, e.g. for a cover page. The decoration elements get the page
+    /// index, so they can be left out or replaced on the first page by checking for 0.
+    pub first_page: Option<FirstPage<'a>>,
+
+    /// Swaps the left and right borders on every other page for duplex printing. The first page is
+    /// a right-hand page, so the left border is the inner one there.
+    pub mirror: bool,
+
+    /// Fills the whole area of the page, including the borders, below everything else. Since the
+    /// page usually covers the entire media box this gives a full-bleed background.
+    pub background: Option<u32>,
+
+    /// Called for every page with the index of the page and the number of pages. See
+    /// [PageNumbering](crate::numbering::PageNumbering) for turning the index into a page number.
     pub decoration_elements: D,
 }
 
+pub struct FirstPage<'a> {
+    pub border_top: f64,
+    pub border_bottom: f64,
+
+    /// Replaces [Page::header] on the first page.
+    pub header: Option<&'a dyn Element>,
+
+    /// Leaves out the page number on the first page, see [DecorationElements::shows_page_number].
+    pub hide_page_number: bool,
+}
+
 impl<'a, P: Element, D: Fn(&mut DecorationElements, usize, usize)> Element for Page<'a, P, D> {
     fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
         if ctx.first_height < ctx.full_height {
@@ -23,16 +53,16 @@ impl<'a, P: Element, D: Fn(&mut DecorationElements, usize, usize)> Element for P
             let mut extra_location_min_height = None;
             let mut break_count = 0;
 
-            let primary_height = self.height(breakable.full_height);
+            let primary_width = WidthConstraint {
+                max: self.width(ctx.width),
+                expand: true,
+            };
 
             self.primary.measure(MeasureCtx {
-                width: WidthConstraint {
-                    max: self.width(ctx.width),
-                    expand: true,
-                },
-                first_height: primary_height,
+                width: primary_width,
+                first_height: self.primary_height(0, primary_width, breakable.full_height),
                 breakable: Some(BreakableMeasure {
-                    full_height: primary_height,
+                    full_height: self.primary_height(1, primary_width, breakable.full_height),
                     break_count: &mut break_count,
                     extra_location_min_height: &mut extra_location_min_height,
                 }),
@@ -69,7 +99,8 @@ impl<'a, P: Element, D: Fn(&mut DecorationElements, usize, usize)> Element for P
             .map(|b| b.full_height)
             .unwrap_or(ctx.first_height);
 
-        let primary_height = self.height(height);
+        let first_top = self.content_top(0, primary_width, height);
+        let top = self.content_top(1, primary_width, height);
 
         let location;
         let location_offset;
@@ -95,13 +126,13 @@ impl<'a, P: Element, D: Fn(&mut DecorationElements, usize, usize)> Element for P
             pdf: ctx.pdf,
             location: Location {
                 pos: (
-                    location.pos.0 + self.border_left,
-                    location.pos.1 - self.border_top,
+                    location.pos.0 + self.border_left(0),
+                    location.pos.1 - first_top,
                 ),
                 ..primary_location
             },
             width: primary_width,
-            first_height: primary_height,
+            first_height: self.primary_height(0, primary_width, height),
             preferred_height: None,
             breakable: breakable
                 .as_mut()
@@ -115,15 +146,15 @@ impl<'a, P: Element, D: Fn(&mut DecorationElements, usize, usize)> Element for P
                         );
 
                         location = location.next_layer(pdf);
-                        location.pos.0 += self.border_left;
-                        location.pos.1 -= self.border_top;
+                        location.pos.0 += self.border_left(location_idx as usize + 1);
+                        location.pos.1 -= top;
 
                         location
                     }
                 })
                 .as_mut()
                 .map(|get_location| BreakableDraw {
-                    full_height: primary_height,
+                    full_height: self.primary_height(1, primary_width, height),
                     preferred_height_break_count: 0,
                     do_break: get_location,
                 }),
@@ -141,24 +172,38 @@ impl<'a, P: Element, D: Fn(&mut DecorationElements, usize, usize)> Element for P
                     )
                 };
 
+                let page = i as usize;
+
+                self.draw_background(&location, ctx.width.max, height);
+                self.draw_header(page, ctx.pdf, &location, primary_width, height);
+
                 (self.decoration_elements)(
                     &mut DecorationElements {
                         pdf: ctx.pdf,
                         location,
                         width: ctx.width.max,
                         height,
+                        borders: self.borders(page),
+                        mirrored: self.mirrored(page),
+                        page_number: self.shows_page_number(page),
                     },
-                    i as usize,
+                    page,
                     (break_count + 1) as usize,
                 );
             }
         } else {
+            self.draw_background(&location, ctx.width.max, height);
+            self.draw_header(0, ctx.pdf, &location, primary_width, height);
+
             (self.decoration_elements)(
                 &mut DecorationElements {
                     pdf: ctx.pdf,
                     location,
                     width: ctx.width.max,
                     height,
+                    borders: self.borders(0),
+                    mirrored: false,
+                    page_number: self.shows_page_number(0),
                 },
                 0,
                 1,
@@ -177,8 +222,144 @@ impl<'a, P: Element, D: Fn(&mut DecorationElements, usize, usize)> Page<'a, P, D
         width.max - self.border_left - self.border_right
     }
 
-    fn height(&self, full_height: f64) -> f64 {
-        full_height - self.border_top - self.border_bottom
+    fn mirrored(&self, page: usize) -> bool {
+        self.mirror && page % 2 == 1
+    }
+
+    fn border_left(&self, page: usize) -> f64 {
+        if self.mirrored(page) {
+            self.border_right
+        } else {
+            self.border_left
+        }
+    }
+
+    fn border_right(&self, page: usize) -> f64 {
+        if self.mirrored(page) {
+            self.border_left
+        } else {
+            self.border_right
+        }
+    }
+
+    fn border_top(&self, page: usize) -> f64 {
+        match self.first_page {
+            Some(ref first_page) if page == 0 => first_page.border_top,
+            _ => self.border_top,
+        }
+    }
+
+    fn border_bottom(&self, page: usize) -> f64 {
+        match self.first_page {
+            Some(ref first_page) if page == 0 => first_page.border_bottom,
+            _ => self.border_bottom,
+        }
+    }
+
+    fn shows_page_number(&self, page: usize) -> bool {
+        match self.first_page {
+            Some(ref first_page) if page == 0 => !first_page.hide_page_number,
+            _ => true,
+        }
+    }
+
+    fn header(&self, page: usize) -> Option<&'a dyn Element> {
+        match self.first_page {
+            Some(ref first_page) if page == 0 => first_page.header,
+            _ => self.header,
+        }
+    }
+
+    /// The height between the borders.
+    fn height(&self, page: usize, full_height: f64) -> f64 {
+        full_height - self.border_top(page) - self.border_bottom(page)
+    }
+
+    fn header_height(&self, page: usize, width: WidthConstraint, full_height: f64) -> f64 {
+        self.header(page)
+            .and_then(|header| {
+                header
+                    .measure(MeasureCtx {
+                        width,
+                        first_height: self.height(page, full_height),
+                        breakable: None,
+                    })
+                    .height
+            })
+            .unwrap_or(0.)
+    }
+
+    /// The offset of the primary content from the top of the page.
+    fn content_top(&self, page: usize, width: WidthConstraint, full_height: f64) -> f64 {
+        self.border_top(page) + self.header_height(page, width, full_height)
+    }
+
+    fn primary_height(&self, page: usize, width: WidthConstraint, full_height: f64) -> f64 {
+        self.height(page, full_height) - self.header_height(page, width, full_height)
+    }
+
+    fn draw_header(
+        &self,
+        page: usize,
+        pdf: &mut Pdf,
+        location: &Location,
+        width: WidthConstraint,
+        height: f64,
+    ) {
+        if let Some(header) = self.header(page) {
+            header.draw(DrawCtx {
+                pdf,
+                location: Location {
+                    pos: (
+                        location.pos.0 + self.border_left(page),
+                        location.pos.1 - self.border_top(page),
+                    ),
+                    ..location.clone()
+                },
+                width,
+                first_height: self.height(page, height),
+                preferred_height: None,
+                breakable: None,
+            });
+        }
+    }
+
+    fn draw_background(&self, location: &Location, width: f64, height: f64) {
+        use printpdf::{utils::calculate_points_for_rect, Line};
+
+        if let Some(color) = self.background {
+            let layer = &location.layer;
+            let (color, alpha) = crate::utils::u32_to_color_and_alpha(color);
+
+            layer.save_graphics_state();
+            layer.set_fill_color(color);
+            layer.set_fill_alpha(alpha);
+            add_shape(
+                layer,
+                Line {
+                    points: calculate_points_for_rect(
+                        Mm(width),
+                        Mm(height),
+                        Mm(location.pos.0 + width / 2.),
+                        Mm(location.pos.1 - height / 2.),
+                    ),
+                    is_closed: true,
+                    has_fill: true,
+                    has_stroke: false,
+                    is_clipping_path: false,
+                },
+            );
+            layer.restore_graphics_state();
+        }
+    }
+
+    fn borders(&self, page: usize) -> [f64; 4] {
+        [
+            self.border_left(page),
+            self.border_right(page),
+            self.border_top(page),
+            self.border_bottom(page),
+        ]
     }
 }
 
@@ -187,12 +368,25 @@ pub struct DecorationElements<'a> {
     location: Location,
     width: f64,
     height: f64,
+
+    /// left, right, top, bottom
+    borders: [f64; 4],
+
+    mirrored: bool,
+    page_number: bool,
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub enum X {
     Left(f64),
     Right(f64),
+
+    /// The binding edge, so left on right-hand pages and right on left-hand pages of a
+    /// [mirrored](Page::mirror) page.
+    Inside(f64),
+
+    /// The edge opposite of the binding, e.g. for page numbers.
+    Outside(f64),
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
@@ -201,16 +395,81 @@ pub enum Y {
     Bottom(f64),
 }
 
+/// The margin boxes of CSS paged media. The boxes along an edge split the length of the content
+/// area between them evenly and the corner boxes fill the corners between the borders. As in CSS,
+/// the content is centered vertically in the top and bottom boxes and horizontally aligned
+/// towards the content area in the left and right boxes.
+///
+/// The boxes are named for right-hand pages. On the left-hand pages of a [mirrored](Page::mirror)
+/// page they're mirrored too, so a page number in [MarginBox::BottomRight] stays on the outside.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MarginBox {
+    TopLeftCorner,
+    TopLeft,
+    TopCenter,
+    TopRight,
+    TopRightCorner,
+    RightTop,
+    RightMiddle,
+    RightBottom,
+    BottomRightCorner,
+    BottomRight,
+    BottomCenter,
+    BottomLeft,
+    BottomLeftCorner,
+    LeftBottom,
+    LeftMiddle,
+    LeftTop,
+}
+
+impl MarginBox {
+    /// The box on the other side of the page.
+    pub fn mirrored(self) -> Self {
+        use MarginBox::*;
+
+        match self {
+            TopLeftCorner => TopRightCorner,
+            TopLeft => TopRight,
+            TopCenter => TopCenter,
+            TopRight => TopLeft,
+            TopRightCorner => TopLeftCorner,
+            RightTop => LeftTop,
+            RightMiddle => LeftMiddle,
+            RightBottom => LeftBottom,
+            BottomRightCorner => BottomLeftCorner,
+            BottomRight => BottomLeft,
+            BottomCenter => BottomCenter,
+            BottomLeft => BottomRight,
+            BottomLeftCorner => BottomRightCorner,
+            LeftBottom => RightBottom,
+            LeftMiddle => RightMiddle,
+            LeftTop => RightTop,
+        }
+    }
+}
+
 impl<'a> DecorationElements<'a> {
+    /// Whether the page number should be drawn on this page. It's left out on a first page that
+    /// [hides it](FirstPage::hide_page_number).
+    pub fn shows_page_number(&self) -> bool {
+        self.page_number
+    }
+
     pub fn add(&mut self, element: &impl Element, pos: (X, Y), width: Option<f64>) {
+        let (from_left, x) = match (pos.0, self.mirrored) {
+            (X::Left(x), _) | (X::Inside(x), false) | (X::Outside(x), true) => (true, x),
+            (X::Right(x), _) | (X::Inside(x), true) | (X::Outside(x), false) => (false, x),
+        };
+
         element.draw(DrawCtx {
             pdf: self.pdf,
             location: Location {
                 layer: self.location.layer.clone(),
                 pos: (
-                    match pos.0 {
-                        X::Left(left) => self.location.pos.0 + left,
-                        X::Right(right) => self.location.pos.0 + self.width - right,
+                    if from_left {
+                        self.location.pos.0 + x
+                    } else {
+                        self.location.pos.0 + self.width - x
                     },
                     match pos.1 {
                         Y::Top(top) => self.location.pos.1 - top,
@@ -220,10 +479,7 @@ impl<'a> DecorationElements<'a> {
                 ..self.location
             },
             width: WidthConstraint {
-                max: width.unwrap_or_else(|| match pos.0 {
-                    X::Left(left) => self.width - left,
-                    X::Right(right) => right,
-                }),
+                max: width.unwrap_or(if from_left { self.width - x } else { x }),
                 expand: width.is_some(),
             },
             first_height: match pos.1 {
@@ -234,6 +490,133 @@ impl<'a> DecorationElements<'a> {
             breakable: None,
         });
     }
+
+    /// Draws the element into one of the [margin boxes](MarginBox). Elements that don't fit into
+    /// the box overflow it.
+    pub fn add_to_margin_box(&mut self, element: &impl Element, margin_box: MarginBox) {
+        use MarginBox::*;
+
+        let margin_box = if self.mirrored {
+            margin_box.mirrored()
+        } else {
+            margin_box
+        };
+
+        let [left, right, top, bottom] = self.borders;
+        let content_width = (self.width - left - right) / 3.;
+        let content_height = (self.height - top - bottom) / 3.;
+
+        let right_x = self.width - right;
+        let bottom_y = self.height - bottom;
+
+        // The area of the box as x, y, width and height from the top left of the page, followed by
+        // the horizontal and vertical alignment as a fraction of the remaining space.
+        let ((x, y, width, height), align) = match margin_box {
+            TopLeftCorner => ((0., 0., left, top), (1., 0.5)),
+            TopLeft => ((left, 0., content_width, top), (0., 0.5)),
+            TopCenter => ((left + content_width, 0., content_width, top), (0.5, 0.5)),
+            TopRight => (
+                (left + 2. * content_width, 0., content_width, top),
+                (1., 0.5),
+            ),
+            TopRightCorner => ((right_x, 0., right, top), (0., 0.5)),
+            RightTop => ((right_x, top, right, content_height), (0., 0.)),
+            RightMiddle => (
+                (right_x, top + content_height, right, content_height),
+                (0., 0.5),
+            ),
+            RightBottom => (
+                (right_x, top + 2. * content_height, right, content_height),
+                (0., 1.),
+            ),
+            BottomRightCorner => ((right_x, bottom_y, right, bottom), (0., 0.5)),
+            BottomRight => (
+                (left + 2. * content_width, bottom_y, content_width, bottom),
+                (1., 0.5),
+            ),
+            BottomCenter => (
+                (left + content_width, bottom_y, content_width, bottom),
+                (0.5, 0.5),
+            ),
+            BottomLeft => ((left, bottom_y, content_width, bottom), (0., 0.5)),
+            BottomLeftCorner => ((0., bottom_y, left, bottom), (1., 0.5)),
+            LeftBottom => (
+                (0., top + 2. * content_height, left, content_height),
+                (1., 1.),
+            ),
+            LeftMiddle => ((0., top + content_height, left, content_height), (1., 0.5)),
+            LeftTop => ((0., top, left, content_height), (1., 0.)),
+        };
+
+        let width_constraint = WidthConstraint {
+            max: width,
+            expand: false,
+        };
+
+        let size = element.measure(MeasureCtx {
+            width: width_constraint,
+            first_height: height,
+            breakable: None,
+        });
+
+        let x = x + (width - size.width.unwrap_or(0.)).max(0.) * align.0;
+        let y = y + (height - size.height.unwrap_or(0.)).max(0.) * align.1;
+
+        element.draw(DrawCtx {
+            pdf: self.pdf,
+            location: Location {
+                layer: self.location.layer.clone(),
+                pos: (self.location.pos.0 + x, self.location.pos.1 - y),
+                ..self.location
+            },
+            width: width_constraint,
+            first_height: height,
+            preferred_height: None,
+            breakable: None,
+        });
+    }
+
+    /// Draws a dashed outline around the area of the primary content. Useful for checking margins
+    /// while working on a template.
+    pub fn margin_guides(&mut self, color: u32) {
+        use printpdf::{utils::calculate_points_for_rect, Line};
+
+        let [left, right, top, bottom] = self.borders;
+        let size = (self.width - left - right, self.height - top - bottom);
+
+        let points = calculate_points_for_rect(
+            Mm(size.0),
+            Mm(size.1),
+            Mm(self.location.pos.0 + left + size.0 / 2.),
+            Mm(self.location.pos.1 - top - size.1 / 2.),
+        );
+
+        let layer = &self.location.layer;
+
+        layer.save_graphics_state();
+        layer.set_outline_thickness(0.);
+        layer.set_outline_color(crate::utils::u32_to_color_and_alpha(color).0);
+        layer.set_line_dash_pattern(printpdf::LineDashPattern::new(
+            0,
+            Some(2),
+            Some(2),
+            None,
+            None,
+            None,
+            None,
+        ));
+        add_shape(
+            layer,
+            Line {
+                points,
+                is_closed: true,
+                has_fill: false,
+                has_stroke: true,
+                is_clipping_path: false,
+            },
+        );
+        layer.restore_graphics_state();
+    }
 }
 
 #[cfg(test)]
@@ -241,7 +624,11 @@ mod tests {
     use insta::assert_debug_snapshot;
 
     use super::*;
-    use crate::test_utils::{record_passes::RecordPasses, *};
+    use crate::elements::none::NoneElement;
+    use crate::test_utils::{
+        record_passes::{DrawPass, Pass, RecordPasses},
+        *,
+    };
     use X::*;
     use Y::*;
 
@@ -283,6 +670,10 @@ mod tests {
                     border_right: 3.,
                     border_top: 4.,
                     border_bottom: 5.,
+                    header: None,
+                    first_page: None,
+                    mirror: false,
+                    background: None,
                     decoration_elements: |content: &mut DecorationElements, _, _| {
                         content.add(&top_left, (Left(1.), Top(2.)), None);
                         content.add(&bottom_right, (Right(2.), Bottom(5.)), Some(4.));
@@ -306,6 +697,470 @@ mod tests {
         assert_debug_snapshot!(output);
     }
 
+    #[test]
+    fn test_margin_boxes() {
+        let output = test_element(
+            TestElementParams {
+                width: WidthConstraint {
+                    max: 40.,
+                    expand: true,
+                },
+                first_height: 40.,
+                pos: (0., 40.),
+                page_size: (40., 40.),
+                ..Default::default()
+            },
+            |assert, callback| {
+                let element = |width| {
+                    RecordPasses::new(FakeText {
+                        lines: 1,
+                        line_height: 2.,
+                        width,
+                    })
+                };
+
+                let top_right = element(3.);
+                let left_bottom = element(1.);
+                let bottom_right_corner = element(1.);
+
+                let element = Page {
+                    primary: &NoneElement,
+                    border_left: 4.,
+                    border_right: 6.,
+                    border_top: 6.,
+                    border_bottom: 4.,
+                    header: None,
+                    first_page: None,
+                    mirror: false,
+                    background: None,
+                    decoration_elements: |content: &mut DecorationElements, _, _| {
+                        content.add_to_margin_box(&top_right, MarginBox::TopRight);
+                        content.add_to_margin_box(&left_bottom, MarginBox::LeftBottom);
+                        content
+                            .add_to_margin_box(&bottom_right_corner, MarginBox::BottomRightCorner);
+                    },
+                };
+
+                let ret = callback.call(element);
+
+                if assert {
+                    let draw = |width, first_height, pos| DrawPass {
+                        width: WidthConstraint {
+                            max: width,
+                            expand: false,
+                        },
+                        first_height,
+                        preferred_height: None,
+                        page: 0,
+                        layer: 0,
+                        pos,
+                        breakable: None,
+                    };
+
+                    // Right aligned in the right third of the top border, centered vertically.
+                    top_right.assert_draw(draw(10., 6., (31., 38.)));
+
+                    // At the bottom of the left border, right aligned.
+                    left_bottom.assert_draw(draw(4., 10., (3., 6.)));
+
+                    bottom_right_corner.assert_draw(draw(6., 4., (34., 3.)));
+                }
+
+                ret
+            },
+        );
+
+        output.assert_size(ElementSize {
+            width: Some(40.),
+            height: Some(40.),
+        });
+    }
+
+    #[test]
+    fn test_header() {
+        let primary = FakeText {
+            lines: 3,
+            line_height: 5.,
+            width: 3.,
+        };
+
+        let header = FakeText {
+            lines: 1,
+            line_height: 6.,
+            width: 3.,
+        };
+
+        let element = Page {
+            primary: &primary,
+            border_left: 0.,
+            border_right: 0.,
+            border_top: 0.,
+            border_bottom: 0.,
+            header: Some(&header),
+            first_page: None,
+            mirror: false,
+            background: None,
+            decoration_elements: |_: &mut DecorationElements, _, _| {},
+        };
+
+        for output in (ElementTestParams {
+            first_height: 20.,
+            full_height: 20.,
+            ..Default::default()
+        })
+        .run(&element)
+        {
+            // Without the header all three lines would fit on one page.
+            if let Some(b) = output.breakable {
+                b.assert_break_count(1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_first_page() {
+        let primary = FakeText {
+            lines: 4,
+            line_height: 5.,
+            width: 3.,
+        };
+
+        let element = Page {
+            primary: &primary,
+            border_left: 0.,
+            border_right: 0.,
+            border_top: 0.,
+            border_bottom: 0.,
+            header: None,
+            first_page: Some(FirstPage {
+                border_top: 8.,
+                border_bottom: 2.,
+                header: None,
+                hide_page_number: false,
+            }),
+            mirror: false,
+            background: None,
+            decoration_elements: |_: &mut DecorationElements, _, _| {},
+        };
+
+        for output in (ElementTestParams {
+            first_height: 20.,
+            full_height: 20.,
+            ..Default::default()
+        })
+        .run(&element)
+        {
+            // Two lines fit on the first page, the other two on the second one.
+            if let Some(b) = output.breakable {
+                b.assert_break_count(1);
+            }
+        }
+    }
+
+    #[test]
+    fn test_first_page_number() {
+        test_element(
+            TestElementParams {
+                width: WidthConstraint {
+                    max: 10.,
+                    expand: true,
+                },
+                first_height: 20.,
+                breakable: Some(TestElementParamsBreakable {
+                    full_height: 20.,
+                    ..Default::default()
+                }),
+                pos: (0., 20.),
+                page_size: (10., 20.),
+                ..Default::default()
+            },
+            |assert, callback| {
+                let primary = FakeText {
+                    lines: 6,
+                    line_height: 5.,
+                    width: 3.,
+                };
+
+                let pages = std::cell::RefCell::new(Vec::new());
+
+                let element = Page {
+                    primary: &primary,
+                    border_left: 0.,
+                    border_right: 0.,
+                    border_top: 0.,
+                    border_bottom: 0.,
+                    header: None,
+                    first_page: Some(FirstPage {
+                        border_top: 0.,
+                        border_bottom: 0.,
+                        header: None,
+                        hide_page_number: true,
+                    }),
+                    mirror: false,
+                    background: None,
+                    decoration_elements: |content: &mut DecorationElements, page, _| {
+                        pages.borrow_mut().push((page, content.shows_page_number()));
+                    },
+                };
+
+                let ret = callback.call(element);
+
+                if assert {
+                    assert_eq!(*pages.borrow(), [(0, false), (1, true)]);
+                }
+
+                ret
+            },
+        );
+    }
+
+    #[test]
+    fn test_mirrored_margin_boxes() {
+        test_element(
+            TestElementParams {
+                width: WidthConstraint {
+                    max: 40.,
+                    expand: true,
+                },
+                first_height: 40.,
+                breakable: Some(TestElementParamsBreakable {
+                    full_height: 40.,
+                    ..Default::default()
+                }),
+                pos: (0., 40.),
+                page_size: (40., 40.),
+                ..Default::default()
+            },
+            |assert, callback| {
+                // Six lines fit between the borders, so there are two pages.
+                let primary = FakeText {
+                    lines: 12,
+                    line_height: 5.,
+                    width: 3.,
+                };
+
+                let number = RecordPasses::new(FakeText {
+                    lines: 1,
+                    line_height: 2.,
+                    width: 3.,
+                });
+
+                let element = Page {
+                    primary: &primary,
+                    border_left: 4.,
+                    border_right: 6.,
+                    border_top: 6.,
+                    border_bottom: 4.,
+                    header: None,
+                    first_page: None,
+                    mirror: true,
+                    background: None,
+                    decoration_elements: |content: &mut DecorationElements, _, _| {
+                        content.add_to_margin_box(&number, MarginBox::BottomRight);
+                    },
+                };
+
+                let ret = callback.call(element);
+
+                if assert {
+                    let draws = number
+                        .into_passes()
+                        .into_iter()
+                        .filter_map(|pass| match pass {
+                            Pass::Draw(draw) => Some((draw.page, draw.pos)),
+                            _ => None,
+                        })
+                        .collect::<Vec<_>>();
+
+                    // Right aligned in the right third of the bottom border on the first page and
+                    // left aligned in the left third on the second one, where the borders are
+                    // swapped too.
+                    assert_eq!(draws, [(0, (31., 3.)), (1, (6., 3.))]);
+                }
+
+                ret
+            },
+        );
+    }
+
+    #[test]
+    fn test_mirror() {
+        test_element(
+            TestElementParams {
+                width: WidthConstraint {
+                    max: 40.,
+                    expand: true,
+                },
+                first_height: 40.,
+                breakable: Some(TestElementParamsBreakable {
+                    full_height: 40.,
+                    ..Default::default()
+                }),
+                pos: (0., 40.),
+                page_size: (40., 40.),
+                ..Default::default()
+            },
+            |assert, callback| {
+                // Eight lines fit on a page, so there are three pages.
+                let primary = RecordPasses::new(FakeText {
+                    lines: 20,
+                    line_height: 5.,
+                    width: 3.,
+                });
+
+                let element = || {
+                    RecordPasses::new(FakeText {
+                        lines: 1,
+                        line_height: 2.,
+                        width: 1.,
+                    })
+                };
+
+                let number = element();
+                let mark = element();
+
+                let element = Page {
+                    primary: &primary,
+                    border_left: 4.,
+                    border_right: 6.,
+                    border_top: 0.,
+                    border_bottom: 0.,
+                    header: None,
+                    first_page: None,
+                    mirror: true,
+                    background: None,
+                    decoration_elements: |content: &mut DecorationElements, _, _| {
+                        content.add(&number, (Outside(5.), Bottom(5.)), None);
+                        content.add(&mark, (Inside(2.), Top(3.)), None);
+                    },
+                };
+
+                let ret = callback.call(element);
+
+                if assert {
+                    let draws = |element: RecordPasses<FakeText>| {
+                        element
+                            .into_passes()
+                            .into_iter()
+                            .filter_map(|pass| match pass {
+                                Pass::Draw(draw) => Some((draw.page, draw.pos)),
+                                _ => None,
+                            })
+                            .collect::<Vec<_>>()
+                    };
+
+                    // The first page is a right-hand page with the page number on the right. The
+                    // second one is a left-hand page, where the outside is on the left.
+                    assert_eq!(
+                        draws(number),
+                        [(0, (35., 5.)), (1, (5., 5.)), (2, (35., 5.))]
+                    );
+                    assert_eq!(
+                        draws(mark),
+                        [(0, (2., 37.)), (1, (38., 37.)), (2, (2., 37.))]
+                    );
+
+                    // The wider right border is the inner one on the left-hand page.
+                    let passes = primary.into_passes();
+                    let Some(Pass::Draw(draw)) = passes.iter().find(|p| matches!(p, Pass::Draw(_)))
+                    else {
+                        panic!("the primary content wasn't drawn");
+                    };
+
+                    assert_eq!(draw.pos.0, 4.);
+
+                    let breaks = draw.breakable.as_ref().unwrap().breaks.iter();
+                    assert_eq!(
+                        breaks.map(|b| (b.page, b.pos.0)).collect::<Vec<_>>(),
+                        [(1, 6.), (2, 4.)]
+                    );
+                }
+
+                ret
+            },
+        );
+    }
+
+    #[test]
+    fn test_background() {
+        use lopdf::Object;
+
+        use crate::elements::{column::Column, rectangle::Rectangle};
+
+        let rectangle = Rectangle {
+            size: (50., 60.),
+            fill: Some(0x00_00_ff_ff),
+            outline: None,
+        };
+
+        // Only one of the rectangles fits on a page.
+        let content = Column::new(|content| {
+            content.add(&rectangle)?.add(&rectangle)?.add(&rectangle)?;
+            None
+        });
+
+        let document = crate::build_pdf(
+            "test",
+            (100., 100.),
+            |_| (),
+            |_: &()| Page {
+                primary: &content,
+                border_left: 10.,
+                border_right: 10.,
+                border_top: 10.,
+                border_bottom: 10.,
+                header: None,
+                first_page: None,
+                mirror: false,
+                background: Some(0xff_00_00_ff),
+                decoration_elements: |_: &mut DecorationElements, _, _| {},
+            },
+        );
+
+        let pages = page_operations(&save(document));
+        assert_eq!(pages.len(), 3);
+
+        let number = |object: &Object| match *object {
+            Object::Integer(i) => i as f64,
+            Object::Real(r) => r as f64,
+            _ => panic!("not a number: {object:?}"),
+        };
+
+        for operations in pages {
+            let fill = |color: [f64; 3]| {
+                operations
+                    .iter()
+                    .position(|o| {
+                        o.operator == "rg"
+                            && o.operands.iter().map(number).eq(color.iter().copied())
+                    })
+                    .unwrap()
+            };
+
+            let background = fill([1., 0., 0.]);
+
+            // The background is painted before the content on every page.
+            assert!(background < fill([0., 0., 1.]));
+
+            // And covers the whole page, borders included.
+            let path = operations[background..]
+                .iter()
+                .take_while(|o| o.operator != "f")
+                .filter(|o| o.operator == "m" || o.operator == "l")
+                .map(|o| (number(&o.operands[0]), number(&o.operands[1])))
+                .collect::<Vec<_>>();
+
+            let page = crate::utils::mm_to_pt(100.);
+            let bounds = path.iter().fold(
+                (f64::MAX, f64::MAX, f64::MIN, f64::MIN),
+                |(x0, y0, x1, y1), &(x, y)| (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+            );
+
+            assert!(bounds.0.abs() < 0.01 && bounds.1.abs() < 0.01);
+            assert!((bounds.2 - page).abs() < 0.01 && (bounds.3 - page).abs() < 0.01);
+        }
+    }
+
     #[test]
     fn test_breakable() {
         let output = test_element(
@@ -347,6 +1202,10 @@ mod tests {
                     border_right: 3.,
                     border_top: 4.,
                     border_bottom: 5.,
+                    header: None,
+                    first_page: None,
+                    mirror: false,
+                    background: None,
                     decoration_elements: |content: &mut DecorationElements, _, _| {
                         content.add(&top_right, (Right(2.5), Top(2.)), None);
                         content.add(&bottom_left, (Left(2.), Bottom(5.)), Some(4.));
@@ -0,0 +1,388 @@
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    utils::{add_optional_size_with_gap, max_optional_size},
+    *,
+};
+
+/// The penalties [Paginate] adds up for its breaks. Only their ratios matter.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Penalties {
+    /// For breaking between two blocks of the same group.
+    pub inside: f64,
+
+    /// For breaking after the first block of a group, which is then alone at the bottom of a
+    /// location.
+    pub orphan: f64,
+
+    /// For breaking before the last block of a group, which is then alone at the top of a
+    /// location.
+    pub widow: f64,
+
+    /// Multiplied with the square of the fraction of a location that's left empty, for every
+    /// location but the last one.
+    pub underfill: f64,
+
+    /// Multiplied with the fraction of the last location that's left empty, so the last page
+    /// doesn't end up with just a few lines on it.
+    pub last_location: f64,
+}
+
+impl Default for Penalties {
+    fn default() -> Self {
+        Penalties {
+            inside: 10.,
+            orphan: 100.,
+            widow: 100.,
+            underfill: 100.,
+            last_location: 20.,
+        }
+    }
+}
+
+/// Content of [Paginate]. Blocks are never broken themselves.
+#[derive(Clone, Copy)]
+pub struct Block<'a> {
+    pub element: &'a dyn Element,
+
+    /// Blocks with the same group next to each other are parts of one box, like the lines of a
+    /// paragraph or the rows of a table, so breaking between them is penalized.
+    pub group: Option<u32>,
+
+    /// An extra penalty for breaking before the block. Negative values make a break there more
+    /// likely and with infinity there's never a break there, unless the content doesn't fit
+    /// otherwise.
+    pub penalty: f64,
+}
+
+impl<'a> Block<'a> {
+    pub fn new(element: &'a dyn Element) -> Self {
+        Block {
+            element,
+            group: None,
+            penalty: 0.,
+        }
+    }
+}
+
+/// Experimental: a column that chooses its breaks for the whole content at once, minimizing the
+/// sum of the [Penalties], instead of filling every location before breaking like
+/// [Column](super::column::Column). This moves lines to the next location to avoid widows and
+/// orphans or to even out the fill of the last page, for example.
+///
+/// A block that's higher than a full location gets a location on its own and overflows it.
+pub struct Paginate<'a> {
+    pub blocks: &'a [Block<'a>],
+    pub gap: f64,
+    pub penalties: Penalties,
+}
+
+struct Layout {
+    heights: Vec<Option<f64>>,
+    width: Option<f64>,
+
+    /// The blocks on every location. The first one can be empty.
+    locations: Vec<Range<usize>>,
+}
+
+impl<'a> Paginate<'a> {
+    fn height(&self, heights: &[Option<f64>]) -> Option<f64> {
+        heights
+            .iter()
+            .fold(None, |acc, &h| add_optional_size_with_gap(acc, h, self.gap))
+    }
+
+    /// The penalty for breaking before the block, which can't be the first one.
+    fn break_penalty(&self, i: usize) -> f64 {
+        let blocks = self.blocks;
+        let mut penalty = blocks[i].penalty;
+
+        let group = blocks[i].group;
+
+        if group.is_some() && blocks[i - 1].group == group {
+            let same_group = |b: &Block| b.group == group;
+
+            let before = blocks[..i]
+                .iter()
+                .rev()
+                .take_while(|b| same_group(b))
+                .count();
+            let after = blocks[i..].iter().take_while(|b| same_group(b)).count();
+
+            penalty += self.penalties.inside;
+
+            if before == 1 {
+                penalty += self.penalties.orphan;
+            }
+
+            if after == 1 {
+                penalty += self.penalties.widow;
+            }
+        }
+
+        penalty
+    }
+
+    fn empty_fraction(height: Option<f64>, available: f64) -> f64 {
+        if available > 0. {
+            (1. - height.unwrap_or(0.) / available).clamp(0., 1.)
+        } else {
+            0.
+        }
+    }
+
+    /// The cost of a location with the blocks `i..j`, including the rest of the content after it,
+    /// which starts on a full location.
+    fn cost(&self, layout: &Layout, i: usize, j: usize, available: f64, rest: &[f64]) -> f64 {
+        let fraction = Self::empty_fraction(self.height(&layout.heights[i..j]), available);
+
+        if j == self.blocks.len() {
+            self.penalties.last_location * fraction
+        } else {
+            self.penalties.underfill * fraction * fraction + self.break_penalty(j) + rest[j]
+        }
+    }
+
+    fn layout(
+        &self,
+        width: WidthConstraint,
+        first_height: f64,
+        full_height: Option<f64>,
+    ) -> Layout {
+        let mut heights = Vec::with_capacity(self.blocks.len());
+        let mut max_width = None;
+
+        for block in self.blocks {
+            let size = block.element.measure(MeasureCtx {
+                width,
+                first_height: full_height.unwrap_or(first_height),
+                breakable: None,
+            });
+
+            heights.push(size.height);
+            max_width = max_optional_size(max_width, size.width);
+        }
+
+        let mut layout = Layout {
+            heights,
+            width: if width.expand {
+                Some(width.max)
+            } else {
+                max_width
+            },
+            locations: vec![0..self.blocks.len()],
+        };
+
+        let n = self.blocks.len();
+
+        let Some(full_height) = full_height else {
+            return layout;
+        };
+
+        if n == 0 {
+            return layout;
+        }
+
+        let fits = |layout: &Layout, range: Range<usize>, available: f64| {
+            self.height(&layout.heights[range]).unwrap_or(0.) <= available
+        };
+
+        // The cheapest cost of the blocks from an index on, starting on a full location, and the
+        // start of the location after the one they start on.
+        let mut rest = vec![0.; n + 1];
+        let mut next = vec![n; n + 1];
+
+        for i in (0..n).rev() {
+            rest[i] = f64::INFINITY;
+
+            // If the penalties rule everything out, the locations are filled like in a column.
+            next[i] = i + 1;
+
+            for j in i + 1..=n {
+                if j > i + 1 && !fits(&layout, i..j, full_height) {
+                    break;
+                }
+
+                let cost = self.cost(&layout, i, j, full_height, &rest);
+
+                if !rest[i].is_finite() && !cost.is_finite() {
+                    next[i] = j;
+                } else if cost < rest[i] {
+                    rest[i] = cost;
+                    next[i] = j;
+                }
+            }
+        }
+
+        // Leaving the first location empty is an option too.
+        let mut first_cost = self.penalties.underfill + rest[0];
+        let mut first_end = 0;
+
+        for j in 1..=n {
+            // A block that's too high only overflows the first location if it's a full one.
+            if !(j == 1 && first_height >= full_height) && !fits(&layout, 0..j, first_height) {
+                break;
+            }
+
+            let cost = self.cost(&layout, 0, j, first_height, &rest);
+
+            if !first_cost.is_finite() && !cost.is_finite() {
+                first_end = j;
+            } else if cost < first_cost {
+                first_cost = cost;
+                first_end = j;
+            }
+        }
+
+        let mut locations = vec![0..first_end];
+        let mut start = first_end;
+
+        while start < n {
+            locations.push(start..next[start]);
+            start = next[start];
+        }
+
+        layout.locations = locations;
+        layout
+    }
+}
+
+impl<'a> Element for Paginate<'a> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        let layout = self.layout(ctx.width, ctx.first_height, Some(ctx.full_height));
+
+        if self.height(&layout.heights).is_none() {
+            FirstLocationUsage::NoneHeight
+        } else if self
+            .height(&layout.heights[layout.locations[0].clone()])
+            .is_none()
+        {
+            FirstLocationUsage::WillSkip
+        } else {
+            FirstLocationUsage::WillUse
+        }
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        let layout = self.layout(
+            ctx.width,
+            ctx.first_height,
+            ctx.breakable.as_ref().map(|b| b.full_height),
+        );
+
+        if let Some(breakable) = ctx.breakable {
+            *breakable.break_count = layout.locations.len() as u32 - 1;
+        }
+
+        ElementSize {
+            width: layout.width,
+            height: self.height(&layout.heights[layout.locations.last().unwrap().clone()]),
+        }
+    }
+
+    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+        let full_height = ctx.breakable.as_ref().map(|b| b.full_height);
+        let layout = self.layout(ctx.width, ctx.first_height, full_height);
+
+        let mut location = ctx.location.clone();
+        let mut height = None;
+
+        for (i, range) in layout.locations.iter().enumerate() {
+            if i > 0 {
+                if let Some(ref mut breakable) = ctx.breakable {
+                    location = (breakable.do_break)(ctx.pdf, i as u32 - 1, height);
+                }
+            }
+
+            height = self.height(&layout.heights[range.clone()]);
+
+            let mut y = location.pos.1;
+
+            for block in range.clone() {
+                self.blocks[block].element.draw(DrawCtx {
+                    pdf: ctx.pdf,
+                    location: Location {
+                        pos: (location.pos.0, y),
+                        ..location.clone()
+                    },
+                    width: ctx.width,
+                    first_height: full_height.unwrap_or(ctx.first_height),
+                    preferred_height: None,
+                    breakable: None,
+                });
+
+                if let Some(block_height) = layout.heights[block] {
+                    y -= block_height + self.gap;
+                }
+            }
+        }
+
+        ElementSize {
+            width: layout.width,
+            height,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_paginate() {
+        let line = FakeText {
+            lines: 1,
+            line_height: 10.,
+            width: 1.,
+        };
+
+        let paragraph = |penalty| {
+            [Block {
+                group: Some(0),
+                penalty,
+                ..Block::new(&line)
+            }; 6]
+        };
+
+        // Five lines fit on a location. Filling the first one would leave a widow, so two lines
+        // are moved to the second one.
+        let blocks = paragraph(0.);
+
+        // With breaks ruled out everywhere, the locations are filled like in a column.
+        let filled = paragraph(f64::INFINITY);
+
+        for (blocks, last_height) in [(&blocks, 20.), (&filled, 10.)] {
+            let element = Paginate {
+                blocks,
+                gap: 0.,
+                penalties: Penalties::default(),
+            };
+
+            for output in (ElementTestParams {
+                first_height: 50.,
+                full_height: 50.,
+                width: 10.,
+                ..Default::default()
+            })
+            .run(&element)
+            {
+                output.assert_size(ElementSize {
+                    width: Some(if output.width.expand { 10. } else { 1. }),
+                    height: Some(if output.breakable.is_some() {
+                        last_height
+                    } else {
+                        60.
+                    }),
+                });
+
+                if let Some(b) = output.breakable {
+                    b.assert_break_count(1);
+                }
+            }
+        }
+    }
+}
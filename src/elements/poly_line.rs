@@ -0,0 +1,284 @@
+use printpdf::{utils::calculate_points_for_circle, Line, Point};
+
+use crate::{line_rendering::LineRendering, utils::*, *};
+
+/// A decoration at the start or end of a [PolyLine]. Sizes are in millimeters.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LineEnd {
+    None,
+
+    /// A filled triangle with its tip at the end point. The line is shortened so that it doesn't
+    /// poke out of the tip.
+    Arrow {
+        length: f64,
+        width: f64,
+    },
+
+    /// Two strokes forming a V with its tip at the end point.
+    OpenArrow {
+        length: f64,
+        width: f64,
+    },
+
+    /// A filled circle centered on the end point.
+    Circle {
+        radius: f64,
+    },
+
+    /// A stroke perpendicular to the line through the end point.
+    Tick {
+        length: f64,
+    },
+}
+
+/// Connected straight line segments, e.g. for callout arrows and simple diagrams.
+///
+/// The points are in millimeters relative to the top left of the element, with y going down. The
+/// size of the element is the bounding box of the points starting at the origin. The stroke
+/// thickness and the decorations aren't included in it.
+pub struct PolyLine<'a> {
+    pub points: &'a [(f64, f64)],
+    pub style: LineStyle,
+    pub start: LineEnd,
+    pub end: LineEnd,
+}
+
+impl<'a> Element for PolyLine<'a> {
+    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        let size = self.size();
+        ctx.break_if_appropriate_for_min_height(size.1);
+
+        self.element_size(size)
+    }
+
+    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+        let size = self.size();
+        ctx.break_if_appropriate_for_min_height(size.1);
+
+        if self.points.len() >= 2 {
+            self.draw_line(&ctx.location, ctx.pdf.line_rendering);
+        }
+
+        self.element_size(size)
+    }
+}
+
+impl<'a> PolyLine<'a> {
+    fn size(&self) -> (f64, f64) {
+        self.points
+            .iter()
+            .fold((0., 0.), |(w, h), &(x, y)| (x.max(w), y.max(h)))
+    }
+
+    fn element_size(&self, size: (f64, f64)) -> ElementSize {
+        if self.points.is_empty() {
+            ElementSize {
+                width: None,
+                height: None,
+            }
+        } else {
+            ElementSize {
+                width: Some(size.0),
+                height: Some(size.1),
+            }
+        }
+    }
+
+    fn draw_line(&self, location: &Location, line_rendering: LineRendering) {
+        let layer = &location.layer;
+
+        let to_point =
+            |(x, y): (f64, f64)| Point::new(Mm(location.pos.0 + x), Mm(location.pos.1 - y));
+
+        let mut points = self.points.to_vec();
+        let last = points.len() - 1;
+
+        let start = end_geometry(points[1], points[0]);
+        let end = end_geometry(points[last - 1], points[last]);
+
+        if let LineEnd::Arrow { length, .. } = self.start {
+            points[0] = start.back(length);
+        }
+
+        if let LineEnd::Arrow { length, .. } = self.end {
+            points[last] = end.back(length);
+        }
+
+        layer.save_graphics_state();
+
+        let (color, alpha) = u32_to_color_and_alpha(self.style.color);
+        layer.set_outline_color(color.clone());
+        layer.set_fill_color(color);
+        layer.set_fill_alpha(alpha);
+        layer.set_outline_thickness(self.style.stroke_width(line_rendering));
+        layer.set_line_cap_style(self.style.cap_style.into());
+        set_line_join(layer, &self.style);
+
+        // The decorations are drawn solid even if the line itself is dashed.
+        for (decoration, geometry) in [(self.start, start), (self.end, end)] {
+            match decoration {
+                LineEnd::None => (),
+                LineEnd::Arrow { length, width } => add_shape(
+                    layer,
+                    Line {
+                        points: vec![
+                            (to_point(geometry.tip), false),
+                            (to_point(geometry.side(length, width / 2.)), false),
+                            (to_point(geometry.side(length, -width / 2.)), false),
+                        ],
+                        is_closed: true,
+                        has_fill: true,
+                        has_stroke: false,
+                        is_clipping_path: false,
+                    },
+                ),
+                LineEnd::OpenArrow { length, width } => add_shape(
+                    layer,
+                    Line {
+                        points: vec![
+                            (to_point(geometry.side(length, width / 2.)), false),
+                            (to_point(geometry.tip), false),
+                            (to_point(geometry.side(length, -width / 2.)), false),
+                        ],
+                        is_closed: false,
+                        has_fill: false,
+                        has_stroke: true,
+                        is_clipping_path: false,
+                    },
+                ),
+                LineEnd::Circle { radius } => add_shape(
+                    layer,
+                    Line {
+                        points: calculate_points_for_circle(
+                            Mm(radius),
+                            Mm(location.pos.0 + geometry.tip.0),
+                            Mm(location.pos.1 - geometry.tip.1),
+                        ),
+                        is_closed: true,
+                        has_fill: true,
+                        has_stroke: false,
+                        is_clipping_path: false,
+                    },
+                ),
+                LineEnd::Tick { length } => add_shape(
+                    layer,
+                    Line {
+                        points: vec![
+                            (to_point(geometry.side(0., length / 2.)), false),
+                            (to_point(geometry.side(0., -length / 2.)), false),
+                        ],
+                        is_closed: false,
+                        has_fill: false,
+                        has_stroke: true,
+                        is_clipping_path: false,
+                    },
+                ),
+            }
+        }
+
+        set_line_dash_pattern(layer, &self.style);
+
+        add_shape(
+            layer,
+            Line {
+                points: points.into_iter().map(|p| (to_point(p), false)).collect(),
+                is_closed: false,
+                has_fill: false,
+                has_stroke: true,
+                is_clipping_path: false,
+            },
+        );
+
+        layer.restore_graphics_state();
+    }
+}
+
+#[derive(Copy, Clone)]
+struct EndGeometry {
+    tip: (f64, f64),
+
+    /// Unit vector pointing from the line towards the tip.
+    direction: (f64, f64),
+}
+
+fn end_geometry(from: (f64, f64), tip: (f64, f64)) -> EndGeometry {
+    let (dx, dy) = (tip.0 - from.0, tip.1 - from.1);
+    let len = dx.hypot(dy);
+
+    EndGeometry {
+        tip,
+        direction: if len > 0. {
+            (dx / len, dy / len)
+        } else {
+            (1., 0.)
+        },
+    }
+}
+
+impl EndGeometry {
+    fn back(&self, distance: f64) -> (f64, f64) {
+        self.side(distance, 0.)
+    }
+
+    /// The point `back` along the line from the tip and then `offset` to the side.
+    fn side(&self, back: f64, offset: f64) -> (f64, f64) {
+        let (dx, dy) = self.direction;
+
+        (
+            self.tip.0 - dx * back - dy * offset,
+            self.tip.1 - dy * back + dx * offset,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_poly_line() {
+        for output in (ElementTestParams {
+            first_height: 4.,
+            ..Default::default()
+        })
+        .run(&PolyLine {
+            points: &[(0., 0.), (6., 5.), (3., 2.)],
+            style: LineStyle {
+                thickness: 0.5,
+                color: 0x00_00_00_FF,
+                dash_pattern: None,
+                cap_style: LineCapStyle::Butt,
+                join_style: LineJoinStyle::Round,
+                miter_limit: DEFAULT_MITER_LIMIT,
+                rendering: None,
+            },
+            start: LineEnd::Circle { radius: 1. },
+            end: LineEnd::Arrow {
+                length: 2.,
+                width: 1.5,
+            },
+        }) {
+            output.assert_size(ElementSize {
+                width: Some(6.),
+                height: Some(5.),
+            });
+
+            if let Some(b) = output.breakable {
+                if output.first_height == 4. {
+                    b.assert_break_count(1);
+                } else {
+                    b.assert_break_count(0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_end_geometry() {
+        let end = end_geometry((0., 0.), (4., 0.));
+
+        assert_eq!(end.back(1.), (3., 0.));
+        assert_eq!(end.side(1., 0.5), (3., 0.5));
+    }
+}
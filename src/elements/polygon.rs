@@ -0,0 +1,135 @@
+use printpdf::{Line, Point};
+
+use crate::{utils::*, *};
+
+/// A closed shape through the given points. The points are in millimeters relative to the top left
+/// of the element, with y going down. The size of the element is the bounding box of the points
+/// starting at the origin, plus the outline thickness.
+pub struct Polygon<'a> {
+    pub points: &'a [(f64, f64)],
+    pub fill: Option<u32>,
+    pub outline: Option<LineStyle>,
+}
+
+impl<'a> Element for Polygon<'a> {
+    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        let size = self.size();
+        ctx.break_if_appropriate_for_min_height(size.1);
+
+        self.element_size(size)
+    }
+
+    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+        let size = self.size();
+        ctx.break_if_appropriate_for_min_height(size.1);
+
+        if self.points.len() >= 2 {
+            let extra_outline_offset = self.outline_thickness() / 2.0;
+            let pos = ctx.location.pos;
+
+            let points = self
+                .points
+                .iter()
+                .map(|&(x, y)| {
+                    (
+                        Point::new(
+                            Mm(pos.0 + x + extra_outline_offset),
+                            Mm(pos.1 - y - extra_outline_offset),
+                        ),
+                        false,
+                    )
+                })
+                .collect();
+
+            ctx.location.layer.save_graphics_state();
+
+            if let Some(color) = self.fill {
+                let (color, alpha) = u32_to_color_and_alpha(color);
+                ctx.location.layer.set_fill_color(color);
+                ctx.location.layer.set_fill_alpha(alpha);
+            }
+
+            if let Some(line_style) = self.outline {
+                set_line_style(&ctx.location.layer, &line_style, ctx.pdf.line_rendering);
+            }
+
+            add_shape(
+                &ctx.location.layer,
+                Line {
+                    points,
+                    is_closed: true,
+                    has_fill: self.fill.is_some(),
+                    has_stroke: self.outline.is_some(),
+                    is_clipping_path: false,
+                },
+            );
+
+            ctx.location.layer.restore_graphics_state();
+        }
+
+        self.element_size(size)
+    }
+}
+
+impl<'a> Polygon<'a> {
+    fn outline_thickness(&self) -> f64 {
+        self.outline.map(|o| o.thickness).unwrap_or(0.0)
+    }
+
+    fn size(&self) -> (f64, f64) {
+        let outline_thickness = self.outline_thickness();
+
+        let (width, height) = self
+            .points
+            .iter()
+            .fold((0., 0.), |(w, h), &(x, y)| (x.max(w), y.max(h)));
+
+        (width + outline_thickness, height + outline_thickness)
+    }
+
+    fn element_size(&self, size: (f64, f64)) -> ElementSize {
+        if self.points.is_empty() {
+            ElementSize {
+                width: None,
+                height: None,
+            }
+        } else {
+            ElementSize {
+                width: Some(size.0),
+                height: Some(size.1),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_polygon() {
+        for output in (ElementTestParams {
+            first_height: 8.,
+            ..Default::default()
+        })
+        .run(&Polygon {
+            points: &[(5., 0.), (10., 8.), (0., 8.)],
+            fill: Some(0x00_00_FF_FF),
+            outline: Some(LineStyle::solid(1., 0)),
+        }) {
+            output.assert_size(ElementSize {
+                width: Some(11.),
+                height: Some(9.),
+            });
+
+            if let Some(b) = output.breakable {
+                if output.first_height == 8. {
+                    b.assert_break_count(1);
+                } else {
+                    b.assert_break_count(0);
+                }
+            }
+        }
+    }
+}
@@ -5,7 +5,7 @@ use crate::{utils::*, *};
 pub struct Rectangle {
     pub size: (f64, f64),
     pub fill: Option<u32>,
-    pub outline: Option<(f64, u32)>,
+    pub outline: Option<LineStyle>,
 }
 
 impl Element for Rectangle {
@@ -46,22 +46,20 @@ impl Element for Rectangle {
             ctx.location.layer.set_fill_alpha(alpha);
         }
 
-        if let Some((thickness, color)) = self.outline {
-            // No outline alpha?
-            let (color, _alpha) = u32_to_color_and_alpha(color);
-            ctx.location.layer.set_outline_color(color);
-            ctx.location
-                .layer
-                .set_outline_thickness(mm_to_pt(thickness));
+        if let Some(line_style) = self.outline {
+            set_line_style(&ctx.location.layer, &line_style, ctx.pdf.line_rendering);
         }
 
-        ctx.location.layer.add_shape(Line {
-            points,
-            is_closed: true,
-            has_fill: self.fill.is_some(),
-            has_stroke: self.outline.is_some(),
-            is_clipping_path: false,
-        });
+        add_shape(
+            &ctx.location.layer,
+            Line {
+                points,
+                is_closed: true,
+                has_fill: self.fill.is_some(),
+                has_stroke: self.outline.is_some(),
+                is_clipping_path: false,
+            },
+        );
 
         ctx.location.layer.restore_graphics_state();
 
@@ -70,7 +68,7 @@ impl Element for Rectangle {
 }
 
 fn outline_thickness(rectangle: &Rectangle) -> f64 {
-    rectangle.outline.map(|o| o.0).unwrap_or(0.0)
+    rectangle.outline.map(|o| o.thickness).unwrap_or(0.0)
 }
 
 fn size(rectangle: &Rectangle) -> ElementSize {
@@ -96,7 +94,7 @@ mod tests {
         .run(&Rectangle {
             size: (11., 12.),
             fill: None,
-            outline: Some((1., 0)),
+            outline: Some(LineStyle::solid(1., 0)),
         }) {
             output.assert_size(ElementSize {
                 width: Some(12.),
@@ -114,4 +112,39 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_outline_join() {
+        use lopdf::Object;
+
+        let document = build_pdf(
+            "test",
+            (100., 100.),
+            |_| (),
+            |_: &()| Rectangle {
+                size: (10., 10.),
+                fill: None,
+                outline: Some(LineStyle {
+                    join_style: LineJoinStyle::Round,
+                    miter_limit: 4.,
+                    ..LineStyle::solid(1., 0)
+                }),
+            },
+        );
+
+        let operations = &page_operations(&save(document))[0];
+
+        let operand = |operator: &str| {
+            let operation = operations.iter().find(|o| o.operator == operator).unwrap();
+
+            match operation.operands[0] {
+                Object::Integer(i) => i as f64,
+                Object::Real(r) => r as f64,
+                _ => panic!("{operator} without a number"),
+            }
+        };
+
+        assert_eq!(operand("j"), 1.);
+        assert_eq!(operand("M"), 4.);
+    }
 }
@@ -1,9 +1,15 @@
+use crate::annotations::{self, MarkupAnnotation, Quad, SpanMarkup};
 use crate::fonts::Font;
 use crate::fonts::GeneralMetrics;
+use crate::fonts::{color, Synthesis, SyntheticFaces};
+use crate::language::{begin_language, end_language};
+use crate::line_break::LineBreaking;
+use crate::script::Script;
 use crate::text::remove_non_trailing_soft_hyphens;
 use crate::text::*;
 use crate::utils::*;
 use crate::{text::text_width, *};
+use printpdf::{CurTransMat, TextRenderingMode};
 
 use serde::{Deserialize, Serialize};
 
@@ -13,7 +19,54 @@ pub struct Span {
     pub bold: bool,
     pub italic: bool,
     pub underline: bool,
+
+    #[serde(deserialize_with = "crate::serde_elements::color::deserialize_color")]
     pub color: u32,
+
+    /// The color of the underline if it should differ from the color of the text.
+    #[serde(
+        default,
+        deserialize_with = "crate::serde_elements::color::deserialize_optional_color"
+    )]
+    pub underline_color: Option<u32>,
+
+    /// The thickness of the underline in mm. By default it's thicker for bold text.
+    #[serde(
+        default,
+        deserialize_with = "crate::serde_elements::expr::deserialize_optional_f64"
+    )]
+    pub underline_thickness: Option<f64>,
+
+    /// The script of the text, for when it can't be told from the characters.
+    #[serde(default)]
+    pub script: Option<Script>,
+
+    /// A language tag like `th` or `hi-IN`. The text is marked with it for screen readers, see
+    /// [crate::language].
+    #[serde(default)]
+    pub language: Option<String>,
+
+    /// A markup annotation for the whole text of the span, see [crate::annotations].
+    #[serde(default)]
+    pub markup: Option<SpanMarkup>,
+
+    /// Small annotation text centered above the text of the span, like furigana for Japanese.
+    /// The text of a span with ruby isn't broken across lines.
+    #[serde(default)]
+    pub ruby: Option<String>,
+}
+
+impl Span {
+    /// The color and thickness of the underline, if there is one.
+    fn underline(&self) -> Option<(u32, f64)> {
+        self.underline.then(|| {
+            (
+                self.underline_color.unwrap_or(self.color),
+                self.underline_thickness
+                    .unwrap_or(pt_to_mm(if self.bold { 1.0 } else { 0.5 })),
+            )
+        })
+    }
 }
 
 pub struct RichText<'a, F: Font> {
@@ -21,7 +74,36 @@ pub struct RichText<'a, F: Font> {
     pub size: f64,
     pub small_size: f64,
     pub extra_line_height: f64,
+
+    /// Applied to the tallest line height of the fonts before [Self::extra_line_height] is added,
+    /// so all lines get the same height.
+    pub line_spacing: LineSpacing,
+
+    /// Where lines are broken, see [LineBreaking].
+    pub line_breaking: LineBreaking,
+
+    /// See [Text::baseline_grid](super::text::Text::baseline_grid).
+    pub baseline_grid: Option<f64>,
+
     pub fonts: FontSet<'a, F>,
+
+    /// Which faces of the font set are faked.
+    pub synthetic: SyntheticFaces,
+}
+
+/// The size of ruby text relative to the size of the text it's above.
+const RUBY_SCALE: f64 = 0.5;
+
+#[derive(Copy, Clone)]
+struct LineMetrics {
+    /// The distance from the top of a line to its baseline.
+    baseline: f64,
+
+    /// The distance from the top of a line to the baseline of the ruby text.
+    ruby_baseline: f64,
+
+    /// Including [RichText::extra_line_height] and the room for ruby text.
+    line_height: f64,
 }
 
 pub struct LineFragment<'a, F: Font> {
@@ -33,10 +115,12 @@ pub struct LineFragment<'a, F: Font> {
 
     font: &'a F,
     size: f64,
-    bold: bool,
-    underline: bool,
+    synthesis: Synthesis,
+    underline: Option<(u32, f64)>,
     color: u32,
-    ascent: f64,
+    ruby: Option<&'a str>,
+    language: Option<&'a str>,
+    markup: Option<SpanMarkup>,
     new_line: bool,
     x_offset: f64,
 }
@@ -58,18 +142,21 @@ pub struct LineFragmentTrimmed<'a, F: Font> {
     font: &'a F,
     size: f64,
 
-    // needed for underline thickness
-    bold: bool,
-
-    underline: bool,
+    synthesis: Synthesis,
+    underline: Option<(u32, f64)>,
     color: u32,
-    ascent: f64,
+    ruby: Option<&'a str>,
+    language: Option<&'a str>,
+    markup: Option<SpanMarkup>,
     new_line: bool,
     x_offset: f64,
 }
 
 impl<'a, F: Font> RichText<'a, F> {
-    fn pieces(&'a self, width: f64) -> (impl Iterator<Item = LineFragment<'a, F>> + 'a, f64) {
+    fn pieces(
+        &'a self,
+        width: f64,
+    ) -> (impl Iterator<Item = LineFragment<'a, F>> + 'a, LineMetrics) {
         #[derive(Copy, Clone)]
         struct FontVars {
             ascent: f64,
@@ -90,13 +177,23 @@ impl<'a, F: Font> RichText<'a, F> {
             }
         }
 
+        /// With ruby, the text is kept together and is at least as wide as the ruby text (in pt).
         fn mk_gen<'a, F: Font>(
             text: &'a str,
             font: &'a F,
             size: f64,
+            breaking: LineBreaking,
+            ruby_width: Option<f64>,
         ) -> LineGenerator<'a, impl Fn(&str) -> f64 + 'a> {
-            let text_width = move |t: &str| text_width(t, size, font, 0., 0.);
-            LineGenerator::new(text, text_width)
+            let min_width = ruby_width.unwrap_or(0.);
+            let text_width = move |t: &str| text_width(t, size, font, 0., 0.).max(min_width);
+            let generator = LineGenerator::new(text, text_width).with_breaking(breaking);
+
+            if ruby_width.is_some() {
+                generator.unbreakable()
+            } else {
+                generator
+            }
         }
 
         let regular_vars = font_vars(self.fonts.regular, self.size as f64);
@@ -104,11 +201,41 @@ impl<'a, F: Font> RichText<'a, F> {
         let italic_vars = font_vars(self.fonts.italic, self.size as f64);
         let bold_italic_vars = font_vars(self.fonts.bold_italic, self.size as f64);
 
-        let line_height = regular_vars
-            .line_height
-            .max(bold_vars.line_height)
-            .max(italic_vars.line_height)
-            .max(bold_italic_vars.line_height);
+        let line_height = self.line_spacing.line_height(
+            regular_vars
+                .line_height
+                .max(bold_vars.line_height)
+                .max(italic_vars.line_height)
+                .max(bold_italic_vars.line_height),
+        );
+
+        // All fragments of a line share the baseline of the font that reaches up the most, so
+        // mixing fonts doesn't make the text jump up and down.
+        let ascent = regular_vars
+            .ascent
+            .max(bold_vars.ascent)
+            .max(italic_vars.ascent)
+            .max(bold_italic_vars.ascent);
+
+        // If any span has ruby, all lines get room for it above them, so they keep the same
+        // height.
+        let ruby_vars = self
+            .spans
+            .iter()
+            .any(|s| s.ruby.is_some())
+            .then(|| font_vars(self.fonts.regular, self.size * RUBY_SCALE));
+        let ruby_height = ruby_vars.map_or(0., |r| r.line_height);
+
+        let baseline = snap_to_baseline_grid(ascent + ruby_height, self.baseline_grid);
+
+        let metrics = LineMetrics {
+            baseline,
+            ruby_baseline: ruby_vars.map_or(0., |r| baseline - ascent - (r.line_height - r.ascent)),
+            line_height: snap_to_baseline_grid(
+                line_height + ruby_height + self.extra_line_height,
+                self.baseline_grid,
+            ),
+        };
 
         let mut spans = self.spans.iter();
         let mut generator = None;
@@ -134,31 +261,68 @@ impl<'a, F: Font> RichText<'a, F> {
                             if let Some(span) = spans.next() {
                                 // this way we make sure the generator has at least one item
                                 if span.text.len() > 0 {
-                                    let (font, font_vars): (&F, FontVars) =
+                                    let synthetic = &self.synthetic;
+
+                                    let (font, font_vars, synthesis): (&F, FontVars, Synthesis) =
                                         match (span.bold, span.italic) {
-                                            (false, false) => (self.fonts.regular, regular_vars),
-                                            (false, true) => (self.fonts.italic, italic_vars),
-                                            (true, false) => (self.fonts.bold, bold_vars),
-                                            (true, true) => {
-                                                (self.fonts.bold_italic, bold_italic_vars)
+                                            (false, false) => (
+                                                self.fonts.regular,
+                                                regular_vars,
+                                                Synthesis::default(),
+                                            ),
+                                            (false, true) => {
+                                                (self.fonts.italic, italic_vars, synthetic.italic)
+                                            }
+                                            (true, false) => {
+                                                (self.fonts.bold, bold_vars, synthetic.bold)
                                             }
+                                            (true, true) => (
+                                                self.fonts.bold_italic,
+                                                bold_italic_vars,
+                                                synthetic.bold_italic,
+                                            ),
                                         };
 
+                                    let ruby = span.ruby.as_deref();
+                                    let ruby_width = ruby.map(|r| {
+                                        text_width(r, self.size * RUBY_SCALE, font, 0., 0.)
+                                    });
+
                                     generator = Some((
-                                        mk_gen(&span.text, font, self.size),
+                                        mk_gen(
+                                            &span.text,
+                                            font,
+                                            self.size,
+                                            self.line_breaking,
+                                            ruby_width,
+                                        ),
                                         font,
                                         font_vars,
-                                        span.bold,
-                                        span.italic,
-                                        span.underline,
+                                        synthesis,
+                                        span.underline(),
                                         span.color,
+                                        ruby,
+                                        span.language.as_deref(),
+                                        span.markup,
+                                        ruby_width.map_or(0., pt_to_mm),
                                     ));
                                 }
                             } else {
                                 break None;
                             }
                         }
-                        Some((ref mut gen, font, font_vars, bold, _italic, underline, color)) => {
+                        Some((
+                            ref mut gen,
+                            font,
+                            font_vars,
+                            synthesis,
+                            underline,
+                            color,
+                            ruby,
+                            language,
+                            markup,
+                            ruby_width,
+                        )) => {
                             let next = if let FirstLine | LineDone = line_state {
                                 gen.next(mm_to_pt(width), false)
                             } else {
@@ -171,7 +335,8 @@ impl<'a, F: Font> RichText<'a, F> {
 
                                 let trimmed = next.trim_end();
                                 let length_trimmed =
-                                    pt_to_mm(text_width(trimmed, self.size, font, 0., 0.));
+                                    pt_to_mm(text_width(trimmed, self.size, font, 0., 0.))
+                                        .max(ruby_width);
                                 let length_full = length_trimmed
                                     + pt_to_mm(text_width(
                                         &next[trimmed.len()..],
@@ -197,10 +362,12 @@ impl<'a, F: Font> RichText<'a, F> {
 
                                     font,
                                     size: self.size,
-                                    bold,
+                                    synthesis,
                                     underline,
                                     color,
-                                    ascent: font_vars.ascent,
+                                    ruby,
+                                    language,
+                                    markup,
                                     new_line,
                                     x_offset: ret_x_offset,
                                 });
@@ -213,15 +380,18 @@ impl<'a, F: Font> RichText<'a, F> {
                 }
             })
             .filter(|i| i.new_line || i.text_trimmed.len() != 0),
-            line_height,
+            metrics,
         )
     }
 
     fn pieces_trimmed(
         &'a self,
         width: f64,
-    ) -> (impl Iterator<Item = LineFragmentTrimmed<'a, F>> + 'a, f64) {
-        let (mut iter, line_height) = self.pieces(width);
+    ) -> (
+        impl Iterator<Item = LineFragmentTrimmed<'a, F>> + 'a,
+        LineMetrics,
+    ) {
+        let (mut iter, metrics) = self.pieces(width);
 
         let mut last = iter.next();
 
@@ -250,10 +420,12 @@ impl<'a, F: Font> RichText<'a, F> {
 
                         font: last_frag.font,
                         size: last_frag.size,
-                        bold: last_frag.bold,
+                        synthesis: last_frag.synthesis,
                         underline: last_frag.underline,
                         color: last_frag.color,
-                        ascent: last_frag.ascent,
+                        ruby: last_frag.ruby,
+                        language: last_frag.language,
+                        markup: last_frag.markup,
                         new_line: last_frag.new_line,
                         x_offset: last_frag.x_offset,
                     })
@@ -261,15 +433,14 @@ impl<'a, F: Font> RichText<'a, F> {
                     None
                 }
             }),
-            line_height,
+            metrics,
         )
     }
 }
 
 impl<'a, F: Font> Element for RichText<'a, F> {
     fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
-        let (_, line_height) = self.pieces_trimmed(ctx.width.max);
-        let line_height = line_height + self.extra_line_height;
+        let (_, LineMetrics { line_height, .. }) = self.pieces_trimmed(ctx.width.max);
 
         if ctx.first_height < line_height {
             FirstLocationUsage::WillSkip
@@ -281,8 +452,7 @@ impl<'a, F: Font> Element for RichText<'a, F> {
     fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
         let mut max_width = ctx.width.constrain(0.);
 
-        let (iter, line_height) = self.pieces_trimmed(ctx.width.max);
-        let line_height = line_height + self.extra_line_height;
+        let (iter, LineMetrics { line_height, .. }) = self.pieces_trimmed(ctx.width.max);
 
         let mut height_available = ctx.first_height;
 
@@ -325,8 +495,14 @@ impl<'a, F: Font> Element for RichText<'a, F> {
     fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
         let mut max_width = ctx.width.constrain(0.);
 
-        let (iter, line_height) = self.pieces_trimmed(ctx.width.max);
-        let line_height = line_height + self.extra_line_height;
+        let (
+            iter,
+            LineMetrics {
+                baseline,
+                ruby_baseline,
+                line_height,
+            },
+        ) = self.pieces_trimmed(ctx.width.max);
 
         let mut x = ctx.location.pos.0;
         let mut y = ctx.location.pos.1;
@@ -348,9 +524,11 @@ impl<'a, F: Font> Element for RichText<'a, F> {
 
         let mut line_count = 1;
 
-        for frag in iter {
-            let pdf_font = &frag.font.indirect_font_ref();
+        // The annotation of the last fragments with markup, see [add_markup_quad].
+        let collecting = annotations::collecting();
+        let mut markup = None;
 
+        for frag in iter {
             let line_width = frag.length;
 
             max_width = max_width.max(frag.x_offset + line_width);
@@ -379,38 +557,95 @@ impl<'a, F: Font> Element for RichText<'a, F> {
                 }
             }
 
+            // Text with ruby is centered in the fragment, which is wider if the ruby is.
+            let text_x = match frag.ruby {
+                Some(_) => {
+                    let text_width = text_width(frag.text, frag.size, frag.font, 0., 0.);
+                    x + frag.x_offset + (frag.length - pt_to_mm(text_width)) / 2.
+                }
+                None => x + frag.x_offset,
+            };
+
+            if collecting {
+                let quad = frag.markup.map(|span_markup| {
+                    let width = text_width(frag.text, frag.size, frag.font, 0., 0.);
+
+                    let quad = Quad {
+                        left: text_x,
+                        right: text_x + pt_to_mm(width),
+                        top: y,
+                        bottom: y - line_height,
+                    };
+
+                    (span_markup, quad)
+                });
+
+                add_markup_quad(&mut markup, quad, ctx.location.layer.page.0);
+            }
+
             ctx.location.layer.save_graphics_state();
             ctx.location
                 .layer
                 .set_fill_color(u32_to_color_and_alpha(frag.color).0);
-            ctx.location.layer.use_text(
+
+            if let Some(language) = frag.language {
+                begin_language(&ctx.location.layer, language);
+            }
+
+            draw_text(
+                &ctx.location.layer,
                 &remove_non_trailing_soft_hyphens(frag.text),
+                frag.font,
                 frag.size,
-                Mm(x + frag.x_offset),
-                Mm(y - frag.ascent),
-                pdf_font,
+                frag.synthesis,
+                frag.color,
+                (text_x, y - baseline),
             );
 
+            if let Some(ruby) = frag.ruby {
+                let ruby_size = frag.size * RUBY_SCALE;
+                let ruby_width = pt_to_mm(text_width(ruby, ruby_size, frag.font, 0., 0.));
+
+                draw_text(
+                    &ctx.location.layer,
+                    ruby,
+                    frag.font,
+                    ruby_size,
+                    frag.synthesis,
+                    frag.color,
+                    (
+                        x + frag.x_offset + (frag.length - ruby_width) / 2.,
+                        y - ruby_baseline,
+                    ),
+                );
+            }
+
+            if frag.language.is_some() {
+                end_language(&ctx.location.layer);
+            }
+
             // This isn't quite correct currently. The truetype format has underline position and
             // thickness information in the `post` table. This information is however not
             // exposed in the `stb_truetype` crate. To get this information we'll have to switch
             // to another crate, such as `ttf-parser`, which exposes the `post` table and the
             // underline information. For now we'll just use some hard-coded values that look
             // mostly right.
-            if frag.underline {
+            if let Some((color, thickness)) = frag.underline {
                 ctx.location
                     .layer
-                    .set_outline_color(u32_to_color_and_alpha(frag.color).0);
+                    .set_outline_color(u32_to_color_and_alpha(color).0);
                 crate::utils::line(
                     &ctx.location.layer,
-                    [x + frag.x_offset, y - frag.ascent - 1.0],
+                    [text_x, y - baseline - 1.0],
                     pt_to_mm(text_width(frag.text, frag.size, frag.font, 0., 0.)),
-                    pt_to_mm(if frag.bold { 1.0 } else { 0.5 }),
+                    thickness,
                 );
             }
             ctx.location.layer.restore_graphics_state();
         }
 
+        markup.into_iter().for_each(annotations::add);
+
         ElementSize {
             width: Some(max_width),
             height: Some(line_count as f64 * line_height),
@@ -418,6 +653,118 @@ impl<'a, F: Font> Element for RichText<'a, F> {
     }
 }
 
+/// Adds the quad of a fragment to the annotation of the fragments before it if they have the same
+/// markup and are on the same page. Otherwise the annotation is done and a new one is started for
+/// the fragment, if it has markup.
+fn add_markup_quad(
+    annotation: &mut Option<MarkupAnnotation>,
+    quad: Option<(SpanMarkup, Quad)>,
+    page: usize,
+) {
+    if let (Some(current), Some((markup, quad))) = (annotation.as_mut(), quad) {
+        if (current.kind, current.color, current.page) == (markup.kind, markup.color, page) {
+            current.quads.push(quad);
+            return;
+        }
+    }
+
+    let next = quad.map(|(markup, quad)| MarkupAnnotation {
+        kind: markup.kind,
+        color: markup.color,
+        page,
+        quads: vec![quad],
+    });
+
+    std::mem::replace(annotation, next)
+        .into_iter()
+        .for_each(annotations::add);
+}
+
+/// The slant of synthesized oblique text, as the horizontal offset per unit of height. This is
+/// about 12°, which is what most oblique faces use.
+const OBLIQUE_SLANT: f64 = 0.2126;
+
+/// The width of the outline of synthesized bold text relative to the font size.
+const BOLD_STROKE: f64 = 0.03;
+
+fn draw_text(
+    layer: &PdfLayerReference,
+    text: &str,
+    font: &impl Font,
+    size: f64,
+    synthesis: Synthesis,
+    color: u32,
+    pos: (f64, f64),
+) {
+    if synthesis == Synthesis::default() {
+        write_text(layer, text, font, size, color, pos);
+        return;
+    }
+
+    layer.save_graphics_state();
+
+    if synthesis.bold {
+        layer.set_outline_color(u32_to_color_and_alpha(color).0);
+        layer.set_outline_thickness(size * BOLD_STROKE);
+        layer.set_text_rendering_mode(TextRenderingMode::FillStroke);
+    }
+
+    let pos = if synthesis.oblique {
+        layer.set_ctm(CurTransMat::Translate(Mm(pos.0), Mm(pos.1)));
+
+        // A shear can't be expressed directly, so it's decomposed into a rotation, a scale and
+        // another rotation. This is the singular value decomposition of [[1, k], [0, 1]].
+        let k = OBLIQUE_SLANT;
+        let q = (1. + k * k / 4.).sqrt();
+        let a = (k / 2.).atan().to_degrees();
+
+        layer.set_ctm(CurTransMat::Rotate((90. - a) / 2.));
+        layer.set_ctm(CurTransMat::Scale(q + k / 2., q - k / 2.));
+        layer.set_ctm(CurTransMat::Rotate((-90. - a) / 2.));
+
+        (0., 0.)
+    } else {
+        pos
+    };
+
+    write_text(layer, text, font, size, color, pos);
+    layer.restore_graphics_state();
+}
+
+/// Writes the text, drawing the color glyphs in it as paths.
+fn write_text(
+    layer: &PdfLayerReference,
+    text: &str,
+    font: &impl Font,
+    size: f64,
+    color: u32,
+    pos: (f64, f64),
+) {
+    let pdf_font = font.indirect_font_ref();
+    let runs = color::color_runs(font, text);
+
+    if runs.iter().all(|(_, glyph)| glyph.is_none()) {
+        use_text(layer, text, size, Mm(pos.0), Mm(pos.1), pdf_font);
+        return;
+    }
+
+    for (range, glyph) in runs {
+        let x = pos.0 + pt_to_mm(text_width(&text[..range.start], size, font, 0., 0.));
+
+        match glyph {
+            Some(glyph) => color::draw_color_glyph(
+                layer,
+                &glyph,
+                size,
+                font.units_per_em() as f64,
+                color,
+                (x, pos.1),
+            ),
+            None => use_text(layer, &text[range], size, Mm(x), Mm(pos.1), pdf_font),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use printpdf::PdfDocument;
@@ -442,6 +789,12 @@ mod tests {
                     italic: false,
                     underline: false,
                     color: 0,
+                    underline_color: None,
+                    underline_thickness: None,
+                    script: None,
+                    language: None,
+                    markup: None,
+                    ruby: None,
                 },
                 Span {
                     text: "sum dol ".to_string(),
@@ -449,6 +802,12 @@ mod tests {
                     italic: true,
                     underline: false,
                     color: 0,
+                    underline_color: None,
+                    underline_thickness: None,
+                    script: None,
+                    language: None,
+                    markup: None,
+                    ruby: None,
                 },
                 Span {
                     text: "or sit amet".to_string(),
@@ -456,17 +815,27 @@ mod tests {
                     italic: true,
                     underline: false,
                     color: 0,
+                    underline_color: None,
+                    underline_thickness: None,
+                    script: None,
+                    language: None,
+                    markup: None,
+                    ruby: None,
                 },
             ],
             size: 12.,
             small_size: 12.,
             extra_line_height: 12.,
+            line_spacing: LineSpacing::default(),
+            line_breaking: LineBreaking::default(),
+            baseline_grid: None,
             fonts: FontSet {
                 regular: &BuiltinFont::courier(&doc),
                 bold: &BuiltinFont::courier_bold(&doc),
                 italic: &BuiltinFont::courier_oblique(&doc),
                 bold_italic: &BuiltinFont::courier_bold_oblique(&doc),
             },
+            synthetic: SyntheticFaces::default(),
         };
 
         // Should be broken into lines like this:
@@ -524,4 +893,252 @@ mod tests {
             });
         }
     }
+
+    #[test]
+    fn test_synthetic_faces() {
+        let doc = PdfDocument::empty("i contain a font");
+        let courier = BuiltinFont::courier(&doc);
+
+        let span = |text: &str, bold, italic| Span {
+            text: text.to_string(),
+            bold,
+            italic,
+            underline: false,
+            color: 0,
+            underline_color: None,
+            underline_thickness: None,
+            script: None,
+            language: None,
+            markup: None,
+            ruby: None,
+        };
+
+        let faked = Synthesis {
+            bold: true,
+            oblique: true,
+        };
+
+        let text_element = RichText {
+            spans: &[
+                span("Lorem ", false, false),
+                span("ipsum ", true, false),
+                span("dolor", true, true),
+            ],
+            size: 12.,
+            small_size: 12.,
+            extra_line_height: 12.,
+            line_spacing: LineSpacing::default(),
+            line_breaking: LineBreaking::default(),
+            baseline_grid: None,
+            fonts: FontSet {
+                regular: &courier,
+                bold: &courier,
+                italic: &courier,
+                bold_italic: &courier,
+            },
+            synthetic: SyntheticFaces {
+                bold: Synthesis {
+                    bold: true,
+                    oblique: false,
+                },
+                italic: Synthesis {
+                    bold: false,
+                    oblique: true,
+                },
+                bold_italic: faked,
+            },
+        };
+
+        let element = ElementProxy {
+            before_draw: &|ctx: &mut DrawCtx| {
+                ctx.pdf
+                    .document
+                    .add_builtin_font(printpdf::BuiltinFont::Courier)
+                    .unwrap();
+            },
+            ..ElementProxy::new(text_element)
+        };
+
+        // The effects don't change the metrics.
+        let letter_width = 2.5400016;
+        let line_height = 16.466169479999998;
+
+        for output in (ElementTestParams {
+            width: letter_width * 20.,
+            ..Default::default()
+        })
+        .run(&element)
+        {
+            output.assert_size(ElementSize {
+                width: Some(output.width.constrain(letter_width * 17.)),
+                height: Some(line_height),
+            });
+        }
+    }
+
+    #[test]
+    fn test_ruby() {
+        let doc = PdfDocument::empty("i contain a font");
+        let courier = BuiltinFont::courier(&doc);
+
+        let span = |text: &str, ruby: Option<&str>| Span {
+            text: text.to_string(),
+            bold: false,
+            italic: false,
+            underline: false,
+            color: 0,
+            underline_color: None,
+            underline_thickness: None,
+            script: None,
+            language: None,
+            markup: None,
+            ruby: ruby.map(str::to_string),
+        };
+
+        let spans = [
+            span("a ", None),
+            span("b", Some("rrrrrr")),
+            span(" cc cc", None),
+        ];
+        let plain = [span("a ", None), span("b", None), span(" cc cc", None)];
+
+        let text_element = |spans| RichText {
+            spans,
+            size: 12.,
+            small_size: 12.,
+            extra_line_height: 0.,
+            line_spacing: LineSpacing::default(),
+            line_breaking: LineBreaking::default(),
+            baseline_grid: None,
+            fonts: FontSet {
+                regular: &courier,
+                bold: &courier,
+                italic: &courier,
+                bold_italic: &courier,
+            },
+            synthetic: SyntheticFaces::default(),
+        };
+
+        let element = |spans| ElementProxy {
+            before_draw: &|ctx: &mut DrawCtx| {
+                ctx.pdf
+                    .document
+                    .add_builtin_font(printpdf::BuiltinFont::Courier)
+                    .unwrap();
+            },
+            ..ElementProxy::new(text_element(spans))
+        };
+
+        let letter_width = 2.5400016;
+        let line_height = 4.466169479999998;
+
+        let params = ElementTestParams {
+            width: letter_width * 20.,
+            ..Default::default()
+        };
+
+        for output in params.run(&element(&plain)) {
+            output.assert_size(ElementSize {
+                width: Some(output.width.constrain(letter_width * 9.)),
+                height: Some(line_height),
+            });
+        }
+
+        // The ruby is half the size, so six letters of it are as wide as three of the text, which
+        // is centered below it. The line gets half a line more for the ruby.
+        for output in params.run(&element(&spans)) {
+            let size = output.size;
+            let width = size.width.unwrap();
+            let height = size.height.unwrap();
+
+            if !output.width.expand {
+                assert!((width - letter_width * 11.).abs() < 1e-9);
+            }
+
+            assert!((height - line_height * 1.5).abs() < 1e-9);
+        }
+
+        // Text with ruby isn't broken and all lines are as high.
+        let spans = [span("a ", None), span("bb bb", Some("r")), span(" c", None)];
+        let measure = text_element(&spans).measure(MeasureCtx {
+            width: WidthConstraint {
+                max: letter_width * 6.,
+                expand: false,
+            },
+            first_height: 100.,
+            breakable: None,
+        });
+
+        assert!((measure.width.unwrap() - letter_width * 5.).abs() < 1e-9);
+        assert!((measure.height.unwrap() - line_height * 4.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_markup() {
+        use crate::annotations::{collect_markup, MarkupKind};
+
+        let highlight = SpanMarkup {
+            kind: MarkupKind::Highlight,
+            color: 0xFF_FF_00_FF,
+        };
+
+        let span = |text: &str, markup: Option<SpanMarkup>| Span {
+            text: text.to_string(),
+            bold: false,
+            italic: false,
+            underline: false,
+            color: 0,
+            underline_color: None,
+            underline_thickness: None,
+            script: None,
+            language: None,
+            markup,
+            ruby: None,
+        };
+
+        let spans = [
+            span("plain ", None),
+            span("marked", Some(highlight)),
+            span(" more", Some(highlight)),
+            span(" plain", None),
+        ];
+
+        let (_, annotations) = collect_markup(|| {
+            crate::build_pdf(
+                "test",
+                (100., 100.),
+                BuiltinFont::courier,
+                |font: &BuiltinFont| RichText {
+                    spans: &spans,
+                    size: 12.,
+                    small_size: 12.,
+                    extra_line_height: 0.,
+                    line_spacing: LineSpacing::default(),
+                    line_breaking: LineBreaking::default(),
+                    baseline_grid: None,
+                    fonts: FontSet {
+                        regular: font,
+                        bold: font,
+                        italic: font,
+                        bold_italic: font,
+                    },
+                    synthetic: SyntheticFaces::default(),
+                },
+            )
+        });
+
+        // Adjacent spans with the same markup end up in the same annotation.
+        assert_eq!(annotations.len(), 1);
+
+        let annotation = &annotations[0];
+        assert_eq!(annotation.kind, MarkupKind::Highlight);
+        assert_eq!(annotation.page, 0);
+        assert_eq!(annotation.quads.len(), 2);
+
+        let [marked, more] = [annotation.quads[0], annotation.quads[1]];
+        let letter_width = 2.5400016;
+
+        assert!((marked.right - marked.left - letter_width * 6.).abs() < 1e-6);
+        assert!((more.left - marked.right).abs() < 1e-6);
+    }
 }
@@ -15,7 +15,36 @@ pub struct Row<F: Fn(&mut RowContent)> {
 
 impl<F: Fn(&mut RowContent)> Element for Row<F> {
     fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
-        FirstLocationUsage::WillUse
+        let mut measure_layout = MeasureLayout::new(ctx.width.max, self.gap);
+
+        (self.content)(&mut RowContent {
+            width: ctx.width,
+            first_height: ctx.first_height,
+            pass: Pass::MeasureNonExpanded {
+                layout: &mut measure_layout,
+                max_height: None,
+                breakable: None,
+            },
+        });
+
+        let draw_layout = measure_layout.build();
+        let mut ret = FirstLocationUsage::NoneHeight;
+
+        (self.content)(&mut RowContent {
+            width: ctx.width,
+            first_height: ctx.first_height,
+            pass: Pass::FirstLocationUsage {
+                layout: &draw_layout,
+                full_height: ctx.full_height,
+                ret: &mut ret,
+            },
+        });
+
+        if !self.collapse && ret == FirstLocationUsage::NoneHeight {
+            FirstLocationUsage::WillUse
+        } else {
+            ret
+        }
     }
 
     fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
@@ -204,7 +233,14 @@ enum Pass<'a, 'b, 'c> {
         breakable: Option<&'a mut BreakableMeasure<'b>>,
     },
 
-    FirstLocationUsage {},
+    FirstLocationUsage {
+        layout: &'a DrawLayout,
+        full_height: f64,
+
+        /// The row uses the first location if any element does and skips it if the others have no
+        /// height.
+        ret: &'a mut FirstLocationUsage,
+    },
 
     MeasureExpanded {
         layout: &'a DrawLayout,
@@ -239,6 +275,23 @@ pub enum Flex {
     Fixed(f64),
 }
 
+fn width_constraint(flex: Flex, layout: &DrawLayout, width: WidthConstraint) -> WidthConstraint {
+    match flex {
+        Flex::Expand(fraction) => WidthConstraint {
+            max: layout.expand_width(fraction),
+            expand: width.expand,
+        },
+        Flex::SelfSized => WidthConstraint {
+            max: width.max,
+            expand: false,
+        },
+        Flex::Fixed(width) => WidthConstraint {
+            max: width,
+            expand: true,
+        },
+    }
+}
+
 fn add_height(
     max_height: &mut Option<f64>,
     breakable: Option<&mut BreakableMeasure>,
@@ -344,6 +397,28 @@ impl<'a, 'b, 'c> RowContent<'a, 'b, 'c> {
                 }
             },
 
+            Pass::FirstLocationUsage {
+                layout,
+                full_height,
+                ret: &mut ref mut ret,
+            } => {
+                let usage = element.first_location_usage(FirstLocationUsageCtx {
+                    width: width_constraint(flex, layout, self.width),
+                    first_height: self.first_height,
+                    full_height,
+                });
+
+                match usage {
+                    FirstLocationUsage::NoneHeight => (),
+                    FirstLocationUsage::WillUse => *ret = FirstLocationUsage::WillUse,
+                    FirstLocationUsage::WillSkip => {
+                        if *ret == FirstLocationUsage::NoneHeight {
+                            *ret = FirstLocationUsage::WillSkip;
+                        }
+                    }
+                }
+            }
+
             Pass::MeasureExpanded {
                 layout,
                 max_height: &mut ref mut max_height,
@@ -405,20 +480,14 @@ impl<'a, 'b, 'c> RowContent<'a, 'b, 'c> {
                 ref mut break_count,
                 ref mut breakable,
             } => {
-                let width_constraint = match flex {
-                    Flex::Expand(fraction) => WidthConstraint {
-                        max: layout.expand_width(fraction),
+                let width_constraint = width_constraint(
+                    flex,
+                    layout,
+                    WidthConstraint {
                         expand: width_expand,
+                        ..self.width
                     },
-                    Flex::SelfSized => WidthConstraint {
-                        max: self.width.max,
-                        expand: false,
-                    },
-                    Flex::Fixed(width) => WidthConstraint {
-                        max: width,
-                        expand: true,
-                    },
-                };
+                );
 
                 let mut element_break_count = 0;
 
@@ -513,8 +582,6 @@ impl<'a, 'b, 'c> RowContent<'a, 'b, 'c> {
                     }
                 }
             }
-
-            _ => todo!(),
         }
     }
 }
@@ -914,4 +981,72 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_row_first_location_usage() {
+        let text = FakeText {
+            lines: 2,
+            line_height: 5.,
+            width: 3.,
+        };
+
+        let rectangle = Rectangle {
+            size: (5., 2.),
+            fill: None,
+            outline: None,
+        };
+
+        let ctx = || FirstLocationUsageCtx {
+            width: WidthConstraint {
+                max: 20.,
+                expand: false,
+            },
+            first_height: 3.,
+            full_height: 20.,
+        };
+
+        let (text, rectangle) = (&text, &rectangle);
+
+        let row = |collapse, with_rectangle| Row {
+            gap: 1.,
+            expand: false,
+            collapse,
+            content: move |content: &mut RowContent| {
+                content.add(&NoneElement, Flex::SelfSized);
+                content.add(text, Flex::Expand(1));
+
+                if with_rectangle {
+                    content.add(rectangle, Flex::Fixed(5.));
+                }
+
+                content.add(text, Flex::SelfSized);
+            },
+        };
+
+        // The text doesn't fit a line into the first location.
+        assert_eq!(
+            row(true, false).first_location_usage(ctx()),
+            FirstLocationUsage::WillSkip
+        );
+        assert_eq!(
+            row(true, true).first_location_usage(ctx()),
+            FirstLocationUsage::WillUse
+        );
+
+        let empty = |collapse| Row {
+            gap: 1.,
+            expand: false,
+            collapse,
+            content: |content: &mut RowContent| content.add(&NoneElement, Flex::Expand(1)),
+        };
+
+        assert_eq!(
+            empty(true).first_location_usage(ctx()),
+            FirstLocationUsage::NoneHeight
+        );
+        assert_eq!(
+            empty(false).first_location_usage(ctx()),
+            FirstLocationUsage::WillUse
+        );
+    }
 }
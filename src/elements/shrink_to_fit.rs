@@ -308,6 +308,9 @@ mod tests {
                         color: 0x00_00_00_FF,
                         dash_pattern: None,
                         cap_style: LineCapStyle::Round,
+                        join_style: LineJoinStyle::Miter,
+                        miter_limit: DEFAULT_MITER_LIMIT,
+                        rendering: None,
                     }),
                     ..StyledBox::new(text)
                 };
@@ -349,6 +352,9 @@ mod tests {
                         color: 0x00_00_00_FF,
                         dash_pattern: None,
                         cap_style: LineCapStyle::Round,
+                        join_style: LineJoinStyle::Miter,
+                        miter_limit: DEFAULT_MITER_LIMIT,
+                        rendering: None,
                     }),
                     ..StyledBox::new(text)
                 };
@@ -364,6 +370,9 @@ mod tests {
                         color: 0xAA_00_00_FF,
                         dash_pattern: None,
                         cap_style: LineCapStyle::Round,
+                        join_style: LineJoinStyle::Miter,
+                        miter_limit: DEFAULT_MITER_LIMIT,
+                        rendering: None,
                     }),
                     ..StyledBox::new(&shrink_to_fit)
                 };
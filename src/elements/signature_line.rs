@@ -0,0 +1,143 @@
+use crate::{fonts::Font, *};
+
+use super::{
+    break_whole::BreakWhole,
+    column::Column,
+    line::Line,
+    padding::Padding,
+    row::{Flex, Row},
+    text::Text,
+};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignatureLayout {
+    /// Each label on its own line below the signature line.
+    Stacked,
+
+    /// All labels next to each other below the signature line. The date is aligned to the right.
+    Row,
+}
+
+/// A line to sign on with labels for the name, title and date below it. The block never gets
+/// broken across pages.
+pub struct SignatureLine<'a, F: Font> {
+    pub line_style: LineStyle,
+
+    /// The empty space above the line for the signature itself.
+    pub space: f64,
+
+    pub name: Option<&'a str>,
+    pub title: Option<&'a str>,
+    pub date: Option<&'a str>,
+
+    pub font: &'a F,
+    pub size: f64,
+    pub color: u32,
+
+    /// The gap between the line and the labels and between the labels themselves.
+    pub gap: f64,
+
+    pub layout: SignatureLayout,
+}
+
+impl<'a, F: Font> SignatureLine<'a, F> {
+    fn label(&self, text: &'a str) -> Text<'a, F> {
+        Text {
+            color: self.color,
+            ..Text::basic(text, self.font, self.size)
+        }
+    }
+}
+
+impl<'a, F: Font> CompositeElement for SignatureLine<'a, F> {
+    fn element(&self, callback: impl CompositeElementCallback) {
+        let labels = [self.name, self.title, self.date];
+
+        callback.call(&BreakWhole(&Column {
+            gap: self.gap,
+            collapse: false,
+            ..Column::new(|content| {
+                let mut content = content.add(&Padding::top(
+                    self.space,
+                    &Line {
+                        style: self.line_style,
+                    },
+                ))?;
+
+                match self.layout {
+                    SignatureLayout::Stacked => {
+                        for label in labels.into_iter().flatten() {
+                            content = content.add(&self.label(label))?;
+                        }
+                    }
+                    SignatureLayout::Row if labels.iter().any(Option::is_some) => {
+                        content = content.add(&Row {
+                            gap: self.gap,
+                            expand: false,
+                            collapse: true,
+                            content: |content| {
+                                for label in [self.name, self.title].into_iter().flatten() {
+                                    content.add(&self.label(label), Flex::Expand(1));
+                                }
+
+                                if let Some(date) = self.date {
+                                    content.add(&self.label(date), Flex::SelfSized);
+                                }
+                            },
+                        })?;
+                    }
+                    SignatureLayout::Row => (),
+                }
+
+                Option::None
+            })
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fonts::builtin::BuiltinFont, test_utils::*};
+
+    #[test]
+    fn test_signature_line() {
+        let doc = printpdf::PdfDocument::empty("i contain a font");
+        let font = BuiltinFont::courier(&doc);
+
+        for layout in [SignatureLayout::Stacked, SignatureLayout::Row] {
+            let element = SignatureLine {
+                line_style: LineStyle {
+                    thickness: 0.2,
+                    color: 0x00_00_00_FF,
+                    dash_pattern: None,
+                    cap_style: LineCapStyle::Butt,
+                    join_style: LineJoinStyle::Miter,
+                    miter_limit: DEFAULT_MITER_LIMIT,
+                    rendering: None,
+                },
+                space: 10.,
+                name: Some("Jane Doe"),
+                title: Some("CEO"),
+                date: Some("2024-01-31"),
+                font: &font,
+                size: 10.,
+                color: 0x00_00_00_FF,
+                gap: 1.,
+                layout,
+            };
+
+            for output in ElementTestParams::default().run(&element) {
+                output.assert_no_breaks();
+
+                // Stacked has three lines of text below the line, Row only one.
+                let height = output.size.height.unwrap();
+
+                match layout {
+                    SignatureLayout::Stacked => assert!(height > 10.2 + 3. * 3.),
+                    SignatureLayout::Row => assert!(height > 10.2 + 3. && height < 10.2 + 8.),
+                }
+            }
+        }
+    }
+}
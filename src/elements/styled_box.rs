@@ -1,8 +1,24 @@
 use crate::{
-    utils::{mm_to_pt, u32_to_color_and_alpha},
+    line_rendering::LineRendering,
+    utils::{add_op, add_path, mm_to_pt, set_line_style, u32_to_color_and_alpha},
     *,
 };
 
+/// How a [StyledBox] looks where its content breaks.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BoxBreak {
+    /// Every location gets a complete box.
+    #[default]
+    Closed,
+
+    /// The edges at the breaks are left out and the corners next to them are square, so the
+    /// pieces look like one box that was cut apart.
+    Open,
+
+    /// Like [Self::Open], but the edges at the breaks are drawn dashed.
+    Dashed,
+}
+
 pub struct StyledBox<'a, E: Element> {
     pub element: &'a E,
     pub padding_left: f64,
@@ -12,6 +28,7 @@ pub struct StyledBox<'a, E: Element> {
     pub border_radius: f64,
     pub fill: Option<u32>,
     pub outline: Option<LineStyle>,
+    pub break_style: BoxBreak,
 }
 
 impl<'a, E: Element> StyledBox<'a, E> {
@@ -25,6 +42,7 @@ impl<'a, E: Element> StyledBox<'a, E> {
             border_radius: 0.,
             fill: None,
             outline: None,
+            break_style: BoxBreak::Closed,
         }
     }
 }
@@ -88,8 +106,17 @@ impl<'a, E: Element> StyledBox<'a, E> {
         }
     }
 
-    fn draw_box(&self, location: &Location, size: (f64, f64)) {
-        use kurbo::{PathEl, RoundedRect, Shape};
+    /// `open_top` and `open_bottom` are whether the box continues from the previous location or
+    /// on the next one.
+    fn draw_box(
+        &self,
+        location: &Location,
+        size: (f64, f64),
+        open_top: bool,
+        open_bottom: bool,
+        line_rendering: LineRendering,
+    ) {
+        use kurbo::RoundedRect;
         use lopdf::content::Operation;
         use printpdf::LineDashPattern;
 
@@ -101,12 +128,11 @@ impl<'a, E: Element> StyledBox<'a, E> {
         let thickness = self.outline.map(|o| o.thickness).unwrap_or(0.);
         let half_thickness = thickness / 2.;
 
-        let shape = RoundedRect::new(
+        let (x0, top, x1, bottom) = (
             mm_to_pt(location.pos.0 + half_thickness),
             mm_to_pt(location.pos.1 - half_thickness),
             mm_to_pt(location.pos.0 + size.0 + thickness + half_thickness),
             mm_to_pt(location.pos.1 - size.1 - thickness - half_thickness),
-            mm_to_pt(self.border_radius),
         );
 
         let layer = &location.layer;
@@ -120,61 +146,212 @@ impl<'a, E: Element> StyledBox<'a, E> {
         }
 
         if let Some(line_style) = self.outline {
-            // No outline alpha?
-            let (color, _alpha) = u32_to_color_and_alpha(line_style.color);
-            layer.set_outline_color(color);
-            layer.set_outline_thickness(mm_to_pt(line_style.thickness));
-            layer.set_line_cap_style(line_style.cap_style.into());
-            layer.set_line_dash_pattern(if let Some(pattern) = line_style.dash_pattern {
-                pattern.into()
-            } else {
-                LineDashPattern::default()
-            });
+            set_line_style(layer, &line_style, line_rendering);
         }
 
-        let els = shape.path_elements(0.1);
+        let open_top = open_top && self.break_style != BoxBreak::Closed;
+        let open_bottom = open_bottom && self.break_style != BoxBreak::Closed;
 
-        let mut closed = false;
+        if !open_top && !open_bottom {
+            let shape = RoundedRect::new(x0, top, x1, bottom, mm_to_pt(self.border_radius));
+            let closed = add_path(layer, &shape);
 
-        for el in els {
-            use PathEl::*;
+            match (self.outline.is_some(), self.fill.is_some(), closed) {
+                (true, true, true) => add_op(layer, Operation::new("b", Vec::new())),
+                (true, true, false) => add_op(layer, Operation::new("f", Vec::new())),
+                (true, false, true) => add_op(layer, Operation::new("s", Vec::new())),
+                (true, false, false) => add_op(layer, Operation::new("S", Vec::new())),
+                (false, true, _) => add_op(layer, Operation::new("f", Vec::new())),
+                _ => add_op(layer, Operation::new("n", Vec::new())),
+            }
+        } else {
+            let radius = mm_to_pt(self.border_radius)
+                .min((x1 - x0).abs() / 2.)
+                .min((top - bottom).abs() / 2.);
+
+            let edges = OpenEdges {
+                x0,
+                x1,
+                top,
+                bottom,
+                radius,
+                open_top,
+                open_bottom,
+            };
 
-            match el {
-                MoveTo(point) => {
-                    layer.add_op(Operation::new("m", vec![point.x.into(), point.y.into()]))
-                }
-                LineTo(point) => {
-                    layer.add_op(Operation::new("l", vec![point.x.into(), point.y.into()]))
+            if self.fill.is_some() {
+                add_path(layer, &edges.fill());
+                add_op(layer, Operation::new("f", Vec::new()));
+            }
+
+            if self.outline.is_some() {
+                add_path(layer, &edges.outline());
+                add_op(layer, Operation::new("S", Vec::new()));
+
+                if self.break_style == BoxBreak::Dashed {
+                    layer.set_line_dash_pattern(LineDashPattern {
+                        dash_1: Some(3),
+                        gap_1: Some(2),
+                        ..LineDashPattern::default()
+                    });
+
+                    let mut path = kurbo::BezPath::new();
+
+                    for (y, open) in [(top, open_top), (bottom, open_bottom)] {
+                        if open {
+                            path.move_to((x0, y));
+                            path.line_to((x1, y));
+                        }
+                    }
+
+                    add_path(layer, &path);
+                    add_op(layer, Operation::new("S", Vec::new()));
                 }
-                QuadTo(a, b) => layer.add_op(
-                    // i dunno
-                    Operation::new("v", vec![a.x.into(), a.y.into(), b.x.into(), b.y.into()]),
-                ),
-                CurveTo(a, b, c) => layer.add_op(Operation::new(
-                    "c",
-                    vec![
-                        a.x.into(),
-                        a.y.into(),
-                        b.x.into(),
-                        b.y.into(),
-                        c.x.into(),
-                        c.y.into(),
-                    ],
-                )),
-                ClosePath => closed = true,
-            };
+            }
         }
 
-        match (self.outline.is_some(), self.fill.is_some(), closed) {
-            (true, true, true) => layer.add_op(Operation::new("b", Vec::new())),
-            (true, true, false) => layer.add_op(Operation::new("f", Vec::new())),
-            (true, false, true) => layer.add_op(Operation::new("s", Vec::new())),
-            (true, false, false) => layer.add_op(Operation::new("S", Vec::new())),
-            (false, true, _) => layer.add_op(Operation::new("f", Vec::new())),
-            _ => layer.add_op(Operation::new("n", Vec::new())),
+        location.layer.restore_graphics_state();
+    }
+}
+
+/// The paths of a box with the top and/or bottom edge left out, in points with y going up.
+struct OpenEdges {
+    x0: f64,
+    x1: f64,
+    top: f64,
+    bottom: f64,
+    radius: f64,
+    open_top: bool,
+    open_bottom: bool,
+}
+
+impl OpenEdges {
+    fn top_radius(&self) -> f64 {
+        if self.open_top {
+            0.
+        } else {
+            self.radius
         }
+    }
 
-        location.layer.restore_graphics_state();
+    fn bottom_radius(&self) -> f64 {
+        if self.open_bottom {
+            0.
+        } else {
+            self.radius
+        }
+    }
+
+    /// The whole area, with square corners at the open edges.
+    fn fill(&self) -> kurbo::BezPath {
+        let mut path = kurbo::BezPath::new();
+
+        path.move_to((self.x0, self.bottom + self.bottom_radius()));
+        corner(
+            &mut path,
+            (self.x0, self.top),
+            (0., 1.),
+            (1., 0.),
+            self.top_radius(),
+        );
+        corner(
+            &mut path,
+            (self.x1, self.top),
+            (1., 0.),
+            (0., -1.),
+            self.top_radius(),
+        );
+        corner(
+            &mut path,
+            (self.x1, self.bottom),
+            (0., -1.),
+            (-1., 0.),
+            self.bottom_radius(),
+        );
+        corner(
+            &mut path,
+            (self.x0, self.bottom),
+            (-1., 0.),
+            (0., 1.),
+            self.bottom_radius(),
+        );
+        path.close_path();
+
+        path
+    }
+
+    /// The edges that aren't open. The path starts after an open edge, so that the rest is one
+    /// line.
+    fn outline(&self) -> kurbo::BezPath {
+        let mut path = kurbo::BezPath::new();
+
+        match (self.open_top, self.open_bottom) {
+            (true, true) => {
+                path.move_to((self.x0, self.bottom));
+                path.line_to((self.x0, self.top));
+                path.move_to((self.x1, self.top));
+                path.line_to((self.x1, self.bottom));
+            }
+            (false, true) => {
+                path.move_to((self.x0, self.bottom));
+                corner(
+                    &mut path,
+                    (self.x0, self.top),
+                    (0., 1.),
+                    (1., 0.),
+                    self.radius,
+                );
+                corner(
+                    &mut path,
+                    (self.x1, self.top),
+                    (1., 0.),
+                    (0., -1.),
+                    self.radius,
+                );
+                path.line_to((self.x1, self.bottom));
+            }
+            (true, false) => {
+                path.move_to((self.x1, self.top));
+                corner(
+                    &mut path,
+                    (self.x1, self.bottom),
+                    (0., -1.),
+                    (-1., 0.),
+                    self.radius,
+                );
+                corner(
+                    &mut path,
+                    (self.x0, self.bottom),
+                    (-1., 0.),
+                    (0., 1.),
+                    self.radius,
+                );
+                path.line_to((self.x0, self.top));
+            }
+            (false, false) => unreachable!(),
+        }
+
+        path
+    }
+}
+
+/// Adds a line up to the corner and a quarter circle around it, going in the direction `from` and
+/// leaving in the direction `to`.
+fn corner(path: &mut kurbo::BezPath, (x, y): (f64, f64), from: (f64, f64), to: (f64, f64), r: f64) {
+    // The usual distance of the control points for approximating a quarter circle with a cubic.
+    const K: f64 = 0.5522847498;
+
+    let start = (x - from.0 * r, y - from.1 * r);
+    let end = (x + to.0 * r, y + to.1 * r);
+
+    path.line_to(start);
+
+    if r > 0. {
+        path.curve_to(
+            (start.0 + from.0 * r * K, start.1 + from.1 * r * K),
+            (end.0 - to.0 * r * K, end.1 - to.1 * r * K),
+            end,
+        );
     }
 }
 
@@ -227,6 +404,7 @@ impl<'a, E: Element> Element for StyledBox<'a, E> {
     fn draw(&self, ctx: DrawCtx) -> ElementSize {
         let common = self.common(ctx.width);
         let first_height = common.height(ctx.first_height);
+        let line_rendering = ctx.pdf.line_rendering;
 
         let size = if let Some(breakable) = ctx.breakable {
             let full_height = common.height(breakable.full_height);
@@ -254,6 +432,10 @@ impl<'a, E: Element> Element for StyledBox<'a, E> {
 
             let element_location = common.location(ctx.pdf, &ctx.location);
             let mut last_location = ctx.location;
+
+            // Whether a piece of the box was drawn on an earlier location.
+            let mut drawn = false;
+
             let size = self.element.draw(DrawCtx {
                 pdf: ctx.pdf,
                 location: element_location,
@@ -278,7 +460,14 @@ impl<'a, E: Element> Element for StyledBox<'a, E> {
                                     &(breakable.do_break)(pdf, location_idx, None)
                                 };
 
-                                self.draw_box(location, (width, height));
+                                self.draw_box(
+                                    location,
+                                    (width, height),
+                                    drawn,
+                                    true,
+                                    line_rendering,
+                                );
+                                drawn = true;
                             }
                             _ => (),
                         }
@@ -293,7 +482,13 @@ impl<'a, E: Element> Element for StyledBox<'a, E> {
             });
 
             if let (Some(width), Some(height)) = (width, size.height) {
-                self.draw_box(&last_location, (width, height));
+                self.draw_box(
+                    &last_location,
+                    (width, height),
+                    drawn,
+                    false,
+                    line_rendering,
+                );
             }
 
             size
@@ -314,7 +509,7 @@ impl<'a, E: Element> Element for StyledBox<'a, E> {
                 height: Some(height),
             } = size
             {
-                self.draw_box(&ctx.location, (width, height));
+                self.draw_box(&ctx.location, (width, height), false, false, line_rendering);
             }
 
             size
@@ -499,7 +694,7 @@ mod tests {
             let first = Rectangle {
                 size: (12., 12.),
                 fill: Some(0x00_00_77_FF),
-                outline: Some((2., 0x00_00_00_FF)),
+                outline: Some(LineStyle::solid(2., 0x00_00_00_FF)),
             };
             let first = first.debug(1).show_max_width();
 
@@ -517,6 +712,9 @@ mod tests {
                         color: 0x00_00_00_FF,
                         dash_pattern: None,
                         cap_style: LineCapStyle::Butt,
+                        join_style: LineJoinStyle::Miter,
+                        miter_limit: DEFAULT_MITER_LIMIT,
+                        rendering: None,
                     }),
                 }
                 .debug(0)
@@ -547,6 +745,9 @@ mod tests {
                         color: 0x00_00_00_FF,
                         dash_pattern: None,
                         cap_style: LineCapStyle::Butt,
+                        join_style: LineJoinStyle::Miter,
+                        miter_limit: DEFAULT_MITER_LIMIT,
+                        rendering: None,
                     }),
                     ..StyledBox::new(&first)
                 }
@@ -557,4 +758,69 @@ mod tests {
         });
         assert_binary_snapshot!(".pdf", bytes);
     }
+
+    #[test]
+    fn test_open_edges() {
+        use kurbo::{PathEl, Point};
+
+        let edges = |open_top, open_bottom| OpenEdges {
+            x0: 0.,
+            x1: 10.,
+            top: 20.,
+            bottom: 0.,
+            radius: 2.,
+            open_top,
+            open_bottom,
+        };
+
+        let ends = |path: kurbo::BezPath| {
+            let moves = path
+                .elements()
+                .iter()
+                .filter(|el| matches!(el, PathEl::MoveTo(_)))
+                .count();
+
+            let first = match path.elements().first() {
+                Some(&PathEl::MoveTo(p)) => p,
+                _ => unreachable!(),
+            };
+
+            let last = match path.elements().last() {
+                Some(&PathEl::LineTo(p)) => p,
+                _ => unreachable!(),
+            };
+
+            (moves, first, last)
+        };
+
+        // Without the bottom edge, the outline goes around the top from one bottom corner to the
+        // other.
+        assert_eq!(
+            ends(edges(false, true).outline()),
+            (1, Point::new(0., 0.), Point::new(10., 0.)),
+        );
+
+        assert_eq!(
+            ends(edges(true, false).outline()),
+            (1, Point::new(10., 20.), Point::new(0., 20.)),
+        );
+
+        // With both open, only the sides are left.
+        assert_eq!(
+            ends(edges(true, true).outline()),
+            (2, Point::new(0., 0.), Point::new(10., 0.)),
+        );
+
+        // The corners at the open edge are square, the others are rounded.
+        let curves = |path: kurbo::BezPath| {
+            path.elements()
+                .iter()
+                .filter(|el| matches!(el, PathEl::CurveTo(..)))
+                .count()
+        };
+
+        assert_eq!(curves(edges(false, true).fill()), 2);
+        assert_eq!(curves(edges(true, true).fill()), 0);
+        assert_eq!(curves(edges(false, true).outline()), 2);
+    }
 }
@@ -56,7 +56,9 @@ impl<'a> Element for Svg<'a> {
         ));
         layer.set_ctm(CurTransMat::Scale(view_box_scale[0], view_box_scale[1]));
 
-        layer.add_svg(&self.data);
+        if crate::budget::add_content(content_size(self.data)) {
+            layer.add_svg(&self.data);
+        }
 
         layer.restore_graphics_state();
 
@@ -64,6 +66,18 @@ impl<'a> Element for Svg<'a> {
     }
 }
 
+/// Roughly how many bytes the paths of the SVG take up in a content stream, for the budget, see
+/// [crate::budget].
+fn content_size(data: &usvg::Tree) -> u64 {
+    data.root()
+        .descendants()
+        .map(|node| match &*node.borrow() {
+            usvg::NodeKind::Path(path) => 20 * path.data.len() as u64 + 32,
+            _ => 16,
+        })
+        .sum()
+}
+
 #[inline]
 fn calculate_size(data: &usvg::Tree, width: WidthConstraint) -> (f64, f64, ElementSize) {
     let svg = data.svg_node();
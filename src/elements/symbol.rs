@@ -0,0 +1,309 @@
+use std::f64::consts::PI;
+
+use printpdf::{utils::calculate_points_for_rect, Line, Point};
+
+use crate::{utils::*, *};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SymbolKind {
+    Checkbox,
+    CheckboxChecked,
+    CheckboxCrossed,
+    Check,
+    Cross,
+    Dash,
+}
+
+/// Small vector symbols for forms and checklists, so that they don't depend on a dingbat font.
+/// The symbol is a square of `size` millimeters drawn with strokes of `thickness`.
+pub struct Symbol {
+    pub kind: SymbolKind,
+    pub size: f64,
+    pub thickness: f64,
+    pub color: u32,
+}
+
+impl Element for Symbol {
+    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        ctx.break_if_appropriate_for_min_height(self.size);
+
+        self.element_size()
+    }
+
+    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+        ctx.break_if_appropriate_for_min_height(self.size);
+
+        let layer = &ctx.location.layer;
+        let pos = ctx.location.pos;
+        let size = self.size;
+
+        // Takes coordinates in the range 0 to 1 with y going down.
+        let point = |x: f64, y: f64| {
+            (
+                Point::new(Mm(pos.0 + x * size), Mm(pos.1 - y * size)),
+                false,
+            )
+        };
+
+        let stroke = |points: Vec<(Point, bool)>| {
+            add_shape(
+                layer,
+                Line {
+                    points,
+                    is_closed: false,
+                    has_fill: false,
+                    has_stroke: true,
+                    is_clipping_path: false,
+                },
+            );
+        };
+
+        layer.save_graphics_state();
+
+        let (color, _alpha) = u32_to_color_and_alpha(self.color);
+        layer.set_outline_color(color);
+        layer.set_outline_thickness(mm_to_pt(self.thickness));
+        layer.set_line_cap_style(printpdf::LineCapStyle::Round);
+        layer.set_line_join_style(printpdf::LineJoinStyle::Round);
+
+        let checkbox = matches!(
+            self.kind,
+            SymbolKind::Checkbox | SymbolKind::CheckboxChecked | SymbolKind::CheckboxCrossed
+        );
+
+        if checkbox {
+            let inner = size - self.thickness;
+
+            add_shape(
+                layer,
+                Line {
+                    points: calculate_points_for_rect(
+                        Mm(inner),
+                        Mm(inner),
+                        Mm(pos.0 + size / 2.),
+                        Mm(pos.1 - size / 2.),
+                    ),
+                    is_closed: true,
+                    has_fill: false,
+                    has_stroke: true,
+                    is_clipping_path: false,
+                },
+            );
+        }
+
+        match self.kind {
+            SymbolKind::Checkbox => (),
+            SymbolKind::Check | SymbolKind::CheckboxChecked => {
+                stroke(vec![point(0.2, 0.55), point(0.42, 0.75), point(0.8, 0.28)])
+            }
+            SymbolKind::Cross | SymbolKind::CheckboxCrossed => {
+                let (a, b) = if checkbox { (0.25, 0.75) } else { (0.15, 0.85) };
+
+                stroke(vec![point(a, a), point(b, b)]);
+                stroke(vec![point(a, b), point(b, a)]);
+            }
+            SymbolKind::Dash => stroke(vec![point(0.15, 0.5), point(0.85, 0.5)]),
+        }
+
+        layer.restore_graphics_state();
+
+        self.element_size()
+    }
+}
+
+impl Symbol {
+    fn element_size(&self) -> ElementSize {
+        ElementSize {
+            width: Some(self.size),
+            height: Some(self.size),
+        }
+    }
+}
+
+/// A row of `max` five pointed stars, of which the first `rating` are filled. Half stars are drawn
+/// for ratings ending in .5 or more, everything else gets rounded down.
+pub struct StarRating {
+    pub rating: f64,
+    pub max: u8,
+
+    /// The width and height of a single star.
+    pub size: f64,
+
+    pub gap: f64,
+    pub color: u32,
+
+    /// The outline of the empty stars.
+    pub thickness: f64,
+}
+
+impl Element for StarRating {
+    fn measure(&self, mut ctx: MeasureCtx) -> ElementSize {
+        ctx.break_if_appropriate_for_min_height(self.size);
+
+        self.element_size()
+    }
+
+    fn draw(&self, mut ctx: DrawCtx) -> ElementSize {
+        ctx.break_if_appropriate_for_min_height(self.size);
+
+        let layer = &ctx.location.layer;
+
+        layer.save_graphics_state();
+
+        let (color, alpha) = u32_to_color_and_alpha(self.color);
+        layer.set_outline_color(color.clone());
+        layer.set_fill_color(color);
+        layer.set_fill_alpha(alpha);
+        layer.set_outline_thickness(mm_to_pt(self.thickness));
+        layer.set_line_join_style(printpdf::LineJoinStyle::Round);
+
+        let half_stars = (self.rating * 2.).floor().max(0.) as u32;
+
+        for i in 0..self.max as u32 {
+            let x = ctx.location.pos.0 + i as f64 * (self.size + self.gap);
+            let y = ctx.location.pos.1;
+
+            let star = || star_points(x, y, self.size);
+
+            if half_stars >= (i + 1) * 2 {
+                add_shape(
+                    layer,
+                    Line {
+                        points: star(),
+                        is_closed: true,
+                        has_fill: true,
+                        has_stroke: true,
+                        is_clipping_path: false,
+                    },
+                );
+            } else {
+                if half_stars == i * 2 + 1 {
+                    layer.save_graphics_state();
+
+                    add_shape(
+                        layer,
+                        Line {
+                            points: calculate_points_for_rect(
+                                Mm(self.size / 2.),
+                                Mm(self.size),
+                                Mm(x + self.size / 4.),
+                                Mm(y - self.size / 2.),
+                            ),
+                            is_closed: true,
+                            has_fill: false,
+                            has_stroke: false,
+                            is_clipping_path: true,
+                        },
+                    );
+
+                    add_shape(
+                        layer,
+                        Line {
+                            points: star(),
+                            is_closed: true,
+                            has_fill: true,
+                            has_stroke: false,
+                            is_clipping_path: false,
+                        },
+                    );
+
+                    layer.restore_graphics_state();
+                }
+
+                add_shape(
+                    layer,
+                    Line {
+                        points: star(),
+                        is_closed: true,
+                        has_fill: false,
+                        has_stroke: true,
+                        is_clipping_path: false,
+                    },
+                );
+            }
+        }
+
+        layer.restore_graphics_state();
+
+        self.element_size()
+    }
+}
+
+impl StarRating {
+    fn element_size(&self) -> ElementSize {
+        if self.max == 0 {
+            return ElementSize {
+                width: None,
+                height: None,
+            };
+        }
+
+        ElementSize {
+            width: Some(self.max as f64 * self.size + (self.max - 1) as f64 * self.gap),
+            height: Some(self.size),
+        }
+    }
+}
+
+/// The points of a five pointed star in the square of `size` at the top left corner `(x, y)`.
+fn star_points(x: f64, y: f64, size: f64) -> Vec<(Point, bool)> {
+    let outer = size / 2.;
+
+    // The ratio of a regular pentagram.
+    let inner = outer * 0.381966;
+
+    let center = (x + outer, y - outer);
+
+    (0..10)
+        .map(|i| {
+            let radius = if i % 2 == 0 { outer } else { inner };
+            let angle = PI / 2. + i as f64 * PI / 5.;
+
+            (
+                Point::new(
+                    Mm(center.0 + radius * angle.cos()),
+                    Mm(center.1 + radius * angle.sin()),
+                ),
+                false,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_symbol() {
+        for output in ElementTestParams::default().run(&Symbol {
+            kind: SymbolKind::CheckboxChecked,
+            size: 4.,
+            thickness: 0.3,
+            color: 0x00_00_00_FF,
+        }) {
+            output.assert_size(ElementSize {
+                width: Some(4.),
+                height: Some(4.),
+            });
+        }
+    }
+
+    #[test]
+    fn test_star_rating() {
+        for output in ElementTestParams::default().run(&StarRating {
+            rating: 3.5,
+            max: 5,
+            size: 4.,
+            gap: 1.,
+            color: 0xFF_CC_00_FF,
+            thickness: 0.2,
+        }) {
+            output.assert_size(ElementSize {
+                width: Some(24.),
+                height: Some(4.),
+            });
+        }
+    }
+}
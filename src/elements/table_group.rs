@@ -0,0 +1,201 @@
+use crate::*;
+
+use super::{changing_title::ChangingTitle, pin_below::PinBelow};
+
+/// A group of table rows with a header. After a page break inside of the group the continued
+/// header is shown instead (e.g. with a "(continued)" suffix). The optional footer is kept together
+/// with the last part of the content. Groups can be nested for multi-level grouping.
+pub struct TableGroup<'a, H: Element, R: Element, C: Element, F: Element> {
+    pub header: &'a H,
+    pub continued_header: &'a R,
+    pub content: &'a C,
+    pub footer: Option<&'a F>,
+    pub gap: f64,
+}
+
+impl<'a, H: Element, R: Element, C: Element, F: Element> CompositeElement
+    for TableGroup<'a, H, R, C, F>
+{
+    fn element(&self, callback: impl CompositeElementCallback) {
+        if let Some(footer) = self.footer {
+            callback.call(&ChangingTitle {
+                first_title: self.header,
+                remaining_title: self.continued_header,
+                content: &PinBelow {
+                    content: self.content,
+                    pinned_element: footer,
+                    gap: self.gap,
+                    collapse: false,
+                },
+                gap: self.gap,
+                collapse: false,
+            });
+        } else {
+            callback.call(&ChangingTitle {
+                first_title: self.header,
+                remaining_title: self.continued_header,
+                content: self.content,
+                gap: self.gap,
+                collapse: false,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, collections::BTreeSet};
+
+    use super::*;
+    use crate::{elements::none::NoneElement, test_utils::*};
+
+    /// Records the pages the element is drawn on.
+    struct Pages<'a, E: Element> {
+        element: &'a E,
+        pages: RefCell<BTreeSet<usize>>,
+    }
+
+    impl<'a, E: Element> Pages<'a, E> {
+        fn new(element: &'a E) -> Self {
+            Pages {
+                element,
+                pages: RefCell::new(BTreeSet::new()),
+            }
+        }
+
+        fn take(&self) -> Vec<usize> {
+            self.pages.take().into_iter().collect()
+        }
+    }
+
+    impl<'a, E: Element> Element for Pages<'a, E> {
+        fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+            self.element.first_location_usage(ctx)
+        }
+
+        fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+            self.element.measure(ctx)
+        }
+
+        fn draw(&self, ctx: DrawCtx) -> ElementSize {
+            self.pages.borrow_mut().insert(ctx.location.layer.page.0);
+            self.element.draw(ctx)
+        }
+    }
+
+    #[test]
+    fn test_table_group() {
+        let header = FakeText {
+            lines: 1,
+            line_height: 1.,
+            width: 3.,
+        };
+
+        let content = FakeText {
+            lines: 3,
+            line_height: 1.,
+            width: 4.,
+        };
+
+        let footer = FakeText {
+            lines: 1,
+            line_height: 1.,
+            width: 2.,
+        };
+
+        for output in ElementTestParams::default().run(&TableGroup {
+            header: &header,
+            continued_header: &header,
+            content: &content,
+            footer: Some(&footer),
+            gap: 0.5,
+        }) {
+            output.assert_no_breaks().assert_size(ElementSize {
+                width: Some(output.width.constrain(4.)),
+                height: Some(6.),
+            });
+        }
+
+        for output in ElementTestParams::default().run(&TableGroup {
+            header: &header,
+            continued_header: &header,
+            content: &content,
+            footer: None::<&NoneElement>,
+            gap: 0.5,
+        }) {
+            output.assert_no_breaks().assert_size(ElementSize {
+                width: Some(output.width.constrain(4.)),
+                height: Some(4.5),
+            });
+        }
+    }
+
+    #[test]
+    fn test_table_group_breaking() {
+        let line = |width| FakeText {
+            lines: 1,
+            line_height: 1.,
+            width,
+        };
+
+        let (header, continued_header, footer) = (line(3.), line(3.), line(2.));
+        let header = Pages::new(&header);
+        let continued_header = Pages::new(&continued_header);
+        let footer = Pages::new(&footer);
+
+        let content = FakeText {
+            lines: 10,
+            line_height: 1.,
+            width: 4.,
+        };
+
+        let element = TableGroup {
+            header: &header,
+            continued_header: &continued_header,
+            content: &content,
+            footer: Some(&footer),
+            gap: 0.5,
+        };
+
+        let params = ElementTestParams {
+            width: 10.,
+            first_height: 4.,
+            full_height: 6.,
+            ..Default::default()
+        };
+
+        for output in params.run(&element) {
+            let width = Some(output.width.constrain(4.));
+
+            if let Some(b) = output.breakable {
+                // Between the header and the height reserved for the footer, a full location has
+                // room for 3 lines of content and the smaller first one for a single line.
+                b.assert_break_count(3);
+
+                output.assert_size(ElementSize {
+                    width,
+                    height: Some(if output.first_height == 4. {
+                        // Three lines, with the header and the footer.
+                        6.
+                    } else {
+                        // The last line, with the header and the footer.
+                        4.
+                    }),
+                });
+
+                assert_eq!(header.take(), [0]);
+                assert_eq!(continued_header.take(), [1, 2, 3]);
+                assert_eq!(footer.take(), [3]);
+            } else {
+                output.assert_size(ElementSize {
+                    width,
+                    height: Some(13.),
+                });
+
+                assert_eq!(header.take(), [0]);
+                assert!(continued_header.take().is_empty());
+                assert_eq!(footer.take(), [0]);
+            }
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use crate::{
     flex::{DrawLayout, MeasureLayout},
-    utils::{max_optional_size, mm_to_pt, u32_to_color_and_alpha},
+    utils::{
+        add_shape, max_optional_size, set_line_dash_pattern, set_line_join, u32_to_color_and_alpha,
+    },
     *,
 };
 
@@ -11,6 +13,12 @@ pub struct TableRow<F: Fn(&mut RowContent)> {
     pub line_style: LineStyle,
     pub expand: bool,
     pub content: F,
+
+    /// Cells next to each other that were both added with an outline, see
+    /// [RowContent::add_outlined], share a single border instead of doubling up around the line
+    /// between them. The later cell is widened to the left over the line and the thinner outline,
+    /// and the line isn't drawn.
+    pub border_collapse: bool,
 }
 
 impl<F: Fn(&mut RowContent)> Element for TableRow<F> {
@@ -26,6 +34,8 @@ impl<F: Fn(&mut RowContent)> Element for TableRow<F> {
         (self.content)(&mut RowContent {
             width: ctx.width,
             first_height: ctx.first_height,
+            border_collapse: self.border_collapse.then_some(self.line_style.thickness),
+            outline: None,
             pass: Pass::MeasureNonExpanded {
                 layout: &mut measure_layout,
                 max_height: Some(&mut max_height),
@@ -40,6 +50,8 @@ impl<F: Fn(&mut RowContent)> Element for TableRow<F> {
         (self.content)(&mut RowContent {
             width: ctx.width,
             first_height: ctx.first_height,
+            border_collapse: self.border_collapse.then_some(self.line_style.thickness),
+            outline: None,
             pass: Pass::MeasureExpanded {
                 layout: &draw_layout,
                 max_height: &mut max_height,
@@ -74,6 +86,8 @@ impl<F: Fn(&mut RowContent)> Element for TableRow<F> {
         (self.content)(&mut RowContent {
             width: ctx.width,
             first_height: ctx.first_height,
+            border_collapse: self.border_collapse.then_some(self.line_style.thickness),
+            outline: None,
             pass: Pass::MeasureNonExpanded {
                 layout: &mut measure_layout,
                 max_height: if self.expand {
@@ -104,6 +118,8 @@ impl<F: Fn(&mut RowContent)> Element for TableRow<F> {
             (self.content)(&mut RowContent {
                 width: ctx.width,
                 first_height: ctx.first_height,
+                border_collapse: self.border_collapse.then_some(self.line_style.thickness),
+                outline: None,
                 pass: Pass::MeasureExpanded {
                     layout: &draw_layout,
                     max_height: &mut max_height,
@@ -145,6 +161,8 @@ impl<F: Fn(&mut RowContent)> Element for TableRow<F> {
         (self.content)(&mut RowContent {
             width: ctx.width,
             first_height: ctx.first_height,
+            border_collapse: self.border_collapse.then_some(self.line_style.thickness),
+            outline: None,
             pass: Pass::Draw {
                 layout: &draw_layout,
                 max_height: &mut max_height,
@@ -162,6 +180,8 @@ impl<F: Fn(&mut RowContent)> Element for TableRow<F> {
             (self.content)(&mut RowContent {
                 width: ctx.width,
                 first_height: ctx.first_height,
+                border_collapse: self.border_collapse.then_some(self.line_style.thickness),
+                outline: None,
                 pass: Pass::DrawLines {
                     layout: &draw_layout,
                     width: None,
@@ -189,6 +209,13 @@ impl<F: Fn(&mut RowContent)> Element for TableRow<F> {
 pub struct RowContent<'a, 'b, 'c> {
     width: WidthConstraint,
     first_height: f64,
+
+    /// The thickness of the lines between the cells if the borders collapse.
+    border_collapse: Option<f64>,
+
+    /// The outline of the cell before, if it was added with one.
+    outline: Option<f64>,
+
     pass: Pass<'a, 'b, 'c>,
 }
 
@@ -271,6 +298,25 @@ fn add_height(
 
 impl<'a, 'b, 'c> RowContent<'a, 'b, 'c> {
     pub fn add<E: Element>(&mut self, element: &E, flex: Flex) {
+        self.add_with_outline(element, flex, None);
+    }
+
+    /// Adds a cell that has an outline of the thickness around it, like a
+    /// [StyledBox](super::styled_box::StyledBox). With [TableRow::border_collapse], it shares its
+    /// left border with the cell before it if that was added with an outline too.
+    pub fn add_outlined<E: Element>(&mut self, element: &E, flex: Flex, outline: f64) {
+        self.add_with_outline(element, flex, Some(outline));
+    }
+
+    fn add_with_outline<E: Element>(&mut self, element: &E, flex: Flex, outline: Option<f64>) {
+        // How far the cell reaches to the left over the line and the border of the cell before.
+        let overlap = match (self.border_collapse, self.outline, outline) {
+            (Some(line), Some(previous), Some(outline)) => line + previous.min(outline),
+            _ => 0.,
+        };
+
+        self.outline = outline;
+
         match self.pass {
             Pass::MeasureNonExpanded {
                 layout: &mut ref mut layout,
@@ -289,7 +335,7 @@ impl<'a, 'b, 'c> RowContent<'a, 'b, 'c> {
 
                         let size = element.measure(MeasureCtx {
                             width: WidthConstraint {
-                                max: width,
+                                max: width + overlap,
                                 expand: true,
                             },
                             first_height: self.first_height,
@@ -319,7 +365,7 @@ impl<'a, 'b, 'c> RowContent<'a, 'b, 'c> {
                 ref mut breakable,
             } => match flex {
                 Flex::Expand(fraction) => {
-                    let element_width = layout.expand_width(fraction);
+                    let element_width = layout.expand_width(fraction) + overlap;
 
                     let mut break_count = 0;
                     let mut extra_location_min_height = None;
@@ -348,7 +394,7 @@ impl<'a, 'b, 'c> RowContent<'a, 'b, 'c> {
                     if let &mut Some(&mut ref mut width) = width {
                         if let Some(w) = size.width {
                             if let Some(width) = width {
-                                *width += gap + w;
+                                *width += gap - overlap + w;
                             } else {
                                 *width = Some(w);
                             }
@@ -371,11 +417,11 @@ impl<'a, 'b, 'c> RowContent<'a, 'b, 'c> {
             } => {
                 let width_constraint = match flex {
                     Flex::Expand(fraction) => WidthConstraint {
-                        max: layout.expand_width(fraction),
+                        max: layout.expand_width(fraction) + overlap,
                         expand: true,
                     },
                     Flex::Fixed(width) => WidthConstraint {
-                        max: width,
+                        max: width + overlap,
                         expand: true,
                     },
                 };
@@ -383,7 +429,7 @@ impl<'a, 'b, 'c> RowContent<'a, 'b, 'c> {
                 let mut element_break_count = 0;
 
                 let x_offset = if let &mut Some(width) = width {
-                    width + gap
+                    width + gap - overlap
                 } else {
                     0.
                 };
@@ -462,7 +508,7 @@ impl<'a, 'b, 'c> RowContent<'a, 'b, 'c> {
                     }
                 };
 
-                width_add(width_constraint.max);
+                width_add(width_constraint.max - overlap);
             }
 
             Pass::DrawLines {
@@ -481,6 +527,14 @@ impl<'a, 'b, 'c> RowContent<'a, 'b, 'c> {
                 };
 
                 if let Some(width) = width {
+                    // A cell that shares its border covers the line.
+                    if overlap > 0. {
+                        *width += line_style.thickness + element_width;
+                        return;
+                    }
+
+                    let line_rendering = pdf.line_rendering;
+
                     let draw_line = |location: &Location, height: f64| {
                         let x = location.pos.0 + *width;
                         let y = location.pos.1;
@@ -490,29 +544,28 @@ impl<'a, 'b, 'c> RowContent<'a, 'b, 'c> {
 
                         let (color, _alpha) = u32_to_color_and_alpha(line_style.color);
                         layer.set_outline_color(color);
-                        layer.set_outline_thickness(mm_to_pt(line_style.thickness));
+                        layer.set_outline_thickness(line_style.stroke_width(line_rendering));
                         layer.set_line_cap_style(line_style.cap_style.into());
-                        layer.set_line_dash_pattern(
-                            if let Some(pattern) = line_style.dash_pattern {
-                                pattern.into()
-                            } else {
-                                printpdf::LineDashPattern::default()
+                        set_line_join(layer, &line_style);
+                        set_line_dash_pattern(layer, &line_style);
+
+                        let line_x =
+                            line_style.snap_position(line_rendering, x + line_style.thickness / 2.);
+
+                        add_shape(
+                            &location.layer,
+                            printpdf::Line {
+                                points: vec![
+                                    (printpdf::Point::new(Mm(line_x), Mm(y)), false),
+                                    (printpdf::Point::new(Mm(line_x), Mm(y - height)), false),
+                                ],
+                                is_closed: false,
+                                has_fill: false,
+                                has_stroke: true,
+                                is_clipping_path: false,
                             },
                         );
 
-                        let line_x = x + line_style.thickness / 2.;
-
-                        location.layer.add_shape(printpdf::Line {
-                            points: vec![
-                                (printpdf::Point::new(Mm(line_x), Mm(y)), false),
-                                (printpdf::Point::new(Mm(line_x), Mm(y - height)), false),
-                            ],
-                            is_closed: false,
-                            has_fill: false,
-                            has_stroke: true,
-                            is_clipping_path: false,
-                        });
-
                         location.layer.restore_graphics_state();
                     };
 
@@ -555,3 +608,80 @@ impl<'a, 'b, 'c> RowContent<'a, 'b, 'c> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{
+        record_passes::{Pass, RecordPasses},
+        *,
+    };
+
+    #[test]
+    fn test_border_collapse() {
+        for border_collapse in [false, true] {
+            test_element(
+                TestElementParams {
+                    width: WidthConstraint {
+                        max: 40.,
+                        expand: true,
+                    },
+                    first_height: 20.,
+                    breakable: None,
+                    pos: (3., 30.),
+                    page_size: (50., 50.),
+                    ..Default::default()
+                },
+                |assert, callback| {
+                    let cell = || {
+                        RecordPasses::new(FakeText {
+                            lines: 1,
+                            line_height: 5.,
+                            width: 3.,
+                        })
+                    };
+
+                    let (first, second) = (cell(), cell());
+
+                    let element = TableRow {
+                        line_style: LineStyle::solid(1., 0x00_00_00_FF),
+                        expand: true,
+                        border_collapse,
+                        content: |content| {
+                            content.add_outlined(&first, Flex::Fixed(10.), 0.5);
+                            content.add_outlined(&second, Flex::Fixed(10.), 1.);
+                        },
+                    };
+
+                    let ret = callback.call(element);
+
+                    if assert {
+                        let draw = |cell: RecordPasses<FakeText>| {
+                            cell.into_passes()
+                                .into_iter()
+                                .find_map(|p| match p {
+                                    Pass::Draw(d) => Some((d.pos.0, d.width.max)),
+                                    _ => None,
+                                })
+                                .unwrap()
+                        };
+
+                        assert_eq!(draw(first), (3., 10.));
+
+                        // The second cell reaches over the line and the thinner outline.
+                        assert_eq!(
+                            draw(second),
+                            if border_collapse {
+                                (3. + 10. + 1. - 1.5, 11.5)
+                            } else {
+                                (3. + 10. + 1., 10.)
+                            },
+                        );
+                    }
+
+                    ret
+                },
+            );
+        }
+    }
+}
@@ -1,17 +1,106 @@
-use printpdf::types::pdf_layer::GappedTextElement;
+use std::ops::Range;
+
+use printpdf::{types::pdf_layer::GappedTextElement, PdfLayerReference};
 
 use crate::{
-    fonts::{Font, GeneralMetrics},
-    text::{break_text_into_lines, remove_non_trailing_soft_hyphens, text_width},
-    utils::{mm_to_pt, pt_to_mm, u32_to_color_and_alpha},
+    annotations::{self, MarkupAnnotation, Quad, TextMarkup},
+    elements::debug::draw_baseline,
+    fonts::{color, Font, GeneralMetrics},
+    line_break::LineBreaking,
+    text::{
+        min_content_width, punctuation_compressions, remove_non_trailing_soft_hyphens, text_width,
+        LineGenerator,
+    },
+    utils::{
+        add_shape, mm_to_pt, pt_to_mm, snap_to_baseline_grid, u32_to_color_and_alpha, use_text,
+    },
     *,
 };
 
-#[derive(Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Copy, Clone, Serialize, Deserialize)]
 pub enum TextAlign {
     Left,
     Center,
     Right,
+
+    /// Lines up the decimal separator of every line at `fraction_width` mm from the right edge, so
+    /// the numbers in a table column line up if their cells all use the same width. The separator
+    /// is the last one followed by a digit. Without one, the separator would be after the last
+    /// digit, so e.g. `3 kg` lines up with `2.5 kg`. The text is at least as wide as its integer
+    /// parts plus `fraction_width`.
+    Decimal {
+        separator: char,
+
+        #[serde(deserialize_with = "crate::serde_elements::expr::deserialize_f64")]
+        fraction_width: f64,
+    },
+}
+
+// The widths are compared by their bits, so that alignments can still be compared like before
+// there were any widths.
+impl PartialEq for TextAlign {
+    fn eq(&self, other: &Self) -> bool {
+        match (*self, *other) {
+            (
+                TextAlign::Decimal {
+                    separator: a,
+                    fraction_width: a_width,
+                },
+                TextAlign::Decimal {
+                    separator: b,
+                    fraction_width: b_width,
+                },
+            ) => a == b && a_width.to_bits() == b_width.to_bits(),
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for TextAlign {}
+
+/// The length of the part of the line before its decimal separator, see [TextAlign::Decimal].
+pub fn decimal_integer_len(line: &str, separator: char) -> usize {
+    let followed_by_digit = |&(i, c): &(usize, char)| {
+        c == separator && line[i + c.len_utf8()..].starts_with(|c: char| c.is_ascii_digit())
+    };
+
+    match line.char_indices().rev().find(followed_by_digit) {
+        Some((i, _)) => i,
+        None => line
+            .rfind(|c: char| c.is_ascii_digit())
+            .map_or(line.len(), |i| i + 1),
+    }
+}
+
+/// Sets the first letter of the text in a bigger size spanning several lines, with the first lines
+/// wrapping around it. The letter uses the font and color of the text.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DropCap {
+    /// The number of lines the letter spans.
+    pub lines: u32,
+
+    /// The space between the letter and the lines next to it in mm.
+    #[serde(
+        default,
+        deserialize_with = "crate::serde_elements::expr::deserialize_f64"
+    )]
+    pub gap: f64,
+}
+
+/// Layout rules for Chinese and Japanese text, in addition to [LineBreaking::Kinsoku] for where
+/// lines can be broken.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CjkLayout {
+    /// Stretches the lines that had to be broken to the full width by adding space between all of
+    /// their characters, since there are no spaces between the words. The last line of every
+    /// paragraph keeps the alignment of the text.
+    #[serde(default)]
+    pub justify: bool,
+
+    /// Removes half an em between fullwidth punctuation marks in a row, see
+    /// [punctuation_compressions].
+    #[serde(default)]
+    pub compress_punctuation: bool,
 }
 
 pub struct Text<'a, F: Font> {
@@ -23,7 +112,30 @@ pub struct Text<'a, F: Font> {
     pub extra_character_spacing: f64,
     pub extra_word_spacing: f64,
     pub extra_line_height: f64,
+
+    /// Applied before [Self::extra_line_height] is added.
+    pub line_spacing: LineSpacing,
+
+    /// Where lines are broken, see [LineBreaking].
+    pub line_breaking: LineBreaking,
+
+    /// The step of a baseline grid in mm. Line heights are rounded up to whole steps and the first
+    /// baseline is moved down to a step below the top, so multi-column layouts line up if the
+    /// elements above the text also take whole steps.
+    pub baseline_grid: Option<f64>,
+
     pub align: TextAlign,
+
+    /// Ranges that get markup annotations, see [annotations].
+    pub markup: &'a [TextMarkup],
+
+    /// Byte ranges that are covered with black boxes. The text in them isn't written to the
+    /// document at all, so it can't be copied or extracted from the PDF.
+    pub redactions: &'a [Range<usize>],
+
+    pub drop_cap: Option<DropCap>,
+
+    pub cjk: CjkLayout,
 }
 
 struct FontMetrics {
@@ -31,6 +143,21 @@ struct FontMetrics {
     line_height: f64,
 }
 
+struct DropCapLayout<'a> {
+    letter: &'a str,
+
+    /// The text after the letter.
+    rest: &'a str,
+
+    /// The font size of the letter.
+    size: f64,
+
+    /// The width of the letter plus the gap.
+    indent: f64,
+
+    lines: usize,
+}
+
 impl<'a, F: Font> Text<'a, F> {
     pub fn basic(text: &'a str, font: &'a F, size: f64) -> Self {
         Text {
@@ -42,10 +169,38 @@ impl<'a, F: Font> Text<'a, F> {
             extra_character_spacing: 0.,
             extra_word_spacing: 0.,
             extra_line_height: 0.,
+            line_spacing: LineSpacing::default(),
+            line_breaking: LineBreaking::default(),
+            baseline_grid: None,
             align: TextAlign::Left,
+            markup: &[],
+            redactions: &[],
+            drop_cap: None,
+            cjk: CjkLayout::default(),
         }
     }
 
+    /// The width of the longest fragment that can't be broken further, in mm. Useful for automatic
+    /// column widths.
+    pub fn min_content_width(&self) -> f64 {
+        pt_to_mm(min_content_width(
+            self.text,
+            self.size,
+            self.font,
+            self.extra_character_spacing,
+            self.extra_word_spacing,
+            self.line_breaking,
+        ))
+    }
+
+    /// The width of the text without any soft line breaks, in mm.
+    pub fn max_content_width(&self) -> f64 {
+        self.text
+            .lines()
+            .map(|line| pt_to_mm(self.width_pt(&remove_non_trailing_soft_hyphens(line))))
+            .fold(0., f64::max)
+    }
+
     fn compute_font_metrics(&self) -> FontMetrics {
         let GeneralMetrics {
             ascent,
@@ -56,10 +211,60 @@ impl<'a, F: Font> Text<'a, F> {
 
         FontMetrics {
             ascent: pt_to_mm(ascent * self.size / units_per_em),
-            line_height: pt_to_mm(line_height * self.size / units_per_em) + self.extra_line_height,
+            line_height: snap_to_baseline_grid(
+                self.line_spacing
+                    .line_height(pt_to_mm(line_height * self.size / units_per_em))
+                    + self.extra_line_height,
+                self.baseline_grid,
+            ),
         }
     }
 
+    fn drop_cap_layout(&self) -> Option<DropCapLayout<'a>> {
+        let drop_cap = self.drop_cap.filter(|d| d.lines > 0)?;
+        let letter = self.text.chars().next().filter(|c| !c.is_whitespace())?;
+
+        let (letter, rest) = self.text.split_at(letter.len_utf8());
+
+        let FontMetrics {
+            ascent,
+            line_height,
+        } = self.compute_font_metrics();
+
+        let lines = drop_cap.lines as usize;
+
+        // The letter goes from the top of the first line to the baseline of the last one.
+        let size = self.size * (ascent + (lines - 1) as f64 * line_height) / ascent;
+
+        Some(DropCapLayout {
+            letter,
+            rest,
+            size,
+            indent: pt_to_mm(text_width(letter, size, self.font, 0., 0.)) + drop_cap.gap,
+            lines,
+        })
+    }
+
+    /// A drop cap that's taller than the text still needs its space. If there are fewer lines than
+    /// the letter spans, they all are on the same location.
+    fn pad_for_drop_cap(
+        &self,
+        line_count: usize,
+        total_lines: usize,
+        drop_cap: Option<&DropCapLayout>,
+    ) -> usize {
+        match drop_cap {
+            Some(d) if total_lines > 0 && total_lines < d.lines => d.lines,
+            _ => line_count,
+        }
+    }
+
+    /// The height the first line needs on its location. With a drop cap that's the height of all
+    /// the lines the letter spans, so the letter doesn't get split.
+    fn first_line_height(&self, line_height: f64, drop_cap: Option<&DropCapLayout>) -> f64 {
+        line_height * drop_cap.map_or(1, |d| d.lines) as f64
+    }
+
     #[inline(always)]
     fn render_lines<'b, L: Iterator<Item = &'b str>>(
         &self,
@@ -76,24 +281,37 @@ impl<'a, F: Font> Text<'a, F> {
 
         let mut height_available = ctx.first_height;
 
-        let pdf_font = &self.font.indirect_font_ref();
-
         let mut line_count = 0;
         let mut draw_rect = 0;
 
-        for line in lines {
-            let line: &str = &remove_non_trailing_soft_hyphens(line);
+        let drop_cap = self.drop_cap_layout();
+        let mut total_lines = 0;
+
+        // The annotation of every markup range on the current page.
+        let mut markup = if !self.markup.is_empty() && annotations::collecting() {
+            self.markup.iter().map(|_| None).collect()
+        } else {
+            Vec::new()
+        };
+
+        for (i, raw_line) in lines.enumerate() {
+            let line: &str = &remove_non_trailing_soft_hyphens(raw_line);
 
-            let line_width = pt_to_mm(text_width(
-                line,
-                self.size,
-                self.font,
-                self.extra_character_spacing,
-                self.extra_word_spacing,
-            ));
-            max_width = max_width.max(line_width);
+            let line_width = pt_to_mm(self.width_pt(line));
+            let indent = match drop_cap {
+                Some(ref d) if i < d.lines => d.indent,
+                _ => 0.,
+            };
 
-            if height_available < line_height {
+            max_width = max_width.max(indent + self.aligned_width(line, line_width));
+
+            let needed_height = if i == 0 {
+                self.first_line_height(line_height, drop_cap.as_ref())
+            } else {
+                line_height
+            };
+
+            if height_available < needed_height {
                 if let Some(ref mut breakable) = ctx.breakable {
                     let new_location = (breakable.do_break)(
                         ctx.pdf,
@@ -113,59 +331,389 @@ impl<'a, F: Font> Text<'a, F> {
                 }
             }
 
+            // Justified lines are drawn with more character spacing, which the positions of the
+            // redactions and markup are then computed with as well.
+            let justified;
+
+            let (line_text, line_width) = match self.justification(raw_line, line, width - indent) {
+                Some(spacing) => {
+                    justified = Text {
+                        extra_character_spacing: self.extra_character_spacing + spacing,
+                        ..*self
+                    };
+
+                    (&justified, width - indent)
+                }
+                None => (self, line_width),
+            };
+
+            draw_baseline(&ctx.location.layer, x, y, width);
+
             ctx.location.layer.save_graphics_state();
             ctx.location
                 .layer
-                .set_fill_color(u32_to_color_and_alpha(self.color).0);
+                .set_fill_color(u32_to_color_and_alpha(line_text.color).0);
 
-            if self.extra_character_spacing != 0. {
+            if line_text.extra_character_spacing != 0. {
                 ctx.location
                     .layer
-                    .set_character_spacing(self.extra_character_spacing);
+                    .set_character_spacing(line_text.extra_character_spacing);
             }
 
-            let x_offset = match self.align {
+            if let (0, Some(d)) = (i, &drop_cap) {
+                if self.drop_cap_redacted(d) {
+                    // Like with the ranges in the lines, the letter isn't written at all.
+                    let width = pt_to_mm(text_width(d.letter, d.size, self.font, 0., 0.));
+                    let height = d.lines as f64 * line_height;
+                    draw_black_box(&ctx.location.layer, x, x + width, y + ascent, height);
+                } else {
+                    let y = y - (d.lines - 1) as f64 * line_height;
+                    line_text.write_drop_cap(&ctx.location.layer, d, x, y);
+                }
+            }
+
+            let x_offset = match line_text.align {
                 TextAlign::Left => 0.,
-                TextAlign::Center => (width - line_width) / 2.,
-                TextAlign::Right => width - line_width,
+                TextAlign::Center => (width - indent - line_width) / 2.,
+                TextAlign::Right => width - indent - line_width,
+                TextAlign::Decimal {
+                    separator,
+                    fraction_width,
+                } => {
+                    let integer = &line[..decimal_integer_len(line, separator)];
+                    width - indent - fraction_width - line_text.width_mm(integer)
+                }
             };
 
-            let x = x + x_offset;
+            let x = x + indent + x_offset;
 
-            if self.extra_word_spacing != 0. {
-                ctx.location.layer.begin_text_section();
-                ctx.location.layer.set_font(pdf_font, self.size);
-                ctx.location.layer.set_text_cursor(Mm(x), Mm(y));
+            let redactions = line_text.redactions_in_line(raw_line);
+
+            if redactions.is_empty() {
+                line_text.write_text(&ctx.location.layer, line, x, y);
+            } else {
+                // The redacted parts are left out of the content stream entirely and the rest is
+                // written piece by piece at the positions it would have had.
+                let mut pos = 0;
 
-                let word_spacing = self.extra_word_spacing * 1000. / self.size;
+                for range in redactions
+                    .iter()
+                    .chain([raw_line.len()..raw_line.len()].iter())
+                {
+                    if range.start > pos {
+                        let piece = &raw_line[pos..range.start];
 
-                ctx.location.layer.write_gapped_text(
-                    line.split_inclusive(" ").flat_map(|s| {
-                        std::iter::once(GappedTextElement::Text(s)).chain(if s.ends_with(' ') {
-                            Some(GappedTextElement::Gap(word_spacing))
+                        let piece = if range.start == raw_line.len() {
+                            remove_non_trailing_soft_hyphens(piece)
                         } else {
-                            None
-                        })
-                    }),
-                    pdf_font,
+                            piece.replace('\u{00ad}', "").into()
+                        };
+
+                        let piece_x = x + line_text.width_mm(&raw_line[..pos]);
+                        line_text.write_text(&ctx.location.layer, &piece, piece_x, y);
+                    }
+
+                    pos = pos.max(range.end);
+                }
+
+                line_text.draw_redaction_boxes(
+                    &ctx.location.layer,
+                    raw_line,
+                    &redactions,
+                    x,
+                    y,
+                    ascent,
+                    line_height,
                 );
-                ctx.location.layer.end_text_section();
-            } else {
-                ctx.location
-                    .layer
-                    .use_text(line, self.size, Mm(x), Mm(y), pdf_font);
             }
 
-            if self.underline {
+            if line_text.underline {
                 crate::utils::line(&ctx.location.layer, [x, y - 1.0], line_width, pt_to_mm(2.0));
             }
             ctx.location.layer.restore_graphics_state();
+
+            if !markup.is_empty() {
+                line_text.add_markup_quads(
+                    &mut markup,
+                    raw_line,
+                    ctx.location.layer.page.0,
+                    x,
+                    y + ascent,
+                    line_height,
+                );
+            }
+
             y -= line_height;
             height_available -= line_height;
             line_count += 1;
+            total_lines += 1;
+        }
+
+        markup.into_iter().flatten().for_each(annotations::add);
+
+        (
+            max_width,
+            self.pad_for_drop_cap(line_count, total_lines, drop_cap.as_ref()) as f64 * line_height,
+        )
+    }
+
+    fn write_text(&self, layer: &PdfLayerReference, text: &str, x: f64, y: f64) {
+        let runs = color::color_runs(self.font, text);
+
+        if runs.iter().all(|(_, glyph)| glyph.is_none()) {
+            return self.write_plain_text(layer, text, x, y);
+        }
+
+        for (range, glyph) in runs {
+            let x = x + pt_to_mm(self.width_pt(&text[..range.start]));
+
+            match glyph {
+                Some(glyph) => color::draw_color_glyph(
+                    layer,
+                    &glyph,
+                    self.size,
+                    self.font.units_per_em() as f64,
+                    self.color,
+                    (x, y),
+                ),
+                None => self.write_plain_text(layer, &text[range], x, y),
+            }
+        }
+    }
+
+    fn write_plain_text(&self, layer: &PdfLayerReference, text: &str, x: f64, y: f64) {
+        if !crate::budget::add_content(crate::budget::text_size(text)) {
+            return;
+        }
+
+        let pdf_font = self.font.indirect_font_ref();
+
+        let compressions = if self.cjk.compress_punctuation {
+            punctuation_compressions(text)
+        } else {
+            Vec::new()
+        };
+
+        if self.extra_word_spacing != 0. || !compressions.is_empty() {
+            layer.begin_text_section();
+            layer.set_font(pdf_font, self.size);
+            layer.set_text_cursor(Mm(x), Mm(y));
+
+            // In thousandths of an em.
+            let word_spacing = self.extra_word_spacing * 1000. / self.size;
+
+            let mut elements = Vec::new();
+            let mut start = 0;
+
+            for (i, c) in text.char_indices() {
+                let end = i + c.len_utf8();
+
+                let gap = if c == ' ' && self.extra_word_spacing != 0. {
+                    word_spacing
+                } else if compressions.contains(&end) {
+                    -500.
+                } else {
+                    continue;
+                };
+
+                elements.push(GappedTextElement::Text(&text[start..end]));
+                elements.push(GappedTextElement::Gap(gap));
+                start = end;
+            }
+
+            if start < text.len() {
+                elements.push(GappedTextElement::Text(&text[start..]));
+            }
+
+            layer.write_gapped_text(elements.into_iter(), pdf_font);
+            layer.end_text_section();
+        } else {
+            layer.use_text(text, self.size, Mm(x), Mm(y), pdf_font);
+        }
+    }
+
+    /// The width of the text in pt, with the punctuation compressed if that's enabled.
+    fn width_pt(&self, text: &str) -> f64 {
+        let width = text_width(
+            text,
+            self.size,
+            self.font,
+            self.extra_character_spacing,
+            self.extra_word_spacing,
+        );
+
+        if self.cjk.compress_punctuation {
+            width - punctuation_compressions(text).len() as f64 * self.size / 2.
+        } else {
+            width
+        }
+    }
+
+    /// Whether the line is the last one of a paragraph, so the text ends after it or there's a
+    /// newline. `line` has to be a slice of the text.
+    fn ends_paragraph(&self, line: &str) -> bool {
+        match self.line_start(line) {
+            Some(start) => {
+                let rest = self.text[start + line.len()..].trim_start_matches([' ', '\r']);
+                rest.is_empty() || rest.starts_with('\n')
+            }
+            None => true,
+        }
+    }
+
+    /// The character spacing in pt that stretches the line to `available` mm with
+    /// [CjkLayout::justify], if it's stretched.
+    fn justification(&self, raw_line: &str, line: &str, available: f64) -> Option<f64> {
+        let line = line.trim_end();
+        let gaps = line.chars().count().saturating_sub(1);
+        let spare = available - pt_to_mm(self.width_pt(line));
+
+        if !self.cjk.justify || gaps == 0 || spare <= 0. || self.ends_paragraph(raw_line) {
+            return None;
         }
 
-        (max_width, line_count as f64 * line_height)
+        Some(mm_to_pt(spare) / gaps as f64)
+    }
+
+    /// The width of a piece of a line in mm.
+    fn width_mm(&self, text: &str) -> f64 {
+        pt_to_mm(self.width_pt(&remove_non_trailing_soft_hyphens(text)))
+    }
+
+    /// The start of the line in the text in bytes. `line` has to be a slice of the text.
+    fn line_start(&self, line: &str) -> Option<usize> {
+        match (line.as_ptr() as usize).checked_sub(self.text.as_ptr() as usize) {
+            Some(start) if start + line.len() <= self.text.len() => Some(start),
+            _ => None,
+        }
+    }
+
+    /// The redacted ranges of the line, relative to the line, sorted and without overlaps.
+    fn redactions_in_line(&self, line: &str) -> Vec<Range<usize>> {
+        if self.redactions.is_empty() {
+            return Vec::new();
+        }
+
+        let line_start = match self.line_start(line) {
+            Some(start) => start,
+            None => return Vec::new(),
+        };
+
+        let mut ranges = self
+            .redactions
+            .iter()
+            .map(|r| {
+                r.start.clamp(line_start, line_start + line.len()) - line_start
+                    ..r.end.clamp(line_start, line_start + line.len()) - line_start
+            })
+            .filter(|r| r.start < r.end)
+            .map(|r| {
+                // Ranges that end inside of a character cover all of it.
+                let mut r = r;
+
+                while !line.is_char_boundary(r.start) {
+                    r.start -= 1;
+                }
+
+                while !line.is_char_boundary(r.end) {
+                    r.end += 1;
+                }
+
+                r
+            })
+            .collect::<Vec<_>>();
+
+        ranges.sort_by_key(|r| r.start);
+
+        let mut merged: Vec<Range<usize>> = Vec::new();
+
+        for range in ranges {
+            match merged.last_mut() {
+                Some(last) if range.start <= last.end => last.end = last.end.max(range.end),
+                _ => merged.push(range),
+            }
+        }
+
+        merged
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw_redaction_boxes(
+        &self,
+        layer: &PdfLayerReference,
+        line: &str,
+        redactions: &[Range<usize>],
+        x: f64,
+        y: f64,
+        ascent: f64,
+        line_height: f64,
+    ) {
+        for range in redactions {
+            let left = x + self.width_mm(&line[..range.start]);
+            let right = x + self.width_mm(&line[..range.end]);
+
+            draw_black_box(layer, left, right, y + ascent, line_height);
+        }
+    }
+
+    /// Whether a redaction covers part of the drop cap letter, which is at the start of the text.
+    fn drop_cap_redacted(&self, drop_cap: &DropCapLayout) -> bool {
+        self.redactions
+            .iter()
+            .any(|r| r.start < drop_cap.letter.len() && r.start < r.end)
+    }
+
+    /// Adds the parts of the markup ranges on the line to their annotations. `line` has to be a
+    /// slice of the text.
+    fn add_markup_quads(
+        &self,
+        markup: &mut [Option<MarkupAnnotation>],
+        line: &str,
+        page: usize,
+        x: f64,
+        top: f64,
+        line_height: f64,
+    ) {
+        let line_start = match self.line_start(line) {
+            Some(start) => start,
+            None => return,
+        };
+
+        for (text_markup, annotation) in self.markup.iter().zip(markup) {
+            let start = text_markup.range.start.max(line_start) - line_start;
+            let end = text_markup.range.end.min(line_start + line.len());
+
+            if end <= line_start + start || !line.is_char_boundary(start) {
+                continue;
+            }
+
+            let end = end - line_start;
+
+            if !line.is_char_boundary(end) {
+                continue;
+            }
+
+            let quad = Quad {
+                left: x + self.width_mm(&line[..start]),
+                right: x + self.width_mm(&line[..end]),
+                top,
+                bottom: top - line_height,
+            };
+
+            match annotation {
+                Some(annotation) if annotation.page == page => annotation.quads.push(quad),
+                _ => {
+                    let previous = annotation.replace(MarkupAnnotation {
+                        kind: text_markup.kind,
+                        color: text_markup.color,
+                        page,
+                        quads: vec![quad],
+                    });
+
+                    previous.into_iter().for_each(annotations::add);
+                }
+            }
+        }
     }
 
     #[inline(always)]
@@ -186,45 +734,116 @@ impl<'a, F: Font> Text<'a, F> {
             f64::INFINITY
         };
 
-        for line in lines {
+        let drop_cap = self.drop_cap_layout();
+        let mut total_lines = 0;
+
+        for (i, line) in lines.enumerate() {
+            let (indent, needed_height) = match drop_cap {
+                Some(ref d) if i == 0 => (d.indent, self.first_line_height(line_height, Some(d))),
+                Some(ref d) if i < d.lines => (d.indent, line_height),
+                _ => (0., line_height),
+            };
+
             if let Some(&mut MeasureCtx {
                 breakable: Some(ref mut breakable),
                 ..
             }) = measure_ctx
             {
-                if height_available < line_height {
+                if height_available < needed_height {
                     *breakable.break_count += 1;
                     height_available = breakable.full_height;
                     line_count = 0;
                 }
             }
 
-            max_width = max_width.max(pt_to_mm(text_width(
-                line,
-                self.size,
-                self.font,
-                self.extra_character_spacing,
-                self.extra_word_spacing,
-            )));
+            let line_width = pt_to_mm(self.width_pt(line));
+            max_width = max_width.max(indent + self.aligned_width(line, line_width));
 
             height_available -= line_height;
             line_count += 1;
+            total_lines += 1;
         }
 
-        (max_width, line_count as f64 * line_height)
+        (
+            max_width,
+            self.pad_for_drop_cap(line_count, total_lines, drop_cap.as_ref()) as f64 * line_height,
+        )
+    }
+
+    /// The width a line needs with the alignment. With decimal alignment, the fraction part takes
+    /// up at least the fraction width.
+    fn aligned_width(&self, line: &str, line_width: f64) -> f64 {
+        match self.align {
+            TextAlign::Decimal {
+                separator,
+                fraction_width,
+            } => {
+                let integer_width = self.width_mm(&line[..decimal_integer_len(line, separator)]);
+                integer_width + fraction_width.max(line_width - integer_width)
+            }
+            _ => line_width,
+        }
     }
 
     fn break_into_lines(&'a self, width: f64) -> impl Iterator<Item = &'a str> + Clone {
-        break_text_into_lines(self.text, mm_to_pt(width), move |text| {
-            text_width(
-                text,
-                self.size,
-                self.font,
-                self.extra_character_spacing,
-                self.extra_word_spacing,
-            )
+        let (text, indent, indented_lines) = match self.drop_cap_layout() {
+            Some(d) => (d.rest, d.indent, d.lines),
+            None => (self.text, 0., 0),
+        };
+
+        let mut generator = LineGenerator::new(text, move |text| self.width_pt(text))
+            .with_breaking(self.line_breaking);
+
+        let mut line = 0;
+
+        std::iter::from_fn(move || {
+            let width = if line < indented_lines {
+                (width - indent).max(0.)
+            } else {
+                width
+            };
+
+            line += 1;
+
+            generator.next(mm_to_pt(width), false)
         })
     }
+
+    fn write_drop_cap(&self, layer: &PdfLayerReference, drop_cap: &DropCapLayout, x: f64, y: f64) {
+        use_text(
+            layer,
+            drop_cap.letter,
+            drop_cap.size,
+            Mm(x),
+            Mm(y),
+            self.font.indirect_font_ref(),
+        );
+    }
+}
+
+fn draw_black_box(layer: &PdfLayerReference, left: f64, right: f64, top: f64, height: f64) {
+    use printpdf::{utils::calculate_points_for_rect, Line};
+
+    layer.save_graphics_state();
+    layer.set_fill_color(u32_to_color_and_alpha(0x00_00_00_FF).0);
+
+    add_shape(
+        layer,
+        Line {
+            points: calculate_points_for_rect(
+                Mm(right - left),
+                Mm(height),
+                Mm((left + right) / 2.),
+                Mm(top - height / 2.),
+            ),
+            is_closed: true,
+            has_fill: true,
+            has_stroke: false,
+            is_clipping_path: false,
+        },
+    );
+
+    layer.restore_graphics_state();
 }
 
 impl<'a, F: Font> Element for Text<'a, F> {
@@ -234,6 +853,8 @@ impl<'a, F: Font> Element for Text<'a, F> {
             line_height,
         } = self.compute_font_metrics();
 
+        let line_height = self.first_line_height(line_height, self.drop_cap_layout().as_ref());
+
         if line_height > ctx.first_height {
             FirstLocationUsage::WillSkip
         } else {
@@ -265,17 +886,18 @@ impl<'a, F: Font> Element for Text<'a, F> {
         let lines = self.break_into_lines(ctx.width.max);
 
         // For left alignment we don't need to pre-layout because the
-        // x offset is always zero.
+        // x offset is always zero, unless lines are justified.
         let width = if ctx.width.expand {
             ctx.width.max
-        } else if self.align == TextAlign::Left {
+        } else if self.align == TextAlign::Left && !self.cjk.justify {
             0.
         } else {
             self.layout_lines(lines.clone(), line_height, None).0
         };
 
         let width_constraint = ctx.width;
-        let size = self.render_lines(lines, ctx, ascent, line_height, width);
+        let baseline = snap_to_baseline_grid(ascent, self.baseline_grid);
+        let size = self.render_lines(lines, ctx, baseline, line_height, width);
 
         ElementSize {
             width: Some(width_constraint.constrain(size.0)),
@@ -364,4 +986,435 @@ mod tests {
             });
         }
     }
+
+    #[test]
+    fn test_markup() {
+        use crate::annotations::{collect_markup, MarkupKind};
+
+        static MARKUP: [TextMarkup; 1] = [TextMarkup {
+            // "line\nso"
+            range: 7..14,
+            kind: MarkupKind::Highlight,
+            color: 0xFF_FF_00_FF,
+        }];
+
+        fn text(font: &BuiltinFont) -> Text<'_, BuiltinFont> {
+            Text {
+                markup: &MARKUP,
+                ..Text::basic("i am a line\nso am i", font, 12.)
+            }
+        }
+
+        let (_, annotations) =
+            collect_markup(|| crate::build_pdf("test", (100., 100.), BuiltinFont::helvetica, text));
+
+        assert_eq!(annotations.len(), 1);
+
+        let annotation = &annotations[0];
+        assert_eq!(annotation.page, 0);
+        assert_eq!(annotation.quads.len(), 2);
+
+        let [first, second] = [annotation.quads[0], annotation.quads[1]];
+        assert!(first.left > 0. && first.right > first.left);
+        assert_eq!(second.left, 0.);
+        assert_eq!(first.bottom, second.top);
+        assert!(first.top <= 100.);
+    }
+
+    #[test]
+    fn test_decimal_align() {
+        use crate::annotations::{collect_markup, MarkupKind};
+
+        // The separators of the first two lines and the number of the last one.
+        static MARKUP: [TextMarkup; 3] = [
+            TextMarkup {
+                range: 2..3,
+                kind: MarkupKind::Highlight,
+                color: 0xFF_FF_00_FF,
+            },
+            TextMarkup {
+                range: 10..11,
+                kind: MarkupKind::Highlight,
+                color: 0xFF_FF_00_FF,
+            },
+            TextMarkup {
+                range: 14..15,
+                kind: MarkupKind::Highlight,
+                color: 0xFF_FF_00_FF,
+            },
+        ];
+
+        fn text(font: &BuiltinFont) -> Text<'_, BuiltinFont> {
+            Text {
+                markup: &MARKUP,
+                align: TextAlign::Decimal {
+                    separator: '.',
+                    fraction_width: 10.,
+                },
+                ..Text::basic("12.5\n1,234.75\n7", font, 12.)
+            }
+        }
+
+        let (_, annotations) =
+            collect_markup(|| crate::build_pdf("test", (100., 100.), BuiltinFont::helvetica, text));
+
+        let quads = annotations.iter().map(|a| a.quads[0]).collect::<Vec<_>>();
+
+        assert_eq!(quads.len(), 3);
+        assert!((quads[0].left - quads[1].left).abs() < 1e-9);
+        assert!((quads[1].left - quads[2].right).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_decimal_align_width() {
+        use crate::annotations::{collect_markup, MarkupKind};
+
+        static MARKUP: [TextMarkup; 1] = [TextMarkup {
+            range: 0..1,
+            kind: MarkupKind::Highlight,
+            color: 0xFF_FF_00_FF,
+        }];
+
+        let doc = PdfDocument::empty("i contain a font");
+        let font = BuiltinFont::helvetica(&doc);
+
+        let align = TextAlign::Decimal {
+            separator: '.',
+            fraction_width: 10.,
+        };
+
+        let element = Text {
+            markup: &MARKUP,
+            align,
+            ..Text::basic("7", &font, 12.)
+        };
+
+        let digit = element.width_mm("7");
+        let params = ElementTestParams::default();
+
+        let (outputs, annotations) = collect_markup(|| params.run(&element).collect::<Vec<_>>());
+
+        // The fraction width is part of the width even without a fraction.
+        for output in &outputs {
+            assert_eq!(output.size.width, Some(output.width.constrain(digit + 10.)));
+        }
+
+        // The digit is at the left edge unless the text expands.
+        let left = params.pos.0;
+        let right = params.pos.0 + params.width - 10.;
+
+        assert!(!annotations.is_empty());
+
+        for annotation in &annotations {
+            let quad = annotation.quads[0];
+            assert!(
+                (quad.left - left).abs() < 1e-9 || (quad.right - right).abs() < 1e-9,
+                "{} {}",
+                quad.left,
+                quad.right,
+            );
+        }
+
+        assert_eq!(decimal_integer_len("1,234.75", '.'), 5);
+        assert_eq!(decimal_integer_len("12.5 in.", '.'), 2);
+        assert_eq!(decimal_integer_len("3 kg", ','), 1);
+        assert_eq!(decimal_integer_len("n/a", '.'), 3);
+
+        assert!(align == align);
+        assert!(TextAlign::Left != align);
+    }
+
+    #[test]
+    fn test_redactions() {
+        let doc = PdfDocument::empty("i contain a font");
+        let font = BuiltinFont::helvetica(&doc);
+
+        let text = "name: Jane Doe\nphone: 555";
+        let redactions = [6..10, 9..14, 22..25];
+
+        let element = Text {
+            redactions: &redactions,
+            ..Text::basic(text, &font, 12.)
+        };
+
+        let lines = element.break_into_lines(100.).collect::<Vec<_>>();
+
+        assert_eq!(element.redactions_in_line(lines[0]), [6..14]);
+        assert_eq!(element.redactions_in_line(lines[1]), [7..10]);
+
+        // Redacting doesn't change the layout.
+        let measure = |element: &Text<_>| {
+            element.measure(MeasureCtx {
+                width: WidthConstraint {
+                    max: 100.,
+                    expand: false,
+                },
+                first_height: 100.,
+                breakable: None,
+            })
+        };
+
+        assert_eq!(measure(&element), measure(&Text::basic(text, &font, 12.)));
+    }
+
+    #[test]
+    fn test_line_spacing() {
+        let doc = PdfDocument::empty("i contain a font");
+        let font = BuiltinFont::helvetica(&doc);
+
+        let height = |line_spacing| {
+            Text {
+                line_spacing,
+                extra_line_height: 1.,
+                ..Text::basic("one\ntwo", &font, 12.)
+            }
+            .measure(MeasureCtx {
+                width: WidthConstraint {
+                    max: 100.,
+                    expand: false,
+                },
+                first_height: 100.,
+                breakable: None,
+            })
+            .height
+            .unwrap()
+        };
+
+        let natural = height(LineSpacing::default()) / 2. - 1.;
+
+        assert_eq!(height(LineSpacing::Exact(3.)), 8.);
+        assert_eq!(
+            height(LineSpacing::AtLeast(1.)),
+            height(LineSpacing::default())
+        );
+        assert_eq!(height(LineSpacing::AtLeast(20.)), 42.);
+        assert!((height(LineSpacing::Multiple(1.5)) - (natural * 1.5 + 1.) * 2.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_baseline_grid() {
+        let doc = PdfDocument::empty("i contain a font");
+        let font = BuiltinFont::helvetica(&doc);
+
+        let height = |line_spacing, baseline_grid| {
+            Text {
+                line_spacing,
+                baseline_grid,
+                ..Text::basic("one\ntwo", &font, 12.)
+            }
+            .measure(MeasureCtx {
+                width: WidthConstraint {
+                    max: 100.,
+                    expand: false,
+                },
+                first_height: 100.,
+                breakable: None,
+            })
+            .height
+            .unwrap()
+        };
+
+        assert!(height(LineSpacing::default(), None) < 12.);
+        assert_eq!(height(LineSpacing::default(), Some(6.)), 12.);
+        assert_eq!(height(LineSpacing::Exact(6.), Some(6.)), 12.);
+        assert_eq!(height(LineSpacing::Exact(6.1), Some(6.)), 24.);
+        assert_eq!(height(LineSpacing::Exact(3.), Some(0.5)), 6.);
+    }
+
+    #[test]
+    fn test_drop_cap() {
+        let doc = PdfDocument::empty("i contain a font");
+        let font = BuiltinFont::helvetica(&doc);
+
+        let drop_cap = Some(DropCap { lines: 3, gap: 1. });
+
+        let measure = |element: &Text<_>| {
+            element.measure(MeasureCtx {
+                width: WidthConstraint {
+                    max: 40.,
+                    expand: false,
+                },
+                first_height: 100.,
+                breakable: None,
+            })
+        };
+
+        let line_height = measure(&Text::basic("Hi", &font, 12.)).height.unwrap();
+
+        // The letter needs three lines even though the text only has one.
+        let short = Text {
+            drop_cap,
+            ..Text::basic("Hi", &font, 12.)
+        };
+        assert_eq!(measure(&short).height, Some(line_height * 3.));
+
+        let text = "Once upon a time there was a drop cap that took up some space.";
+        let element = Text {
+            drop_cap,
+            ..Text::basic(text, &font, 12.)
+        };
+        let plain = Text::basic(text, &font, 12.);
+
+        let lines = element.break_into_lines(40.).collect::<Vec<_>>();
+        let plain_lines = plain.break_into_lines(40.).collect::<Vec<_>>();
+
+        assert!(lines[0].starts_with("nce"));
+        assert!(lines.len() >= plain_lines.len());
+
+        let layout = element.drop_cap_layout().unwrap();
+        assert!(layout.size > 12. * 2.);
+
+        for line in &lines[..3] {
+            assert!(element.width_mm(line.trim_end()) <= 40. - layout.indent);
+        }
+
+        // The letter doesn't get split, so there has to be room for all of its lines.
+        let mut break_count = 0;
+        element.measure(MeasureCtx {
+            width: WidthConstraint {
+                max: 40.,
+                expand: false,
+            },
+            first_height: line_height * 2.,
+            breakable: Some(BreakableMeasure {
+                full_height: 100.,
+                break_count: &mut break_count,
+                extra_location_min_height: &mut None,
+            }),
+        });
+        assert_eq!(break_count, 1);
+    }
+
+    #[test]
+    fn test_redacted_drop_cap() {
+        use lopdf::Object;
+
+        use crate::test_utils::{page_operations, save};
+
+        const TEXT: &str = "Quiet night falls";
+
+        fn redacted(font: &BuiltinFont) -> Text<'_, BuiltinFont> {
+            Text {
+                drop_cap: Some(DropCap { lines: 2, gap: 1. }),
+                redactions: &[0..1],
+                ..Text::basic(TEXT, font, 12.)
+            }
+        }
+
+        fn visible(font: &BuiltinFont) -> Text<'_, BuiltinFont> {
+            Text {
+                redactions: &[],
+                ..redacted(font)
+            }
+        }
+
+        // All the text written to the content streams of the document.
+        fn written_text(document: printpdf::PdfDocumentReference) -> Vec<u8> {
+            fn strings(object: &Object, out: &mut Vec<u8>) {
+                match object {
+                    Object::String(bytes, _) => out.extend(bytes),
+                    Object::Array(objects) => objects.iter().for_each(|o| strings(o, out)),
+                    _ => (),
+                }
+            }
+
+            let mut text = Vec::new();
+
+            for operation in page_operations(&save(document)).into_iter().flatten() {
+                if operation.operator == "Tj" || operation.operator == "TJ" {
+                    operation
+                        .operands
+                        .iter()
+                        .for_each(|o| strings(o, &mut text));
+                }
+            }
+
+            text
+        }
+
+        let visible = written_text(crate::build_pdf(
+            "test",
+            (100., 100.),
+            BuiltinFont::helvetica,
+            visible,
+        ));
+        assert!(visible.contains(&b'Q'));
+        assert!(visible.windows(5).any(|w| w == b"night"));
+
+        let redacted = written_text(crate::build_pdf(
+            "test",
+            (100., 100.),
+            BuiltinFont::helvetica,
+            redacted,
+        ));
+        assert!(!redacted.contains(&b'Q'));
+        assert!(redacted.windows(5).any(|w| w == b"night"));
+    }
+
+    #[test]
+    fn test_line_breaking() {
+        let doc = PdfDocument::empty("i contain a font");
+        let font = BuiltinFont::courier(&doc);
+
+        let simple = Text::basic("either/or (maybe)", &font, 12.);
+        let unicode = Text {
+            line_breaking: LineBreaking::Unicode,
+            ..Text::basic("either/or (maybe)", &font, 12.)
+        };
+
+        // Courier is monospaced, so the widths compare like the lengths of the fragments.
+        assert_eq!(simple.min_content_width(), simple.width_mm("either/or"));
+        assert_eq!(unicode.min_content_width(), unicode.width_mm("(maybe)"));
+
+        assert_eq!(
+            unicode.break_into_lines(0.).collect::<Vec<_>>(),
+            ["either/", "or", "(maybe)"]
+        );
+    }
+
+    #[test]
+    fn test_cjk_layout() {
+        let doc = PdfDocument::empty("i contain a font");
+        let font = BuiltinFont::courier(&doc);
+
+        let cjk = |cjk, text| Text {
+            cjk,
+            ..Text::basic(text, &font, 12.)
+        };
+
+        let text = "「引用」「次」";
+        let compressed = cjk(
+            CjkLayout {
+                compress_punctuation: true,
+                ..Default::default()
+            },
+            text,
+        );
+        let plain = Text::basic(text, &font, 12.);
+
+        // Half an em between `」` and `「`.
+        let difference = plain.width_mm(text) - compressed.width_mm(text);
+        assert!((difference - pt_to_mm(6.)).abs() < 1e-9);
+
+        let text = "one two\nthree";
+        let justified = cjk(
+            CjkLayout {
+                justify: true,
+                ..Default::default()
+            },
+            text,
+        );
+
+        // Courier is monospaced with 0.6 em per character, so three characters are 21.6pt wide.
+        let spacing = justified.justification(&text[..3], "one", 20.).unwrap();
+        assert!((spacing - (mm_to_pt(20.) - 21.6) / 2.).abs() < 1e-9);
+
+        // The last lines of paragraphs aren't stretched.
+        assert_eq!(justified.justification(&text[4..7], "two", 20.), None);
+        assert_eq!(justified.justification(&text[8..], "three", 20.), None);
+        assert_eq!(
+            Text::basic(text, &font, 12.).justification(&text[..3], "one", 20.),
+            None
+        );
+    }
 }
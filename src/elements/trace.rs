@@ -0,0 +1,155 @@
+use crate::*;
+
+/// Passes everything through to the inner element, but logs the inputs and outputs of each pass
+/// together with an id when the `tracing` feature is enabled. This is meant for figuring out why
+/// an element in a large template breaks where it does.
+pub struct Trace<'a, E: Element> {
+    pub id: &'a str,
+    pub element: &'a E,
+}
+
+#[cfg(not(feature = "tracing"))]
+impl<'a, E: Element> Element for Trace<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        self.element.draw(ctx)
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl<'a, E: Element> Element for Trace<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        let (width, first_height, full_height) = (ctx.width, ctx.first_height, ctx.full_height);
+
+        let ret = self.element.first_location_usage(ctx);
+
+        tracing::debug!(
+            id = self.id,
+            max_width = width.max,
+            expand = width.expand,
+            first_height,
+            full_height,
+            result = ?ret,
+            "first_location_usage"
+        );
+
+        ret
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        let width = ctx.width;
+        let first_height = ctx.first_height;
+
+        let mut break_count = 0;
+        let mut extra_location_min_height = None;
+
+        let full_height = ctx.breakable.as_ref().map(|b| b.full_height);
+
+        let size = self.element.measure(MeasureCtx {
+            width,
+            first_height,
+            breakable: full_height.map(|full_height| BreakableMeasure {
+                full_height,
+                break_count: &mut break_count,
+                extra_location_min_height: &mut extra_location_min_height,
+            }),
+        });
+
+        if let Some(breakable) = ctx.breakable {
+            *breakable.break_count = break_count;
+            *breakable.extra_location_min_height = extra_location_min_height;
+        }
+
+        tracing::debug!(
+            id = self.id,
+            max_width = width.max,
+            expand = width.expand,
+            first_height,
+            full_height,
+            break_count,
+            extra_location_min_height,
+            width = size.width,
+            height = size.height,
+            "measure"
+        );
+
+        size
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let width = ctx.width;
+        let first_height = ctx.first_height;
+        let preferred_height = ctx.preferred_height;
+        let full_height = ctx.breakable.as_ref().map(|b| b.full_height);
+        let id = self.id;
+
+        let size = if let Some(breakable) = ctx.breakable {
+            self.element.draw(DrawCtx {
+                breakable: Some(BreakableDraw {
+                    do_break: &mut |pdf, location_idx, height| {
+                        tracing::debug!(id, location_idx, height, "break");
+
+                        (breakable.do_break)(pdf, location_idx, height)
+                    },
+                    ..breakable
+                }),
+                ..ctx
+            })
+        } else {
+            self.element.draw(ctx)
+        };
+
+        tracing::debug!(
+            id,
+            max_width = width.max,
+            expand = width.expand,
+            first_height,
+            full_height,
+            preferred_height,
+            width = size.width,
+            height = size.height,
+            "draw"
+        );
+
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_trace_passes_through() {
+        for output in (ElementTestParams {
+            first_height: 4.,
+            full_height: 5.,
+            ..Default::default()
+        })
+        .run(&Trace {
+            id: "text",
+            element: &FakeText {
+                lines: 3,
+                line_height: 2.,
+                width: 5.,
+            },
+        }) {
+            output.assert_size(ElementSize {
+                width: Some(output.width.constrain(5.)),
+                height: Some(if output.breakable.is_some() { 2. } else { 6. }),
+            });
+
+            if let Some(b) = output.breakable {
+                b.assert_break_count(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Facts about the whole document, like the number of pages or of attachments, are only known
+//! after layout. For content that shows them, like "3 attachments, 12 pages", the document is laid
+//! out twice: elements [count] things while [collect_facts] is running around the first layout,
+//! and read the totals with [pages] and [counter] while [with_facts] is running around the second
+//! one. [build_pdf_two_pass] does both:
+//!
+//! ```ignore
+//! let (document, facts) = build_pdf_two_pass("Report", (210., 297.), build_fonts, |fonts| {
+//!     let pages = facts::pages().map_or("??".to_string(), |p| p.to_string());
+//!     ..
+//! });
+//! ```
+//!
+//! The facts are from the first layout, so they can be off if showing them changes the layout,
+//! e.g. when a longer number makes a line wrap.
+
+use std::{cell::RefCell, collections::HashMap};
+
+use printpdf::PdfDocumentReference;
+
+use crate::{utils::scoped, *};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Facts {
+    pub pages: u32,
+
+    /// The totals of the counters, see [count].
+    pub counters: HashMap<String, u32>,
+}
+
+impl Facts {
+    /// The total of a counter, zero if nothing was counted.
+    pub fn counter(&self, name: &str) -> u32 {
+        self.counters.get(name).copied().unwrap_or(0)
+    }
+}
+
+thread_local! {
+    /// The facts of the layout that's running, while collecting.
+    static COLLECTING: RefCell<Option<Facts>> = const { RefCell::new(None) };
+
+    /// The facts of the previous layout.
+    static FACTS: RefCell<Option<Facts>> = const { RefCell::new(None) };
+}
+
+/// Collects the facts of the documents built on this thread while `f` is running. If more than
+/// one document is built, the pages are the ones of the last one and the counters add up.
+pub fn collect_facts<R>(f: impl FnOnce() -> R) -> (R, Facts) {
+    let (ret, facts) = scoped(&COLLECTING, Some(Facts::default()), f);
+    (ret, facts.unwrap_or_default())
+}
+
+/// Makes the facts available to [pages] and [counter] on this thread while `f` is running.
+pub fn with_facts<R>(facts: &Facts, f: impl FnOnce() -> R) -> R {
+    scoped(&FACTS, Some(facts.clone()), f).0
+}
+
+/// Adds to a counter of the document. This should only be called when drawing, since elements
+/// are measured any number of times. The [Count] element does it for other elements.
+pub fn count(name: &str, amount: u32) {
+    COLLECTING.with(|facts| {
+        if let Some(facts) = &mut *facts.borrow_mut() {
+            *facts.counters.entry(name.to_string()).or_insert(0) += amount;
+        }
+    });
+}
+
+pub(crate) fn set_pages(pages: u32) {
+    COLLECTING.with(|facts| {
+        if let Some(facts) = &mut *facts.borrow_mut() {
+            facts.pages = pages;
+        }
+    });
+}
+
+/// The number of pages of the previous layout, if there was one.
+pub fn pages() -> Option<u32> {
+    FACTS.with(|facts| facts.borrow().as_ref().map(|f| f.pages))
+}
+
+/// The total of a counter in the previous layout, if there was one.
+pub fn counter(name: &str) -> Option<u32> {
+    FACTS.with(|facts| facts.borrow().as_ref().map(|f| f.counter(name)))
+}
+
+/// Like [build_pdf], but the document is built twice and the facts of the first layout are
+/// available to the second one. The facts returned are the ones of the second layout.
+pub fn build_pdf_two_pass<F: 'static>(
+    name: &str,
+    page_size: (f64, f64),
+    build_fonts: impl Fn(&PdfDocumentReference) -> F,
+    build_element: impl for<'a> BuildElement<'a, F> + Clone,
+) -> (PdfDocumentReference, Facts) {
+    let (_, facts) =
+        collect_facts(|| build_pdf(name, page_size, &build_fonts, build_element.clone()));
+
+    with_facts(&facts, || {
+        collect_facts(|| build_pdf(name, page_size, &build_fonts, build_element))
+    })
+}
+
+/// Counts `amount` for the counter every time it's drawn. It takes up no space.
+pub struct Count<'a> {
+    pub counter: &'a str,
+    pub amount: u32,
+}
+
+impl<'a> Element for Count<'a> {
+    fn first_location_usage(&self, _ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        FirstLocationUsage::NoneHeight
+    }
+
+    fn measure(&self, _ctx: MeasureCtx) -> ElementSize {
+        ElementSize {
+            width: None,
+            height: None,
+        }
+    }
+
+    fn draw(&self, _ctx: DrawCtx) -> ElementSize {
+        count(self.counter, self.amount);
+
+        ElementSize {
+            width: None,
+            height: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{elements::column::Column, test_utils::FakeText};
+
+    #[test]
+    fn test_two_pass() {
+        let build_fonts = |_: &PdfDocumentReference| ();
+
+        let build_element = |_: &()| {
+            // Two pages worth of lines, plus one for every counted attachment in the first layout.
+            let lines = 2 * 10 + counter("attachments").unwrap_or(0);
+
+            Column::new(move |content| {
+                content
+                    .add(&Count {
+                        counter: "attachments",
+                        amount: 3,
+                    })?
+                    .add(&FakeText {
+                        lines,
+                        line_height: 10.,
+                        width: 10.,
+                    })?
+                    .add(&Count {
+                        counter: "attachments",
+                        amount: 2,
+                    })?;
+                None
+            })
+        };
+
+        assert_eq!(pages(), None);
+
+        let (_, facts) = build_pdf_two_pass("test", (100., 100.), build_fonts, build_element);
+
+        assert_eq!(facts.pages, 3);
+        assert_eq!(facts.counter("attachments"), 5);
+        assert_eq!(facts.counter("other"), 0);
+
+        // The facts are only available while building.
+        assert_eq!(counter("attachments"), None);
+    }
+}
@@ -72,10 +72,108 @@ impl DrawLayout {
     }
 }
 
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct AutoColumn {
+    /// The narrowest the content of the column can get without overflowing.
+    pub min: f64,
+
+    /// The width the content of the column would need to avoid any soft line breaks.
+    pub max: f64,
+}
+
+/// Computes column widths from the intrinsic widths of the cells, similar to the automatic table
+/// layout in HTML. The resulting widths can be passed to rows as fixed widths.
+#[derive(Clone, Debug, Default)]
+pub struct AutoColumnWidths {
+    columns: Vec<AutoColumn>,
+}
+
+impl AutoColumnWidths {
+    pub fn new(column_count: usize) -> Self {
+        AutoColumnWidths {
+            columns: vec![AutoColumn::default(); column_count],
+        }
+    }
+
+    /// Adds the intrinsic widths of a cell. The widths of a column are the maximums of its cells.
+    pub fn add_cell(&mut self, column: usize, min: f64, max: f64) {
+        if column >= self.columns.len() {
+            self.columns.resize(column + 1, AutoColumn::default());
+        }
+
+        let col = &mut self.columns[column];
+        col.min = col.min.max(min);
+        col.max = col.max.max(max).max(col.min);
+    }
+
+    pub fn columns(&self) -> &[AutoColumn] {
+        &self.columns
+    }
+
+    /// If all columns fit at their max width, they get their max width and any remaining space is
+    /// distributed proportionally to it. If not even the min widths fit, the columns get their min
+    /// widths and will overflow. Otherwise each column gets its min width plus a share of the
+    /// remaining space proportional to the difference between its max and min width.
+    pub fn widths(&self, width: f64, gap: f64) -> Vec<f64> {
+        let gaps = gap * self.columns.len().saturating_sub(1) as f64;
+        let available = (width - gaps).max(0.);
+
+        let total_min: f64 = self.columns.iter().map(|c| c.min).sum();
+        let total_max: f64 = self.columns.iter().map(|c| c.max).sum();
+
+        if total_max <= available {
+            if total_max <= 0. {
+                let count = self.columns.len().max(1) as f64;
+                return vec![available / count; self.columns.len()];
+            }
+
+            let scale = available / total_max;
+            self.columns.iter().map(|c| c.max * scale).collect()
+        } else if total_min >= available {
+            self.columns.iter().map(|c| c.min).collect()
+        } else {
+            let ratio = (available - total_min) / (total_max - total_min);
+
+            self.columns
+                .iter()
+                .map(|c| c.min + (c.max - c.min) * ratio)
+                .collect()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_auto_column_widths() {
+        let mut auto = AutoColumnWidths::new(2);
+        auto.add_cell(0, 10., 20.);
+        auto.add_cell(1, 5., 10.);
+        auto.add_cell(1, 8., 9.);
+
+        assert_eq!(
+            auto.columns(),
+            [
+                AutoColumn { min: 10., max: 20. },
+                AutoColumn { min: 8., max: 10. },
+            ]
+        );
+
+        // everything fits, extra space is distributed proportionally
+        assert_eq!(auto.widths(62., 2.), [40., 20.]);
+
+        // nothing fits
+        assert_eq!(auto.widths(10., 2.), [10., 8.]);
+
+        // in between: 4 of the 12 extra mm get distributed 10:2
+        let widths = auto.widths(24., 2.);
+        assert!((widths[0] - (10. + 10. / 3.)).abs() < 1e-9);
+        assert!((widths[1] - (8. + 2. / 3.)).abs() < 1e-9);
+        assert!((widths[0] + widths[1] + 2. - 24.).abs() < 1e-9);
+    }
+
     #[test]
     fn test_alignment() {
         let mut layout = MeasureLayout::new(15., 2.);
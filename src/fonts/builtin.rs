@@ -112,11 +112,15 @@ impl Font for BuiltinFont {
     }
 
     fn codepoint_h_metrics(&self, codepoint: u32) -> super::HMetrics {
-        let metrics = self.char_metrics_by_codepoint.get(&codepoint).unwrap();
-
-        super::HMetrics {
-            advance_width: metrics.wx,
-        }
+        // Characters that aren't in the font can't be shown, but shouldn't take the layout down.
+        // They get the width of a space.
+        let advance_width = self
+            .char_metrics_by_codepoint
+            .get(&codepoint)
+            .or_else(|| self.char_metrics_by_codepoint.get(&(' ' as u32)))
+            .map_or(0., |metrics| metrics.wx);
+
+        super::HMetrics { advance_width }
     }
 
     fn units_per_em(&self) -> u16 {
@@ -0,0 +1,301 @@
+//! Color glyphs from the `COLR` and `CPAL` tables (version 0), which is how most color emoji fonts
+//! that aren't bitmap based work. A color glyph is a stack of regular glyphs, each filled with a
+//! color from the palette. PDF fonts can't have colors, so these are drawn as filled paths instead
+//! of being written as text.
+
+use std::{collections::HashMap, ops::Range};
+
+use lopdf::content::Operation;
+use printpdf::PdfLayerReference;
+
+use crate::utils::{add_op, mm_to_pt, u32_to_color_and_alpha};
+
+use super::Font;
+
+/// The layers of the color glyphs of a font, parsed from its `COLR` and `CPAL` tables.
+#[derive(Clone, Debug, Default)]
+pub struct ColorGlyphs {
+    /// The layer glyphs and their colors by base glyph, bottom layer first. A color of `None` means
+    /// the color of the text.
+    layers: HashMap<u16, Vec<(u16, Option<u32>)>>,
+}
+
+/// One layer of a color glyph in font units.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ColorLayer {
+    /// `None` means the color of the text.
+    pub color: Option<u32>,
+
+    pub contours: Vec<Vec<PathSegment>>,
+}
+
+/// The outline of a glyph. Every contour starts with a [PathSegment::MoveTo].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum PathSegment {
+    MoveTo(f64, f64),
+    LineTo(f64, f64),
+
+    /// A quadratic curve with the control point first.
+    QuadTo(f64, f64, f64, f64),
+}
+
+fn u16_at(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn u32_at(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn find_table<'a>(font: &'a [u8], tag: &[u8; 4]) -> Option<&'a [u8]> {
+    let num_tables = u16_at(font, 4)? as usize;
+
+    (0..num_tables).find_map(|i| {
+        let record = 12 + i * 16;
+
+        if font.get(record..record + 4)? != tag {
+            return None;
+        }
+
+        let offset = u32_at(font, record + 8)? as usize;
+        let length = u32_at(font, record + 12)? as usize;
+
+        font.get(offset..offset.checked_add(length)?)
+    })
+}
+
+impl ColorGlyphs {
+    /// Returns `None` if the font has no color glyphs that can be read. Only the first palette is
+    /// used.
+    pub fn parse(font: &[u8]) -> Option<Self> {
+        let colr = find_table(font, b"COLR")?;
+        let cpal = find_table(font, b"CPAL")?;
+
+        let palette_size = u16_at(cpal, 2)? as usize;
+        let color_records = u32_at(cpal, 8)? as usize;
+        let first_color = u16_at(cpal, 12)? as usize;
+
+        let color = |index: u16| -> Option<Option<u32>> {
+            if index == 0xFFFF {
+                return Some(None);
+            }
+
+            if index as usize >= palette_size {
+                return None;
+            }
+
+            let record = color_records + (first_color + index as usize) * 4;
+            let [b, g, r, a]: [u8; 4] = cpal.get(record..record + 4)?.try_into().ok()?;
+
+            Some(Some(u32::from_be_bytes([r, g, b, a])))
+        };
+
+        let base_glyph_count = u16_at(colr, 2)? as usize;
+        let base_glyphs = u32_at(colr, 4)? as usize;
+        let layer_records = u32_at(colr, 8)? as usize;
+
+        let mut layers = HashMap::with_capacity(base_glyph_count);
+
+        for i in 0..base_glyph_count {
+            let record = base_glyphs + i * 6;
+
+            let glyph = u16_at(colr, record)?;
+            let first_layer = u16_at(colr, record + 2)? as usize;
+            let layer_count = u16_at(colr, record + 4)? as usize;
+
+            let glyph_layers = (first_layer..first_layer + layer_count)
+                .map(|layer| {
+                    let record = layer_records + layer * 4;
+                    Some((u16_at(colr, record)?, color(u16_at(colr, record + 2)?)?))
+                })
+                .collect::<Option<Vec<_>>>()?;
+
+            layers.insert(glyph, glyph_layers);
+        }
+
+        if layers.is_empty() {
+            None
+        } else {
+            Some(ColorGlyphs { layers })
+        }
+    }
+
+    /// The layer glyphs and their colors of a base glyph.
+    pub fn layers(&self, glyph: u16) -> Option<&[(u16, Option<u32>)]> {
+        self.layers.get(&glyph).map(|l| &l[..])
+    }
+}
+
+/// Splits the text into runs drawn as text and single characters that are color glyphs.
+pub(crate) fn color_runs(
+    font: &impl Font,
+    text: &str,
+) -> Vec<(Range<usize>, Option<Vec<ColorLayer>>)> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+
+    for (i, c) in text.char_indices() {
+        if let Some(layers) = font.color_glyph(c as u32) {
+            if start < i {
+                runs.push((start..i, None));
+            }
+
+            start = i + c.len_utf8();
+            runs.push((i..start, Some(layers)));
+        }
+    }
+
+    if start < text.len() {
+        runs.push((start..text.len(), None));
+    }
+
+    runs
+}
+
+/// Draws a color glyph with its origin at `pos`, in mm.
+pub(crate) fn draw_color_glyph(
+    layer: &PdfLayerReference,
+    glyph: &[ColorLayer],
+    size: f64,
+    units_per_em: f64,
+    text_color: u32,
+    pos: (f64, f64),
+) {
+    let scale = size / units_per_em;
+    let (x, y) = (mm_to_pt(pos.0), mm_to_pt(pos.1));
+    let point = |px: f64, py: f64| (x + px * scale, y + py * scale);
+
+    layer.save_graphics_state();
+
+    for glyph_layer in glyph {
+        let (color, alpha) = u32_to_color_and_alpha(glyph_layer.color.unwrap_or(text_color));
+        layer.set_fill_color(color);
+        layer.set_fill_alpha(alpha);
+
+        for contour in &glyph_layer.contours {
+            let mut current = (0., 0.);
+
+            for &segment in contour {
+                match segment {
+                    PathSegment::MoveTo(px, py) => {
+                        current = point(px, py);
+                        add_op(
+                            layer,
+                            Operation::new("m", vec![current.0.into(), current.1.into()]),
+                        );
+                    }
+                    PathSegment::LineTo(px, py) => {
+                        current = point(px, py);
+                        add_op(
+                            layer,
+                            Operation::new("l", vec![current.0.into(), current.1.into()]),
+                        );
+                    }
+                    PathSegment::QuadTo(cx, cy, px, py) => {
+                        // PDF only has cubic curves, but every quadratic one can be written as one.
+                        let control = point(cx, cy);
+                        let end = point(px, py);
+
+                        let c1 = (
+                            current.0 + 2. / 3. * (control.0 - current.0),
+                            current.1 + 2. / 3. * (control.1 - current.1),
+                        );
+                        let c2 = (
+                            end.0 + 2. / 3. * (control.0 - end.0),
+                            end.1 + 2. / 3. * (control.1 - end.1),
+                        );
+
+                        add_op(
+                            layer,
+                            Operation::new(
+                                "c",
+                                vec![
+                                    c1.0.into(),
+                                    c1.1.into(),
+                                    c2.0.into(),
+                                    c2.1.into(),
+                                    end.0.into(),
+                                    end.1.into(),
+                                ],
+                            ),
+                        );
+
+                        current = end;
+                    }
+                }
+            }
+
+            add_op(layer, Operation::new("h", Vec::new()));
+        }
+
+        // Glyph outlines use the nonzero winding rule.
+        add_op(layer, Operation::new("f", Vec::new()));
+    }
+
+    layer.restore_graphics_state();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A font with only the tables needed here.
+    fn font(tables: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut font = vec![0, 1, 0, 0];
+        font.extend((tables.len() as u16).to_be_bytes());
+        font.extend([0; 6]);
+
+        let mut offset = 12 + tables.len() * 16;
+
+        for (tag, data) in tables {
+            font.extend(*tag);
+            font.extend([0; 4]);
+            font.extend((offset as u32).to_be_bytes());
+            font.extend((data.len() as u32).to_be_bytes());
+            offset += data.len();
+        }
+
+        for (_, data) in tables {
+            font.extend(data);
+        }
+
+        font
+    }
+
+    #[test]
+    fn test_parse() {
+        let mut colr = Vec::new();
+        colr.extend(0u16.to_be_bytes()); // version
+        colr.extend(1u16.to_be_bytes()); // base glyphs
+        colr.extend(14u32.to_be_bytes()); // base glyph records
+        colr.extend(20u32.to_be_bytes()); // layer records
+        colr.extend(2u16.to_be_bytes()); // layers
+        colr.extend([0, 5, 0, 0, 0, 2]); // glyph 5, layers 0..2
+        colr.extend([0, 6, 0, 1]); // glyph 6, palette entry 1
+        colr.extend([0, 7, 0xFF, 0xFF]); // glyph 7, text color
+
+        let mut cpal = Vec::new();
+        cpal.extend(0u16.to_be_bytes()); // version
+        cpal.extend(2u16.to_be_bytes()); // palette entries
+        cpal.extend(1u16.to_be_bytes()); // palettes
+        cpal.extend(2u16.to_be_bytes()); // color records
+        cpal.extend(14u32.to_be_bytes()); // color records offset
+        cpal.extend(0u16.to_be_bytes()); // first color of the first palette
+        cpal.extend([0xFF, 0, 0, 0xFF, 0, 0xFF, 0, 0x80]); // blue, half transparent green
+
+        let glyphs = ColorGlyphs::parse(&font(&[(b"COLR", colr), (b"CPAL", cpal)])).unwrap();
+
+        assert_eq!(
+            glyphs.layers(5).unwrap(),
+            [(6, Some(0x00_FF_00_80)), (7, None)]
+        );
+        assert!(glyphs.layers(6).is_none());
+
+        assert!(ColorGlyphs::parse(&font(&[])).is_none());
+        assert!(ColorGlyphs::parse(&[]).is_none());
+    }
+}
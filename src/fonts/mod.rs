@@ -1,6 +1,8 @@
 use printpdf::IndirectFontRef;
+use serde::{Deserialize, Serialize};
 
 pub mod builtin;
+pub mod color;
 pub mod truetype;
 
 pub struct HMetrics {
@@ -20,4 +22,30 @@ pub trait Font {
     fn units_per_em(&self) -> u16;
 
     fn general_metrics(&self) -> GeneralMetrics;
+
+    /// The layers of the glyph if it's a color glyph, like most emoji in color fonts.
+    fn color_glyph(&self, _codepoint: u32) -> Option<Vec<color::ColorLayer>> {
+        None
+    }
+}
+
+/// Effects that fake a style the font doesn't have a face for.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Synthesis {
+    /// Strokes the outlines of the glyphs in addition to filling them.
+    #[serde(default)]
+    pub bold: bool,
+
+    /// Slants the glyphs to the right.
+    #[serde(default)]
+    pub oblique: bool,
+}
+
+/// The effects to apply to the faces of a [FontSet](crate::FontSet) that stand in for missing
+/// ones, e.g. the regular face with [Synthesis::bold] for a bold face.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyntheticFaces {
+    pub bold: Synthesis,
+    pub italic: Synthesis,
+    pub bold_italic: Synthesis,
 }
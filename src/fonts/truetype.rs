@@ -1,26 +1,86 @@
 use std::ops::Deref;
 
 use printpdf::{IndirectFontRef, PdfDocumentReference};
-use stb_truetype::FontInfo;
+use stb_truetype::{FontInfo, VertexType};
 
-use super::Font;
+use super::{
+    color::{ColorGlyphs, ColorLayer, PathSegment},
+    Font,
+};
 
 #[derive(Debug)]
 pub struct TruetypeFont<D: Deref<Target = [u8]>> {
     pub font_ref: IndirectFontRef,
     pub font: FontInfo<D>,
+
+    /// Drawn as paths instead of text, since PDF fonts can't have colors.
+    pub color_glyphs: Option<ColorGlyphs>,
 }
 
 impl<D: AsRef<[u8]> + Deref<Target = [u8]>> TruetypeFont<D> {
+    /// Panics if the bytes aren't a valid font. See [Self::try_new].
     pub fn new(doc: &PdfDocumentReference, bytes: D) -> Self {
+        Self::try_new(doc, bytes).unwrap()
+    }
+
+    pub fn try_new(doc: &PdfDocumentReference, bytes: D) -> Result<Self, String> {
+        // Checked before adding the font to the document, so an invalid font doesn't end up in it.
+        if FontInfo::new(&bytes[..], 0).is_none() {
+            return Err("invalid font".to_string());
+        }
+
+        let color_glyphs = ColorGlyphs::parse(&bytes);
+
         let font_reader = std::io::Cursor::new(&bytes);
-        let pdf_font = doc.add_external_font(font_reader).unwrap();
-        let font_info = FontInfo::new(bytes, 0).unwrap();
+        let pdf_font = doc
+            .add_external_font(font_reader)
+            .map_err(|e| format!("couldn't add font: {e:?}"))?;
+        let font_info = FontInfo::new(bytes, 0).expect("checked above");
 
-        TruetypeFont {
+        Ok(TruetypeFont {
             font_ref: pdf_font,
             font: font_info,
-        }
+            color_glyphs,
+        })
+    }
+}
+
+/// A font that's only checked and parsed once, to add it to many documents, see
+/// [TruetypeFont::from_parsed]. Cloning it is as cheap as cloning the bytes, so they should be
+/// shared, like with an `Arc<[u8]>`.
+#[derive(Clone, Debug)]
+pub struct ParsedFont<D: Deref<Target = [u8]>> {
+    bytes: D,
+    font: FontInfo<D>,
+    color_glyphs: Option<ColorGlyphs>,
+}
+
+impl<D: Clone + AsRef<[u8]> + Deref<Target = [u8]>> ParsedFont<D> {
+    pub fn new(bytes: D) -> Result<Self, String> {
+        let font = FontInfo::new(bytes.clone(), 0).ok_or_else(|| "invalid font".to_string())?;
+        let color_glyphs = ColorGlyphs::parse(&bytes);
+
+        Ok(ParsedFont {
+            bytes,
+            font,
+            color_glyphs,
+        })
+    }
+}
+
+impl<D: Clone + AsRef<[u8]> + Deref<Target = [u8]>> TruetypeFont<D> {
+    /// Like [Self::try_new], but without parsing the font again.
+    pub fn from_parsed(doc: &PdfDocumentReference, parsed: &ParsedFont<D>) -> Result<Self, String> {
+        let font_reader = std::io::Cursor::new(&parsed.bytes);
+        let pdf_font = doc
+            .add_external_font(font_reader)
+            .map_err(|e| format!("couldn't add font: {e:?}"))?;
+
+        Ok(TruetypeFont {
+            font_ref: pdf_font,
+            font: parsed.font.clone(),
+            color_glyphs: parsed.color_glyphs.clone(),
+        })
     }
 }
 
@@ -51,4 +111,47 @@ impl<D: Deref<Target = [u8]>> Font for TruetypeFont<D> {
             line_height: (v_metrics.ascent + v_metrics.descent.abs() + v_metrics.line_gap) as f64,
         }
     }
+
+    fn color_glyph(&self, codepoint: u32) -> Option<Vec<ColorLayer>> {
+        let color_glyphs = self.color_glyphs.as_ref()?;
+        let glyph = self.font.find_glyph_index(codepoint);
+        let layers = color_glyphs.layers(u16::try_from(glyph).ok()?)?;
+
+        Some(
+            layers
+                .iter()
+                .map(|&(layer_glyph, color)| ColorLayer {
+                    color,
+                    contours: self.glyph_contours(layer_glyph as u32),
+                })
+                .collect(),
+        )
+    }
+}
+
+impl<D: Deref<Target = [u8]>> TruetypeFont<D> {
+    fn glyph_contours(&self, glyph: u32) -> Vec<Vec<PathSegment>> {
+        let mut contours: Vec<Vec<PathSegment>> = Vec::new();
+
+        for vertex in self.font.get_glyph_shape(glyph).unwrap_or_default() {
+            let (x, y) = (vertex.x as f64, vertex.y as f64);
+
+            match vertex.vertex_type() {
+                VertexType::MoveTo => contours.push(vec![PathSegment::MoveTo(x, y)]),
+                VertexType::LineTo => {
+                    if let Some(contour) = contours.last_mut() {
+                        contour.push(PathSegment::LineTo(x, y));
+                    }
+                }
+                VertexType::CurveTo => {
+                    if let Some(contour) = contours.last_mut() {
+                        let (cx, cy) = (vertex.cx as f64, vertex.cy as f64);
+                        contour.push(PathSegment::QuadTo(cx, cy, x, y));
+                    }
+                }
+            }
+        }
+
+        contours
+    }
 }
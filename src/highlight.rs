@@ -0,0 +1,145 @@
+//! Syntax highlighting of source code for [RichText](crate::elements::rich_text::RichText), with
+//! the syntax definitions and themes that come with syntect. This needs the `highlight` feature,
+//! without it highlighting always fails.
+//!
+//! The languages are looked up by name or file extension, like `Rust` or `rs`. The themes are
+//! `InspiredGitHub`, `Solarized (light)`, `Solarized (dark)`, `base16-ocean.light`,
+//! `base16-ocean.dark`, `base16-eighties.dark` and `base16-mocha.dark`.
+
+use crate::elements::rich_text::Span;
+
+/// A light theme, since reports are usually printed on white.
+pub const DEFAULT_THEME: &str = "InspiredGitHub";
+
+/// Colors the code with the theme. Tabs are replaced by spaces up to the next multiple of
+/// `tab_width` columns, like in [Code](crate::elements::code::Code). Adjacent runs with the same
+/// style end up in the same span.
+pub fn highlight(
+    code: &str,
+    language: &str,
+    theme: &str,
+    tab_width: u32,
+) -> Result<Vec<Span>, String> {
+    #[cfg(feature = "highlight")]
+    {
+        imp::highlight(code, language, theme, tab_width)
+    }
+
+    #[cfg(not(feature = "highlight"))]
+    {
+        let _ = (code, theme, tab_width);
+
+        Err(format!(
+            "could not highlight {language}: highlighting needs the `highlight` feature"
+        ))
+    }
+}
+
+#[cfg(feature = "highlight")]
+mod imp {
+    use std::sync::OnceLock;
+
+    use syntect::{
+        easy::HighlightLines,
+        highlighting::{FontStyle, ThemeSet},
+        parsing::SyntaxSet,
+    };
+
+    use super::*;
+    use crate::elements::code::expand_tabs;
+
+    fn syntaxes() -> &'static SyntaxSet {
+        static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+        SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+    }
+
+    fn themes() -> &'static ThemeSet {
+        static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+        THEMES.get_or_init(ThemeSet::load_defaults)
+    }
+
+    pub fn highlight(
+        code: &str,
+        language: &str,
+        theme: &str,
+        tab_width: u32,
+    ) -> Result<Vec<Span>, String> {
+        let syntaxes = syntaxes();
+
+        let syntax = syntaxes
+            .find_syntax_by_token(language)
+            .ok_or_else(|| format!("unknown language `{language}`"))?;
+
+        let theme = themes()
+            .themes
+            .get(theme)
+            .ok_or_else(|| format!("unknown theme `{theme}`"))?;
+
+        let mut highlighter = HighlightLines::new(syntax, theme);
+        let mut spans: Vec<Span> = Vec::new();
+
+        for line in code.split_inclusive('\n') {
+            let line = expand_tabs(line, tab_width);
+
+            for (style, text) in highlighter
+                .highlight_line(&line, syntaxes)
+                .map_err(|e| format!("could not highlight {language}: {e}"))?
+            {
+                let c = style.foreground;
+
+                let span = Span {
+                    text: text.into(),
+                    bold: style.font_style.contains(FontStyle::BOLD),
+                    italic: style.font_style.contains(FontStyle::ITALIC),
+                    underline: style.font_style.contains(FontStyle::UNDERLINE),
+                    color: u32::from_be_bytes([c.r, c.g, c.b, c.a]),
+                    underline_color: None,
+                    underline_thickness: None,
+                    script: None,
+                    language: None,
+                    markup: None,
+                    ruby: None,
+                };
+
+                match spans.last_mut() {
+                    Some(last)
+                        if (last.bold, last.italic, last.underline, last.color)
+                            == (span.bold, span.italic, span.underline, span.color) =>
+                    {
+                        last.text.push_str(&span.text);
+                    }
+                    _ => spans.push(span),
+                }
+            }
+        }
+
+        Ok(spans)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "highlight")]
+    fn test_highlight() {
+        let spans = highlight("fn main() {\n\tlet a = 1;\n}\n", "rs", DEFAULT_THEME, 4).unwrap();
+
+        let text: String = spans.iter().map(|s| &s.text[..]).collect();
+        assert_eq!(text, "fn main() {\n    let a = 1;\n}\n");
+
+        // The keywords are colored differently than the rest.
+        let color = |word: &str| spans.iter().find(|s| s.text.contains(word)).unwrap().color;
+        assert_ne!(color("fn"), color("main"));
+
+        assert!(highlight("", "no such language", DEFAULT_THEME, 4).is_err());
+        assert!(highlight("", "rs", "no such theme", 4).is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "highlight"))]
+    fn test_highlight_without_feature() {
+        assert!(highlight("fn main() {}", "rs", DEFAULT_THEME, 4).is_err());
+    }
+}
@@ -1,5 +1,146 @@
+use std::{
+    cell::RefCell,
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    rc::{Rc, Weak},
+};
+
 use serde::{de::Visitor, Deserializer};
 
+use crate::utils::scoped;
+
+/// Restricts the files that can be read with [read_file] to a directory, for rendering documents
+/// from untrusted input. Only relative paths are allowed and they're resolved against the
+/// directory. Symbolic links that lead out of the directory are rejected as well.
+#[derive(Clone, Debug)]
+pub struct BaseDir(pub PathBuf);
+
+thread_local! {
+    static BASE_DIR: RefCell<Option<BaseDir>> = const { RefCell::new(None) };
+}
+
+impl BaseDir {
+    /// Restricts the files read on this thread while `f` is running.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        scoped(&BASE_DIR, Some(self.clone()), f).0
+    }
+
+    #[cfg(feature = "fs")]
+    fn resolve(&self, path: &Path) -> Result<PathBuf, String> {
+        let outside = || {
+            format!(
+                "could not read {}: only paths inside of the base directory can be read",
+                path.display()
+            )
+        };
+
+        if !is_plain_relative(path) {
+            return Err(outside());
+        }
+
+        let base = self
+            .0
+            .canonicalize()
+            .map_err(|e| format!("could not read {}: {e}", self.0.display()))?;
+
+        let resolved = base
+            .join(path)
+            .canonicalize()
+            .map_err(|e| format!("could not read {}: {e}", path.display()))?;
+
+        if resolved.starts_with(&base) {
+            Ok(resolved)
+        } else {
+            Err(outside())
+        }
+    }
+
+    /// Resolves a path to write a file to like [read_file] resolves the ones it reads. The file
+    /// doesn't have to exist yet, but its directory does.
+    #[cfg(feature = "fs")]
+    pub fn resolve_output(&self, path: &Path) -> Result<PathBuf, String> {
+        let outside = || {
+            format!(
+                "could not write {}: only paths inside of the base directory can be written",
+                path.display()
+            )
+        };
+
+        let file_name = match path.file_name() {
+            Some(file_name) => file_name,
+            None => return Err(outside()),
+        };
+
+        if !is_plain_relative(path) {
+            return Err(outside());
+        }
+
+        let base = self
+            .0
+            .canonicalize()
+            .map_err(|e| format!("could not write {}: {e}", self.0.display()))?;
+
+        let joined = base.join(path);
+        let dir = joined
+            .parent()
+            .unwrap_or(&base)
+            .canonicalize()
+            .map_err(|e| format!("could not write {}: {e}", path.display()))?;
+
+        let resolved = dir.join(file_name);
+
+        // A file that's already there could be a symbolic link, which is written through.
+        let target = if resolved.symlink_metadata().is_ok() {
+            resolved
+                .canonicalize()
+                .map_err(|e| format!("could not write {}: {e}", path.display()))?
+        } else {
+            resolved
+        };
+
+        if target.starts_with(&base) {
+            Ok(target)
+        } else {
+            Err(outside())
+        }
+    }
+}
+
+/// Whether the path is relative and doesn't go up any directories.
+#[cfg(feature = "fs")]
+fn is_plain_relative(path: &Path) -> bool {
+    use std::path::Component;
+
+    path.components()
+        .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// Reads a file. Without the `fs` feature, e.g. on WASM, there's no file system and this always
+/// fails, so everything that loads files from paths fails with an error instead. Inside of a
+/// [BaseDir::scope] only files in that directory can be read.
+pub fn read_file(path: impl AsRef<Path>) -> Result<Vec<u8>, String> {
+    #[cfg(feature = "fs")]
+    {
+        let path = path.as_ref();
+
+        let resolved = BASE_DIR.with(|base_dir| match &*base_dir.borrow() {
+            Some(base_dir) => base_dir.resolve(path),
+            None => Ok(path.to_path_buf()),
+        })?;
+
+        std::fs::read(resolved).map_err(|e| format!("could not read {}: {e}", path.display()))
+    }
+
+    #[cfg(not(feature = "fs"))]
+    {
+        Err(format!(
+            "could not read {}: reading files needs the `fs` feature",
+            path.as_ref().display()
+        ))
+    }
+}
+
 pub fn deserialize_buffer<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
     struct FileVisitor;
 
@@ -11,15 +152,7 @@ pub fn deserialize_buffer<'de, D: Deserializer<'de>>(deserializer: D) -> Result<
         }
 
         fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
-            std::fs::read(v).map_err(|e| E::custom(e))
-        }
-
-        fn visit_borrowed_str<E: serde::de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
-            std::fs::read(v).map_err(|e| E::custom(e))
-        }
-
-        fn visit_string<E: serde::de::Error>(self, v: String) -> Result<Self::Value, E> {
-            std::fs::read(v).map_err(|e| E::custom(e))
+            read_file(v).map_err(E::custom)
         }
     }
 
@@ -65,15 +198,9 @@ pub fn deserialize_svg_from_path<'de, D: Deserializer<'de>>(
         }
 
         fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
-            usvg::Tree::from_file(v, &Default::default()).map_err(|e| E::custom(e))
-        }
-
-        fn visit_borrowed_str<E: serde::de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
-            usvg::Tree::from_file(v, &Default::default()).map_err(|e| E::custom(e))
-        }
+            let path = Path::new(v);
 
-        fn visit_string<E: serde::de::Error>(self, v: String) -> Result<Self::Value, E> {
-            usvg::Tree::from_file(&v, &Default::default()).map_err(|e| E::custom(e))
+            load_svg(path, &read_file(path).map_err(E::custom)?).map_err(E::custom)
         }
     }
 
@@ -86,40 +213,319 @@ pub enum Image {
     Pixel(printpdf::image::DynamicImage),
 }
 
-pub fn deserialize_image<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Image, D::Error> {
-    struct ImageVisitor;
+fn load_svg(path: &Path, data: &[u8]) -> Result<usvg::Tree, String> {
+    let options = usvg::Options {
+        resources_dir: path.parent().map(|p| p.to_path_buf()),
+        ..Default::default()
+    };
 
-    fn visit<E: serde::de::Error>(path: impl AsRef<std::path::Path>) -> Result<Image, E> {
-        if path.as_ref().extension().map_or(false, |e| e == "svg") {
-            Ok(Image::Svg(
-                usvg::Tree::from_file(path, &Default::default()).map_err(E::custom)?,
-            ))
-        } else {
-            Ok(Image::Pixel(
-                printpdf::image::open(path).map_err(E::custom)?,
-            ))
+    usvg::Tree::from_data(data, &options).map_err(|e| e.to_string())
+}
+
+fn is_svg(path: &Path) -> bool {
+    path.extension().map_or(false, |e| e == "svg")
+}
+
+fn load(path: &Path, data: &[u8]) -> Result<Image, String> {
+    if is_svg(path) {
+        Ok(Image::Svg(load_svg(path, data)?))
+    } else {
+        Ok(Image::Pixel(
+            printpdf::image::load_from_memory(data).map_err(|e| e.to_string())?,
+        ))
+    }
+}
+
+/// An image in the [POOL] with the file content it was loaded from.
+struct Pooled {
+    svg: bool,
+    data: Vec<u8>,
+    image: Weak<Image>,
+}
+
+thread_local! {
+    /// Images by the hash of their file content. The content itself is compared as well, so
+    /// images whose hashes collide aren't mixed up. Only weak references are kept, so images are
+    /// dropped as soon as nothing uses them anymore.
+    static POOL: RefCell<HashMap<u64, Vec<Pooled>>> = RefCell::new(HashMap::new());
+
+    static LOADED: RefCell<Option<Vec<Rc<Image>>>> = const { RefCell::new(None) };
+}
+
+/// Collects the images [load_shared] returns on this thread while `f` is running. As long as they
+/// are kept alive, they stay in the [POOL], so later documents that use them don't decode them
+/// again.
+pub fn collect_loaded_images<R>(f: impl FnOnce() -> R) -> (R, Vec<Rc<Image>>) {
+    let (ret, images) = scoped(&LOADED, Some(Vec::new()), f);
+    (ret, images.unwrap_or_default())
+}
+
+fn loaded(image: &Rc<Image>) {
+    LOADED.with(|loaded| {
+        if let Some(loaded) = loaded.borrow_mut().as_mut() {
+            if !loaded.iter().any(|i| Rc::ptr_eq(i, image)) {
+                loaded.push(image.clone());
+            }
         }
+    });
+}
+
+/// Loads an image, sharing it with every other image loaded this way on this thread that has the
+/// same file content and is still alive. This way an image that's used in many places, like a logo,
+/// is only decoded and kept in memory once.
+///
+/// Every place the image is drawn still embeds it in the document, see [share_image_xobjects] for
+/// storing it only once there.
+pub fn load_shared(path: impl AsRef<Path>) -> Result<Rc<Image>, String> {
+    let path = path.as_ref();
+    let data = read_file(path)?;
+    let svg = is_svg(path);
+
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    let key = hasher.finish();
+
+    let pooled = POOL.with(|pool| {
+        pool.borrow()
+            .get(&key)
+            .into_iter()
+            .flatten()
+            .find(|pooled| pooled.svg == svg && pooled.data == data)
+            .and_then(|pooled| pooled.image.upgrade())
+    });
+
+    if let Some(image) = pooled {
+        loaded(&image);
+        return Ok(image);
     }
 
-    impl<'de> Visitor<'de> for ImageVisitor {
-        type Value = Image;
+    let image = Rc::new(load(path, &data)?);
 
-        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-            formatter.write_str("a valid image")
+    POOL.with(|pool| {
+        let mut pool = pool.borrow_mut();
+
+        pool.retain(|_, images| {
+            images.retain(|pooled| pooled.image.strong_count() > 0);
+            !images.is_empty()
+        });
+
+        pool.entry(key).or_default().push(Pooled {
+            svg,
+            data,
+            image: Rc::downgrade(&image),
+        });
+    });
+
+    loaded(&image);
+
+    Ok(image)
+}
+
+/// Makes all the image XObjects of the document that are exactly the same one object, e.g. when
+/// a logo is drawn on every page. printpdf embeds an image every time it's drawn.
+pub fn share_image_xobjects(document: &mut lopdf::Document) {
+    use std::collections::{hash_map::Entry, BTreeMap};
+
+    use lopdf::Object;
+
+    // Images with a soft mask only become the same after their masks were merged.
+    loop {
+        let mut originals = HashMap::new();
+        let mut duplicates = BTreeMap::new();
+
+        for (&id, object) in &document.objects {
+            let Object::Stream(stream) = object else {
+                continue;
+            };
+
+            if !matches!(stream.dict.get(b"Subtype"), Ok(Object::Name(n)) if n == b"Image") {
+                continue;
+            }
+
+            match originals.entry((format!("{:?}", stream.dict), &stream.content)) {
+                Entry::Occupied(original) => {
+                    duplicates.insert(id, *original.get());
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(id);
+                }
+            }
         }
 
-        fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
-            visit(v)
+        if duplicates.is_empty() {
+            return;
         }
 
-        fn visit_borrowed_str<E: serde::de::Error>(self, v: &'de str) -> Result<Self::Value, E> {
-            visit(v)
+        for id in duplicates.keys() {
+            document.objects.remove(id);
         }
 
-        fn visit_string<E: serde::de::Error>(self, v: String) -> Result<Self::Value, E> {
-            visit(v)
+        for object in document.objects.values_mut() {
+            *object = crate::letterhead::remap(object, &duplicates);
         }
     }
+}
+
+struct ImageVisitor;
+
+impl<'de> Visitor<'de> for ImageVisitor {
+    type Value = Rc<Image>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a valid image")
+    }
+
+    fn visit_str<E: serde::de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        load_shared(v).map_err(E::custom)
+    }
+}
+
+pub fn deserialize_image<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Image, D::Error> {
+    let image = deserializer.deserialize_str(ImageVisitor)?;
 
-    Ok(deserializer.deserialize_str(ImageVisitor)?)
+    Ok(Rc::try_unwrap(image).unwrap_or_else(|image| (*image).clone()))
+}
+
+/// Like [deserialize_image], but shares the image using [load_shared].
+pub fn deserialize_shared_image<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Rc<Image>, D::Error> {
+    deserializer.deserialize_str(ImageVisitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_load_shared() {
+        let dir = std::env::temp_dir().join(format!("laser-pdf-image-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"/>"#;
+        let a = dir.join("a.svg");
+        let b = dir.join("b.svg");
+        std::fs::write(&a, svg).unwrap();
+        std::fs::write(&b, svg).unwrap();
+
+        let first = load_shared(&a).unwrap();
+        let second = load_shared(&b).unwrap();
+        assert!(Rc::ptr_eq(&first, &second));
+
+        drop((first, second));
+        let third = load_shared(&a).unwrap();
+        assert_eq!(Rc::strong_count(&third), 1);
+
+        // An image with the same hash but other content isn't shared.
+        let other = r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="20"/>"#;
+        std::fs::write(&b, other).unwrap();
+
+        let mut hasher = DefaultHasher::new();
+        other.as_bytes().hash(&mut hasher);
+
+        POOL.with(|pool| {
+            pool.borrow_mut().insert(
+                hasher.finish(),
+                vec![Pooled {
+                    svg: true,
+                    data: svg.as_bytes().to_vec(),
+                    image: Rc::downgrade(&third),
+                }],
+            )
+        });
+
+        assert!(!Rc::ptr_eq(&load_shared(&b).unwrap(), &third));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_base_dir() {
+        let dir = std::env::temp_dir().join(format!("laser-pdf-base-dir-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("inside")).unwrap();
+        std::fs::write(dir.join("inside/a.txt"), "a").unwrap();
+        std::fs::write(dir.join("b.txt"), "b").unwrap();
+
+        let base_dir = BaseDir(dir.join("inside"));
+
+        base_dir.scope(|| {
+            assert_eq!(read_file("a.txt").unwrap(), b"a");
+            assert_eq!(read_file("./a.txt").unwrap(), b"a");
+            assert!(read_file("../b.txt").is_err());
+            assert!(read_file(dir.join("b.txt")).is_err());
+            assert!(read_file(dir.join("inside/a.txt")).is_err());
+        });
+
+        assert_eq!(read_file(dir.join("b.txt")).unwrap(), b"b");
+
+        let inside = dir.join("inside").canonicalize().unwrap();
+        assert_eq!(
+            base_dir.resolve_output(Path::new("c.pdf")).unwrap(),
+            inside.join("c.pdf")
+        );
+        assert_eq!(
+            base_dir.resolve_output(Path::new("./a.txt")).unwrap(),
+            inside.join("a.txt")
+        );
+        assert!(base_dir.resolve_output(Path::new("../c.pdf")).is_err());
+        assert!(base_dir.resolve_output(&dir.join("inside/c.pdf")).is_err());
+        assert!(base_dir.resolve_output(Path::new("missing/c.pdf")).is_err());
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(dir.join("b.txt"), dir.join("inside/b.pdf")).unwrap();
+            assert!(base_dir.resolve_output(Path::new("b.pdf")).is_err());
+        }
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_share_image_xobjects() {
+        use crate::{
+            build_pdf,
+            elements::{column::Column, image::ImageElement},
+        };
+
+        let image = Image::Pixel(printpdf::image::DynamicImage::new_rgb8(4, 4));
+        let element = ImageElement::new(&image);
+
+        let document = build_pdf(
+            "test",
+            (210., 297.),
+            |_| (),
+            |_: &()| {
+                Column::new(|content| {
+                    content.add(&element)?.add(&element)?;
+                    None
+                })
+            },
+        );
+
+        let mut bytes = Vec::new();
+        document
+            .save(&mut std::io::BufWriter::new(&mut bytes))
+            .unwrap();
+
+        let images = |document: &lopdf::Document| {
+            document
+                .objects
+                .values()
+                .filter_map(|o| o.as_stream().ok())
+                .filter(|s| {
+                    matches!(s.dict.get(b"Subtype"), Ok(lopdf::Object::Name(n)) if n == b"Image")
+                })
+                .count()
+        };
+
+        let mut document = lopdf::Document::load_mem(&bytes).unwrap();
+        let before = images(&document);
+
+        share_image_xobjects(&mut document);
+
+        assert_eq!(before, 2);
+        assert_eq!(images(&document), 1);
+    }
 }
@@ -0,0 +1,82 @@
+//! The natural language of the document and of the text in it (ISO 32000-1:2008 14.9.2), so that
+//! screen readers pick the right pronunciation.
+//!
+//! The default language of the document is in the catalog. printpdf doesn't support writing it, so
+//! it's added to the saved document afterwards, like [threads](crate::threads):
+//!
+//! ```ignore
+//! let mut document = lopdf::Document::load_mem(&save(build_pdf(..)))?;
+//! set_language(&mut document, "de-CH")?;
+//! ```
+//!
+//! Text in other languages is drawn in marked content with a `/Lang` property, which is how the
+//! specification sets the language without a structure tree. That's what
+//! [Lang](crate::elements::lang::Lang) does for its element and
+//! [RichText](crate::elements::rich_text::RichText) for spans with a language. A document rendered
+//! with `"lang":".."` gets the default, see [crate::render].
+
+use lopdf::{content::Operation, dictionary, Document, Object};
+use printpdf::PdfLayerReference;
+
+use crate::utils::add_op;
+
+/// Sets the default language of the document to a language tag like `en` or `de-CH`.
+pub fn set_language(document: &mut Document, lang: &str) -> lopdf::Result<()> {
+    let catalog_id = document.trailer.get(b"Root")?.as_reference()?;
+    let catalog = document.get_object_mut(catalog_id)?.as_dict_mut()?;
+
+    catalog.set("Lang", Object::string_literal(lang));
+
+    Ok(())
+}
+
+/// Starts marked content in the language, which has to be ended with [end_language] on the same
+/// layer.
+pub(crate) fn begin_language(layer: &PdfLayerReference, lang: &str) {
+    add_op(
+        layer,
+        Operation::new(
+            "BDC",
+            vec![
+                Object::Name(b"Span".to_vec()),
+                Object::Dictionary(dictionary! {
+                    "Lang" => Object::string_literal(lang),
+                }),
+            ],
+        ),
+    );
+}
+
+pub(crate) fn end_language(layer: &PdfLayerReference) {
+    add_op(layer, Operation::new("EMC", vec![]));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_language() {
+        let mut document = Document::with_version("1.5");
+
+        let catalog_id = document.add_object(dictionary! {
+            "Type" => "Catalog",
+        });
+
+        assert!(set_language(&mut document, "en").is_err());
+
+        document.trailer.set("Root", catalog_id);
+        set_language(&mut document, "de-CH").unwrap();
+
+        let lang = document
+            .get_object(catalog_id)
+            .and_then(Object::as_dict)
+            .unwrap()
+            .get(b"Lang")
+            .unwrap()
+            .as_str()
+            .unwrap();
+
+        assert_eq!(lang, b"de-CH");
+    }
+}
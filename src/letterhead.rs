@@ -0,0 +1,368 @@
+//! Letterheads are PDFs, usually designed elsewhere, whose first page is drawn under the content
+//! of every page. The first page of a document often gets a different one than the following
+//! pages, e.g. with the full address block.
+//!
+//! Like [threads](crate::threads), they're added to the saved document afterwards. The page of
+//! the letterhead is imported as a form XObject, together with the fonts and images it uses, and
+//! drawn before the content of the page.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream};
+
+/// The name of the form XObject in the resources of the pages.
+const XOBJECT_NAME: &str = "LaserPdfLetterhead";
+
+/// Draws the first page of `first` under the content of the first page and the first page of
+/// `following` under the content of all the other pages. The lower left corners of the media
+/// boxes are aligned and the letterheads aren't scaled, so they should have the page size of the
+/// document.
+pub fn add_letterheads(
+    document: &mut Document,
+    first: Option<&Document>,
+    following: Option<&Document>,
+) -> lopdf::Result<()> {
+    let first = first.map(|l| import_page(document, l)).transpose()?;
+    let following = following.map(|l| import_page(document, l)).transpose()?;
+
+    for (number, page_id) in document.get_pages() {
+        if let Some(ids) = if number == 1 { first } else { following } {
+            add_to_page(document, page_id, ids)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Loads a letterhead from a path, see [read_file](crate::image::read_file).
+pub fn load_letterhead(path: &str) -> Result<Document, String> {
+    Document::load_mem(&crate::image::read_file(path)?)
+        .map_err(|e| format!("could not load {path}: {e:?}"))
+}
+
+/// Copies the first page of the letterhead into the document as a form XObject. Returns its id
+/// and the id of a content stream that draws it.
+fn import_page(
+    document: &mut Document,
+    letterhead: &Document,
+) -> lopdf::Result<(ObjectId, ObjectId)> {
+    let page_id = *letterhead
+        .get_pages()
+        .get(&1)
+        .ok_or(lopdf::Error::ObjectNotFound)?;
+
+    let content = letterhead.get_page_content(page_id)?;
+
+    let resources = inherited(letterhead, page_id, b"Resources")
+        .cloned()
+        .unwrap_or_else(|| Dictionary::new().into());
+
+    let media_box = match inherited(letterhead, page_id, b"MediaBox") {
+        Some(Object::Array(media_box)) => media_box.iter().map(number).collect::<Vec<_>>(),
+        _ => Vec::new(),
+    };
+
+    // US Letter is the default in the PDF specification.
+    let media_box = match media_box[..] {
+        [Some(a), Some(b), Some(c), Some(d)] => [a, b, c, d],
+        _ => [0., 0., 612., 792.],
+    };
+
+    // Only the objects the page draws with are copied, with new ids.
+    let mut references = BTreeSet::new();
+    collect_references(letterhead, &resources, &mut references);
+
+    let ids = references
+        .into_iter()
+        .map(|id| (id, document.new_object_id()))
+        .collect::<BTreeMap<_, _>>();
+
+    for (&old, &new) in &ids {
+        let object = remap(letterhead.get_object(old)?, &ids);
+        document.objects.insert(new, object);
+    }
+
+    let real = |v: f64| Object::Real(v as _);
+
+    let form_id = document.add_object(Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Form",
+            "BBox" => media_box.into_iter().map(real).collect::<Vec<_>>(),
+            "Matrix" => [1., 0., 0., 1., -media_box[0], -media_box[1]]
+                .into_iter()
+                .map(real)
+                .collect::<Vec<_>>(),
+            "Resources" => remap(&resources, &ids),
+        },
+        content,
+    ));
+
+    let content_id = document.add_object(Stream::new(
+        Dictionary::new(),
+        format!("q /{XOBJECT_NAME} Do Q\n").into_bytes(),
+    ));
+
+    Ok((form_id, content_id))
+}
+
+fn add_to_page(
+    document: &mut Document,
+    page_id: ObjectId,
+    (form_id, content_id): (ObjectId, ObjectId),
+) -> lopdf::Result<()> {
+    // The resources might be shared with other pages, so the page gets its own copy.
+    let mut resources = match inherited(document, page_id, b"Resources") {
+        Some(Object::Dictionary(resources)) => resources.clone(),
+        _ => Dictionary::new(),
+    };
+
+    let mut xobjects = match resources.get(b"XObject").map(|o| resolve(document, o)) {
+        Ok(Some(Object::Dictionary(xobjects))) => xobjects.clone(),
+        _ => Dictionary::new(),
+    };
+
+    xobjects.set(XOBJECT_NAME, form_id);
+    resources.set("XObject", xobjects);
+
+    let page = document.get_object_mut(page_id)?.as_dict_mut()?;
+    page.set("Resources", resources);
+
+    let mut contents = vec![Object::Reference(content_id)];
+
+    match page.remove(b"Contents") {
+        Some(Object::Array(existing)) => contents.extend(existing),
+        Some(existing) => contents.push(existing),
+        None => {}
+    }
+
+    page.set("Contents", contents);
+
+    Ok(())
+}
+
+/// An attribute of the page, or of the closest page tree node that has it.
+pub(crate) fn inherited<'a>(
+    document: &'a Document,
+    page_id: ObjectId,
+    key: &[u8],
+) -> Option<&'a Object> {
+    let mut node = document.get_object(page_id).ok()?.as_dict().ok()?;
+
+    // The depth is limited in case the parents form a cycle.
+    for _ in 0..64 {
+        if let Ok(value) = node.get(key) {
+            return resolve(document, value);
+        }
+
+        let parent = node.get(b"Parent").ok()?.as_reference().ok()?;
+        node = document.get_object(parent).ok()?.as_dict().ok()?;
+    }
+
+    None
+}
+
+pub(crate) fn resolve<'a>(document: &'a Document, object: &'a Object) -> Option<&'a Object> {
+    match object {
+        Object::Reference(id) => document.get_object(*id).ok(),
+        object => Some(object),
+    }
+}
+
+fn number(object: &Object) -> Option<f64> {
+    match *object {
+        Object::Integer(v) => Some(v as f64),
+        Object::Real(v) => Some(v as f64),
+        _ => None,
+    }
+}
+
+fn collect_references(document: &Document, object: &Object, ids: &mut BTreeSet<ObjectId>) {
+    match object {
+        Object::Reference(id) => {
+            if ids.insert(*id) {
+                if let Ok(object) = document.get_object(*id) {
+                    collect_references(document, object, ids);
+                }
+            }
+        }
+        Object::Array(items) => {
+            for item in items {
+                collect_references(document, item, ids);
+            }
+        }
+        Object::Dictionary(dictionary) => {
+            for (_, value) in dictionary.iter() {
+                collect_references(document, value, ids);
+            }
+        }
+        Object::Stream(stream) => {
+            for (_, value) in stream.dict.iter() {
+                collect_references(document, value, ids);
+            }
+        }
+        _ => {}
+    }
+}
+
+pub(crate) fn remap(object: &Object, ids: &BTreeMap<ObjectId, ObjectId>) -> Object {
+    let remap_dictionary = |dictionary: &Dictionary| {
+        let mut remapped = Dictionary::new();
+
+        for (key, value) in dictionary.iter() {
+            remapped.set(key.clone(), remap(value, ids));
+        }
+
+        remapped
+    };
+
+    match object {
+        Object::Reference(id) => Object::Reference(*ids.get(id).unwrap_or(id)),
+        Object::Array(items) => Object::Array(items.iter().map(|i| remap(i, ids)).collect()),
+        Object::Dictionary(dictionary) => Object::Dictionary(remap_dictionary(dictionary)),
+        Object::Stream(stream) => {
+            let mut stream = stream.clone();
+            stream.dict = remap_dictionary(&stream.dict);
+            Object::Stream(stream)
+        }
+        object => object.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(contents: &[&str], font: &str) -> Document {
+        let mut document = Document::with_version("1.5");
+
+        let pages_id = document.new_object_id();
+
+        let font_id = document.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => font,
+        });
+
+        let resources_id = document.add_object(dictionary! {
+            "Font" => dictionary! { "F1" => font_id },
+        });
+
+        let page_ids = contents
+            .iter()
+            .map(|content| {
+                let content_id = document
+                    .add_object(Stream::new(Dictionary::new(), content.as_bytes().to_vec()));
+
+                document.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                    "Contents" => content_id,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        // The pages inherit the resources and the media box.
+        document.objects.insert(
+            pages_id,
+            dictionary! {
+                "Type" => "Pages",
+                "Kids" => page_ids.iter().map(|&id| id.into()).collect::<Vec<Object>>(),
+                "Count" => page_ids.len() as i64,
+                "MediaBox" => [0, 0, 595, 842].map(Object::Integer).to_vec(),
+                "Resources" => resources_id,
+            }
+            .into(),
+        );
+
+        let catalog_id = document.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        document.trailer.set("Root", catalog_id);
+
+        document
+    }
+
+    #[test]
+    fn test_add_letterheads() {
+        let mut document = document(&["(1) Tj", "(2) Tj", "(3) Tj"], "Courier");
+        let first = self::document(&["/F1 12 Tf (First) Tj"], "Helvetica");
+        let following = self::document(&["/F1 12 Tf (Following) Tj"], "Times-Roman");
+
+        add_letterheads(&mut document, Some(&first), Some(&following)).unwrap();
+
+        for (number, page_id) in document.get_pages() {
+            let page = document.get_object(page_id).unwrap().as_dict().unwrap();
+            let get = |dictionary: &Dictionary, key: &[u8]| {
+                let object = dictionary.get(key).unwrap();
+                resolve(&document, object).unwrap().clone()
+            };
+
+            let contents = page.get(b"Contents").unwrap().as_array().unwrap();
+            assert_eq!(contents.len(), 2);
+
+            let content = |i: usize| {
+                let stream = resolve(&document, &contents[i]).unwrap();
+                stream.as_stream().unwrap().content.clone()
+            };
+
+            assert_eq!(content(0), b"q /LaserPdfLetterhead Do Q\n");
+            assert_eq!(content(1), format!("({number}) Tj").into_bytes());
+
+            // The page keeps its own fonts.
+            let resources = get(page, b"Resources");
+            let resources = resources.as_dict().unwrap();
+            let font = get(get(resources, b"Font").as_dict().unwrap(), b"F1");
+            assert_eq!(
+                get(font.as_dict().unwrap(), b"BaseFont")
+                    .as_name_str()
+                    .unwrap(),
+                "Courier"
+            );
+
+            let xobjects = get(resources, b"XObject");
+            let form = get(xobjects.as_dict().unwrap(), XOBJECT_NAME.as_bytes());
+            let form = form.as_stream().unwrap();
+
+            let (content, font) = if number == 1 {
+                ("/F1 12 Tf (First) Tj", "Helvetica")
+            } else {
+                ("/F1 12 Tf (Following) Tj", "Times-Roman")
+            };
+
+            assert_eq!(form.content, content.as_bytes());
+
+            // The fonts of the letterhead are copied along.
+            let form_font = get(
+                get(get(&form.dict, b"Resources").as_dict().unwrap(), b"Font")
+                    .as_dict()
+                    .unwrap(),
+                b"F1",
+            );
+            assert_eq!(
+                get(form_font.as_dict().unwrap(), b"BaseFont")
+                    .as_name_str()
+                    .unwrap(),
+                font
+            );
+        }
+
+        // Only the first page gets a letterhead.
+        let mut document = self::document(&["(1) Tj", "(2) Tj"], "Courier");
+        add_letterheads(&mut document, Some(&first), None).unwrap();
+
+        let content_count = |page_id| {
+            let page = document.get_object(page_id).unwrap().as_dict().unwrap();
+
+            match page.get(b"Contents").unwrap() {
+                Object::Array(contents) => contents.len(),
+                _ => 1,
+            }
+        };
+
+        let pages = document.get_pages();
+        assert_eq!(content_count(pages[&1]), 2);
+        assert_eq!(content_count(pages[&2]), 1);
+    }
+}
@@ -1,10 +1,29 @@
+pub mod annotations;
+pub mod audit;
+pub mod budget;
+pub mod direction;
 pub mod elements;
+pub mod facts;
 pub mod flex;
 pub mod fonts;
+pub mod highlight;
 pub mod image;
+pub mod language;
+pub mod letterhead;
+pub mod line_break;
+pub mod line_rendering;
+pub mod markup;
+pub mod numbering;
+#[cfg(feature = "preview")]
+pub mod preview;
+pub mod render;
+pub mod script;
 pub mod serde_elements;
+pub mod spot_colors;
+pub mod structure;
 pub mod test_utils;
 pub mod text;
+pub mod threads;
 pub mod utils;
 
 use elements::padding::Padding;
@@ -39,6 +58,110 @@ pub enum VAlign {
 
 pub type Color = u32;
 
+/// How the height of a line of text is determined from the line height of the font, like the line
+/// spacing options of word processors.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum LineSpacing {
+    /// A multiple of the line height of the font.
+    Multiple(f64),
+
+    /// A fixed line height in mm, no matter the font. Glyphs can overlap with the next line if it's
+    /// smaller than the line height of the font.
+    Exact(#[serde(deserialize_with = "serde_elements::expr::deserialize_f64")] f64),
+
+    /// The line height of the font, but at least this many mm.
+    AtLeast(#[serde(deserialize_with = "serde_elements::expr::deserialize_f64")] f64),
+}
+
+impl Default for LineSpacing {
+    fn default() -> Self {
+        LineSpacing::Multiple(1.)
+    }
+}
+
+impl LineSpacing {
+    /// The height of a line given the line height of the font, both in mm.
+    pub fn line_height(self, font_line_height: f64) -> f64 {
+        match self {
+            LineSpacing::Multiple(factor) => font_line_height * factor,
+            LineSpacing::Exact(height) => height,
+            LineSpacing::AtLeast(height) => font_line_height.max(height),
+        }
+    }
+
+    /// Scales the leading, the part of the line height above the line height of the font, by the
+    /// factor, e.g. to tighten the text in a
+    /// [BalanceLastPage](elements::balance_last_page::BalanceLastPage). The leading of an
+    /// [Exact](LineSpacing::Exact) line height isn't known apart from the font, so it stays.
+    pub fn scale_leading(self, factor: f64) -> Self {
+        match self {
+            LineSpacing::Multiple(multiple) if multiple > 1. => {
+                LineSpacing::Multiple(1. + (multiple - 1.) * factor)
+            }
+            LineSpacing::AtLeast(height) => LineSpacing::AtLeast(height * factor),
+            _ => self,
+        }
+    }
+}
+
+/// ISO 32000-1:2008 8.6.6.4
+///
+/// A named ink, like "PANTONE 300 C", with the CMYK values that shall be used
+/// for the alternate color space when the ink isn't available. The components
+/// are in the range 0 to 1.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpotColor {
+    pub name: String,
+    pub cmyk: [f64; 4],
+}
+
+impl SpotColor {
+    /// The name of the Separation color space in the page resources. Characters that aren't
+    /// alphanumeric are replaced with `_` and the hex digits of their UTF-8 bytes, so different
+    /// inks get different names. It's only used to refer to the resource, the ink keeps its actual
+    /// name.
+    pub fn resource_name(&self) -> String {
+        let mut name = "LaserPdfSpot_".to_string();
+
+        for byte in self.name.bytes() {
+            if byte.is_ascii_alphanumeric() {
+                name.push(byte as char);
+            } else {
+                name.push_str(&format!("_{byte:02X}"));
+            }
+        }
+
+        name
+    }
+
+    /// Sets the fill color, which is also used for text, to this ink at the given tint, where 1
+    /// is the full ink. The color space has to be added to the saved document with
+    /// [add_spot_colors](spot_colors::add_spot_colors).
+    pub fn set_fill_color(&self, layer: &PdfLayerReference, tint: f64) {
+        for operation in self.operations(false, tint) {
+            utils::add_op(layer, operation);
+        }
+    }
+
+    /// Like [SpotColor::set_fill_color], but for strokes.
+    pub fn set_outline_color(&self, layer: &PdfLayerReference, tint: f64) {
+        for operation in self.operations(true, tint) {
+            utils::add_op(layer, operation);
+        }
+    }
+
+    fn operations(&self, stroke: bool, tint: f64) -> [lopdf::content::Operation; 2] {
+        use lopdf::{content::Operation, Object};
+
+        let (color_space, color) = if stroke { ("CS", "SCN") } else { ("cs", "scn") };
+
+        [
+            Operation::new(color_space, vec![Object::Name(self.resource_name().into())]),
+            Operation::new(color, vec![Object::Real(tint.clamp(0., 1.) as _)]),
+        ]
+    }
+}
+
 /// ISO 32000-1:2008 8.4.3.3
 ///
 /// The line cap style shall specify the shape that shall be used at the ends of
@@ -69,6 +192,40 @@ impl Into<printpdf::LineCapStyle> for LineCapStyle {
     }
 }
 
+/// ISO 32000-1:2008 8.4.3.4
+///
+/// The line join style shall specify the shape to be used at the corners of
+/// paths that are stroked.
+#[derive(Copy, Clone, Default, Serialize, Deserialize)]
+pub enum LineJoinStyle {
+    /// 0: Miter join. The outer edges of the strokes for the two segments shall
+    /// be extended until they meet at an angle. If the segments meet at too
+    /// sharp an angle (see [LineStyle::miter_limit]), a bevel join shall be
+    /// used instead.
+    #[default]
+    Miter,
+
+    /// 1: Round join. An arc of a circle with a diameter equal to the line
+    /// width shall be drawn around the point where the two segments meet.
+    Round,
+
+    /// 2: Bevel join. The two segments shall be finished with butt caps and the
+    /// resulting notch beyond the ends of the segments shall be filled with a
+    /// triangle.
+    Bevel,
+}
+
+impl Into<printpdf::LineJoinStyle> for LineJoinStyle {
+    fn into(self) -> printpdf::LineJoinStyle {
+        match self {
+            LineJoinStyle::Miter => printpdf::LineJoinStyle::Miter,
+            LineJoinStyle::Round => printpdf::LineJoinStyle::Round,
+            // printpdf calls the bevel join `Limit`.
+            LineJoinStyle::Bevel => printpdf::LineJoinStyle::Limit,
+        }
+    }
+}
+
 /// ISO 32000-1:2008 8.4.3.6
 ///
 /// The line dash pattern shall control the pattern of dashes and gaps used to
@@ -77,39 +234,146 @@ impl Into<printpdf::LineCapStyle> for LineCapStyle {
 pub struct LineDashPattern {
     /// The dash phase shall specify the distance into the dash pattern at which
     /// to start the dash.
-    pub offset: u16,
+    pub offset: f32,
 
     /// The dash array’s elements shall be numbers that specify the lengths of
     /// alternating dashes and gaps; the numbers shall be nonnegative and not
     /// all zero.
-    pub dashes: [u16; 2],
-}
-
-impl Into<printpdf::LineDashPattern> for LineDashPattern {
-    fn into(self) -> printpdf::LineDashPattern {
-        printpdf::LineDashPattern {
-            offset: self.offset as i64,
-            dash_1: Some(self.dashes[0] as i64),
-            gap_1: Some(self.dashes[1] as i64),
-            dash_2: None,
-            gap_2: None,
-            dash_3: None,
-            gap_3: None,
+    pub dashes: DashArray,
+}
+
+/// The dash array of a [LineDashPattern]. It's stored inline so that [LineStyle] stays `Copy`,
+/// which limits it to [DashArray::MAX_LEN] elements. An array with an odd number of elements
+/// repeats, like in PDF.
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Vec<f32>", into = "Vec<f32>")]
+pub struct DashArray {
+    values: [f32; DashArray::MAX_LEN],
+    len: u8,
+}
+
+impl DashArray {
+    pub const MAX_LEN: usize = 16;
+
+    pub fn new(values: &[f32]) -> Result<Self, String> {
+        if values.is_empty() || values.len() > Self::MAX_LEN {
+            return Err(format!(
+                "a dash array needs between one and {} elements, got {}",
+                Self::MAX_LEN,
+                values.len()
+            ));
         }
+
+        if values.iter().any(|&v| v < 0.) || values.iter().all(|&v| v == 0.) {
+            return Err("dash array elements need to be nonnegative and not all zero".to_string());
+        }
+
+        let mut array = DashArray {
+            values: [0.; Self::MAX_LEN],
+            len: values.len() as u8,
+        };
+
+        array.values[..values.len()].copy_from_slice(values);
+
+        Ok(array)
+    }
+
+    pub fn as_slice(&self) -> &[f32] {
+        &self.values[..self.len as usize]
+    }
+}
+
+impl TryFrom<Vec<f32>> for DashArray {
+    type Error = String;
+
+    fn try_from(values: Vec<f32>) -> Result<Self, String> {
+        DashArray::new(&values)
+    }
+}
+
+impl From<DashArray> for Vec<f32> {
+    fn from(array: DashArray) -> Self {
+        array.as_slice().to_vec()
     }
 }
 
+impl LineDashPattern {
+    /// The `d` operator that sets this pattern, or the solid line for `None`. The values are
+    /// written as they are, since printpdf's own pattern type only takes up to three integer
+    /// dash/gap pairs.
+    pub fn operation(pattern: Option<Self>) -> lopdf::content::Operation {
+        let (dashes, offset): (Vec<lopdf::Object>, f32) = match pattern {
+            Some(pattern) => (
+                pattern
+                    .dashes
+                    .as_slice()
+                    .iter()
+                    .map(|&v| lopdf::Object::Real(v as _))
+                    .collect(),
+                pattern.offset,
+            ),
+            None => (Vec::new(), 0.),
+        };
+
+        lopdf::content::Operation::new("d", vec![dashes.into(), lopdf::Object::Real(offset as _)])
+    }
+}
+
+/// The default miter limit of PDF viewers, which cuts off joins at angles below about 11.5°.
+pub const DEFAULT_MITER_LIMIT: f64 = 10.;
+
+const fn default_miter_limit() -> f64 {
+    DEFAULT_MITER_LIMIT
+}
+
 #[derive(Copy, Clone, Serialize, Deserialize)]
 pub struct LineStyle {
     pub thickness: f64,
+
+    #[serde(deserialize_with = "serde_elements::color::deserialize_color")]
     pub color: Color,
+
     pub dash_pattern: Option<LineDashPattern>,
     pub cap_style: LineCapStyle,
+
+    #[serde(default)]
+    pub join_style: LineJoinStyle,
+
+    /// ISO 32000-1:2008 8.4.3.5
+    ///
+    /// The maximum ratio of the miter length to the line width before a miter
+    /// join is converted to a bevel.
+    #[serde(default = "default_miter_limit")]
+    pub miter_limit: f64,
+
+    /// How the line is drawn. Without one, the one of the document is used, see
+    /// [line_rendering].
+    #[serde(default)]
+    pub rendering: Option<line_rendering::LineRendering>,
+}
+
+impl LineStyle {
+    /// A solid line with butt caps and miter joins.
+    pub fn solid(thickness: f64, color: Color) -> Self {
+        LineStyle {
+            thickness,
+            color,
+            dash_pattern: None,
+            cap_style: LineCapStyle::Butt,
+            join_style: LineJoinStyle::Miter,
+            miter_limit: DEFAULT_MITER_LIMIT,
+            rendering: None,
+        }
+    }
 }
 
 pub struct Pdf {
     pub document: PdfDocumentReference,
     pub page_size: (f64, f64),
+
+    /// How the line styles without their own rendering are drawn, see
+    /// [WithLineRendering](line_rendering::WithLineRendering).
+    pub line_rendering: line_rendering::LineRendering,
 }
 
 /// A position for an element to render at.
@@ -148,11 +412,12 @@ pub struct WidthConstraint {
 }
 
 impl WidthConstraint {
+    /// Never returns a negative width, even if the maximum is negative.
     pub fn constrain(&self, width: f64) -> f64 {
         if self.expand {
-            self.max
+            self.max.max(0.)
         } else {
-            width.min(self.max)
+            width.min(self.max).max(0.)
         }
     }
 }
@@ -333,6 +598,8 @@ pub trait Element {
             color,
             show_max_width: false,
             show_last_location_max_height: false,
+            show_rulers: false,
+            show_baselines: false,
         }
     }
 }
@@ -433,6 +700,266 @@ pub fn build_pdf<F: 'static>(
     build_fonts: impl FnOnce(&PdfDocumentReference) -> F,
     build_element: impl for<'a> BuildElement<'a, F>,
 ) -> printpdf::PdfDocumentReference {
+    build(
+        name,
+        page_size,
+        build_fonts,
+        build_element,
+        Limits::default(),
+        &mut |_| {},
+    )
+    .0
+}
+
+/// Like [build_pdf], but calls `on_page` with the number of pages every time a page is added,
+/// including the first one.
+pub fn build_pdf_with_progress<F: 'static>(
+    name: &str,
+    page_size: (f64, f64),
+    build_fonts: impl FnOnce(&PdfDocumentReference) -> F,
+    build_element: impl for<'a> BuildElement<'a, F>,
+    mut on_page: impl FnMut(u32),
+) -> printpdf::PdfDocumentReference {
+    build(
+        name,
+        page_size,
+        build_fonts,
+        build_element,
+        Limits::default(),
+        &mut on_page,
+    )
+    .0
+}
+
+/// Limits for documents built from untrusted input. The content and image budgets are counted
+/// while the document is drawn, see [budget].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Limits {
+    /// At least one page is always added, so `Some(0)` is always exceeded.
+    pub max_pages: Option<u32>,
+
+    /// The estimated size of the content streams of all pages, in bytes.
+    pub max_content_bytes: Option<u64>,
+
+    /// The memory of the decoded images, in bytes.
+    pub max_image_memory: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub pages: u32,
+
+    /// The estimated size of the content streams of all pages, see [budget].
+    pub content_bytes: u64,
+
+    /// The memory of the decoded images, counted every time an image is drawn.
+    pub image_memory: u64,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LimitError {
+    TooManyPages { max_pages: u32 },
+    ContentTooLarge { max_content_bytes: u64 },
+    TooMuchImageMemory { max_image_memory: u64 },
+}
+
+impl LimitError {
+    /// The first limit that's exceeded, if any.
+    fn exceeded(limits: Limits, stats: &Stats, too_many_pages: bool) -> Option<LimitError> {
+        if let (Some(max_pages), true) = (limits.max_pages, too_many_pages) {
+            return Some(LimitError::TooManyPages { max_pages });
+        }
+
+        if let Some(max_content_bytes) = limits
+            .max_content_bytes
+            .filter(|&max| stats.content_bytes > max)
+        {
+            return Some(LimitError::ContentTooLarge { max_content_bytes });
+        }
+
+        limits
+            .max_image_memory
+            .filter(|&max| stats.image_memory > max)
+            .map(|max_image_memory| LimitError::TooMuchImageMemory { max_image_memory })
+    }
+}
+
+impl std::fmt::Display for LimitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LimitError::TooManyPages { max_pages } => {
+                write!(f, "the document has more than {max_pages} pages")
+            }
+            LimitError::ContentTooLarge { max_content_bytes } => {
+                write!(
+                    f,
+                    "the content of the document is larger than {max_content_bytes} bytes"
+                )
+            }
+            LimitError::TooMuchImageMemory { max_image_memory } => {
+                write!(
+                    f,
+                    "the images of the document take up more than {max_image_memory} bytes"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for LimitError {}
+
+/// Like [build_pdf], but fails when the document exceeds the limits and also returns some
+/// statistics about the document. When a limit is exceeded, the rest of the layout still runs, but
+/// no more pages, content or images of the exceeded kind are added.
+pub fn build_pdf_with_limits<F: 'static>(
+    name: &str,
+    page_size: (f64, f64),
+    build_fonts: impl FnOnce(&PdfDocumentReference) -> F,
+    build_element: impl for<'a> BuildElement<'a, F>,
+    limits: Limits,
+) -> Result<(printpdf::PdfDocumentReference, Stats), LimitError> {
+    let (document, stats, exceeded) = build(
+        name,
+        page_size,
+        build_fonts,
+        build_element,
+        limits,
+        &mut |_| {},
+    );
+
+    match exceeded {
+        Some(error) => Err(error),
+        None => Ok((document, stats)),
+    }
+}
+
+/// Statistics about a saved document, see [finish_with_stats].
+#[derive(Clone, Debug, PartialEq)]
+pub struct SavedStats {
+    pub pages: u32,
+
+    /// The size of the saved document.
+    pub bytes: usize,
+
+    /// The number of indirect objects, like fonts, images and page content streams.
+    pub objects: u32,
+
+    /// The size of the embedded font file of every font, by the name of the font, which is the
+    /// subset if the font was subset.
+    pub font_bytes: Vec<(String, usize)>,
+
+    /// The size of the embedded images.
+    pub image_bytes: usize,
+
+    /// The estimated size of the content streams, see [Stats].
+    pub content_bytes: u64,
+
+    /// The memory of the decoded images, see [Stats].
+    pub image_memory: u64,
+}
+
+/// Saves a document returned by [build_pdf_with_limits] and adds the size, object count and the
+/// sizes of the embedded fonts and images of the saved file to its statistics.
+pub fn finish_with_stats(
+    document: PdfDocumentReference,
+    stats: Stats,
+) -> Result<(Vec<u8>, SavedStats), printpdf::Error> {
+    let mut bytes = Vec::new();
+    document.save(&mut std::io::BufWriter::new(&mut bytes))?;
+
+    let (font_bytes, image_bytes) = embedded_sizes(&bytes);
+
+    let saved = SavedStats {
+        pages: stats.pages,
+        bytes: bytes.len(),
+        objects: object_count(&bytes),
+        font_bytes,
+        image_bytes,
+        content_bytes: stats.content_bytes,
+        image_memory: stats.image_memory,
+    };
+
+    Ok((bytes, saved))
+}
+
+/// The sizes of the font files by font name and the total size of the images in a saved document.
+/// A document that can't be parsed has neither.
+fn embedded_sizes(pdf: &[u8]) -> (Vec<(String, usize)>, usize) {
+    use lopdf::Object;
+
+    fn has_name(dict: &lopdf::Dictionary, key: &[u8], name: &[u8]) -> bool {
+        matches!(dict.get(key), Ok(Object::Name(n)) if n == name)
+    }
+
+    let Ok(document) = lopdf::Document::load_mem(pdf) else {
+        return (Vec::new(), 0);
+    };
+
+    let stream_size = |object: &Object| match object {
+        Object::Reference(id) => match document.get_object(*id) {
+            Ok(Object::Stream(stream)) => stream.content.len(),
+            _ => 0,
+        },
+        _ => 0,
+    };
+
+    let mut fonts = Vec::new();
+    let mut images = 0;
+
+    for object in document.objects.values() {
+        match object {
+            Object::Dictionary(dict) if has_name(dict, b"Type", b"FontDescriptor") => {
+                let name = match dict.get(b"FontName") {
+                    Ok(Object::Name(name)) => String::from_utf8_lossy(name).into_owned(),
+                    _ => String::new(),
+                };
+
+                let size: usize = [&b"FontFile"[..], b"FontFile2", b"FontFile3"]
+                    .into_iter()
+                    .filter_map(|key| dict.get(key).ok())
+                    .map(stream_size)
+                    .sum();
+
+                fonts.push((name, size));
+            }
+            Object::Stream(stream) if has_name(&stream.dict, b"Subtype", b"Image") => {
+                images += stream.content.len();
+            }
+            _ => {}
+        }
+    }
+
+    fonts.sort();
+
+    (fonts, images)
+}
+
+/// The number of objects in a saved document, from the `/Size` of its trailer, which counts the
+/// entries of the cross-reference table including the free object 0.
+fn object_count(pdf: &[u8]) -> u32 {
+    const SIZE: &[u8] = b"/Size";
+
+    let Some(start) = pdf.windows(SIZE.len()).rposition(|w| w == SIZE) else {
+        return 0;
+    };
+
+    let size = pdf[start + SIZE.len()..]
+        .iter()
+        .skip_while(|c| c.is_ascii_whitespace())
+        .take_while(|c| c.is_ascii_digit())
+        .fold(0u32, |size, &c| size * 10 + (c - b'0') as u32);
+
+    size.saturating_sub(1)
+}
+
+pub(crate) fn build<F: 'static>(
+    name: &str,
+    page_size: (f64, f64),
+    build_fonts: impl FnOnce(&PdfDocumentReference) -> F,
+    build_element: impl for<'a> BuildElement<'a, F>,
+    limits: Limits,
+    on_page: &mut dyn FnMut(u32),
+) -> (printpdf::PdfDocumentReference, Stats, Option<LimitError>) {
     use printpdf::{
         indices::{PdfLayerIndex, PdfPageIndex},
         PdfDocument,
@@ -441,21 +968,39 @@ pub fn build_pdf<F: 'static>(
     let (doc, page, layer) = PdfDocument::new(name, Mm(page_size.0), Mm(page_size.1), "Layer 0");
     let mut page_idx = 0;
 
+    let max_pages = limits.max_pages;
+
+    // There's always a first page, so a limit of zero pages is exceeded from the start.
+    let mut exceeded = max_pages == Some(0);
+
+    on_page(1);
+
     let mut pdf = Pdf {
         document: doc,
         page_size,
+        line_rendering: Default::default(),
     };
 
-    let do_break = &mut |pdf: &mut Pdf, location_idx, size| {
-        while page_idx <= location_idx {
+    let do_break = &mut |pdf: &mut Pdf, location_idx: u32, size| {
+        // Past the limit everything is drawn on the last page. The document is discarded anyway.
+        let new_page_idx = match max_pages {
+            Some(max_pages) if location_idx + 1 >= max_pages => {
+                exceeded = true;
+                max_pages.max(1) - 1
+            }
+            _ => location_idx + 1,
+        };
+
+        while page_idx < new_page_idx {
             pdf.document
                 .add_page(Mm(page_size.0), Mm(page_size.1), "Layer 0");
             page_idx += 1;
+            on_page(page_idx + 1);
         }
 
         let layer = pdf
             .document
-            .get_page(PdfPageIndex((location_idx + 1) as usize))
+            .get_page(PdfPageIndex(new_page_idx as usize))
             .get_layer(PdfLayerIndex(0));
 
         Location {
@@ -493,7 +1038,222 @@ pub fn build_pdf<F: 'static>(
         }),
     };
 
-    element.draw(ctx);
+    let (_, usage) = budget::with_budget(limits.max_content_bytes, limits.max_image_memory, || {
+        element.draw(ctx)
+    });
+
+    let stats = Stats {
+        pages: page_idx + 1,
+        content_bytes: usage.content_bytes,
+        image_memory: usage.image_memory,
+    };
+
+    facts::set_pages(stats.pages);
+
+    let exceeded = LimitError::exceeded(limits, &stats, exceeded);
 
-    pdf.document
+    (pdf.document, stats, exceeded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::FakeText;
+
+    #[test]
+    fn test_limits() {
+        let build = |max_pages| {
+            build_pdf_with_limits(
+                "test",
+                (100., 100.),
+                |_| (),
+                |_: &()| FakeText {
+                    lines: 30,
+                    line_height: 10.,
+                    width: 50.,
+                },
+                Limits {
+                    max_pages,
+                    ..Default::default()
+                },
+            )
+            .map(|(_, stats)| stats.pages)
+        };
+
+        assert_eq!(build(None), Ok(3));
+        assert_eq!(build(Some(3)), Ok(3));
+        assert_eq!(
+            build(Some(2)),
+            Err(LimitError::TooManyPages { max_pages: 2 })
+        );
+        assert_eq!(
+            build(Some(0)),
+            Err(LimitError::TooManyPages { max_pages: 0 })
+        );
+    }
+
+    #[test]
+    fn test_content_budget() {
+        use elements::rectangle::Rectangle;
+
+        let build = |max_content_bytes| {
+            build_pdf_with_limits(
+                "test",
+                (100., 100.),
+                |_| (),
+                |_: &()| Rectangle {
+                    size: (10., 10.),
+                    fill: Some(0x00_00_00_FF),
+                    outline: None,
+                },
+                Limits {
+                    max_content_bytes,
+                    ..Default::default()
+                },
+            )
+            .map(|(_, stats)| stats)
+        };
+
+        let stats = build(None).unwrap();
+        assert!(stats.content_bytes > 0);
+        assert_eq!(stats.image_memory, 0);
+
+        assert_eq!(build(Some(stats.content_bytes)), Ok(stats));
+        assert_eq!(
+            build(Some(stats.content_bytes - 1)),
+            Err(LimitError::ContentTooLarge {
+                max_content_bytes: stats.content_bytes - 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_scale_leading() {
+        assert_eq!(
+            LineSpacing::Multiple(1.5).scale_leading(0.5),
+            LineSpacing::Multiple(1.25)
+        );
+        assert_eq!(
+            LineSpacing::Multiple(0.9).scale_leading(0.5),
+            LineSpacing::Multiple(0.9)
+        );
+        assert_eq!(
+            LineSpacing::AtLeast(6.).scale_leading(0.5),
+            LineSpacing::AtLeast(3.)
+        );
+        assert_eq!(
+            LineSpacing::Exact(6.).scale_leading(0.5),
+            LineSpacing::Exact(6.)
+        );
+    }
+
+    #[test]
+    fn test_dash_pattern_operation() {
+        use lopdf::Object;
+
+        let operation = LineDashPattern::operation(Some(LineDashPattern {
+            offset: 0.2,
+            dashes: DashArray::new(&[0.4, 0.4, 1.5]).unwrap(),
+        }));
+
+        assert_eq!(operation.operator, "d");
+
+        let values: Vec<f64> = operation.operands[0]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|d| match *d {
+                Object::Real(v) => v as _,
+                _ => unreachable!(),
+            })
+            .collect();
+
+        assert_eq!(values, [0.4f32 as f64, 0.4f32 as f64, 1.5]);
+        assert!(matches!(operation.operands[1], Object::Real(v) if v == 0.2f32 as _));
+
+        let solid = LineDashPattern::operation(None);
+        assert!(solid.operands[0].as_array().unwrap().is_empty());
+
+        assert!(DashArray::new(&[]).is_err());
+        assert!(DashArray::new(&[1.; DashArray::MAX_LEN + 1]).is_err());
+        assert!(DashArray::new(&[0., 0.]).is_err());
+    }
+
+    #[test]
+    fn test_finish_with_stats() {
+        let (document, stats) = build_pdf_with_limits(
+            "test",
+            (100., 100.),
+            |_| (),
+            |_: &()| FakeText {
+                lines: 30,
+                line_height: 10.,
+                width: 50.,
+            },
+            Limits::default(),
+        )
+        .unwrap();
+
+        let (bytes, saved) = finish_with_stats(document, stats).unwrap();
+
+        assert_eq!(saved.pages, 3);
+        assert_eq!(saved.bytes, bytes.len());
+        assert!(saved.font_bytes.is_empty());
+        assert_eq!(saved.image_bytes, 0);
+
+        // At least the catalog, the page tree and a page with its contents for every page.
+        assert!(saved.objects >= 2 + 2 * 3);
+
+        assert_eq!(object_count(b"trailer\n<</Root 1 0 R/Size 12>>\n"), 11);
+        assert_eq!(object_count(b"%PDF-1.3"), 0);
+    }
+
+    #[test]
+    fn test_embedded_sizes() {
+        use lopdf::{dictionary, Document, Object, Stream};
+
+        let mut document = Document::with_version("1.5");
+
+        let font_file = document.add_object(Stream::new(dictionary! {}, vec![0; 100]));
+        document.add_object(dictionary! {
+            "Type" => "FontDescriptor",
+            "FontName" => "ABCDEF+Font",
+            "FontFile2" => font_file,
+        });
+        document.add_object(Stream::new(
+            dictionary! { "Type" => "XObject", "Subtype" => "Image" },
+            vec![0; 30],
+        ));
+
+        let catalog = document.add_object(dictionary! { "Type" => "Catalog" });
+        document.trailer.set("Root", Object::Reference(catalog));
+
+        let mut bytes = Vec::new();
+        document.save_to(&mut bytes).unwrap();
+
+        assert_eq!(
+            embedded_sizes(&bytes),
+            (vec![("ABCDEF+Font".to_string(), 100)], 30)
+        );
+        assert_eq!(embedded_sizes(b"%PDF-1.3"), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn test_progress() {
+        let mut pages = Vec::new();
+
+        build_pdf_with_progress(
+            "test",
+            (100., 100.),
+            |_| (),
+            |_: &()| FakeText {
+                lines: 30,
+                line_height: 10.,
+                width: 50.,
+            },
+            |p| pages.push(p),
+        );
+
+        assert_eq!(pages, [1, 2, 3]);
+    }
 }
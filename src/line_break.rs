@@ -0,0 +1,574 @@
+//! Line break opportunities following the Unicode line breaking algorithm (UAX #14). The classes
+//! and rules cover Latin, Cyrillic, Greek, CJK and the common punctuation. Classes that depend on
+//! the East Asian width or need a dictionary are simplified: ambiguous characters are treated as
+//! alphabetic and Thai as alphabetic too, see [crate::script::insert_word_breaks] for that. Hangul
+//! syllables break like ideographs and `B2`, `CB`, `HL` and emoji modifier rules are left out.
+
+use serde::{Deserialize, Serialize};
+
+/// Where lines of text can be broken.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineBreaking {
+    /// At whitespace, after hyphens and at soft hyphens.
+    #[default]
+    Simple,
+
+    /// At the opportunities of the Unicode line breaking algorithm, so for example not before
+    /// closing punctuation and between any two ideographs.
+    Unicode,
+
+    /// Like [LineBreaking::Unicode], but also following the Japanese rules for the characters that
+    /// can't start or end a line (kinsoku shori), see [kinsoku_break_opportunities].
+    Kinsoku,
+}
+
+/// A position a line can start at.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct BreakOpportunity {
+    /// In bytes.
+    pub offset: usize,
+
+    /// Whether the line has to be broken here, like after a newline.
+    pub mandatory: bool,
+}
+
+/// Line breaking classes, see UAX #14 section 5.1.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Class {
+    /// Mandatory break.
+    BK,
+    CR,
+    LF,
+    /// Next line.
+    NL,
+    /// Space.
+    SP,
+    /// Zero width space.
+    ZW,
+    /// Word joiner.
+    WJ,
+    /// Non-breaking ("glue").
+    GL,
+    /// Zero width joiner.
+    ZWJ,
+    /// Combining mark.
+    CM,
+    /// Opening punctuation.
+    OP,
+    /// Closing punctuation.
+    CL,
+    /// Closing parenthesis.
+    CP,
+    /// Quotation.
+    QU,
+    /// Exclamation or interrogation.
+    EX,
+    /// Infix numeric separator.
+    IS,
+    /// Symbols allowing a break after.
+    SY,
+    /// Nonstarter.
+    NS,
+    Hyphen,
+    /// Break after.
+    BA,
+    /// Break before.
+    BB,
+    /// Inseparable.
+    IN,
+    /// Ideographic.
+    ID,
+    /// Numeric.
+    NU,
+    /// Prefix numeric.
+    PR,
+    /// Postfix numeric.
+    PO,
+    /// Alphabetic.
+    AL,
+}
+
+use Class::*;
+
+fn class(c: char) -> Class {
+    match c {
+        '\n' => LF,
+        '\r' => CR,
+        '\u{0b}' | '\u{0c}' | '\u{2028}' | '\u{2029}' => BK,
+        '\u{85}' => NL,
+        ' ' => SP,
+        '\u{200b}' => ZW,
+        '\u{2060}' | '\u{feff}' => WJ,
+        '\u{a0}' | '\u{202f}' | '\u{2007}' | '\u{2011}' | '\u{034f}' => GL,
+        '\u{200d}' => ZWJ,
+        '\t'
+        | '|'
+        | '\u{ad}'
+        | '\u{2010}'
+        | '\u{2012}'
+        | '\u{2013}'
+        | '\u{2014}'
+        | '\u{1680}'
+        | '\u{2000}'..='\u{2006}'
+        | '\u{2008}'..='\u{200a}'
+        | '\u{3000}' => BA,
+        '\u{b4}' | '\u{2c8}' | '\u{2cc}' => BB,
+        '-' => Hyphen,
+        '(' | '[' | '{' | '\u{a1}' | '\u{bf}' | '\u{201a}' | '\u{201e}' | '\u{2045}'
+        | '\u{207d}' | '\u{208d}' | '\u{ff08}' | '\u{ff3b}' | '\u{ff5b}' => OP,
+        '\u{3008}' | '\u{300a}' | '\u{300c}' | '\u{300e}' | '\u{3010}' | '\u{3014}'
+        | '\u{3016}' | '\u{3018}' | '\u{301a}' | '\u{301d}' => OP,
+        ')' | ']' => CP,
+        '}' | '\u{2046}' | '\u{207e}' | '\u{208e}' | '\u{3001}' | '\u{3002}' | '\u{ff09}'
+        | '\u{ff0c}' | '\u{ff0e}' | '\u{ff3d}' | '\u{ff5d}' | '\u{ff61}' | '\u{ff64}' => CL,
+        '\u{3009}' | '\u{300b}' | '\u{300d}' | '\u{300f}' | '\u{3011}' | '\u{3015}'
+        | '\u{3017}' | '\u{3019}' | '\u{301b}' | '\u{301e}' | '\u{301f}' => CL,
+        '"'
+        | '\''
+        | '\u{ab}'
+        | '\u{bb}'
+        | '\u{2018}'
+        | '\u{2019}'
+        | '\u{201b}'..='\u{201d}'
+        | '\u{201f}'
+        | '\u{2039}'
+        | '\u{203a}' => QU,
+        '!' | '?' | '\u{ff01}' | '\u{ff1f}' => EX,
+        ',' | '.' | ':' | ';' | '\u{37e}' | '\u{589}' | '\u{60c}' | '\u{2044}' => IS,
+        '/' => SY,
+        '\u{2024}'..='\u{2026}' => IN,
+        '$'
+        | '+'
+        | '\\'
+        | '\u{a3}'
+        | '\u{a5}'
+        | '\u{b1}'
+        | '\u{2116}'
+        | '\u{20a0}'..='\u{20cf}' => PR,
+        '%' | '\u{a2}' | '\u{b0}' | '\u{2030}'..='\u{2037}' | '\u{2103}' | '\u{2109}' => PO,
+        '0'..='9' | '\u{660}'..='\u{669}' | '\u{6f0}'..='\u{6f9}' | '\u{966}'..='\u{96f}' => NU,
+
+        // Small kana, iteration marks and the prolonged sound mark can't start a line.
+        '\u{3005}'
+        | '\u{301c}'
+        | '\u{303b}'
+        | '\u{309b}'..='\u{309e}'
+        | '\u{30a0}'
+        | '\u{30fb}'..='\u{30fe}'
+        | '\u{ff1a}'
+        | '\u{ff1b}'
+        | '\u{ff65}' => NS,
+        '\u{3041}' | '\u{3043}' | '\u{3045}' | '\u{3047}' | '\u{3049}' | '\u{3063}'
+        | '\u{3083}' | '\u{3085}' | '\u{3087}' | '\u{308e}' | '\u{3095}' | '\u{3096}' => NS,
+        '\u{30a1}'
+        | '\u{30a3}'
+        | '\u{30a5}'
+        | '\u{30a7}'
+        | '\u{30a9}'
+        | '\u{30c3}'
+        | '\u{30e3}'
+        | '\u{30e5}'
+        | '\u{30e7}'
+        | '\u{30ee}'
+        | '\u{30f5}'
+        | '\u{30f6}'
+        | '\u{31f0}'..='\u{31ff}' => NS,
+
+        '\u{2e80}'..='\u{2fff}'
+        | '\u{3003}'..='\u{3004}'
+        | '\u{3006}'..='\u{3007}'
+        | '\u{3012}'..='\u{3013}'
+        | '\u{3020}'..='\u{3029}'
+        | '\u{3030}'..='\u{303a}'
+        | '\u{303c}'..='\u{303f}'
+        | '\u{3040}'..='\u{309f}'
+        | '\u{30a0}'..='\u{30ff}'
+        | '\u{3100}'..='\u{31ef}'
+        | '\u{3200}'..='\u{4dbf}'
+        | '\u{4e00}'..='\u{9fff}'
+        | '\u{a000}'..='\u{a4cf}'
+        | '\u{ac00}'..='\u{d7a3}'
+        | '\u{f900}'..='\u{faff}'
+        | '\u{fe30}'..='\u{fe4f}'
+        | '\u{ff00}'..='\u{ff60}'
+        | '\u{ffe0}'..='\u{ffe6}'
+        | '\u{1f000}'..='\u{1faff}'
+        | '\u{20000}'..='\u{3fffd}' => ID,
+
+        '\u{300}'..='\u{36f}'
+        | '\u{483}'..='\u{489}'
+        | '\u{591}'..='\u{5bd}'
+        | '\u{610}'..='\u{61a}'
+        | '\u{64b}'..='\u{65f}'
+        | '\u{670}'
+        | '\u{6d6}'..='\u{6dc}'
+        | '\u{900}'..='\u{903}'
+        | '\u{93a}'..='\u{93c}'
+        | '\u{93e}'..='\u{94f}'
+        | '\u{e31}'
+        | '\u{e34}'..='\u{e3a}'
+        | '\u{e47}'..='\u{e4e}'
+        | '\u{1ab0}'..='\u{1aff}'
+        | '\u{1dc0}'..='\u{1dff}'
+        | '\u{20d0}'..='\u{20ff}'
+        | '\u{fe00}'..='\u{fe0f}'
+        | '\u{fe20}'..='\u{fe2f}'
+        | '\u{e0100}'..='\u{e01ef}' => CM,
+
+        _ => AL,
+    }
+}
+
+/// Whether a line can be broken between the classes, with `spaces` between them. Mandatory breaks,
+/// spaces and combining marks are handled before this.
+fn can_break(before: Class, spaces: bool, after: Class) -> bool {
+    // LB8: after a zero width space, even if it's followed by spaces.
+    if before == ZW {
+        return true;
+    }
+
+    // LB11, LB12, LB12a
+    if !spaces
+        && (before == WJ
+            || after == WJ
+            || before == GL
+            || (after == GL && !matches!(before, BA | Hyphen)))
+    {
+        return false;
+    }
+
+    // LB13
+    if matches!(after, CL | CP | EX | IS | SY) {
+        return false;
+    }
+
+    // LB14, LB15, LB16
+    if before == OP || (before == QU && after == OP) || (matches!(before, CL | CP) && after == NS) {
+        return false;
+    }
+
+    // LB18
+    if spaces {
+        return true;
+    }
+
+    !matches!(
+        (before, after),
+        // LB19
+        (QU, _) | (_, QU)
+        // LB21
+        | (_, BA | Hyphen | NS) | (BB, _)
+        // LB22
+        | (_, IN)
+        // LB23, LB23a, LB24
+        | (AL, NU) | (NU, AL)
+        | (PR, ID) | (ID, PO)
+        | (PR | PO, AL) | (AL, PR | PO)
+        // LB25
+        | (CL | CP | NU, PO | PR)
+        | (PO | PR, OP | NU)
+        | (Hyphen | IS | NU | SY, NU)
+        // LB28, LB29, LB30
+        | (AL, AL) | (IS, AL)
+        | (AL | NU, OP) | (CP, AL | NU)
+    )
+}
+
+/// All positions in the text where a line can start, in order. The start of the text isn't one,
+/// while the end is only one if the text ends with a mandatory break.
+pub fn break_opportunities(text: &str) -> Vec<BreakOpportunity> {
+    let mut opportunities = Vec::new();
+    break_opportunities_into(text, &mut opportunities);
+    opportunities
+}
+
+/// [break_opportunities] into a buffer that's reused, which is cleared first.
+pub fn break_opportunities_into(text: &str, opportunities: &mut Vec<BreakOpportunity>) {
+    opportunities.clear();
+
+    // The class of the last character that isn't a space or combining mark.
+    let mut before: Option<Class> = None;
+    let mut spaces = false;
+    let mut after_zwj = false;
+
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let mut current = class(c);
+        let end = i + c.len_utf8();
+
+        let zwj = after_zwj;
+        after_zwj = current == ZWJ;
+
+        // LB4, LB5
+        if matches!(current, BK | LF | NL)
+            || (current == CR && chars.peek().map(|p| p.1) != Some('\n'))
+        {
+            opportunities.push(BreakOpportunity {
+                offset: end,
+                mandatory: true,
+            });
+
+            before = None;
+            spaces = false;
+            continue;
+        }
+
+        // LB6, LB7
+        if current == CR || current == SP {
+            spaces |= current == SP && before.is_some();
+            continue;
+        }
+
+        // LB7
+        if current == ZW {
+            before = Some(ZW);
+            spaces = false;
+            continue;
+        }
+
+        // LB9, LB10: combining marks belong to the character before them.
+        if matches!(current, CM | ZWJ) {
+            if before.is_some() && before != Some(ZW) && !spaces {
+                continue;
+            }
+
+            current = AL;
+        }
+
+        if let Some(before) = before {
+            // LB8a
+            if !zwj && can_break(before, spaces, current) {
+                opportunities.push(BreakOpportunity {
+                    offset: i,
+                    mandatory: false,
+                });
+            }
+        }
+
+        before = Some(current);
+        spaces = false;
+    }
+}
+
+/// Whether kinsoku shori doesn't allow the character at the start of a line: closing brackets,
+/// full stops, commas, middle dots, question and exclamation marks, hyphens and dashes, iteration
+/// marks, the prolonged sound mark, small kana and the inseparable dots.
+fn no_line_start(c: char) -> bool {
+    matches!(
+        c,
+        ')' | ']'
+            | '}'
+            | '\u{bb}'
+            | '\u{2019}'
+            | '\u{201d}'
+            | '\u{3009}'
+            | '\u{300b}'
+            | '\u{300d}'
+            | '\u{300f}'
+            | '\u{3011}'
+            | '\u{3015}'
+            | '\u{3017}'
+            | '\u{3019}'
+            | '\u{301f}'
+            | '\u{ff09}'
+            | '\u{ff3d}'
+            | '\u{ff5d}'
+            | '\u{ff60}'
+            | '.'
+            | ','
+            | ':'
+            | ';'
+            | '!'
+            | '?'
+            | '\u{3001}'
+            | '\u{3002}'
+            | '\u{30fb}'
+            | '\u{ff01}'
+            | '\u{ff0c}'
+            | '\u{ff0e}'
+            | '\u{ff1a}'
+            | '\u{ff1b}'
+            | '\u{ff1f}'
+            | '\u{203c}'
+            | '\u{2047}'..='\u{2049}'
+            | '\u{2010}'
+            | '\u{2013}'
+            | '\u{301c}'
+            | '\u{30a0}'
+            | '\u{ff5e}'
+            | '\u{3005}'
+            | '\u{303b}'
+            | '\u{309d}'
+            | '\u{309e}'
+            | '\u{30fc}'..='\u{30fe}'
+            | '\u{2025}'
+            | '\u{2026}'
+    ) || class(c) == NS
+}
+
+/// Whether kinsoku shori doesn't allow the character at the end of a line: opening brackets and
+/// currency signs that go before numbers.
+fn no_line_end(c: char) -> bool {
+    matches!(
+        c,
+        '(' | '['
+            | '{'
+            | '\u{ab}'
+            | '\u{2018}'
+            | '\u{201c}'
+            | '\u{3008}'
+            | '\u{300a}'
+            | '\u{300c}'
+            | '\u{300e}'
+            | '\u{3010}'
+            | '\u{3014}'
+            | '\u{3016}'
+            | '\u{3018}'
+            | '\u{301d}'
+            | '\u{ff08}'
+            | '\u{ff3b}'
+            | '\u{ff5b}'
+            | '\u{ff5f}'
+            | '$'
+            | '\u{a3}'
+            | '\u{a5}'
+            | '\u{ff04}'
+            | '\u{ffe1}'
+            | '\u{ffe5}'
+    )
+}
+
+/// The opportunities of [break_opportunities] that kinsoku shori allows as well. Where a line can
+/// be broken is decided by the characters around the spaces at the opportunity, if there are any.
+pub fn kinsoku_break_opportunities(text: &str) -> Vec<BreakOpportunity> {
+    let mut opportunities = Vec::new();
+    kinsoku_break_opportunities_into(text, &mut opportunities);
+    opportunities
+}
+
+/// [kinsoku_break_opportunities] into a buffer that's reused, which is cleared first.
+pub fn kinsoku_break_opportunities_into(text: &str, opportunities: &mut Vec<BreakOpportunity>) {
+    break_opportunities_into(text, opportunities);
+
+    opportunities.retain(|opportunity| {
+        if opportunity.mandatory {
+            return true;
+        }
+
+        let (before, after) = text.split_at(opportunity.offset);
+
+        let last = before.trim_end().chars().next_back();
+        let next = after.chars().next();
+
+        !last.is_some_and(no_line_end) && !next.is_some_and(no_line_start)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Checks cases written like the ones in `LineBreakTest.txt`, with `÷` where a line can start.
+    /// Those after newlines are the mandatory ones.
+    fn check(cases: &[&str]) {
+        check_with(break_opportunities, cases);
+    }
+
+    fn check_with(opportunities: fn(&str) -> Vec<BreakOpportunity>, cases: &[&str]) {
+        for case in cases {
+            let mut text = String::new();
+            let mut expected = Vec::new();
+
+            for c in case.chars() {
+                if c == '÷' {
+                    expected.push(BreakOpportunity {
+                        offset: text.len(),
+                        mandatory: text.ends_with(['\n', '\r', '\u{2029}']),
+                    });
+                } else {
+                    text.push(c);
+                }
+            }
+
+            assert_eq!(opportunities(&text), expected, "{case:?}");
+        }
+    }
+
+    #[test]
+    fn test_spaces_and_mandatory_breaks() {
+        check(&[
+            "",
+            "word",
+            "two ÷words",
+            "many   ÷spaces ÷here ",
+            "line\n÷break",
+            "windows\r\n÷line\r÷mac",
+            "  leading",
+            "para\u{2029}÷graph",
+            "zero\u{200b}÷width",
+            "zero\u{200b} ÷width",
+        ]);
+    }
+
+    #[test]
+    fn test_punctuation() {
+        check(&[
+            "end. ÷Start",
+            "no ÷\"break\" ÷inside",
+            "(opening ÷and ÷closing)",
+            "( spaced )",
+            "wait ! ÷what ?",
+            "self-÷aware",
+            "well-÷known ÷-5",
+            "1,000.50",
+            "$100 ÷and ÷50%",
+            "a\u{a0}b",
+            "soft\u{ad}÷hyphen",
+            "wait\u{2026}",
+            "and/÷or",
+            "word\u{2060}joiner",
+        ]);
+    }
+
+    #[test]
+    fn test_cjk() {
+        check(&[
+            "漢÷字÷仮÷名",
+            "日÷本÷語。÷次",
+            "「引÷用」÷で",
+            "ちょっ÷と",
+            "コー÷ヒー",
+            "中÷文，÷好",
+            "Latin ÷and ÷漢÷字",
+            "한÷국÷어",
+            "¥100",
+        ]);
+    }
+
+    #[test]
+    fn test_kinsoku() {
+        check(&["あ÷～÷い", "値÷\u{ffe5}÷100"]);
+
+        check_with(
+            kinsoku_break_opportunities,
+            &[
+                "あ～÷い",
+                "値÷\u{ffe5}100",
+                "日÷本÷語。÷次",
+                "「引÷用」÷で",
+                "end. ÷Start\n÷(next)",
+            ],
+        );
+    }
+
+    #[test]
+    fn test_combining_marks() {
+        check(&[
+            "e\u{301}te\u{301} ÷ok",
+            "\u{301}start",
+            "a ÷\u{301}b",
+            "👍\u{200d}👍÷👍",
+        ]);
+    }
+}
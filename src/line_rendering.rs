@@ -0,0 +1,253 @@
+//! Viewers anti-alias thin lines, so a 0.2mm table line that doesn't fall on whole pixels comes out
+//! as a blurry gray band two pixels wide. A [LineStyle] can instead be drawn as a hairline or be
+//! snapped to a pixel grid, either on its own with [LineStyle::rendering] or for the lines in an
+//! element with [WithLineRendering]. The rendering of the document is [Pdf::line_rendering].
+
+use crate::{
+    utils::{mm_to_pt, pt_to_mm},
+    *,
+};
+
+/// The size of the pixels lines are snapped to, in pt. This is a pixel of a 96 dpi screen at 100%
+/// zoom and a whole number of them at 200% and 300%.
+pub const SNAP_GRID: f64 = 0.75;
+
+/// How a line is drawn.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineRendering {
+    /// With its thickness, where it is.
+    #[default]
+    Exact,
+
+    /// As a stroke of zero width, which viewers and printers draw as the thinnest line the device
+    /// can show. The layout still uses the thickness of the line.
+    Hairline,
+
+    /// The thickness is rounded to whole pixels of the [SNAP_GRID], at least one, and straight
+    /// lines along the axes are moved so that their edges fall between pixels. The grid starts at
+    /// the bottom left corner of the page. This applies to [Line](elements::line::Line), column
+    /// separators and the lines between the cells of table rows.
+    Snap,
+}
+
+/// Draws the line styles in the element that don't have their own rendering with `rendering`. The
+/// elements around it keep theirs, also on the locations the element breaks to.
+pub struct WithLineRendering<'a, E: Element> {
+    pub rendering: LineRendering,
+    pub element: &'a E,
+}
+
+impl<'a, E: Element> Element for WithLineRendering<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        let outer = std::mem::replace(&mut ctx.pdf.line_rendering, self.rendering);
+
+        let size = if let Some(breakable) = ctx.breakable {
+            self.element.draw(DrawCtx {
+                pdf: &mut *ctx.pdf,
+                breakable: Some(BreakableDraw {
+                    do_break: &mut |pdf, location_idx, height| {
+                        // The parent might draw lines of its own when breaking.
+                        pdf.line_rendering = outer;
+                        let location = (breakable.do_break)(pdf, location_idx, height);
+                        pdf.line_rendering = self.rendering;
+                        location
+                    },
+                    ..breakable
+                }),
+                ..ctx
+            })
+        } else {
+            self.element.draw(DrawCtx {
+                pdf: &mut *ctx.pdf,
+                ..ctx
+            })
+        };
+
+        ctx.pdf.line_rendering = outer;
+
+        size
+    }
+}
+
+impl LineStyle {
+    /// The rendering of the line, the one of the document if it doesn't have its own.
+    pub fn rendering(&self, document: LineRendering) -> LineRendering {
+        self.rendering.unwrap_or(document)
+    }
+
+    /// The width to stroke the line with, in pt.
+    pub fn stroke_width(&self, document: LineRendering) -> f64 {
+        match self.rendering(document) {
+            LineRendering::Exact => mm_to_pt(self.thickness),
+            LineRendering::Hairline => 0.,
+            LineRendering::Snap => snapped_pixels(self.thickness) * SNAP_GRID,
+        }
+    }
+
+    /// Moves the center of a line along one of the axes, in mm, so that the edges of the line fall
+    /// between pixels when snapping.
+    pub fn snap_position(&self, document: LineRendering, center: f64) -> f64 {
+        if self.rendering(document) != LineRendering::Snap {
+            return center;
+        }
+
+        let center = mm_to_pt(center) / SNAP_GRID;
+
+        // With an odd number of pixels, the center is in the middle of one.
+        let snapped = if snapped_pixels(self.thickness) % 2. == 1. {
+            (center - 0.5).round() + 0.5
+        } else {
+            center.round()
+        };
+
+        pt_to_mm(snapped * SNAP_GRID)
+    }
+}
+
+fn snapped_pixels(thickness: f64) -> f64 {
+    (mm_to_pt(thickness) / SNAP_GRID).round().max(1.)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_line_rendering() {
+        use LineRendering::*;
+
+        let style = |thickness, rendering| LineStyle {
+            rendering,
+            ..elements::line::Line::new(thickness).style
+        };
+
+        // Converting between mm and pt isn't exact.
+        let assert_close = |a: f64, b: f64| assert!((a - b).abs() < 1e-5, "{a} != {b}");
+
+        let exact = style(pt_to_mm(1.), None);
+        assert_close(exact.stroke_width(Exact), 1.);
+        assert_eq!(exact.snap_position(Exact, 1.), 1.);
+
+        let hairline = style(pt_to_mm(1.), Some(Hairline));
+        assert_eq!(hairline.stroke_width(Exact), 0.);
+
+        // 1pt is rounded to one pixel, which is centered between the grid lines.
+        let snapped = style(pt_to_mm(1.), Some(Snap));
+        assert_eq!(snapped.stroke_width(Exact), 0.75);
+        assert_close(mm_to_pt(snapped.snap_position(Exact, pt_to_mm(1.))), 1.125);
+
+        // Two pixels are centered on a grid line.
+        let snapped = style(pt_to_mm(1.6), Some(Snap));
+        assert_eq!(snapped.stroke_width(Exact), 1.5);
+        assert_close(mm_to_pt(snapped.snap_position(Exact, pt_to_mm(1.))), 0.75);
+
+        // Even a hairline thickness gets at least one pixel.
+        assert_eq!(style(0.01, Some(Snap)).stroke_width(Exact), 0.75);
+
+        // The document's rendering only applies to the styles without their own.
+        assert_eq!(exact.stroke_width(Hairline), 0.);
+        assert_eq!(snapped.stroke_width(Hairline), 1.5);
+    }
+
+    /// Records the line rendering when it's drawn and, if it breaks, after breaking once.
+    struct Probe<'a> {
+        seen: &'a RefCell<Vec<LineRendering>>,
+    }
+
+    impl<'a> Element for Probe<'a> {
+        fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+            if let Some(breakable) = ctx.breakable {
+                *breakable.break_count = 1;
+            }
+
+            ElementSize {
+                width: Some(1.),
+                height: Some(1.),
+            }
+        }
+
+        fn draw(&self, ctx: DrawCtx) -> ElementSize {
+            self.seen.borrow_mut().push(ctx.pdf.line_rendering);
+
+            if let Some(breakable) = ctx.breakable {
+                (breakable.do_break)(ctx.pdf, 0, Some(1.));
+                self.seen.borrow_mut().push(ctx.pdf.line_rendering);
+            }
+
+            ElementSize {
+                width: Some(1.),
+                height: Some(1.),
+            }
+        }
+    }
+
+    /// Records the line rendering when the element breaks.
+    struct Parent<'a, E: Element> {
+        element: &'a E,
+        seen: &'a RefCell<Vec<LineRendering>>,
+    }
+
+    impl<'a, E: Element> Element for Parent<'a, E> {
+        fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+            self.element.measure(ctx)
+        }
+
+        fn draw(&self, ctx: DrawCtx) -> ElementSize {
+            if let Some(breakable) = ctx.breakable {
+                self.element.draw(DrawCtx {
+                    pdf: ctx.pdf,
+                    breakable: Some(BreakableDraw {
+                        do_break: &mut |pdf, location_idx, height| {
+                            self.seen.borrow_mut().push(pdf.line_rendering);
+                            (breakable.do_break)(pdf, location_idx, height)
+                        },
+                        ..breakable
+                    }),
+                    ..ctx
+                })
+            } else {
+                self.element.draw(ctx)
+            }
+        }
+    }
+
+    #[test]
+    fn test_with_line_rendering() {
+        use LineRendering::*;
+
+        let seen = RefCell::new(Vec::new());
+
+        let element = Parent {
+            element: &WithLineRendering {
+                rendering: Snap,
+                element: &WithLineRendering {
+                    rendering: Hairline,
+                    element: &Probe { seen: &seen },
+                },
+            },
+            seen: &seen,
+        };
+
+        for output in ElementTestParams::default().run(&element) {
+            // Every configuration is drawn twice.
+            let expected = if output.breakable.is_some() {
+                [Hairline, Exact, Hairline].repeat(2)
+            } else {
+                [Hairline].repeat(2)
+            };
+
+            assert_eq!(seen.take(), expected);
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! A small inline markup for [RichText](crate::elements::rich_text::RichText).
+//!
+//! - `**` toggles bold
+//! - `*` toggles italic
+//! - `__` toggles underline
+//! - `\` escapes the next character
+//!
+//! Markers that are still open at the end of the text are simply ignored, so unbalanced input
+//! never fails to parse.
+
+use crate::elements::rich_text::Span;
+
+/// Parses the markup into spans, with the text drawn in `color`. Adjacent runs with the same style
+/// end up in the same span.
+pub fn parse(text: &str, color: u32) -> Vec<Span> {
+    let mut spans = Vec::new();
+
+    let mut current = Span {
+        text: String::new(),
+        bold: false,
+        italic: false,
+        underline: false,
+        color,
+        underline_color: None,
+        underline_thickness: None,
+        script: None,
+        language: None,
+        markup: None,
+        ruby: None,
+    };
+
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        let toggle: Option<fn(&mut Span)> = match c {
+            '\\' => {
+                if let Some(escaped) = chars.next() {
+                    current.text.push(escaped);
+                }
+
+                continue;
+            }
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                Some(|s| s.bold = !s.bold)
+            }
+            '*' => Some(|s| s.italic = !s.italic),
+            '_' if chars.peek() == Some(&'_') => {
+                chars.next();
+                Some(|s| s.underline = !s.underline)
+            }
+            _ => None,
+        };
+
+        if let Some(toggle) = toggle {
+            let mut next = Span {
+                text: String::new(),
+                ..current.clone()
+            };
+
+            toggle(&mut next);
+
+            push(&mut spans, current);
+            current = next;
+        } else {
+            current.text.push(c);
+        }
+    }
+
+    push(&mut spans, current);
+
+    spans
+}
+
+fn push(spans: &mut Vec<Span>, span: Span) {
+    if span.text.is_empty() {
+        return;
+    }
+
+    match spans.last_mut() {
+        Some(last)
+            if (last.bold, last.italic, last.underline)
+                == (span.bold, span.italic, span.underline) =>
+        {
+            last.text.push_str(&span.text);
+        }
+        _ => spans.push(span),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let spans = parse(r"a **b *c*** __d__ \*e\* f_g****h", 0x00_00_00_FF);
+
+        let styles: Vec<_> = spans
+            .iter()
+            .map(|s| (s.text.as_str(), s.bold, s.italic, s.underline))
+            .collect();
+
+        assert_eq!(
+            styles,
+            [
+                ("a ", false, false, false),
+                ("b ", true, false, false),
+                ("c", true, true, false),
+                (" ", false, false, false),
+                ("d", false, false, true),
+                (" *e* f_gh", false, false, false),
+            ]
+        );
+    }
+}
@@ -0,0 +1,210 @@
+//! Formats for page numbers and other counters. [Page](crate::elements::page::Page) passes the
+//! page index and count to its decoration elements; [PageNumbering] turns the index into the
+//! number to show, so front matter can be numbered `i, ii, iii` and every section can start
+//! counting again:
+//!
+//! ```ignore
+//! let numbering = PageNumbering {
+//!     format: NumberFormat::RomanLower,
+//!     start: 1,
+//!     restarts: &[4],
+//! };
+//!
+//! let text = numbering.text(page);
+//! ```
+//!
+//! Formats for other locales can be plugged in by implementing [NumberFormatter], which closures
+//! from `u32` to [String] do already.
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum NumberFormat {
+    /// 1, 2, 3
+    #[default]
+    Arabic,
+
+    /// I, II, III
+    RomanUpper,
+
+    /// i, ii, iii
+    RomanLower,
+
+    /// A, B, ..., Z, AA, AB
+    AlphabeticUpper,
+
+    /// a, b, ..., z, aa, ab
+    AlphabeticLower,
+}
+
+pub trait NumberFormatter {
+    fn format(&self, number: u32) -> String;
+}
+
+impl<F: Fn(u32) -> String> NumberFormatter for F {
+    fn format(&self, number: u32) -> String {
+        self(number)
+    }
+}
+
+impl NumberFormatter for NumberFormat {
+    /// Numbers that can't be written in the format, like zero in roman numerals, are written in
+    /// arabic numerals instead.
+    fn format(&self, number: u32) -> String {
+        match self {
+            NumberFormat::Arabic => number.to_string(),
+            NumberFormat::RomanUpper => roman(number).unwrap_or_else(|| number.to_string()),
+            NumberFormat::RomanLower => roman(number)
+                .map(|r| r.to_lowercase())
+                .unwrap_or_else(|| number.to_string()),
+            NumberFormat::AlphabeticUpper => alphabetic(number, b'A'),
+            NumberFormat::AlphabeticLower => alphabetic(number, b'a'),
+        }
+    }
+}
+
+/// Only numbers from 1 to 3999 can be written without extensions to the notation.
+fn roman(mut number: u32) -> Option<String> {
+    const NUMERALS: [(u32, &str); 13] = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+
+    if !(1..4000).contains(&number) {
+        return None;
+    }
+
+    let mut result = String::new();
+
+    for (value, numeral) in NUMERALS {
+        while number >= value {
+            result.push_str(numeral);
+            number -= value;
+        }
+    }
+
+    Some(result)
+}
+
+/// Like the columns of a spreadsheet: after `z` comes `aa`.
+fn alphabetic(mut number: u32, first: u8) -> String {
+    if number == 0 {
+        return number.to_string();
+    }
+
+    let mut letters = Vec::new();
+
+    while number > 0 {
+        number -= 1;
+        letters.push(first + (number % 26) as u8);
+        number /= 26;
+    }
+
+    letters.iter().rev().map(|&l| l as char).collect()
+}
+
+/// Maps page indices, starting at 0, to the numbers shown on the pages.
+#[derive(Clone, Debug)]
+pub struct PageNumbering<'a, F: NumberFormatter = NumberFormat> {
+    pub format: F,
+
+    /// The number of the first page and of every page counting restarts at.
+    pub start: u32,
+
+    /// The indices of the pages where counting starts over, like the first pages of sections.
+    pub restarts: &'a [usize],
+}
+
+impl<'a, F: NumberFormatter> PageNumbering<'a, F> {
+    pub fn number(&self, page: usize) -> u32 {
+        let section_start = self
+            .restarts
+            .iter()
+            .copied()
+            .filter(|&r| r <= page)
+            .max()
+            .unwrap_or(0);
+
+        self.start + (page - section_start) as u32
+    }
+
+    pub fn text(&self, page: usize) -> String {
+        self.format.format(self.number(page))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_formats() {
+        let format = |format: NumberFormat, numbers: &[u32]| {
+            numbers
+                .iter()
+                .map(|&n| format.format(n))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(format(NumberFormat::Arabic, &[0, 7, 12]), ["0", "7", "12"]);
+        assert_eq!(
+            format(
+                NumberFormat::RomanUpper,
+                &[1, 4, 9, 14, 40, 1994, 3999, 4000, 0]
+            ),
+            [
+                "I",
+                "IV",
+                "IX",
+                "XIV",
+                "XL",
+                "MCMXCIV",
+                "MMMCMXCIX",
+                "4000",
+                "0"
+            ]
+        );
+        assert_eq!(format(NumberFormat::RomanLower, &[3, 19]), ["iii", "xix"]);
+        assert_eq!(
+            format(
+                NumberFormat::AlphabeticUpper,
+                &[1, 26, 27, 52, 53, 702, 703]
+            ),
+            ["A", "Z", "AA", "AZ", "BA", "ZZ", "AAA"]
+        );
+        assert_eq!(format(NumberFormat::AlphabeticLower, &[2, 0]), ["b", "0"]);
+    }
+
+    #[test]
+    fn test_page_numbering() {
+        let numbering = PageNumbering {
+            format: NumberFormat::RomanLower,
+            start: 1,
+            restarts: &[3],
+        };
+
+        assert_eq!(
+            (0..5).map(|p| numbering.text(p)).collect::<Vec<_>>(),
+            ["i", "ii", "iii", "i", "ii"]
+        );
+
+        let numbering = PageNumbering {
+            format: |n: u32| format!("- {n} -"),
+            start: 5,
+            restarts: &[],
+        };
+
+        assert_eq!(numbering.text(2), "- 7 -");
+    }
+}
@@ -0,0 +1,281 @@
+//! Rendering of documents to PNG images, e.g. for previews in a web frontend that doesn't have a
+//! PDF renderer. The PDF is rasterized by interpreting the drawing operators of its pages, see
+//! [rasterizer]. For an exact rendering, e.g. in golden tests, [rasterize_pdf] uses `pdftoppm` from
+//! poppler instead, which needs to be installed for it to work.
+
+pub mod rasterizer;
+
+use std::{
+    fs::{DirBuilder, File},
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::Command,
+    sync::atomic::{AtomicU32, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use printpdf::PdfDocumentReference;
+
+/// An 8-bit RGB raster of a single page.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Raster {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl std::fmt::Debug for Raster {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Raster")
+            .field("width", &self.width)
+            .field("height", &self.height)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Raster {
+    pub fn read_png(path: impl AsRef<Path>) -> io::Result<Self> {
+        let decoder = png::Decoder::new(BufReader::new(File::open(path)?));
+        let mut reader = decoder.read_info().map_err(io::Error::other)?;
+
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).map_err(io::Error::other)?;
+        buf.truncate(info.buffer_size());
+
+        let pixels = match info.color_type {
+            png::ColorType::Rgb => buf,
+            png::ColorType::Rgba => buf
+                .chunks_exact(4)
+                .flat_map(|p| [p[0], p[1], p[2]])
+                .collect(),
+            png::ColorType::Grayscale => buf.iter().flat_map(|&g| [g, g, g]).collect(),
+            png::ColorType::GrayscaleAlpha => buf
+                .chunks_exact(2)
+                .flat_map(|p| [p[0], p[0], p[0]])
+                .collect(),
+            png::ColorType::Indexed => {
+                return Err(io::Error::other("indexed pngs are not supported"));
+            }
+        };
+
+        Ok(Raster {
+            width: info.width,
+            height: info.height,
+            pixels,
+        })
+    }
+
+    pub fn write_png(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        self.write_png_to(BufWriter::new(File::create(path)?))
+    }
+
+    pub fn write_png_to(&self, writer: impl Write) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer
+            .write_image_data(&self.pixels)
+            .map_err(io::Error::other)?;
+
+        Ok(())
+    }
+
+    /// Returns the number of pixels that differ by more than `channel_tolerance` in any channel, or
+    /// `None` if the dimensions don't match.
+    pub fn differing_pixels(&self, other: &Raster, channel_tolerance: u8) -> Option<usize> {
+        if self.width != other.width || self.height != other.height {
+            return None;
+        }
+
+        Some(
+            self.pixels
+                .chunks_exact(3)
+                .zip(other.pixels.chunks_exact(3))
+                .filter(|(a, b)| {
+                    a.iter()
+                        .zip(b.iter())
+                        .any(|(&a, &b)| a.abs_diff(b) > channel_tolerance)
+                })
+                .count(),
+        )
+    }
+}
+
+/// A directory that's removed again when this is dropped, including on errors.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new() -> io::Result<Self> {
+        static COUNTER: AtomicU32 = AtomicU32::new(0);
+
+        // The names are predictable, so someone else could have created the directory already.
+        // Only a directory we created ourselves is used, otherwise we try another name.
+        for _ in 0..64 {
+            let nanos = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.subsec_nanos());
+
+            let dir = std::env::temp_dir().join(format!(
+                "laser-pdf-preview-{}-{}-{nanos}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed),
+            ));
+
+            let mut builder = DirBuilder::new();
+
+            #[cfg(unix)]
+            std::os::unix::fs::DirBuilderExt::mode(&mut builder, 0o700);
+
+            match builder.create(&dir) {
+                Ok(()) => return Ok(TempDir(dir)),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "could not create a temporary directory",
+        ))
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        // There's nothing sensible to do with an error here.
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Rasterizes every page of the PDF with the built-in [rasterizer].
+pub fn rasterize(pdf: &[u8], dpi: u32) -> io::Result<Vec<Raster>> {
+    let document = lopdf::Document::load_mem(pdf).map_err(io::Error::other)?;
+
+    document
+        .get_pages()
+        .into_values()
+        .map(|page_id| {
+            rasterizer::rasterize_page(&document, page_id, dpi).map_err(io::Error::other)
+        })
+        .collect()
+}
+
+/// Rasterizes every page of the PDF with `pdftoppm`.
+pub fn rasterize_pdf(pdf: &[u8], dpi: u32) -> io::Result<Vec<Raster>> {
+    let temp_dir = TempDir::new()?;
+    let dir = &temp_dir.0;
+    let input = dir.join("input.pdf");
+    std::fs::write(&input, pdf)?;
+
+    let status = Command::new("pdftoppm")
+        .arg("-png")
+        .arg("-r")
+        .arg(dpi.to_string())
+        .arg(&input)
+        .arg(dir.join("page"))
+        .status()?;
+
+    if !status.success() {
+        return Err(io::Error::other(format!("pdftoppm failed: {status}")));
+    }
+
+    // pdftoppm pads the page number depending on the page count, so we sort the names instead of
+    // trying to guess them.
+    let mut pages = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<io::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|path| path.extension().is_some_and(|e| e == "png"))
+        .collect::<Vec<_>>();
+    pages.sort();
+
+    pages.iter().map(Raster::read_png).collect()
+}
+
+/// Rasterizes every page of the PDF and encodes each of them as a PNG.
+pub fn render_png(pdf: &[u8], dpi: u32) -> io::Result<Vec<Vec<u8>>> {
+    rasterize(pdf, dpi)?
+        .iter()
+        .map(|page| {
+            let mut png = Vec::new();
+            page.write_png_to(&mut png)?;
+            Ok(png)
+        })
+        .collect()
+}
+
+/// Saves the document, for example one returned by [build_pdf](crate::build_pdf), and renders it
+/// with [render_png].
+pub fn render_document_png(document: PdfDocumentReference, dpi: u32) -> io::Result<Vec<Vec<u8>>> {
+    let mut bytes = Vec::new();
+
+    document
+        .save(&mut BufWriter::new(&mut bytes))
+        .map_err(io::Error::other)?;
+
+    render_png(&bytes, dpi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_differing_pixels() {
+        let a = Raster {
+            width: 2,
+            height: 1,
+            pixels: vec![0, 0, 0, 255, 255, 255],
+        };
+
+        let b = Raster {
+            width: 2,
+            height: 1,
+            pixels: vec![5, 0, 0, 200, 255, 255],
+        };
+
+        assert_eq!(a.differing_pixels(&b, 8), Some(1));
+        assert_eq!(a.differing_pixels(&b, 60), Some(0));
+
+        let c = Raster {
+            width: 1,
+            height: 2,
+            ..b.clone()
+        };
+
+        assert_eq!(a.differing_pixels(&c, 8), None);
+    }
+
+    #[test]
+    fn test_temp_dir() {
+        let a = TempDir::new().unwrap();
+        let b = TempDir::new().unwrap();
+
+        assert_ne!(a.0, b.0);
+        assert!(a.0.is_dir());
+
+        let path = a.0.clone();
+        drop(a);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_render_png() {
+        let document = crate::build_pdf(
+            "test",
+            (100., 100.),
+            |_| (),
+            |_: &()| crate::elements::rectangle::Rectangle {
+                size: (10., 10.),
+                fill: Some(0xff_00_00_ff),
+                outline: None,
+            },
+        );
+
+        let pngs = render_document_png(document, 36).unwrap();
+        assert_eq!(pngs.len(), 1);
+        assert_eq!(&pngs[0][1..4], b"PNG");
+    }
+}
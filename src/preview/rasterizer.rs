@@ -0,0 +1,1505 @@
+//! A rasterizer for the drawing operators in the content streams of a PDF, so previews don't need
+//! any external tools. It covers what this crate draws: paths, text in embedded TrueType fonts,
+//! images and form XObjects, with constant alpha, clipping and spot colors. The built-in fonts
+//! have no outlines in the document, so their text is drawn as blocks of the size of the glyphs.
+//! Dash patterns, line joins, shadings and patterns are ignored.
+
+use std::{collections::HashMap, rc::Rc};
+
+use lopdf::{
+    content::{Content, Operation},
+    Dictionary, Document, Object, ObjectId, Stream,
+};
+use stb_truetype::{FontInfo, VertexType};
+
+use crate::letterhead::{inherited, resolve};
+
+use super::Raster;
+
+/// Sub-scanlines per row of pixels, for antialiasing in the vertical direction. Horizontally the
+/// coverage is exact.
+const SUBSAMPLES: usize = 4;
+
+/// How deeply form XObjects can be nested, in case they form a cycle.
+const MAX_FORM_DEPTH: u32 = 16;
+
+/// Rasterizes a page of the document at the resolution in dots per inch.
+pub fn rasterize_page(document: &Document, page_id: ObjectId, dpi: u32) -> Result<Raster, String> {
+    let media_box = match inherited(document, page_id, b"MediaBox") {
+        Some(Object::Array(media_box)) => numbers(media_box),
+        _ => None,
+    };
+
+    // US Letter is the default in the PDF specification.
+    let [left, bottom, right, top] = match media_box.as_deref() {
+        Some(&[a, b, c, d]) => [a.min(c), b.min(d), a.max(c), b.max(d)],
+        _ => [0., 0., 612., 792.],
+    };
+
+    let scale = dpi as f64 / 72.;
+    let width = ((right - left) * scale).ceil().max(1.) as usize;
+    let height = ((top - bottom) * scale).ceil().max(1.) as usize;
+
+    let content = document
+        .get_page_content(page_id)
+        .map_err(|e| format!("could not read the page content: {e:?}"))?;
+    let operations = Content::decode(&content)
+        .map_err(|e| format!("could not decode the page content: {e:?}"))?
+        .operations;
+
+    let empty = Dictionary::new();
+    let resources = match inherited(document, page_id, b"Resources") {
+        Some(Object::Dictionary(resources)) => resources,
+        _ => &empty,
+    };
+
+    let mut rasterizer = Rasterizer {
+        document,
+        width,
+        height,
+        pixels: vec![255; width * height * 3],
+        fonts: HashMap::new(),
+    };
+
+    let state = State {
+        // From points with the origin at the bottom left to pixels from the top left.
+        ctm: Matrix([scale, 0., 0., -scale, -left * scale, top * scale]),
+        ..State::default()
+    };
+
+    rasterizer.run(&operations, resources, state, 0);
+
+    Ok(Raster {
+        width: width as u32,
+        height: height as u32,
+        pixels: rasterizer.pixels,
+    })
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Matrix([f64; 6]);
+
+impl Matrix {
+    const IDENTITY: Matrix = Matrix([1., 0., 0., 1., 0., 0.]);
+
+    fn translate(x: f64, y: f64) -> Matrix {
+        Matrix([1., 0., 0., 1., x, y])
+    }
+
+    fn from_operands(operands: &[Object]) -> Option<Matrix> {
+        Some(Matrix(numbers(operands)?.try_into().ok()?))
+    }
+
+    /// This transformation followed by `other`.
+    fn then(self, other: Matrix) -> Matrix {
+        let [a, b, c, d, e, f] = self.0;
+        let [a2, b2, c2, d2, e2, f2] = other.0;
+
+        Matrix([
+            a * a2 + b * c2,
+            a * b2 + b * d2,
+            c * a2 + d * c2,
+            c * b2 + d * d2,
+            e * a2 + f * c2 + e2,
+            e * b2 + f * d2 + f2,
+        ])
+    }
+
+    fn apply(self, (x, y): (f64, f64)) -> (f64, f64) {
+        let [a, b, c, d, e, f] = self.0;
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+
+    fn invert(self) -> Option<Matrix> {
+        let [a, b, c, d, e, f] = self.0;
+        let det = a * d - b * c;
+
+        if det.abs() < 1e-12 {
+            return None;
+        }
+
+        Some(Matrix([
+            d / det,
+            -b / det,
+            -c / det,
+            a / det,
+            (c * f - d * e) / det,
+            (b * e - a * f) / det,
+        ]))
+    }
+
+    /// How much lengths are scaled on average.
+    fn scale(self) -> f64 {
+        let [a, b, c, d, ..] = self.0;
+        (a * d - b * c).abs().sqrt()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ColorSpace {
+    Gray,
+    Rgb,
+    Cmyk,
+
+    /// A spot color whose alternate is CMYK, with the values at no ink and at the full ink.
+    Separation {
+        c0: [f64; 4],
+        c1: [f64; 4],
+    },
+
+    Unknown,
+}
+
+impl ColorSpace {
+    fn color(self, values: &[f64]) -> Option<[f64; 3]> {
+        match (self, values) {
+            (ColorSpace::Gray, &[gray]) => Some([gray; 3]),
+            (ColorSpace::Rgb, &[r, g, b]) => Some([r, g, b]),
+            (ColorSpace::Cmyk, &[c, m, y, k]) => Some(cmyk_to_rgb([c, m, y, k])),
+            (ColorSpace::Separation { c0, c1 }, &[tint]) => {
+                Some(cmyk_to_rgb(std::array::from_fn(|i| {
+                    c0[i] + tint * (c1[i] - c0[i])
+                })))
+            }
+            _ => None,
+        }
+    }
+
+    /// The color a color space starts with when it's selected.
+    fn initial_color(self) -> [f64; 3] {
+        match self {
+            ColorSpace::Separation { .. } => self.color(&[1.]).unwrap(),
+            _ => [0.; 3],
+        }
+    }
+}
+
+fn cmyk_to_rgb([c, m, y, k]: [f64; 4]) -> [f64; 3] {
+    [
+        (1. - c) * (1. - k),
+        (1. - m) * (1. - k),
+        (1. - y) * (1. - k),
+    ]
+}
+
+#[derive(Clone)]
+struct State {
+    ctm: Matrix,
+
+    fill_space: ColorSpace,
+    fill: [f64; 3],
+    fill_alpha: f64,
+
+    stroke_space: ColorSpace,
+    stroke: [f64; 3],
+    stroke_alpha: f64,
+
+    line_width: f64,
+
+    /// The coverage of the clipping path for every pixel, or `None` if nothing is clipped.
+    clip: Option<Rc<Vec<f32>>>,
+
+    font: Option<Rc<PdfFont>>,
+    font_size: f64,
+    char_spacing: f64,
+    word_spacing: f64,
+    horizontal_scaling: f64,
+    leading: f64,
+    rise: f64,
+    render_mode: i64,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        State {
+            ctm: Matrix::IDENTITY,
+            fill_space: ColorSpace::Gray,
+            fill: [0.; 3],
+            fill_alpha: 1.,
+            stroke_space: ColorSpace::Gray,
+            stroke: [0.; 3],
+            stroke_alpha: 1.,
+            line_width: 1.,
+            clip: None,
+            font: None,
+            font_size: 0.,
+            char_spacing: 0.,
+            word_spacing: 0.,
+            horizontal_scaling: 1.,
+            leading: 0.,
+            rise: 0.,
+            render_mode: 0,
+        }
+    }
+}
+
+/// Subpaths in device space, flattened to polygons, and whether they're closed.
+#[derive(Default)]
+struct Path {
+    subpaths: Vec<(Vec<(f64, f64)>, bool)>,
+}
+
+impl Path {
+    fn current(&self) -> Option<(f64, f64)> {
+        self.subpaths
+            .last()
+            .and_then(|(points, _)| points.last().copied())
+    }
+
+    fn move_to(&mut self, point: (f64, f64)) {
+        self.subpaths.push((vec![point], false));
+    }
+
+    fn line_to(&mut self, point: (f64, f64)) {
+        match self.subpaths.last_mut() {
+            Some((points, _)) => points.push(point),
+            None => self.move_to(point),
+        }
+    }
+
+    fn curve_to(&mut self, a: (f64, f64), b: (f64, f64), to: (f64, f64)) {
+        let Some(from) = self.current() else {
+            return self.move_to(to);
+        };
+
+        if let Some((points, _)) = self.subpaths.last_mut() {
+            flatten_cubic(points, from, a, b, to);
+        }
+    }
+
+    fn close(&mut self) {
+        if let Some((points, closed)) = self.subpaths.last_mut() {
+            *closed = true;
+
+            // Drawing continues from the start of the closed subpath.
+            let start = points[0];
+            self.subpaths.push((vec![start], false));
+        }
+    }
+
+    fn polygons(&self) -> Vec<Vec<(f64, f64)>> {
+        self.subpaths
+            .iter()
+            .filter(|(points, _)| points.len() > 1)
+            .map(|(points, _)| points.clone())
+            .collect()
+    }
+}
+
+/// The number of line segments a curve with the control points is flattened to, so that each of
+/// them is at most a few pixels long.
+fn segments(points: &[(f64, f64)]) -> usize {
+    let length = points
+        .windows(2)
+        .map(|w| (w[1].0 - w[0].0).hypot(w[1].1 - w[0].1))
+        .sum::<f64>();
+
+    ((length / 3.).ceil() as usize).clamp(1, 64)
+}
+
+fn flatten_cubic(
+    points: &mut Vec<(f64, f64)>,
+    p0: (f64, f64),
+    p1: (f64, f64),
+    p2: (f64, f64),
+    p3: (f64, f64),
+) {
+    let n = segments(&[p0, p1, p2, p3]);
+
+    for i in 1..=n {
+        let t = i as f64 / n as f64;
+        let s = 1. - t;
+
+        let (a, b, c, d) = (s * s * s, 3. * s * s * t, 3. * s * t * t, t * t * t);
+
+        points.push((
+            a * p0.0 + b * p1.0 + c * p2.0 + d * p3.0,
+            a * p0.1 + b * p1.1 + c * p2.1 + d * p3.1,
+        ));
+    }
+}
+
+fn flatten_quad(points: &mut Vec<(f64, f64)>, p0: (f64, f64), p1: (f64, f64), p2: (f64, f64)) {
+    let n = segments(&[p0, p1, p2]);
+
+    for i in 1..=n {
+        let t = i as f64 / n as f64;
+        let s = 1. - t;
+
+        let (a, b, c) = (s * s, 2. * s * t, t * t);
+
+        points.push((
+            a * p0.0 + b * p1.0 + c * p2.0,
+            a * p0.1 + b * p1.1 + c * p2.1,
+        ));
+    }
+}
+
+/// The coverage of a shape for a rectangle of pixels, between 0 and 1.
+struct Coverage {
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    values: Vec<f32>,
+}
+
+/// Computes the coverage of the polygons, which are closed implicitly, with the nonzero or the
+/// even-odd rule. Returns `None` if nothing is covered.
+fn coverage(
+    polygons: &[Vec<(f64, f64)>],
+    even_odd: bool,
+    width: usize,
+    height: usize,
+) -> Option<Coverage> {
+    // The edges as (x0, y0, x1, y1) that aren't horizontal.
+    let edges = polygons
+        .iter()
+        .flat_map(|points| {
+            points
+                .iter()
+                .zip(points.iter().cycle().skip(1))
+                .map(|(&(x0, y0), &(x1, y1))| (x0, y0, x1, y1))
+        })
+        .filter(|&(x0, y0, x1, y1)| y0 != y1 && [x0, y0, x1, y1].iter().all(|v| v.is_finite()))
+        .collect::<Vec<_>>();
+
+    let (min_x, max_x, min_y, max_y) = edges.iter().fold(
+        (
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+        ),
+        |(min_x, max_x, min_y, max_y), &(x0, y0, x1, y1)| {
+            (
+                min_x.min(x0).min(x1),
+                max_x.max(x0).max(x1),
+                min_y.min(y0).min(y1),
+                max_y.max(y0).max(y1),
+            )
+        },
+    );
+
+    let x = min_x.floor().max(0.) as usize;
+    let y = min_y.floor().max(0.) as usize;
+    let right = (max_x.ceil().min(width as f64) as usize).max(x);
+    let bottom = (max_y.ceil().min(height as f64) as usize).max(y);
+
+    if edges.is_empty() || right <= x || bottom <= y {
+        return None;
+    }
+
+    let mut coverage = Coverage {
+        x,
+        y,
+        width: right - x,
+        height: bottom - y,
+        values: vec![0.; (right - x) * (bottom - y)],
+    };
+
+    let weight = 1. / SUBSAMPLES as f32;
+    let mut crossings = Vec::new();
+
+    for row in 0..coverage.height {
+        let row_top = (y + row) as f64;
+
+        let active = edges
+            .iter()
+            .filter(|e| e.1.min(e.3) < row_top + 1. && e.1.max(e.3) > row_top)
+            .collect::<Vec<_>>();
+
+        let values = &mut coverage.values[row * coverage.width..(row + 1) * coverage.width];
+
+        for sample in 0..SUBSAMPLES {
+            let sample_y = row_top + (sample as f64 + 0.5) / SUBSAMPLES as f64;
+
+            crossings.clear();
+
+            for &&(x0, y0, x1, y1) in &active {
+                if (y0 <= sample_y && sample_y < y1) || (y1 <= sample_y && sample_y < y0) {
+                    let crossing_x = x0 + (sample_y - y0) * (x1 - x0) / (y1 - y0);
+                    crossings.push((crossing_x - x as f64, if y1 > y0 { 1 } else { -1 }));
+                }
+            }
+
+            crossings.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+            let mut winding = 0;
+            let mut start = 0.;
+
+            for &(crossing_x, direction) in &crossings {
+                let was_inside = if even_odd {
+                    winding % 2 != 0
+                } else {
+                    winding != 0
+                };
+
+                winding += direction;
+
+                let inside = if even_odd {
+                    winding % 2 != 0
+                } else {
+                    winding != 0
+                };
+
+                if !was_inside && inside {
+                    start = crossing_x;
+                } else if was_inside && !inside {
+                    add_span(values, start, crossing_x, weight);
+                }
+            }
+        }
+    }
+
+    Some(coverage)
+}
+
+/// Adds the weight to the pixels of the row from `a` to `b`, partially for the pixels at the ends.
+fn add_span(row: &mut [f32], a: f64, b: f64, weight: f32) {
+    let a = a.max(0.);
+    let b = b.min(row.len() as f64);
+
+    if a >= b {
+        return;
+    }
+
+    let first = a.floor() as usize;
+    let last = b.floor() as usize;
+
+    if first == last {
+        row[first] += (b - a) as f32 * weight;
+        return;
+    }
+
+    row[first] += (first as f64 + 1. - a) as f32 * weight;
+
+    for value in &mut row[first + 1..last] {
+        *value += weight;
+    }
+
+    if last < row.len() {
+        row[last] += (b - last as f64) as f32 * weight;
+    }
+}
+
+/// The outline of the stroke of the subpaths as polygons, with square ends and round joins. They
+/// all have the same orientation, so they can be filled together with the nonzero rule.
+fn stroke_polygons(subpaths: &[(Vec<(f64, f64)>, bool)], width: f64) -> Vec<Vec<(f64, f64)>> {
+    let radius = width / 2.;
+    let mut polygons = Vec::new();
+
+    for (points, closed) in subpaths {
+        if points.len() < 2 {
+            continue;
+        }
+
+        let closing = closed.then(|| (points[points.len() - 1], points[0]));
+
+        let segments = points
+            .windows(2)
+            .map(|w| (w[0], w[1]))
+            .chain(closing)
+            .collect::<Vec<_>>();
+
+        for (i, &((x0, y0), (x1, y1))) in segments.iter().enumerate() {
+            let length = (x1 - x0).hypot(y1 - y0);
+
+            if length == 0. {
+                continue;
+            }
+
+            let (dx, dy) = ((x1 - x0) / length * radius, (y1 - y0) / length * radius);
+
+            // Open subpaths get square caps at their ends.
+            let (start, end) = (
+                if i == 0 && !closed {
+                    (dx, dy)
+                } else {
+                    (0., 0.)
+                },
+                if i == segments.len() - 1 && !closed {
+                    (dx, dy)
+                } else {
+                    (0., 0.)
+                },
+            );
+
+            polygons.push(vec![
+                (x0 - start.0 - dy, y0 - start.1 + dx),
+                (x1 + end.0 - dy, y1 + end.1 + dx),
+                (x1 + end.0 + dy, y1 + end.1 - dx),
+                (x0 - start.0 + dy, y0 - start.1 - dx),
+            ]);
+
+            // The joins, as octagons around the end of every segment that's followed by another.
+            if i < segments.len() - 1 || *closed {
+                polygons.push(
+                    (0..8)
+                        .map(|j| {
+                            let angle = j as f64 * std::f64::consts::FRAC_PI_4;
+                            (x1 + radius * angle.cos(), y1 + radius * angle.sin())
+                        })
+                        .collect(),
+                );
+            }
+        }
+    }
+
+    for polygon in &mut polygons {
+        let area = polygon
+            .iter()
+            .zip(polygon.iter().cycle().skip(1))
+            .map(|(a, b)| a.0 * b.1 - b.0 * a.1)
+            .sum::<f64>();
+
+        if area < 0. {
+            polygon.reverse();
+        }
+    }
+
+    polygons
+}
+
+/// A font with what's needed to draw its glyphs.
+struct PdfFont {
+    /// The embedded TrueType font, if there is one.
+    outlines: Option<FontInfo<Vec<u8>>>,
+
+    /// Composite fonts like the ones printpdf writes for TrueType fonts use two byte codes, which
+    /// are the glyph indices.
+    two_bytes: bool,
+
+    /// The widths by code in thousandths of the font size.
+    widths: HashMap<u32, f64>,
+    default_width: f64,
+}
+
+impl PdfFont {
+    fn load(document: &Document, font: &Dictionary) -> PdfFont {
+        let get = |dictionary: &Dictionary, key: &[u8]| {
+            dictionary
+                .get(key)
+                .ok()
+                .and_then(|o| resolve(document, o))
+                .cloned()
+        };
+
+        let two_bytes = matches!(get(font, b"Subtype"), Some(Object::Name(n)) if n == b"Type0");
+        let mut widths = HashMap::new();
+
+        let (descriptor, default_width) = if two_bytes {
+            let descendant = match get(font, b"DescendantFonts") {
+                Some(Object::Array(fonts)) => fonts
+                    .first()
+                    .and_then(|o| resolve(document, o))
+                    .and_then(|o| o.as_dict().ok())
+                    .cloned(),
+                _ => None,
+            }
+            .unwrap_or_else(Dictionary::new);
+
+            // Like `[first [w1 w2 ..] first last w ..]`.
+            if let Some(Object::Array(w)) = get(&descendant, b"W") {
+                let w = w
+                    .iter()
+                    .map(|o| resolve(document, o).cloned().unwrap_or(Object::Null))
+                    .collect::<Vec<_>>();
+
+                let mut i = 0;
+
+                while i + 1 < w.len() {
+                    let Some(first) = number(&w[i]) else {
+                        break;
+                    };
+
+                    match &w[i + 1] {
+                        Object::Array(list) => {
+                            for (j, width) in list.iter().enumerate() {
+                                if let Some(width) = number(width) {
+                                    widths.insert(first as u32 + j as u32, width);
+                                }
+                            }
+
+                            i += 2;
+                        }
+                        last => {
+                            let (Some(last), Some(width)) =
+                                (number(last), w.get(i + 2).and_then(number))
+                            else {
+                                break;
+                            };
+
+                            for code in first as u32..=(last as u32).min(first as u32 + 0xffff) {
+                                widths.insert(code, width);
+                            }
+
+                            i += 3;
+                        }
+                    }
+                }
+            }
+
+            let default_width = get(&descendant, b"DW").as_ref().and_then(number);
+            (
+                get(&descendant, b"FontDescriptor"),
+                default_width.unwrap_or(1000.),
+            )
+        } else {
+            let first = get(font, b"FirstChar").as_ref().and_then(number);
+
+            if let (Some(first), Some(Object::Array(list))) = (first, get(font, b"Widths")) {
+                for (i, width) in list.iter().enumerate() {
+                    if let Some(width) = resolve(document, width).and_then(number) {
+                        widths.insert(first as u32 + i as u32, width);
+                    }
+                }
+            }
+
+            (get(font, b"FontDescriptor"), 500.)
+        };
+
+        let outlines = match descriptor {
+            Some(Object::Dictionary(descriptor)) => match get(&descriptor, b"FontFile2") {
+                Some(Object::Stream(stream)) => FontInfo::new(stream_content(&stream), 0),
+                _ => None,
+            },
+            _ => None,
+        };
+
+        PdfFont {
+            outlines,
+            two_bytes,
+            widths,
+            default_width,
+        }
+    }
+
+    fn codes(&self, bytes: &[u8]) -> Vec<u32> {
+        if self.two_bytes {
+            bytes
+                .chunks(2)
+                .map(|c| ((c[0] as u32) << 8) | c.get(1).copied().unwrap_or(0) as u32)
+                .collect()
+        } else {
+            bytes.iter().map(|&b| b as u32).collect()
+        }
+    }
+
+    fn glyph(&self, info: &FontInfo<Vec<u8>>, code: u32) -> u32 {
+        if self.two_bytes {
+            code
+        } else {
+            info.find_glyph_index(code)
+        }
+    }
+
+    /// The advance of the glyph, as a fraction of the font size.
+    fn width(&self, code: u32) -> f64 {
+        if let Some(&width) = self.widths.get(&code) {
+            return width / 1000.;
+        }
+
+        match &self.outlines {
+            Some(info) => {
+                let advance = info
+                    .get_glyph_h_metrics(self.glyph(info, code))
+                    .advance_width;
+                advance as f64 / info.units_per_em() as f64
+            }
+            None => self.default_width / 1000.,
+        }
+    }
+
+    /// The outline of the glyph in device space, given the text rendering matrix.
+    fn polygons(&self, code: u32, trm: Matrix) -> Vec<Vec<(f64, f64)>> {
+        let Some(info) = &self.outlines else {
+            if !self.two_bytes && code == 32 {
+                return Vec::new();
+            }
+
+            // A block instead of the glyph, roughly of the size of a lowercase letter.
+            let width = self.width(code) * 0.9;
+            return vec![[(0., 0.), (width, 0.), (width, 0.5), (0., 0.5)]
+                .map(|p| trm.apply(p))
+                .to_vec()];
+        };
+
+        let scale = 1. / info.units_per_em() as f64;
+        let point = |x: f64, y: f64| trm.apply((x * scale, y * scale));
+
+        let mut polygons: Vec<Vec<(f64, f64)>> = Vec::new();
+
+        for vertex in info
+            .get_glyph_shape(self.glyph(info, code))
+            .unwrap_or_default()
+        {
+            let to = point(vertex.x as f64, vertex.y as f64);
+
+            match vertex.vertex_type() {
+                VertexType::MoveTo => polygons.push(vec![to]),
+                VertexType::LineTo => {
+                    if let Some(polygon) = polygons.last_mut() {
+                        polygon.push(to);
+                    }
+                }
+                VertexType::CurveTo => {
+                    if let Some(polygon) = polygons.last_mut() {
+                        let from = polygon[polygon.len() - 1];
+                        let control = point(vertex.cx as f64, vertex.cy as f64);
+                        flatten_quad(polygon, from, control, to);
+                    }
+                }
+            }
+        }
+
+        polygons
+    }
+}
+
+/// The pixels of an image XObject.
+struct Pixels {
+    width: usize,
+    height: usize,
+    rgb: Vec<u8>,
+    alpha: Option<Vec<u8>>,
+}
+
+/// Decodes images with 8 bits per component that are either not compressed or with Flate, which
+/// are the ones printpdf writes for images that aren't JPEGs.
+fn decode_image(document: &Document, stream: &Stream) -> Option<Pixels> {
+    let get = |key: &[u8]| stream.dict.get(key).ok().and_then(|o| resolve(document, o));
+    let integer = |key: &[u8]| get(key).and_then(number).map(|v| v as usize);
+
+    let width = integer(b"Width")?;
+    let height = integer(b"Height")?;
+
+    if integer(b"BitsPerComponent")? != 8 {
+        return None;
+    }
+
+    let components = match get(b"ColorSpace")? {
+        Object::Name(name) => match &name[..] {
+            b"DeviceGray" => 1,
+            b"DeviceRGB" => 3,
+            b"DeviceCMYK" => 4,
+            _ => return None,
+        },
+        Object::Array(array) => match &array[..] {
+            [Object::Name(kind), profile] if kind == b"ICCBased" => {
+                let profile = resolve(document, profile)?.as_stream().ok()?;
+                number(profile.dict.get(b"N").ok()?)? as usize
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let flate = |o: &Object| matches!(o, Object::Name(n) if n == b"FlateDecode");
+
+    let data = match get(b"Filter") {
+        None => stream.content.clone(),
+        Some(filter) if flate(filter) => stream.decompressed_content().ok()?,
+        Some(Object::Array(filters)) if filters.len() == 1 && flate(&filters[0]) => {
+            stream.decompressed_content().ok()?
+        }
+        _ => return None,
+    };
+
+    if !matches!(components, 1 | 3 | 4) || data.len() < width * height * components {
+        return None;
+    }
+
+    let rgb =
+        data.chunks_exact(components)
+            .take(width * height)
+            .flat_map(|p| match p {
+                &[gray] => [gray; 3],
+                &[r, g, b] => [r, g, b],
+                &[c, m, y, k] => cmyk_to_rgb([c, m, y, k].map(|v| v as f64 / 255.))
+                    .map(|v| (v * 255.).round() as u8),
+                _ => unreachable!(),
+            })
+            .collect();
+
+    let alpha = get(b"SMask")
+        .and_then(|o| o.as_stream().ok())
+        .and_then(|mask| decode_image(document, mask))
+        .filter(|mask| mask.width == width && mask.height == height)
+        .map(|mask| mask.rgb.chunks_exact(3).map(|p| p[0]).collect());
+
+    Some(Pixels {
+        width,
+        height,
+        rgb,
+        alpha,
+    })
+}
+
+fn stream_content(stream: &Stream) -> Vec<u8> {
+    stream
+        .decompressed_content()
+        .unwrap_or_else(|_| stream.content.clone())
+}
+
+fn number(object: &Object) -> Option<f64> {
+    match *object {
+        Object::Integer(v) => Some(v as f64),
+        Object::Real(v) => Some(v as f64),
+        _ => None,
+    }
+}
+
+fn numbers(objects: &[Object]) -> Option<Vec<f64>> {
+    objects.iter().map(number).collect()
+}
+
+struct Rasterizer<'a> {
+    document: &'a Document,
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+
+    /// The fonts that were loaded already, by the id of their dictionary.
+    fonts: HashMap<ObjectId, Rc<PdfFont>>,
+}
+
+impl<'a> Rasterizer<'a> {
+    fn run(
+        &mut self,
+        operations: &[Operation],
+        resources: &'a Dictionary,
+        mut state: State,
+        depth: u32,
+    ) {
+        let mut stack = Vec::new();
+        let mut path = Path::default();
+
+        // Whether the path is used for clipping once it's painted, with the even-odd rule.
+        let mut clip = None;
+
+        let mut text_matrix = Matrix::IDENTITY;
+        let mut line_matrix = Matrix::IDENTITY;
+
+        for operation in operations {
+            let operands = &operation.operands[..];
+            let values = numbers(operands).unwrap_or_default();
+            let point = |i: usize| state.ctm.apply((values[i], values[i + 1]));
+
+            match (&operation.operator[..], values.len()) {
+                ("q", _) => stack.push(state.clone()),
+                ("Q", _) => {
+                    if let Some(saved) = stack.pop() {
+                        state = saved;
+                    }
+                }
+                ("cm", 6) => {
+                    if let Some(matrix) = Matrix::from_operands(operands) {
+                        state.ctm = matrix.then(state.ctm);
+                    }
+                }
+                ("w", 1) => state.line_width = values[0],
+                ("gs", _) => {
+                    let Some(Object::Dictionary(parameters)) =
+                        self.resource(resources, b"ExtGState", operands)
+                    else {
+                        continue;
+                    };
+
+                    let parameter = |key: &[u8]| parameters.get(key).ok().and_then(number);
+
+                    if let Some(alpha) = parameter(b"ca") {
+                        state.fill_alpha = alpha;
+                    }
+
+                    if let Some(alpha) = parameter(b"CA") {
+                        state.stroke_alpha = alpha;
+                    }
+
+                    if let Some(width) = parameter(b"LW") {
+                        state.line_width = width;
+                    }
+                }
+
+                ("m", 2) => path.move_to(point(0)),
+                ("l", 2) => path.line_to(point(0)),
+                ("c", 6) => path.curve_to(point(0), point(2), point(4)),
+                ("v", 4) => {
+                    let current = path.current().unwrap_or(point(2));
+                    path.curve_to(current, point(0), point(2));
+                }
+                ("y", 4) => path.curve_to(point(0), point(2), point(2)),
+                ("h", _) => path.close(),
+                ("re", 4) => {
+                    let [x, y, width, height] = [values[0], values[1], values[2], values[3]];
+
+                    path.move_to(state.ctm.apply((x, y)));
+                    path.line_to(state.ctm.apply((x + width, y)));
+                    path.line_to(state.ctm.apply((x + width, y + height)));
+                    path.line_to(state.ctm.apply((x, y + height)));
+                    path.close();
+                }
+
+                ("W", _) => clip = Some(false),
+                ("W*", _) => clip = Some(true),
+
+                (operator @ ("S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" | "n"), _) => {
+                    if matches!(operator, "s" | "b" | "b*") {
+                        path.close();
+                    }
+
+                    if matches!(operator, "f" | "F" | "B" | "b") {
+                        self.fill(
+                            &path.polygons(),
+                            false,
+                            state.fill,
+                            state.fill_alpha,
+                            &state,
+                        );
+                    } else if matches!(operator, "f*" | "B*" | "b*") {
+                        self.fill(&path.polygons(), true, state.fill, state.fill_alpha, &state);
+                    }
+
+                    if matches!(operator, "S" | "s" | "B" | "B*" | "b" | "b*") {
+                        // Zero is the thinnest line the device can draw.
+                        let width = match state.line_width * state.ctm.scale() {
+                            width if width > 0. => width,
+                            _ => 1.,
+                        };
+
+                        let polygons = stroke_polygons(&path.subpaths, width);
+                        self.fill(&polygons, false, state.stroke, state.stroke_alpha, &state);
+                    }
+
+                    if let Some(even_odd) = clip.take() {
+                        self.clip(&path.polygons(), even_odd, &mut state);
+                    }
+
+                    path = Path::default();
+                }
+
+                ("g", 1) => (state.fill_space, state.fill) = (ColorSpace::Gray, [values[0]; 3]),
+                ("G", 1) => (state.stroke_space, state.stroke) = (ColorSpace::Gray, [values[0]; 3]),
+                ("rg", 3) => {
+                    (state.fill_space, state.fill) =
+                        (ColorSpace::Rgb, [values[0], values[1], values[2]])
+                }
+                ("RG", 3) => {
+                    (state.stroke_space, state.stroke) =
+                        (ColorSpace::Rgb, [values[0], values[1], values[2]])
+                }
+                ("k", 4) => {
+                    state.fill_space = ColorSpace::Cmyk;
+                    state.fill = ColorSpace::Cmyk.color(&values).unwrap();
+                }
+                ("K", 4) => {
+                    state.stroke_space = ColorSpace::Cmyk;
+                    state.stroke = ColorSpace::Cmyk.color(&values).unwrap();
+                }
+                ("cs", _) => {
+                    state.fill_space = self.color_space(resources, operands);
+                    state.fill = state.fill_space.initial_color();
+                }
+                ("CS", _) => {
+                    state.stroke_space = self.color_space(resources, operands);
+                    state.stroke = state.stroke_space.initial_color();
+                }
+                ("sc" | "scn", _) => {
+                    let values = numbers(operands).unwrap_or_default();
+
+                    if let Some(color) = state.fill_space.color(&values) {
+                        state.fill = color;
+                    }
+                }
+                ("SC" | "SCN", _) => {
+                    let values = numbers(operands).unwrap_or_default();
+
+                    if let Some(color) = state.stroke_space.color(&values) {
+                        state.stroke = color;
+                    }
+                }
+
+                ("BT", _) => {
+                    text_matrix = Matrix::IDENTITY;
+                    line_matrix = Matrix::IDENTITY;
+                }
+                ("Tf", _) => {
+                    if let [_, size] = operands {
+                        state.font = self.font(resources, operands);
+                        state.font_size = number(size).unwrap_or(0.);
+                    }
+                }
+                ("Tc", 1) => state.char_spacing = values[0],
+                ("Tw", 1) => state.word_spacing = values[0],
+                ("Tz", 1) => state.horizontal_scaling = values[0] / 100.,
+                ("TL", 1) => state.leading = values[0],
+                ("Ts", 1) => state.rise = values[0],
+                ("Tr", 1) => state.render_mode = values[0] as i64,
+                (operator @ ("Td" | "TD"), 2) => {
+                    if operator == "TD" {
+                        state.leading = -values[1];
+                    }
+
+                    line_matrix = Matrix::translate(values[0], values[1]).then(line_matrix);
+                    text_matrix = line_matrix;
+                }
+                ("Tm", 6) => {
+                    if let Some(matrix) = Matrix::from_operands(operands) {
+                        line_matrix = matrix;
+                        text_matrix = matrix;
+                    }
+                }
+                ("T*", _) => {
+                    line_matrix = Matrix::translate(0., -state.leading).then(line_matrix);
+                    text_matrix = line_matrix;
+                }
+                (operator @ ("Tj" | "'" | "\""), _) => {
+                    if operator != "Tj" {
+                        if let [word_spacing, char_spacing, _] = operands {
+                            state.word_spacing = number(word_spacing).unwrap_or(0.);
+                            state.char_spacing = number(char_spacing).unwrap_or(0.);
+                        }
+
+                        line_matrix = Matrix::translate(0., -state.leading).then(line_matrix);
+                        text_matrix = line_matrix;
+                    }
+
+                    if let Some(Object::String(bytes, _)) = operands.last() {
+                        self.show_text(bytes, &state, &mut text_matrix);
+                    }
+                }
+                ("TJ", _) => {
+                    let Some(Object::Array(items)) = operands.first() else {
+                        continue;
+                    };
+
+                    for item in items {
+                        match item {
+                            Object::String(bytes, _) => {
+                                self.show_text(bytes, &state, &mut text_matrix)
+                            }
+                            item => {
+                                let adjustment = number(item).unwrap_or(0.);
+                                let x = -adjustment / 1000.
+                                    * state.font_size
+                                    * state.horizontal_scaling;
+
+                                text_matrix = Matrix::translate(x, 0.).then(text_matrix);
+                            }
+                        }
+                    }
+                }
+
+                ("Do", _) => {
+                    let Some(Object::Stream(stream)) =
+                        self.resource(resources, b"XObject", operands)
+                    else {
+                        continue;
+                    };
+
+                    match stream.dict.get(b"Subtype") {
+                        Ok(Object::Name(n)) if n == b"Image" => self.draw_image(stream, &state),
+                        Ok(Object::Name(n)) if n == b"Form" && depth < MAX_FORM_DEPTH => {
+                            self.draw_form(stream, resources, &state, depth)
+                        }
+                        _ => {}
+                    }
+                }
+
+                _ => {}
+            }
+        }
+    }
+
+    /// The named resource of the category, where the name is the first operand.
+    fn resource(
+        &self,
+        resources: &'a Dictionary,
+        category: &[u8],
+        operands: &[Object],
+    ) -> Option<&'a Object> {
+        let name = operands.first()?.as_name().ok()?;
+
+        let entries = resolve(self.document, resources.get(category).ok()?)?
+            .as_dict()
+            .ok()?;
+
+        resolve(self.document, entries.get(name).ok()?)
+    }
+
+    fn color_space(&self, resources: &'a Dictionary, operands: &[Object]) -> ColorSpace {
+        match operands.first().and_then(|o| o.as_name().ok()) {
+            Some(b"DeviceGray") => return ColorSpace::Gray,
+            Some(b"DeviceRGB") => return ColorSpace::Rgb,
+            Some(b"DeviceCMYK") => return ColorSpace::Cmyk,
+            _ => {}
+        }
+
+        let Some(Object::Array(array)) = self.resource(resources, b"ColorSpace", operands) else {
+            return ColorSpace::Unknown;
+        };
+
+        match &array[..] {
+            [Object::Name(kind), _, alternate, function] if kind == b"Separation" => {
+                let cmyk = matches!(alternate, Object::Name(n) if n == b"DeviceCMYK");
+
+                let function = resolve(self.document, function).and_then(|f| f.as_dict().ok());
+
+                let values = |key: &[u8], default: f64| -> Option<[f64; 4]> {
+                    match function?.get(key) {
+                        Ok(Object::Array(values)) => numbers(values)?.try_into().ok(),
+                        _ => Some([default; 4]),
+                    }
+                };
+
+                match (cmyk, values(b"C0", 0.), values(b"C1", 1.)) {
+                    (true, Some(c0), Some(c1)) => ColorSpace::Separation { c0, c1 },
+                    _ => ColorSpace::Unknown,
+                }
+            }
+            [Object::Name(kind), profile] if kind == b"ICCBased" => {
+                let components = resolve(self.document, profile)
+                    .and_then(|p| p.as_stream().ok())
+                    .and_then(|p| p.dict.get(b"N").ok())
+                    .and_then(number);
+
+                match components.map(|n| n as u32) {
+                    Some(1) => ColorSpace::Gray,
+                    Some(3) => ColorSpace::Rgb,
+                    Some(4) => ColorSpace::Cmyk,
+                    _ => ColorSpace::Unknown,
+                }
+            }
+            _ => ColorSpace::Unknown,
+        }
+    }
+
+    fn font(&mut self, resources: &'a Dictionary, operands: &[Object]) -> Option<Rc<PdfFont>> {
+        let name = operands.first()?.as_name().ok()?;
+
+        let entry = resolve(self.document, resources.get(b"Font").ok()?)?
+            .as_dict()
+            .ok()?
+            .get(name)
+            .ok()?;
+
+        let id = match entry {
+            Object::Reference(id) => *id,
+            entry => return Some(Rc::new(PdfFont::load(self.document, entry.as_dict().ok()?))),
+        };
+
+        if let Some(font) = self.fonts.get(&id) {
+            return Some(font.clone());
+        }
+
+        let font = Rc::new(PdfFont::load(
+            self.document,
+            self.document.get_object(id).ok()?.as_dict().ok()?,
+        ));
+
+        self.fonts.insert(id, font.clone());
+
+        Some(font)
+    }
+
+    fn show_text(&mut self, bytes: &[u8], state: &State, text_matrix: &mut Matrix) {
+        let Some(font) = &state.font else {
+            return;
+        };
+
+        // Modes 3 and 7 don't paint the glyphs.
+        let visible = !matches!(state.render_mode, 3 | 7);
+
+        for code in font.codes(bytes) {
+            if visible {
+                let trm = Matrix([
+                    state.font_size * state.horizontal_scaling,
+                    0.,
+                    0.,
+                    state.font_size,
+                    0.,
+                    state.rise,
+                ])
+                .then(*text_matrix)
+                .then(state.ctm);
+
+                self.fill(
+                    &font.polygons(code, trm),
+                    false,
+                    state.fill,
+                    state.fill_alpha,
+                    state,
+                );
+            }
+
+            let word_spacing = if !font.two_bytes && code == 32 {
+                state.word_spacing
+            } else {
+                0.
+            };
+
+            let advance = (font.width(code) * state.font_size + state.char_spacing + word_spacing)
+                * state.horizontal_scaling;
+
+            *text_matrix = Matrix::translate(advance, 0.).then(*text_matrix);
+        }
+    }
+
+    fn draw_form(
+        &mut self,
+        stream: &'a Stream,
+        resources: &'a Dictionary,
+        state: &State,
+        depth: u32,
+    ) {
+        let Ok(content) = Content::decode(&stream_content(stream)) else {
+            return;
+        };
+
+        let matrix = match stream.dict.get(b"Matrix") {
+            Ok(Object::Array(matrix)) => Matrix::from_operands(matrix),
+            _ => None,
+        };
+
+        let form_resources = stream
+            .dict
+            .get(b"Resources")
+            .ok()
+            .and_then(|o| resolve(self.document, o))
+            .and_then(|o| o.as_dict().ok())
+            .unwrap_or(resources);
+
+        let state = State {
+            ctm: matrix.unwrap_or(Matrix::IDENTITY).then(state.ctm),
+            ..state.clone()
+        };
+
+        self.run(&content.operations, form_resources, state, depth + 1);
+    }
+
+    fn draw_image(&mut self, stream: &Stream, state: &State) {
+        let Some(inverse) = state.ctm.invert() else {
+            return;
+        };
+
+        let pixels = decode_image(self.document, stream);
+
+        let corners = [(0., 0.), (1., 0.), (1., 1.), (0., 1.)].map(|p| state.ctm.apply(p));
+        let xs = corners.map(|c| c.0);
+        let ys = corners.map(|c| c.1);
+
+        let min = |values: [f64; 4]| values.into_iter().fold(f64::INFINITY, f64::min);
+        let max = |values: [f64; 4]| values.into_iter().fold(f64::NEG_INFINITY, f64::max);
+
+        let left = min(xs).floor().max(0.) as usize;
+        let right = max(xs).ceil().clamp(0., self.width as f64) as usize;
+        let top = min(ys).floor().max(0.) as usize;
+        let bottom = max(ys).ceil().clamp(0., self.height as f64) as usize;
+
+        for y in top..bottom {
+            for x in left..right {
+                let (u, v) = inverse.apply((x as f64 + 0.5, y as f64 + 0.5));
+
+                if !(0. ..1.).contains(&u) || !(0. ..1.).contains(&v) {
+                    continue;
+                }
+
+                // Images that can't be decoded are drawn grey.
+                let (color, alpha) = match &pixels {
+                    Some(pixels) => {
+                        let column = ((u * pixels.width as f64) as usize).min(pixels.width - 1);
+                        let row =
+                            (((1. - v) * pixels.height as f64) as usize).min(pixels.height - 1);
+                        let i = row * pixels.width + column;
+
+                        let color = [0, 1, 2].map(|c| pixels.rgb[i * 3 + c] as f64 / 255.);
+                        let alpha = pixels.alpha.as_ref().map_or(1., |a| a[i] as f64 / 255.);
+
+                        (color, alpha)
+                    }
+                    None => ([0.5; 3], 1.),
+                };
+
+                self.blend(x, y, color, (alpha * state.fill_alpha) as f32, state);
+            }
+        }
+    }
+
+    fn fill(
+        &mut self,
+        polygons: &[Vec<(f64, f64)>],
+        even_odd: bool,
+        color: [f64; 3],
+        alpha: f64,
+        state: &State,
+    ) {
+        let Some(coverage) = coverage(polygons, even_odd, self.width, self.height) else {
+            return;
+        };
+
+        for row in 0..coverage.height {
+            for column in 0..coverage.width {
+                let value = coverage.values[row * coverage.width + column].min(1.);
+
+                if value > 0. {
+                    let (x, y) = (coverage.x + column, coverage.y + row);
+                    self.blend(x, y, color, value * alpha as f32, state);
+                }
+            }
+        }
+    }
+
+    fn blend(&mut self, x: usize, y: usize, color: [f64; 3], alpha: f32, state: &State) {
+        let alpha = match &state.clip {
+            Some(clip) => alpha * clip[y * self.width + x],
+            None => alpha,
+        };
+
+        if alpha <= 0. {
+            return;
+        }
+
+        let i = (y * self.width + x) * 3;
+
+        for (pixel, value) in self.pixels[i..i + 3].iter_mut().zip(color) {
+            let value = value.clamp(0., 1.) as f32 * 255.;
+            *pixel = (*pixel as f32 * (1. - alpha) + value * alpha).round() as u8;
+        }
+    }
+
+    fn clip(&self, polygons: &[Vec<(f64, f64)>], even_odd: bool, state: &mut State) {
+        let mut mask = vec![0.; self.width * self.height];
+
+        if let Some(coverage) = coverage(polygons, even_odd, self.width, self.height) {
+            for row in 0..coverage.height {
+                let start = (coverage.y + row) * self.width + coverage.x;
+                let values = &coverage.values[row * coverage.width..(row + 1) * coverage.width];
+
+                for (mask, &value) in mask[start..start + coverage.width].iter_mut().zip(values) {
+                    *mask = value.min(1.);
+                }
+            }
+        }
+
+        if let Some(previous) = &state.clip {
+            for (mask, previous) in mask.iter_mut().zip(previous.iter()) {
+                *mask *= previous;
+            }
+        }
+
+        state.clip = Some(Rc::new(mask));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        build_pdf,
+        elements::{image::ImageElement, rectangle::Rectangle, text::Text},
+        fonts::builtin::BuiltinFont,
+        image::Image,
+    };
+
+    fn rasterize(document: printpdf::PdfDocumentReference) -> Raster {
+        let mut bytes = Vec::new();
+        document
+            .save(&mut std::io::BufWriter::new(&mut bytes))
+            .unwrap();
+
+        let document = Document::load_mem(&bytes).unwrap();
+        rasterize_page(&document, document.get_pages()[&1], 72).unwrap()
+    }
+
+    fn pixel(raster: &Raster, x: usize, y: usize) -> [u8; 3] {
+        let i = (y * raster.width as usize + x) * 3;
+        [raster.pixels[i], raster.pixels[i + 1], raster.pixels[i + 2]]
+    }
+
+    #[test]
+    fn test_coverage() {
+        let square = vec![(1., 1.), (3., 1.), (3., 3.), (1., 3.)];
+        let coverage = coverage(&[square.clone()], false, 4, 4).unwrap();
+
+        assert_eq!((coverage.x, coverage.y), (1, 1));
+        assert_eq!((coverage.width, coverage.height), (2, 2));
+        assert!(coverage.values.iter().all(|&v| (v - 1.).abs() < 1e-6));
+
+        // A hole with the even-odd rule, or with the opposite direction.
+        let inner = vec![(1.5, 1.5), (2.5, 1.5), (2.5, 2.5), (1.5, 2.5)];
+        let mut reversed = inner.clone();
+        reversed.reverse();
+
+        let with_hole = |polygons: &[Vec<(f64, f64)>], even_odd| {
+            let coverage = super::coverage(polygons, even_odd, 4, 4).unwrap();
+            coverage.values.iter().sum::<f32>()
+        };
+
+        assert!((with_hole(&[square.clone(), inner.clone()], true) - 3.).abs() < 1e-6);
+        assert!((with_hole(&[square.clone(), inner], false) - 4.).abs() < 1e-6);
+        assert!((with_hole(&[square, reversed], false) - 3.).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_rasterize_shapes() {
+        let raster = rasterize(build_pdf(
+            "test",
+            (100., 100.),
+            |_| (),
+            |_: &()| Rectangle {
+                size: (20., 20.),
+                fill: Some(0x00_00_ff_ff),
+                outline: Some(LineStyle::solid(2., 0xff_00_00_ff)),
+            },
+        ));
+
+        assert_eq!((raster.width, raster.height), (284, 284));
+
+        assert_eq!(pixel(&raster, 30, 30), [0, 0, 255]);
+        assert_eq!(pixel(&raster, 200, 200), [255, 255, 255]);
+
+        // The outline is centered on the edge of the rectangle, which is 2mm in from the corner.
+        assert_eq!(pixel(&raster, 30, 2), [255, 0, 0]);
+    }
+
+    #[test]
+    fn test_rasterize_text() {
+        fn text(font: &BuiltinFont) -> Text<'_, BuiltinFont> {
+            Text::basic("Preview", font, 20.)
+        }
+
+        let raster = rasterize(build_pdf(
+            "test",
+            (100., 100.),
+            BuiltinFont::helvetica,
+            text,
+        ));
+
+        // The built-in font is drawn as blocks on the first line.
+        let dark = (0..100)
+            .flat_map(|x| (0..25).map(move |y| (x, y)))
+            .filter(|&(x, y)| pixel(&raster, x, y) == [0, 0, 0])
+            .count();
+
+        assert!(dark > 100);
+        assert_eq!(pixel(&raster, 150, 150), [255, 255, 255]);
+    }
+
+    #[test]
+    fn test_rasterize_image() {
+        let image = Image::Pixel(printpdf::image::DynamicImage::ImageRgb8(
+            printpdf::image::ImageBuffer::from_pixel(4, 4, printpdf::image::Rgb([255, 0, 0])),
+        ));
+
+        let raster = rasterize(build_pdf(
+            "test",
+            (100., 100.),
+            |_| (),
+            |_: &()| ImageElement::new(&image),
+        ));
+
+        // The image is stretched to the width of the page.
+        let [r, g, b] = pixel(&raster, 140, 140);
+        assert!(r > 250 && g < 5 && b < 5);
+    }
+}
@@ -0,0 +1,1302 @@
+//! A single entry point from JSON to PDF bytes for environments that can't do anything else, like
+//! WASM in a browser. Fonts are passed in as bytes, since there might not be a file system to load
+//! them from. [serve] renders many documents in a long running process.
+
+use std::{collections::HashMap, io::BufWriter, path::PathBuf, rc::Rc, sync::Arc};
+
+#[cfg(feature = "fs")]
+use std::io::{BufRead, Write};
+
+use serde::{de::DeserializeOwned, Deserialize};
+
+use crate::{
+    annotations::{add_markup_annotations, collect_markup},
+    audit::collect_divergences,
+    direction::{set_direction, Direction},
+    elements::{
+        cached::{mark_drawings, share_drawings},
+        meta::{collect_regions, Region},
+    },
+    facts::{collect_facts, with_facts, Facts},
+    fonts::truetype::{ParsedFont, TruetypeFont},
+    image::{collect_loaded_images, read_file, share_image_xobjects, BaseDir, Image},
+    language::set_language,
+    letterhead::{add_letterheads, load_letterhead},
+    line_rendering::{LineRendering, WithLineRendering},
+    serde_elements::{
+        color,
+        defaults::Defaults,
+        definitions::Definitions,
+        expr::Constants,
+        limits,
+        outline::Outline,
+        page_size::{self, PageSizes},
+        warnings::warn,
+        ElementValue, Font, SerdeElement,
+    },
+    spot_colors::replace_with_spot_colors,
+    structure::{add_structure_tree, collect_tagged_images},
+    text::cache_text_widths,
+    threads::add_threads,
+    BuildElement, CompositeElement, CompositeElementCallback, Element, Limits, SpotColor,
+};
+
+/// How many text widths are cached while a document is built, see [cache_text_widths]. Documents
+/// repeat a lot of labels and every text is measured at least twice, so this is always on. The
+/// widths of the texts that are shaped before layout take up at most half of these.
+const TEXT_WIDTH_CACHE: usize = 10_000;
+
+/// Limits for rendering documents from untrusted input. The defaults are generous for real
+/// documents, but keep a single document from taking up all the stack, memory or time of the
+/// process. `None` turns a limit off.
+///
+/// Documents can lower the limits of the [Renderer] with `"limits":{..}`, but not raise them. The
+/// limits a document leaves out are `None` when deserialized, so they don't lower anything.
+///
+/// The fonts and letterheads a document loads aren't counted, so the size of the files it can load,
+/// see [Renderer::with_base_dir], should be limited too.
+#[derive(Clone, Copy, Debug, PartialEq, Deserialize)]
+pub struct RenderLimits {
+    /// How deep elements can be nested, including the definitions.
+    #[serde(default)]
+    pub max_depth: Option<u32>,
+
+    /// The total number of elements in the document, including the definitions.
+    #[serde(default)]
+    pub max_elements: Option<u32>,
+
+    /// Every document has a page, so zero pages is always exceeded.
+    #[serde(default)]
+    pub max_pages: Option<u32>,
+
+    /// The estimated size of the content streams of all pages in bytes, see [crate::budget].
+    #[serde(default)]
+    pub max_content_bytes: Option<u64>,
+
+    /// The memory of the decoded images in bytes, counted every time an image is drawn.
+    #[serde(default)]
+    pub max_image_memory: Option<u64>,
+}
+
+impl Default for RenderLimits {
+    fn default() -> Self {
+        RenderLimits {
+            max_depth: Some(128),
+            max_elements: Some(100_000),
+            max_pages: Some(10_000),
+            max_content_bytes: Some(512 << 20),
+            max_image_memory: Some(1 << 30),
+        }
+    }
+}
+
+impl RenderLimits {
+    /// The lower of both limits, where a limit that is `None` is the higher one.
+    fn min(self, other: RenderLimits) -> RenderLimits {
+        fn min<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            }
+        }
+
+        RenderLimits {
+            max_depth: min(self.max_depth, other.max_depth),
+            max_elements: min(self.max_elements, other.max_elements),
+            max_pages: min(self.max_pages, other.max_pages),
+            max_content_bytes: min(self.max_content_bytes, other.max_content_bytes),
+            max_image_memory: min(self.max_image_memory, other.max_image_memory),
+        }
+    }
+}
+
+/// The `limits`, `direction`, `constants`, `page_sizes`, `defaults` and `definitions` of a document
+/// are taken out of it first, since they have to be in scope for the rest, see [Renderer::parse].
+#[derive(Deserialize)]
+struct Input {
+    #[serde(default)]
+    title: String,
+
+    /// In mm or a name like `"A4"`.
+    #[serde(deserialize_with = "page_size::deserialize_page_size")]
+    page_size: (f64, f64),
+
+    element: ElementValue,
+
+    /// Paths of TrueType fonts by name, in addition to the ones passed in as bytes.
+    #[serde(default)]
+    fonts: HashMap<String, String>,
+
+    /// Checks that every element draws like it measured and warns about the ones that don't.
+    #[serde(default)]
+    audit: bool,
+
+    #[serde(default)]
+    letterhead: Letterhead,
+
+    /// How the lines without their own rendering are drawn.
+    #[serde(default)]
+    line_rendering: LineRendering,
+
+    /// RGB colors that are replaced with inks, so every element can draw with spot colors.
+    #[serde(default)]
+    spot_colors: Vec<SpotColorInput>,
+
+    /// Also the direction of the [Defaults] if they don't set one.
+    #[serde(skip)]
+    direction: Direction,
+
+    /// The language of the document as a tag like `"de-CH"`, for screen readers. Elements in
+    /// other languages are wrapped in a `Lang`.
+    #[serde(default)]
+    lang: Option<String>,
+
+    /// Wraps the element in a [Debug](crate::elements::debug::Debug) with rulers and baseline
+    /// guides.
+    #[serde(default)]
+    debug: bool,
+
+    /// Measured before layout, see [shaping](crate::serde_elements::shaping).
+    #[cfg(feature = "parallel-shaping")]
+    #[serde(skip)]
+    texts: Vec<crate::serde_elements::shaping::CollectedText>,
+}
+
+/// Like `{"color":"#0033a0","name":"PANTONE 300 C","cmyk":[1,0.44,0,0]}`, see
+/// [replace_with_spot_colors](crate::spot_colors::replace_with_spot_colors).
+#[derive(Deserialize)]
+struct SpotColorInput {
+    #[serde(deserialize_with = "color::deserialize_color")]
+    color: u32,
+
+    #[serde(flatten)]
+    spot: SpotColor,
+}
+
+/// Paths of PDFs whose first page is drawn under the content, see [crate::letterhead].
+#[derive(Default, Deserialize)]
+struct Letterhead {
+    /// For the first page.
+    #[serde(default)]
+    first: Option<String>,
+
+    /// For all the pages after the first one.
+    #[serde(default)]
+    following: Option<String>,
+}
+
+type Fonts = HashMap<String, Font>;
+
+struct Root<'a> {
+    element: ElementValue,
+    fonts: &'a Fonts,
+    line_rendering: LineRendering,
+    debug: bool,
+}
+
+impl<'a> CompositeElement for Root<'a> {
+    fn element(&self, callback: impl CompositeElementCallback) {
+        self.element.element(
+            self.fonts,
+            RootCallback {
+                line_rendering: self.line_rendering,
+                debug: self.debug,
+                callback,
+            },
+        );
+    }
+}
+
+struct RootCallback<C: CompositeElementCallback> {
+    line_rendering: LineRendering,
+    debug: bool,
+    callback: C,
+}
+
+impl<C: CompositeElementCallback> CompositeElementCallback for RootCallback<C> {
+    fn call(self, element: &impl Element) {
+        if self.debug {
+            self.callback.call(&WithLineRendering {
+                rendering: self.line_rendering,
+                element: &element.debug(0).show_rulers().show_baselines(),
+            });
+        } else {
+            self.callback.call(&WithLineRendering {
+                rendering: self.line_rendering,
+                element,
+            });
+        }
+    }
+}
+
+struct BuildRoot(ElementValue, LineRendering, bool);
+
+impl<'a> BuildElement<'a, Fonts> for BuildRoot {
+    type R = Root<'a>;
+
+    fn call(self, fonts: &'a Fonts) -> Root<'a> {
+        Root {
+            element: self.0,
+            fonts,
+            line_rendering: self.1,
+            debug: self.2,
+        }
+    }
+}
+
+/// Renders a document given as JSON with a `page_size` in mm or by name, an `element`, an
+/// optional `title` and optional `fonts` to load from paths. `"letterhead":{"first":..,
+/// "following":..}` are paths of PDFs drawn under the content. The fonts passed in are TrueType
+/// font files by the names the elements use for them.
+///
+/// The element and the page size can use `constants` for expressions, custom `page_sizes` by
+/// name, `defaults` for element fields and `definitions` of elements to reference by name. With
+/// `"direction":"rtl"`, text is aligned to the right, rows are laid out from right to left and
+/// viewers show the pages from right to left. `"lang":"de-CH"` sets the language of the document.
+/// The regions of `Meta` elements whose metadata has a `"thread"` name are linked into an article
+/// thread by that name, in the order they're drawn, see [crate::threads].
+/// `"debug":true` outlines the boxes of the elements and draws rulers and baselines. The document
+/// is rendered within the default [RenderLimits].
+pub fn render_json(input: &str, fonts: &[(&str, &[u8])]) -> Result<Vec<u8>, String> {
+    Renderer::default().render_json(input, fonts)
+}
+
+/// Like [render_json], but also returns the [Region]s of the `Meta` elements and images, for
+/// post-processors that need to find them in the PDF.
+pub fn render_json_with_regions(
+    input: &str,
+    fonts: &[(&str, &[u8])],
+) -> Result<(Vec<u8>, Vec<Region>), String> {
+    Renderer::default().render_json_with_regions(input, fonts)
+}
+
+/// Keeps the fonts loaded from paths parsed in memory, for rendering many documents in a row. The
+/// images of the last document stay decoded as well, so the next one can share them, see
+/// [load_shared](crate::image::load_shared).
+#[derive(Default)]
+pub struct Renderer {
+    fonts: HashMap<String, ParsedFont<Arc<[u8]>>>,
+    images: Vec<Rc<Image>>,
+    limits: RenderLimits,
+    base_dir: Option<BaseDir>,
+
+    #[cfg(feature = "preview")]
+    preview_dpi: Option<u32>,
+}
+
+impl Renderer {
+    /// Replaces the default limits for all the documents.
+    pub fn with_limits(self, limits: RenderLimits) -> Self {
+        Renderer { limits, ..self }
+    }
+
+    /// Only allows fonts, images, CSV files and letterheads to be loaded from relative paths
+    /// inside of `base_dir`, see [BaseDir]. [serve](Renderer::serve) only writes the PDFs and
+    /// previews to relative paths inside of it too.
+    pub fn with_base_dir(self, base_dir: impl Into<PathBuf>) -> Self {
+        Renderer {
+            base_dir: Some(BaseDir(base_dir.into())),
+            ..self
+        }
+    }
+
+    /// Makes [serve](Renderer::serve) also write a PNG preview of every page at the resolution,
+    /// next to the PDF, see [crate::preview].
+    #[cfg(feature = "preview")]
+    pub fn with_preview(self, dpi: u32) -> Self {
+        Renderer {
+            preview_dpi: Some(dpi),
+            ..self
+        }
+    }
+
+    /// Like [render_json], but font files are only read and parsed the first time they're used.
+    pub fn render_json(&mut self, input: &str, fonts: &[(&str, &[u8])]) -> Result<Vec<u8>, String> {
+        self.render_json_with_regions(input, fonts)
+            .map(|(pdf, _)| pdf)
+    }
+
+    /// Like [render_json_with_regions], but font files are only read and parsed the first time
+    /// they're used.
+    pub fn render_json_with_regions(
+        &mut self,
+        input: &str,
+        fonts: &[(&str, &[u8])],
+    ) -> Result<(Vec<u8>, Vec<Region>), String> {
+        let request = serde_json::from_str(input).map_err(|e| e.to_string())?;
+
+        self.with_base_dir_scope(|renderer| {
+            let outline = Outline::default();
+            let (input, limits) = renderer.parse(request, &outline)?;
+
+            renderer.render(input, limits, &outline, fonts, &mut |_| {})
+        })
+    }
+
+    /// Also keeps the images loaded by `f` until the next call, since the ones of the last
+    /// document are only dropped after the next one has loaded its own.
+    fn with_base_dir_scope<R>(&mut self, f: impl FnOnce(&mut Self) -> R) -> R {
+        let (ret, images) = collect_loaded_images(|| match self.base_dir.clone() {
+            Some(base_dir) => base_dir.scope(|| f(self)),
+            None => f(self),
+        });
+
+        self.images = images;
+        ret
+    }
+
+    /// Deserializes the document, with the fields the element depends on in scope.
+    fn parse(
+        &self,
+        mut request: serde_json::Value,
+        outline: &Outline,
+    ) -> Result<(Input, RenderLimits), String> {
+        fn field<T: DeserializeOwned + Default>(
+            request: &mut serde_json::Value,
+            name: &str,
+        ) -> Result<T, String> {
+            let value = match request.as_object_mut().and_then(|r| r.remove(name)) {
+                Some(value) => value,
+                None => return Ok(T::default()),
+            };
+
+            serde_json::from_value(value).map_err(|e| format!("invalid `{name}`: {e}"))
+        }
+
+        let limits = match field(&mut request, "limits")? {
+            Some(limits) => self.limits.min(limits),
+            None => self.limits,
+        };
+        let direction: Option<Direction> = field(&mut request, "direction")?;
+        let constants = Constants(field(&mut request, "constants")?);
+        let page_sizes = PageSizes(field(&mut request, "page_sizes")?);
+
+        let element_limits = limits::Limits {
+            max_depth: limits.max_depth,
+            max_elements: limits.max_elements,
+        };
+
+        let deserialize = || -> Result<Input, String> {
+            element_limits.scope(|| {
+                constants.scope(|| {
+                    page_sizes.scope(|| {
+                        let mut defaults: Defaults = field(&mut request, "defaults")?;
+                        defaults.direction = defaults.direction.or(direction);
+
+                        defaults.scope(|| {
+                            let definitions: Definitions = field(&mut request, "definitions")?;
+
+                            definitions.scope(|| {
+                                outline
+                                    .scope(|| serde_json::from_value(request))
+                                    .map_err(|e| e.to_string())
+                            })
+                        })
+                    })
+                })
+            })
+        };
+
+        #[cfg(feature = "parallel-shaping")]
+        let mut input = {
+            let (input, texts) = crate::serde_elements::shaping::collect_texts(deserialize);
+            Input { texts, ..input? }
+        };
+
+        #[cfg(not(feature = "parallel-shaping"))]
+        let mut input = deserialize()?;
+
+        input.direction = direction.unwrap_or_default();
+
+        Ok((input, limits))
+    }
+
+    fn render(
+        &mut self,
+        input: Input,
+        limits: RenderLimits,
+        outline: &Outline,
+        fonts: &[(&str, &[u8])],
+        on_page: &mut dyn FnMut(u32),
+    ) -> Result<(Vec<u8>, Vec<Region>), String> {
+        let mut parsed_fonts = fonts
+            .iter()
+            .map(|&(name, bytes)| {
+                ParsedFont::new(Arc::<[u8]>::from(bytes))
+                    .map(|font| (name.to_string(), font))
+                    .map_err(|_| format!("invalid font `{name}`"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        for (name, path) in &input.fonts {
+            let font = match self.fonts.get(path) {
+                Some(font) => font.clone(),
+                None => {
+                    let font = ParsedFont::new(Arc::<[u8]>::from(read_file(path)?))
+                        .map_err(|_| format!("invalid font `{name}`"))?;
+                    self.fonts.insert(path.clone(), font.clone());
+                    font
+                }
+            };
+
+            parsed_fonts.push((name.clone(), font));
+        }
+
+        // The fonts are only alive during a build, so each build gets its own cache.
+        let build = |on_page: &mut dyn FnMut(u32)| {
+            let (document, _, exceeded) = cache_text_widths(TEXT_WIDTH_CACHE, || {
+                crate::build(
+                    &input.title,
+                    input.page_size,
+                    |document| {
+                        let fonts = parsed_fonts
+                            .iter()
+                            .map(|(name, parsed)| {
+                                let font = TruetypeFont::from_parsed(document, parsed).unwrap();
+                                (name.clone(), Rc::new(font))
+                            })
+                            .collect::<Fonts>();
+
+                        // Inside of the cache's scope, with the fonts at the addresses layout
+                        // measures them by.
+                        #[cfg(feature = "parallel-shaping")]
+                        crate::serde_elements::shaping::pre_shape(&input.texts, &fonts);
+
+                        fonts
+                    },
+                    BuildRoot(input.element.clone(), input.line_rendering, input.debug),
+                    Limits {
+                        max_pages: limits.max_pages,
+                        max_content_bytes: limits.max_content_bytes,
+                        max_image_memory: limits.max_image_memory,
+                    },
+                    on_page,
+                )
+            });
+
+            match exceeded {
+                Some(error) => Err(error.to_string()),
+                None => Ok(document),
+            }
+        };
+
+        // Page references and facts are only known after layout, so the first layout is just for
+        // finding them.
+        let facts = if outline.has_page_refs() || outline.has_fact_refs() {
+            let (document, facts) = collect_facts(|| build(&mut |_| {}));
+            document?;
+            outline.resolve_pages();
+            facts
+        } else {
+            Facts::default()
+        };
+
+        let build_document = || {
+            with_facts(&facts, || {
+                if input.audit {
+                    let (document, divergences) = collect_divergences(|| build(on_page));
+
+                    for divergence in divergences {
+                        warn(format!("draw doesn't match measure for {divergence}"));
+                    }
+
+                    document
+                } else {
+                    build(on_page)
+                }
+            })
+        };
+
+        let ((((document, marked), annotations), regions), images) = collect_tagged_images(|| {
+            collect_regions(|| collect_markup(|| mark_drawings(build_document)))
+        });
+        let document = document?;
+
+        let mut bytes = Vec::new();
+
+        document
+            .save(&mut BufWriter::new(&mut bytes))
+            .map_err(|e| format!("could not save the document: {e:?}"))?;
+
+        // printpdf can't write any of this, so it's added to the saved document, which is only
+        // loaded once for all of it.
+        let mut document = lopdf::Document::load_mem(&bytes)
+            .map_err(|e| format!("could not load the document: {e:?}"))?;
+
+        if !input.spot_colors.is_empty() {
+            let colors = input
+                .spot_colors
+                .iter()
+                .map(|c| (c.color, c.spot.clone()))
+                .collect::<Vec<_>>();
+
+            replace_with_spot_colors(&mut document, &colors)
+                .map_err(|e| format!("could not add the spot colors: {e:?}"))?;
+        }
+
+        if marked > 0 {
+            share_drawings(&mut document)
+                .map_err(|e| format!("could not share drawings: {e:?}"))?;
+        }
+
+        share_image_xobjects(&mut document);
+
+        if input.direction == Direction::Rtl {
+            set_direction(&mut document, input.direction)
+                .map_err(|e| format!("could not set the direction: {e:?}"))?;
+        }
+
+        if let Some(lang) = &input.lang {
+            set_language(&mut document, lang)
+                .map_err(|e| format!("could not set the language: {e:?}"))?;
+        }
+
+        add_markup_annotations(&mut document, &annotations)
+            .map_err(|e| format!("could not add the markup annotations: {e:?}"))?;
+
+        if !images.is_empty() {
+            add_structure_tree(&mut document, &images)
+                .map_err(|e| format!("could not add the structure tree: {e:?}"))?;
+        }
+
+        let threads = threads(&regions);
+
+        if !threads.is_empty() {
+            add_threads(
+                &mut document,
+                threads.iter().map(|(title, beads)| (*title, &beads[..])),
+            )
+            .map_err(|e| format!("could not add the threads: {e:?}"))?;
+        }
+
+        let Letterhead { first, following } = &input.letterhead;
+
+        if first.is_some() || following.is_some() {
+            let first = first.as_deref().map(load_letterhead).transpose()?;
+            let following = following.as_deref().map(load_letterhead).transpose()?;
+
+            add_letterheads(&mut document, first.as_ref(), following.as_ref())
+                .map_err(|e| format!("could not add the letterhead: {e:?}"))?;
+        }
+
+        let mut bytes = Vec::new();
+
+        document
+            .save_to(&mut bytes)
+            .map_err(|e| format!("could not save the document: {e:?}"))?;
+
+        Ok((bytes, regions))
+    }
+
+    #[cfg(feature = "fs")]
+    fn serve_request(
+        &mut self,
+        request: &str,
+        on_page: &mut dyn FnMut(u32),
+    ) -> Result<(Vec<String>, Vec<Region>), String> {
+        let mut request: serde_json::Value =
+            serde_json::from_str(request).map_err(|e| e.to_string())?;
+
+        let output = match request.as_object_mut().and_then(|r| r.remove("output")) {
+            Some(serde_json::Value::String(output)) => output,
+            _ => return Err("missing field `output`".to_string()),
+        };
+
+        let (result, warnings) = crate::serde_elements::warnings::collect_warnings(|| {
+            self.with_base_dir_scope(|renderer| {
+                let outline = Outline::default();
+                let (input, limits) = renderer.parse(request, &outline)?;
+
+                renderer.render(input, limits, &outline, &[], on_page)
+            })
+        });
+        let (pdf, regions) = result?;
+
+        let output = std::path::Path::new(&output);
+
+        std::fs::write(self.output_path(output)?, &pdf)
+            .map_err(|e| format!("could not write {}: {e}", output.display()))?;
+
+        #[cfg(feature = "preview")]
+        if let Some(dpi) = self.preview_dpi {
+            let pages = crate::preview::render_png(&pdf, dpi)
+                .map_err(|e| format!("could not render the preview: {e}"))?;
+
+            let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+
+            for (i, png) in pages.iter().enumerate() {
+                let path = output.with_file_name(format!("{stem}-{}.png", i + 1));
+
+                std::fs::write(self.output_path(&path)?, png)
+                    .map_err(|e| format!("could not write {}: {e}", path.display()))?;
+            }
+        }
+
+        Ok((warnings, regions))
+    }
+
+    /// Where to write an output file to, checked against the base directory if there is one.
+    #[cfg(feature = "fs")]
+    fn output_path(&self, path: &std::path::Path) -> Result<PathBuf, String> {
+        match &self.base_dir {
+            Some(base_dir) => base_dir.resolve_output(path),
+            None => Ok(path.to_path_buf()),
+        }
+    }
+}
+
+/// The regions with a `"thread"` name in their metadata by name, in the order the names first
+/// appear.
+fn threads(regions: &[Region]) -> Vec<(&str, Vec<Region>)> {
+    let mut threads: Vec<(&str, Vec<Region>)> = Vec::new();
+
+    for region in regions {
+        let Some(title) = region.meta.get("thread").and_then(|t| t.as_str()) else {
+            continue;
+        };
+
+        match threads.iter_mut().find(|(t, _)| *t == title) {
+            Some((_, beads)) => beads.push(region.clone()),
+            None => threads.push((title, vec![region.clone()])),
+        }
+    }
+
+    threads
+}
+
+/// Renders documents from newline delimited JSON until the input ends, keeping the fonts and images
+/// in memory in between, see [Renderer]. Every line is a document like for [render_json] with an
+/// additional `output` path to write the PDF to. For every line, either `{"ok":true}` or
+/// `{"error":"..."}` is written as a line to the output. If there were warnings, like for faked
+/// font faces or for elements that didn't draw like they measured with `"audit":true`, they're
+/// added to the first as `"warnings":["..."]`. The [Region]s of the document are added as
+/// `"regions":[..]` with the `meta`, `page`, `pos` and `size` of each, if there are any.
+///
+/// If there's a `progress` writer, JSON lines are written to it as the work goes on:
+/// `{"document":0,"event":"page","pages":1}` when a page is added to a document and
+/// `{"documents":1,"event":"document"}` with the number of documents finished so far. Documents
+/// are counted from zero in the order of the input lines, including the ones that fail.
+///
+/// With a renderer [with a preview](Renderer::with_preview), the pages of a document written to
+/// `out/invoice.pdf` are also written as `out/invoice-1.png`, `out/invoice-2.png` and so on.
+#[cfg(feature = "fs")]
+pub fn serve(
+    input: impl BufRead,
+    output: impl Write,
+    progress: Option<&mut dyn Write>,
+) -> std::io::Result<()> {
+    Renderer::default().serve(input, output, progress)
+}
+
+#[cfg(feature = "fs")]
+impl Renderer {
+    /// Like [serve], but with the limits and base directory of this renderer.
+    pub fn serve(
+        mut self,
+        input: impl BufRead,
+        mut output: impl Write,
+        mut progress: Option<&mut dyn Write>,
+    ) -> std::io::Result<()> {
+        let mut documents = 0;
+
+        for line in input.lines() {
+            let line = line?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            // The callback can't return errors, so the first one is kept for after the document.
+            let mut progress_result = Ok(());
+
+            let result = self.serve_request(&line, &mut |pages| {
+                if let (Some(progress), true) = (&mut progress, progress_result.is_ok()) {
+                    let event = serde_json::json!({
+                        "event": "page",
+                        "document": documents,
+                        "pages": pages,
+                    });
+
+                    progress_result = writeln!(progress, "{event}").and_then(|()| progress.flush());
+                }
+            });
+
+            progress_result?;
+
+            let response = match result {
+                Ok((warnings, regions)) => {
+                    let mut response = serde_json::json!({ "ok": true });
+
+                    if !warnings.is_empty() {
+                        response["warnings"] = serde_json::json!(warnings);
+                    }
+
+                    if !regions.is_empty() {
+                        response["regions"] = serde_json::json!(regions);
+                    }
+
+                    response
+                }
+                Err(error) => serde_json::json!({ "error": error }),
+            };
+
+            writeln!(output, "{response}")?;
+            output.flush()?;
+
+            documents += 1;
+
+            if let Some(progress) = &mut progress {
+                let event = serde_json::json!({ "event": "document", "documents": documents });
+                writeln!(progress, "{event}")?;
+                progress.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde_elements::warnings::collect_warnings;
+
+    #[test]
+    fn test_render_json() {
+        let input = r#"{ "page_size": [210, 297], "element": { "VGap": { "gap": 10 } } }"#;
+
+        let pdf = render_json(input, &[]).unwrap();
+        assert!(pdf.starts_with(b"%PDF"));
+
+        assert!(render_json(input, &[("font", &b"not a font"[..])]).is_err());
+
+        let input = r#"{ "page_size": "A4 landscape", "element": { "VGap": { "gap": 10 } } }"#;
+        assert!(render_json(input, &[]).is_ok());
+        assert!(render_json("{}", &[]).is_err());
+
+        let input = r#"{
+            "page_size": "A4",
+            "audit": true,
+            "element": {
+                "Padding": {
+                    "left": 5, "right": 5, "top": 5, "bottom": 5,
+                    "element": { "VGap": { "gap": 10 } }
+                }
+            }
+        }"#;
+        let (pdf, warnings) = collect_warnings(|| render_json(input, &[]));
+        assert!(pdf.is_ok());
+        assert_eq!(warnings, Vec::<String>::new());
+    }
+
+    #[test]
+    fn test_line_rendering() {
+        let input = r##"{
+            "page_size": "A4",
+            "line_rendering": "Snap",
+            "element": { "Column": {
+                "content": [
+                    { "Line": { "style": {
+                        "thickness": 0.2, "color": "#000000", "dash_pattern": null,
+                        "cap_style": "Butt"
+                    } } },
+                    { "Line": { "style": {
+                        "thickness": 0.2, "color": "#000000", "dash_pattern": null,
+                        "cap_style": "Butt", "rendering": "Hairline"
+                    } } }
+                ],
+                "gap": 5
+            } }
+        }"##;
+
+        assert!(render_json(input, &[]).is_ok());
+    }
+
+    #[test]
+    fn test_scoped_fields() {
+        let input = r##"{
+            "page_size": ["width", "width * 1.5"],
+            "constants": { "width": 100 },
+            "defaults": { "line_style": {
+                "thickness": 0.2, "color": "#000000", "dash_pattern": null, "cap_style": "Butt"
+            } },
+            "definitions": { "rule": { "Line": {} } },
+            "element": { "Column": {
+                "content": [{ "Ref": { "name": "rule" } }, { "VGap": { "gap": "width / 10" } }],
+                "gap": 5
+            } }
+        }"##;
+
+        assert!(render_json(input, &[]).is_ok());
+
+        let input = r#"{
+            "page_size": "Card",
+            "page_sizes": { "Card": [85, 55] },
+            "element": { "VGap": { "gap": 10 } }
+        }"#;
+
+        assert!(render_json(input, &[]).is_ok());
+    }
+
+    #[test]
+    fn test_limits() {
+        let document = |limits: &str| {
+            format!(
+                r#"{{
+                    "page_size": [100, 100],
+                    "limits": {limits},
+                    "element": {{ "Column": {{
+                        "content": [{{ "VGap": {{ "gap": 80 }} }}, {{ "VGap": {{ "gap": 80 }} }}],
+                        "gap": 0
+                    }} }}
+                }}"#
+            )
+        };
+
+        assert!(render_json(&document("{}"), &[]).is_ok());
+        assert!(render_json(&document(r#"{ "max_pages": 2 }"#), &[]).is_ok());
+        assert!(render_json(&document(r#"{ "max_pages": 1 }"#), &[]).is_err());
+        assert!(render_json(&document(r#"{ "max_pages": 0 }"#), &[]).is_err());
+        assert!(render_json(&document(r#"{ "max_elements": 2 }"#), &[]).is_err());
+        assert!(render_json(&document(r#"{ "max_depth": 1 }"#), &[]).is_err());
+
+        // Documents can't raise the limits of the renderer.
+        let mut renderer = Renderer::default().with_limits(RenderLimits {
+            max_pages: Some(1),
+            ..Default::default()
+        });
+        assert!(renderer
+            .render_json(&document(r#"{ "max_pages": 5 }"#), &[])
+            .is_err());
+
+        let mut renderer = Renderer::default().with_limits(RenderLimits {
+            max_depth: None,
+            max_elements: None,
+            max_pages: None,
+            max_content_bytes: None,
+            max_image_memory: None,
+        });
+        assert!(renderer.render_json(&document("null"), &[]).is_ok());
+
+        let rectangle = |limits: &str| {
+            format!(
+                r#"{{
+                    "page_size": [100, 100],
+                    "limits": {limits},
+                    "element": {{ "Rectangle": {{ "size": [10, 10], "fill": "#0033a0" }} }}
+                }}"#
+            )
+        };
+
+        assert!(render_json(&rectangle(r#"{ "max_content_bytes": 1000 }"#), &[]).is_ok());
+        assert_eq!(
+            render_json(&rectangle(r#"{ "max_content_bytes": 10 }"#), &[]).unwrap_err(),
+            "the content of the document is larger than 10 bytes",
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_base_dir() {
+        let dir = std::env::temp_dir().join(format!("laser-pdf-render-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let input = |path: &str| {
+            serde_json::json!({
+                "page_size": "A4",
+                "fonts": { "font": path },
+                "element": { "VGap": { "gap": 10 } },
+            })
+            .to_string()
+        };
+
+        let mut renderer = Renderer::default().with_base_dir(&dir);
+
+        let error = renderer
+            .render_json(&input("../font.ttf"), &[])
+            .unwrap_err();
+        assert!(error.contains("inside of the base directory"), "{error}");
+
+        let error = renderer
+            .render_json(&input("/etc/passwd"), &[])
+            .unwrap_err();
+        assert!(error.contains("inside of the base directory"), "{error}");
+
+        std::fs::write(dir.join("font.ttf"), "not a font").unwrap();
+        let error = renderer.render_json(&input("font.ttf"), &[]).unwrap_err();
+        assert_eq!(error, "invalid font `font`");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_kept_images() {
+        let dir = std::env::temp_dir().join(format!("laser-pdf-kept-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let svg = r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"/>"#;
+        std::fs::write(dir.join("logo.svg"), svg).unwrap();
+
+        let input = serde_json::json!({
+            "page_size": "A4",
+            "element": { "Image": { "path": "logo.svg" } },
+        })
+        .to_string();
+
+        let mut renderer = Renderer::default().with_base_dir(&dir);
+
+        renderer.render_json(&input, &[]).unwrap();
+        let first = Rc::downgrade(&renderer.images[0]);
+
+        renderer.render_json(&input, &[]).unwrap();
+        assert!(Rc::ptr_eq(&first.upgrade().unwrap(), &renderer.images[0]));
+        assert_eq!(renderer.images.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cached_forms() {
+        use lopdf::Object;
+
+        let gaps = vec![serde_json::json!({ "VGap": { "gap": 20 } }); 10];
+
+        let input = serde_json::json!({
+            "page_size": [100, 100],
+            "element": { "RepeatAfterBreak": {
+                "title": { "Cached": { "element": {
+                    "Rectangle": { "size": [50, 10], "fill": "#0000ff" }
+                } } },
+                "content": { "Column": { "content": gaps, "gap": 0 } },
+                "gap": 0
+            } }
+        });
+
+        let pdf = render_json(&input.to_string(), &[]).unwrap();
+        let document = lopdf::Document::load_mem(&pdf).unwrap();
+
+        let forms = document
+            .objects
+            .values()
+            .filter_map(|o| o.as_stream().ok())
+            .filter(|s| matches!(s.dict.get(b"Subtype"), Ok(Object::Name(n)) if n == b"Form"))
+            .count();
+
+        assert!(document.get_pages().len() > 1);
+        assert_eq!(forms, 1);
+    }
+
+    #[test]
+    fn test_cached_breakable() {
+        let gaps = vec![serde_json::json!({ "VGap": { "gap": 20 } }); 10];
+        let column = serde_json::json!({ "Column": { "content": gaps, "gap": 0 } });
+
+        let pages = |element: serde_json::Value| {
+            let input = serde_json::json!({
+                "page_size": [100, 100],
+                "element": { "Column": { "content": [
+                    { "VGap": { "gap": 30 } },
+                    element,
+                ], "gap": 0 } },
+            });
+
+            let pdf = render_json(&input.to_string(), &[]).unwrap();
+            crate::test_utils::page_operations(&pdf).len()
+        };
+
+        // The remembered breakable measurements lay out the column like it's laid out without them.
+        let cached = pages(serde_json::json!({ "Cached": { "element": column.clone() } }));
+        assert_eq!(cached, pages(column));
+        assert!(cached > 1);
+    }
+
+    #[test]
+    fn test_spot_colors() {
+        let input = r##"{
+            "page_size": "A4",
+            "spot_colors": [
+                { "color": "#0033a0", "name": "PANTONE 300 C", "cmyk": [1, 0.44, 0, 0] }
+            ],
+            "element": { "Rectangle": { "size": [10, 10], "fill": "#0033a0" } }
+        }"##;
+
+        let pdf = render_json(input, &[]).unwrap();
+        let operations = &crate::test_utils::page_operations(&pdf)[0];
+
+        assert!(operations.iter().any(|o| o.operator == "scn"));
+        assert!(!operations.iter().any(|o| o.operator == "rg"));
+    }
+
+    #[test]
+    fn test_direction() {
+        let input = |direction: &str| {
+            serde_json::json!({
+                "page_size": "A4",
+                "direction": direction,
+                "element": { "VGap": { "gap": 10 } },
+            })
+            .to_string()
+        };
+
+        assert!(render_json(&input("ltr"), &[]).is_ok());
+
+        let rtl = render_json(&input("rtl"), &[]).unwrap();
+        let document = lopdf::Document::load_mem(&rtl).unwrap();
+        let root = document
+            .trailer
+            .get(b"Root")
+            .unwrap()
+            .as_reference()
+            .unwrap();
+        let catalog = document.get_object(root).unwrap().as_dict().unwrap();
+        let preferences = catalog
+            .get(b"ViewerPreferences")
+            .unwrap()
+            .as_dict()
+            .unwrap();
+
+        assert_eq!(
+            preferences.get(b"Direction").unwrap().as_name().unwrap(),
+            b"R2L"
+        );
+    }
+
+    #[test]
+    fn test_debug() {
+        let input = |debug: bool| {
+            serde_json::json!({
+                "page_size": "A4",
+                "debug": debug,
+                "element": { "Rectangle": { "size": [5, 10] } },
+            })
+            .to_string()
+        };
+
+        let plain = render_json(&input(false), &[]).unwrap();
+        let debug = render_json(&input(true), &[]).unwrap();
+
+        // The outline and the rulers of the rectangle.
+        assert!(debug.len() > plain.len());
+
+        let lines = |pdf: &[u8]| {
+            crate::test_utils::page_operations(pdf)[0]
+                .iter()
+                .filter(|op| op.operator == "l")
+                .count()
+        };
+
+        // The rectangle is at the top left corner of the page, so the ruler along the top edge gets
+        // a tick for every mm from 0 to 5 and the one along the left edge from 0 to 10. The
+        // outline is a closed path of line segments on top of that.
+        assert_eq!(lines(&plain), 0);
+        assert!(lines(&debug) > 6 + 11);
+    }
+
+    #[test]
+    fn test_lang() {
+        let input =
+            r#"{ "page_size": "A4", "lang": "de-CH", "element": { "VGap": { "gap": 10 } } }"#;
+
+        let pdf = render_json(input, &[]).unwrap();
+        let document = lopdf::Document::load_mem(&pdf).unwrap();
+        let root = document
+            .trailer
+            .get(b"Root")
+            .unwrap()
+            .as_reference()
+            .unwrap();
+        let catalog = document.get_object(root).unwrap().as_dict().unwrap();
+
+        assert_eq!(catalog.get(b"Lang").unwrap().as_str().unwrap(), b"de-CH");
+
+        let input = serde_json::json!({
+            "page_size": "A4",
+            "lang": "de-CH",
+            "element": { "Lang": { "lang": "fr", "element": { "VGap": { "gap": 10 } } } },
+        });
+
+        let pdf = render_json(&input.to_string(), &[]).unwrap();
+        let operations = &crate::test_utils::page_operations(&pdf)[0];
+
+        assert!(operations.iter().any(|o| o.operator == "BDC"
+            && o.operands[1]
+                .as_dict()
+                .and_then(|p| p.get(b"Lang"))
+                .and_then(lopdf::Object::as_str)
+                .ok()
+                == Some(&b"fr"[..])));
+    }
+
+    #[test]
+    fn test_missing_letterhead() {
+        let input = r#"{
+            "page_size": "A4",
+            "letterhead": { "following": "letterhead.pdf" },
+            "element": { "VGap": { "gap": 10 } }
+        }"#;
+
+        assert!(render_json(input, &[]).is_err());
+    }
+
+    #[test]
+    fn test_regions() {
+        let meta = |meta: serde_json::Value| {
+            serde_json::json!({ "Meta": {
+                "meta": meta,
+                "element": { "Rectangle": { "size": [10, 20] } },
+            } })
+        };
+
+        let input = serde_json::json!({
+            "page_size": [100, 100],
+            "element": { "Column": { "content": [
+                meta(serde_json::json!({ "field": "total", "thread": "article" })),
+                meta(serde_json::json!({ "thread": "article" })),
+                { "VGap": { "gap": 10 } },
+            ], "gap": 0 } },
+        });
+
+        let (pdf, regions) = render_json_with_regions(&input.to_string(), &[]).unwrap();
+
+        assert_eq!(
+            regions.iter().map(|r| (r.page, r.size)).collect::<Vec<_>>(),
+            [(0, (10., 20.)), (0, (10., 20.))],
+        );
+        assert_eq!(regions[0].meta["field"], "total");
+        assert_eq!(regions[0].pos.1 - regions[1].pos.1, 20.);
+
+        // Both regions are beads of the same thread.
+        let document = lopdf::Document::load_mem(&pdf).unwrap();
+        let root = document
+            .trailer
+            .get(b"Root")
+            .unwrap()
+            .as_reference()
+            .unwrap();
+        let catalog = document.get_object(root).unwrap().as_dict().unwrap();
+        let threads = catalog.get(b"Threads").unwrap().as_array().unwrap();
+        assert_eq!(threads.len(), 1);
+
+        let beads = document
+            .objects
+            .values()
+            .filter_map(|o| o.as_dict().ok())
+            .filter(|d| matches!(d.get(b"Type"), Ok(lopdf::Object::Name(n)) if n == b"Bead"))
+            .count();
+        assert_eq!(beads, 2);
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_serve() {
+        let output =
+            std::env::temp_dir().join(format!("laser-pdf-serve-{}.pdf", std::process::id()));
+
+        let input = format!(
+            "{}\n\n{{}}\n",
+            serde_json::json!({
+                "output": output,
+                "page_size": [210, 297],
+                "element": { "VGap": { "gap": 10 } },
+            })
+        );
+
+        let mut responses = Vec::new();
+        let mut progress = Vec::new();
+        serve(input.as_bytes(), &mut responses, Some(&mut progress)).unwrap();
+
+        let responses = String::from_utf8(responses).unwrap();
+        let responses = responses.lines().collect::<Vec<_>>();
+
+        assert_eq!(
+            String::from_utf8(progress).unwrap(),
+            concat!(
+                "{\"document\":0,\"event\":\"page\",\"pages\":1}\n",
+                "{\"documents\":1,\"event\":\"document\"}\n",
+                "{\"documents\":2,\"event\":\"document\"}\n",
+            ),
+        );
+
+        assert_eq!(responses[0], r#"{"ok":true}"#);
+        assert!(responses[1].starts_with(r#"{"error":"#));
+        assert_eq!(responses.len(), 2);
+
+        assert!(std::fs::read(&output).unwrap().starts_with(b"%PDF"));
+        std::fs::remove_file(&output).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_serve_base_dir() {
+        let dir = std::env::temp_dir().join(format!("laser-pdf-serve-dir-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("inside")).unwrap();
+
+        let input = [
+            "out.pdf".into(),
+            "../out.pdf".into(),
+            dir.join("out.pdf").display().to_string(),
+        ]
+        .map(|output| {
+            serde_json::json!({
+                "output": output,
+                "page_size": [210, 297],
+                "element": { "VGap": { "gap": 10 } },
+            })
+            .to_string()
+        })
+        .join("\n");
+
+        let mut responses = Vec::new();
+        Renderer::default()
+            .with_base_dir(dir.join("inside"))
+            .serve(input.as_bytes(), &mut responses, None)
+            .unwrap();
+
+        let responses = String::from_utf8(responses).unwrap();
+        let responses = responses.lines().collect::<Vec<_>>();
+
+        assert_eq!(responses[0], r#"{"ok":true}"#);
+        assert!(responses[1].contains("inside of the base directory"));
+        assert!(responses[2].contains("inside of the base directory"));
+
+        assert!(dir.join("inside/out.pdf").exists());
+        assert!(!dir.join("out.pdf").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    #[cfg(all(feature = "fs", feature = "preview"))]
+    fn test_serve_preview() {
+        let dir = std::env::temp_dir().join(format!("laser-pdf-preview-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let input = serde_json::json!({
+            "output": dir.join("document.pdf"),
+            "page_size": [100, 100],
+            "element": { "Column": { "content": [
+                { "VGap": { "gap": 80 } },
+                { "VGap": { "gap": 80 } },
+            ], "gap": 0 } },
+        })
+        .to_string();
+
+        let mut responses = Vec::new();
+        Renderer::default()
+            .with_preview(36)
+            .serve(input.as_bytes(), &mut responses, None)
+            .unwrap();
+
+        assert_eq!(String::from_utf8(responses).unwrap(), "{\"ok\":true}\n");
+
+        for page in ["document-1.png", "document-2.png"] {
+            let raster = crate::preview::Raster::read_png(dir.join(page)).unwrap();
+            assert_eq!((raster.width, raster.height), (142, 142));
+        }
+
+        assert!(!dir.join("document-3.png").exists());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
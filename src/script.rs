@@ -0,0 +1,141 @@
+//! Scripts that need more than mapping characters to glyphs one by one. There's no shaping engine
+//! here, so text in these scripts is drawn character by character: Arabic letters aren't joined,
+//! Devanagari has no conjuncts or reordered vowel signs. [complex_scripts] finds such text, so it
+//! can at least be reported instead of silently looking wrong.
+//!
+//! Thai doesn't have spaces between words, so lines can only be broken where there are zero width
+//! spaces. [insert_word_breaks] adds them between the words of a dictionary.
+
+use serde::{Deserialize, Serialize};
+
+/// Lines can be broken at this character, which is otherwise invisible.
+pub const ZERO_WIDTH_SPACE: char = '\u{200b}';
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Script {
+    Arabic,
+    Devanagari,
+    Thai,
+}
+
+impl Script {
+    /// The complex script of the character, if any.
+    pub fn of(c: char) -> Option<Script> {
+        match c {
+            '\u{0600}'..='\u{06ff}'
+            | '\u{0750}'..='\u{077f}'
+            | '\u{08a0}'..='\u{08ff}'
+            | '\u{fb50}'..='\u{fdff}'
+            | '\u{fe70}'..='\u{feff}' => Some(Script::Arabic),
+            '\u{0900}'..='\u{097f}' | '\u{a8e0}'..='\u{a8ff}' => Some(Script::Devanagari),
+            '\u{0e00}'..='\u{0e7f}' => Some(Script::Thai),
+            _ => None,
+        }
+    }
+
+    /// Whether text in the script is drawn correctly without shaping.
+    pub fn is_supported(self) -> bool {
+        match self {
+            // Thai needs some marks positioned, but most fonts handle that without shaping.
+            Script::Thai => true,
+            Script::Arabic | Script::Devanagari => false,
+        }
+    }
+}
+
+/// The complex scripts used in the text, in the order they first appear.
+pub fn complex_scripts(text: &str) -> Vec<Script> {
+    let mut scripts = Vec::new();
+
+    for script in text.chars().filter_map(Script::of) {
+        if !scripts.contains(&script) {
+            scripts.push(script);
+        }
+    }
+
+    scripts
+}
+
+/// Inserts zero width spaces between the words of Thai text, so lines can be broken there. At
+/// every position the longest word of the dictionary is taken. Characters that don't start a word
+/// of the dictionary are kept together with the ones after them.
+pub fn insert_word_breaks(text: &str, is_word: impl Fn(&str) -> bool) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    // Whether the last thing added was a Thai word, so another one needs a break before it.
+    let mut after_word = false;
+
+    while let Some(c) = rest.chars().next() {
+        if Script::of(c) != Some(Script::Thai) {
+            result.push(c);
+            rest = &rest[c.len_utf8()..];
+            after_word = false;
+            continue;
+        }
+
+        let thai_len = rest
+            .char_indices()
+            .find(|&(_, c)| Script::of(c) != Some(Script::Thai))
+            .map_or(rest.len(), |(i, _)| i);
+
+        let word_len = rest[..thai_len]
+            .char_indices()
+            .map(|(i, c)| i + c.len_utf8())
+            .filter(|&end| is_word(&rest[..end]))
+            .last();
+
+        match word_len {
+            Some(len) => {
+                if after_word {
+                    result.push(ZERO_WIDTH_SPACE);
+                }
+
+                result.push_str(&rest[..len]);
+                rest = &rest[len..];
+                after_word = true;
+            }
+            None => {
+                result.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complex_scripts() {
+        assert_eq!(complex_scripts("plain text"), []);
+        assert_eq!(
+            complex_scripts("नमस्ते and مرحبا and नमस्ते"),
+            [Script::Devanagari, Script::Arabic]
+        );
+        assert_eq!(complex_scripts("สวัสดี"), [Script::Thai]);
+
+        assert!(!Script::Arabic.is_supported());
+        assert!(Script::Thai.is_supported());
+    }
+
+    #[test]
+    fn test_insert_word_breaks() {
+        let dictionary = ["ภาษา", "ไทย", "ง่าย", "ภา"];
+        let is_word = |w: &str| dictionary.contains(&w);
+
+        assert_eq!(
+            insert_word_breaks("ภาษาไทยง่าย", is_word),
+            "ภาษา\u{200b}ไทย\u{200b}ง่าย"
+        );
+
+        // Unknown characters stick to the word before them and text in other scripts is kept.
+        assert_eq!(
+            insert_word_breaks("ไทยกง่าย (ok)", is_word),
+            "ไทยก\u{200b}ง่าย (ok)"
+        );
+    }
+}
@@ -1,11 +1,33 @@
+pub mod color;
+pub mod csv_table;
+pub mod defaults;
+pub mod definitions;
 pub mod elements;
+pub mod expr;
+pub mod format;
+pub mod limits;
+pub mod outline;
+pub mod page_size;
+pub mod registry;
+pub mod shaping;
+pub mod warnings;
 
 use std::{ops::Index, rc::Rc};
 
-use crate::{fonts::truetype::TruetypeFont, CompositeElement, CompositeElementCallback};
+use crate::{
+    elements::audit::Audit, fonts::truetype::TruetypeFont, CompositeElement,
+    CompositeElementCallback, Element,
+};
+use csv_table::CsvTable;
+use definitions::Ref;
 use elements::*;
+use format::{Formatted, UnitValue};
+use outline::{Count, Figure, NumberRef, Numbered, RefText};
+use registry::Custom;
 
-pub type Font = Rc<TruetypeFont<Vec<u8>>>;
+/// The bytes are shared, so a font that's parsed once can be added to many documents, see
+/// [ParsedFont](crate::fonts::truetype::ParsedFont).
+pub type Font = Rc<TruetypeFont<std::sync::Arc<[u8]>>>;
 
 pub trait SerdeElement {
     fn element(
@@ -13,6 +35,16 @@ pub trait SerdeElement {
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     );
+
+    /// The weight of the element when it's a [VExpand] inside of a [Column].
+    fn v_expand_weight(&self) -> Option<u8> {
+        None
+    }
+
+    /// The thickness of the border around the element, for the border collapse of a [Column].
+    fn outline_thickness(&self) -> Option<f64> {
+        None
+    }
 }
 
 pub struct SerdeElementElement<'a, E: SerdeElement, F: for<'b> Index<&'b str, Output = Font>> {
@@ -28,23 +60,76 @@ impl<'a, E: SerdeElement, F: for<'b> Index<&'b str, Output = Font>> CompositeEle
     }
 }
 
+/// Wraps the element in an [Audit] with the name of its type as the id, so every element of a
+/// document is checked while divergences are being collected.
+pub struct AuditCallback<C: CompositeElementCallback> {
+    pub id: &'static str,
+    pub callback: C,
+}
+
+impl<C: CompositeElementCallback> CompositeElementCallback for AuditCallback<C> {
+    fn call(self, element: &impl Element) {
+        self.callback.call(&Audit {
+            id: self.id,
+            element,
+        });
+    }
+}
+
 #[macro_export]
 macro_rules! define_serde_element_value {
     ($enum_name:ident {$($type:ident $(<$($rest:ident),*>)*),*,}) => {
         #[derive(Clone, serde::Deserialize)]
+        #[serde(remote = "Self")]
         pub enum $enum_name {
             $($type ($type $(<$($rest)*>)*)),*
         }
 
+        impl<'de> serde::Deserialize<'de> for $enum_name {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let _enter = $crate::serde_elements::limits::enter()?;
+
+                // The derived implementation, because of `remote = "Self"`.
+                $enum_name::deserialize(deserializer)
+            }
+        }
+
         impl $crate::serde_elements::SerdeElement for $enum_name {
             fn element(
                 &self,
                 fonts: &impl for<'a> core::ops::Index<&'a str, Output = $crate::serde_elements::Font>,
                 callback: impl $crate::CompositeElementCallback,
             ) {
+                if !$crate::audit::auditing() {
+                    return match self {
+                        $($enum_name::$type(ref val) => $crate::serde_elements::SerdeElement
+                            ::element(val, fonts, callback)),*
+                    };
+                }
+
+                match self {
+                    $($enum_name::$type(ref val) => $crate::serde_elements::SerdeElement::element(
+                        val,
+                        fonts,
+                        $crate::serde_elements::AuditCallback {
+                            id: stringify!($type),
+                            callback,
+                        },
+                    )),*
+                }
+            }
+
+            fn v_expand_weight(&self) -> Option<u8> {
+                match self {
+                    $($enum_name::$type(ref val) => $crate::serde_elements::SerdeElement
+                        ::v_expand_weight(val)),*
+                }
+            }
+
+            fn outline_thickness(&self) -> Option<f64> {
                 match self {
                     $($enum_name::$type(ref val) => $crate::serde_elements::SerdeElement
-                        ::element(val, fonts, callback)),*
+                        ::outline_thickness(val)),*
                 }
             }
         }
@@ -56,19 +141,33 @@ define_serde_element_value!(ElementValue {
     Debug<ElementValue>,
     Text,
     RichText,
+    Code,
     VGap,
     HAlign<ElementValue>,
     Padding<ElementValue>,
     StyledBox<ElementValue>,
     Line,
+    PolyLine,
     Image,
+    Figure,
     Rectangle,
     Circle,
+    Ellipse,
+    Polygon,
+    Arc,
+    SignatureLine,
+    CalendarMonth<ElementValue>,
+    Symbol,
+    StarRating,
     Column<ElementValue>,
+    Paginate<ElementValue>,
     Row<ElementValue>,
     BreakList<ElementValue>,
     Stack<ElementValue>,
     TableRow<ElementValue>,
+    TableGroup<ElementValue>,
+    EqualRows<ElementValue>,
+    CsvTable,
     Titled<ElementValue>,
     TitleOrBreak<ElementValue>,
     RepeatAfterBreak<ElementValue>,
@@ -76,10 +175,30 @@ define_serde_element_value!(ElementValue {
     PinBelow<ElementValue>,
     ForceBreak,
     BreakWhole<ElementValue>,
+    MultiColumn<ElementValue>,
     MinFirstHeight<ElementValue>,
+    MinBreakHeight<ElementValue>,
     AlignLocationBottom<ElementValue>,
     AlignPreferredHeightBottom<ElementValue>,
     ExpandToPreferredHeight<ElementValue>,
+    FillRemaining<ElementValue>,
+    FadeOut<ElementValue>,
+    ChangeBar<ElementValue>,
+    VExpand<ElementValue>,
+    Continued<ElementValue>,
     ShrinkToFit<ElementValue>,
+    BalanceLastPage<ElementValue>,
     Rotate<ElementValue>,
+    Trace<ElementValue>,
+    Cached<ElementValue>,
+    Meta<ElementValue>,
+    Lang<ElementValue>,
+    Numbered<ElementValue>,
+    NumberRef,
+    RefText,
+    Count,
+    Formatted,
+    UnitValue,
+    Ref,
+    Custom,
 });
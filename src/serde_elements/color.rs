@@ -0,0 +1,384 @@
+//! Colors in the serde model are `0xRRGGBBAA` integers. For convenience they can also be given as
+//! strings in one of the following forms:
+//!
+//! - `"#RGB"`, `"#RRGGBB"` or `"#RRGGBBAA"`
+//! - `"rgb(255, 0, 0)"` or `"rgba(255, 0, 0, 0.5)"`
+//! - a CSS named color like `"rebeccapurple"` or `"transparent"`
+
+use std::fmt;
+
+use serde::{
+    de::{Error, Visitor},
+    Deserialize, Deserializer,
+};
+
+use crate::LineStyle;
+
+/// Sorted by name so that it can be binary searched.
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xf0f8ffff),
+    ("antiquewhite", 0xfaebd7ff),
+    ("aqua", 0x00ffffff),
+    ("aquamarine", 0x7fffd4ff),
+    ("azure", 0xf0ffffff),
+    ("beige", 0xf5f5dcff),
+    ("bisque", 0xffe4c4ff),
+    ("black", 0x000000ff),
+    ("blanchedalmond", 0xffebcdff),
+    ("blue", 0x0000ffff),
+    ("blueviolet", 0x8a2be2ff),
+    ("brown", 0xa52a2aff),
+    ("burlywood", 0xdeb887ff),
+    ("cadetblue", 0x5f9ea0ff),
+    ("chartreuse", 0x7fff00ff),
+    ("chocolate", 0xd2691eff),
+    ("coral", 0xff7f50ff),
+    ("cornflowerblue", 0x6495edff),
+    ("cornsilk", 0xfff8dcff),
+    ("crimson", 0xdc143cff),
+    ("cyan", 0x00ffffff),
+    ("darkblue", 0x00008bff),
+    ("darkcyan", 0x008b8bff),
+    ("darkgoldenrod", 0xb8860bff),
+    ("darkgray", 0xa9a9a9ff),
+    ("darkgreen", 0x006400ff),
+    ("darkgrey", 0xa9a9a9ff),
+    ("darkkhaki", 0xbdb76bff),
+    ("darkmagenta", 0x8b008bff),
+    ("darkolivegreen", 0x556b2fff),
+    ("darkorange", 0xff8c00ff),
+    ("darkorchid", 0x9932ccff),
+    ("darkred", 0x8b0000ff),
+    ("darksalmon", 0xe9967aff),
+    ("darkseagreen", 0x8fbc8fff),
+    ("darkslateblue", 0x483d8bff),
+    ("darkslategray", 0x2f4f4fff),
+    ("darkslategrey", 0x2f4f4fff),
+    ("darkturquoise", 0x00ced1ff),
+    ("darkviolet", 0x9400d3ff),
+    ("deeppink", 0xff1493ff),
+    ("deepskyblue", 0x00bfffff),
+    ("dimgray", 0x696969ff),
+    ("dimgrey", 0x696969ff),
+    ("dodgerblue", 0x1e90ffff),
+    ("firebrick", 0xb22222ff),
+    ("floralwhite", 0xfffaf0ff),
+    ("forestgreen", 0x228b22ff),
+    ("fuchsia", 0xff00ffff),
+    ("gainsboro", 0xdcdcdcff),
+    ("ghostwhite", 0xf8f8ffff),
+    ("gold", 0xffd700ff),
+    ("goldenrod", 0xdaa520ff),
+    ("gray", 0x808080ff),
+    ("green", 0x008000ff),
+    ("greenyellow", 0xadff2fff),
+    ("grey", 0x808080ff),
+    ("honeydew", 0xf0fff0ff),
+    ("hotpink", 0xff69b4ff),
+    ("indianred", 0xcd5c5cff),
+    ("indigo", 0x4b0082ff),
+    ("ivory", 0xfffff0ff),
+    ("khaki", 0xf0e68cff),
+    ("lavender", 0xe6e6faff),
+    ("lavenderblush", 0xfff0f5ff),
+    ("lawngreen", 0x7cfc00ff),
+    ("lemonchiffon", 0xfffacdff),
+    ("lightblue", 0xadd8e6ff),
+    ("lightcoral", 0xf08080ff),
+    ("lightcyan", 0xe0ffffff),
+    ("lightgoldenrodyellow", 0xfafad2ff),
+    ("lightgray", 0xd3d3d3ff),
+    ("lightgreen", 0x90ee90ff),
+    ("lightgrey", 0xd3d3d3ff),
+    ("lightpink", 0xffb6c1ff),
+    ("lightsalmon", 0xffa07aff),
+    ("lightseagreen", 0x20b2aaff),
+    ("lightskyblue", 0x87cefaff),
+    ("lightslategray", 0x778899ff),
+    ("lightslategrey", 0x778899ff),
+    ("lightsteelblue", 0xb0c4deff),
+    ("lightyellow", 0xffffe0ff),
+    ("lime", 0x00ff00ff),
+    ("limegreen", 0x32cd32ff),
+    ("linen", 0xfaf0e6ff),
+    ("magenta", 0xff00ffff),
+    ("maroon", 0x800000ff),
+    ("mediumaquamarine", 0x66cdaaff),
+    ("mediumblue", 0x0000cdff),
+    ("mediumorchid", 0xba55d3ff),
+    ("mediumpurple", 0x9370dbff),
+    ("mediumseagreen", 0x3cb371ff),
+    ("mediumslateblue", 0x7b68eeff),
+    ("mediumspringgreen", 0x00fa9aff),
+    ("mediumturquoise", 0x48d1ccff),
+    ("mediumvioletred", 0xc71585ff),
+    ("midnightblue", 0x191970ff),
+    ("mintcream", 0xf5fffaff),
+    ("mistyrose", 0xffe4e1ff),
+    ("moccasin", 0xffe4b5ff),
+    ("navajowhite", 0xffdeadff),
+    ("navy", 0x000080ff),
+    ("oldlace", 0xfdf5e6ff),
+    ("olive", 0x808000ff),
+    ("olivedrab", 0x6b8e23ff),
+    ("orange", 0xffa500ff),
+    ("orangered", 0xff4500ff),
+    ("orchid", 0xda70d6ff),
+    ("palegoldenrod", 0xeee8aaff),
+    ("palegreen", 0x98fb98ff),
+    ("paleturquoise", 0xafeeeeff),
+    ("palevioletred", 0xdb7093ff),
+    ("papayawhip", 0xffefd5ff),
+    ("peachpuff", 0xffdab9ff),
+    ("peru", 0xcd853fff),
+    ("pink", 0xffc0cbff),
+    ("plum", 0xdda0ddff),
+    ("powderblue", 0xb0e0e6ff),
+    ("purple", 0x800080ff),
+    ("rebeccapurple", 0x663399ff),
+    ("red", 0xff0000ff),
+    ("rosybrown", 0xbc8f8fff),
+    ("royalblue", 0x4169e1ff),
+    ("saddlebrown", 0x8b4513ff),
+    ("salmon", 0xfa8072ff),
+    ("sandybrown", 0xf4a460ff),
+    ("seagreen", 0x2e8b57ff),
+    ("seashell", 0xfff5eeff),
+    ("sienna", 0xa0522dff),
+    ("silver", 0xc0c0c0ff),
+    ("skyblue", 0x87ceebff),
+    ("slateblue", 0x6a5acdff),
+    ("slategray", 0x708090ff),
+    ("slategrey", 0x708090ff),
+    ("snow", 0xfffafaff),
+    ("springgreen", 0x00ff7fff),
+    ("steelblue", 0x4682b4ff),
+    ("tan", 0xd2b48cff),
+    ("teal", 0x008080ff),
+    ("thistle", 0xd8bfd8ff),
+    ("tomato", 0xff6347ff),
+    ("turquoise", 0x40e0d0ff),
+    ("violet", 0xee82eeff),
+    ("wheat", 0xf5deb3ff),
+    ("white", 0xffffffff),
+    ("whitesmoke", 0xf5f5f5ff),
+    ("yellow", 0xffff00ff),
+    ("yellowgreen", 0x9acd32ff),
+];
+
+fn parse_hex(hex: &str) -> Option<u32> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    match hex.len() {
+        3 => {
+            let rgb = u32::from_str_radix(hex, 16).ok()?;
+            let r = (rgb >> 8) & 0xf;
+            let g = (rgb >> 4) & 0xf;
+            let b = rgb & 0xf;
+
+            Some((r * 0x11) << 24 | (g * 0x11) << 16 | (b * 0x11) << 8 | 0xff)
+        }
+        6 => Some(u32::from_str_radix(hex, 16).ok()? << 8 | 0xff),
+        8 => u32::from_str_radix(hex, 16).ok(),
+        _ => None,
+    }
+}
+
+fn parse_function(args: &str, alpha: bool) -> Option<u32> {
+    let args = args.split(',').map(str::trim).collect::<Vec<_>>();
+
+    if args.len() != if alpha { 4 } else { 3 } {
+        return None;
+    }
+
+    let mut color = 0;
+
+    for channel in &args[..3] {
+        let channel: u8 = channel.parse().ok()?;
+        color = color << 8 | channel as u32;
+    }
+
+    let alpha = if alpha {
+        let alpha: f64 = args[3].parse().ok()?;
+
+        if !(0. ..=1.).contains(&alpha) {
+            return None;
+        }
+
+        (alpha * 255.).round() as u32
+    } else {
+        0xff
+    };
+
+    Some(color << 8 | alpha)
+}
+
+/// Parses a color string into the `0xRRGGBBAA` format.
+pub fn parse_color(input: &str) -> Option<u32> {
+    let input = input.trim();
+
+    if let Some(hex) = input.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+
+    let lowercase = input.to_ascii_lowercase();
+
+    if let Some(args) = lowercase
+        .strip_prefix("rgba(")
+        .and_then(|s| s.strip_suffix(')'))
+    {
+        return parse_function(args, true);
+    }
+
+    if let Some(args) = lowercase
+        .strip_prefix("rgb(")
+        .and_then(|s| s.strip_suffix(')'))
+    {
+        return parse_function(args, false);
+    }
+
+    if lowercase == "transparent" {
+        return Some(0);
+    }
+
+    NAMED_COLORS
+        .binary_search_by_key(&lowercase.as_str(), |&(name, _)| name)
+        .ok()
+        .map(|i| NAMED_COLORS[i].1)
+}
+
+struct ColorVisitor;
+
+impl<'de> Visitor<'de> for ColorVisitor {
+    type Value = u32;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a color as an integer or a string")
+    }
+
+    fn visit_u64<E: Error>(self, v: u64) -> Result<u32, E> {
+        u32::try_from(v).map_err(|_| E::custom(format!("color {v} is out of range")))
+    }
+
+    fn visit_i64<E: Error>(self, v: i64) -> Result<u32, E> {
+        u32::try_from(v).map_err(|_| E::custom(format!("color {v} is out of range")))
+    }
+
+    fn visit_str<E: Error>(self, v: &str) -> Result<u32, E> {
+        parse_color(v).ok_or_else(|| E::custom(format!("invalid color `{v}`")))
+    }
+}
+
+/// A color that deserializes from either an integer or a string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColorValue(pub u32);
+
+impl<'de> Deserialize<'de> for ColorValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ColorVisitor).map(ColorValue)
+    }
+}
+
+/// For use with `#[serde(deserialize_with = "...")]` on `u32` color fields.
+pub fn deserialize_color<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    ColorValue::deserialize(deserializer).map(|c| c.0)
+}
+
+/// For use with `#[serde(default, deserialize_with = "...")]` on `Option<u32>` color fields.
+pub fn deserialize_optional_color<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<u32>, D::Error> {
+    Ok(Option::<ColorValue>::deserialize(deserializer)?.map(|c| c.0))
+}
+
+/// For the outlines of the simple shapes, which are either a [LineStyle] or a solid
+/// `[thickness, color]`.
+pub fn deserialize_optional_outline<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<LineStyle>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Outline {
+        Solid(f64, ColorValue),
+        Style(LineStyle),
+    }
+
+    Ok(
+        Option::<Outline>::deserialize(deserializer)?.map(|outline| match outline {
+            Outline::Solid(thickness, color) => LineStyle::solid(thickness, color.0),
+            Outline::Style(style) => style,
+        }),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!(parse_color("#ff0000"), Some(0xff_00_00_ff));
+        assert_eq!(parse_color("#FF000080"), Some(0xff_00_00_80));
+        assert_eq!(parse_color("#f80"), Some(0xff_88_00_ff));
+        assert_eq!(parse_color("rgb(1, 2, 3)"), Some(0x01_02_03_ff));
+        assert_eq!(parse_color("RGBA(1,2,3,0.5)"), Some(0x01_02_03_80));
+        assert_eq!(parse_color("rebeccapurple"), Some(0x66_33_99_ff));
+        assert_eq!(parse_color("White"), Some(0xff_ff_ff_ff));
+        assert_eq!(parse_color("transparent"), Some(0));
+
+        assert_eq!(parse_color("#ff00"), None);
+        assert_eq!(parse_color("#gg0000"), None);
+        assert_eq!(parse_color("rgb(256, 0, 0)"), None);
+        assert_eq!(parse_color("rgba(0, 0, 0, 2)"), None);
+        assert_eq!(parse_color("notacolor"), None);
+    }
+
+    #[test]
+    fn test_named_colors_sorted() {
+        assert!(NAMED_COLORS.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn test_deserialize() {
+        assert_eq!(
+            serde_json::from_str::<ColorValue>("255").unwrap(),
+            ColorValue(255)
+        );
+        assert_eq!(
+            serde_json::from_str::<ColorValue>(r##""#000000ff""##).unwrap(),
+            ColorValue(255)
+        );
+        assert!(serde_json::from_str::<ColorValue>("4294967296").is_err());
+    }
+
+    #[test]
+    fn test_deserialize_outline() {
+        #[derive(Deserialize)]
+        struct Shape {
+            #[serde(default, deserialize_with = "deserialize_optional_outline")]
+            outline: Option<LineStyle>,
+        }
+
+        let outline = |json: &str| serde_json::from_str::<Shape>(json).unwrap().outline;
+
+        assert!(outline("{}").is_none());
+
+        let solid = outline(r##"{ "outline": [2, "#ff0000"] }"##).unwrap();
+        assert_eq!((solid.thickness, solid.color), (2., 0xff_00_00_ff));
+        assert!(matches!(solid.join_style, crate::LineJoinStyle::Miter));
+
+        let style = outline(
+            r##"{ "outline": {
+                "thickness": 1, "color": "#0000ff", "dash_pattern": null, "cap_style": "Round",
+                "join_style": "Round", "miter_limit": 4
+            } }"##,
+        )
+        .unwrap();
+
+        assert_eq!((style.thickness, style.color), (1., 0x00_00_ff_ff));
+        assert!(matches!(style.join_style, crate::LineJoinStyle::Round));
+        assert_eq!(style.miter_limit, 4.);
+    }
+}
@@ -0,0 +1,544 @@
+//! A table built from CSV or TSV data, for quick data dumps that don't warrant mapping every cell
+//! to a [TableRow](super::elements::TableRow):
+//!
+//! ```json
+//! {
+//!     "CsvTable": {
+//!         "path": "data.tsv",
+//!         "columns": [{ "flex": { "Fixed": 20 } }, { "align": "Right" }]
+//!     }
+//! }
+//! ```
+//!
+//! The data is given either inline as `data` or as a `path` that is read while deserializing. The
+//! delimiter defaults to a tab for `.tsv` files and to a comma otherwise. If `header` is set (the
+//! default) the first row is drawn in the header font and repeated after page breaks. With
+//! `"layout": "Auto"`, the widths of the columns come from their content instead, see
+//! [TableLayout::Auto].
+
+use std::ops::Index;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    elements::{
+        column::Column, line::Line, padding::Padding, repeat_after_break::RepeatAfterBreak,
+        table_row::Flex, text::TextAlign,
+    },
+    flex::AutoColumnWidths,
+    fonts::truetype::TruetypeFont,
+    line_break::LineBreaking,
+    *,
+};
+
+use super::{
+    color,
+    defaults::{self, or_default},
+    expr, Font, SerdeElement,
+};
+
+/// Parses RFC 4180 style delimited data. Fields can be quoted with `"`, in which case they can
+/// contain the delimiter, line breaks and doubled quotes. Empty lines are skipped.
+pub fn parse(input: &str, delimiter: char) -> Result<Vec<Vec<String>>, String> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut field = String::new();
+
+    // Whether the current field has any content, including an empty quoted string. A quote is
+    // only special at the start of a field.
+    let mut started = false;
+
+    // Whether the current row has any fields, so that a line of only delimiters isn't dropped.
+    let mut row_started = false;
+
+    let mut chars = input.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if !started => {
+                started = true;
+
+                loop {
+                    match chars.next() {
+                        Some('"') if chars.peek() == Some(&'"') => {
+                            chars.next();
+                            field.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => field.push(c),
+                        None => return Err(format!("unterminated quote in row {}", rows.len())),
+                    }
+                }
+
+                if !matches!(chars.peek(), Some(&c) if c == delimiter || c == '\r' || c == '\n')
+                    && chars.peek().is_some()
+                {
+                    return Err(format!(
+                        "unexpected character after quote in row {}",
+                        rows.len()
+                    ));
+                }
+            }
+            c if c == delimiter => {
+                row.push(std::mem::take(&mut field));
+                started = false;
+                row_started = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => (),
+            '\n' | '\r' => {
+                if row_started || started {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+
+                started = false;
+                row_started = false;
+            }
+            c => {
+                field.push(c);
+                started = true;
+            }
+        }
+    }
+
+    if row_started || started {
+        row.push(field);
+        rows.push(row);
+    }
+
+    Ok(rows)
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CsvColumn {
+    #[serde(default = "default_flex")]
+    pub flex: Flex,
+
+    /// Defaults to the side lines start on in the direction of the document.
+    #[serde(default = "defaults::default_align")]
+    pub align: TextAlign,
+
+    /// Overrides the font of the table for this column. Doesn't affect the header.
+    #[serde(default)]
+    pub font: Option<String>,
+}
+
+const fn default_flex() -> Flex {
+    Flex::Expand(1)
+}
+
+/// How the widths of the columns are determined.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum TableLayout {
+    /// From the `flex` of the columns.
+    #[default]
+    Flex,
+
+    /// From the narrowest and the widest the text of the cells can be laid out, distributed like
+    /// the automatic table layout in HTML, see [AutoColumnWidths]. The `flex` of the columns is
+    /// ignored. The widths depend on the width the table gets, so they're computed during layout.
+    Auto,
+}
+
+static DEFAULT_COLUMN: CsvColumn = CsvColumn {
+    flex: default_flex(),
+    align: TextAlign::Left,
+    font: Option::None,
+};
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "CsvTableInput")]
+pub struct CsvTable {
+    pub rows: Vec<Vec<String>>,
+    pub header: bool,
+
+    /// Columns without an entry here are [Flex::Expand(1)] and left aligned.
+    pub columns: Vec<CsvColumn>,
+
+    pub font: String,
+    pub header_font: String,
+    pub size: f64,
+    pub color: u32,
+    pub line_style: LineStyle,
+    pub padding: f64,
+    pub layout: TableLayout,
+}
+
+#[derive(Deserialize)]
+struct CsvTableInput {
+    #[serde(default)]
+    data: Option<String>,
+
+    #[serde(default)]
+    path: Option<String>,
+
+    #[serde(default)]
+    delimiter: Option<char>,
+
+    #[serde(default = "default_true")]
+    header: bool,
+
+    #[serde(default)]
+    columns: Vec<CsvColumn>,
+
+    #[serde(default)]
+    font: Option<String>,
+
+    /// Defaults to the bold font of the document defaults and then to `font`.
+    #[serde(default)]
+    header_font: Option<String>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_optional_pt")]
+    size: Option<f64>,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    color: Option<u32>,
+
+    #[serde(default)]
+    line_style: Option<LineStyle>,
+
+    #[serde(
+        default = "default_padding",
+        deserialize_with = "expr::deserialize_f64"
+    )]
+    padding: f64,
+
+    #[serde(default)]
+    layout: TableLayout,
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+const fn default_padding() -> f64 {
+    1.
+}
+
+impl TryFrom<CsvTableInput> for CsvTable {
+    type Error = String;
+
+    fn try_from(input: CsvTableInput) -> Result<Self, String> {
+        let (data, tsv) = match (input.data, input.path) {
+            (Some(data), Option::None) => (data, false),
+            (Option::None, Some(path)) => (
+                String::from_utf8(crate::image::read_file(&path)?)
+                    .map_err(|e| format!("could not read {path}: {e}"))?,
+                path.ends_with(".tsv"),
+            ),
+            _ => return Err("exactly one of `data` and `path` has to be given".to_string()),
+        };
+
+        let delimiter = input.delimiter.unwrap_or(if tsv { '\t' } else { ',' });
+
+        let font = or_default(input.font, "font", |d| &d.font)?;
+
+        let header_font = match input.header_font {
+            Some(header_font) => header_font,
+            Option::None => or_default(Option::None, "header_font", |d| &d.bold_font)
+                .unwrap_or_else(|_| font.clone()),
+        };
+
+        let rows = parse(&data, delimiter)?;
+
+        // The columns without an entry get the default alignment of the document, which isn't
+        // known anymore when the table is drawn.
+        let mut columns = input.columns;
+        let column_count = rows.iter().map(Vec::len).max().unwrap_or(0);
+
+        while columns.len() < column_count {
+            columns.push(CsvColumn {
+                align: defaults::default_align(),
+                ..DEFAULT_COLUMN.clone()
+            });
+        }
+
+        Ok(CsvTable {
+            rows,
+            header: input.header,
+            columns,
+            font,
+            header_font,
+            size: or_default(input.size, "size", |d| &d.size)?,
+            color: or_default(input.color, "color", |d| &d.color)?,
+            line_style: or_default(input.line_style, "line_style", |d| &d.line_style)?,
+            padding: input.padding,
+            layout: input.layout,
+        })
+    }
+}
+
+impl CsvTable {
+    fn column_count(&self) -> usize {
+        self.rows.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    fn column(&self, index: usize) -> &CsvColumn {
+        self.columns.get(index).unwrap_or(&DEFAULT_COLUMN)
+    }
+
+    fn font(&self, column: usize, header: bool) -> &str {
+        if header {
+            &self.header_font
+        } else {
+            self.column(column).font.as_ref().unwrap_or(&self.font)
+        }
+    }
+
+    fn text<'a>(
+        &'a self,
+        cells: &'a [String],
+        column: usize,
+        fonts: &'a impl for<'b> Index<&'b str, Output = Font>,
+        header: bool,
+    ) -> elements::text::Text<'a, TruetypeFont<std::sync::Arc<[u8]>>> {
+        elements::text::Text {
+            text: cells.get(column).map(|c| &c[..]).unwrap_or(""),
+            font: &*fonts[self.font(column, header)],
+            size: self.size,
+            color: self.color,
+            underline: false,
+            extra_character_spacing: 0.,
+            extra_word_spacing: 0.,
+            extra_line_height: 0.,
+            line_spacing: LineSpacing::default(),
+            line_breaking: LineBreaking::default(),
+            baseline_grid: None,
+            align: self.column(column).align,
+            markup: &[],
+            redactions: &[],
+            drop_cap: None,
+            cjk: Default::default(),
+        }
+    }
+
+    /// The widths of the columns for [TableLayout::Auto], given the narrowest and the widest width
+    /// of the text of a cell in a font.
+    fn auto_widths(&self, width: f64, text_widths: impl Fn(&str, &str) -> (f64, f64)) -> Vec<f64> {
+        let mut auto = AutoColumnWidths::new(self.column_count());
+
+        for (row, cells) in self.rows.iter().enumerate() {
+            let header = self.header && row == 0;
+
+            for (column, cell) in cells.iter().enumerate() {
+                let (min, max) = text_widths(cell, self.font(column, header));
+                let padding = self.padding * 2.;
+
+                auto.add_cell(column, min + padding, max + padding);
+            }
+        }
+
+        auto.widths(width, self.line_style.thickness)
+    }
+
+    fn row<'a>(
+        &'a self,
+        cells: &'a [String],
+        fonts: &'a impl for<'b> Index<&'b str, Output = Font>,
+        header: bool,
+        widths: Option<&'a [f64]>,
+    ) -> elements::table_row::TableRow<impl Fn(&mut elements::table_row::RowContent) + 'a> {
+        elements::table_row::TableRow {
+            line_style: self.line_style,
+            expand: true,
+            border_collapse: false,
+            content: move |content| {
+                for i in 0..self.column_count() {
+                    let flex = match widths {
+                        Some(widths) => Flex::Fixed(widths[i]),
+                        Option::None => self.column(i).flex,
+                    };
+
+                    content.add(
+                        &Padding {
+                            left: self.padding,
+                            right: self.padding,
+                            top: self.padding,
+                            bottom: self.padding,
+                            element: &self.text(cells, i, fonts, header),
+                        },
+                        flex,
+                    );
+                }
+            },
+        }
+    }
+
+    fn table(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        widths: Option<&[f64]>,
+        callback: impl CompositeElementCallback,
+    ) {
+        let line = Line {
+            style: self.line_style,
+        };
+
+        let (header, body) = match self.rows.split_first() {
+            Some((header, body)) if self.header => (Some(header), body),
+            _ => (Option::None, &self.rows[..]),
+        };
+
+        let body = Column::new(|mut content| {
+            for (i, cells) in body.iter().enumerate() {
+                if i > 0 {
+                    content = content.add(&line)?;
+                }
+
+                content = content.add(&self.row(cells, fonts, false, widths))?;
+            }
+
+            Option::None
+        });
+
+        if let Some(header) = header {
+            callback.call(&RepeatAfterBreak {
+                title: &Column::new(|content| {
+                    content
+                        .add(&self.row(header, fonts, true, widths))?
+                        .add(&line)?;
+                    Option::None
+                }),
+                content: &body,
+                gap: 0.,
+                collapse_on_empty_content: false,
+            });
+        } else {
+            callback.call(&body);
+        }
+    }
+}
+
+impl SerdeElement for CsvTable {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        match self.layout {
+            TableLayout::Flex => self.table(fonts, Option::None, callback),
+            TableLayout::Auto => callback.call(&AutoTable { table: self, fonts }),
+        }
+    }
+}
+
+/// A table with [TableLayout::Auto], which is only built once the width is known.
+struct AutoTable<'a, F> {
+    table: &'a CsvTable,
+    fonts: &'a F,
+}
+
+struct FnCallback<F: FnOnce(&dyn Element)>(F);
+
+impl<F: FnOnce(&dyn Element)> CompositeElementCallback for FnCallback<F> {
+    fn call(self, element: &impl Element) {
+        (self.0)(element);
+    }
+}
+
+impl<'a, F: for<'b> Index<&'b str, Output = Font>> AutoTable<'a, F> {
+    fn with_table<R>(&self, width: f64, f: impl FnOnce(&dyn Element) -> R) -> R {
+        let widths = self.table.auto_widths(width, |text, font| {
+            let text = elements::text::Text::basic(text, &*self.fonts[font], self.table.size);
+            (text.min_content_width(), text.max_content_width())
+        });
+
+        let mut ret = Option::None;
+
+        self.table.table(
+            self.fonts,
+            Some(&widths),
+            FnCallback(|element: &dyn Element| ret = Some(f(element))),
+        );
+
+        ret.unwrap()
+    }
+}
+
+impl<'a, F: for<'b> Index<&'b str, Output = Font>> Element for AutoTable<'a, F> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.with_table(ctx.width.max, |table| table.first_location_usage(ctx))
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.with_table(ctx.width.max, |table| table.measure(ctx))
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        self.with_table(ctx.width.max, |table| table.draw(ctx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse(
+                "a,b,c\n1,\"2,5\",3\r\n\n\"say \"\"hi\"\"\",,\"multi\nline\"",
+                ','
+            )
+            .unwrap(),
+            vec![
+                vec!["a", "b", "c"],
+                vec!["1", "2,5", "3"],
+                vec!["say \"hi\"", "", "multi\nline"],
+            ],
+        );
+
+        assert_eq!(
+            parse("x\ty\n1\t2\n", '\t').unwrap(),
+            vec![vec!["x", "y"], vec!["1", "2"]],
+        );
+
+        assert_eq!(parse("a,\n", ',').unwrap(), vec![vec!["a", ""]]);
+        assert_eq!(parse(",\n", ',').unwrap(), vec![vec!["", ""]]);
+
+        assert_eq!(
+            parse("\"a\",\"b\"\"c\",d\"e", ',').unwrap(),
+            vec![vec!["a", "b\"c", "d\"e"]],
+        );
+
+        assert!(parse("\"open", ',').is_err());
+        assert!(parse("\"a\"b", ',').is_err());
+    }
+
+    #[test]
+    fn test_auto_widths() {
+        let table = serde_json::from_str::<CsvTable>(
+            r##"{
+                "data": "name,note\nlonger name,x\nn,a much longer note",
+                "font": "regular",
+                "header_font": "bold",
+                "size": 10,
+                "color": "#000000",
+                "line_style": {
+                    "thickness": 1, "color": "#000000", "dash_pattern": null, "cap_style": "Butt"
+                },
+                "padding": 0,
+                "layout": "Auto"
+            }"##,
+        )
+        .unwrap();
+
+        assert_eq!(table.layout, TableLayout::Auto);
+
+        // A character is 1mm wide, 2mm in the header font, and lines can be broken at spaces.
+        let text_widths = |text: &str, font: &str| {
+            let scale = if font == "bold" { 2. } else { 1. };
+            let longest_word = text.split(' ').map(str::len).max().unwrap_or(0);
+            (longest_word as f64 * scale, text.len() as f64 * scale)
+        };
+
+        // The header needs 8mm in both columns, the body 6 to 11 in the first and 6 to 18 in the
+        // second. The line between the columns is 1mm.
+        assert_eq!(table.auto_widths(30., text_widths), [11., 18.]);
+        assert_eq!(table.auto_widths(17., text_widths), [8., 8.]);
+
+        let widths = table.auto_widths(20., text_widths);
+        assert!((widths[0] + widths[1] + 1. - 20.).abs() < 1e-9);
+        assert!(widths[0] > 8. && widths[1] > 8.);
+    }
+}
@@ -0,0 +1,147 @@
+//! Document level defaults for fields that tend to be the same for most elements. Like the
+//! constants for expressions these are made available with [Defaults::scope] while deserializing.
+//! An element field that is left out falls back to the default and it's an error if neither is
+//! given.
+
+use std::cell::RefCell;
+
+use serde::Deserialize;
+
+use crate::{
+    direction::Direction, elements::text::TextAlign, fonts::Synthesis, utils::scoped, LineStyle,
+};
+
+use super::{color, format::Locale};
+
+#[derive(Clone, Default, Deserialize)]
+pub struct Defaults {
+    /// Used for [Text](super::elements::Text) and as the regular font of
+    /// [RichText](super::elements::RichText).
+    #[serde(default)]
+    pub font: Option<String>,
+
+    #[serde(default)]
+    pub bold_font: Option<String>,
+
+    #[serde(default)]
+    pub italic_font: Option<String>,
+
+    #[serde(default)]
+    pub bold_italic_font: Option<String>,
+
+    /// Which effects may be used to fake the faces of [RichText](super::elements::RichText) fonts
+    /// that aren't given.
+    #[serde(default)]
+    pub synthesize: Option<Synthesis>,
+
+    /// The font size in points.
+    #[serde(default, deserialize_with = "super::expr::deserialize_optional_pt")]
+    pub size: Option<f64>,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    pub color: Option<u32>,
+
+    #[serde(default)]
+    pub line_style: Option<LineStyle>,
+
+    /// The step in mm of the baseline grid that [Text](super::elements::Text) and
+    /// [RichText](super::elements::RichText) lines are snapped to.
+    #[serde(default, deserialize_with = "super::expr::deserialize_optional_f64")]
+    pub baseline_grid: Option<f64>,
+
+    /// Used for [Formatted](super::format::Formatted) values.
+    #[serde(default)]
+    pub locale: Option<Locale>,
+
+    /// With [Direction::Rtl], text is aligned to the right and the content of rows is laid out from
+    /// right to left unless an element sets an alignment or a direction.
+    #[serde(default)]
+    pub direction: Option<Direction>,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Defaults>> = const { RefCell::new(None) };
+}
+
+impl Defaults {
+    /// Makes the defaults available to elements deserialized on this thread while `f` is running.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        scoped(&CURRENT, Some(self.clone()), f).0
+    }
+}
+
+/// Returns `value` or, if that's `None`, the field of the current defaults. Errors with the name of
+/// the field if neither is set.
+pub(super) fn or_default<T: Clone>(
+    value: Option<T>,
+    name: &str,
+    field: impl FnOnce(&Defaults) -> &Option<T>,
+) -> Result<T, String> {
+    if let Some(value) = value {
+        return Ok(value);
+    }
+
+    CURRENT
+        .with(|current| current.borrow().as_ref().and_then(|d| field(d).clone()))
+        .ok_or_else(|| format!("missing field `{name}` and no default is set"))
+}
+
+/// The alignment of text elements that don't set one, for `#[serde(default = "...")]`.
+pub(super) fn default_align() -> TextAlign {
+    default_direction().text_align()
+}
+
+/// The direction of rows that don't set one, for `#[serde(default = "...")]`.
+pub(super) fn default_direction() -> Direction {
+    CURRENT
+        .with(|current| current.borrow().as_ref().and_then(|d| d.direction))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde_elements::elements::{Row, Text};
+
+    #[test]
+    fn test_defaults() {
+        let json = r#"{ "text": "hi", "size": "10pt" }"#;
+
+        assert!(serde_json::from_str::<Text>(json).is_err());
+
+        let defaults = Defaults {
+            font: Some("regular".to_string()),
+            size: Some(12.),
+            color: Some(0x00_00_00_FF),
+            ..Default::default()
+        };
+
+        let text = defaults.scope(|| serde_json::from_str::<Text>(json).unwrap());
+
+        assert_eq!(text.font, "regular");
+        assert_eq!(text.size, 10.);
+        assert_eq!(text.color, 0x00_00_00_FF);
+        assert!(text.align == TextAlign::Left);
+
+        let defaults = Defaults {
+            direction: Some(Direction::Rtl),
+            ..defaults
+        };
+
+        let text = defaults.scope(|| serde_json::from_str::<Text>(json).unwrap());
+        assert!(text.align == TextAlign::Right);
+
+        let json = r#"{ "text": "hi", "align": "Center" }"#;
+        let text = defaults.scope(|| serde_json::from_str::<Text>(json).unwrap());
+        assert!(text.align == TextAlign::Center);
+
+        let json = r#"{ "content": [], "gap": 0, "expand": false, "collapse": true }"#;
+        let row = defaults.scope(|| serde_json::from_str::<Row<()>>(json).unwrap());
+        assert_eq!(row.direction, Direction::Rtl);
+
+        let json = r#"{ "content": [], "gap": 0, "expand": false, "collapse": true,
+            "direction": "ltr" }"#;
+        let row = defaults.scope(|| serde_json::from_str::<Row<()>>(json).unwrap());
+        assert_eq!(row.direction, Direction::Ltr);
+    }
+}
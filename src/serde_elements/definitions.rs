@@ -0,0 +1,125 @@
+//! Named elements that are defined once and referenced with [Ref] in multiple places. Like the
+//! constants and defaults these are made available with [Definitions::scope] while deserializing.
+//! The definitions are only deserialized once and shared between the references.
+//!
+//! Definitions can't reference each other, since they're all deserialized before they're in scope.
+
+use std::{cell::RefCell, collections::HashMap, ops::Index, rc::Rc};
+
+use serde::{Deserialize, Deserializer};
+
+use crate::{utils::scoped, CompositeElementCallback};
+
+use super::{ElementValue, Font, SerdeElement};
+
+#[derive(Clone, Default)]
+pub struct Definitions(pub HashMap<String, Rc<ElementValue>>);
+
+impl<'de> Deserialize<'de> for Definitions {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let definitions = HashMap::<String, ElementValue>::deserialize(deserializer)?;
+
+        Ok(Definitions(
+            definitions
+                .into_iter()
+                .map(|(name, element)| (name, Rc::new(element)))
+                .collect(),
+        ))
+    }
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Definitions>> = const { RefCell::new(None) };
+}
+
+impl Definitions {
+    /// Makes the definitions available to [Ref] elements deserialized on this thread while `f` is
+    /// running.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        scoped(&CURRENT, Some(self.clone()), f).0
+    }
+}
+
+/// A reference to an element in the current [Definitions].
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "RefInput")]
+pub struct Ref {
+    pub name: String,
+    pub element: Rc<ElementValue>,
+}
+
+#[derive(Deserialize)]
+struct RefInput {
+    name: String,
+}
+
+impl TryFrom<RefInput> for Ref {
+    type Error = String;
+
+    fn try_from(input: RefInput) -> Result<Self, String> {
+        let element = CURRENT
+            .with(|current| {
+                current
+                    .borrow()
+                    .as_ref()
+                    .and_then(|definitions| definitions.0.get(&input.name).cloned())
+            })
+            .ok_or_else(|| format!("no element defined as `{}`", input.name))?;
+
+        Ok(Ref {
+            name: input.name,
+            element,
+        })
+    }
+}
+
+impl SerdeElement for Ref {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        self.element.element(fonts, callback);
+    }
+
+    fn v_expand_weight(&self) -> Option<u8> {
+        self.element.v_expand_weight()
+    }
+
+    fn outline_thickness(&self) -> Option<f64> {
+        self.element.outline_thickness()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_definitions() {
+        let json = r#"{ "Column": {
+            "gap": 1,
+            "content": [
+                { "Ref": { "name": "gap" } },
+                { "Ref": { "name": "gap" } }
+            ]
+        } }"#;
+
+        assert!(serde_json::from_str::<ElementValue>(json).is_err());
+
+        let definitions =
+            serde_json::from_str::<Definitions>(r#"{ "gap": { "VGap": { "gap": 3 } } }"#).unwrap();
+
+        let value = definitions.scope(|| serde_json::from_str::<ElementValue>(json).unwrap());
+
+        let ElementValue::Column(column) = value else {
+            panic!("expected a column");
+        };
+
+        let [ElementValue::Ref(a), ElementValue::Ref(b)] = &column.content[..] else {
+            panic!("expected two references");
+        };
+
+        assert!(Rc::ptr_eq(&a.element, &b.element));
+    }
+}
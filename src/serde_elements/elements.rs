@@ -1,13 +1,41 @@
-use std::ops::Index;
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    ops::{Index, Range},
+};
 
 use elements::rotate::Rotation;
+use serde::de::IgnoredAny;
 
 use crate::{
-    elements::{h_align::HorizontalAlignment, rich_text::Span, row::Flex, text::TextAlign},
+    annotations::{SpanMarkup, TextMarkup},
+    direction::Direction,
+    elements::{
+        code::CodeOverflow,
+        h_align::HorizontalAlignment,
+        poly_line::LineEnd,
+        rich_text::Span,
+        row::Flex,
+        signature_line::SignatureLayout,
+        styled_box::BoxBreak,
+        symbol::SymbolKind,
+        text::{CjkLayout, DropCap, TextAlign},
+    },
+    fonts::{Synthesis, SyntheticFaces},
+    line_break::LineBreaking,
+    script::{complex_scripts, Script},
+    utils::scoped,
     *,
 };
 
-use super::{Font, SerdeElement, SerdeElementElement};
+use super::{
+    color,
+    defaults::{self, or_default},
+    expr::{self, Length},
+    shaping::{self, CollectedText},
+    warnings::warn,
+    Font, SerdeElement, SerdeElementElement,
+};
 
 const fn default_false() -> bool {
     false
@@ -17,6 +45,10 @@ const fn default_0u8() -> u8 {
     0
 }
 
+const fn default_tab_width() -> u32 {
+    4
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct None;
 
@@ -41,6 +73,12 @@ pub struct Debug<E> {
 
     #[serde(default = "default_false")]
     pub show_last_location_max_height: bool,
+
+    #[serde(default = "default_false")]
+    pub show_rulers: bool,
+
+    #[serde(default = "default_false")]
+    pub show_baselines: bool,
 }
 
 impl<E: SerdeElement> SerdeElement for Debug<E> {
@@ -57,11 +95,18 @@ impl<E: SerdeElement> SerdeElement for Debug<E> {
             color: self.color,
             show_max_width: self.show_max_width,
             show_last_location_max_height: self.show_last_location_max_height,
+            show_rulers: self.show_rulers,
+            show_baselines: self.show_baselines,
         });
     }
+
+    fn outline_thickness(&self) -> Option<f64> {
+        self.element.outline_thickness()
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "TextInput")]
 pub struct Text {
     pub text: String,
     pub font: String,
@@ -71,7 +116,125 @@ pub struct Text {
     pub extra_character_spacing: f64,
     pub extra_word_spacing: f64,
     pub extra_line_height: f64,
+    pub line_spacing: LineSpacing,
+    pub line_breaking: LineBreaking,
+    pub baseline_grid: Option<f64>,
     pub align: TextAlign,
+    pub markup: Vec<TextMarkup>,
+    pub redactions: Vec<Range<usize>>,
+    pub drop_cap: Option<DropCap>,
+    pub cjk: CjkLayout,
+}
+
+/// The fields that are covered by [Defaults](super::defaults::Defaults) are optional here.
+#[derive(Deserialize)]
+struct TextInput {
+    text: String,
+
+    #[serde(default)]
+    font: Option<String>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_optional_pt")]
+    size: Option<f64>,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    color: Option<u32>,
+
+    #[serde(default = "default_false")]
+    underline: bool,
+
+    #[serde(default, deserialize_with = "expr::deserialize_pt")]
+    extra_character_spacing: f64,
+
+    #[serde(default, deserialize_with = "expr::deserialize_pt")]
+    extra_word_spacing: f64,
+
+    #[serde(default, deserialize_with = "expr::deserialize_f64")]
+    extra_line_height: f64,
+
+    #[serde(default)]
+    line_spacing: LineSpacing,
+
+    #[serde(default)]
+    line_breaking: LineBreaking,
+
+    /// The step of the baseline grid in mm.
+    #[serde(default, deserialize_with = "expr::deserialize_optional_f64")]
+    baseline_grid: Option<f64>,
+
+    #[serde(default = "defaults::default_align")]
+    align: TextAlign,
+
+    #[serde(default)]
+    markup: Vec<TextMarkup>,
+
+    /// Byte ranges of the text to redact.
+    #[serde(default)]
+    redactions: Vec<Range<usize>>,
+
+    #[serde(default)]
+    drop_cap: Option<DropCap>,
+
+    #[serde(default)]
+    cjk: CjkLayout,
+}
+
+/// Warns about text in scripts that can't be drawn correctly without shaping, once per script.
+fn warn_unsupported_scripts<'a>(texts: impl IntoIterator<Item = (&'a str, Option<Script>)>) {
+    let mut scripts = Vec::new();
+
+    for (text, hint) in texts {
+        for script in hint.into_iter().chain(complex_scripts(text)) {
+            if !script.is_supported() && !scripts.contains(&script) {
+                scripts.push(script);
+            }
+        }
+    }
+
+    for script in scripts {
+        warn(format!(
+            "{script:?} text is drawn without shaping, so it probably looks wrong"
+        ));
+    }
+}
+
+impl TryFrom<TextInput> for Text {
+    type Error = String;
+
+    fn try_from(input: TextInput) -> Result<Self, String> {
+        warn_unsupported_scripts([(&input.text[..], Option::None)]);
+
+        let text = Text {
+            text: input.text,
+            font: or_default(input.font, "font", |d| &d.font)?,
+            size: or_default(input.size, "size", |d| &d.size)?,
+            color: or_default(input.color, "color", |d| &d.color)?,
+            underline: input.underline,
+            extra_character_spacing: input.extra_character_spacing,
+            extra_word_spacing: input.extra_word_spacing,
+            extra_line_height: input.extra_line_height,
+            line_spacing: input.line_spacing,
+            line_breaking: input.line_breaking,
+            baseline_grid: or_default(input.baseline_grid, "baseline_grid", |d| &d.baseline_grid)
+                .ok(),
+            align: input.align,
+            markup: input.markup,
+            redactions: input.redactions,
+            drop_cap: input.drop_cap,
+            cjk: input.cjk,
+        };
+
+        shaping::collect(|| CollectedText {
+            text: text.text.clone(),
+            font: text.font.clone(),
+            size: text.size,
+            character_spacing: text.extra_character_spacing,
+            word_spacing: text.extra_word_spacing,
+            breaking: text.line_breaking,
+        });
+
+        Ok(text)
+    }
 }
 
 impl SerdeElement for Text {
@@ -88,22 +251,444 @@ impl SerdeElement for Text {
             underline: self.underline,
             extra_character_spacing: self.extra_character_spacing,
             extra_word_spacing: self.extra_word_spacing,
-            extra_line_height: self.extra_line_height,
+            extra_line_height: self.extra_line_height * spacing(),
+            line_spacing: self.line_spacing.scale_leading(spacing()),
+            line_breaking: self.line_breaking,
+            baseline_grid: self.baseline_grid,
             align: self.align,
+            markup: &self.markup,
+            redactions: &self.redactions,
+            drop_cap: self.drop_cap,
+            cjk: self.cjk,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "CodeInput")]
+pub struct Code {
+    pub text: String,
+    pub font: String,
+    pub size: f64,
+    pub color: u32,
+    pub tab_width: u32,
+    pub overflow: CodeOverflow,
+    pub background: Option<u32>,
+}
+
+#[derive(Deserialize)]
+struct CodeInput {
+    text: String,
+
+    #[serde(default)]
+    font: Option<String>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_optional_pt")]
+    size: Option<f64>,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    color: Option<u32>,
+
+    #[serde(default = "default_tab_width")]
+    tab_width: u32,
+
+    #[serde(default)]
+    overflow: CodeOverflow,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    background: Option<u32>,
+}
+
+impl TryFrom<CodeInput> for Code {
+    type Error = String;
+
+    fn try_from(input: CodeInput) -> Result<Self, String> {
+        Ok(Code {
+            text: input.text,
+            font: or_default(input.font, "font", |d| &d.font)?,
+            size: or_default(input.size, "size", |d| &d.size)?,
+            color: or_default(input.color, "color", |d| &d.color)?,
+            tab_width: input.tab_width,
+            overflow: input.overflow,
+            background: input.background,
+        })
+    }
+}
+
+impl SerdeElement for Code {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::code::Code {
+            text: &self.text,
+            font: &*fonts[&self.font],
+            size: self.size,
+            color: self.color,
+            tab_width: self.tab_width,
+            overflow: self.overflow,
+            background: self.background,
         });
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "RichTextInput")]
 pub struct RichText {
     pub spans: Vec<Span>,
     pub size: f64,
     pub small_size: f64,
     pub extra_line_height: f64,
+    pub line_spacing: LineSpacing,
+    pub line_breaking: LineBreaking,
+    pub baseline_grid: Option<f64>,
     pub regular: String,
     pub bold: String,
     pub italic: String,
     pub bold_italic: String,
+    pub synthetic: SyntheticFaces,
+}
+
+#[derive(Clone, Default, Deserialize)]
+struct SpanStyle {
+    #[serde(default)]
+    bold: Option<bool>,
+
+    #[serde(default)]
+    italic: Option<bool>,
+
+    #[serde(default)]
+    underline: Option<bool>,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    color: Option<u32>,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    underline_color: Option<u32>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_optional_f64")]
+    underline_thickness: Option<f64>,
+
+    #[serde(default)]
+    script: Option<Script>,
+
+    #[serde(default)]
+    language: Option<String>,
+
+    #[serde(default)]
+    markup: Option<SpanMarkup>,
+}
+
+/// Spans can be nested in groups. Attributes that aren't set on a span or group are inherited from
+/// the enclosing group.
+#[derive(Deserialize)]
+#[serde(try_from = "SpanNodeInput")]
+enum SpanNode {
+    Group {
+        style: SpanStyle,
+        spans: Vec<SpanNode>,
+    },
+    Span {
+        style: SpanStyle,
+        text: String,
+
+        /// Isn't inherited, since it's annotating this text.
+        ruby: Option<String>,
+    },
+}
+
+/// A span with a `text` or a group with `spans`. Since `deny_unknown_fields` doesn't work together
+/// with `flatten`, the fields that aren't known are collected to fail on them.
+#[derive(Deserialize)]
+struct SpanNodeInput {
+    #[serde(flatten)]
+    style: SpanStyle,
+
+    #[serde(default)]
+    text: Option<String>,
+
+    #[serde(default)]
+    spans: Option<Vec<SpanNode>>,
+
+    #[serde(default)]
+    ruby: Option<String>,
+
+    #[serde(flatten)]
+    unknown: BTreeMap<String, IgnoredAny>,
+}
+
+impl TryFrom<SpanNodeInput> for SpanNode {
+    type Error = String;
+
+    fn try_from(input: SpanNodeInput) -> Result<Self, String> {
+        if let Some(field) = input.unknown.keys().next() {
+            return Err(format!("unknown field `{field}` of a span"));
+        }
+
+        match (input.text, input.spans, input.ruby) {
+            (Some(text), Option::None, ruby) => Ok(SpanNode::Span {
+                style: input.style,
+                text,
+                ruby,
+            }),
+            (Option::None, Some(spans), Option::None) => Ok(SpanNode::Group {
+                style: input.style,
+                spans,
+            }),
+            (Option::None, Some(_), Some(_)) => Err("a group of spans can't have a `ruby`".into()),
+            _ => Err("a span needs either a `text` or `spans`".into()),
+        }
+    }
+}
+
+fn flatten_spans(nodes: Vec<SpanNode>, inherited: &Span, out: &mut Vec<Span>) {
+    fn apply(style: SpanStyle, inherited: &Span) -> Span {
+        Span {
+            text: String::new(),
+            bold: style.bold.unwrap_or(inherited.bold),
+            italic: style.italic.unwrap_or(inherited.italic),
+            underline: style.underline.unwrap_or(inherited.underline),
+            color: style.color.unwrap_or(inherited.color),
+            underline_color: style.underline_color.or(inherited.underline_color),
+            underline_thickness: style.underline_thickness.or(inherited.underline_thickness),
+            script: style.script.or(inherited.script),
+            language: style.language.or_else(|| inherited.language.clone()),
+            markup: style.markup.or(inherited.markup),
+            ruby: Option::None,
+        }
+    }
+
+    for node in nodes {
+        match node {
+            SpanNode::Group { style, spans } => flatten_spans(spans, &apply(style, inherited), out),
+            SpanNode::Span { style, text, ruby } => out.push(Span {
+                text,
+                ruby,
+                ..apply(style, inherited)
+            }),
+        }
+    }
+}
+
+/// Source code that's colored by syntax, see [crate::highlight].
+#[derive(Deserialize)]
+struct CodeSource {
+    code: String,
+    language: String,
+
+    #[serde(default = "default_theme")]
+    theme: String,
+
+    #[serde(default = "default_tab_width")]
+    tab_width: u32,
+}
+
+fn default_theme() -> String {
+    crate::highlight::DEFAULT_THEME.into()
+}
+
+#[derive(Deserialize)]
+struct RichTextInput {
+    #[serde(default)]
+    spans: Option<Vec<SpanNode>>,
+
+    /// An alternative to `spans`, see [crate::markup].
+    #[serde(default)]
+    markup: Option<String>,
+
+    /// Another alternative to `spans`, see [crate::highlight].
+    #[serde(default)]
+    code: Option<CodeSource>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_optional_pt")]
+    size: Option<f64>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_optional_pt")]
+    small_size: Option<f64>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_f64")]
+    extra_line_height: f64,
+
+    #[serde(default)]
+    line_spacing: LineSpacing,
+
+    #[serde(default)]
+    line_breaking: LineBreaking,
+
+    /// The step of the baseline grid in mm.
+    #[serde(default, deserialize_with = "expr::deserialize_optional_f64")]
+    baseline_grid: Option<f64>,
+
+    #[serde(default)]
+    regular: Option<String>,
+
+    #[serde(default)]
+    bold: Option<String>,
+
+    #[serde(default)]
+    italic: Option<String>,
+
+    #[serde(default)]
+    bold_italic: Option<String>,
+
+    /// Which effects may be used to fake the faces that aren't given.
+    #[serde(default)]
+    synthesize: Option<Synthesis>,
+}
+
+/// Returns the face if it's given. Otherwise the first of the fallbacks that exists and only needs
+/// allowed effects is used with these effects, with a warning.
+fn face_or_synthesized(
+    face: Option<&String>,
+    name: &str,
+    fallbacks: &[(Option<&String>, Synthesis)],
+    allowed: Synthesis,
+) -> Result<(String, Synthesis), String> {
+    if let Some(face) = face {
+        return Ok((face.clone(), Synthesis::default()));
+    }
+
+    let fallback = fallbacks.iter().find_map(|&(font, synthesis)| {
+        let allowed = (allowed.bold || !synthesis.bold) && (allowed.oblique || !synthesis.oblique);
+        font.filter(|_| allowed).map(|font| (font, synthesis))
+    });
+
+    match fallback {
+        Some((font, synthesis)) => {
+            warn(format!(
+                "the `{name}` font is missing, faking it with `{font}`"
+            ));
+            Ok((font.clone(), synthesis))
+        }
+        Option::None => Err(format!("missing field `{name}` and no default is set")),
+    }
+}
+
+impl TryFrom<RichTextInput> for RichText {
+    type Error = String;
+
+    fn try_from(input: RichTextInput) -> Result<Self, String> {
+        let size = or_default(input.size, "size", |d| &d.size)?;
+
+        let root = Span {
+            text: String::new(),
+            bold: false,
+            italic: false,
+            underline: false,
+            color: or_default(Option::None, "color", |d| &d.color).unwrap_or(0x00_00_00_FF),
+            underline_color: Option::None,
+            underline_thickness: Option::None,
+            script: Option::None,
+            language: Option::None,
+            markup: Option::None,
+            ruby: Option::None,
+        };
+
+        let spans = match (input.spans, input.markup, input.code) {
+            (Some(nodes), Option::None, Option::None) => {
+                let mut spans = Vec::new();
+                flatten_spans(nodes, &root, &mut spans);
+                spans
+            }
+            (Option::None, Some(markup), Option::None) => crate::markup::parse(&markup, root.color),
+            (Option::None, Option::None, Some(code)) => crate::highlight::highlight(
+                &code.code,
+                &code.language,
+                &code.theme,
+                code.tab_width,
+            )?,
+            _ => return Err("exactly one of spans, markup and code has to be set".into()),
+        };
+
+        warn_unsupported_scripts(spans.iter().map(|s| (&s.text[..], s.script)));
+
+        let allowed =
+            or_default(input.synthesize, "synthesize", |d| &d.synthesize).unwrap_or_default();
+
+        let bold_effect = Synthesis {
+            bold: true,
+            oblique: false,
+        };
+        let oblique_effect = Synthesis {
+            bold: false,
+            oblique: true,
+        };
+        let both_effects = Synthesis {
+            bold: true,
+            oblique: true,
+        };
+
+        let regular = or_default(input.regular, "regular", |d| &d.font)?;
+        let bold = or_default(input.bold, "bold", |d| &d.bold_font).ok();
+        let italic = or_default(input.italic, "italic", |d| &d.italic_font).ok();
+        let bold_italic =
+            or_default(input.bold_italic, "bold_italic", |d| &d.bold_italic_font).ok();
+
+        let (bold_font, bold_synthesis) = face_or_synthesized(
+            bold.as_ref(),
+            "bold",
+            &[(Some(&regular), bold_effect)],
+            allowed,
+        )?;
+        let (italic_font, italic_synthesis) = face_or_synthesized(
+            italic.as_ref(),
+            "italic",
+            &[(Some(&regular), oblique_effect)],
+            allowed,
+        )?;
+        let (bold_italic_font, bold_italic_synthesis) = face_or_synthesized(
+            bold_italic.as_ref(),
+            "bold_italic",
+            &[
+                (italic.as_ref(), bold_effect),
+                (bold.as_ref(), oblique_effect),
+                (Some(&regular), both_effects),
+            ],
+            allowed,
+        )?;
+
+        let rich_text = RichText {
+            spans,
+            size,
+            small_size: input.small_size.unwrap_or(size),
+            extra_line_height: input.extra_line_height,
+            line_spacing: input.line_spacing,
+            line_breaking: input.line_breaking,
+            baseline_grid: or_default(input.baseline_grid, "baseline_grid", |d| &d.baseline_grid)
+                .ok(),
+            regular,
+            bold: bold_font,
+            italic: italic_font,
+            bold_italic: bold_italic_font,
+            synthetic: SyntheticFaces {
+                bold: bold_synthesis,
+                italic: italic_synthesis,
+                bold_italic: bold_italic_synthesis,
+            },
+        };
+
+        // Spans are measured in the face of their style, at the size of the text and without
+        // extra spacing.
+        for span in rich_text.spans.iter().filter(|s| !s.text.is_empty()) {
+            shaping::collect(|| CollectedText {
+                text: span.text.clone(),
+                font: match (span.bold, span.italic) {
+                    (false, false) => &rich_text.regular,
+                    (false, true) => &rich_text.italic,
+                    (true, false) => &rich_text.bold,
+                    (true, true) => &rich_text.bold_italic,
+                }
+                .clone(),
+                size: rich_text.size,
+                character_spacing: 0.,
+                word_spacing: 0.,
+                breaking: rich_text.line_breaking,
+            });
+        }
+
+        Ok(rich_text)
+    }
 }
 
 impl SerdeElement for RichText {
@@ -116,19 +701,24 @@ impl SerdeElement for RichText {
             spans: &self.spans,
             size: self.size,
             small_size: self.small_size,
-            extra_line_height: self.extra_line_height,
+            extra_line_height: self.extra_line_height * spacing(),
+            line_spacing: self.line_spacing.scale_leading(spacing()),
+            line_breaking: self.line_breaking,
+            baseline_grid: self.baseline_grid,
             fonts: FontSet {
                 regular: &*fonts[&self.regular],
                 bold: &*fonts[&self.bold],
                 italic: &*fonts[&self.italic],
                 bold_italic: &*fonts[&self.bold_italic],
             },
+            synthetic: self.synthetic,
         });
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct VGap {
+    #[serde(deserialize_with = "expr::deserialize_f64")]
     pub gap: f64,
 }
 
@@ -138,7 +728,7 @@ impl SerdeElement for VGap {
         _: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::v_gap::VGap(self.gap));
+        callback.call(&elements::v_gap::VGap(self.gap * spacing()));
     }
 }
 
@@ -166,9 +756,16 @@ impl<E: SerdeElement> SerdeElement for HAlign<E> {
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Padding<E> {
-    pub left: f64,
-    pub right: f64,
+    /// Can be a percentage of the width, like `"10%"`.
+    pub left: Length,
+
+    /// Can be a percentage of the width, like `"10%"`.
+    pub right: Length,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
     pub top: f64,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
     pub bottom: f64,
 
     #[serde(alias = "elem")]
@@ -181,7 +778,7 @@ impl<E: SerdeElement> SerdeElement for Padding<E> {
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::padding::Padding {
+        callback.call(&ResolvedPadding {
             left: self.left,
             right: self.right,
             top: self.top,
@@ -194,16 +791,67 @@ impl<E: SerdeElement> SerdeElement for Padding<E> {
     }
 }
 
+/// A padding whose horizontal lengths are resolved against the width it gets during layout.
+struct ResolvedPadding<'a, E: Element> {
+    left: Length,
+    right: Length,
+    top: f64,
+    bottom: f64,
+    element: &'a E,
+}
+
+impl<'a, E: Element> ResolvedPadding<'a, E> {
+    fn padding(&self, width: WidthConstraint) -> elements::padding::Padding<'a, E> {
+        elements::padding::Padding {
+            left: self.left.resolve(width.max),
+            right: self.right.resolve(width.max),
+            top: self.top,
+            bottom: self.bottom,
+            element: self.element,
+        }
+    }
+}
+
+impl<'a, E: Element> Element for ResolvedPadding<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.padding(ctx.width).first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.padding(ctx.width).measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        self.padding(ctx.width).draw(ctx)
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct StyledBox<E> {
     pub element: Box<E>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
     pub padding_left: f64,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
     pub padding_right: f64,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
     pub padding_top: f64,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
     pub padding_bottom: f64,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
     pub border_radius: f64,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
     pub fill: Option<u32>,
+
     pub outline: Option<LineStyle>,
+
+    #[serde(default)]
+    pub break_style: BoxBreak,
 }
 
 impl<E: SerdeElement> SerdeElement for StyledBox<E> {
@@ -224,15 +872,37 @@ impl<E: SerdeElement> SerdeElement for StyledBox<E> {
             border_radius: self.border_radius,
             fill: self.fill,
             outline: self.outline,
+            break_style: self.break_style,
         });
     }
+
+    fn outline_thickness(&self) -> Option<f64> {
+        self.outline.map(|o| o.thickness)
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "LineInput")]
 pub struct Line {
     pub style: LineStyle,
 }
 
+#[derive(Deserialize)]
+struct LineInput {
+    #[serde(default)]
+    style: Option<LineStyle>,
+}
+
+impl TryFrom<LineInput> for Line {
+    type Error = String;
+
+    fn try_from(input: LineInput) -> Result<Self, String> {
+        Ok(Line {
+            style: or_default(input.style, "style", |d| &d.line_style)?,
+        })
+    }
+}
+
 impl SerdeElement for Line {
     fn element(
         &self,
@@ -243,10 +913,90 @@ impl SerdeElement for Line {
     }
 }
 
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(try_from = "PolyLineInput")]
+pub struct PolyLine {
+    pub points: Vec<(f64, f64)>,
+    pub style: LineStyle,
+    pub start: LineEnd,
+    pub end: LineEnd,
+}
+
+const fn default_line_end() -> LineEnd {
+    LineEnd::None
+}
+
+#[derive(Deserialize)]
+struct PolyLineInput {
+    points: Vec<(f64, f64)>,
+
+    #[serde(default)]
+    style: Option<LineStyle>,
+
+    #[serde(default = "default_line_end")]
+    start: LineEnd,
+
+    #[serde(default = "default_line_end")]
+    end: LineEnd,
+}
+
+impl TryFrom<PolyLineInput> for PolyLine {
+    type Error = String;
+
+    fn try_from(input: PolyLineInput) -> Result<Self, String> {
+        Ok(PolyLine {
+            points: input.points,
+            style: or_default(input.style, "style", |d| &d.line_style)?,
+            start: input.start,
+            end: input.end,
+        })
+    }
+}
+
+impl SerdeElement for PolyLine {
+    fn element(
+        &self,
+        _: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::poly_line::PolyLine {
+            points: &self.points,
+            style: self.style,
+            start: self.start,
+            end: self.end,
+        });
+    }
+}
+
 #[derive(Clone, Deserialize)]
 pub struct Image {
-    #[serde(rename = "path", deserialize_with = "crate::image::deserialize_image")]
-    pub image: crate::image::Image,
+    #[serde(
+        rename = "path",
+        deserialize_with = "crate::image::deserialize_shared_image"
+    )]
+    pub image: std::rc::Rc<crate::image::Image>,
+
+    #[serde(default)]
+    pub alt: Option<String>,
+
+    #[serde(default)]
+    pub caption: Option<String>,
+
+    #[serde(default)]
+    pub role: elements::image::ImageRole,
+
+    #[serde(default)]
+    pub mask: Option<elements::image::ImageMask>,
+
+    /// Clockwise, in degrees.
+    #[serde(default)]
+    pub rotation: f64,
+
+    #[serde(default)]
+    pub mirror: bool,
+
+    #[serde(default)]
+    pub tint: Option<elements::image::ImageTint>,
 }
 
 impl SerdeElement for Image {
@@ -255,15 +1005,28 @@ impl SerdeElement for Image {
         _: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::image::ImageElement { image: &self.image });
+        callback.call(&elements::image::ImageElement {
+            image: &self.image,
+            alt: self.alt.as_deref(),
+            caption: self.caption.as_deref(),
+            role: self.role,
+            mask: self.mask,
+            rotation: self.rotation,
+            mirror: self.mirror,
+            tint: self.tint,
+        });
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Rectangle {
     pub size: (f64, f64),
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
     pub fill: Option<u32>,
-    pub outline: Option<(f64, u32)>,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_outline")]
+    pub outline: Option<LineStyle>,
 }
 
 impl SerdeElement for Rectangle {
@@ -282,9 +1045,14 @@ impl SerdeElement for Rectangle {
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Circle {
+    #[serde(deserialize_with = "expr::deserialize_f64")]
     pub radius: f64,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
     pub fill: Option<u32>,
-    pub outline: Option<(f64, u32)>,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_outline")]
+    pub outline: Option<LineStyle>,
 }
 
 impl SerdeElement for Circle {
@@ -302,497 +1070,2074 @@ impl SerdeElement for Circle {
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct Column<E> {
-    pub content: Vec<E>,
-    pub gap: f64,
+pub struct Ellipse {
+    pub size: (f64, f64),
 
-    #[serde(default = "default_false")]
-    pub collapse: bool,
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    pub fill: Option<u32>,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_outline")]
+    pub outline: Option<LineStyle>,
 }
 
-impl<E: SerdeElement> SerdeElement for Column<E> {
+impl SerdeElement for Ellipse {
     fn element(
         &self,
-        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        _: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::column::Column {
-            content: |mut content| {
-                for element in &self.content {
-                    content = content.add(&SerdeElementElement { element, fonts })?;
-                }
-
-                Option::None
-            },
-            gap: self.gap,
-            collapse: self.collapse,
+        callback.call(&elements::ellipse::Ellipse {
+            size: self.size,
+            fill: self.fill,
+            outline: self.outline,
         });
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct RowElement<E> {
-    pub element: E,
-    pub flex: Flex,
-}
+pub struct Polygon {
+    pub points: Vec<(f64, f64)>,
 
-#[derive(Clone, Serialize, Deserialize)]
-pub struct Row<E> {
-    pub content: Vec<RowElement<E>>,
-    pub gap: f64,
-    pub expand: bool,
-    pub collapse: bool,
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    pub fill: Option<u32>,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_outline")]
+    pub outline: Option<LineStyle>,
 }
 
-impl<E: SerdeElement> SerdeElement for Row<E> {
+impl SerdeElement for Polygon {
     fn element(
         &self,
-        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        _: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::row::Row {
-            content: |content| {
-                for RowElement { element, flex } in &self.content {
-                    content.add(&SerdeElementElement { element, fonts }, *flex);
-                }
-            },
-            gap: self.gap,
-            expand: self.expand,
-            collapse: self.collapse,
+        callback.call(&elements::polygon::Polygon {
+            points: &self.points,
+            fill: self.fill,
+            outline: self.outline,
         });
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct BreakList<E> {
-    pub content: Vec<E>,
-    pub gap: f64,
+pub struct Arc {
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub radius: f64,
+
+    pub start_angle: f64,
+    pub end_angle: f64,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    pub fill: Option<u32>,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_outline")]
+    pub outline: Option<LineStyle>,
 }
 
-impl<E: SerdeElement> SerdeElement for BreakList<E> {
+impl SerdeElement for Arc {
     fn element(
         &self,
-        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        _: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::break_list::BreakList {
-            content: |mut content| {
-                for element in &self.content {
-                    content = content.add(&SerdeElementElement { element, fonts })?;
-                }
-
-                Option::None
-            },
-            gap: self.gap,
+        callback.call(&elements::arc::Arc {
+            radius: self.radius,
+            start_angle: self.start_angle,
+            end_angle: self.end_angle,
+            fill: self.fill,
+            outline: self.outline,
         });
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct Stack<E> {
-    pub content: Vec<E>,
-    pub expand: bool,
+#[serde(try_from = "SignatureLineInput")]
+pub struct SignatureLine {
+    pub line_style: LineStyle,
+    pub space: f64,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub date: Option<String>,
+    pub font: String,
+    pub size: f64,
+    pub color: u32,
+    pub gap: f64,
+    pub layout: SignatureLayout,
 }
 
-impl<E: SerdeElement> SerdeElement for Stack<E> {
+const fn default_signature_layout() -> SignatureLayout {
+    SignatureLayout::Stacked
+}
+
+#[derive(Deserialize)]
+struct SignatureLineInput {
+    #[serde(default)]
+    line_style: Option<LineStyle>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    space: f64,
+
+    #[serde(default)]
+    name: Option<String>,
+
+    #[serde(default)]
+    title: Option<String>,
+
+    #[serde(default)]
+    date: Option<String>,
+
+    #[serde(default)]
+    font: Option<String>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_optional_pt")]
+    size: Option<f64>,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    color: Option<u32>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_f64")]
+    gap: f64,
+
+    #[serde(default = "default_signature_layout")]
+    layout: SignatureLayout,
+}
+
+impl TryFrom<SignatureLineInput> for SignatureLine {
+    type Error = String;
+
+    fn try_from(input: SignatureLineInput) -> Result<Self, String> {
+        Ok(SignatureLine {
+            line_style: or_default(input.line_style, "line_style", |d| &d.line_style)?,
+            space: input.space,
+            name: input.name,
+            title: input.title,
+            date: input.date,
+            font: or_default(input.font, "font", |d| &d.font)?,
+            size: or_default(input.size, "size", |d| &d.size)?,
+            color: or_default(input.color, "color", |d| &d.color)?,
+            gap: input.gap,
+            layout: input.layout,
+        })
+    }
+}
+
+impl SerdeElement for SignatureLine {
     fn element(
         &self,
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::stack::Stack {
-            content: |content| {
-                for element in &self.content {
-                    content.add(&SerdeElementElement { element, fonts });
-                }
-            },
-            expand: self.expand,
+        callback.call(&elements::signature_line::SignatureLine {
+            line_style: self.line_style,
+            space: self.space,
+            name: self.name.as_deref(),
+            title: self.title.as_deref(),
+            date: self.date.as_deref(),
+            font: &*fonts[&self.font],
+            size: self.size,
+            color: self.color,
+            gap: self.gap,
+            layout: self.layout,
         });
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct TableRowElement<E> {
+pub struct CalendarEvent {
+    pub day: u32,
+    pub text: String,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    pub color: Option<u32>,
+}
+
+/// The content of a day of a [CalendarMonth], below its events.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct CalendarDay<E> {
+    pub day: u32,
     pub element: E,
-    pub flex: elements::table_row::Flex,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct TableRow<E> {
-    pub content: Vec<TableRowElement<E>>,
+#[serde(try_from = "CalendarMonthInput<E>")]
+pub struct CalendarMonth<E> {
+    pub year: i32,
+    pub month: u32,
+    pub events: Vec<CalendarEvent>,
+    pub days: Vec<CalendarDay<E>>,
+    pub first_weekday: u32,
+    pub weekday_names: [String; 7],
+    pub font: String,
+    pub size: f64,
+    pub color: u32,
     pub line_style: LineStyle,
+    pub padding: f64,
+    pub min_day_height: f64,
+}
 
-    #[serde(alias = "y_expand")]
-    pub expand: bool,
+#[derive(Deserialize)]
+struct CalendarMonthInput<E> {
+    year: i32,
+    month: u32,
+
+    #[serde(default)]
+    events: Vec<CalendarEvent>,
+
+    #[serde(default = "Vec::new")]
+    days: Vec<CalendarDay<E>>,
+
+    #[serde(default)]
+    first_weekday: u32,
+
+    weekday_names: [String; 7],
+
+    #[serde(default)]
+    font: Option<String>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_optional_pt")]
+    size: Option<f64>,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    color: Option<u32>,
+
+    #[serde(default)]
+    line_style: Option<LineStyle>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_f64")]
+    padding: f64,
+
+    #[serde(default, deserialize_with = "expr::deserialize_f64")]
+    min_day_height: f64,
 }
 
-impl<E: SerdeElement> SerdeElement for TableRow<E> {
+impl<E> TryFrom<CalendarMonthInput<E>> for CalendarMonth<E> {
+    type Error = String;
+
+    fn try_from(input: CalendarMonthInput<E>) -> Result<Self, String> {
+        if !(1..=12).contains(&input.month) {
+            return Err(format!("invalid month {}", input.month));
+        }
+
+        Ok(CalendarMonth {
+            year: input.year,
+            month: input.month,
+            events: input.events,
+            days: input.days,
+            first_weekday: input.first_weekday,
+            weekday_names: input.weekday_names,
+            font: or_default(input.font, "font", |d| &d.font)?,
+            size: or_default(input.size, "size", |d| &d.size)?,
+            color: or_default(input.color, "color", |d| &d.color)?,
+            line_style: or_default(input.line_style, "line_style", |d| &d.line_style)?,
+            padding: input.padding,
+            min_day_height: input.min_day_height,
+        })
+    }
+}
+
+impl<E: SerdeElement> SerdeElement for CalendarMonth<E> {
     fn element(
         &self,
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::table_row::TableRow {
-            content: |content| {
-                for TableRowElement { element, flex } in &self.content {
-                    content.add(&SerdeElementElement { element, fonts }, *flex);
+        let events: Vec<_> = self
+            .events
+            .iter()
+            .map(|event| elements::calendar::CalendarEvent {
+                day: event.day,
+                text: &event.text,
+                color: event.color.unwrap_or(self.color),
+            })
+            .collect();
+
+        callback.call(&elements::calendar::CalendarMonth {
+            year: self.year,
+            month: self.month,
+            events: &events,
+            day_content: |day, mut content| {
+                for CalendarDay { element, .. } in self.days.iter().filter(|d| d.day == day) {
+                    content = content.add(&SerdeElementElement { element, fonts })?;
                 }
+
+                Option::None
             },
+            first_weekday: self.first_weekday,
+            weekday_names: self.weekday_names.each_ref().map(|name| &name[..]),
+            font: &*fonts[&self.font],
+            size: self.size,
+            color: self.color,
             line_style: self.line_style,
-            expand: self.expand,
+            padding: self.padding,
+            min_day_height: self.min_day_height,
         });
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct Titled<E> {
-    pub title: Box<E>,
-    pub content: Box<E>,
+pub struct Symbol {
+    pub kind: SymbolKind,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub size: f64,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub thickness: f64,
+
+    #[serde(deserialize_with = "color::deserialize_color")]
+    pub color: u32,
+}
+
+impl SerdeElement for Symbol {
+    fn element(
+        &self,
+        _: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::symbol::Symbol {
+            kind: self.kind,
+            size: self.size,
+            thickness: self.thickness,
+            color: self.color,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct StarRating {
+    pub rating: f64,
+    pub max: u8,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub size: f64,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
     pub gap: f64,
 
-    #[serde(default = "default_false")]
-    pub collapse_on_empty_content: bool,
+    #[serde(deserialize_with = "color::deserialize_color")]
+    pub color: u32,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub thickness: f64,
 }
 
-impl<E: SerdeElement> SerdeElement for Titled<E> {
+impl SerdeElement for StarRating {
     fn element(
         &self,
-        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        _: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::titled::Titled {
-            title: &SerdeElementElement {
-                element: &*self.title,
-                fonts,
-            },
-            content: &SerdeElementElement {
-                element: &*self.content,
-                fonts,
-            },
+        callback.call(&elements::symbol::StarRating {
+            rating: self.rating,
+            max: self.max,
+            size: self.size,
             gap: self.gap,
-            collapse_on_empty_content: self.collapse_on_empty_content,
+            color: self.color,
+            thickness: self.thickness,
         });
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct TitleOrBreak<E> {
-    pub title: Box<E>,
-    pub content: Box<E>,
+pub struct Column<E> {
+    pub content: Vec<E>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
     pub gap: f64,
 
     #[serde(default = "default_false")]
-    pub collapse_on_empty_content: bool,
+    pub collapse: bool,
+
+    #[serde(default)]
+    pub separator: Option<LineStyle>,
+
+    #[serde(default = "default_false")]
+    pub outer_gaps: bool,
+
+    #[serde(default = "default_false")]
+    pub gap_after_break: bool,
+
+    #[serde(default)]
+    pub avoid_single_line_tail: Option<f64>,
+
+    #[serde(default)]
+    pub min_items_before_break: Option<u32>,
+
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    pub background: Option<u32>,
+
+    /// Elements with an outline that follow each other, like bordered table rows, overlap by the
+    /// thinner of their outlines. With a gap of zero they share a single border as thick as the
+    /// thicker one instead of doubling up. See [elements::column::Column::border_collapse].
+    #[serde(default = "default_false")]
+    pub border_collapse: bool,
 }
 
-impl<E: SerdeElement> SerdeElement for TitleOrBreak<E> {
+impl<E: SerdeElement> SerdeElement for Column<E> {
     fn element(
         &self,
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::title_or_break::TitleOrBreak {
-            title: &SerdeElementElement {
-                element: &*self.title,
-                fonts,
+        callback.call(&elements::column::Column {
+            content: |mut content| {
+                for element in &self.content {
+                    let serde_element = SerdeElementElement { element, fonts };
+
+                    content = match (element.v_expand_weight(), element.outline_thickness()) {
+                        (Some(weight), Some(outline)) => {
+                            content.add_expand_outlined(&serde_element, weight, outline)?
+                        }
+                        (Some(weight), Option::None) => {
+                            content.add_expand(&serde_element, weight)?
+                        }
+                        (Option::None, Some(outline)) => {
+                            content.add_outlined(&serde_element, outline)?
+                        }
+                        (Option::None, Option::None) => content.add(&serde_element)?,
+                    };
+                }
+
+                Option::None
             },
-            content: &SerdeElementElement {
-                element: &*self.content,
+            gap: self.gap * spacing(),
+            collapse: self.collapse,
+            separator: self.separator,
+            outer_gaps: self.outer_gaps,
+            gap_after_break: self.gap_after_break,
+            avoid_single_line_tail: self.avoid_single_line_tail,
+            min_items_before_break: self.min_items_before_break,
+            background: self.background,
+            border_collapse: self.border_collapse,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PaginateBlock<E> {
+    pub element: E,
+
+    #[serde(default)]
+    pub group: Option<u32>,
+
+    #[serde(default)]
+    pub penalty: f64,
+
+    /// Never breaks before the block, unless the content doesn't fit otherwise.
+    #[serde(default = "default_false")]
+    pub keep_with_previous: bool,
+}
+
+/// Experimental, see [elements::paginate::Paginate].
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Paginate<E> {
+    pub blocks: Vec<PaginateBlock<E>>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_f64")]
+    pub gap: f64,
+
+    #[serde(default)]
+    pub penalties: elements::paginate::Penalties,
+}
+
+impl<E: SerdeElement> SerdeElement for Paginate<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        let elements = self
+            .blocks
+            .iter()
+            .map(|block| SerdeElementElement {
+                element: &block.element,
                 fonts,
-            },
-            gap: self.gap,
-            collapse_on_empty_content: self.collapse_on_empty_content,
+            })
+            .collect::<Vec<_>>();
+
+        let blocks = self
+            .blocks
+            .iter()
+            .zip(&elements)
+            .map(|(block, element)| elements::paginate::Block {
+                element,
+                group: block.group,
+                penalty: if block.keep_with_previous {
+                    f64::INFINITY
+                } else {
+                    block.penalty
+                },
+            })
+            .collect::<Vec<_>>();
+
+        callback.call(&elements::paginate::Paginate {
+            blocks: &blocks,
+            gap: self.gap * spacing(),
+            penalties: self.penalties,
         });
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct ChangingTitle<E> {
-    pub first_title: Box<E>,
+pub struct RowElement<E> {
+    pub element: E,
+    pub flex: Flex,
+}
 
-    #[serde(alias = "second_title")]
-    pub remaining_title: Box<E>,
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Row<E> {
+    pub content: Vec<RowElement<E>>,
 
-    pub content: Box<E>,
+    #[serde(deserialize_with = "expr::deserialize_f64")]
     pub gap: f64,
 
-    #[serde(default = "default_false")]
+    pub expand: bool,
     pub collapse: bool,
+
+    /// [Rtl](Direction::Rtl) lays out the content from right to left.
+    #[serde(default = "defaults::default_direction")]
+    pub direction: Direction,
 }
 
-impl<E: SerdeElement> SerdeElement for ChangingTitle<E> {
+impl<E: SerdeElement> SerdeElement for Row<E> {
     fn element(
         &self,
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::changing_title::ChangingTitle {
-            first_title: &SerdeElementElement {
-                element: &*self.first_title,
-                fonts,
-            },
-            remaining_title: &SerdeElementElement {
-                element: &*self.remaining_title,
-                fonts,
+        callback.call(&elements::row::Row {
+            content: |content| {
+                for RowElement { element, flex } in self.direction.order(&self.content) {
+                    content.add(&SerdeElementElement { element, fonts }, *flex);
+                }
             },
-            content: &SerdeElementElement {
-                element: &*self.content,
+            gap: self.gap,
+            expand: self.expand,
+            collapse: self.collapse,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BreakList<E> {
+    pub content: Vec<E>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub gap: f64,
+}
+
+impl<E: SerdeElement> SerdeElement for BreakList<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::break_list::BreakList {
+            content: |mut content| {
+                for element in &self.content {
+                    content = content.add(&SerdeElementElement { element, fonts })?;
+                }
+
+                Option::None
+            },
+            gap: self.gap,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Stack<E> {
+    pub content: Vec<E>,
+    pub expand: bool,
+}
+
+impl<E: SerdeElement> SerdeElement for Stack<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::stack::Stack {
+            content: |content| {
+                for element in &self.content {
+                    content.add(&SerdeElementElement { element, fonts });
+                }
+            },
+            expand: self.expand,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TableRowElement<E> {
+    pub element: E,
+    pub flex: elements::table_row::Flex,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TableRow<E> {
+    pub content: Vec<TableRowElement<E>>,
+
+    /// The lines between the cells.
+    pub line_style: LineStyle,
+
+    #[serde(alias = "y_expand")]
+    pub expand: bool,
+
+    /// A background behind the whole row.
+    #[serde(default, deserialize_with = "color::deserialize_optional_color")]
+    pub fill: Option<u32>,
+
+    /// A border around the whole row. Rows in a column each get their own, so for a table with
+    /// single lines between the rows, the column's gap can be the negative thickness.
+    #[serde(default)]
+    pub outline: Option<LineStyle>,
+
+    /// [Rtl](Direction::Rtl) lays out the cells from right to left.
+    #[serde(default = "defaults::default_direction")]
+    pub direction: Direction,
+
+    /// Cells with an outline next to each other share a single border instead of doubling up
+    /// around the line between them. See [elements::table_row::TableRow::border_collapse].
+    #[serde(default = "default_false")]
+    pub border_collapse: bool,
+}
+
+impl<E: SerdeElement> SerdeElement for TableRow<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        let row = elements::table_row::TableRow {
+            content: |content| {
+                for TableRowElement { element, flex } in self.direction.order(&self.content) {
+                    let serde_element = SerdeElementElement { element, fonts };
+
+                    match element.outline_thickness() {
+                        Some(outline) => content.add_outlined(&serde_element, *flex, outline),
+                        Option::None => content.add(&serde_element, *flex),
+                    }
+                }
+            },
+            line_style: self.line_style,
+            expand: self.expand,
+            border_collapse: self.border_collapse,
+        };
+
+        if self.fill.is_some() || self.outline.is_some() {
+            callback.call(&elements::styled_box::StyledBox {
+                fill: self.fill,
+                outline: self.outline,
+                ..elements::styled_box::StyledBox::new(&row)
+            });
+        } else {
+            callback.call(&row);
+        }
+    }
+
+    fn outline_thickness(&self) -> Option<f64> {
+        self.outline.map(|o| o.thickness)
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EqualRows<E> {
+    pub rows: Vec<E>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_f64")]
+    pub gap: f64,
+}
+
+impl<E: SerdeElement> SerdeElement for EqualRows<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::equal_rows::EqualRows {
+            content: |content| {
+                for element in &self.rows {
+                    content.add(&SerdeElementElement { element, fonts });
+                }
+            },
+            gap: self.gap,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TableGroup<E> {
+    pub header: Box<E>,
+
+    /// Shown after page breaks inside of the group. Defaults to the header.
+    #[serde(default)]
+    pub continued_header: Option<Box<E>>,
+
+    pub content: Box<E>,
+
+    #[serde(default)]
+    pub footer: Option<Box<E>>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub gap: f64,
+}
+
+impl<E: SerdeElement> SerdeElement for TableGroup<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        let footer = self.footer.as_ref().map(|footer| SerdeElementElement {
+            element: &**footer,
+            fonts,
+        });
+
+        callback.call(&elements::table_group::TableGroup {
+            header: &SerdeElementElement {
+                element: &*self.header,
+                fonts,
+            },
+            continued_header: &SerdeElementElement {
+                element: &**self.continued_header.as_ref().unwrap_or(&self.header),
+                fonts,
+            },
+            content: &SerdeElementElement {
+                element: &*self.content,
+                fonts,
+            },
+            footer: footer.as_ref(),
+            gap: self.gap,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Titled<E> {
+    pub title: Box<E>,
+    pub content: Box<E>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub gap: f64,
+
+    #[serde(default = "default_false")]
+    pub collapse_on_empty_content: bool,
+}
+
+impl<E: SerdeElement> SerdeElement for Titled<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::titled::Titled {
+            title: &SerdeElementElement {
+                element: &*self.title,
+                fonts,
+            },
+            content: &SerdeElementElement {
+                element: &*self.content,
+                fonts,
+            },
+            gap: self.gap,
+            collapse_on_empty_content: self.collapse_on_empty_content,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TitleOrBreak<E> {
+    pub title: Box<E>,
+    pub content: Box<E>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub gap: f64,
+
+    #[serde(default = "default_false")]
+    pub collapse_on_empty_content: bool,
+}
+
+impl<E: SerdeElement> SerdeElement for TitleOrBreak<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::title_or_break::TitleOrBreak {
+            title: &SerdeElementElement {
+                element: &*self.title,
+                fonts,
+            },
+            content: &SerdeElementElement {
+                element: &*self.content,
+                fonts,
+            },
+            gap: self.gap,
+            collapse_on_empty_content: self.collapse_on_empty_content,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ChangingTitle<E> {
+    pub first_title: Box<E>,
+
+    #[serde(alias = "second_title")]
+    pub remaining_title: Box<E>,
+
+    pub content: Box<E>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub gap: f64,
+
+    #[serde(default = "default_false")]
+    pub collapse: bool,
+}
+
+impl<E: SerdeElement> SerdeElement for ChangingTitle<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::changing_title::ChangingTitle {
+            first_title: &SerdeElementElement {
+                element: &*self.first_title,
+                fonts,
+            },
+            remaining_title: &SerdeElementElement {
+                element: &*self.remaining_title,
+                fonts,
+            },
+            content: &SerdeElementElement {
+                element: &*self.content,
+                fonts,
+            },
+            gap: self.gap,
+            collapse: self.collapse,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RepeatAfterBreak<E> {
+    pub title: Box<E>,
+    pub content: Box<E>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub gap: f64,
+
+    #[serde(default = "default_false")]
+    pub collapse_on_empty_content: bool,
+}
+
+impl<E: SerdeElement> SerdeElement for RepeatAfterBreak<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::repeat_after_break::RepeatAfterBreak {
+            title: &SerdeElementElement {
+                element: &*self.title,
+                fonts,
+            },
+            content: &SerdeElementElement {
+                element: &*self.content,
+                fonts,
+            },
+            gap: self.gap,
+            collapse_on_empty_content: self.collapse_on_empty_content,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RepeatBottom<E> {
+    pub content: Box<E>,
+    pub bottom: Box<E>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub gap: f64,
+
+    #[serde(default = "default_false")]
+    pub collapse: bool,
+}
+
+impl<E: SerdeElement> SerdeElement for RepeatBottom<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::repeat_bottom::RepeatBottom {
+            content: &SerdeElementElement {
+                element: &*self.content,
+                fonts,
+            },
+            bottom: &SerdeElementElement {
+                element: &*self.bottom,
+                fonts,
+            },
+            gap: self.gap,
+            collapse: self.collapse,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PinBelow<E> {
+    pub content: Box<E>,
+    pub pinned_element: Box<E>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub gap: f64,
+
+    #[serde(default = "default_false")]
+    pub collapse: bool,
+}
+
+impl<E: SerdeElement> SerdeElement for PinBelow<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::pin_below::PinBelow {
+            content: &SerdeElementElement {
+                element: &*self.content,
+                fonts,
+            },
+            pinned_element: &SerdeElementElement {
+                element: &*self.pinned_element,
+                fonts,
+            },
+            gap: self.gap,
+            collapse: self.collapse,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ForceBreak;
+
+impl SerdeElement for ForceBreak {
+    fn element(
+        &self,
+        _: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::force_break::ForceBreak);
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BreakWhole<E> {
+    pub element: Box<E>,
+}
+
+impl<E: SerdeElement> SerdeElement for BreakWhole<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::break_whole::BreakWhole(&SerdeElementElement {
+            element: &*self.element,
+            fonts,
+        }));
+    }
+
+    fn outline_thickness(&self) -> Option<f64> {
+        self.element.outline_thickness()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MultiColumn<E> {
+    pub element: Box<E>,
+    pub columns: u32,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub gap: f64,
+
+    #[serde(default = "default_false")]
+    pub balance: bool,
+}
+
+impl<E: SerdeElement> SerdeElement for MultiColumn<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::multi_column::MultiColumn {
+            element: &SerdeElementElement {
+                element: &*self.element,
+                fonts,
+            },
+            columns: self.columns,
+            gap: self.gap,
+            balance: self.balance,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MinFirstHeight<E> {
+    pub element: Box<E>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub min_first_height: f64,
+}
+
+impl<E: SerdeElement> SerdeElement for MinFirstHeight<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::min_first_height::MinFirstHeight {
+            element: &SerdeElementElement {
+                element: &*self.element,
+                fonts,
+            },
+            min_first_height: self.min_first_height,
+        });
+    }
+
+    fn outline_thickness(&self) -> Option<f64> {
+        self.element.outline_thickness()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MinBreakHeight<E> {
+    pub element: Box<E>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub min: f64,
+}
+
+impl<E: SerdeElement> SerdeElement for MinBreakHeight<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::min_break_height::MinBreakHeight {
+            element: &SerdeElementElement {
+                element: &*self.element,
+                fonts,
+            },
+            min: self.min,
+        });
+    }
+
+    fn outline_thickness(&self) -> Option<f64> {
+        self.element.outline_thickness()
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AlignLocationBottom<E> {
+    pub element: Box<E>,
+}
+
+impl<E: SerdeElement> SerdeElement for AlignLocationBottom<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::align_location_bottom::AlignLocationBottom(
+            &SerdeElementElement {
+                element: &*self.element,
+                fonts,
+            },
+        ));
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct AlignPreferredHeightBottom<E> {
+    pub element: Box<E>,
+}
+
+impl<E: SerdeElement> SerdeElement for AlignPreferredHeightBottom<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(
+            &elements::align_preferred_height_bottom::AlignPreferredHeightBottom(
+                &SerdeElementElement {
+                    element: &*self.element,
+                    fonts,
+                },
+            ),
+        );
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ExpandToPreferredHeight<E> {
+    pub element: Box<E>,
+}
+
+impl<E: SerdeElement> SerdeElement for ExpandToPreferredHeight<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(
+            &elements::expand_to_preferred_height::ExpandToPreferredHeight(&SerdeElementElement {
+                element: &*self.element,
+                fonts,
+            }),
+        );
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FillRemaining<E> {
+    pub element: Box<E>,
+}
+
+impl<E: SerdeElement> SerdeElement for FillRemaining<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::fill_remaining::FillRemaining(
+            &SerdeElementElement {
+                element: &*self.element,
+                fonts,
+            },
+        ));
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct FadeOut<E> {
+    pub element: Box<E>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub length: f64,
+
+    #[serde(deserialize_with = "color::deserialize_color")]
+    pub color: u32,
+}
+
+impl<E: SerdeElement> SerdeElement for FadeOut<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::fade_out::FadeOut {
+            element: &SerdeElementElement {
+                element: &*self.element,
+                fonts,
+            },
+            length: self.length,
+            color: self.color,
+        });
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ChangeBar<E> {
+    pub element: Box<E>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub offset: f64,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub width: f64,
+
+    #[serde(deserialize_with = "color::deserialize_color")]
+    pub color: u32,
+}
+
+impl<E: SerdeElement> SerdeElement for ChangeBar<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&elements::change_bar::ChangeBar {
+            element: &SerdeElementElement {
+                element: &*self.element,
                 fonts,
             },
-            gap: self.gap,
-            collapse: self.collapse,
+            offset: self.offset,
+            width: self.width,
+            color: self.color,
         });
     }
 }
 
+/// Gets a share of the leftover preferred height relative to its weight when it's an element of a
+/// [Column]. Anywhere else it's just the element.
 #[derive(Clone, Serialize, Deserialize)]
-pub struct RepeatAfterBreak<E> {
-    pub title: Box<E>,
-    pub content: Box<E>,
-    pub gap: f64,
-
-    #[serde(default = "default_false")]
-    pub collapse_on_empty_content: bool,
+pub struct VExpand<E> {
+    pub weight: u8,
+    pub element: Box<E>,
 }
 
-impl<E: SerdeElement> SerdeElement for RepeatAfterBreak<E> {
+impl<E: SerdeElement> SerdeElement for VExpand<E> {
     fn element(
         &self,
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::repeat_after_break::RepeatAfterBreak {
-            title: &SerdeElementElement {
-                element: &*self.title,
-                fonts,
-            },
-            content: &SerdeElementElement {
-                element: &*self.content,
-                fonts,
-            },
-            gap: self.gap,
-            collapse_on_empty_content: self.collapse_on_empty_content,
-        });
+        self.element.element(fonts, callback);
+    }
+
+    fn v_expand_weight(&self) -> Option<u8> {
+        Some(self.weight)
+    }
+
+    fn outline_thickness(&self) -> Option<f64> {
+        self.element.outline_thickness()
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct RepeatBottom<E> {
+pub struct Continued<E> {
     pub content: Box<E>,
-    pub bottom: Box<E>,
-    pub gap: f64,
 
-    #[serde(default = "default_false")]
-    pub collapse: bool,
+    #[serde(default)]
+    pub bottom: Option<Box<E>>,
+
+    #[serde(default)]
+    pub top: Option<Box<E>>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_f64")]
+    pub gap: f64,
 }
 
-impl<E: SerdeElement> SerdeElement for RepeatBottom<E> {
+impl<E: SerdeElement> SerdeElement for Continued<E> {
     fn element(
         &self,
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::repeat_bottom::RepeatBottom {
+        let bottom = self
+            .bottom
+            .as_deref()
+            .map(|element| SerdeElementElement { element, fonts });
+        let top = self
+            .top
+            .as_deref()
+            .map(|element| SerdeElementElement { element, fonts });
+
+        callback.call(&elements::continued::Continued {
             content: &SerdeElementElement {
                 element: &*self.content,
                 fonts,
             },
-            bottom: &SerdeElementElement {
-                element: &*self.bottom,
-                fonts,
-            },
+            bottom: bottom.as_ref().map(|e| e as &dyn Element),
+            top: top.as_ref().map(|e| e as &dyn Element),
             gap: self.gap,
-            collapse: self.collapse,
         });
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct PinBelow<E> {
-    pub content: Box<E>,
-    pub pinned_element: Box<E>,
-    pub gap: f64,
+pub struct ShrinkToFit<E> {
+    pub element: Box<E>,
 
-    #[serde(default = "default_false")]
-    pub collapse: bool,
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    pub min_height: f64,
 }
 
-impl<E: SerdeElement> SerdeElement for PinBelow<E> {
+impl<E: SerdeElement> SerdeElement for ShrinkToFit<E> {
     fn element(
         &self,
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::pin_below::PinBelow {
-            content: &SerdeElementElement {
-                element: &*self.content,
-                fonts,
-            },
-            pinned_element: &SerdeElementElement {
-                element: &*self.pinned_element,
+        callback.call(&elements::shrink_to_fit::ShrinkToFit {
+            element: &SerdeElementElement {
+                element: &*self.element,
                 fonts,
             },
-            gap: self.gap,
-            collapse: self.collapse,
+            min_height: self.min_height,
         });
     }
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-pub struct ForceBreak;
+thread_local! {
+    static SPACING: Cell<f64> = const { Cell::new(1.) };
+}
 
-impl SerdeElement for ForceBreak {
-    fn element(
-        &self,
-        _: &impl for<'a> Index<&'a str, Output = Font>,
-        callback: impl CompositeElementCallback,
-    ) {
-        callback.call(&elements::force_break::ForceBreak);
+/// The factor a [BalanceLastPage] tightens the gaps of [Column], [Paginate] and [VGap] elements
+/// inside of it with, and the leading of [Text] and [RichText] elements, see
+/// [LineSpacing::scale_leading].
+fn spacing() -> f64 {
+    SPACING.with(Cell::get)
+}
+
+/// Builds the element with [spacing] multiplied by the factor.
+struct Spacing<E: Element> {
+    factor: f64,
+    element: E,
+}
+
+impl<E: Element> Spacing<E> {
+    fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        scoped(&SPACING, spacing() * self.factor, f).0
+    }
+}
+
+impl<E: Element> Element for Spacing<E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.scope(|| self.element.first_location_usage(ctx))
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.scope(|| self.element.measure(ctx))
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        self.scope(|| self.element.draw(ctx))
     }
 }
 
+const fn default_min_fill() -> f64 {
+    0.2
+}
+
+const fn default_min_factor() -> f64 {
+    0.8
+}
+
+const fn default_steps() -> u32 {
+    4
+}
+
+/// The gaps and the leading of text are tightened, see [spacing].
 #[derive(Clone, Serialize, Deserialize)]
-pub struct BreakWhole<E> {
+pub struct BalanceLastPage<E> {
     pub element: Box<E>,
+
+    #[serde(default = "default_min_fill")]
+    pub min_fill: f64,
+
+    #[serde(default = "default_min_factor")]
+    pub min_factor: f64,
+
+    #[serde(default = "default_steps")]
+    pub steps: u32,
+
+    #[serde(skip)]
+    factors: BalanceFactors,
 }
 
-impl<E: SerdeElement> SerdeElement for BreakWhole<E> {
+/// Like [CachedMeasurements], the chosen factors are kept between builds of the element and clones
+/// start without them.
+#[derive(Default)]
+struct BalanceFactors(elements::balance_last_page::Factors);
+
+impl Clone for BalanceFactors {
+    fn clone(&self) -> Self {
+        BalanceFactors::default()
+    }
+}
+
+impl<E: SerdeElement> SerdeElement for BalanceLastPage<E> {
     fn element(
         &self,
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::break_whole::BreakWhole(&SerdeElementElement {
-            element: &*self.element,
-            fonts,
-        }));
+        callback.call(&elements::balance_last_page::BalanceLastPage::with_factors(
+            |factor| Spacing {
+                factor,
+                element: SerdeElementElement {
+                    element: &*self.element,
+                    fonts,
+                },
+            },
+            self.min_fill,
+            self.min_factor,
+            self.steps,
+            &self.factors.0,
+        ));
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct MinFirstHeight<E> {
+pub struct Rotate<E> {
     pub element: Box<E>,
-    pub min_first_height: f64,
+    pub rotation: Rotation,
 }
 
-impl<E: SerdeElement> SerdeElement for MinFirstHeight<E> {
+impl<E: SerdeElement> SerdeElement for Rotate<E> {
     fn element(
         &self,
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::min_first_height::MinFirstHeight {
+        callback.call(&elements::rotate::Rotate {
             element: &SerdeElementElement {
                 element: &*self.element,
                 fonts,
             },
-            min_first_height: self.min_first_height,
+            rotation: self.rotation,
         });
     }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
-pub struct AlignLocationBottom<E> {
+pub struct Trace<E> {
+    pub id: String,
     pub element: Box<E>,
 }
 
-impl<E: SerdeElement> SerdeElement for AlignLocationBottom<E> {
+impl<E: SerdeElement> SerdeElement for Trace<E> {
     fn element(
         &self,
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::align_location_bottom::AlignLocationBottom(
-            &SerdeElementElement {
+        callback.call(&elements::trace::Trace {
+            id: &self.id,
+            element: &SerdeElementElement {
                 element: &*self.element,
                 fonts,
             },
-        ));
+        });
+    }
+
+    fn outline_thickness(&self) -> Option<f64> {
+        self.element.outline_thickness()
     }
 }
 
+/// Remembers the measurements of the element and stores its drawings only once in the document
+/// when they repeat, e.g. in a header. See [elements::cached]. The element is still drawn every
+/// time, only its measurements are skipped.
 #[derive(Clone, Serialize, Deserialize)]
-pub struct AlignPreferredHeightBottom<E> {
+pub struct Cached<E> {
     pub element: Box<E>,
-}
 
-impl<E: SerdeElement> SerdeElement for AlignPreferredHeightBottom<E> {
-    fn element(
-        &self,
-        fonts: &impl for<'a> Index<&'a str, Output = Font>,
-        callback: impl CompositeElementCallback,
-    ) {
-        callback.call(
-            &elements::align_preferred_height_bottom::AlignPreferredHeightBottom(
-                &SerdeElementElement {
-                    element: &*self.element,
-                    fonts,
-                },
-            ),
-        );
-    }
+    #[serde(skip)]
+    measurements: CachedMeasurements,
 }
 
-#[derive(Clone, Serialize, Deserialize)]
-pub struct ExpandToPreferredHeight<E> {
-    pub element: Box<E>,
+/// Deserialized elements are built again for every measurement, so the measurements are kept
+/// here. A document is cloned for every build and the fonts and facts can differ between builds,
+/// so clones start without measurements.
+#[derive(Default)]
+struct CachedMeasurements(elements::cached::Measurements);
+
+impl Clone for CachedMeasurements {
+    fn clone(&self) -> Self {
+        CachedMeasurements::default()
+    }
 }
 
-impl<E: SerdeElement> SerdeElement for ExpandToPreferredHeight<E> {
+impl<E: SerdeElement> SerdeElement for Cached<E> {
     fn element(
         &self,
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(
-            &elements::expand_to_preferred_height::ExpandToPreferredHeight(&SerdeElementElement {
+        callback.call(&elements::cached::Cached::with_measurements(
+            &SerdeElementElement {
                 element: &*self.element,
                 fonts,
-            }),
-        );
+            },
+            &self.measurements.0,
+        ));
+    }
+
+    fn v_expand_weight(&self) -> Option<u8> {
+        self.element.v_expand_weight()
+    }
+
+    fn outline_thickness(&self) -> Option<f64> {
+        self.element.outline_thickness()
     }
 }
 
+/// Marks the text of the element as being in another language than the document, like
+/// `{"Lang":{"lang":"fr","element":..}}`. See [elements::lang].
 #[derive(Clone, Serialize, Deserialize)]
-pub struct ShrinkToFit<E> {
+pub struct Lang<E> {
+    pub lang: String,
     pub element: Box<E>,
-    pub min_height: f64,
 }
 
-impl<E: SerdeElement> SerdeElement for ShrinkToFit<E> {
+impl<E: SerdeElement> SerdeElement for Lang<E> {
     fn element(
         &self,
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::shrink_to_fit::ShrinkToFit {
+        callback.call(&elements::lang::Lang {
+            lang: &self.lang,
             element: &SerdeElementElement {
                 element: &*self.element,
                 fonts,
             },
-            min_height: self.min_height,
         });
     }
+
+    fn v_expand_weight(&self) -> Option<u8> {
+        self.element.v_expand_weight()
+    }
+
+    fn outline_thickness(&self) -> Option<f64> {
+        self.element.outline_thickness()
+    }
 }
 
+/// Attaches arbitrary JSON metadata to the element. See [elements::meta].
 #[derive(Clone, Serialize, Deserialize)]
-pub struct Rotate<E> {
+pub struct Meta<E> {
+    pub meta: serde_json::Value,
     pub element: Box<E>,
-    pub rotation: Rotation,
 }
 
-impl<E: SerdeElement> SerdeElement for Rotate<E> {
+impl<E: SerdeElement> SerdeElement for Meta<E> {
     fn element(
         &self,
         fonts: &impl for<'a> Index<&'a str, Output = Font>,
         callback: impl CompositeElementCallback,
     ) {
-        callback.call(&elements::rotate::Rotate {
+        callback.call(&elements::meta::Meta {
+            meta: &self.meta,
             element: &SerdeElementElement {
                 element: &*self.element,
                 fonts,
             },
-            rotation: self.rotation,
         });
     }
+
+    fn v_expand_weight(&self) -> Option<u8> {
+        self.element.v_expand_weight()
+    }
+
+    fn outline_thickness(&self) -> Option<f64> {
+        self.element.outline_thickness()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::annotations::MarkupKind;
+    use crate::serde_elements::ElementValue;
+
+    #[test]
+    fn test_nested_spans() {
+        let json = r##"{
+            "size": 10,
+            "regular": "r",
+            "bold": "b",
+            "italic": "i",
+            "bold_italic": "bi",
+            "spans": [
+                { "text": "plain " },
+                { "color": "red", "spans": [
+                    { "text": "red " },
+                    { "bold": true, "spans": [{ "text": "bold red" }] },
+                    { "text": "blue", "color": "#0000ff" }
+                ]},
+                { "underline": true, "underline_color": "grey", "spans": [
+                    { "text": "link", "underline_thickness": "0.5pt" }
+                ]}
+            ]
+        }"##;
+
+        let rich_text = serde_json::from_str::<RichText>(json).unwrap();
+
+        let spans = rich_text
+            .spans
+            .iter()
+            .map(|s| (s.text.as_str(), s.bold, s.color))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            spans,
+            [
+                ("plain ", false, 0x00_00_00_FF),
+                ("red ", false, 0xFF_00_00_FF),
+                ("bold red", true, 0xFF_00_00_FF),
+                ("blue", false, 0x00_00_FF_FF),
+                ("link", false, 0x00_00_00_FF),
+            ]
+        );
+
+        let link = &rich_text.spans[4];
+        assert_eq!(link.underline_color, color::parse_color("grey"));
+        assert!((link.underline_thickness.unwrap() - 0.5 * 25.4 / 72.).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_span_markup() {
+        let json = r##"{
+            "size": 10,
+            "regular": "r",
+            "bold": "b",
+            "italic": "i",
+            "bold_italic": "bi",
+            "spans": [
+                { "text": "plain " },
+                { "markup": { "kind": "StrikeOut", "color": "#ff0000" }, "spans": [
+                    { "text": "struck " },
+                    { "text": "marked", "markup": { "kind": "Highlight", "color": "yellow" } }
+                ]}
+            ]
+        }"##;
+
+        let rich_text = serde_json::from_str::<RichText>(json).unwrap();
+
+        let markup = rich_text
+            .spans
+            .iter()
+            .map(|s| s.markup.map(|m| (m.kind, m.color)))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            markup,
+            [
+                None,
+                Some((MarkupKind::StrikeOut, 0xFF_00_00_FF)),
+                Some((MarkupKind::Highlight, color::parse_color("yellow").unwrap())),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_collect_rich_text() {
+        let json = r##"{
+            "size": 10,
+            "regular": "r",
+            "bold": "b",
+            "italic": "i",
+            "bold_italic": "bi",
+            "spans": [
+                { "text": "plain " },
+                { "text": "" },
+                { "bold": true, "spans": [{ "text": "bold " }, { "text": "both", "italic": true }] }
+            ]
+        }"##;
+
+        let (rich_text, texts) = shaping::collect_texts(|| serde_json::from_str::<RichText>(json));
+        assert!(rich_text.is_ok());
+
+        let texts = texts
+            .iter()
+            .map(|t| (&t.text[..], &t.font[..], t.size))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            texts,
+            [
+                ("plain ", "r", 10.),
+                ("bold ", "b", 10.),
+                ("both", "bi", 10.)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_spans() {
+        let error = |spans: &str| {
+            let json = format!(
+                r#"{{ "size": 10, "regular": "r", "bold": "b", "italic": "i", "bold_italic": "bi",
+                    "spans": [{spans}] }}"#
+            );
+
+            match serde_json::from_str::<RichText>(&json) {
+                Ok(_) => panic!("{spans} should be invalid"),
+                Err(error) => error.to_string(),
+            }
+        };
+
+        assert!(error(r#"{ "text": "a", "colour": "red" }"#).contains("`colour`"));
+        assert!(error(r#"{ "spans": [{ "text": "a", "bolt": true }] }"#).contains("`bolt`"));
+        assert!(error(r#"{ "text": "a", "spans": [] }"#).contains("either"));
+        assert!(error(r#"{ "bold": true }"#).contains("either"));
+        assert!(error(r#"{ "ruby": "a", "spans": [] }"#).contains("`ruby`"));
+    }
+
+    #[test]
+    fn test_markup() {
+        let json = r##"{
+            "size": 10,
+            "regular": "r",
+            "bold": "b",
+            "italic": "i",
+            "bold_italic": "bi",
+            "markup": "plain **bold**"
+        }"##;
+
+        let rich_text = serde_json::from_str::<RichText>(json).unwrap();
+
+        let spans = rich_text
+            .spans
+            .iter()
+            .map(|s| (s.text.as_str(), s.bold))
+            .collect::<Vec<_>>();
+
+        assert_eq!(spans, [("plain ", false), ("bold", true)]);
+
+        assert!(serde_json::from_str::<RichText>(r#"{ "size": 10, "regular": "r" }"#).is_err());
+    }
+
+    #[test]
+    fn test_highlighted_code() {
+        let json = r##"{
+            "size": 10,
+            "regular": "r",
+            "bold": "b",
+            "italic": "i",
+            "bold_italic": "bi",
+            "code": { "code": "let a = 1;", "language": "rs" }
+        }"##;
+
+        let rich_text = serde_json::from_str::<RichText>(json);
+
+        if cfg!(feature = "highlight") {
+            let text: String = rich_text
+                .unwrap()
+                .spans
+                .iter()
+                .map(|s| &s.text[..])
+                .collect();
+            assert_eq!(text, "let a = 1;");
+        } else {
+            assert!(rich_text.is_err());
+        }
+    }
+
+    #[test]
+    fn test_synthesized_faces() {
+        let json = r#"{
+            "size": 10,
+            "regular": "r",
+            "italic": "i",
+            "synthesize": { "bold": true },
+            "markup": "text"
+        }"#;
+
+        let (rich_text, warnings) = crate::serde_elements::warnings::collect_warnings(|| {
+            serde_json::from_str::<RichText>(json)
+        });
+        let rich_text = rich_text.unwrap();
+
+        assert_eq!(
+            (rich_text.bold.as_str(), rich_text.bold_italic.as_str()),
+            ("r", "i")
+        );
+        assert_eq!(
+            rich_text.synthetic,
+            SyntheticFaces {
+                bold: Synthesis {
+                    bold: true,
+                    oblique: false,
+                },
+                italic: Synthesis::default(),
+                bold_italic: Synthesis {
+                    bold: true,
+                    oblique: false,
+                },
+            }
+        );
+        assert_eq!(warnings.len(), 2);
+
+        // Faking the italic face would need the oblique effect.
+        let json =
+            r#"{ "size": 10, "regular": "r", "synthesize": { "bold": true }, "markup": "" }"#;
+        assert!(serde_json::from_str::<RichText>(json).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_scripts() {
+        let json = r#"{
+            "size": 10,
+            "regular": "r",
+            "bold": "b",
+            "italic": "i",
+            "bold_italic": "bi",
+            "spans": [
+                { "text": "مرحبا" },
+                { "text": "สวัสดี", "language": "th" },
+                { "script": "Devanagari", "spans": [{ "text": "namaste" }] },
+                { "text": "नमस्ते" },
+                { "text": "مرحبا" }
+            ]
+        }"#;
+
+        let (rich_text, warnings) = crate::serde_elements::warnings::collect_warnings(|| {
+            serde_json::from_str::<RichText>(json).unwrap()
+        });
+
+        assert_eq!(rich_text.spans[1].language.as_deref(), Some("th"));
+        assert_eq!(rich_text.spans[2].script, Some(Script::Devanagari));
+
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings[0].starts_with("Arabic"));
+        assert!(warnings[1].starts_with("Devanagari"));
+    }
+
+    #[test]
+    fn test_bordered_table_row() {
+        let line = r##"{
+            "thickness": 0.5, "color": "#000000", "dash_pattern": null, "cap_style": "Butt"
+        }"##;
+        let row = format!(
+            r##"{{ "TableRow": {{
+                "content": [
+                    {{ "element": {{ "VGap": {{ "gap": 10 }} }}, "flex": {{ "Expand": 1 }} }},
+                    {{ "element": {{ "VGap": {{ "gap": 5 }} }}, "flex": {{ "Fixed": 20 }} }}
+                ],
+                "line_style": {line},
+                "expand": true,
+                "fill": "#eeeeee",
+                "outline": {line}
+            }} }}"##
+        );
+        let input = format!(
+            r#"{{ "page_size": "A4", "element": {{ "Column": {{
+                "content": [{row}, {row}],
+                "gap": -0.5
+            }} }} }}"#
+        );
+
+        assert!(crate::render::render_json(&input, &[]).is_ok());
+    }
+
+    #[test]
+    fn test_border_collapse() {
+        let row = |thickness| {
+            format!(
+                r##"{{ "TableRow": {{
+                    "content": [
+                        {{ "element": {{ "VGap": {{ "gap": 10 }} }}, "flex": {{ "Expand": 1 }} }}
+                    ],
+                    "line_style": {{
+                        "thickness": 0.5, "color": "#000000", "dash_pattern": null,
+                        "cap_style": "Butt"
+                    }},
+                    "expand": true,
+                    "outline": {{
+                        "thickness": {thickness}, "color": "#000000", "dash_pattern": null,
+                        "cap_style": "Butt"
+                    }}
+                }} }}"##
+            )
+        };
+
+        let column: Column<ElementValue> = serde_json::from_str(&format!(
+            r#"{{
+                "content": [{}, {}, {{ "VGap": {{ "gap": 2 }} }}, {}],
+                "gap": 0,
+                "border_collapse": true
+            }}"#,
+            row(0.5),
+            row(1),
+            row(1),
+        ))
+        .unwrap();
+
+        let outlines: Vec<_> = column
+            .content
+            .iter()
+            .map(|e| e.outline_thickness())
+            .collect();
+        assert_eq!(outlines, [Some(0.5), Some(1.), None, Some(1.)]);
+
+        // The rows are 10 high plus their outlines. The first two overlap by the thinner outline,
+        // the last one doesn't follow a row.
+        let fonts = std::collections::HashMap::<String, Font>::new();
+
+        let size = SerdeElementElement {
+            element: &column,
+            fonts: &fonts,
+        }
+        .measure(MeasureCtx {
+            width: WidthConstraint {
+                max: 100.,
+                expand: true,
+            },
+            first_height: 100.,
+            breakable: None,
+        });
+
+        assert_eq!(size.height, Some(11. + 12. - 0.5 + 2. + 12.));
+    }
+
+    #[test]
+    fn test_column_background() {
+        let column = |background: &str| {
+            serde_json::from_str::<Column<ElementValue>>(&format!(
+                r#"{{ "content": [], "gap": 0, "background": {background} }}"#
+            ))
+            .map(|c| c.background)
+        };
+
+        assert_eq!(column(r##""#ff0000""##).unwrap(), Some(0xff_00_00_ff));
+        assert_eq!(column(r#""rgb(1, 2, 3)""#).unwrap(), Some(0x01_02_03_ff));
+        assert_eq!(column("null").unwrap(), None);
+        assert!(column(r#""not a color""#).is_err());
+    }
+
+    #[test]
+    fn test_padding_percent() {
+        let padding = serde_json::from_str::<Padding<ElementValue>>(
+            r#"{
+                "left": "10%",
+                "right": 5,
+                "top": 0,
+                "bottom": 0,
+                "element": { "Rectangle": { "size": [10, 10] } }
+            }"#,
+        )
+        .unwrap();
+
+        let fonts = std::collections::HashMap::<String, Font>::new();
+
+        let width = |max| {
+            SerdeElementElement {
+                element: &padding,
+                fonts: &fonts,
+            }
+            .measure(MeasureCtx {
+                width: WidthConstraint { max, expand: false },
+                first_height: 100.,
+                breakable: None,
+            })
+            .width
+        };
+
+        assert_eq!(width(100.), Some(10. + 10. + 5.));
+        assert_eq!(width(200.), Some(20. + 10. + 5.));
+    }
+
+    #[test]
+    fn test_calendar_days() {
+        let calendar = |month: u32| {
+            serde_json::from_str::<CalendarMonth<ElementValue>>(&format!(
+                r##"{{
+                    "year": 2024,
+                    "month": {month},
+                    "weekday_names": ["M", "T", "W", "T", "F", "S", "S"],
+                    "font": "regular",
+                    "size": 10,
+                    "color": "#000000",
+                    "line_style": {{
+                        "thickness": 0.5, "color": "#000000", "dash_pattern": null,
+                        "cap_style": "Butt"
+                    }},
+                    "days": [
+                        {{ "day": 3, "element": {{ "Rectangle": {{ "size": [10, 10] }} }} }},
+                        {{ "day": 3, "element": {{ "VGap": {{ "gap": 5 }} }} }}
+                    ]
+                }}"##
+            ))
+        };
+
+        let days = calendar(2).unwrap().days;
+        assert_eq!(days.iter().map(|d| d.day).collect::<Vec<_>>(), [3, 3]);
+        assert!(matches!(days[0].element, ElementValue::Rectangle(_)));
+
+        assert!(calendar(13).is_err());
+    }
+
+    /// Text that counts how often it's measured.
+    #[derive(Clone, Default, Deserialize)]
+    struct Counted {
+        #[serde(skip)]
+        measures: Cell<u32>,
+    }
+
+    impl Counted {
+        const TEXT: crate::test_utils::FakeText = crate::test_utils::FakeText {
+            lines: 3,
+            line_height: 1.,
+            width: 5.,
+        };
+    }
+
+    impl Element for Counted {
+        fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+            Self::TEXT.first_location_usage(ctx)
+        }
+
+        fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+            self.measures.set(self.measures.get() + 1);
+            Self::TEXT.measure(ctx)
+        }
+
+        fn draw(&self, ctx: DrawCtx) -> ElementSize {
+            Self::TEXT.draw(ctx)
+        }
+    }
+
+    impl SerdeElement for Counted {
+        fn element(
+            &self,
+            _: &impl for<'a> Index<&'a str, Output = Font>,
+            callback: impl CompositeElementCallback,
+        ) {
+            callback.call(self);
+        }
+    }
+
+    #[test]
+    fn test_cached_measurements() {
+        let cached = serde_json::from_str::<Cached<Counted>>(r#"{ "element": {} }"#).unwrap();
+        let fonts = std::collections::HashMap::<String, Font>::new();
+
+        // Every measurement builds the element again.
+        let measure = |cached: &Cached<Counted>, first_height| {
+            let mut break_count = 0;
+            let mut extra_location_min_height = None;
+
+            let size = SerdeElementElement {
+                element: cached,
+                fonts: &fonts,
+            }
+            .measure(MeasureCtx {
+                width: WidthConstraint {
+                    max: 10.,
+                    expand: false,
+                },
+                first_height,
+                breakable: Some(BreakableMeasure {
+                    full_height: 2.,
+                    break_count: &mut break_count,
+                    extra_location_min_height: &mut extra_location_min_height,
+                }),
+            });
+
+            (size, break_count)
+        };
+
+        let first = measure(&cached, 1.);
+        assert_eq!(first.1, 1);
+        assert_eq!(measure(&cached, 1.), first);
+        assert_eq!(cached.element.measures.get(), 1);
+
+        measure(&cached, 2.);
+        assert_eq!(cached.element.measures.get(), 2);
+
+        // Clones are built for other documents, so they don't share the measurements.
+        let clone = cached.clone();
+        clone.element.measures.set(0);
+
+        assert_eq!(measure(&clone, 1.), first);
+        assert_eq!(clone.element.measures.get(), 1);
+    }
 }
@@ -0,0 +1,520 @@
+//! Numeric fields of the serde elements can be given either as a number or as a string containing
+//! a simple arithmetic expression. Expressions can reference named constants, which are made
+//! available with [Constants::scope] while deserializing:
+//!
+//! ```json
+//! { "Column": { "gap": "base_gap * 2", "content": [] } }
+//! ```
+//!
+//! Supported are numbers, constants, parentheses, unary minus and the operators `+`, `-`, `*` and
+//! `/` with the usual precedence. Numbers can have a unit suffix (`mm`, `cm`, `pt` or `in`, e.g.
+//! `"10pt"` or `"1in - 2mm"`), in which case they get converted to the unit of the field. Without a
+//! suffix a number is already in the unit of the field. Most fields are in millimeters, font sizes
+//! are in points. Percentages of the width (`"50%"`) are only possible for [Length] fields, which
+//! are resolved during layout, since the width isn't known while deserializing.
+//!
+//! Division by zero and results that aren't finite numbers are errors, as is nesting parentheses or
+//! unary minuses deeper than [MAX_DEPTH].
+
+use std::{cell::RefCell, collections::HashMap, fmt};
+
+use serde::{
+    de::{Error, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::utils::scoped;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    Mm,
+    Cm,
+    Pt,
+    In,
+}
+
+impl Unit {
+    pub fn from_suffix(suffix: &str) -> Option<Self> {
+        match suffix {
+            "mm" => Some(Unit::Mm),
+            "cm" => Some(Unit::Cm),
+            "pt" => Some(Unit::Pt),
+            "in" => Some(Unit::In),
+            _ => None,
+        }
+    }
+
+    pub fn in_mm(self) -> f64 {
+        match self {
+            Unit::Mm => 1.,
+            Unit::Cm => 10.,
+            Unit::Pt => 25.4 / 72.,
+            Unit::In => 25.4,
+        }
+    }
+}
+
+/// How deeply parentheses and unary minuses can be nested in an expression, so untrusted input
+/// can't overflow the stack of the recursive parser.
+pub const MAX_DEPTH: u32 = 64;
+
+#[derive(Clone, Debug, Default)]
+pub struct Constants(pub HashMap<String, f64>);
+
+thread_local! {
+    static CURRENT: RefCell<Option<Constants>> = const { RefCell::new(None) };
+}
+
+impl Constants {
+    /// Makes the constants available to expressions deserialized on this thread while `f` is
+    /// running.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        scoped(&CURRENT, Some(self.clone()), f).0
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ExprError(String);
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+struct Parser<'a, 'c> {
+    input: &'a str,
+    pos: usize,
+    constants: Option<&'c Constants>,
+    unit: Unit,
+    depth: u32,
+}
+
+impl<'a, 'c> Parser<'a, 'c> {
+    fn skip_whitespace(&mut self) {
+        let rest = &self.input[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.input[self.pos..].chars().next()
+    }
+
+    fn error<T>(&self, message: &str) -> Result<T, ExprError> {
+        Err(ExprError(format!(
+            "{message} at position {} in expression `{}`",
+            self.pos, self.input
+        )))
+    }
+
+    fn sum(&mut self) -> Result<f64, ExprError> {
+        let mut value = self.product()?;
+
+        loop {
+            match self.peek() {
+                Some('+') => {
+                    self.pos += 1;
+                    value += self.product()?;
+                }
+                Some('-') => {
+                    self.pos += 1;
+                    value -= self.product()?;
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    fn product(&mut self) -> Result<f64, ExprError> {
+        let mut value = self.unary()?;
+
+        loop {
+            match self.peek() {
+                Some('*') => {
+                    self.pos += 1;
+                    value *= self.unary()?;
+                }
+                Some('/') => {
+                    self.pos += 1;
+                    let divisor = self.unary()?;
+
+                    if divisor == 0. {
+                        return self.error("division by zero");
+                    }
+
+                    value /= divisor;
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<f64, ExprError> {
+        // Parentheses recurse through here as well.
+        if self.depth == MAX_DEPTH {
+            return self.error("expression nested too deeply");
+        }
+
+        self.depth += 1;
+
+        let value = if self.peek() == Some('-') {
+            self.pos += 1;
+            -self.unary()?
+        } else {
+            self.atom()?
+        };
+
+        self.depth -= 1;
+        Ok(value)
+    }
+
+    fn atom(&mut self) -> Result<f64, ExprError> {
+        match self.peek() {
+            Some('(') => {
+                self.pos += 1;
+                let value = self.sum()?;
+
+                if self.peek() != Some(')') {
+                    return self.error("expected `)`");
+                }
+
+                self.pos += 1;
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_digit() || c == '.' => {
+                let len = self.input[self.pos..]
+                    .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                    .unwrap_or(self.input.len() - self.pos);
+
+                let number = &self.input[self.pos..self.pos + len];
+
+                let value: f64 = match number.parse() {
+                    Ok(value) => value,
+                    Err(_) => return self.error("invalid number"),
+                };
+
+                self.pos += len;
+
+                let suffix_len = self.input[self.pos..]
+                    .find(|c: char| !c.is_alphabetic())
+                    .unwrap_or(self.input.len() - self.pos);
+
+                if suffix_len == 0 {
+                    return Ok(value);
+                }
+
+                match Unit::from_suffix(&self.input[self.pos..self.pos + suffix_len]) {
+                    Some(unit) => {
+                        self.pos += suffix_len;
+                        Ok(value * unit.in_mm() / self.unit.in_mm())
+                    }
+                    None => self.error("unknown unit"),
+                }
+            }
+            Some(c) if c.is_alphabetic() || c == '_' => {
+                let len = self.input[self.pos..]
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .unwrap_or(self.input.len() - self.pos);
+
+                let name = &self.input[self.pos..self.pos + len];
+
+                match self.constants.and_then(|c| c.0.get(name)) {
+                    Some(&value) => {
+                        self.pos += len;
+                        Ok(value)
+                    }
+                    None => self.error(&format!("unknown constant `{name}`")),
+                }
+            }
+            Some(_) => self.error("unexpected character"),
+            None => self.error("unexpected end"),
+        }
+    }
+}
+
+/// Evaluates an expression with the given constants. The result is in millimeters.
+pub fn evaluate(input: &str, constants: Option<&Constants>) -> Result<f64, ExprError> {
+    evaluate_in(input, constants, Unit::Mm)
+}
+
+/// Evaluates an expression with the given constants. Numbers with a unit suffix get converted to
+/// `unit`.
+pub fn evaluate_in(
+    input: &str,
+    constants: Option<&Constants>,
+    unit: Unit,
+) -> Result<f64, ExprError> {
+    let mut parser = Parser {
+        input,
+        pos: 0,
+        constants,
+        unit,
+        depth: 0,
+    };
+
+    let value = parser.sum()?;
+
+    if parser.peek().is_some() {
+        return parser.error("unexpected character");
+    }
+
+    if !value.is_finite() {
+        return Err(ExprError(format!(
+            "expression `{input}` isn't a finite number"
+        )));
+    }
+
+    Ok(value)
+}
+
+/// For use with `#[serde(deserialize_with = "...")]` on `f64` fields in millimeters.
+pub fn deserialize_f64<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    deserialize_in(deserializer, Unit::Mm)
+}
+
+/// For use with `#[serde(deserialize_with = "...")]` on `f64` fields in points, like font sizes.
+pub fn deserialize_pt<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+    deserialize_in(deserializer, Unit::Pt)
+}
+
+/// Like [deserialize_f64], but for `Option<f64>` fields. Needs `#[serde(default)]` as well.
+pub fn deserialize_optional_f64<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<f64>, D::Error> {
+    struct Mm(f64);
+
+    impl<'de> Deserialize<'de> for Mm {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize_f64(deserializer).map(Mm)
+        }
+    }
+
+    Ok(Option::<Mm>::deserialize(deserializer)?.map(|m| m.0))
+}
+
+/// Like [deserialize_pt], but for `Option<f64>` fields. Needs `#[serde(default)]` as well.
+pub fn deserialize_optional_pt<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<f64>, D::Error> {
+    struct Pt(f64);
+
+    impl<'de> Deserialize<'de> for Pt {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            deserialize_pt(deserializer).map(Pt)
+        }
+    }
+
+    Ok(Option::<Pt>::deserialize(deserializer)?.map(|p| p.0))
+}
+
+/// A horizontal length in millimeters or in percent of the width that's available to the element.
+/// Deserialized from an expression like for [deserialize_f64] or from an expression followed by
+/// `%`, like `"50%"` or `"(100 / 3)%"`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Length {
+    Mm(f64),
+    Percent(f64),
+}
+
+impl Length {
+    /// The length in millimeters given the available width.
+    pub fn resolve(self, width: f64) -> f64 {
+        match self {
+            Length::Mm(mm) => mm,
+            Length::Percent(percent) => width * percent / 100.,
+        }
+    }
+}
+
+impl Serialize for Length {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Length::Mm(mm) => serializer.serialize_f64(mm),
+            Length::Percent(percent) => serializer.serialize_str(&format!("{percent}%")),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Length {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct LengthVisitor;
+
+        impl<'de> Visitor<'de> for LengthVisitor {
+            type Value = Length;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a number, an expression or a percentage")
+            }
+
+            fn visit_f64<E: Error>(self, v: f64) -> Result<Length, E> {
+                Ok(Length::Mm(v))
+            }
+
+            fn visit_i64<E: Error>(self, v: i64) -> Result<Length, E> {
+                Ok(Length::Mm(v as f64))
+            }
+
+            fn visit_u64<E: Error>(self, v: u64) -> Result<Length, E> {
+                Ok(Length::Mm(v as f64))
+            }
+
+            fn visit_str<E: Error>(self, v: &str) -> Result<Length, E> {
+                let (expression, length): (_, fn(f64) -> Length) =
+                    match v.trim_end().strip_suffix('%') {
+                        Some(expression) => (expression, Length::Percent),
+                        None => (v, Length::Mm),
+                    };
+
+                CURRENT
+                    .with(|current| evaluate(expression, current.borrow().as_ref()))
+                    .map(length)
+                    .map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(LengthVisitor)
+    }
+}
+
+fn deserialize_in<'de, D: Deserializer<'de>>(deserializer: D, unit: Unit) -> Result<f64, D::Error> {
+    struct NumberVisitor(Unit);
+
+    impl<'de> Visitor<'de> for NumberVisitor {
+        type Value = f64;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a number or an expression")
+        }
+
+        fn visit_f64<E: Error>(self, v: f64) -> Result<f64, E> {
+            Ok(v)
+        }
+
+        fn visit_i64<E: Error>(self, v: i64) -> Result<f64, E> {
+            Ok(v as f64)
+        }
+
+        fn visit_u64<E: Error>(self, v: u64) -> Result<f64, E> {
+            Ok(v as f64)
+        }
+
+        fn visit_str<E: Error>(self, v: &str) -> Result<f64, E> {
+            CURRENT
+                .with(|current| evaluate_in(v, current.borrow().as_ref(), self.0))
+                .map_err(E::custom)
+        }
+    }
+
+    deserializer.deserialize_any(NumberVisitor(unit))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evaluate() {
+        let constants = Constants(HashMap::from([
+            ("base_gap".to_string(), 2.5),
+            ("margin".to_string(), 10.),
+        ]));
+
+        assert_eq!(evaluate("1 + 2 * 3", None), Ok(7.));
+        assert_eq!(evaluate("(1 + 2) * 3", None), Ok(9.));
+        assert_eq!(evaluate("-2 - -3", None), Ok(1.));
+        assert_eq!(evaluate("8 / 4 / 2", None), Ok(1.));
+        assert_eq!(evaluate("base_gap * 2", Some(&constants)), Ok(5.));
+        assert_eq!(evaluate(" margin/2+base_gap ", Some(&constants)), Ok(7.5));
+
+        assert!(evaluate("base_gap", None).is_err());
+        assert!(evaluate("1 +", None).is_err());
+        assert!(evaluate("(1", None).is_err());
+        assert!(evaluate("1 2", None).is_err());
+    }
+
+    #[test]
+    fn test_invalid_results() {
+        assert!(evaluate("1 / 0", None).is_err());
+        assert!(evaluate("0 / (2 - 2)", None).is_err());
+        assert!(evaluate("1 / (1 / 0)", None).is_err());
+
+        let large = "9".repeat(400);
+        assert!(evaluate(&large, None).is_err());
+        assert!(evaluate(&format!("{large} - {large}"), None).is_err());
+    }
+
+    #[test]
+    fn test_depth() {
+        let nested = |depth: usize| format!("{}1{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert_eq!(evaluate(&nested(MAX_DEPTH as usize - 1), None), Ok(1.));
+        assert!(evaluate(&nested(MAX_DEPTH as usize), None).is_err());
+        assert!(evaluate(&nested(100_000), None).is_err());
+
+        let negated = |depth: usize| format!("{}1", "-".repeat(depth));
+
+        assert_eq!(evaluate(&negated(MAX_DEPTH as usize - 1), None), Ok(-1.));
+        assert!(evaluate(&negated(MAX_DEPTH as usize), None).is_err());
+        assert!(evaluate(&negated(100_000), None).is_err());
+    }
+
+    #[test]
+    fn test_units() {
+        fn assert_close(a: Result<f64, ExprError>, b: f64) {
+            assert!((a.unwrap() - b).abs() < 1e-9);
+        }
+
+        assert_close(evaluate("1cm", None), 10.);
+        assert_close(evaluate("1in - 4.4mm", None), 21.);
+        assert_close(evaluate("72pt", None), 25.4);
+        assert_close(evaluate_in("1in", None, Unit::Pt), 72.);
+        assert_close(evaluate_in("12", None, Unit::Pt), 12.);
+
+        assert!(evaluate("10px", None).is_err());
+        assert!(evaluate("50%", None).is_err());
+    }
+
+    #[test]
+    fn test_length() {
+        let length = |json: &str| serde_json::from_str::<Length>(json).map_err(|e| e.to_string());
+
+        assert_eq!(length("12"), Ok(Length::Mm(12.)));
+        assert_eq!(length(r#""1cm + 2""#), Ok(Length::Mm(12.)));
+        assert_eq!(length(r#""50%""#), Ok(Length::Percent(50.)));
+        assert_eq!(length(r#""(10 + 15) %""#), Ok(Length::Percent(25.)));
+        assert!(length(r#""50%%""#).is_err());
+        assert!(length(r#""%""#).is_err());
+
+        assert_eq!(Length::Mm(12.).resolve(200.), 12.);
+        assert_eq!(Length::Percent(25.).resolve(200.), 50.);
+
+        assert_eq!(
+            serde_json::to_string(&Length::Percent(50.)).unwrap(),
+            r#""50%""#
+        );
+    }
+
+    #[test]
+    fn test_deserialize() {
+        #[derive(serde::Deserialize)]
+        struct Gap {
+            #[serde(deserialize_with = "deserialize_f64")]
+            gap: f64,
+        }
+
+        let constants = Constants(HashMap::from([("base_gap".to_string(), 3.)]));
+
+        let gap = constants.scope(|| {
+            serde_json::from_str::<Gap>(r#"{ "gap": "base_gap * 2" }"#)
+                .unwrap()
+                .gap
+        });
+        assert_eq!(gap, 6.);
+
+        let gap = serde_json::from_str::<Gap>(r#"{ "gap": 4 }"#).unwrap().gap;
+        assert_eq!(gap, 4.);
+    }
+}
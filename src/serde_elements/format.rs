@@ -0,0 +1,933 @@
+//! Locale aware formatting of numbers, amounts of money and dates, so documents can be built from
+//! raw data instead of every caller formatting it beforehand. [Formatted] shows a formatted value
+//! in a text template:
+//!
+//! ```json
+//! { "Formatted": {
+//!     "text": "Total: {}",
+//!     "value": { "Currency": { "amount": "net * 1.19", "currency": "EUR" } },
+//!     "locale": "de-DE"
+//! } }
+//! ```
+//!
+//! [UnitValue] shows a measured value with its unit, like `12,5 kg`, optionally aligned at the
+//! decimal separator.
+//!
+//! Amounts and numbers can be expressions using the current constants. Dates are given as
+//! `YYYY-MM-DD` and formatted with a pattern like `"d. MMMM yyyy"`, see [format_date]. The locale
+//! can also be set for the whole document in the [Defaults](super::defaults::Defaults).
+
+use std::ops::Index;
+
+use serde::Deserialize;
+
+use crate::{
+    elements::{
+        calendar::{days_in_month, weekday},
+        text::{Text, TextAlign},
+    },
+    *,
+};
+
+use super::{
+    defaults::{self, or_default},
+    expr, Font, SerdeElement,
+};
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum Locale {
+    #[default]
+    #[serde(rename = "en-US")]
+    EnUs,
+    #[serde(rename = "en-GB")]
+    EnGb,
+    #[serde(rename = "de-DE")]
+    DeDe,
+    #[serde(rename = "de-CH")]
+    DeCh,
+    #[serde(rename = "fr-FR")]
+    FrFr,
+    #[serde(rename = "it-IT")]
+    ItIt,
+    #[serde(rename = "nl-NL")]
+    NlNl,
+}
+
+/// Where the currency symbol goes relative to the amount.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum SymbolPosition {
+    Before,
+    BeforeSpaced,
+    AfterSpaced,
+}
+
+/// How the currency of an amount is shown.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum CurrencyDisplay {
+    /// The symbol like `€` for the common currencies, the code for the others.
+    #[default]
+    Symbol,
+
+    /// The ISO 4217 code like `EUR`, which is always separated from the amount by a space.
+    Code,
+}
+
+/// How negative amounts are shown.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum NegativeStyle {
+    #[default]
+    Minus,
+
+    /// In parentheses without a sign, like in accounting.
+    Parentheses,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct CurrencyStyle {
+    pub display: CurrencyDisplay,
+    pub negative: NegativeStyle,
+}
+
+const NO_BREAK_SPACE: char = '\u{a0}';
+const NARROW_NO_BREAK_SPACE: char = '\u{202f}';
+
+impl Locale {
+    pub fn decimal_separator(self) -> char {
+        match self {
+            Locale::EnUs | Locale::EnGb | Locale::DeCh => '.',
+            Locale::DeDe | Locale::FrFr | Locale::ItIt | Locale::NlNl => ',',
+        }
+    }
+
+    pub fn group_separator(self) -> char {
+        match self {
+            Locale::EnUs | Locale::EnGb => ',',
+            Locale::DeDe | Locale::ItIt | Locale::NlNl => '.',
+            Locale::DeCh => '’',
+            Locale::FrFr => NARROW_NO_BREAK_SPACE,
+        }
+    }
+
+    fn symbol_position(self) -> SymbolPosition {
+        match self {
+            Locale::EnUs | Locale::EnGb => SymbolPosition::Before,
+            Locale::DeCh | Locale::NlNl => SymbolPosition::BeforeSpaced,
+            Locale::DeDe | Locale::FrFr | Locale::ItIt => SymbolPosition::AfterSpaced,
+        }
+    }
+
+    pub fn month_names(self) -> [&'static str; 12] {
+        match self {
+            Locale::EnUs | Locale::EnGb => [
+                "January",
+                "February",
+                "March",
+                "April",
+                "May",
+                "June",
+                "July",
+                "August",
+                "September",
+                "October",
+                "November",
+                "December",
+            ],
+            Locale::DeDe | Locale::DeCh => [
+                "Januar",
+                "Februar",
+                "März",
+                "April",
+                "Mai",
+                "Juni",
+                "Juli",
+                "August",
+                "September",
+                "Oktober",
+                "November",
+                "Dezember",
+            ],
+            Locale::FrFr => [
+                "janvier",
+                "février",
+                "mars",
+                "avril",
+                "mai",
+                "juin",
+                "juillet",
+                "août",
+                "septembre",
+                "octobre",
+                "novembre",
+                "décembre",
+            ],
+            Locale::ItIt => [
+                "gennaio",
+                "febbraio",
+                "marzo",
+                "aprile",
+                "maggio",
+                "giugno",
+                "luglio",
+                "agosto",
+                "settembre",
+                "ottobre",
+                "novembre",
+                "dicembre",
+            ],
+            Locale::NlNl => [
+                "januari",
+                "februari",
+                "maart",
+                "april",
+                "mei",
+                "juni",
+                "juli",
+                "augustus",
+                "september",
+                "oktober",
+                "november",
+                "december",
+            ],
+        }
+    }
+
+    /// Starting with Monday.
+    pub fn weekday_names(self) -> [&'static str; 7] {
+        match self {
+            Locale::EnUs | Locale::EnGb => [
+                "Monday",
+                "Tuesday",
+                "Wednesday",
+                "Thursday",
+                "Friday",
+                "Saturday",
+                "Sunday",
+            ],
+            Locale::DeDe | Locale::DeCh => [
+                "Montag",
+                "Dienstag",
+                "Mittwoch",
+                "Donnerstag",
+                "Freitag",
+                "Samstag",
+                "Sonntag",
+            ],
+            Locale::FrFr => [
+                "lundi", "mardi", "mercredi", "jeudi", "vendredi", "samedi", "dimanche",
+            ],
+            Locale::ItIt => [
+                "lunedì",
+                "martedì",
+                "mercoledì",
+                "giovedì",
+                "venerdì",
+                "sabato",
+                "domenica",
+            ],
+            Locale::NlNl => [
+                "maandag",
+                "dinsdag",
+                "woensdag",
+                "donderdag",
+                "vrijdag",
+                "zaterdag",
+                "zondag",
+            ],
+        }
+    }
+}
+
+/// Rounds the absolute value to `decimals` places, with halves rounded away from zero. This is
+/// done on the shortest decimal representation of the value instead of on the binary one, so for
+/// example 1.005 is rounded to 1.01 even though the closest `f64` is slightly below 1.005.
+fn round_decimal(value: f64, decimals: usize) -> String {
+    let repr = value.abs().to_string();
+
+    if !value.is_finite() {
+        return repr;
+    }
+
+    let (integer, fraction) = repr.split_once('.').unwrap_or((&repr, ""));
+
+    let mut digits = integer
+        .bytes()
+        .chain(
+            fraction
+                .bytes()
+                .chain(std::iter::repeat(b'0'))
+                .take(decimals),
+        )
+        .collect::<Vec<_>>();
+
+    if matches!(fraction.as_bytes().get(decimals), Some(b'5'..=b'9')) {
+        // The nines after the digit that's increased carry over.
+        let start = match digits.iter().rposition(|&d| d != b'9') {
+            Some(i) => {
+                digits[i] += 1;
+                i + 1
+            }
+            None => {
+                digits.insert(0, b'1');
+                1
+            }
+        };
+
+        digits[start..].fill(b'0');
+    }
+
+    let mut result = String::from_utf8(digits).unwrap();
+
+    if decimals > 0 {
+        result.insert(result.len() - decimals, '.');
+    }
+
+    result
+}
+
+/// Rounds the number to `decimals` places and adds the separators of the locale.
+pub fn format_number(value: f64, decimals: usize, locale: Locale) -> String {
+    let digits = round_decimal(value, decimals);
+
+    let (integer, fraction) = match digits.split_once('.') {
+        Some((integer, fraction)) => (integer, Some(fraction)),
+        None => (&digits[..], None),
+    };
+
+    let mut result = String::with_capacity(digits.len() + integer.len() / 3 + 1);
+
+    // Rounding can turn small negative numbers into zero, which shouldn't get a sign.
+    if value < 0. && digits.bytes().any(|b| matches!(b, b'1'..=b'9')) {
+        result.push('-');
+    }
+
+    for (i, c) in integer.chars().enumerate() {
+        if i > 0 && (integer.len() - i) % 3 == 0 {
+            result.push(locale.group_separator());
+        }
+
+        result.push(c);
+    }
+
+    if let Some(fraction) = fraction {
+        result.push(locale.decimal_separator());
+        result.push_str(fraction);
+    }
+
+    result
+}
+
+/// The symbol of the common currencies by their ISO 4217 code. Other codes are used as they are.
+fn currency_symbol(currency: &str) -> &str {
+    match currency {
+        "EUR" => "€",
+        "USD" => "$",
+        "GBP" => "£",
+        "JPY" => "¥",
+        _ => currency,
+    }
+}
+
+/// Formats an amount of money with the symbol or code of the currency placed like the locale
+/// does.
+pub fn format_currency(
+    amount: f64,
+    currency: &str,
+    decimals: usize,
+    locale: Locale,
+    style: CurrencyStyle,
+) -> String {
+    let number = format_number(amount, decimals, locale);
+
+    let (negative, number) = match number.strip_prefix('-') {
+        Some(number) => (true, number),
+        None => (false, &number[..]),
+    };
+
+    let (symbol, position) = match style.display {
+        CurrencyDisplay::Symbol => (currency_symbol(currency), locale.symbol_position()),
+        CurrencyDisplay::Code => match locale.symbol_position() {
+            SymbolPosition::Before => (currency, SymbolPosition::BeforeSpaced),
+            position => (currency, position),
+        },
+    };
+
+    let parentheses = negative && style.negative == NegativeStyle::Parentheses;
+    let sign = if negative && !parentheses { "-" } else { "" };
+
+    let formatted = match position {
+        SymbolPosition::Before => format!("{sign}{symbol}{number}"),
+        SymbolPosition::BeforeSpaced => format!("{symbol}{NO_BREAK_SPACE}{sign}{number}"),
+        SymbolPosition::AfterSpaced => format!("{sign}{number}{NO_BREAK_SPACE}{symbol}"),
+    };
+
+    if parentheses {
+        format!("({formatted})")
+    } else {
+        formatted
+    }
+}
+
+/// Formats a value with its unit, separated by a no-break space so they stay on the same line.
+pub fn format_unit_value(value: f64, unit: &str, decimals: usize, locale: Locale) -> String {
+    let number = format_number(value, decimals, locale);
+
+    if unit.is_empty() {
+        number
+    } else {
+        format!("{number}{NO_BREAK_SPACE}{unit}")
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Date {
+    pub year: i32,
+
+    /// 1 to 12.
+    pub month: u32,
+
+    pub day: u32,
+}
+
+impl Date {
+    /// Parses a date like `2024-03-01`.
+    pub fn parse(input: &str) -> Result<Date, String> {
+        let error = || format!("invalid date `{input}`, expected YYYY-MM-DD");
+
+        let mut parts = input.split('-');
+
+        let (Some(year), Some(month), Some(day), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(error());
+        };
+
+        if year.len() != 4 || month.len() != 2 || day.len() != 2 {
+            return Err(error());
+        }
+
+        let date = Date {
+            year: year.parse().map_err(|_| error())?,
+            month: month.parse().map_err(|_| error())?,
+            day: day.parse().map_err(|_| error())?,
+        };
+
+        if !(1..=12).contains(&date.month)
+            || !(1..=days_in_month(date.year, date.month)).contains(&date.day)
+        {
+            return Err(error());
+        }
+
+        Ok(date)
+    }
+}
+
+/// Formats the date with a pattern like `"dd.MM.yyyy"` or `"EEEE, d MMMM yyyy"`. The fields are
+/// `d` and `dd` for the day, `M` and `MM` for the month as a number, `MMM` and `MMMM` for its
+/// abbreviated and full name, `yy` and `yyyy` for the year and `EEE` and `EEEE` for the
+/// abbreviated and full name of the weekday. Other letters are an error. Text in single quotes is
+/// kept as it is and `''` is a single quote.
+pub fn format_date(date: Date, pattern: &str, locale: Locale) -> Result<String, String> {
+    let mut result = String::new();
+    let mut chars = pattern.chars().peekable();
+
+    let abbreviate = |name: &str| name.chars().take(3).collect::<String>();
+
+    while let Some(c) = chars.next() {
+        if c == '\'' {
+            if chars.next_if_eq(&'\'').is_some() {
+                result.push('\'');
+                continue;
+            }
+
+            loop {
+                match chars.next() {
+                    Some('\'') if chars.next_if_eq(&'\'').is_some() => result.push('\''),
+                    Some('\'') => break,
+                    Some(c) => result.push(c),
+                    None => return Err(format!("unclosed quote in date pattern `{pattern}`")),
+                }
+            }
+
+            continue;
+        }
+
+        if !c.is_ascii_alphabetic() {
+            result.push(c);
+            continue;
+        }
+
+        let mut count = 1;
+
+        while chars.next_if_eq(&c).is_some() {
+            count += 1;
+        }
+
+        let month = locale.month_names()[date.month as usize - 1];
+        let day_of_week = weekday(date.year, date.month, date.day)
+            .map_or("", |weekday| locale.weekday_names()[weekday as usize]);
+
+        match (c, count) {
+            ('d', 1) => result.push_str(&date.day.to_string()),
+            ('d', 2) => result.push_str(&format!("{:02}", date.day)),
+            ('M', 1) => result.push_str(&date.month.to_string()),
+            ('M', 2) => result.push_str(&format!("{:02}", date.month)),
+            ('M', 3) => result.push_str(&abbreviate(month)),
+            ('M', 4) => result.push_str(month),
+            ('y', 2) => result.push_str(&format!("{:02}", date.year.rem_euclid(100))),
+            ('y', 4) => result.push_str(&format!("{:04}", date.year)),
+            ('E', 3) => result.push_str(&abbreviate(day_of_week)),
+            ('E', 4) => result.push_str(day_of_week),
+            _ => {
+                return Err(format!(
+                    "unknown field `{}` in date pattern `{pattern}`",
+                    c.to_string().repeat(count)
+                ))
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+/// The most decimals a value can be formatted with in the JSON. Formatting allocates a digit for
+/// every decimal, so the number has to be limited for untrusted input.
+pub const MAX_DECIMALS: usize = 20;
+
+const fn default_decimals() -> usize {
+    2
+}
+
+fn deserialize_decimals<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<usize, D::Error> {
+    let decimals = usize::deserialize(deserializer)?;
+
+    if decimals > MAX_DECIMALS {
+        return Err(serde::de::Error::custom(format!(
+            "{decimals} decimals are more than the {MAX_DECIMALS} supported"
+        )));
+    }
+
+    Ok(decimals)
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub enum FormattedValue {
+    Number {
+        #[serde(deserialize_with = "expr::deserialize_f64")]
+        number: f64,
+
+        #[serde(default, deserialize_with = "deserialize_decimals")]
+        decimals: usize,
+    },
+    Currency {
+        #[serde(deserialize_with = "expr::deserialize_f64")]
+        amount: f64,
+
+        /// An ISO 4217 code like `EUR`.
+        currency: String,
+
+        #[serde(
+            default = "default_decimals",
+            deserialize_with = "deserialize_decimals"
+        )]
+        decimals: usize,
+
+        #[serde(default)]
+        display: CurrencyDisplay,
+
+        #[serde(default)]
+        negative: NegativeStyle,
+    },
+    Date {
+        date: String,
+        pattern: String,
+    },
+}
+
+impl FormattedValue {
+    pub fn format(&self, locale: Locale) -> Result<String, String> {
+        match *self {
+            FormattedValue::Number { number, decimals } => {
+                Ok(format_number(number, decimals, locale))
+            }
+            FormattedValue::Currency {
+                amount,
+                ref currency,
+                decimals,
+                display,
+                negative,
+            } => Ok(format_currency(
+                amount,
+                currency,
+                decimals,
+                locale,
+                CurrencyStyle { display, negative },
+            )),
+            FormattedValue::Date {
+                ref date,
+                ref pattern,
+            } => format_date(Date::parse(date)?, pattern, locale),
+        }
+    }
+}
+
+/// A formatted value in a text template. Every `{}` in the text is replaced with the value.
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "FormattedInput")]
+pub struct Formatted {
+    pub text: String,
+    pub font: String,
+    pub size: f64,
+    pub color: u32,
+    pub align: TextAlign,
+}
+
+#[derive(Deserialize)]
+struct FormattedInput {
+    #[serde(default = "default_text")]
+    text: String,
+
+    value: FormattedValue,
+
+    #[serde(default)]
+    locale: Option<Locale>,
+
+    #[serde(default)]
+    font: Option<String>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_optional_pt")]
+    size: Option<f64>,
+
+    #[serde(default, deserialize_with = "super::color::deserialize_optional_color")]
+    color: Option<u32>,
+
+    #[serde(default = "defaults::default_align")]
+    align: TextAlign,
+}
+
+fn default_text() -> String {
+    "{}".to_string()
+}
+
+impl TryFrom<FormattedInput> for Formatted {
+    type Error = String;
+
+    fn try_from(input: FormattedInput) -> Result<Self, String> {
+        let locale = or_default(input.locale, "locale", |d| &d.locale).unwrap_or_default();
+        let value = input.value.format(locale)?;
+
+        Ok(Formatted {
+            text: input.text.replace("{}", &value),
+            font: or_default(input.font, "font", |d| &d.font)?,
+            size: or_default(input.size, "size", |d| &d.size)?,
+            color: or_default(input.color, "color", |d| &d.color)?,
+            align: input.align,
+        })
+    }
+}
+
+impl SerdeElement for Formatted {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&Text {
+            color: self.color,
+            align: self.align,
+            ..Text::basic(&self.text, &*fonts[&self.font], self.size)
+        });
+    }
+}
+
+/// A value with its unit, like `12.5 kg`.
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "UnitValueInput")]
+pub struct UnitValue {
+    pub text: String,
+    pub font: String,
+    pub size: f64,
+    pub color: u32,
+    pub align: TextAlign,
+}
+
+#[derive(Deserialize)]
+struct UnitValueInput {
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    value: f64,
+
+    #[serde(default)]
+    unit: String,
+
+    #[serde(default, deserialize_with = "deserialize_decimals")]
+    decimals: usize,
+
+    #[serde(default)]
+    locale: Option<Locale>,
+
+    #[serde(default)]
+    font: Option<String>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_optional_pt")]
+    size: Option<f64>,
+
+    #[serde(default, deserialize_with = "super::color::deserialize_optional_color")]
+    color: Option<u32>,
+
+    #[serde(default = "defaults::default_align")]
+    align: TextAlign,
+
+    /// Lines the value up at the decimal separator of the locale, this far from the right edge in
+    /// mm, like [TextAlign::Decimal]. Values in a column line up if their cells all have the same
+    /// width. Overrides the alignment.
+    #[serde(default, deserialize_with = "expr::deserialize_optional_f64")]
+    fraction_width: Option<f64>,
+}
+
+impl TryFrom<UnitValueInput> for UnitValue {
+    type Error = String;
+
+    fn try_from(input: UnitValueInput) -> Result<Self, String> {
+        let locale = or_default(input.locale, "locale", |d| &d.locale).unwrap_or_default();
+
+        let align = match input.fraction_width {
+            Some(fraction_width) => TextAlign::Decimal {
+                separator: locale.decimal_separator(),
+                fraction_width,
+            },
+            Option::None => input.align,
+        };
+
+        Ok(UnitValue {
+            text: format_unit_value(input.value, &input.unit, input.decimals, locale),
+            font: or_default(input.font, "font", |d| &d.font)?,
+            size: or_default(input.size, "size", |d| &d.size)?,
+            color: or_default(input.color, "color", |d| &d.color)?,
+            align,
+        })
+    }
+}
+
+impl SerdeElement for UnitValue {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&Text {
+            color: self.color,
+            align: self.align,
+            ..Text::basic(&self.text, &*fonts[&self.font], self.size)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        elements::text::decimal_integer_len,
+        serde_elements::{defaults::Defaults, expr::Constants, ElementValue},
+    };
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(1234567.891, 2, Locale::EnUs), "1,234,567.89");
+        assert_eq!(format_number(1234567.891, 2, Locale::DeDe), "1.234.567,89");
+        assert_eq!(format_number(1234.5, 0, Locale::DeCh), "1’235");
+        assert_eq!(format_number(-999.999, 2, Locale::FrFr), "-1\u{202f}000,00");
+        assert_eq!(format_number(123., 1, Locale::ItIt), "123,0");
+        assert_eq!(format_number(-0.001, 2, Locale::EnUs), "0.00");
+
+        // Rounded like the decimal numbers they were written as.
+        assert_eq!(format_number(1.005, 2, Locale::EnUs), "1.01");
+        assert_eq!(format_number(-2.675, 2, Locale::EnUs), "-2.68");
+        assert_eq!(format_number(99.95, 1, Locale::EnUs), "100.0");
+        assert_eq!(format_number(0.5, 0, Locale::EnUs), "1");
+        assert_eq!(format_number(0.085, 2, Locale::EnUs), "0.09");
+        assert_eq!(format_number(1e-7, 2, Locale::EnUs), "0.00");
+    }
+
+    #[test]
+    fn test_format_currency() {
+        let style = CurrencyStyle::default();
+
+        assert_eq!(
+            format_currency(1234.5, "USD", 2, Locale::EnUs, style),
+            "$1,234.50"
+        );
+        assert_eq!(
+            format_currency(-3., "GBP", 2, Locale::EnGb, style),
+            "-£3.00"
+        );
+        assert_eq!(
+            format_currency(1234.5, "EUR", 2, Locale::DeDe, style),
+            "1.234,50\u{a0}€"
+        );
+        assert_eq!(
+            format_currency(-1234.5, "EUR", 2, Locale::NlNl, style),
+            "€\u{a0}-1.234,50"
+        );
+        assert_eq!(
+            format_currency(1234.5, "CHF", 2, Locale::DeCh, style),
+            "CHF\u{a0}1’234.50"
+        );
+
+        let accounting = CurrencyStyle {
+            display: CurrencyDisplay::Code,
+            negative: NegativeStyle::Parentheses,
+        };
+
+        assert_eq!(
+            format_currency(-1234.5, "USD", 2, Locale::EnUs, accounting),
+            "(USD\u{a0}1,234.50)"
+        );
+        assert_eq!(
+            format_currency(-0.004, "EUR", 2, Locale::DeDe, accounting),
+            "0,00\u{a0}EUR"
+        );
+    }
+
+    #[test]
+    fn test_format_unit_value() {
+        assert_eq!(
+            format_unit_value(12.5, "kg", 1, Locale::EnUs),
+            "12.5\u{a0}kg"
+        );
+        assert_eq!(
+            format_unit_value(1234.5, "m", 2, Locale::DeDe),
+            "1.234,50\u{a0}m"
+        );
+        assert_eq!(format_unit_value(3., "", 0, Locale::FrFr), "3");
+    }
+
+    #[test]
+    fn test_unit_value() {
+        let json = r#"{ "UnitValue": {
+            "value": "mass / 2",
+            "unit": "kg",
+            "decimals": 2,
+            "font": "f",
+            "size": 10,
+            "color": 0,
+            "fraction_width": 10
+        } }"#;
+
+        let constants = Constants([("mass".to_string(), 2469.)].into());
+        let defaults = Defaults {
+            locale: Some(Locale::DeDe),
+            ..Default::default()
+        };
+
+        let element = constants
+            .scope(|| defaults.scope(|| serde_json::from_str::<ElementValue>(json).unwrap()));
+
+        let ElementValue::UnitValue(unit_value) = element else {
+            panic!("expected a UnitValue element");
+        };
+
+        assert_eq!(unit_value.text, "1.234,50\u{a0}kg");
+        assert!(
+            unit_value.align
+                == TextAlign::Decimal {
+                    separator: ',',
+                    fraction_width: 10.,
+                }
+        );
+
+        let integer_len = decimal_integer_len(&unit_value.text, ',');
+        assert_eq!(&unit_value.text[..integer_len], "1.234");
+    }
+
+    #[test]
+    fn test_decimals_limit() {
+        let value = |decimals: &str| {
+            serde_json::from_str::<FormattedValue>(&format!(
+                r#"{{ "Number": {{ "number": 1, "decimals": {decimals} }} }}"#
+            ))
+        };
+
+        assert!(value("20").is_ok());
+        assert!(value("21").is_err());
+        assert!(value("1000000000000000000").is_err());
+
+        let unit_value = r#"{ "UnitValue": {
+            "value": 1, "decimals": 1000000000000000000, "font": "f", "size": 10, "color": 0
+        } }"#;
+        assert!(serde_json::from_str::<ElementValue>(unit_value).is_err());
+    }
+
+    #[test]
+    fn test_format_date() {
+        let date = Date::parse("2024-03-01").unwrap();
+
+        assert_eq!(
+            date,
+            Date {
+                year: 2024,
+                month: 3,
+                day: 1,
+            },
+        );
+
+        assert_eq!(
+            format_date(date, "dd.MM.yyyy", Locale::DeDe).unwrap(),
+            "01.03.2024"
+        );
+        assert_eq!(
+            format_date(date, "EEEE, d MMMM yyyy", Locale::FrFr).unwrap(),
+            "vendredi, 1 mars 2024"
+        );
+        assert_eq!(
+            format_date(date, "EEE d MMM ''yy", Locale::EnGb).unwrap(),
+            "Fri 1 Mar '24"
+        );
+        assert_eq!(
+            format_date(date, "'day' d 'of the year''s' M", Locale::EnUs).unwrap(),
+            "day 1 of the year's 3"
+        );
+
+        assert!(format_date(date, "ddd", Locale::EnUs).is_err());
+        assert!(format_date(date, "d 'of", Locale::EnUs).is_err());
+
+        assert!(Date::parse("2023-02-29").is_err());
+        assert!(Date::parse("2024-13-01").is_err());
+        assert!(Date::parse("1.3.2024").is_err());
+    }
+
+    #[test]
+    fn test_formatted() {
+        let json = r#"{ "Formatted": {
+            "text": "Total: {}",
+            "value": { "Currency": { "amount": "net * 2", "currency": "EUR" } },
+            "font": "f",
+            "size": 10,
+            "color": 0
+        } }"#;
+
+        let constants = Constants([("net".to_string(), 600.25)].into());
+        let defaults = Defaults {
+            locale: Some(Locale::DeDe),
+            ..Default::default()
+        };
+
+        let element = constants
+            .scope(|| defaults.scope(|| serde_json::from_str::<ElementValue>(json).unwrap()));
+
+        let ElementValue::Formatted(formatted) = element else {
+            panic!("expected a Formatted element");
+        };
+
+        assert_eq!(formatted.text, "Total: 1.200,50\u{a0}€");
+
+        let json = r#"{
+            "value": { "Date": { "date": "2024-02-30", "pattern": "d.M.yyyy" } },
+            "font": "f",
+            "size": 10,
+            "color": 0
+        }"#;
+
+        assert!(serde_json::from_str::<Formatted>(json).is_err());
+    }
+}
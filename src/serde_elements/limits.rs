@@ -0,0 +1,128 @@
+//! Limits for deserializing untrusted element trees. Like the constants and defaults these are
+//! made available with [Limits::scope] while deserializing. Without a scope nothing is limited.
+//!
+//! Layout recurses along the element tree as well, so limiting the depth here also limits the
+//! stack usage of building the document.
+
+use std::cell::Cell;
+
+use crate::utils::scoped;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Limits {
+    /// How deep elements can be nested. The root element is at depth 1.
+    pub max_depth: Option<u32>,
+
+    /// The total number of elements that can be deserialized in the scope.
+    pub max_elements: Option<u32>,
+}
+
+#[derive(Clone, Copy)]
+struct State {
+    limits: Limits,
+    depth: u32,
+    elements: u32,
+}
+
+thread_local! {
+    static CURRENT: Cell<Option<State>> = const { Cell::new(None) };
+}
+
+impl Limits {
+    /// Applies the limits to everything deserialized on this thread while `f` is running.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        let state = State {
+            limits: *self,
+            depth: 0,
+            elements: 0,
+        };
+
+        scoped(&CURRENT, Some(state), f).0
+    }
+}
+
+/// Leaves the element when dropped.
+pub struct Enter(());
+
+impl Drop for Enter {
+    fn drop(&mut self) {
+        CURRENT.with(|current| {
+            if let Some(mut state) = current.get() {
+                state.depth -= 1;
+                current.set(Some(state));
+            }
+        });
+    }
+}
+
+/// Called at the start of deserializing an element. Errors if that would exceed the limits.
+pub fn enter<E: serde::de::Error>() -> Result<Enter, E> {
+    CURRENT.with(|current| {
+        let mut state = match current.get() {
+            Some(state) => state,
+            None => return Ok(()),
+        };
+
+        state.depth += 1;
+        state.elements += 1;
+
+        if let Some(max_depth) = state.limits.max_depth.filter(|&m| state.depth > m) {
+            return Err(E::custom(format!(
+                "elements are nested deeper than {max_depth} levels"
+            )));
+        }
+
+        if let Some(max_elements) = state.limits.max_elements.filter(|&m| state.elements > m) {
+            return Err(E::custom(format!("more than {max_elements} elements")));
+        }
+
+        current.set(Some(state));
+
+        Ok(())
+    })?;
+
+    Ok(Enter(()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde_elements::ElementValue;
+
+    #[test]
+    fn test_limits() {
+        let json = r#"{ "Column": {
+            "gap": 0,
+            "content": [
+                { "VGap": { "gap": 1 } },
+                { "Column": { "gap": 0, "content": [{ "VGap": { "gap": 1 } }] } }
+            ]
+        } }"#;
+
+        let parse = |limits: Limits| {
+            limits
+                .scope(|| serde_json::from_str::<ElementValue>(json))
+                .map(|_| ())
+        };
+
+        assert!(parse(Limits::default()).is_ok());
+
+        assert!(parse(Limits {
+            max_depth: Some(3),
+            max_elements: Some(4),
+        })
+        .is_ok());
+
+        assert!(parse(Limits {
+            max_depth: Some(2),
+            ..Default::default()
+        })
+        .is_err());
+
+        assert!(parse(Limits {
+            max_elements: Some(3),
+            ..Default::default()
+        })
+        .is_err());
+    }
+}
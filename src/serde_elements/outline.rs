@@ -0,0 +1,909 @@
+//! Hierarchical numbering like `1.`, `1.1` and `1.1.1` for contracts and similar documents. The
+//! counters live in an [Outline], which is made available with [Outline::scope] while
+//! deserializing, so they continue across the whole document:
+//!
+//! ```json
+//! { "Numbered": { "level": 2, "anchor": "termination", "number_width": 12, "element": ... } }
+//! ```
+//!
+//! A [NumberRef] shows the number of the [Numbered] element with the same anchor. References are
+//! resolved when the element is built, so they can point to sections further down in the
+//! document. An anchor that doesn't exist is shown as `??`.
+//!
+//! A [Figure] with a label like `"Figure {}: "` gets the next figure number, which is counted
+//! separately from the sections. Its anchor can be referenced like the ones of sections.
+//!
+//! [RefText] can also show the page of an anchor, like in `"see section {number:termination} on
+//! page {page:termination}"`. Pages are only known after layout, so documents with page references
+//! have to be laid out twice, calling [Outline::resolve_pages] in between. The page numbers are
+//! from the first layout, so they can be off if the references change the layout.
+//!
+//! Totals of the document work the same way with the [facts](crate::facts) of the first layout:
+//! `{pages}` is the number of pages and `{count:attachments}` the total of the `attachments`
+//! counter, which is added to by [Count] elements.
+
+use std::{cell::RefCell, collections::HashMap, ops::Index, rc::Rc};
+
+use serde::Deserialize;
+
+use crate::{
+    elements::{
+        figure,
+        image::{ImageElement, ImageRole},
+        row::{Flex, Row},
+        text::{Text, TextAlign},
+    },
+    utils::scoped,
+    *,
+};
+
+use super::{
+    defaults::{self, or_default},
+    expr, Font, SerdeElement, SerdeElementElement,
+};
+
+#[derive(Default)]
+struct State {
+    counters: Vec<u32>,
+    figures: u32,
+    anchors: HashMap<String, String>,
+
+    /// Whether any [RefText] references a page.
+    page_refs: bool,
+
+    /// Whether any [RefText] shows a fact of the document.
+    fact_refs: bool,
+
+    /// The page indices of the anchors drawn in the current layout.
+    pages: HashMap<String, usize>,
+
+    /// The page indices from the previous layout, used by [RefText].
+    resolved_pages: HashMap<String, usize>,
+}
+
+/// The counters and anchors of a document. Clones share the state.
+#[derive(Clone, Default)]
+pub struct Outline(Rc<RefCell<State>>);
+
+thread_local! {
+    static CURRENT: RefCell<Option<Outline>> = const { RefCell::new(None) };
+}
+
+impl Outline {
+    /// Makes the outline available to [Numbered] and [NumberRef] elements deserialized on this
+    /// thread while `f` is running.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        scoped(&CURRENT, Some(self.clone()), f).0
+    }
+
+    /// Counts a new section at the level, starting at 1, and returns its number.
+    pub fn next(&self, level: usize) -> Result<String, String> {
+        let mut state = self.0.borrow_mut();
+
+        if level == 0 || level > state.counters.len() + 1 {
+            return Err(format!(
+                "level {level} can't follow level {}",
+                state.counters.len()
+            ));
+        }
+
+        state.counters.truncate(level);
+
+        if state.counters.len() < level {
+            state.counters.push(0);
+        }
+
+        state.counters[level - 1] += 1;
+
+        Ok(format_number(&state.counters))
+    }
+
+    /// Counts a new figure, starting at 1, and returns its number.
+    pub fn next_figure(&self) -> String {
+        let mut state = self.0.borrow_mut();
+        state.figures += 1;
+        state.figures.to_string()
+    }
+
+    /// The number of the section with the anchor, if there is one yet.
+    pub fn number(&self, anchor: &str) -> Option<String> {
+        self.0.borrow().anchors.get(anchor).cloned()
+    }
+
+    /// Whether the document has to be laid out twice because of page references.
+    pub fn has_page_refs(&self) -> bool {
+        self.0.borrow().page_refs
+    }
+
+    /// Whether the document has to be laid out twice, with the facts of the first layout available
+    /// to the second one, see [with_facts](crate::facts::with_facts).
+    pub fn has_fact_refs(&self) -> bool {
+        self.0.borrow().fact_refs
+    }
+
+    /// Makes the pages of the anchors drawn since the last call available to [RefText].
+    pub fn resolve_pages(&self) {
+        let mut state = self.0.borrow_mut();
+        state.resolved_pages = std::mem::take(&mut state.pages);
+    }
+
+    /// The page number of the anchor from the last layout before [Outline::resolve_pages],
+    /// starting at 1.
+    pub fn page(&self, anchor: &str) -> Option<usize> {
+        self.0
+            .borrow()
+            .resolved_pages
+            .get(anchor)
+            .map(|page| page + 1)
+    }
+
+    fn add_page(&self, anchor: &str, page: usize) {
+        self.0
+            .borrow_mut()
+            .pages
+            .entry(anchor.to_string())
+            .or_insert(page);
+    }
+
+    fn add_anchor(&self, anchor: String, number: String) -> Result<(), String> {
+        let mut state = self.0.borrow_mut();
+
+        if state.anchors.contains_key(&anchor) {
+            return Err(format!("duplicate anchor `{anchor}`"));
+        }
+
+        state.anchors.insert(anchor, number);
+
+        Ok(())
+    }
+}
+
+fn format_number(counters: &[u32]) -> String {
+    let number = counters
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(".");
+
+    if counters.len() == 1 {
+        number + "."
+    } else {
+        number
+    }
+}
+
+fn current() -> Result<Outline, String> {
+    CURRENT
+        .with(|current| current.borrow().clone())
+        .ok_or_else(|| "numbered elements need an outline".to_string())
+}
+
+/// An element with its section number to the left of it.
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "NumberedInput<E>")]
+pub struct Numbered<E> {
+    pub number: String,
+    pub anchor: Option<String>,
+    pub outline: Outline,
+    pub font: String,
+    pub size: f64,
+    pub color: u32,
+    pub number_width: f64,
+    pub gap: f64,
+    pub element: E,
+}
+
+#[derive(Deserialize)]
+struct NumberedInput<E> {
+    /// Starts at 1.
+    level: usize,
+
+    #[serde(default)]
+    anchor: Option<String>,
+
+    #[serde(default)]
+    font: Option<String>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_optional_pt")]
+    size: Option<f64>,
+
+    #[serde(default, deserialize_with = "super::color::deserialize_optional_color")]
+    color: Option<u32>,
+
+    #[serde(deserialize_with = "expr::deserialize_f64")]
+    number_width: f64,
+
+    #[serde(default, deserialize_with = "expr::deserialize_f64")]
+    gap: f64,
+
+    element: E,
+}
+
+impl<E> TryFrom<NumberedInput<E>> for Numbered<E> {
+    type Error = String;
+
+    fn try_from(input: NumberedInput<E>) -> Result<Self, String> {
+        let outline = current()?;
+        let number = outline.next(input.level)?;
+
+        if let Some(ref anchor) = input.anchor {
+            outline.add_anchor(anchor.clone(), number.clone())?;
+        }
+
+        Ok(Numbered {
+            number,
+            anchor: input.anchor,
+            outline,
+            font: or_default(input.font, "font", |d| &d.font)?,
+            size: or_default(input.size, "size", |d| &d.size)?,
+            color: or_default(input.color, "color", |d| &d.color)?,
+            number_width: input.number_width,
+            gap: input.gap,
+            element: input.element,
+        })
+    }
+}
+
+impl<E: SerdeElement> SerdeElement for Numbered<E> {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        let number = Text {
+            color: self.color,
+            ..Text::basic(&self.number, &*fonts[&self.font], self.size)
+        };
+
+        let row = Row {
+            gap: self.gap,
+            expand: true,
+            collapse: false,
+            content: |content| {
+                content.add(&number, Flex::Fixed(self.number_width));
+                content.add(
+                    &SerdeElementElement {
+                        element: &self.element,
+                        fonts,
+                    },
+                    Flex::Expand(1),
+                );
+            },
+        };
+
+        if let Some(ref anchor) = self.anchor {
+            callback.call(&Anchor {
+                outline: &self.outline,
+                anchor,
+                element: &row,
+            });
+        } else {
+            callback.call(&row);
+        }
+    }
+}
+
+/// Records the page the element starts on.
+struct Anchor<'a, E: Element> {
+    outline: &'a Outline,
+    anchor: &'a str,
+    element: &'a E,
+}
+
+impl<'a, E: Element> Element for Anchor<'a, E> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        // The page of every location the element was drawn on so far.
+        let mut pages = vec![ctx.location.layer.page.0];
+        let mut first_page = None;
+
+        let size = if let Some(breakable) = ctx.breakable {
+            self.element.draw(DrawCtx {
+                breakable: Some(BreakableDraw {
+                    do_break: &mut |pdf, location_idx, height| {
+                        let location = (breakable.do_break)(pdf, location_idx, height);
+
+                        let idx = location_idx as usize;
+
+                        // A height means something was drawn on the location that's left. Skipped
+                        // locations can still report a height of zero.
+                        if first_page.is_none() && matches!(height, Some(h) if h > 0.) {
+                            first_page = pages.get(idx).copied();
+                        }
+
+                        if pages.len() <= idx + 1 {
+                            pages.resize(idx + 2, location.layer.page.0);
+                        }
+
+                        location
+                    },
+                    ..breakable
+                }),
+                ..ctx
+            })
+        } else {
+            self.element.draw(ctx)
+        };
+
+        if let Some(page) = first_page.or(size.height.and(pages.last().copied())) {
+            self.outline.add_page(self.anchor, page);
+        }
+
+        size
+    }
+}
+
+/// The number of the [Numbered] element with the anchor, as text.
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "NumberRefInput")]
+pub struct NumberRef {
+    pub anchor: String,
+    pub font: String,
+    pub size: f64,
+    pub color: u32,
+    pub outline: Outline,
+}
+
+#[derive(Deserialize)]
+struct NumberRefInput {
+    anchor: String,
+
+    #[serde(default)]
+    font: Option<String>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_optional_pt")]
+    size: Option<f64>,
+
+    #[serde(default, deserialize_with = "super::color::deserialize_optional_color")]
+    color: Option<u32>,
+}
+
+impl TryFrom<NumberRefInput> for NumberRef {
+    type Error = String;
+
+    fn try_from(input: NumberRefInput) -> Result<Self, String> {
+        Ok(NumberRef {
+            anchor: input.anchor,
+            font: or_default(input.font, "font", |d| &d.font)?,
+            size: or_default(input.size, "size", |d| &d.size)?,
+            color: or_default(input.color, "color", |d| &d.color)?,
+            outline: current()?,
+        })
+    }
+}
+
+impl SerdeElement for NumberRef {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        let number = self
+            .outline
+            .number(&self.anchor)
+            .unwrap_or_else(|| "??".to_string());
+
+        callback.call(&Text {
+            color: self.color,
+            ..Text::basic(&number, &*fonts[&self.font], self.size)
+        });
+    }
+}
+
+/// An image with a caption below it, see [figure::Figure].
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "FigureInput")]
+pub struct Figure {
+    pub image: Rc<crate::image::Image>,
+    pub alt: Option<String>,
+
+    /// Including the label.
+    pub caption: String,
+
+    pub anchor: Option<String>,
+    pub outline: Option<Outline>,
+    pub font: String,
+    pub size: f64,
+    pub color: u32,
+    pub align: TextAlign,
+    pub gap: f64,
+    pub border: Option<LineStyle>,
+}
+
+#[derive(Deserialize)]
+struct FigureInput {
+    #[serde(deserialize_with = "crate::image::deserialize_shared_image")]
+    path: Rc<crate::image::Image>,
+
+    #[serde(default)]
+    alt: Option<String>,
+
+    caption: String,
+
+    /// Put in front of the caption with `{}` replaced by the number of the figure, like
+    /// `"Figure {}: "`. Figures without a label aren't numbered.
+    #[serde(default)]
+    label: Option<String>,
+
+    /// Needs a label.
+    #[serde(default)]
+    anchor: Option<String>,
+
+    #[serde(default)]
+    font: Option<String>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_optional_pt")]
+    size: Option<f64>,
+
+    #[serde(default, deserialize_with = "super::color::deserialize_optional_color")]
+    color: Option<u32>,
+
+    #[serde(default = "defaults::default_align")]
+    align: TextAlign,
+
+    #[serde(default, deserialize_with = "expr::deserialize_f64")]
+    gap: f64,
+
+    #[serde(default)]
+    border: Option<LineStyle>,
+}
+
+impl TryFrom<FigureInput> for Figure {
+    type Error = String;
+
+    fn try_from(input: FigureInput) -> Result<Self, String> {
+        let (caption, outline) = match input.label {
+            Some(label) => {
+                let outline = current()?;
+                let number = outline.next_figure();
+
+                if let Some(ref anchor) = input.anchor {
+                    outline.add_anchor(anchor.clone(), number.clone())?;
+                }
+
+                (label.replace("{}", &number) + &input.caption, Some(outline))
+            }
+            None if input.anchor.is_some() => {
+                return Err("figures need a label to have an anchor".into());
+            }
+            None => (input.caption, None),
+        };
+
+        Ok(Figure {
+            image: input.path,
+            alt: input.alt,
+            caption,
+            anchor: input.anchor,
+            outline,
+            font: or_default(input.font, "font", |d| &d.font)?,
+            size: or_default(input.size, "size", |d| &d.size)?,
+            color: or_default(input.color, "color", |d| &d.color)?,
+            align: input.align,
+            gap: input.gap,
+            border: input.border,
+        })
+    }
+}
+
+impl SerdeElement for Figure {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        let caption = Text {
+            color: self.color,
+            align: self.align,
+            ..Text::basic(&self.caption, &*fonts[&self.font], self.size)
+        };
+
+        let figure = figure::Figure {
+            image: ImageElement {
+                alt: self.alt.as_deref(),
+                caption: Some(&self.caption),
+                role: ImageRole::Figure,
+                ..ImageElement::new(&self.image)
+            },
+            caption: &caption,
+            gap: self.gap,
+            border: self.border,
+        };
+
+        match (&self.anchor, &self.outline) {
+            (Some(anchor), Some(outline)) => callback.call(&Anchor {
+                outline,
+                anchor,
+                element: &figure,
+            }),
+            _ => callback.call(&figure),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RefTextPart {
+    Text(String),
+    Number(String),
+    Page(String),
+
+    /// The number of pages of the document.
+    Pages,
+
+    /// The total of a counter of the document.
+    Count(String),
+}
+
+fn parse_ref_text(mut text: &str) -> Result<Vec<RefTextPart>, String> {
+    let mut parts = Vec::new();
+
+    while !text.is_empty() {
+        let placeholder = ["{number:", "{page:", "{pages}", "{count:"]
+            .iter()
+            .filter_map(|prefix| text.find(prefix).map(|i| (i, *prefix)))
+            .min();
+
+        let (start, prefix) = match placeholder {
+            Some(placeholder) => placeholder,
+            None => {
+                parts.push(RefTextPart::Text(text.to_string()));
+                break;
+            }
+        };
+
+        if start > 0 {
+            parts.push(RefTextPart::Text(text[..start].to_string()));
+        }
+
+        let rest = &text[start + prefix.len()..];
+
+        if prefix == "{pages}" {
+            parts.push(RefTextPart::Pages);
+            text = rest;
+            continue;
+        }
+
+        let end = rest
+            .find('}')
+            .ok_or_else(|| format!("unclosed placeholder in `{text}`"))?;
+        let anchor = rest[..end].to_string();
+
+        parts.push(match prefix {
+            "{page:" => RefTextPart::Page(anchor),
+            "{count:" => RefTextPart::Count(anchor),
+            _ => RefTextPart::Number(anchor),
+        });
+
+        text = &rest[end + 1..];
+    }
+
+    Ok(parts)
+}
+
+/// Text with placeholders for the number (`{number:anchor}`) and the page (`{page:anchor}`) of
+/// [Numbered] elements and for the number of pages (`{pages}`) and the total of a counter
+/// (`{count:name}`) of the document. Unknown anchors and facts that aren't known yet are shown as
+/// `??`.
+#[derive(Clone, Deserialize)]
+#[serde(try_from = "RefTextInput")]
+pub struct RefText {
+    pub parts: Vec<RefTextPart>,
+    pub font: String,
+    pub size: f64,
+    pub color: u32,
+    pub align: TextAlign,
+    pub outline: Outline,
+}
+
+#[derive(Deserialize)]
+struct RefTextInput {
+    text: String,
+
+    #[serde(default)]
+    font: Option<String>,
+
+    #[serde(default, deserialize_with = "expr::deserialize_optional_pt")]
+    size: Option<f64>,
+
+    #[serde(default, deserialize_with = "super::color::deserialize_optional_color")]
+    color: Option<u32>,
+
+    #[serde(default = "defaults::default_align")]
+    align: TextAlign,
+}
+
+impl TryFrom<RefTextInput> for RefText {
+    type Error = String;
+
+    fn try_from(input: RefTextInput) -> Result<Self, String> {
+        let parts = parse_ref_text(&input.text)?;
+        let outline = current()?;
+
+        if parts.iter().any(|p| matches!(p, RefTextPart::Page(_))) {
+            outline.0.borrow_mut().page_refs = true;
+        }
+
+        if parts
+            .iter()
+            .any(|p| matches!(p, RefTextPart::Pages | RefTextPart::Count(_)))
+        {
+            outline.0.borrow_mut().fact_refs = true;
+        }
+
+        Ok(RefText {
+            parts,
+            font: or_default(input.font, "font", |d| &d.font)?,
+            size: or_default(input.size, "size", |d| &d.size)?,
+            color: or_default(input.color, "color", |d| &d.color)?,
+            align: input.align,
+            outline,
+        })
+    }
+}
+
+impl SerdeElement for RefText {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        let text = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                RefTextPart::Text(text) => Some(text.clone()),
+                RefTextPart::Number(anchor) => self.outline.number(anchor),
+                RefTextPart::Page(anchor) => self.outline.page(anchor).map(|p| p.to_string()),
+                RefTextPart::Pages => facts::pages().map(|p| p.to_string()),
+                RefTextPart::Count(name) => facts::counter(name).map(|c| c.to_string()),
+            })
+            .map(|part| part.unwrap_or_else(|| "??".to_string()))
+            .collect::<String>();
+
+        callback.call(&Text {
+            color: self.color,
+            align: self.align,
+            ..Text::basic(&text, &*fonts[&self.font], self.size)
+        });
+    }
+}
+
+/// Adds to a counter of the document every time it's drawn, for `{count:name}` in [RefText].
+#[derive(Clone, Deserialize)]
+pub struct Count {
+    pub counter: String,
+
+    #[serde(default = "default_amount")]
+    pub amount: u32,
+}
+
+fn default_amount() -> u32 {
+    1
+}
+
+impl SerdeElement for Count {
+    fn element(
+        &self,
+        _: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        callback.call(&facts::Count {
+            counter: &self.counter,
+            amount: self.amount,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde_elements::ElementValue;
+
+    #[test]
+    fn test_numbering() {
+        let outline = Outline::default();
+
+        let numbers = [1, 2, 2, 3, 1, 2]
+            .map(|level| outline.next(level).unwrap())
+            .join(" ");
+
+        assert_eq!(numbers, "1. 1.1 1.2 1.2.1 2. 2.1");
+
+        assert!(outline.next(4).is_err());
+        assert!(outline.next(0).is_err());
+    }
+
+    #[test]
+    fn test_anchors() {
+        let json = r#"{ "Column": {
+            "gap": 0,
+            "content": [
+                { "NumberRef": { "anchor": "b", "font": "f", "size": 10, "color": 0 } },
+                { "Numbered": {
+                    "level": 1,
+                    "font": "f",
+                    "size": 10,
+                    "color": 0,
+                    "number_width": 10,
+                    "element": { "None": null }
+                } },
+                { "Numbered": {
+                    "level": 2,
+                    "anchor": "b",
+                    "font": "f",
+                    "size": 10,
+                    "color": 0,
+                    "number_width": 10,
+                    "element": { "None": null }
+                } }
+            ]
+        } }"#;
+
+        assert!(serde_json::from_str::<ElementValue>(json).is_err());
+
+        let outline = Outline::default();
+
+        outline.scope(|| serde_json::from_str::<ElementValue>(json).unwrap());
+
+        assert_eq!(outline.number("b").as_deref(), Some("1.1"));
+        assert_eq!(outline.number("c"), None);
+    }
+
+    #[test]
+    #[cfg(feature = "fs")]
+    fn test_figures() {
+        let dir = std::env::temp_dir().join(format!("laser-pdf-figure-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let path = dir.join("figure.svg");
+        std::fs::write(
+            &path,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="10" height="10"/>"#,
+        )
+        .unwrap();
+
+        let figure = |label: &str, anchor: &str| {
+            format!(
+                r#"{{ "Figure": {{
+                    "path": {path:?},
+                    "caption": "Revenue",
+                    {label}
+                    {anchor}
+                    "font": "f",
+                    "size": 10,
+                    "color": 0
+                }} }}"#
+            )
+        };
+
+        let outline = Outline::default();
+
+        let captions = outline.scope(|| {
+            [
+                figure(r#""label": "Figure {}: ","#, ""),
+                figure("", ""),
+                figure(r#""label": "Fig. {} – ","#, r#""anchor": "revenue","#),
+            ]
+            .map(
+                |json| match serde_json::from_str::<ElementValue>(&json).unwrap() {
+                    ElementValue::Figure(figure) => figure.caption,
+                    _ => panic!("expected a Figure element"),
+                },
+            )
+        });
+
+        assert_eq!(
+            captions,
+            ["Figure 1: Revenue", "Revenue", "Fig. 2 – Revenue"]
+        );
+        assert_eq!(outline.number("revenue").as_deref(), Some("2"));
+
+        // Numbering needs an outline and anchors need a number.
+        assert!(serde_json::from_str::<ElementValue>(&figure(r#""label": "{}","#, "")).is_err());
+        assert!(outline.scope(|| {
+            serde_json::from_str::<ElementValue>(&figure("", r#""anchor": "a","#)).is_err()
+        }));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_parse_ref_text() {
+        use RefTextPart::*;
+
+        assert_eq!(
+            parse_ref_text("see {number:a} on page {page:a}.").unwrap(),
+            [
+                Text("see ".to_string()),
+                Number("a".to_string()),
+                Text(" on page ".to_string()),
+                Page("a".to_string()),
+                Text(".".to_string()),
+            ],
+        );
+
+        assert_eq!(
+            parse_ref_text("{count:attachments} attachments, {pages} pages").unwrap(),
+            [
+                Count("attachments".to_string()),
+                Text(" attachments, ".to_string()),
+                Pages,
+                Text(" pages".to_string()),
+            ],
+        );
+
+        assert_eq!(parse_ref_text("{x}").unwrap(), [Text("{x}".to_string())]);
+        assert!(parse_ref_text("{page:a").is_err());
+    }
+
+    #[test]
+    fn test_fact_refs() {
+        let ref_text = |text: &str| {
+            format!(
+                r#"{{ "RefText": {{ "text": "{text}", "font": "f", "size": 10, "color": 0 }} }}"#
+            )
+        };
+
+        let outline = Outline::default();
+
+        outline.scope(|| serde_json::from_str::<ElementValue>(&ref_text("{page:a}")).unwrap());
+        assert!(outline.has_page_refs());
+        assert!(!outline.has_fact_refs());
+
+        outline.scope(|| serde_json::from_str::<ElementValue>(&ref_text("{pages}")).unwrap());
+        assert!(outline.has_fact_refs());
+
+        let count = r#"{ "Count": { "counter": "attachments" } }"#;
+
+        match serde_json::from_str::<ElementValue>(count).unwrap() {
+            ElementValue::Count(count) => assert_eq!(count.amount, 1),
+            _ => panic!("expected a Count element"),
+        }
+    }
+
+    #[test]
+    fn test_anchor_pages() {
+        use crate::test_utils::*;
+
+        let page = |first_height| {
+            let outline = Outline::default();
+
+            let element = Anchor {
+                outline: &outline,
+                anchor: "a",
+                element: &FakeText {
+                    lines: 3,
+                    line_height: 5.,
+                    width: 1.,
+                },
+            };
+
+            test_measure_draw_compatibility(
+                &element,
+                WidthConstraint {
+                    max: 10.,
+                    expand: false,
+                },
+                first_height,
+                Some(10.),
+                (0., 10.),
+                (10., 10.),
+            );
+
+            assert_eq!(outline.page("a"), None);
+            outline.resolve_pages();
+            outline.page("a")
+        };
+
+        assert_eq!(page(10.), Some(1));
+
+        // The first line doesn't fit on the first location, so the text starts on the second page.
+        assert_eq!(page(2.), Some(2));
+    }
+}
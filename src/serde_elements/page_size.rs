@@ -0,0 +1,191 @@
+//! Page sizes can be given either as `[width, height]` in mm or by name, like `"A4"` or
+//! `"Letter landscape"`. Names are case insensitive. Additional names can be made available with
+//! [PageSizes::scope] while deserializing.
+//!
+//! Widths and heights have to be between [MIN_LENGTH] and [MAX_LENGTH]. A page without height
+//! would make breakable content request new pages forever.
+
+use std::{cell::RefCell, collections::HashMap, fmt};
+
+use serde::{
+    de::{Error, SeqAccess, Visitor},
+    Deserializer,
+};
+
+use super::expr;
+use crate::utils::scoped;
+
+/// The smallest width or height of a page, in mm.
+pub const MIN_LENGTH: f64 = 1.;
+
+/// The largest width or height of a page, in mm. This is the 14400 units PDF viewers support.
+pub const MAX_LENGTH: f64 = 14400. * 25.4 / 72.;
+
+/// The built in sizes in mm, in portrait orientation.
+const PRESETS: &[(&str, (f64, f64))] = &[
+    ("a3", (297., 420.)),
+    ("a4", (210., 297.)),
+    ("a5", (148., 210.)),
+    ("a6", (105., 148.)),
+    ("b5", (176., 250.)),
+    ("letter", (215.9, 279.4)),
+    ("legal", (215.9, 355.6)),
+    ("tabloid", (279.4, 431.8)),
+];
+
+/// Custom page sizes by name, in mm. These take precedence over the built in ones.
+#[derive(Clone, Debug, Default)]
+pub struct PageSizes(pub HashMap<String, (f64, f64)>);
+
+thread_local! {
+    static CURRENT: RefCell<Option<PageSizes>> = const { RefCell::new(None) };
+}
+
+impl PageSizes {
+    /// Makes the page sizes available to everything deserialized on this thread while `f` is
+    /// running.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        scoped(&CURRENT, Some(self.clone()), f).0
+    }
+}
+
+/// Looks up a page size by name, taking the page sizes of the current scope into account. A
+/// `landscape` suffix swaps width and height.
+pub fn page_size(name: &str) -> Option<(f64, f64)> {
+    let name = name.trim();
+
+    let (name, landscape) = match name.len().checked_sub("landscape".len()) {
+        Some(i) if name.is_char_boundary(i) && name[i..].eq_ignore_ascii_case("landscape") => {
+            (name[..i].trim_end(), true)
+        }
+        _ => (name, false),
+    };
+
+    let custom = CURRENT.with(|current| {
+        current.borrow().as_ref().and_then(|sizes| {
+            sizes
+                .0
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, &size)| size)
+        })
+    });
+
+    let (width, height) = custom.or_else(|| {
+        PRESETS
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|&(_, size)| size)
+    })?;
+
+    Some(if landscape {
+        (height, width)
+    } else {
+        (width, height)
+    })
+}
+
+/// For use with `#[serde(deserialize_with = "...")]` on `(f64, f64)` page size fields.
+pub fn deserialize_page_size<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<(f64, f64), D::Error> {
+    struct PageSizeVisitor;
+
+    impl<'de> Visitor<'de> for PageSizeVisitor {
+        type Value = (f64, f64);
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("a page size name or a [width, height] pair")
+        }
+
+        fn visit_str<E: Error>(self, v: &str) -> Result<Self::Value, E> {
+            let size = page_size(v).ok_or_else(|| E::custom(format!("unknown page size `{v}`")))?;
+            validate(size).map_err(E::custom)
+        }
+
+        fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+            struct Length(f64);
+
+            impl<'de> serde::Deserialize<'de> for Length {
+                fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    expr::deserialize_f64(deserializer).map(Length)
+                }
+            }
+
+            let width = seq
+                .next_element::<Length>()?
+                .ok_or_else(|| A::Error::invalid_length(0, &self))?;
+            let height = seq
+                .next_element::<Length>()?
+                .ok_or_else(|| A::Error::invalid_length(1, &self))?;
+
+            if seq.next_element::<serde::de::IgnoredAny>()?.is_some() {
+                return Err(A::Error::invalid_length(3, &self));
+            }
+
+            validate((width.0, height.0)).map_err(A::Error::custom)
+        }
+    }
+
+    deserializer.deserialize_any(PageSizeVisitor)
+}
+
+fn validate((width, height): (f64, f64)) -> Result<(f64, f64), String> {
+    for length in [width, height] {
+        // Also false for NaN.
+        if !(MIN_LENGTH..=MAX_LENGTH).contains(&length) {
+            return Err(format!(
+                "page size [{width}, {height}] isn't between {MIN_LENGTH}mm and {MAX_LENGTH:.1}mm"
+            ));
+        }
+    }
+
+    Ok((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(serde::Deserialize)]
+    struct Page {
+        #[serde(deserialize_with = "deserialize_page_size")]
+        size: (f64, f64),
+    }
+
+    fn parse(json: &str) -> Result<(f64, f64), serde_json::Error> {
+        serde_json::from_str::<Page>(json).map(|p| p.size)
+    }
+
+    #[test]
+    fn test_page_size() {
+        assert_eq!(parse(r#"{ "size": "A4" }"#).unwrap(), (210., 297.));
+        assert_eq!(
+            parse(r#"{ "size": "a4 Landscape" }"#).unwrap(),
+            (297., 210.)
+        );
+        assert_eq!(parse(r#"{ "size": [100, "5cm"] }"#).unwrap(), (100., 50.));
+        assert!(parse(r#"{ "size": "A0" }"#).is_err());
+        assert!(parse(r#"{ "size": [100] }"#).is_err());
+        assert!(parse(r#"{ "size": [1, 2, 3] }"#).is_err());
+
+        let sizes = PageSizes(HashMap::from([("A0".to_string(), (841., 1189.))]));
+
+        assert_eq!(
+            sizes.scope(|| parse(r#"{ "size": "A0 landscape" }"#).unwrap()),
+            (1189., 841.),
+        );
+    }
+
+    #[test]
+    fn test_invalid_page_size() {
+        assert!(parse(r#"{ "size": [0, 0] }"#).is_err());
+        assert!(parse(r#"{ "size": [210, -297] }"#).is_err());
+        assert!(parse(r#"{ "size": [210, 0.01] }"#).is_err());
+        assert!(parse(r#"{ "size": [1e9, 297] }"#).is_err());
+        assert_eq!(parse(r#"{ "size": [1, 1] }"#).unwrap(), (1., 1.));
+
+        let sizes = PageSizes(HashMap::from([("flat".to_string(), (100., 0.))]));
+        assert!(sizes.scope(|| parse(r#"{ "size": "flat" }"#)).is_err());
+    }
+}
@@ -0,0 +1,199 @@
+//! Support for element types defined outside of this crate. Types are registered under a name in a
+//! [Registry] and deserialization of an [ElementValue](super::ElementValue) then has to happen
+//! inside of [Registry::scope]. In JSON a custom element looks like this:
+//!
+//! ```json
+//! { "Custom": { "type": "MyElement", "some_field": 1 } }
+//! ```
+//!
+//! Everything except `type` is passed on to the deserializer of the registered type.
+
+use std::{cell::RefCell, collections::HashMap, ops::Index, rc::Rc};
+
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+
+use crate::{utils::scoped, *};
+
+use super::{Font, SerdeElement};
+
+/// An object safe version of [SerdeElement]. This is implemented for all [SerdeElement]s.
+pub trait DynSerdeElement {
+    fn dyn_element(
+        &self,
+        fonts: &dyn for<'a> Index<&'a str, Output = Font>,
+        callback: &mut dyn FnMut(&dyn Element),
+    );
+}
+
+struct DynFonts<'a>(&'a dyn for<'b> Index<&'b str, Output = Font>);
+
+impl<'a, 'b> Index<&'b str> for DynFonts<'a> {
+    type Output = Font;
+
+    fn index(&self, index: &'b str) -> &Font {
+        &self.0[index]
+    }
+}
+
+struct DynCallback<'a>(&'a mut dyn FnMut(&dyn Element));
+
+impl<'a> CompositeElementCallback for DynCallback<'a> {
+    fn call(self, element: &impl Element) {
+        (self.0)(element);
+    }
+}
+
+impl<T: SerdeElement> DynSerdeElement for T {
+    fn dyn_element(
+        &self,
+        fonts: &dyn for<'a> Index<&'a str, Output = Font>,
+        callback: &mut dyn FnMut(&dyn Element),
+    ) {
+        self.element(&DynFonts(fonts), DynCallback(callback));
+    }
+}
+
+struct DynElement<'a>(&'a dyn Element);
+
+impl<'a> Element for DynElement<'a> {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.0.first_location_usage(ctx)
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.0.measure(ctx)
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        self.0.draw(ctx)
+    }
+}
+
+type Constructor = dyn Fn(serde_json::Value) -> Result<Rc<dyn DynSerdeElement>, serde_json::Error>;
+
+#[derive(Clone, Default)]
+pub struct Registry {
+    constructors: HashMap<String, Rc<Constructor>>,
+}
+
+thread_local! {
+    static CURRENT: RefCell<Option<Registry>> = const { RefCell::new(None) };
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<T: SerdeElement + DeserializeOwned + 'static>(&mut self, name: &str) {
+        self.constructors.insert(
+            name.to_string(),
+            Rc::new(|value| {
+                Ok(Rc::new(serde_json::from_value::<T>(value)?) as Rc<dyn DynSerdeElement>)
+            }),
+        );
+    }
+
+    /// Makes the registry available to any [Custom] elements deserialized on this thread while `f`
+    /// is running.
+    pub fn scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        scoped(&CURRENT, Some(self.clone()), f).0
+    }
+}
+
+#[derive(Clone)]
+pub struct Custom {
+    pub type_name: String,
+    pub element: Rc<dyn DynSerdeElement>,
+}
+
+impl<'de> Deserialize<'de> for Custom {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let mut fields = serde_json::Map::deserialize(deserializer)?;
+
+        let type_name = match fields.remove("type") {
+            Some(serde_json::Value::String(type_name)) => type_name,
+            Some(_) => {
+                return Err(D::Error::custom(
+                    "`type` of a custom element must be a string",
+                ))
+            }
+            None => return Err(D::Error::missing_field("type")),
+        };
+
+        let constructor = CURRENT
+            .with(|current| {
+                current
+                    .borrow()
+                    .as_ref()
+                    .and_then(|registry| registry.constructors.get(&type_name).cloned())
+            })
+            .ok_or_else(|| {
+                D::Error::custom(format!("no custom element registered as `{type_name}`"))
+            })?;
+
+        let element = constructor(serde_json::Value::Object(fields)).map_err(D::Error::custom)?;
+
+        Ok(Custom { type_name, element })
+    }
+}
+
+impl SerdeElement for Custom {
+    fn element(
+        &self,
+        fonts: &impl for<'a> Index<&'a str, Output = Font>,
+        callback: impl CompositeElementCallback,
+    ) {
+        let mut callback = Some(callback);
+
+        self.element.dyn_element(fonts, &mut |element| {
+            if let Some(callback) = callback.take() {
+                callback.call(&DynElement(element));
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde_elements::ElementValue;
+
+    #[derive(Deserialize)]
+    struct Gap {
+        height: f64,
+    }
+
+    impl SerdeElement for Gap {
+        fn element(
+            &self,
+            _: &impl for<'a> Index<&'a str, Output = Font>,
+            callback: impl CompositeElementCallback,
+        ) {
+            callback.call(&elements::v_gap::VGap(self.height));
+        }
+    }
+
+    #[test]
+    fn test_registry() {
+        let json = r#"{ "Custom": { "type": "Gap", "height": 3 } }"#;
+
+        assert!(serde_json::from_str::<ElementValue>(json).is_err());
+
+        let mut registry = Registry::new();
+        registry.register::<Gap>("Gap");
+
+        let value = registry.scope(|| serde_json::from_str::<ElementValue>(json).unwrap());
+
+        let ElementValue::Custom(custom) = value else {
+            panic!("expected a custom element");
+        };
+
+        assert_eq!(custom.type_name, "Gap");
+
+        // The registry is only available inside of the scope.
+        assert!(serde_json::from_str::<ElementValue>(json).is_err());
+    }
+}
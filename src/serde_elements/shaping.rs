@@ -0,0 +1,61 @@
+//! The texts of a document, collected while it's deserialized, so that their widths can be
+//! measured on multiple threads before layout. Texts are only kept while they're collected with
+//! [collect_texts].
+
+use std::cell::RefCell;
+
+use crate::{line_break::LineBreaking, utils::scoped};
+
+/// A text with what its width depends on.
+pub struct CollectedText {
+    pub text: String,
+    pub font: String,
+    pub size: f64,
+    pub character_spacing: f64,
+    pub word_spacing: f64,
+    pub breaking: LineBreaking,
+}
+
+thread_local! {
+    static TEXTS: RefCell<Option<Vec<CollectedText>>> = const { RefCell::new(None) };
+}
+
+/// Collects the texts of everything deserialized on this thread while `f` is running.
+pub fn collect_texts<R>(f: impl FnOnce() -> R) -> (R, Vec<CollectedText>) {
+    let (ret, texts) = scoped(&TEXTS, Some(Vec::new()), f);
+    (ret, texts.unwrap_or_default())
+}
+
+/// Only clones the text if texts are being collected.
+pub(crate) fn collect(text: impl FnOnce() -> CollectedText) {
+    TEXTS.with(|texts| {
+        if let Some(texts) = texts.borrow_mut().as_mut() {
+            texts.push(text());
+        }
+    });
+}
+
+/// Puts the widths of the texts into the cache of
+/// [cache_text_widths](crate::text::cache_text_widths), see
+/// [pre_shape_text_widths](crate::text::pre_shape_text_widths). Texts in fonts that don't exist are
+/// skipped, since building the document fails on them anyway.
+#[cfg(feature = "parallel-shaping")]
+pub fn pre_shape(texts: &[CollectedText], fonts: &std::collections::HashMap<String, super::Font>) {
+    use crate::text::{pre_shape_text_widths, TextRun};
+
+    let runs = texts
+        .iter()
+        .filter_map(|text| {
+            Some(TextRun {
+                text: &text.text,
+                size: text.size,
+                font: &**fonts.get(&text.font)?,
+                character_spacing: text.character_spacing,
+                word_spacing: text.word_spacing,
+                breaking: text.breaking,
+            })
+        })
+        .collect::<Vec<_>>();
+
+    pre_shape_text_widths(&runs);
+}
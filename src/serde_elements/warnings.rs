@@ -0,0 +1,25 @@
+//! Problems with a document that don't stop it from being rendered, but that should probably be
+//! fixed, like a font face that had to be faked. Warnings are only kept while they're collected
+//! with [collect_warnings].
+
+use std::cell::RefCell;
+
+use crate::utils::scoped;
+
+thread_local! {
+    static WARNINGS: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
+
+/// Collects the warnings of everything deserialized on this thread while `f` is running.
+pub fn collect_warnings<R>(f: impl FnOnce() -> R) -> (R, Vec<String>) {
+    let (ret, warnings) = scoped(&WARNINGS, Some(Vec::new()), f);
+    (ret, warnings.unwrap_or_default())
+}
+
+pub(crate) fn warn(message: String) {
+    WARNINGS.with(|warnings| {
+        if let Some(warnings) = warnings.borrow_mut().as_mut() {
+            warnings.push(message);
+        }
+    });
+}
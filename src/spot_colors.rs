@@ -0,0 +1,325 @@
+//! Spot colors (ISO 32000-1:2008 8.6.6.4) are drawn through Separation color spaces, which name
+//! the ink and give the CMYK values to use where it isn't available.
+//!
+//! printpdf doesn't support color space resources, so elements draw with
+//! [SpotColor::set_fill_color] and [SpotColor::set_outline_color], and the color spaces are added
+//! to the saved document afterwards:
+//!
+//! ```ignore
+//! let mut document = lopdf::Document::load_mem(&save(build_pdf(..)))?;
+//! add_spot_colors(&mut document, [&brand_blue])?;
+//! ```
+//!
+//! Elements that only take RGB colors, which are all of them, can use spot colors through
+//! [replace_with_spot_colors], which swaps the RGB colors mapped to inks for the inks afterwards.
+
+use lopdf::{dictionary, Dictionary, Document, Object};
+
+use crate::{
+    letterhead::{inherited, resolve},
+    SpotColor,
+};
+
+/// Adds the Separation color spaces of the spot colors to the resources of every page.
+pub fn add_spot_colors<'a>(
+    document: &mut Document,
+    colors: impl IntoIterator<Item = &'a SpotColor>,
+) -> lopdf::Result<()> {
+    let color_spaces = colors
+        .into_iter()
+        .map(|color| {
+            let id = document.add_object(separation(color));
+            (color.resource_name(), id)
+        })
+        .collect::<Vec<_>>();
+
+    if color_spaces.is_empty() {
+        return Ok(());
+    }
+
+    for (_, page_id) in document.get_pages() {
+        // The resources might be shared with other pages, so the page gets its own copy.
+        let mut resources = match inherited(document, page_id, b"Resources") {
+            Some(Object::Dictionary(resources)) => resources.clone(),
+            _ => Dictionary::new(),
+        };
+
+        let mut existing = match resources.get(b"ColorSpace").map(|o| resolve(document, o)) {
+            Ok(Some(Object::Dictionary(existing))) => existing.clone(),
+            _ => Dictionary::new(),
+        };
+
+        for (name, id) in &color_spaces {
+            existing.set(name.as_str(), *id);
+        }
+
+        resources.set("ColorSpace", existing);
+
+        let page = document.get_object_mut(page_id)?.as_dict_mut()?;
+        page.set("Resources", resources);
+    }
+
+    Ok(())
+}
+
+/// Replaces the RGB fill and stroke colors in the content of the pages that are mapped to spot
+/// colors with the full ink and adds the color spaces of the inks like [add_spot_colors]. This
+/// way fills, strokes and text can use spot colors by using the mapped RGB colors, whose alpha is
+/// ignored. It should run before the content of the pages is moved into form XObjects, like with
+/// [share_drawings](crate::elements::cached::share_drawings).
+pub fn replace_with_spot_colors(
+    document: &mut Document,
+    colors: &[(u32, SpotColor)],
+) -> lopdf::Result<()> {
+    use lopdf::{content::Content, Stream};
+
+    if colors.is_empty() {
+        return Ok(());
+    }
+
+    add_spot_colors(document, colors.iter().map(|(_, spot)| spot))?;
+
+    for page_id in document.get_pages().into_values() {
+        let content = Content::decode(&document.get_page_content(page_id)?)?;
+
+        let mut operations = Vec::with_capacity(content.operations.len());
+        let mut replaced = false;
+
+        for operation in content.operations {
+            let stroke = match &operation.operator[..] {
+                "rg" => false,
+                "RG" => true,
+                _ => {
+                    operations.push(operation);
+                    continue;
+                }
+            };
+
+            match spot_color(&operation.operands, colors) {
+                Some(spot) => {
+                    operations.extend(spot.operations(stroke, 1.));
+                    replaced = true;
+                }
+                None => operations.push(operation),
+            }
+        }
+
+        if replaced {
+            let content_id = document.add_object(Stream::new(
+                Dictionary::new(),
+                Content { operations }.encode()?,
+            ));
+
+            document
+                .get_object_mut(page_id)?
+                .as_dict_mut()?
+                .set("Contents", content_id);
+        }
+    }
+
+    Ok(())
+}
+
+/// The spot color the RGB components of an `rg` or `RG` operator are mapped to.
+fn spot_color<'a>(operands: &[Object], colors: &'a [(u32, SpotColor)]) -> Option<&'a SpotColor> {
+    let number = |object: &Object| match *object {
+        Object::Integer(v) => Some(v as f64),
+        Object::Real(v) => Some(v as f64),
+        _ => None,
+    };
+
+    let [r, g, b] = operands else {
+        return None;
+    };
+
+    let rgb = [number(r)?, number(g)?, number(b)?];
+
+    colors
+        .iter()
+        .find(|(color, _)| {
+            let [cr, cg, cb, _] = color.to_be_bytes();
+
+            // The components are written with limited precision.
+            rgb.iter()
+                .zip([cr, cg, cb])
+                .all(|(v, c)| (v * 255. - c as f64).abs() < 0.5)
+        })
+        .map(|(_, spot)| spot)
+}
+
+/// A Separation color space whose tint transform scales the alternate CMYK values linearly.
+fn separation(color: &SpotColor) -> Object {
+    let real = |v: f64| Object::Real(v as _);
+
+    vec![
+        "Separation".into(),
+        Object::Name(color.name.clone().into_bytes()),
+        "DeviceCMYK".into(),
+        dictionary! {
+            "FunctionType" => 2,
+            "Domain" => vec![real(0.), real(1.)],
+            "C0" => vec![real(0.); 4],
+            "C1" => color.cmyk.into_iter().map(real).collect::<Vec<_>>(),
+            "N" => 1,
+        }
+        .into(),
+    ]
+    .into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_spot_colors() {
+        let mut document = Document::with_version("1.5");
+
+        let pages_id = document.new_object_id();
+        let page_id = document.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Resources" => dictionary! {
+                "Font" => Dictionary::new(),
+            },
+        });
+
+        document.objects.insert(
+            pages_id,
+            dictionary! {
+                "Type" => "Pages",
+                "Kids" => vec![Object::Reference(page_id)],
+                "Count" => 1,
+            }
+            .into(),
+        );
+
+        let catalog_id = document.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        document.trailer.set("Root", catalog_id);
+
+        let color = SpotColor {
+            name: "PANTONE 300 C".to_string(),
+            cmyk: [1., 0.44, 0., 0.],
+        };
+
+        add_spot_colors(&mut document, [&color]).unwrap();
+
+        let resources = document
+            .get_object(page_id)
+            .and_then(Object::as_dict)
+            .unwrap()
+            .get(b"Resources")
+            .and_then(Object::as_dict)
+            .unwrap();
+
+        // The existing resources are kept.
+        assert!(resources.get(b"Font").is_ok());
+
+        let id = resources
+            .get(b"ColorSpace")
+            .and_then(Object::as_dict)
+            .unwrap()
+            .get(color.resource_name().as_bytes())
+            .and_then(Object::as_reference)
+            .unwrap();
+
+        let separation = document.get_object(id).and_then(Object::as_array).unwrap();
+
+        assert_eq!(separation[0].as_name().unwrap(), b"Separation");
+        assert_eq!(separation[1].as_name().unwrap(), b"PANTONE 300 C");
+        assert_eq!(separation[2].as_name().unwrap(), b"DeviceCMYK");
+
+        let function = separation[3].as_dict().unwrap();
+        assert_eq!(function.get(b"C1").unwrap().as_array().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_operations() {
+        let color = SpotColor {
+            name: "HKS 43 K".to_string(),
+            cmyk: [1., 0.7, 0., 0.],
+        };
+
+        assert_eq!(color.resource_name(), "LaserPdfSpot_HKS_2043_20K");
+
+        let name = |name: &str| {
+            SpotColor {
+                name: name.to_string(),
+                cmyk: [0.; 4],
+            }
+            .resource_name()
+        };
+
+        assert_ne!(name("A B"), name("A_B"));
+        assert_ne!(name("A B"), name("A_20B"));
+        assert_ne!(name("Grün"), name("Gr_n"));
+
+        let [color_space, tint] = color.operations(false, 0.5);
+        assert_eq!(color_space.operator, "cs");
+        assert_eq!(
+            color_space.operands[0].as_name().unwrap(),
+            b"LaserPdfSpot_HKS_2043_20K"
+        );
+        assert_eq!(tint.operator, "scn");
+        assert!(matches!(tint.operands[0], Object::Real(v) if v == 0.5));
+
+        let [color_space, tint] = color.operations(true, 2.);
+        assert_eq!(color_space.operator, "CS");
+        assert_eq!(tint.operator, "SCN");
+        assert!(matches!(tint.operands[0], Object::Real(v) if v == 1.));
+    }
+
+    #[test]
+    fn test_replace_with_spot_colors() {
+        use lopdf::content::Content;
+
+        use crate::{build_pdf, elements::rectangle::Rectangle};
+
+        let blue = SpotColor {
+            name: "PANTONE 300 C".to_string(),
+            cmyk: [1., 0.44, 0., 0.],
+        };
+
+        let document = build_pdf(
+            "test",
+            (100., 100.),
+            |_| (),
+            |_: &()| Rectangle {
+                size: (10., 10.),
+                fill: Some(0x00_5e_b8_ff),
+                outline: Some(LineStyle::solid(1., 0xff_00_00_ff)),
+            },
+        );
+
+        let mut bytes = Vec::new();
+        document
+            .save(&mut std::io::BufWriter::new(&mut bytes))
+            .unwrap();
+
+        let mut document = Document::load_mem(&bytes).unwrap();
+        replace_with_spot_colors(&mut document, &[(0x00_5e_b8_ff, blue.clone())]).unwrap();
+
+        let page_id = document.get_pages()[&1];
+        let content = document.get_page_content(page_id).unwrap();
+        let operations = Content::decode(&content).unwrap().operations;
+
+        let operators = operations
+            .iter()
+            .map(|o| &o.operator[..])
+            .collect::<Vec<_>>();
+
+        // Only the mapped fill is replaced.
+        assert!(operators.contains(&"cs"));
+        assert!(operators.contains(&"scn"));
+        assert!(!operators.contains(&"rg"));
+        assert!(operators.contains(&"RG"));
+
+        let name = blue.resource_name();
+        assert!(operations
+            .iter()
+            .any(|o| o.operator == "cs" && o.operands[0].as_name().ok() == Some(name.as_bytes())));
+    }
+}
@@ -0,0 +1,351 @@
+//! A structure tree (ISO 32000-1:2008 14.7) with the images of the document, so screen readers
+//! read the alternate text of figures and formulas and skip decorative images.
+//!
+//! Images are drawn in marked content while the structure is collected, `/Figure` or `/Formula`
+//! with an MCID and `/Artifact` for decoration. Like threads, printpdf can't write the structure
+//! tree, so it's added to the saved document afterwards:
+//!
+//! ```ignore
+//! let (document, images) = collect_tagged_images(|| build_pdf(..));
+//! let mut document = lopdf::Document::load_mem(&save(document))?;
+//! add_structure_tree(&mut document, &images)?;
+//! ```
+
+use std::{cell::RefCell, collections::BTreeMap};
+
+use lopdf::{content::Operation, dictionary, Document, Object, StringFormat};
+use printpdf::PdfLayerReference;
+
+use crate::{
+    elements::image::ImageRole,
+    utils::{add_op, scoped},
+    *,
+};
+
+/// The structure element of an image that isn't an [ImageRole::Artifact].
+#[derive(Clone, Debug, PartialEq)]
+pub struct TaggedImage {
+    pub role: ImageRole,
+    pub alt: Option<String>,
+    pub caption: Option<String>,
+
+    /// The marked content of the image as the index of the page and the MCID on it, one for every
+    /// location the image was drawn on.
+    pub content: Vec<(usize, i64)>,
+}
+
+thread_local! {
+    static IMAGES: RefCell<Option<Vec<TaggedImage>>> = const { RefCell::new(None) };
+}
+
+/// Collects the structure elements of all images drawn on this thread while `f` is running.
+pub fn collect_tagged_images<R>(f: impl FnOnce() -> R) -> (R, Vec<TaggedImage>) {
+    let (ret, images) = scoped(&IMAGES, Some(Vec::new()), f);
+    (ret, images.unwrap_or_default())
+}
+
+/// Whether images are being tagged, so they're only drawn in marked content when there will be a
+/// structure tree for it.
+pub(crate) fn collecting() -> bool {
+    IMAGES.with(|images| images.borrow().is_some())
+}
+
+/// Draws the image in marked content for its role. Marked content can't span content streams, so
+/// it's ended before every break and started again on the new location.
+pub(crate) fn draw_tagged(
+    ctx: DrawCtx,
+    role: ImageRole,
+    alt: Option<&str>,
+    caption: Option<&str>,
+    draw: impl FnOnce(DrawCtx) -> ElementSize,
+) -> ElementSize {
+    let mut content = Vec::new();
+    let mut layer = ctx.location.layer.clone();
+    begin(&layer, role, &mut content);
+
+    let size = if let Some(breakable) = ctx.breakable {
+        draw(DrawCtx {
+            breakable: Some(BreakableDraw {
+                do_break: &mut |pdf, location_idx, height| {
+                    end(&layer);
+
+                    let location = (breakable.do_break)(pdf, location_idx, height);
+
+                    layer = location.layer.clone();
+                    begin(&layer, role, &mut content);
+
+                    location
+                },
+                ..breakable
+            }),
+            ..ctx
+        })
+    } else {
+        draw(ctx)
+    };
+
+    end(&layer);
+
+    if role != ImageRole::Artifact {
+        IMAGES.with(|images| {
+            if let Some(images) = images.borrow_mut().as_mut() {
+                images.push(TaggedImage {
+                    role,
+                    alt: alt.map(String::from),
+                    caption: caption.map(String::from),
+                    content,
+                });
+            }
+        });
+    }
+
+    size
+}
+
+fn begin(layer: &PdfLayerReference, role: ImageRole, content: &mut Vec<(usize, i64)>) {
+    let Some(structure_type) = structure_type(role) else {
+        add_op(
+            layer,
+            Operation::new("BMC", vec![Object::Name(b"Artifact".to_vec())]),
+        );
+        return;
+    };
+
+    let page = layer.page.0;
+
+    // MCIDs count up from zero on every page.
+    let mcid = IMAGES.with(|images| {
+        images
+            .borrow()
+            .iter()
+            .flatten()
+            .flat_map(|i| &i.content)
+            .chain(content.iter())
+            .filter(|&&(p, _)| p == page)
+            .count()
+    }) as i64;
+
+    content.push((page, mcid));
+
+    add_op(
+        layer,
+        Operation::new(
+            "BDC",
+            vec![
+                Object::Name(structure_type.as_bytes().to_vec()),
+                Object::Dictionary(dictionary! { "MCID" => mcid }),
+            ],
+        ),
+    );
+}
+
+fn end(layer: &PdfLayerReference) {
+    add_op(layer, Operation::new("EMC", vec![]));
+}
+
+fn structure_type(role: ImageRole) -> Option<&'static str> {
+    match role {
+        ImageRole::Figure => Some("Figure"),
+        ImageRole::Formula => Some("Formula"),
+        ImageRole::Artifact => None,
+    }
+}
+
+/// Adds a structure tree with a `/Document` element that has an element for every image, in the
+/// order they were drawn. The alt text is the `/Alt` of the element and the caption its `/T`
+/// title. Images on pages that don't exist in the document are an error.
+pub fn add_structure_tree(document: &mut Document, images: &[TaggedImage]) -> lopdf::Result<()> {
+    let pages = document.get_pages();
+    let root_id = document.new_object_id();
+    let document_element_id = document.new_object_id();
+
+    // The elements of the marked content on every page, by MCID.
+    let mut parent_tree = BTreeMap::<usize, Vec<(i64, Object)>>::new();
+    let mut kids = Vec::new();
+
+    for image in images {
+        let Some(structure_type) = structure_type(image.role) else {
+            continue;
+        };
+
+        let element_id = document.new_object_id();
+        let mut content = Vec::new();
+
+        for &(page, mcid) in &image.content {
+            let page_id = *pages
+                .get(&(page as u32 + 1))
+                .ok_or(lopdf::Error::ObjectNotFound)?;
+
+            content.push(Object::Dictionary(dictionary! {
+                "Type" => "MCR",
+                "Pg" => page_id,
+                "MCID" => mcid,
+            }));
+
+            parent_tree
+                .entry(page)
+                .or_default()
+                .push((mcid, Object::Reference(element_id)));
+        }
+
+        let mut element = dictionary! {
+            "Type" => "StructElem",
+            "S" => structure_type,
+            "P" => document_element_id,
+            "K" => content,
+        };
+
+        if let Some(alt) = &image.alt {
+            element.set("Alt", text_string(alt));
+        }
+
+        if let Some(caption) = &image.caption {
+            element.set("T", text_string(caption));
+        }
+
+        document.objects.insert(element_id, element.into());
+        kids.push(Object::Reference(element_id));
+    }
+
+    document.objects.insert(
+        document_element_id,
+        dictionary! {
+            "Type" => "StructElem",
+            "S" => "Document",
+            "P" => root_id,
+            "K" => kids,
+        }
+        .into(),
+    );
+
+    // The key of every page in the parent tree is its index.
+    let mut nums = Vec::new();
+
+    for (page, mut elements) in parent_tree {
+        let page_id = pages[&(page as u32 + 1)];
+        document
+            .get_object_mut(page_id)?
+            .as_dict_mut()?
+            .set("StructParents", page as i64);
+
+        elements.sort_by_key(|&(mcid, _)| mcid);
+
+        nums.push(Object::Integer(page as i64));
+        nums.push(Object::Array(elements.into_iter().map(|(_, e)| e).collect()));
+    }
+
+    document.objects.insert(
+        root_id,
+        dictionary! {
+            "Type" => "StructTreeRoot",
+            "K" => document_element_id,
+            "ParentTree" => dictionary! { "Nums" => nums },
+            "ParentTreeNextKey" => pages.len() as i64,
+        }
+        .into(),
+    );
+
+    let catalog_id = document.trailer.get(b"Root")?.as_reference()?;
+    let catalog = document.get_object_mut(catalog_id)?.as_dict_mut()?;
+
+    catalog.set("StructTreeRoot", root_id);
+    catalog.set("MarkInfo", dictionary! { "Marked" => true });
+
+    Ok(())
+}
+
+/// A text string that isn't limited to the characters of PDFDocEncoding, as UTF-16 with a byte
+/// order mark unless it's ASCII.
+fn text_string(text: &str) -> Object {
+    if text.is_ascii() {
+        Object::string_literal(text)
+    } else {
+        let bytes = [0xFE, 0xFF]
+            .into_iter()
+            .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+            .collect();
+
+        Object::String(bytes, StringFormat::Hexadecimal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use printpdf::image::DynamicImage;
+
+    use super::*;
+    use crate::{elements::image::ImageElement, image::Image, test_utils::*};
+
+    #[test]
+    fn test_structure_tree() {
+        let image = Image::Pixel(DynamicImage::new_rgb8(2, 1));
+
+        let figure = ImageElement {
+            alt: Some("Ein rotes Rechteck"),
+            caption: Some("Abb. 1 – Rechteck"),
+            ..ImageElement::new(&image)
+        };
+        let logo = ImageElement {
+            role: ImageRole::Artifact,
+            ..ImageElement::new(&image)
+        };
+
+        let (document, images) = collect_tagged_images(|| {
+            build_pdf(
+                "test",
+                (100., 100.),
+                |_| (),
+                |_: &()| elements::column::Column {
+                    gap: 0.,
+                    ..elements::column::Column::new(|content| {
+                        content.add(&logo)?.add(&figure)?.add(&figure)?;
+                        None
+                    })
+                },
+            )
+        });
+
+        // The logo isn't in the structure and the figures are numbered on their page.
+        assert_eq!(
+            images.iter().map(|i| i.content.clone()).collect::<Vec<_>>(),
+            [vec![(0, 0)], vec![(0, 1)]],
+        );
+
+        let bytes = save(document);
+
+        let operations = &page_operations(&bytes)[0];
+        let tags = operations
+            .iter()
+            .filter(|o| o.operator == "BMC" || o.operator == "BDC")
+            .map(|o| o.operands[0].as_name().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(tags, [&b"Artifact"[..], b"Figure", b"Figure"]);
+
+        let mut document = Document::load_mem(&bytes).unwrap();
+        add_structure_tree(&mut document, &images).unwrap();
+
+        let figures = document
+            .objects
+            .values()
+            .filter_map(|o| o.as_dict().ok())
+            .filter(|d| matches!(d.get(b"S"), Ok(Object::Name(n)) if n == b"Figure"))
+            .collect::<Vec<_>>();
+        assert_eq!(figures.len(), 2);
+
+        let alt = figures[0].get(b"Alt").unwrap().as_str().unwrap();
+        assert_eq!(alt, b"Ein rotes Rechteck");
+
+        // The caption isn't ASCII.
+        let caption = figures[0].get(b"T").unwrap().as_str().unwrap();
+        assert_eq!(&caption[..2], [0xFE, 0xFF]);
+
+        let root = document
+            .trailer
+            .get(b"Root")
+            .unwrap()
+            .as_reference()
+            .unwrap();
+        let catalog = document.get_object(root).unwrap().as_dict().unwrap();
+        assert!(catalog.get(b"StructTreeRoot").is_ok());
+    }
+}
@@ -5,7 +5,11 @@ pub mod element_proxy;
 pub mod fake_image;
 pub mod fake_text;
 pub mod frantic_jumper;
+#[cfg(feature = "golden")]
+pub mod golden;
 pub mod old;
+#[cfg(test)]
+pub mod random_tree;
 pub mod record_passes;
 
 pub use build_element::BuildElement;
@@ -17,7 +21,7 @@ pub use old::*;
 
 use printpdf::{
     indices::{PdfLayerIndex, PdfPageIndex},
-    PdfDocument,
+    PdfDocument, PdfDocumentReference,
 };
 
 use crate::{utils::max_optional_size, *};
@@ -271,6 +275,7 @@ fn draw_element<E: Element>(
     let mut pdf = Pdf {
         document: doc,
         page_size,
+        line_rendering: Default::default(),
     };
 
     let mut breaks = vec![];
@@ -359,3 +364,28 @@ pub fn measure_element<E: Element>(
         size,
     }
 }
+
+/// Saves the document, for tests that look at what was written.
+pub fn save(document: PdfDocumentReference) -> Vec<u8> {
+    let mut bytes = Vec::new();
+
+    document
+        .save(&mut std::io::BufWriter::new(&mut bytes))
+        .unwrap();
+
+    bytes
+}
+
+/// The decoded content of every page of a saved document, in the order of the pages.
+pub fn page_operations(pdf: &[u8]) -> Vec<Vec<lopdf::content::Operation>> {
+    let document = lopdf::Document::load_mem(pdf).unwrap();
+
+    document
+        .get_pages()
+        .into_values()
+        .map(|page_id| {
+            let content = document.get_page_content(page_id).unwrap();
+            lopdf::content::Content::decode(&content).unwrap().operations
+        })
+        .collect()
+}
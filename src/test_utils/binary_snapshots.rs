@@ -106,6 +106,7 @@ impl Doc {
         let pdf = Pdf {
             document,
             page_size: params.page_size,
+            line_rendering: Default::default(),
         };
 
         Doc { params, pdf }
@@ -0,0 +1,92 @@
+//! Golden image testing for elements. The PDF is rasterized with [crate::preview], so `pdftoppm`
+//! from poppler needs to be installed for these functions to work. The resulting pages are compared
+//! against PNG files in a directory with some tolerance, so that small antialiasing differences
+//! between rasterizer versions don't fail the test.
+//!
+//! If a golden file doesn't exist yet or the `UPDATE_GOLDENS` environment variable is set, the
+//! rendered page is written as the new golden instead.
+
+use std::path::Path;
+
+use crate::preview::{rasterize_pdf, Raster};
+
+use super::binary_snapshots::{test_element_bytes, Callback, TestElementParams};
+
+#[derive(Clone, Copy, Debug)]
+pub struct GoldenOptions {
+    pub dpi: u32,
+
+    /// The maximum difference per color channel for two pixels to still be considered equal.
+    pub channel_tolerance: u8,
+
+    /// How many pixels per page are allowed to differ by more than [Self::channel_tolerance].
+    pub max_differing_pixels: usize,
+}
+
+impl Default for GoldenOptions {
+    fn default() -> Self {
+        GoldenOptions {
+            dpi: 72,
+            channel_tolerance: 8,
+            max_differing_pixels: 0,
+        }
+    }
+}
+
+/// Rasterizes the PDF and compares each page against `{dir}/{name}-{page}.png`.
+pub fn assert_golden(name: &str, dir: impl AsRef<Path>, pdf: &[u8], options: GoldenOptions) {
+    let dir = dir.as_ref();
+    let update = std::env::var_os("UPDATE_GOLDENS").is_some();
+
+    let pages = rasterize_pdf(pdf, options.dpi).unwrap();
+
+    std::fs::create_dir_all(dir).unwrap();
+
+    for (i, page) in pages.iter().enumerate() {
+        let path = dir.join(format!("{name}-{i}.png"));
+
+        if update || !path.exists() {
+            page.write_png(&path).unwrap();
+            continue;
+        }
+
+        let golden = Raster::read_png(&path).unwrap();
+
+        match page.differing_pixels(&golden, options.channel_tolerance) {
+            None => panic!(
+                "page {i} of {name} is {}x{}, but the golden is {}x{}",
+                page.width, page.height, golden.width, golden.height,
+            ),
+            Some(count) if count > options.max_differing_pixels => {
+                let actual = dir.join(format!("{name}-{i}.actual.png"));
+                page.write_png(&actual).unwrap();
+
+                panic!(
+                    "page {i} of {name} differs from the golden in {count} pixels, see {}",
+                    actual.display(),
+                );
+            }
+            Some(_) => (),
+        }
+    }
+
+    let extra = dir.join(format!("{name}-{}.png", pages.len()));
+    assert!(
+        !extra.exists() || update,
+        "{name} rendered {} pages, but there are more goldens",
+        pages.len(),
+    );
+}
+
+/// Runs the element through the same checks as [test_element_bytes] and compares the drawn pages
+/// against the goldens.
+pub fn assert_element_golden(
+    name: &str,
+    dir: impl AsRef<Path>,
+    params: TestElementParams,
+    options: GoldenOptions,
+    build_element: impl Fn(Callback),
+) {
+    let bytes = test_element_bytes(params, build_element);
+    assert_golden(name, dir, &bytes, options);
+}
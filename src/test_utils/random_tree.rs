@@ -0,0 +1,174 @@
+//! Random element trees for checking the invariants of the layout protocol with proptest.
+
+use proptest::prelude::*;
+
+use crate::elements::{
+    column::{Column, ColumnContent},
+    padding::Padding,
+};
+
+use super::*;
+
+#[derive(Clone, Debug)]
+pub enum RandomTree {
+    Text {
+        lines: u32,
+        line_height: f64,
+        width: f64,
+    },
+    Padding {
+        left: f64,
+        right: f64,
+        top: f64,
+        bottom: f64,
+        element: Box<RandomTree>,
+    },
+    Column {
+        gap: f64,
+        collapse: bool,
+        content: Vec<RandomTree>,
+    },
+}
+
+impl RandomTree {
+    fn element<R>(&self, f: impl FnOnce(&dyn Element) -> R) -> R {
+        match self {
+            &RandomTree::Text {
+                lines,
+                line_height,
+                width,
+            } => f(&FakeText {
+                lines,
+                line_height,
+                width,
+            }),
+            &RandomTree::Padding {
+                left,
+                right,
+                top,
+                bottom,
+                ref element,
+            } => f(&Padding {
+                left,
+                right,
+                top,
+                bottom,
+                element: &**element,
+            }),
+            &RandomTree::Column {
+                gap,
+                collapse,
+                ref content,
+            } => f(&Column {
+                gap,
+                collapse,
+                ..Column::new(|mut column: ColumnContent| {
+                    for element in content {
+                        column = column.add(element)?;
+                    }
+
+                    Some(())
+                })
+            }),
+        }
+    }
+}
+
+impl Element for RandomTree {
+    fn first_location_usage(&self, ctx: FirstLocationUsageCtx) -> FirstLocationUsage {
+        self.element(|e| e.first_location_usage(ctx))
+    }
+
+    fn measure(&self, ctx: MeasureCtx) -> ElementSize {
+        self.element(|e| e.measure(ctx))
+    }
+
+    fn draw(&self, ctx: DrawCtx) -> ElementSize {
+        self.element(|e| e.draw(ctx))
+    }
+}
+
+pub fn random_tree() -> impl Strategy<Value = RandomTree> {
+    let text =
+        (0..20u32, 0.5..5., 0.0..100.).prop_map(|(lines, line_height, width)| RandomTree::Text {
+            lines,
+            line_height,
+            width,
+        });
+
+    text.prop_recursive(4, 32, 4, |inner| {
+        prop_oneof![
+            (0.0..10., 0.0..10., 0.0..10., 0.0..10., inner.clone()).prop_map(
+                |(left, right, top, bottom, element)| RandomTree::Padding {
+                    left,
+                    right,
+                    top,
+                    bottom,
+                    element: Box::new(element),
+                }
+            ),
+            (0.0..5., any::<bool>(), prop::collection::vec(inner, 0..4)).prop_map(
+                |(gap, collapse, content)| RandomTree::Column {
+                    gap,
+                    collapse,
+                    content,
+                }
+            ),
+        ]
+    })
+}
+
+/// Checks the invariants every element has to uphold:
+///
+/// - measure and draw agree on the size and the number of breaks
+/// - sizes are never negative
+/// - more space on the first location never leads to more breaks
+pub fn assert_invariants<E: Element>(element: &E, params: ElementTestParams) {
+    for output in params.run(element) {
+        assert!(output.size.width.unwrap_or(0.) >= 0.);
+        assert!(output.size.height.unwrap_or(0.) >= 0.);
+    }
+
+    let break_count = |first_height| {
+        test_measure_draw_compatibility(
+            element,
+            WidthConstraint {
+                max: params.width,
+                expand: false,
+            },
+            first_height,
+            Some(params.full_height),
+            params.pos,
+            params.page_size,
+        )
+        .breakable
+        .unwrap()
+        .break_count
+    };
+
+    let first_height = params.first_height.min(params.full_height);
+
+    assert!(break_count(params.full_height) <= break_count(first_height));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn test_random_trees(
+            tree in random_tree(),
+            width in 0.0..200.,
+            first_height in 0.0..100.,
+            full_height in 1.0..100.,
+        ) {
+            assert_invariants(&tree, ElementTestParams {
+                width,
+                first_height,
+                full_height,
+                ..Default::default()
+            });
+        }
+    }
+}
@@ -1,4 +1,125 @@
-use crate::fonts::Font;
+use std::{borrow::Cow, cell::RefCell, collections::HashMap};
+
+use crate::{
+    fonts::Font,
+    line_break::{
+        break_opportunities_into, kinsoku_break_opportunities_into, BreakOpportunity, LineBreaking,
+    },
+    script::ZERO_WIDTH_SPACE,
+    utils::scoped,
+};
+
+/// The font (by address), size, character spacing and word spacing.
+type WidthCacheKey = (usize, u64, u64, u64);
+
+struct WidthCache {
+    capacity: usize,
+    entries: HashMap<WidthCacheKey, HashMap<String, (f64, u64)>>,
+    len: usize,
+    clock: u64,
+
+    /// The widths from [pre_shape_text_widths]. They aren't evicted, since they're measured before
+    /// layout and would otherwise be the least recently used ones until layout gets to their text.
+    /// They take up at most half of the capacity, so layout always has the other half.
+    pinned: HashMap<WidthCacheKey, HashMap<String, f64>>,
+    pinned_len: usize,
+}
+
+impl WidthCache {
+    fn new(capacity: usize) -> Self {
+        WidthCache {
+            capacity: capacity.max(1),
+            entries: HashMap::new(),
+            len: 0,
+            clock: 0,
+            pinned: HashMap::new(),
+            pinned_len: 0,
+        }
+    }
+
+    fn get(&mut self, key: WidthCacheKey, text: &str) -> Option<f64> {
+        if let Some(&width) = self.pinned.get(&key).and_then(|texts| texts.get(text)) {
+            return Some(width);
+        }
+
+        self.clock += 1;
+
+        let entry = self.entries.get_mut(&key)?.get_mut(text)?;
+        entry.1 = self.clock;
+
+        Some(entry.0)
+    }
+
+    fn insert(&mut self, key: WidthCacheKey, text: &str, width: f64) {
+        if self.len + self.pinned_len >= self.capacity {
+            self.evict();
+        }
+
+        self.clock += 1;
+
+        if self
+            .entries
+            .entry(key)
+            .or_default()
+            .insert(text.to_string(), (width, self.clock))
+            .is_none()
+        {
+            self.len += 1;
+        }
+    }
+
+    /// How many more widths can be pinned.
+    #[cfg(feature = "parallel-shaping")]
+    fn pin_room(&self) -> usize {
+        (self.capacity / 2).saturating_sub(self.pinned_len)
+    }
+
+    #[cfg(feature = "parallel-shaping")]
+    fn pin(&mut self, key: WidthCacheKey, text: String, width: f64) {
+        if self.pin_room() == 0 {
+            return;
+        }
+
+        let texts = self.pinned.entry(key).or_default();
+
+        if texts.insert(text, width).is_none() {
+            self.pinned_len += 1;
+        }
+    }
+
+    /// Removes the least recently used half of the entries, so that evicting is cheap on average.
+    fn evict(&mut self) {
+        let mut last_used = self
+            .entries
+            .values()
+            .flat_map(|texts| texts.values().map(|&(_, last_used)| last_used))
+            .collect::<Vec<_>>();
+
+        let mid = last_used.len() / 2;
+        let (_, &mut threshold, _) = last_used.select_nth_unstable(mid);
+
+        for texts in self.entries.values_mut() {
+            texts.retain(|_, &mut (_, last_used)| last_used >= threshold);
+        }
+
+        self.entries.retain(|_, texts| !texts.is_empty());
+        self.len = self.entries.values().map(HashMap::len).sum();
+    }
+}
+
+thread_local! {
+    static WIDTH_CACHE: RefCell<Option<WidthCache>> = const { RefCell::new(None) };
+}
+
+/// Caches the results of [text_width] on this thread while `f` is running, keeping at most
+/// `capacity` widths. These are the ones from [pre_shape_text_widths] and the most recently used
+/// ones. Useful for documents that repeat the same labels many times, since text is measured at
+/// least once for measuring and once for drawing.
+///
+/// Fonts are told apart by their address, so they shouldn't be dropped while `f` is running.
+pub fn cache_text_widths<R>(capacity: usize, f: impl FnOnce() -> R) -> R {
+    scoped(&WIDTH_CACHE, Some(WidthCache::new(capacity)), f).0
+}
 
 /**
  * Calculates the width needed for a given string, font and size (in pt).
@@ -9,6 +130,153 @@ pub fn text_width(
     font: &impl Font,
     character_spacing: f64,
     word_spacing: f64,
+) -> f64 {
+    let key = width_cache_key(size, font, character_spacing, word_spacing);
+
+    let cached = WIDTH_CACHE.with(|cache| {
+        cache
+            .borrow_mut()
+            .as_mut()
+            .map(|cache| cache.get(key, text))
+    });
+
+    match cached {
+        Some(Some(width)) => width,
+        Some(None) => {
+            let width = uncached_text_width(text, size, font, character_spacing, word_spacing);
+
+            WIDTH_CACHE.with(|cache| {
+                if let Some(cache) = cache.borrow_mut().as_mut() {
+                    cache.insert(key, text, width);
+                }
+            });
+
+            width
+        }
+        None => uncached_text_width(text, size, font, character_spacing, word_spacing),
+    }
+}
+
+fn width_cache_key(
+    size: f64,
+    font: &impl Font,
+    character_spacing: f64,
+    word_spacing: f64,
+) -> WidthCacheKey {
+    (
+        font as *const _ as *const () as usize,
+        size.to_bits(),
+        character_spacing.to_bits(),
+        word_spacing.to_bits(),
+    )
+}
+
+/// A text whose widths are measured ahead of layout by [pre_shape_text_widths].
+#[cfg(feature = "parallel-shaping")]
+pub struct TextRun<'a, F> {
+    pub text: &'a str,
+    pub size: f64,
+    pub font: &'a F,
+    pub character_spacing: f64,
+    pub word_spacing: f64,
+    pub breaking: LineBreaking,
+}
+
+/// Measures the pieces of the texts that line breaking asks the widths of on multiple threads and
+/// puts them into the cache of [cache_text_widths], so laying the texts out afterwards mostly looks
+/// them up. These are the words with and without the whitespace in front of them and the
+/// paragraphs, which is all of them for text that fits on a line. The widths are kept until the
+/// cache goes out of scope, but only up to half of its capacity, and the pieces beyond that aren't
+/// measured. Needs the `parallel-shaping` feature and does nothing if no cache is in scope.
+#[cfg(feature = "parallel-shaping")]
+pub fn pre_shape_text_widths<F: Font + Sync>(runs: &[TextRun<F>]) {
+    use rayon::prelude::*;
+    use std::collections::HashSet;
+
+    let room = WIDTH_CACHE.with(|cache| cache.borrow().as_ref().map_or(0, WidthCache::pin_room));
+
+    if room == 0 {
+        return;
+    }
+
+    let pieces = runs
+        .par_iter()
+        .map(|run| (run, text_pieces(run.text, run.breaking)))
+        .collect::<Vec<_>>();
+
+    let mut seen = HashSet::new();
+    let pieces = pieces
+        .into_iter()
+        .flat_map(|(run, pieces)| pieces.into_iter().map(move |piece| (run, piece)))
+        .filter(|(run, piece)| {
+            let key = width_cache_key(run.size, run.font, run.character_spacing, run.word_spacing);
+            seen.insert((key, piece.clone()))
+        })
+        .take(room)
+        .collect::<Vec<_>>();
+
+    let widths = pieces
+        .par_iter()
+        .map(|(run, piece)| {
+            uncached_text_width(
+                piece,
+                run.size,
+                run.font,
+                run.character_spacing,
+                run.word_spacing,
+            )
+        })
+        .collect::<Vec<_>>();
+
+    WIDTH_CACHE.with(|cache| {
+        if let Some(cache) = cache.borrow_mut().as_mut() {
+            for ((run, piece), width) in pieces.into_iter().zip(widths) {
+                let key =
+                    width_cache_key(run.size, run.font, run.character_spacing, run.word_spacing);
+                cache.pin(key, piece, width);
+            }
+        }
+    });
+}
+
+/// The pieces of the text that are measured when breaking it into lines of unlimited width.
+/// Since lines start without the whitespace that was broken at, the words are also included
+/// without it.
+#[cfg(feature = "parallel-shaping")]
+fn text_pieces(text: &str, breaking: LineBreaking) -> Vec<String> {
+    let pieces = RefCell::new(Vec::new());
+
+    let mut generator = LineGenerator::new(text, |piece| {
+        pieces.borrow_mut().push(piece.to_string());
+        0.
+    })
+    .with_breaking(breaking);
+
+    while let Some(line) = generator.next(f64::INFINITY, false) {
+        pieces.borrow_mut().push(line.to_string());
+    }
+
+    // It measures into `pieces` and gives its buffer back when it's dropped.
+    drop(generator);
+
+    let mut pieces = pieces.into_inner();
+    let trimmed = pieces
+        .iter()
+        .map(|piece| piece.trim_start())
+        .filter(|trimmed| !trimmed.is_empty())
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    pieces.extend(trimmed);
+    pieces
+}
+
+fn uncached_text_width(
+    text: &str,
+    size: f64,
+    font: &impl Font,
+    character_spacing: f64,
+    word_spacing: f64,
 ) -> f64 {
     use itertools::{Itertools, Position};
 
@@ -23,6 +291,10 @@ pub fn text_width(
                 return None;
             }
 
+            if ch == ZERO_WIDTH_SPACE {
+                return None;
+            }
+
             Some((ch, font.codepoint_h_metrics(ch as u32)))
         })
         .fold(0., |acc, (ch, h_metrics)| {
@@ -33,19 +305,105 @@ pub fn text_width(
     total_width as f64 * size as f64 / scale
 }
 
-pub fn remove_non_trailing_soft_hyphens(text: &str) -> String {
+/// The narrowest width (in pt) the text can be laid out in without any line overflowing, so the
+/// width of the longest fragment between the places lines can be broken at.
+pub fn min_content_width(
+    text: &str,
+    size: f64,
+    font: &impl Font,
+    character_spacing: f64,
+    word_spacing: f64,
+    breaking: LineBreaking,
+) -> f64 {
+    let width = |t: &str| text_width(t, size, font, character_spacing, word_spacing);
+    let mut generator = LineGenerator::new(text, width).with_breaking(breaking);
+
+    std::iter::from_fn(|| generator.next(0., false))
+        .map(|line| width(line.trim_end()))
+        .fold(0., f64::max)
+}
+
+fn is_opening_fullwidth_punctuation(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3008}'
+            | '\u{300a}'
+            | '\u{300c}'
+            | '\u{300e}'
+            | '\u{3010}'
+            | '\u{3014}'
+            | '\u{3016}'
+            | '\u{3018}'
+            | '\u{ff08}'
+            | '\u{ff3b}'
+            | '\u{ff5b}'
+    )
+}
+
+fn is_closing_fullwidth_punctuation(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3001}'
+            | '\u{3002}'
+            | '\u{3009}'
+            | '\u{300b}'
+            | '\u{300d}'
+            | '\u{300f}'
+            | '\u{3011}'
+            | '\u{3015}'
+            | '\u{3017}'
+            | '\u{3019}'
+            | '\u{ff09}'
+            | '\u{ff0c}'
+            | '\u{ff0e}'
+            | '\u{ff3d}'
+            | '\u{ff5d}'
+    )
+}
+
+/// The byte offsets where half an em is removed from between two fullwidth punctuation marks in a
+/// row. The glyphs of these marks are half blank, so `」「` or `。」` would otherwise look like
+/// there's a space between them. Nothing is removed between an opening and a closing mark, since
+/// their blank halves are on the outside.
+pub fn punctuation_compressions(text: &str) -> Vec<usize> {
+    let is_punctuation =
+        |c| is_opening_fullwidth_punctuation(c) || is_closing_fullwidth_punctuation(c);
+
+    text.char_indices()
+        .zip(text.chars().skip(1))
+        .filter(|&((_, a), b)| {
+            is_punctuation(a)
+                && is_punctuation(b)
+                && (is_closing_fullwidth_punctuation(a) || is_opening_fullwidth_punctuation(b))
+        })
+        .map(|((i, a), _)| i + a.len_utf8())
+        .collect()
+}
+
+/// Also removes zero width spaces, since fonts usually don't have a glyph for them. Only
+/// allocates if there actually are soft hyphens or zero width spaces to remove, which is rare.
+pub fn remove_non_trailing_soft_hyphens(text: &str) -> Cow<'_, str> {
     use itertools::{Itertools, Position};
 
+    let trimmed = text.strip_suffix('\u{00ad}').unwrap_or(text);
+
+    if !trimmed.contains(['\u{00ad}', ZERO_WIDTH_SPACE]) {
+        return Cow::Borrowed(text);
+    }
+
     text.chars()
         .with_position()
         .filter_map(|(p, c)| {
-            if c != '\u{00ad}' || matches!(p, Position::Last | Position::Only) {
+            if c == ZERO_WIDTH_SPACE {
+                None
+            } else if c != '\u{00ad}' || matches!(p, Position::Last | Position::Only) {
                 Some(c)
             } else {
                 None
             }
         })
-        .collect()
+        .collect::<String>()
+        .into()
 }
 
 #[derive(Clone)]
@@ -73,11 +431,50 @@ pub fn break_text_into_lines<'a, F: Fn(&str) -> f64>(
     }
 }
 
+/// How many buffers for break opportunities are kept for reuse. Generators only live while a text
+/// is measured or drawn, so there are rarely more than a few at a time.
+const OPPORTUNITY_BUFFERS_KEPT: usize = 16;
+
+thread_local! {
+    /// The buffers of dropped [LineGenerator]s. Text is broken into lines again on every measure
+    /// and draw pass, so without them every pass would allocate the opportunities anew.
+    static OPPORTUNITY_BUFFERS: RefCell<Vec<Vec<BreakOpportunity>>> =
+        const { RefCell::new(Vec::new()) };
+}
+
 #[derive(Clone)]
 pub struct LineGenerator<'a, F: Fn(&str) -> f64> {
     text: Option<&'a str>,
     text_width: F,
     soft_hyphen_width: f64,
+
+    /// The length of the whole text, for finding the offset of what's left of it.
+    len: usize,
+
+    /// With [LineBreaking::Unicode] and [LineBreaking::Kinsoku], the break opportunities of the
+    /// whole text.
+    opportunities: Option<Vec<BreakOpportunity>>,
+}
+
+impl<F: Fn(&str) -> f64> Drop for LineGenerator<'_, F> {
+    fn drop(&mut self) {
+        let Some(opportunities) = self.opportunities.take() else {
+            return;
+        };
+
+        if opportunities.capacity() == 0 {
+            return;
+        }
+
+        // The thread-local is gone if the generator is dropped while the thread exits.
+        let _ = OPPORTUNITY_BUFFERS.try_with(|buffers| {
+            let mut buffers = buffers.borrow_mut();
+
+            if buffers.len() < OPPORTUNITY_BUFFERS_KEPT {
+                buffers.push(opportunities);
+            }
+        });
+    }
 }
 
 impl<'a, F: Fn(&str) -> f64> LineGenerator<'a, F> {
@@ -88,14 +485,51 @@ impl<'a, F: Fn(&str) -> f64> LineGenerator<'a, F> {
             text: Some(text),
             text_width,
             soft_hyphen_width,
+            len: text.len(),
+            opportunities: None,
         }
     }
 
+    pub fn with_breaking(mut self, breaking: LineBreaking) -> Self {
+        let find: fn(&str, &mut Vec<BreakOpportunity>) = match breaking {
+            LineBreaking::Simple => {
+                self.opportunities = None;
+                return self;
+            }
+            LineBreaking::Unicode => break_opportunities_into,
+            LineBreaking::Kinsoku => kinsoku_break_opportunities_into,
+        };
+
+        self.opportunities = self.text.map(|text| {
+            let mut opportunities = OPPORTUNITY_BUFFERS
+                .with(|buffers| buffers.borrow_mut().pop())
+                .unwrap_or_default();
+
+            find(text, &mut opportunities);
+            opportunities
+        });
+
+        self
+    }
+
+    /// Doesn't break the text at all. If it doesn't fit in what's left of a line, it's put on the
+    /// next one as a whole, even if it doesn't fit there either.
+    pub fn unbreakable(mut self) -> Self {
+        self.opportunities = Some(Vec::new());
+        self
+    }
+
     pub fn done(&self) -> bool {
         self.text.is_none()
     }
 
     pub fn next(&mut self, max_width: f64, incomplete: bool) -> Option<&'a str> {
+        if self.opportunities.is_some() {
+            return self
+                .text
+                .map(|slice| self.next_unicode(slice, max_width, incomplete));
+        }
+
         if let Some(slice) = self.text {
             let mut current_width = 0.0;
             let mut last_break = 0;
@@ -117,7 +551,7 @@ impl<'a, F: Fn(&str) -> f64> LineGenerator<'a, F> {
                         self.text = Some(&slice[i + 1..]);
                         return Some(&slice[..i]);
                     }
-                } else if c.is_whitespace() {
+                } else if is_break_space(c) {
                     if in_whitespace == None {
                         current_width += (self.text_width)(&slice[last_break..i]);
                         in_whitespace = Some(i);
@@ -195,12 +629,214 @@ impl<'a, F: Fn(&str) -> f64> LineGenerator<'a, F> {
             None
         }
     }
+
+    /// Like [Self::next], but lines are only broken at the opportunities found up front. Trailing
+    /// whitespace doesn't count towards the width of a line, like for simple breaking.
+    fn next_unicode(&mut self, slice: &'a str, max_width: f64, incomplete: bool) -> &'a str {
+        let offset = self.len - slice.len();
+
+        let opportunities = self.opportunities.as_deref().unwrap_or_default();
+        let first = opportunities.partition_point(|o| o.offset <= offset);
+
+        let mut width = 0.;
+        let mut measured = 0;
+
+        // The end of the line and the start of the next one for the last opportunity that fit.
+        let mut fitting: Option<(usize, usize)> = None;
+
+        for opportunity in &opportunities[first..] {
+            let start = opportunity.offset - offset;
+            let line = slice[..start]
+                .trim_end_matches(|c: char| c.is_whitespace() || c == ZERO_WIDTH_SPACE);
+
+            // The soft hyphen stays at the end of the line, but it's only drawn as a hyphen if the
+            // line actually ends there.
+            let (content, hyphen_width) = match line.strip_suffix('\u{00ad}') {
+                Some(content) => (content, self.soft_hyphen_width),
+                None => (line, 0.),
+            };
+
+            if content.len() > measured {
+                width += (self.text_width)(&slice[measured..content.len()]);
+                measured = content.len();
+            }
+
+            if width + hyphen_width > max_width && (fitting.is_some() || incomplete) {
+                let (end, next) = fitting.unwrap_or((0, 0));
+                self.text = Some(&slice[next..]);
+                return &slice[..end];
+            }
+
+            if opportunity.mandatory {
+                self.text = Some(&slice[start..]);
+
+                let line = &slice[..start];
+                return line
+                    .strip_suffix("\r\n")
+                    .unwrap_or_else(|| line.strip_suffix(|_| true).unwrap_or(line));
+            }
+
+            fitting = Some((line.len(), start));
+        }
+
+        let content = slice.trim_end_matches(|c: char| c.is_whitespace() || c == ZERO_WIDTH_SPACE);
+
+        if content.len() > measured {
+            width += (self.text_width)(&slice[measured..content.len()]);
+        }
+
+        match fitting {
+            Some((end, next)) if width > max_width => {
+                self.text = Some(&slice[next..]);
+                &slice[..end]
+            }
+            None if width > max_width && incomplete => "",
+            _ => {
+                self.text = None;
+                slice
+            }
+        }
+    }
+}
+
+/// Whether lines can be broken at the character with simple breaking. No-break spaces, like the
+/// one between a number and its unit, keep the words around them together.
+fn is_break_space(c: char) -> bool {
+    (c.is_whitespace() && !matches!(c, '\u{a0}' | '\u{2007}' | '\u{202f}')) || c == ZERO_WIDTH_SPACE
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_width_cache_eviction() {
+        let mut cache = WidthCache::new(4);
+        let key = (0, 0, 0, 0);
+
+        for (i, text) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            cache.insert(key, text, i as f64);
+        }
+
+        assert_eq!(cache.get(key, "a"), Some(0.));
+
+        cache.insert(key, "e", 4.);
+
+        assert_eq!(cache.len, 3);
+        assert_eq!(cache.get(key, "a"), Some(0.));
+        assert_eq!(cache.get(key, "b"), None);
+        assert_eq!(cache.get(key, "c"), None);
+        assert_eq!(cache.get(key, "d"), Some(3.));
+        assert_eq!(cache.get(key, "e"), Some(4.));
+    }
+
+    #[test]
+    #[cfg(feature = "parallel-shaping")]
+    fn test_width_cache_pinned() {
+        let mut cache = WidthCache::new(4);
+        let key = (0, 0, 0, 0);
+
+        for (i, text) in ["a", "b", "c"].into_iter().enumerate() {
+            cache.pin(key, text.to_string(), i as f64);
+        }
+
+        // Only half of the capacity is pinned.
+        assert_eq!(cache.pinned_len, 2);
+        assert_eq!(cache.get(key, "c"), None);
+
+        cache.insert(key, "d", 3.);
+        cache.insert(key, "e", 4.);
+        cache.insert(key, "f", 5.);
+
+        // The pinned widths count towards the capacity, but only the others are evicted.
+        assert_eq!(cache.len, 2);
+        assert_eq!(cache.get(key, "a"), Some(0.));
+        assert_eq!(cache.get(key, "b"), Some(1.));
+        assert_eq!(cache.get(key, "d"), None);
+        assert_eq!(cache.get(key, "e"), Some(4.));
+        assert_eq!(cache.get(key, "f"), Some(5.));
+    }
+
+    #[test]
+    fn test_opportunity_buffers() {
+        let width = |s: &str| s.len() as f64;
+
+        let generator =
+            LineGenerator::new("a b c d e f", width).with_breaking(LineBreaking::Unicode);
+        let buffer = generator.opportunities.as_ref().unwrap().as_ptr();
+        drop(generator);
+
+        // The buffer of the dropped generator is reused without its opportunities.
+        let generator = LineGenerator::new("gh ij", width).with_breaking(LineBreaking::Unicode);
+        let opportunities = generator.opportunities.as_ref().unwrap();
+
+        assert_eq!(opportunities.as_ptr(), buffer);
+        assert_eq!(
+            opportunities.iter().map(|o| o.offset).collect::<Vec<_>>(),
+            [3],
+        );
+    }
+
+    #[cfg(feature = "parallel-shaping")]
+    #[test]
+    fn test_pre_shape_text_widths() {
+        use crate::fonts::builtin::BuiltinFont;
+
+        let doc = printpdf::PdfDocument::empty("test");
+        let font = BuiltinFont::helvetica(&doc);
+
+        let runs = [TextRun {
+            text: "Total amount\ndue",
+            size: 12.,
+            font: &font,
+            character_spacing: 0.,
+            word_spacing: 1.,
+            breaking: LineBreaking::Simple,
+        }];
+
+        // Without a cache there's nowhere to put the widths.
+        pre_shape_text_widths(&runs);
+
+        cache_text_widths(100, || {
+            pre_shape_text_widths(&runs);
+
+            let key = width_cache_key(12., &font, 0., 1.);
+
+            WIDTH_CACHE.with(|cache| {
+                let mut cache = cache.borrow_mut();
+                let cache = cache.as_mut().unwrap();
+
+                for piece in ["Total", " amount", "amount", "Total amount", "due"] {
+                    assert_eq!(
+                        cache.get(key, piece),
+                        Some(uncached_text_width(piece, 12., &font, 0., 1.)),
+                    );
+                }
+
+                assert_eq!(cache.get(key, "Total amount\ndue"), None);
+            });
+        });
+
+        // The widths aren't evicted by the ones measured during layout.
+        cache_text_widths(1, || {
+            pre_shape_text_widths(&runs);
+
+            for text in ["a", "b", "c"] {
+                text_width(text, 12., &font, 0., 1.);
+            }
+
+            WIDTH_CACHE.with(|cache| {
+                let mut cache = cache.borrow_mut();
+                let cache = cache.as_mut().unwrap();
+                let key = width_cache_key(12., &font, 0., 1.);
+
+                for piece in ["Total", " amount", "amount", "Total amount", "due"] {
+                    assert!(cache.get(key, piece).is_some());
+                }
+            });
+        });
+    }
+
     #[test]
     fn test_text_flow() {
         let mut generator = LineGenerator::new(
@@ -400,4 +1036,63 @@ mod tests {
         assert_eq!(generator.next(5., false), Some("word"));
         assert_eq!(generator.next(5., false), None);
     }
+
+    #[test]
+    fn test_zero_width_spaces() {
+        let width = |s: &str| s.chars().filter(|&c| c != ZERO_WIDTH_SPACE).count() as f64;
+
+        let mut generator = LineGenerator::new("ภาษา\u{200b}ไทย\u{200b}ง่าย", width);
+
+        assert_eq!(generator.next(7., false), Some("ภาษา\u{200b}ไทย"));
+        assert_eq!(generator.next(7., false), Some("ง่าย"));
+        assert_eq!(generator.next(7., false), None);
+
+        assert_eq!(remove_non_trailing_soft_hyphens("a\u{200b}b"), "ab");
+    }
+
+    #[test]
+    fn test_no_break_spaces() {
+        let width = |s: &str| s.chars().count() as f64;
+
+        let mut generator = LineGenerator::new("weight 12.5\u{a0}kg", width);
+
+        assert_eq!(generator.next(10., false), Some("weight"));
+        assert_eq!(generator.next(10., false), Some("12.5\u{a0}kg"));
+        assert_eq!(generator.next(10., false), None);
+    }
+
+    #[test]
+    fn test_unicode_line_breaking() {
+        let width = |s: &str| s.chars().count() as f64;
+        let lines = |text, max_width| {
+            let mut generator =
+                LineGenerator::new(text, width).with_breaking(LineBreaking::Unicode);
+            std::iter::from_fn(|| generator.next(max_width, false)).collect::<Vec<_>>()
+        };
+
+        // No break before closing punctuation, even though it doesn't fit.
+        assert_eq!(lines("漢字かな。漢字", 3.), ["漢字か", "な。漢", "字"]);
+        assert_eq!(lines("one (two) three", 9.), ["one (two)", "three"]);
+        assert_eq!(lines("first\nsecond\r\n", 20.), ["first", "second", ""]);
+        assert_eq!(
+            lines("hyphen\u{00ad}ation", 7.),
+            ["hyphen\u{00ad}", "ation"]
+        );
+        assert_eq!(lines("averylongword x", 4.), ["averylongword", "x"]);
+
+        let mut generator =
+            LineGenerator::new("word rest", width).with_breaking(LineBreaking::Unicode);
+        assert_eq!(generator.next(3., true), Some(""));
+        assert_eq!(generator.next(4., true), Some("word"));
+        assert_eq!(generator.next(4., true), Some("rest"));
+        assert_eq!(generator.next(4., true), None);
+    }
+
+    #[test]
+    fn test_punctuation_compressions() {
+        assert_eq!(punctuation_compressions("「引用」、「次」"), [12, 15]);
+        assert_eq!(punctuation_compressions("（「」）"), [3, 9]);
+        assert_eq!(punctuation_compressions("「」。"), [6]);
+        assert_eq!(punctuation_compressions("plain (text)."), []);
+    }
 }
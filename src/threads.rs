@@ -0,0 +1,193 @@
+//! Article threads (ISO 32000-1:2008 12.4.3) let viewers and assistive technology follow the
+//! reading order of content that's split into multiple columns or across pages, like the articles
+//! in a newsletter.
+//!
+//! printpdf doesn't support writing threads, so they're added to the saved document afterwards.
+//! The beads are usually the [Region]s of [Meta](crate::elements::meta::Meta) elements collected
+//! while building the document:
+//!
+//! ```ignore
+//! let (document, regions) = collect_regions(|| build_pdf(..));
+//! let mut document = lopdf::Document::load_mem(&save(document))?;
+//! add_threads(&mut document, [("Article", &regions[..])])?;
+//! ```
+
+use lopdf::{dictionary, Document, Object, ObjectId};
+
+use crate::{elements::meta::Region, utils::mm_to_pt};
+
+/// Adds a thread for every title and list of beads, in reading order. Beads on pages that don't
+/// exist in the document are an error.
+pub fn add_threads<'a>(
+    document: &mut Document,
+    threads: impl IntoIterator<Item = (&'a str, &'a [Region])>,
+) -> lopdf::Result<()> {
+    let pages = document.get_pages();
+    let mut thread_ids = Vec::new();
+
+    for (title, beads) in threads {
+        if beads.is_empty() {
+            continue;
+        }
+
+        let thread_id = document.new_object_id();
+        let bead_ids = beads
+            .iter()
+            .map(|_| document.new_object_id())
+            .collect::<Vec<_>>();
+
+        for (i, bead) in beads.iter().enumerate() {
+            let page_id = *pages
+                .get(&(bead.page as u32 + 1))
+                .ok_or(lopdf::Error::ObjectNotFound)?;
+
+            let next = bead_ids[(i + 1) % bead_ids.len()];
+            let previous = bead_ids[(i + bead_ids.len() - 1) % bead_ids.len()];
+
+            let mut dictionary = dictionary! {
+                "Type" => "Bead",
+                "N" => next,
+                "V" => previous,
+                "P" => page_id,
+                "R" => rect(bead),
+            };
+
+            // Only the first bead refers to the thread.
+            if i == 0 {
+                dictionary.set("T", thread_id);
+            }
+
+            document.objects.insert(bead_ids[i], dictionary.into());
+
+            add_to_page(document, page_id, bead_ids[i])?;
+        }
+
+        document.objects.insert(
+            thread_id,
+            dictionary! {
+                "Type" => "Thread",
+                "F" => bead_ids[0],
+                "I" => dictionary! {
+                    "Title" => Object::string_literal(title),
+                },
+            }
+            .into(),
+        );
+
+        thread_ids.push(Object::Reference(thread_id));
+    }
+
+    let catalog_id = document.trailer.get(b"Root")?.as_reference()?;
+    let catalog = document.get_object_mut(catalog_id)?.as_dict_mut()?;
+
+    let mut threads = match catalog.remove(b"Threads") {
+        Some(Object::Array(threads)) => threads,
+        _ => Vec::new(),
+    };
+
+    threads.extend(thread_ids);
+    catalog.set("Threads", threads);
+
+    Ok(())
+}
+
+fn rect(bead: &Region) -> Vec<Object> {
+    [
+        bead.pos.0,
+        bead.pos.1 - bead.size.1,
+        bead.pos.0 + bead.size.0,
+        bead.pos.1,
+    ]
+    .into_iter()
+    .map(|v| Object::Real(mm_to_pt(v) as _))
+    .collect()
+}
+
+fn add_to_page(document: &mut Document, page_id: ObjectId, bead_id: ObjectId) -> lopdf::Result<()> {
+    let page = document.get_object_mut(page_id)?.as_dict_mut()?;
+
+    let mut beads = match page.remove(b"B") {
+        Some(Object::Array(beads)) => beads,
+        _ => Vec::new(),
+    };
+
+    beads.push(Object::Reference(bead_id));
+    page.set("B", beads);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_add_threads() {
+        let mut document = Document::with_version("1.5");
+
+        let pages_id = document.new_object_id();
+        let page_ids = (0..2)
+            .map(|_| {
+                document.add_object(dictionary! {
+                    "Type" => "Page",
+                    "Parent" => pages_id,
+                })
+            })
+            .collect::<Vec<_>>();
+
+        document.objects.insert(
+            pages_id,
+            dictionary! {
+                "Type" => "Pages",
+                "Kids" => page_ids.iter().map(|&id| id.into()).collect::<Vec<Object>>(),
+                "Count" => 2,
+            }
+            .into(),
+        );
+
+        let catalog_id = document.add_object(dictionary! {
+            "Type" => "Catalog",
+            "Pages" => pages_id,
+        });
+        document.trailer.set("Root", catalog_id);
+
+        let region = |page, x| Region {
+            meta: serde_json::Value::Null,
+            page,
+            pos: (x, 200.),
+            size: (80., 100.),
+        };
+
+        let beads = [region(0, 10.), region(0, 100.), region(1, 10.)];
+
+        add_threads(&mut document, [("Article", &beads[..])]).unwrap();
+
+        let bead_count = |page_id| {
+            document
+                .get_object(page_id)
+                .and_then(Object::as_dict)
+                .unwrap()
+                .get(b"B")
+                .unwrap()
+                .as_array()
+                .unwrap()
+                .len()
+        };
+
+        assert_eq!(bead_count(page_ids[0]), 2);
+        assert_eq!(bead_count(page_ids[1]), 1);
+
+        let threads = document
+            .get_object(catalog_id)
+            .and_then(Object::as_dict)
+            .unwrap()
+            .get(b"Threads")
+            .unwrap()
+            .as_array()
+            .unwrap();
+
+        assert_eq!(threads.len(), 1);
+
+        assert!(add_threads(&mut document, [("Missing", &[region(2, 0.)][..])]).is_err());
+    }
+}
@@ -3,27 +3,188 @@ use printpdf::*;
 pub fn circle(layer: &PdfLayerReference, pos: [f64; 2], radius: f64) {
     let circle = printpdf::utils::calculate_points_for_circle(Pt(radius), Pt(pos[0]), Pt(pos[1]));
 
-    layer.add_shape(Line {
-        points: circle,
-        is_closed: true,
-        has_fill: true,
-        has_stroke: false,
-        is_clipping_path: false,
-    });
+    add_shape(
+        layer,
+        Line {
+            points: circle,
+            is_closed: true,
+            has_fill: true,
+            has_stroke: false,
+            is_clipping_path: false,
+        },
+    );
 }
 
 pub fn line(layer: &PdfLayerReference, pos: [f64; 2], width: f64, thickness: f64) {
     layer.set_outline_thickness(mm_to_pt(thickness));
-    layer.add_shape(printpdf::Line {
-        points: vec![
-            (Point::new(Mm(pos[0]), Mm(pos[1])), false),
-            (Point::new(Mm(pos[0] + width), Mm(pos[1])), false),
-        ],
-        is_closed: false,
-        has_fill: false,
-        has_stroke: true,
-        is_clipping_path: false,
-    });
+    add_shape(
+        layer,
+        printpdf::Line {
+            points: vec![
+                (Point::new(Mm(pos[0]), Mm(pos[1])), false),
+                (Point::new(Mm(pos[0] + width), Mm(pos[1])), false),
+            ],
+            is_closed: false,
+            has_fill: false,
+            has_stroke: true,
+            is_clipping_path: false,
+        },
+    );
+}
+
+/// Adds the shape to the layer if it still fits into the content budget, see [crate::budget].
+pub fn add_shape(layer: &PdfLayerReference, shape: Line) {
+    if crate::budget::add_content(crate::budget::shape_size(&shape)) {
+        layer.add_shape(shape);
+    }
+}
+
+/// Adds the operation to the layer if it still fits into the content budget, see [crate::budget].
+pub fn add_op(layer: &PdfLayerReference, operation: lopdf::content::Operation) {
+    if crate::budget::add_content(crate::budget::operation_size(&operation)) {
+        layer.add_op(operation);
+    }
+}
+
+/// Writes the text with the font if it still fits into the content budget, see [crate::budget].
+pub fn use_text(
+    layer: &PdfLayerReference,
+    text: &str,
+    size: f64,
+    x: Mm,
+    y: Mm,
+    font: &IndirectFontRef,
+) {
+    if crate::budget::add_content(crate::budget::text_size(text)) {
+        layer.use_text(text, size, x, y, font);
+    }
+}
+
+/// Sets the join style and miter limit of the line style. They're only written when they differ
+/// from the PDF defaults, so that existing documents don't change.
+pub fn set_line_join(layer: &PdfLayerReference, style: &crate::LineStyle) {
+    if !matches!(style.join_style, crate::LineJoinStyle::Miter) {
+        layer.set_line_join_style(style.join_style.into());
+    }
+
+    if style.miter_limit != crate::DEFAULT_MITER_LIMIT {
+        layer.set_miter_limit(style.miter_limit);
+    }
+}
+
+/// Sets the color, width, caps, joins and dashes of the strokes to the ones of the line style.
+pub fn set_line_style(
+    layer: &PdfLayerReference,
+    style: &crate::LineStyle,
+    rendering: crate::line_rendering::LineRendering,
+) {
+    // No outline alpha?
+    let (color, _alpha) = u32_to_color_and_alpha(style.color);
+    layer.set_outline_color(color);
+    layer.set_outline_thickness(style.stroke_width(rendering));
+    layer.set_line_cap_style(style.cap_style.into());
+    set_line_join(layer, style);
+    set_line_dash_pattern(layer, style);
+}
+
+/// Sets the dash pattern of the line style, or a solid line if it has none.
+pub fn set_line_dash_pattern(layer: &PdfLayerReference, style: &crate::LineStyle) {
+    add_op(layer, crate::LineDashPattern::operation(style.dash_pattern));
+}
+
+/// Adds the path of the shape to the layer, without painting it. The shape is in pt. Returns
+/// whether the path is closed.
+pub fn add_path(layer: &PdfLayerReference, shape: &impl kurbo::Shape) -> bool {
+    use kurbo::PathEl::*;
+    use lopdf::content::Operation;
+
+    let mut closed = false;
+
+    for el in shape.path_elements(0.1) {
+        match el {
+            MoveTo(point) => add_op(
+                layer,
+                Operation::new("m", vec![point.x.into(), point.y.into()]),
+            ),
+            LineTo(point) => add_op(
+                layer,
+                Operation::new("l", vec![point.x.into(), point.y.into()]),
+            ),
+            QuadTo(a, b) => add_op(
+                layer,
+                // i dunno
+                Operation::new("v", vec![a.x.into(), a.y.into(), b.x.into(), b.y.into()]),
+            ),
+            CurveTo(a, b, c) => add_op(
+                layer,
+                Operation::new(
+                    "c",
+                    vec![
+                        a.x.into(),
+                        a.y.into(),
+                        b.x.into(),
+                        b.y.into(),
+                        c.x.into(),
+                        c.y.into(),
+                    ],
+                ),
+            ),
+            ClosePath => closed = true,
+        };
+    }
+
+    closed
+}
+
+/// A thread-local value that [scoped] can swap out.
+pub(crate) trait Replace<T> {
+    fn replace(&self, value: T) -> T;
+}
+
+impl<T> Replace<T> for std::cell::Cell<T> {
+    fn replace(&self, value: T) -> T {
+        std::cell::Cell::replace(self, value)
+    }
+}
+
+impl<T> Replace<T> for std::cell::RefCell<T> {
+    fn replace(&self, value: T) -> T {
+        std::cell::RefCell::replace(self, value)
+    }
+}
+
+/// Sets the thread-local to `value` while `f` is running and puts the previous value back
+/// afterwards, also if `f` panics. Returns the result of `f` and the value the thread-local ended
+/// up with, for the ones that collect something.
+pub(crate) fn scoped<C: Replace<T> + 'static, T: 'static, R>(
+    key: &'static std::thread::LocalKey<C>,
+    value: T,
+    f: impl FnOnce() -> R,
+) -> (R, T) {
+    struct Reset<C: Replace<T> + 'static, T: 'static> {
+        key: &'static std::thread::LocalKey<C>,
+        previous: Option<T>,
+    }
+
+    impl<C: Replace<T> + 'static, T: 'static> Drop for Reset<C, T> {
+        fn drop(&mut self) {
+            if let Some(previous) = self.previous.take() {
+                self.key.with(|current| current.replace(previous));
+            }
+        }
+    }
+
+    let mut reset = Reset {
+        key,
+        previous: Some(key.with(|current| current.replace(value))),
+    };
+
+    let ret = f();
+
+    let previous = reset.previous.take().unwrap();
+    let value = key.with(|current| current.replace(previous));
+
+    (ret, value)
 }
 
 pub fn mm_to_pt(mm: f64) -> f64 {
@@ -58,6 +219,16 @@ pub fn rgb_color_array_to_u32(color: [u8; 3]) -> u32 {
     ((color[0] as u32) << 24) | ((color[1] as u32) << 16) | ((color[2] as u32) << 8) | 0xFF
 }
 
+/// Rounds a length in mm up to whole steps of a baseline grid. Without a grid it's left as is.
+pub fn snap_to_baseline_grid(length: f64, grid: Option<f64>) -> f64 {
+    match grid {
+        // The tolerance keeps lengths that are already on the grid from being rounded up a step
+        // because of floating point errors.
+        Some(step) if step > 0. => (length / step - 1e-9).ceil().max(0.) * step,
+        _ => length,
+    }
+}
+
 pub fn max_optional_size(a: Option<f64>, b: Option<f64>) -> Option<f64> {
     match (a, b) {
         (None, None) => None,